@@ -0,0 +1,639 @@
+// Request and response models of the Norma API, shared by the backend and the Rust clients (the
+// Tauri shell, tools and tests) so they don't hand-roll the JSON. Only serde, chrono and uuid by
+// default; the backend turns on `sqlx` (FromRow for models read straight from the database) and
+// `utoipa` (schemas for the OpenAPI spec).
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Legal system a chat is answered under
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Jurisdiction {
+    #[default]
+    Rs, // Srbija
+    Hr, // Hrvatska
+    Ba, // Bosna i Hercegovina
+    Bg, // Bugarska
+}
+
+impl Jurisdiction {
+    pub const ALL: [Jurisdiction; 4] = [Jurisdiction::Rs, Jurisdiction::Hr, Jurisdiction::Ba, Jurisdiction::Bg];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Jurisdiction::Rs => "rs",
+            Jurisdiction::Hr => "hr",
+            Jurisdiction::Ba => "ba",
+            Jurisdiction::Bg => "bg",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "rs" => Some(Jurisdiction::Rs),
+            "hr" => Some(Jurisdiction::Hr),
+            "ba" => Some(Jurisdiction::Ba),
+            "bg" => Some(Jurisdiction::Bg),
+            _ => None,
+        }
+    }
+
+    /// Country name (in Serbian), also used as the law name suffix in the registry
+    pub fn country_name(&self) -> &'static str {
+        match self {
+            Jurisdiction::Rs => "Srbija",
+            Jurisdiction::Hr => "Hrvatska",
+            Jurisdiction::Ba => "Bosna i Hercegovina",
+            Jurisdiction::Bg => "Bugarska",
+        }
+    }
+}
+
+/// Script answers are returned in - they are generated and stored in Latin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseScript {
+    #[default]
+    Latin,
+    Cyrillic,
+}
+
+impl ResponseScript {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResponseScript::Latin => "latin",
+            ResponseScript::Cyrillic => "cyrillic",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "latin" => Some(ResponseScript::Latin),
+            "cyrillic" => Some(ResponseScript::Cyrillic),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct Chat {
+    pub id: i64,
+    pub title: String,
+    pub user_id: Option<Uuid>,
+    pub folder_id: Option<i64>,
+    pub jurisdiction: String, // Legal system the chat is answered under ("rs", "hr", "ba", "bg")
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "utoipa", into_params(parameter_in = Query))]
+pub struct ChatListQuery {
+    pub folder_id: Option<i64>,
+    pub unfiled: Option<bool>,
+    // Pagination - with any of these set the response is a ChatPage instead of a bare array
+    pub limit: Option<i64>,
+    pub before: Option<String>,  // next_before of the previous page
+    pub include: Option<String>, // "last_message" adds a preview of each chat's latest message
+}
+
+impl ChatListQuery {
+    pub fn is_paginated(&self) -> bool {
+        self.limit.is_some() || self.before.is_some() || self.include.is_some()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct MessagePreview {
+    #[serde(skip)]
+    pub chat_id: i64,
+    pub role: String,
+    pub content: String, // First 200 characters
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ChatListItem {
+    #[serde(flatten)]
+    pub chat: Chat,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_message: Option<MessagePreview>,
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ChatPage {
+    pub chats: Vec<ChatListItem>,
+    pub total: i64,                  // All chats matching the filter, not just this page
+    pub next_before: Option<String>, // Cursor for the next (older) page; None on the last page
+}
+
+/// Older app versions load the whole list and expect a bare array
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(untagged)]
+pub enum ChatListResponse {
+    All(Vec<Chat>),
+    Page(ChatPage),
+}
+
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "utoipa", into_params(parameter_in = Query))]
+pub struct MessageListQuery {
+    pub limit: Option<i64>,
+    pub before: Option<i64>, // Message id - returns the messages sent before it
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct MessagePage {
+    pub messages: Vec<Message>, // Oldest first, like the full list
+    pub total: i64,
+    pub next_before: Option<i64>, // Id of the oldest message in this page while older ones exist
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(untagged)]
+pub enum MessageListResponse {
+    All(Vec<Message>),
+    Page(MessagePage),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct Message {
+    pub id: i64,
+    pub chat_id: i64,
+    pub role: String,
+    pub content: String,
+    pub law_name: Option<String>,
+    pub has_document: Option<bool>,
+    pub document_filename: Option<String>,
+    pub contract_file_id: Option<String>,
+    pub contract_type: Option<String>,
+    pub contract_filename: Option<String>,
+    pub message_feedback: Option<String>,
+    #[cfg_attr(feature = "sqlx", sqlx(default))]
+    pub edited_at: Option<chrono::DateTime<chrono::Utc>>, // User question changed after it was sent
+    #[cfg_attr(feature = "sqlx", sqlx(default))]
+    pub stale_at: Option<chrono::DateTime<chrono::Utc>>, // Answer predates an edit earlier in the chat
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Previous content of an edited message
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct MessageRevision {
+    pub id: i64,
+    pub content: String,
+    pub edited_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct LawCache {
+    pub id: i64,
+    pub law_name: String,
+    pub law_url: String,
+    pub content: String,
+    pub cached_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct CreateChatRequest {
+    pub title: String,
+    #[serde(default)]
+    pub jurisdiction: Option<Jurisdiction>, // Defaults to Serbia
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct CreateChatResponse {
+    pub id: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct AddMessageRequest {
+    pub chat_id: i64,
+    pub role: String,
+    pub content: String,
+    pub law_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct SubmitFeedbackRequest {
+    pub feedback_type: String, // 'positive' or 'negative'
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct SubmitFeedbackResponse {
+    pub success: bool,
+    pub message: String,
+    pub updated: bool, // true if feedback was changed from previous value
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct GetCachedLawRequest {
+    pub law_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LawContent {
+    pub title: String,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FetchLawContentRequest {
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct QuestionRequest {
+    pub question: String,
+    pub document_content: Option<String>, // Extracted document text
+    pub document_filename: Option<String>, // Original filename
+    pub law_name: Option<String>, // Optional - will be auto-detected if not provided
+    pub law_url: Option<String>, // Optional - will be auto-detected if not provided
+    pub chat_id: i64,
+    #[serde(default)]
+    pub client_request_id: Option<String>, // Ticket for polling queue position during peak load
+    #[serde(default)]
+    pub document_id: Option<Uuid>, // Document uploaded via /api/documents/extract (instead of document_content)
+    #[serde(default)]
+    pub script: Option<ResponseScript>, // Overrides the user's saved answer script
+    #[serde(default)]
+    pub jurisdiction: Option<Jurisdiction>, // Overrides the chat's jurisdiction for this question
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct GeneratedContract {
+    pub filename: String,
+    pub download_url: String,
+    pub contract_type: String,
+    pub preview_text: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct QuestionResponse {
+    pub answer: String,
+    pub law_quotes: Vec<String>,
+    pub law_name: Option<String>,
+    pub generated_contract: Option<GeneratedContract>,
+    // True when the LLM was unavailable and the answer was assembled from cached articles only
+    #[serde(default)]
+    pub is_fallback: bool,
+    // Footnote markers ([1], [2]...) inserted in the answer, each bound to a law_quotes entry
+    #[serde(default)]
+    pub footnotes: Vec<Footnote>,
+    // Title generated for the chat after its first exchange
+    #[serde(default)]
+    pub chat_title: Option<String>,
+    // Caveats about how the answer was produced (stale law text, unsupported answer, truncated document...)
+    #[serde(default)]
+    pub warnings: Vec<ResponseWarning>,
+    // One entry per article cited in the answer, checked against the cached law text
+    #[serde(default)]
+    pub citations: Vec<CitationCheck>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct CitationCheck {
+    pub article_number: String,
+    pub verified: bool,                 // Article exists in the cached law and was quoted
+    pub quote_index: Option<usize>,     // Index into law_quotes when verified
+    pub law_last_article: Option<String>, // Last article of the law, set when the citation is beyond it
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ResponseWarning {
+    pub code: String,    // Stable identifier for the UI, e.g. "document_truncated"
+    pub message: String, // Human readable text (Serbian) shown to the user
+}
+
+impl ResponseWarning {
+    pub fn new(code: &str, message: impl Into<String>) -> Self {
+        Self {
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct Footnote {
+    pub marker: usize,         // Number shown in the answer text, e.g. 1 for "[1]"
+    pub quote_index: usize,    // Index into law_quotes
+    pub article_number: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerbianLaw {
+    pub id: i32,
+    pub name: String,
+    pub url: String,
+}
+
+// Authentication Models
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct AuthResponse {
+    pub success: bool,
+    pub user_id: Option<Uuid>,
+    pub access_token: Option<String>,
+    pub refresh_token: Option<String>,
+    pub migrated_chats: Option<i64>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ErrorResponse {
+    pub error: String,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+}
+
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct UserStatusResponse {
+    pub is_authenticated: bool,
+    pub user_id: Option<Uuid>,
+    pub email: Option<String>,
+    pub email_verified: bool, // Email verification status
+    pub oauth_provider: Option<String>, // 'google', 'apple', NULL for email/password
+    pub name: Option<String>,
+    pub profile_picture_url: Option<String>,
+    pub ui_language: Option<String>, // 'sr', 'en' - None before registration
+    pub response_script: Option<String>, // 'latin', 'cyrillic'
+    pub access_type: String, // "trial", "individual", "professional", "team", "premium" - for frontend compatibility
+    pub account_type: String, // "trial_registered", "individual", "professional", "team", "premium" - internal use
+    pub trial_expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub premium_expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub subscription_expires_at: Option<chrono::DateTime<chrono::Utc>>, // Alias for frontend compatibility
+    pub messages_used_today: i32, // Deprecated, always 0
+    pub messages_remaining: Option<i32>, // None for premium (unlimited), includes purchased messages
+    pub purchased_messages_remaining: i32, // From message packs, kept across monthly resets
+    pub total_messages_sent: i32, // Total number of user messages ever sent (for UI hints)
+    // New subscription details
+    pub subscription_type: Option<String>, // "monthly", "yearly"
+    pub subscription_started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub next_billing_date: Option<chrono::DateTime<chrono::Utc>>,
+    pub subscription_status: Option<String>, // "active", "grace", "past_due", "cancelled", "expired"
+    pub grace_period_ends_at: Option<chrono::DateTime<chrono::Utc>>, // Plan is kept until then while "grace"
+}
+
+
+// Complex parsing models removed - using simplified LLM-guided approach
+
+
+// Password Reset Response
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct PasswordResetResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+// Email Verification Response
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct VerificationEmailResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+// Account Deletion Models
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct DeleteAccountRequest {
+    pub confirmation: bool, // Must be true
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct DeleteAccountResponse {
+    pub success: bool,
+    pub message: String,
+    pub grace_period_ends: Option<String>, // ISO 8601 date
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct RestoreAccountResponse {
+    pub success: bool,
+    pub message: String,
+    pub user_status: UserStatusResponse,
+}
+
+
+// LLM Usage Audit Models
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct LlmRequestRecord {
+    pub id: i64,
+    pub chat_id: Option<i64>,
+    pub message_id: Option<i64>,
+    pub model: String,
+    pub purpose: String, // 'answer', 'classification', 'law_detection'
+    pub input_tokens: i32,
+    pub output_tokens: i32,
+    pub tokens_estimated: bool, // true when OpenRouter didn't return usage and we fell back to chars/4
+    pub cost_usd: f64,
+    pub latency_ms: i64,
+    pub status: String, // 'success' or 'error'
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct LlmUsageByModel {
+    pub model: String,
+    pub requests: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct LlmUsageResponse {
+    pub month: String, // YYYY-MM
+    pub total_requests: i64,
+    pub total_input_tokens: i64,
+    pub total_output_tokens: i64,
+    pub total_cost_usd: f64,
+    pub by_model: Vec<LlmUsageByModel>,
+    pub recent_requests: Vec<LlmRequestRecord>,
+}
+
+// Chat Search Models
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "utoipa", into_params(parameter_in = Query))]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ChatSearchResult {
+    pub chat_id: i64,
+    pub title: String,
+    pub title_highlighted: String, // Matches wrapped in **bold** (markdown)
+    pub rank: f32,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct MessageSearchResult {
+    pub message_id: i64,
+    pub chat_id: i64,
+    pub chat_title: String,
+    pub role: String,
+    pub snippet: String, // Matches wrapped in **bold** (markdown)
+    pub rank: f32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct SearchResponse {
+    pub query: String,
+    pub chats: Vec<ChatSearchResult>,
+    pub messages: Vec<MessageSearchResult>,
+}
+
+// Chat Import Models
+/// Norma chat export format (also produced by the export feature)
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct NormaChatExport {
+    pub format: String, // "norma-chats"
+    pub version: u32,
+    pub exported_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub chats: Vec<ImportedChat>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ImportedChat {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub messages: Vec<ImportedMessage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ImportedMessage {
+    pub role: String,
+    #[serde(alias = "text")]
+    pub content: String,
+    #[serde(default)]
+    pub law_name: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Accepted import payloads: a Norma export, a list of chats, or a single chat
+/// (generic format: {"title": "...", "messages": [{"role": "user", "content": "..."}]})
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[serde(untagged)]
+pub enum ChatImportRequest {
+    Norma(NormaChatExport),
+    Many(Vec<ImportedChat>),
+    Single(ImportedChat),
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ChatImportResponse {
+    pub imported_chats: usize,
+    pub imported_messages: usize,
+    pub skipped_messages: usize,
+    pub chat_ids: Vec<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct AutoTitleResponse {
+    pub chat_id: i64,
+    pub title: String,
+}
+
+// Archived / Deleted Chat Models
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ArchivedChat {
+    pub id: i64,
+    pub title: String,
+    pub archived: bool,
+    pub archived_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub purge_at: Option<chrono::DateTime<chrono::Utc>>, // When a deleted chat is permanently removed
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jurisdiction_round_trip() {
+        for jurisdiction in Jurisdiction::ALL {
+            assert_eq!(Jurisdiction::parse(jurisdiction.as_str()), Some(jurisdiction));
+            assert_eq!(serde_json::to_value(jurisdiction).unwrap(), jurisdiction.as_str());
+        }
+        assert_eq!(Jurisdiction::parse(" HR "), Some(Jurisdiction::Hr));
+        assert_eq!(Jurisdiction::parse("si"), None);
+        assert_eq!(Jurisdiction::default(), Jurisdiction::Rs);
+    }
+
+    #[test]
+    fn test_question_request_minimal_body() {
+        // Only question and chat_id are required - older clients send nothing else
+        let request: QuestionRequest = serde_json::from_str(r#"{"question": "Koliki je otkazni rok?", "chat_id": 7}"#).unwrap();
+        assert_eq!(request.chat_id, 7);
+        assert!(request.script.is_none() && request.jurisdiction.is_none() && request.document_id.is_none());
+
+        let response: QuestionResponse = serde_json::from_str(r#"{"answer": "Odgovor", "law_quotes": [], "law_name": null, "generated_contract": null}"#).unwrap();
+        assert!(!response.is_fallback && response.footnotes.is_empty());
+    }
+}
@@ -0,0 +1,739 @@
+-- Baseline: the schema as run_migrations built it at startup before versioned migrations.
+-- Every statement is idempotent so existing databases can be baselined by running it once
+-- (see migrations.rs). Later changes go in new numbered files - never edit an applied migration.
+
+-- Create optimized tables for new schema
+-- 1. Optimized Users table (combines users + subscriptions)
+CREATE TABLE IF NOT EXISTS users (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    auth_user_id UUID UNIQUE,
+    email VARCHAR(255) UNIQUE NOT NULL,
+    password_hash VARCHAR(255) NOT NULL,
+    email_verified BOOLEAN DEFAULT false,
+    name VARCHAR(255),
+    oauth_provider VARCHAR(50),
+    oauth_profile_picture_url TEXT,
+    account_type VARCHAR(20) DEFAULT 'trial_registered' CHECK (account_type IN ('trial_registered', 'individual', 'professional', 'team', 'premium')),
+    account_status VARCHAR(20) DEFAULT 'active' CHECK (account_status IN ('active', 'suspended', 'deleted')),
+    deleted_at TIMESTAMP WITH TIME ZONE,
+    trial_started_at TIMESTAMP WITH TIME ZONE,
+    trial_expires_at TIMESTAMP WITH TIME ZONE,
+    trial_messages_remaining INTEGER DEFAULT 5,
+    premium_expires_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    last_login TIMESTAMP WITH TIME ZONE
+);
+
+-- 2. Authentication tokens table (replaces email_verification_tokens + password_reset_tokens)
+CREATE TABLE IF NOT EXISTS authentication_tokens (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_type VARCHAR(20) NOT NULL CHECK (token_type IN ('email_verification', 'password_reset', 'jwt_refresh')),
+    token VARCHAR(255) NOT NULL UNIQUE,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- 3. User sessions table for device tracking and concurrent login limits
+CREATE TABLE IF NOT EXISTS user_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    session_token_hash VARCHAR(64) NOT NULL UNIQUE,
+    device_info JSONB,
+    ip_address INET,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    revoked BOOLEAN NOT NULL DEFAULT false
+);
+
+-- 4. Existing core tables
+CREATE TABLE IF NOT EXISTS chats (
+    id BIGSERIAL PRIMARY KEY,
+    title TEXT NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS messages (
+    id BIGSERIAL PRIMARY KEY,
+    chat_id BIGINT NOT NULL REFERENCES chats(id) ON DELETE CASCADE,
+    role TEXT NOT NULL CHECK(role IN ('user', 'assistant')),
+    content TEXT NOT NULL,
+    law_name TEXT,
+    has_document BOOLEAN DEFAULT FALSE,
+    document_filename TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Add has_document column to existing messages table (migration for existing databases)
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS has_document BOOLEAN DEFAULT FALSE;
+
+-- Add document_filename column to existing messages table (migration for existing databases)
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS document_filename TEXT;
+
+-- Add contract fields to messages table (migration for contract generation feature)
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS contract_file_id TEXT;
+
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS contract_type TEXT;
+
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS contract_filename TEXT;
+
+-- Answers replaced by a regeneration stay for the audit trail but are hidden from the chat
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS superseded_at TIMESTAMP WITH TIME ZONE;
+
+-- Editing a question keeps earlier versions in message_revisions; answers that followed the
+-- edited question (other than its own re-run answer) are flagged stale
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS edited_at TIMESTAMP WITH TIME ZONE;
+
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS stale_at TIMESTAMP WITH TIME ZONE;
+
+CREATE TABLE IF NOT EXISTS message_revisions (
+    id BIGSERIAL PRIMARY KEY,
+    message_id BIGINT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    edited_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_message_revisions_message ON message_revisions(message_id, edited_at);
+
+-- Add message_feedback column for user feedback tracking
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS message_feedback VARCHAR(20) CHECK (message_feedback IN ('positive', 'negative'));
+
+-- Add index for message_feedback for analytics queries
+CREATE INDEX IF NOT EXISTS idx_messages_feedback ON messages(message_feedback) WHERE message_feedback IS NOT NULL;
+
+-- Add cost tracking columns to existing users table (migration for existing databases)
+ALTER TABLE users ADD COLUMN IF NOT EXISTS monthly_llm_cost_usd DECIMAL(10,2) DEFAULT 0.00;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS current_cost_month VARCHAR(7) DEFAULT TO_CHAR(NOW(), 'YYYY-MM');
+
+-- Add team_id column for team plan support
+ALTER TABLE users ADD COLUMN IF NOT EXISTS team_id UUID;
+
+-- Add trial_messages_remaining column for clean trial implementation
+ALTER TABLE users ADD COLUMN IF NOT EXISTS trial_messages_remaining INTEGER DEFAULT 5;
+
+-- Add auth_user_id column for Supabase integration (links to auth.users)
+ALTER TABLE users ADD COLUMN IF NOT EXISTS auth_user_id UUID UNIQUE;
+
+-- Add name column for user profiles (from OAuth or manual entry)
+ALTER TABLE users ADD COLUMN IF NOT EXISTS name VARCHAR(255);
+
+-- Add oauth_provider column to track OAuth login method
+ALTER TABLE users ADD COLUMN IF NOT EXISTS oauth_provider VARCHAR(50);
+
+-- Add oauth_profile_picture_url column for user avatars
+ALTER TABLE users ADD COLUMN IF NOT EXISTS oauth_profile_picture_url TEXT;
+
+-- Add deleted_at column for soft delete functionality
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE;
+
+-- Profile fields edited manually are no longer synced from the OAuth provider metadata
+ALTER TABLE users ADD COLUMN IF NOT EXISTS name_overridden BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS avatar_overridden BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS profile_synced_at TIMESTAMP WITH TIME ZONE;
+
+-- Script answers are returned in ('latin' or 'cyrillic'); answers are always stored in Latin
+ALTER TABLE users ADD COLUMN IF NOT EXISTS response_script VARCHAR(10) NOT NULL DEFAULT 'latin';
+
+-- Two-factor authentication: encrypted TOTP secret (set at setup, enabled once verified)
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_secret_encrypted TEXT;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_enabled_at TIMESTAMP WITH TIME ZONE;
+
+-- Time step of the last accepted code, so a code can't be used twice
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_last_used_step BIGINT;
+
+CREATE TABLE IF NOT EXISTS two_factor_recovery_codes (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash VARCHAR(64) NOT NULL,
+    used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_two_factor_recovery_codes_user ON two_factor_recovery_codes(user_id);
+
+-- Professional profile details (one row per user, created on first edit)
+CREATE TABLE IF NOT EXISTS user_profiles (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    professional_title TEXT,
+    firm_name TEXT,
+    bar_number TEXT,
+    signature_block TEXT,
+    visibility JSONB NOT NULL DEFAULT '{}',
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Chat archive and soft delete (deleted chats are purged after 30 days by the cleanup job)
+ALTER TABLE chats ADD COLUMN IF NOT EXISTS archived BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TABLE chats ADD COLUMN IF NOT EXISTS archived_at TIMESTAMP WITH TIME ZONE;
+
+ALTER TABLE chats ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE;
+
+-- Legal system a chat is answered under (jurisdictions::Jurisdiction)
+ALTER TABLE chats ADD COLUMN IF NOT EXISTS jurisdiction VARCHAR(2) NOT NULL DEFAULT 'rs';
+
+CREATE TABLE IF NOT EXISTS law_cache (
+    id BIGSERIAL PRIMARY KEY,
+    law_name TEXT UNIQUE NOT NULL,
+    law_url TEXT NOT NULL,
+    content TEXT NOT NULL,
+    cached_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+-- Whether the law was split into law_articles: NULL = not parsed yet, false = no articles found (use raw text)
+ALTER TABLE law_cache ADD COLUMN IF NOT EXISTS articles_indexed BOOLEAN;
+
+-- Source revalidation state - see law_revalidation.rs
+ALTER TABLE law_cache ADD COLUMN IF NOT EXISTS content_hash TEXT;
+
+ALTER TABLE law_cache ADD COLUMN IF NOT EXISTS gazette_version TEXT;
+
+ALTER TABLE law_cache ADD COLUMN IF NOT EXISTS etag TEXT;
+
+ALTER TABLE law_cache ADD COLUMN IF NOT EXISTS last_modified TEXT;
+
+ALTER TABLE law_cache ADD COLUMN IF NOT EXISTS last_checked_at TIMESTAMP WITH TIME ZONE;
+
+-- History of distinct law texts seen at the source (one row per detected change)
+CREATE TABLE IF NOT EXISTS law_versions (
+    id BIGSERIAL PRIMARY KEY,
+    law_name TEXT NOT NULL,
+    law_url TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    gazette_version TEXT,
+    previous_hash TEXT,
+    detected_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- One row per article cited in an answer and whether it could be extracted (citation coverage report)
+CREATE TABLE IF NOT EXISTS citation_events (
+    id BIGSERIAL PRIMARY KEY,
+    law_name TEXT NOT NULL,
+    article_number VARCHAR(20) NOT NULL,
+    outcome VARCHAR(20) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_citation_events_created_at ON citation_events(created_at, law_name);
+
+-- Cached LLM answers to standalone questions, per normalized question hash and plan
+CREATE TABLE IF NOT EXISTS answer_cache (
+    question_hash VARCHAR(64) NOT NULL,
+    account_type VARCHAR(50) NOT NULL,
+    question TEXT NOT NULL,
+    answer TEXT NOT NULL,
+    law_name TEXT,
+    hit_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_hit_at TIMESTAMP WITH TIME ZONE,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    PRIMARY KEY (question_hash, account_type)
+);
+
+CREATE INDEX IF NOT EXISTS idx_answer_cache_law_name ON answer_cache(law_name);
+
+-- Free daily question for devices without an account (claimed by the account registered on that device)
+CREATE TABLE IF NOT EXISTS anonymous_free_questions (
+    id BIGSERIAL PRIMARY KEY,
+    device_session_id UUID NOT NULL,
+    ip_address VARCHAR(64) NOT NULL,
+    question_date DATE NOT NULL DEFAULT CURRENT_DATE,
+    question TEXT NOT NULL,
+    answer TEXT,
+    law_name TEXT,
+    claimed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (device_session_id, question_date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_anonymous_free_questions_ip ON anonymous_free_questions(ip_address, question_date);
+
+-- Devices trial accounts ask questions from (trial abuse heuristics)
+CREATE TABLE IF NOT EXISTS trial_devices (
+    device_session_id UUID NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    ip_address VARCHAR(64) NOT NULL,
+    ip_subnet VARCHAR(64),
+    first_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (device_session_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_trial_devices_subnet ON trial_devices(ip_subnet, first_seen_at);
+
+-- Idempotency-Key of question submissions: the stored answer is replayed to retries
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    idempotency_key VARCHAR(255) NOT NULL,
+    request_hash VARCHAR(64) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'processing' CHECK (status IN ('processing', 'completed')),
+    response JSONB,
+    trial_charged BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMP WITH TIME ZONE,
+    PRIMARY KEY (user_id, idempotency_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_created ON idempotency_keys(created_at);
+
+-- Devices each account has signed in from (new-device sign-in alerts)
+CREATE TABLE IF NOT EXISTS known_devices (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    device_session_id VARCHAR(100) NOT NULL,
+    device_name TEXT,
+    os TEXT,
+    first_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, device_session_id)
+);
+
+-- Security-sensitive account actions (GET /api/auth/audit-log)
+CREATE TABLE IF NOT EXISTS audit_events (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    action VARCHAR(50) NOT NULL,
+    actor VARCHAR(20) NOT NULL DEFAULT 'user',
+    ip_address VARCHAR(64),
+    device_session_id VARCHAR(100),
+    user_agent TEXT,
+    details JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_events_user ON audit_events(user_id, id DESC);
+
+-- Client-matter conflict check: team-registered parties and alerts raised in chats
+CREATE TABLE IF NOT EXISTS conflict_parties (
+    id BIGSERIAL PRIMARY KEY,
+    team_id UUID NOT NULL,
+    name TEXT NOT NULL,
+    normalized_name TEXT NOT NULL,
+    role VARCHAR(20) NOT NULL CHECK (role IN ('client', 'adverse')),
+    matter TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS conflict_alerts (
+    id BIGSERIAL PRIMARY KEY,
+    team_id UUID NOT NULL,
+    chat_id BIGINT NOT NULL REFERENCES chats(id) ON DELETE CASCADE,
+    party_id BIGINT NOT NULL REFERENCES conflict_parties(id) ON DELETE CASCADE,
+    source VARCHAR(20) NOT NULL,
+    raised_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'open',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMP WITH TIME ZONE,
+    resolved_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    UNIQUE (chat_id, party_id)
+);
+
+-- Alternate URLs (other source sites) tried when a law's primary source fails
+CREATE TABLE IF NOT EXISTS law_source_alternates (
+    primary_url TEXT NOT NULL,
+    alternate_url TEXT NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (primary_url, alternate_url)
+);
+
+-- Admin bulk law preload jobs and their per-law results
+CREATE TABLE IF NOT EXISTS law_preload_jobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    status VARCHAR(30) NOT NULL DEFAULT 'running',
+    force BOOLEAN NOT NULL DEFAULT false,
+    total INTEGER NOT NULL,
+    processed INTEGER NOT NULL DEFAULT 0,
+    failed INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE TABLE IF NOT EXISTS law_preload_items (
+    job_id UUID NOT NULL REFERENCES law_preload_jobs(id) ON DELETE CASCADE,
+    law_id INTEGER NOT NULL,
+    law_name TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    article_count INTEGER,
+    error TEXT,
+    finished_at TIMESTAMP WITH TIME ZONE,
+    PRIMARY KEY (job_id, law_id)
+);
+
+-- Per-article law content, populated whenever a law is cached
+CREATE TABLE IF NOT EXISTS law_articles (
+    law_id BIGINT NOT NULL REFERENCES law_cache(id) ON DELETE CASCADE,
+    article_number TEXT NOT NULL,
+    content TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (law_id, article_number)
+);
+
+-- Part/chapter heading each article belongs to (legal_parser). NULL for articles indexed before
+-- sections were parsed - filled in when the law is re-cached.
+ALTER TABLE law_articles ADD COLUMN IF NOT EXISTS section TEXT;
+
+-- Article embeddings for semantic retrieval. pgvector is optional - without it retrieval stays off.
+DO $$
+BEGIN
+    CREATE EXTENSION IF NOT EXISTS vector;
+    CREATE TABLE IF NOT EXISTS law_article_embeddings (
+        law_id BIGINT NOT NULL,
+        article_number TEXT NOT NULL,
+        embedding vector(1536) NOT NULL, -- retrieval::EMBEDDING_MODEL (text-embedding-3-small)
+        model VARCHAR(100) NOT NULL,
+        created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+        PRIMARY KEY (law_id, article_number),
+        FOREIGN KEY (law_id, article_number) REFERENCES law_articles(law_id, article_number) ON DELETE CASCADE
+    );
+    CREATE INDEX IF NOT EXISTS idx_law_article_embeddings_hnsw ON law_article_embeddings USING hnsw (embedding vector_cosine_ops);
+EXCEPTION WHEN OTHERS THEN
+    RAISE NOTICE 'pgvector extension unavailable, semantic law retrieval disabled: %', SQLERRM;
+END
+$$;
+
+-- Articles a targeted single-article scrape couldn't find, retried after a day
+CREATE TABLE IF NOT EXISTS law_article_misses (
+    law_id BIGINT NOT NULL REFERENCES law_cache(id) ON DELETE CASCADE,
+    article_number TEXT NOT NULL,
+    checked_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (law_id, article_number)
+);
+
+-- Persisted webhook payloads - processed asynchronously with retries
+CREATE TABLE IF NOT EXISTS webhook_events (
+    id BIGSERIAL PRIMARY KEY,
+    source VARCHAR(20) NOT NULL DEFAULT 'revenuecat',
+    event_type TEXT,
+    app_user_id TEXT,
+    payload JSONB NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'processing', 'processed', 'failed', 'dead')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    processed_at TIMESTAMP WITH TIME ZONE
+);
+
+-- Per-request LLM audit log (model, token usage, latency)
+CREATE TABLE IF NOT EXISTS llm_requests (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    chat_id BIGINT REFERENCES chats(id) ON DELETE SET NULL,
+    message_id BIGINT REFERENCES messages(id) ON DELETE SET NULL,
+    model TEXT NOT NULL,
+    purpose VARCHAR(30) NOT NULL DEFAULT 'answer',
+    input_tokens INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    tokens_estimated BOOLEAN NOT NULL DEFAULT false,
+    cost_usd DOUBLE PRECISION NOT NULL DEFAULT 0,
+    latency_ms BIGINT NOT NULL DEFAULT 0,
+    status VARCHAR(20) NOT NULL DEFAULT 'success' CHECK (status IN ('success', 'error')),
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- LLM queue metrics (wait times under load, for tuning LLM_MAX_CONCURRENT)
+CREATE TABLE IF NOT EXISTS llm_queue_metrics (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    queue_position INTEGER NOT NULL,
+    in_flight INTEGER NOT NULL,
+    max_concurrent INTEGER NOT NULL,
+    wait_ms BIGINT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Court tax / administrative fee schedules maintained by admins (replace the built-in tariffs)
+CREATE TABLE IF NOT EXISTS fee_schedule_items (
+    id BIGSERIAL PRIMARY KEY,
+    procedure VARCHAR(50) NOT NULL,
+    item TEXT NOT NULL,
+    fee_type VARCHAR(20) NOT NULL CHECK (fee_type IN ('court_tax', 'administrative_fee')),
+    legal_basis TEXT,
+    min_value BIGINT NOT NULL DEFAULT 0,
+    max_value BIGINT,
+    fixed_amount BIGINT NOT NULL DEFAULT 0,
+    percent DOUBLE PRECISION NOT NULL DEFAULT 0,
+    max_amount BIGINT,
+    position INTEGER NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_fee_schedule_items_procedure ON fee_schedule_items(procedure);
+
+-- Laws users follow for amendments, and the notification feed they produce
+CREATE TABLE IF NOT EXISTS law_subscriptions (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    law_id INTEGER NOT NULL,
+    law_name TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE(user_id, law_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_law_subscriptions_law_name ON law_subscriptions(law_name);
+
+CREATE TABLE IF NOT EXISTS user_notifications (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind VARCHAR(30) NOT NULL,
+    title TEXT NOT NULL,
+    body TEXT,
+    law_name TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    read_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_notifications_user_created ON user_notifications(user_id, created_at DESC);
+
+-- LLM model overrides per pipeline step and plan (falls back to env vars / defaults)
+CREATE TABLE IF NOT EXISTS llm_model_config (
+    id BIGSERIAL PRIMARY KEY,
+    purpose VARCHAR(30) NOT NULL CHECK (purpose IN ('answer', 'classification', 'law_detection')),
+    account_type VARCHAR(20),
+    model TEXT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_llm_model_config_unique ON llm_model_config(purpose, COALESCE(account_type, ''));
+
+-- System prompt profiles per plan and A/B variant (falls back to the built-in prompt)
+CREATE TABLE IF NOT EXISTS prompt_profiles (
+    id BIGSERIAL PRIMARY KEY,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('system', 'contract')),
+    account_type VARCHAR(20),
+    variant VARCHAR(50) NOT NULL DEFAULT 'default',
+    content TEXT NOT NULL,
+    weight INTEGER NOT NULL DEFAULT 100 CHECK (weight >= 0),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_prompt_profiles_unique ON prompt_profiles(kind, COALESCE(account_type, ''), variant);
+
+ALTER TABLE llm_requests ADD COLUMN IF NOT EXISTS prompt_profile TEXT;
+
+-- Documents uploaded for server-side text extraction
+CREATE TABLE IF NOT EXISTS documents (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    filename TEXT NOT NULL,
+    document_type VARCHAR(10) NOT NULL CHECK (document_type IN ('pdf', 'docx', 'txt')),
+    content_type TEXT,
+    file_size BIGINT NOT NULL,
+    extracted_text TEXT NOT NULL,
+    char_count INTEGER NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE documents ADD COLUMN IF NOT EXISTS truncated BOOLEAN NOT NULL DEFAULT false;
+
+-- Chat a document was added to (set for counterparty uploads through a document request)
+ALTER TABLE documents ADD COLUMN IF NOT EXISTS chat_id BIGINT REFERENCES chats(id) ON DELETE SET NULL;
+
+-- Expiring upload links a user sends to a counterparty to collect documents into a chat
+CREATE TABLE IF NOT EXISTS document_requests (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    chat_id BIGINT NOT NULL REFERENCES chats(id) ON DELETE CASCADE,
+    note TEXT,
+    max_uploads INTEGER NOT NULL DEFAULT 1,
+    uploads_received INTEGER NOT NULL DEFAULT 0,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Read-only public links to a chat (one live link per chat, token stored hashed)
+CREATE TABLE IF NOT EXISTS chat_shares (
+    id BIGSERIAL PRIMARY KEY,
+    chat_id BIGINT NOT NULL REFERENCES chats(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    allow_contract_download BOOLEAN NOT NULL DEFAULT false,
+    expires_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    view_count INTEGER NOT NULL DEFAULT 0,
+    last_viewed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_chat_shares_chat ON chat_shares(chat_id) WHERE revoked_at IS NULL;
+
+-- Chat folders: users group chats by client or matter (deleting a folder unfiles its chats)
+CREATE TABLE IF NOT EXISTS chat_folders (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, name)
+);
+
+ALTER TABLE chats ADD COLUMN IF NOT EXISTS folder_id BIGINT REFERENCES chat_folders(id) ON DELETE SET NULL;
+
+-- Synthesized speech for assistant messages (text_hash detects answers that changed since)
+CREATE TABLE IF NOT EXISTS message_audio (
+    message_id BIGINT PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+    text_hash VARCHAR(64) NOT NULL,
+    voice TEXT NOT NULL,
+    audio BYTEA NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Account data exports (ZIP archives in /tmp/exports, link delivered by e-mail)
+CREATE TABLE IF NOT EXISTS data_exports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'ready', 'failed')),
+    download_token_hash VARCHAR(64) NOT NULL,
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMP WITH TIME ZONE,
+    expires_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_data_exports_pending ON data_exports(user_id) WHERE status = 'pending';
+
+-- Monthly LLM spend caps per plan (cost_caps), and per-user overrides set by admins
+CREATE TABLE IF NOT EXISTS llm_cost_caps (
+    account_type VARCHAR(20) PRIMARY KEY,
+    monthly_cap_usd DECIMAL(10,2),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS user_cost_cap_overrides (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    monthly_cap_usd DECIMAL(10,2),
+    reason TEXT,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Editable content of generated contracts (contracts::extract_template) and their edit history
+CREATE TABLE IF NOT EXISTS contract_documents (
+    file_id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    chat_id BIGINT REFERENCES chats(id) ON DELETE CASCADE,
+    contract_type TEXT NOT NULL,
+    script VARCHAR(10) NOT NULL DEFAULT 'latin',
+    template TEXT NOT NULL,
+    fields JSONB NOT NULL DEFAULT '[]',
+    version INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS contract_versions (
+    file_id UUID NOT NULL REFERENCES contract_documents(file_id) ON DELETE CASCADE,
+    version INTEGER NOT NULL,
+    fields JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (file_id, version)
+);
+
+-- Structured contract reviews (contract_review), kept with the chat they were requested from
+CREATE TABLE IF NOT EXISTS contract_reviews (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    chat_id BIGINT REFERENCES chats(id) ON DELETE CASCADE,
+    document_id UUID REFERENCES documents(id) ON DELETE SET NULL,
+    filename TEXT,
+    overall_risk VARCHAR(10) NOT NULL CHECK (overall_risk IN ('low', 'medium', 'high', 'critical')),
+    review JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_contract_reviews_chat ON contract_reviews(chat_id, created_at DESC) WHERE chat_id IS NOT NULL;
+
+-- Create optimized indexes
+-- Users table indexes
+CREATE INDEX IF NOT EXISTS idx_users_email ON users(email);
+
+CREATE INDEX IF NOT EXISTS idx_users_account_type ON users(account_type);
+
+CREATE INDEX IF NOT EXISTS idx_users_trial_expires ON users(trial_expires_at) WHERE trial_expires_at IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_users_premium_expires ON users(premium_expires_at) WHERE premium_expires_at IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_users_team_id ON users(team_id) WHERE team_id IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_conflict_parties_team ON conflict_parties(team_id, role);
+
+CREATE INDEX IF NOT EXISTS idx_conflict_alerts_team ON conflict_alerts(team_id, created_at DESC);
+
+-- Authentication tokens indexes
+CREATE INDEX IF NOT EXISTS idx_auth_tokens_user_id ON authentication_tokens(user_id);
+
+CREATE INDEX IF NOT EXISTS idx_auth_tokens_token ON authentication_tokens(token);
+
+CREATE INDEX IF NOT EXISTS idx_auth_tokens_type ON authentication_tokens(token_type);
+
+CREATE INDEX IF NOT EXISTS idx_auth_tokens_expires ON authentication_tokens(expires_at);
+
+-- Refresh token rotation: tokens from one login share a family, revoked together on reuse/logout
+ALTER TABLE authentication_tokens ADD COLUMN IF NOT EXISTS family_id UUID;
+
+ALTER TABLE authentication_tokens ADD COLUMN IF NOT EXISTS revoked_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX IF NOT EXISTS idx_auth_tokens_family ON authentication_tokens(family_id) WHERE family_id IS NOT NULL;
+
+-- User sessions indexes
+CREATE INDEX IF NOT EXISTS idx_sessions_user_id ON user_sessions(user_id);
+
+CREATE INDEX IF NOT EXISTS idx_sessions_token_hash ON user_sessions(session_token_hash);
+
+-- Partial index for active sessions (without NOW() which is non-immutable)
+-- We filter expires_at > NOW() in queries instead of in the index predicate
+CREATE INDEX IF NOT EXISTS idx_sessions_active ON user_sessions(user_id, last_seen_at DESC) WHERE revoked = false;
+
+CREATE INDEX IF NOT EXISTS idx_sessions_cleanup ON user_sessions(expires_at) WHERE revoked = false;
+
+-- Core table indexes
+CREATE INDEX IF NOT EXISTS idx_messages_chat_id ON messages(chat_id);
+
+CREATE INDEX IF NOT EXISTS idx_law_cache_name ON law_cache(law_name);
+
+CREATE INDEX IF NOT EXISTS idx_law_cache_expires ON law_cache(expires_at);
+
+CREATE INDEX IF NOT EXISTS idx_law_articles_position ON law_articles(law_id, position);
+
+CREATE INDEX IF NOT EXISTS idx_law_versions_name ON law_versions(law_name, detected_at DESC);
+
+CREATE INDEX IF NOT EXISTS idx_chats_user_id ON chats(user_id);
+
+CREATE INDEX IF NOT EXISTS idx_chats_user_updated ON chats(user_id, updated_at DESC, id DESC);
+
+CREATE INDEX IF NOT EXISTS idx_chats_deleted_at ON chats(deleted_at) WHERE deleted_at IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_chats_folder_id ON chats(folder_id) WHERE folder_id IS NOT NULL;
+
+-- Full-text search indexes ('simple' config - Postgres has no Serbian stemmer)
+CREATE INDEX IF NOT EXISTS idx_messages_content_fts ON messages USING GIN (to_tsvector('simple', content));
+
+CREATE INDEX IF NOT EXISTS idx_chats_title_fts ON chats USING GIN (to_tsvector('simple', title));
+
+-- LLM audit log indexes
+CREATE INDEX IF NOT EXISTS idx_llm_requests_user_created ON llm_requests(user_id, created_at DESC);
+
+CREATE INDEX IF NOT EXISTS idx_llm_requests_chat_id ON llm_requests(chat_id) WHERE chat_id IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_llm_queue_metrics_created ON llm_queue_metrics(created_at);
+
+CREATE INDEX IF NOT EXISTS idx_documents_user_id ON documents(user_id);
+
+CREATE INDEX IF NOT EXISTS idx_document_requests_chat_id ON document_requests(chat_id);
+
+-- Webhook retry queue index
+CREATE INDEX IF NOT EXISTS idx_webhook_events_due ON webhook_events(status, next_attempt_at) WHERE status IN ('pending', 'processing', 'failed');
@@ -0,0 +1,13 @@
+-- Password reset and e-mail verification tokens are no longer stored in plain text: rows carry an
+-- HMAC of the token for lookup (token_hash) and the token encrypted (token_encrypted), and leave
+-- token empty. Refresh tokens keep using token, which already holds a SHA-256 of the token.
+-- Converting existing plain-text rows needs the application key, so the server does it at startup
+-- (AuthenticationToken::protect_legacy_tokens).
+ALTER TABLE authentication_tokens ALTER COLUMN token DROP NOT NULL;
+ALTER TABLE authentication_tokens ADD COLUMN token_hash VARCHAR(64);
+ALTER TABLE authentication_tokens ADD COLUMN token_encrypted TEXT;
+
+CREATE UNIQUE INDEX idx_auth_tokens_token_hash ON authentication_tokens(token_hash) WHERE token_hash IS NOT NULL;
+
+ALTER TABLE authentication_tokens
+    ADD CONSTRAINT authentication_tokens_token_stored CHECK (token IS NOT NULL OR token_hash IS NOT NULL);
@@ -0,0 +1,11 @@
+-- Interface language chosen in the profile (answer script stays in users.response_script)
+ALTER TABLE users ADD COLUMN ui_language VARCHAR(5) NOT NULL DEFAULT 'sr';
+
+-- Avatars uploaded from the profile page. users.oauth_profile_picture_url points at
+-- /api/avatars/:user_id while one is set, like any other picture URL.
+CREATE TABLE user_avatars (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    content_type VARCHAR(20) NOT NULL,
+    image BYTEA NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,14 @@
+-- Structured payload for notifications that need more than a title (e.g. the session to revoke)
+ALTER TABLE user_notifications ADD COLUMN data JSONB;
+
+CREATE INDEX idx_user_notifications_unread ON user_notifications(user_id) WHERE read_at IS NULL;
+
+-- Per-user delivery channels per notification kind; no row = the kind's defaults
+CREATE TABLE notification_preferences (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind VARCHAR(30) NOT NULL,
+    in_app BOOLEAN NOT NULL,
+    email BOOLEAN NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, kind)
+);
@@ -0,0 +1,44 @@
+-- Buyer details printed on invoices; companies need PIB and matični broj
+CREATE TABLE billing_profiles (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    customer_type VARCHAR(20) NOT NULL DEFAULT 'individual' CHECK (customer_type IN ('individual', 'company')),
+    name VARCHAR(200),
+    address VARCHAR(300),
+    city VARCHAR(100),
+    postal_code VARCHAR(10),
+    pib VARCHAR(9),
+    maticni_broj VARCHAR(8),
+    email VARCHAR(255),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Invoice numbers run without gaps within a year, so they're taken from a counter row that is
+-- locked until the invoice is inserted rather than from a sequence
+CREATE TABLE invoice_sequences (
+    year INTEGER PRIMARY KEY,
+    last_number INTEGER NOT NULL
+);
+
+-- Issued invoices are never edited: the buyer is a snapshot and the PDF is rendered once
+CREATE TABLE invoices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL, -- Accounting records outlive the account
+    number VARCHAR(20) NOT NULL UNIQUE,
+    year INTEGER NOT NULL,
+    sequence INTEGER NOT NULL,
+    issued_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    description VARCHAR(300) NOT NULL,
+    plan VARCHAR(50),
+    billing_period VARCHAR(20),
+    total_minor BIGINT NOT NULL, -- Gross amount in the currency's minor unit (para)
+    currency VARCHAR(3) NOT NULL,
+    vat_rate SMALLINT NOT NULL,
+    buyer JSONB NOT NULL,
+    source VARCHAR(20) NOT NULL, -- 'web' or 'revenuecat'
+    external_id VARCHAR(255), -- Payment provider transaction, so a replayed charge isn't invoiced twice
+    pdf BYTEA NOT NULL,
+    UNIQUE (year, sequence)
+);
+
+CREATE INDEX idx_invoices_user ON invoices(user_id, issued_at DESC);
+CREATE UNIQUE INDEX idx_invoices_external_id ON invoices(external_id) WHERE external_id IS NOT NULL;
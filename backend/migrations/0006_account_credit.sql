@@ -0,0 +1,9 @@
+-- Subscription columns the web and RevenueCat flows write; production databases got them outside
+-- the old run_migrations, so the baseline doesn't create them
+ALTER TABLE users ADD COLUMN IF NOT EXISTS subscription_type VARCHAR(20);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS subscription_started_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS next_billing_date TIMESTAMP WITH TIME ZONE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS subscription_status VARCHAR(20);
+
+-- Unused subscription time left over from a downgrade, taken off the next web charge (RSD)
+ALTER TABLE users ADD COLUMN account_credit_rsd INTEGER NOT NULL DEFAULT 0 CHECK (account_credit_rsd >= 0);
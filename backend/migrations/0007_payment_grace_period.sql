@@ -0,0 +1,10 @@
+-- End of the payment grace period while a failed renewal is retried (see dunning.rs)
+ALTER TABLE users ADD COLUMN grace_period_ends_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX idx_users_grace_period ON users(grace_period_ends_at) WHERE subscription_status = 'grace';
+
+-- RevenueCat columns update_user_subscription writes, missing from the baseline like the
+-- subscription columns in 0006
+ALTER TABLE users ADD COLUMN IF NOT EXISTS revenuecat_subscriber_id VARCHAR(255);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS last_receipt_validation TIMESTAMP WITH TIME ZONE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS platform VARCHAR(20);
@@ -0,0 +1,35 @@
+-- Discount codes taken off a web subscription's price (see promo_codes.rs)
+CREATE TABLE promo_codes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    code VARCHAR(40) NOT NULL UNIQUE, -- Stored uppercase
+    campaign VARCHAR(100),
+    discount_type VARCHAR(10) NOT NULL CHECK (discount_type IN ('percent', 'fixed')),
+    discount_value INTEGER NOT NULL CHECK (discount_value > 0), -- Percent (1-100) or RSD
+    expires_at TIMESTAMP WITH TIME ZONE,
+    max_uses INTEGER CHECK (max_uses > 0), -- NULL: unlimited
+    uses INTEGER NOT NULL DEFAULT 0,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CHECK (discount_type = 'fixed' OR discount_value <= 100)
+);
+
+CREATE INDEX idx_promo_codes_campaign ON promo_codes(campaign);
+
+-- Each user can use a code once
+CREATE TABLE promo_code_redemptions (
+    promo_code_id UUID NOT NULL REFERENCES promo_codes(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    plan VARCHAR(50) NOT NULL,
+    billing_period VARCHAR(20) NOT NULL,
+    discount_rsd INTEGER NOT NULL,
+    redeemed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (promo_code_id, user_id)
+);
+
+-- Referral codes are handed out on first request; referred_by is set once, when a new account
+-- redeems someone's code (see referrals.rs)
+ALTER TABLE users ADD COLUMN referral_code VARCHAR(12) UNIQUE;
+ALTER TABLE users ADD COLUMN referred_by UUID REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE users ADD COLUMN referred_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX idx_users_referred_by ON users(referred_by) WHERE referred_by IS NOT NULL;
@@ -0,0 +1,3 @@
+-- Stripe customer and subscription behind a web subscription (see payments.rs)
+ALTER TABLE users ADD COLUMN stripe_customer_id VARCHAR(255);
+ALTER TABLE users ADD COLUMN stripe_subscription_id VARCHAR(255) UNIQUE;
@@ -0,0 +1,16 @@
+-- Consumable message packs bought on top of the trial or plan allowance (see message_packs.rs).
+-- Kept apart from trial_messages_remaining so monthly resets and plan changes don't touch them.
+ALTER TABLE users ADD COLUMN purchased_messages_remaining INTEGER NOT NULL DEFAULT 0
+    CHECK (purchased_messages_remaining >= 0);
+
+CREATE TABLE message_pack_purchases (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    product_id VARCHAR(255) NOT NULL,
+    messages INTEGER NOT NULL CHECK (messages > 0),
+    source VARCHAR(20) NOT NULL, -- 'revenuecat_webhook', 'app'
+    external_id VARCHAR(255) NOT NULL UNIQUE, -- Store transaction ID
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_message_pack_purchases_user ON message_pack_purchases(user_id, created_at DESC);
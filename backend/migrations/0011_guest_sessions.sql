@@ -0,0 +1,20 @@
+-- Guest trial: a device without an account gets a few messages, kept in chats of its own until
+-- an account is registered on the device and takes them over (see guest.rs)
+CREATE TABLE guest_sessions (
+    device_session_id UUID PRIMARY KEY,
+    ip_address VARCHAR(64) NOT NULL,
+    messages_remaining INTEGER NOT NULL CHECK (messages_remaining >= 0),
+    claimed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    claimed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_guest_sessions_ip ON guest_sessions(ip_address, created_at);
+
+-- A chat belongs to a user, or to a guest device until it is claimed
+ALTER TABLE chats ALTER COLUMN user_id DROP NOT NULL;
+ALTER TABLE chats ADD COLUMN guest_device_session_id UUID REFERENCES guest_sessions(device_session_id) ON DELETE CASCADE;
+ALTER TABLE chats ADD CONSTRAINT chats_owner_check CHECK (user_id IS NOT NULL OR guest_device_session_id IS NOT NULL);
+
+CREATE INDEX idx_chats_guest_device ON chats(guest_device_session_id, updated_at DESC) WHERE guest_device_session_id IS NOT NULL;
@@ -0,0 +1,9 @@
+-- Accounts merged through /api/auth/link-provider (see account_linking.rs). The merged row keeps
+-- its auth_user_id so signing in with that identity resolves to the account it was merged into.
+ALTER TABLE users ADD COLUMN merged_into UUID REFERENCES users(id) ON DELETE CASCADE;
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_account_status_check;
+ALTER TABLE users ADD CONSTRAINT users_account_status_check
+    CHECK (account_status IN ('active', 'suspended', 'deleted', 'merged'));
+
+CREATE INDEX idx_users_merged_into ON users(merged_into) WHERE merged_into IS NOT NULL;
@@ -0,0 +1,6 @@
+-- Opt-in redaction of names, JMBGs and addresses in stored messages (see pii.rs)
+ALTER TABLE users ADD COLUMN pii_redaction_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Placeholder -> original value for a redacted message, encrypted with a key derived for the chat's
+-- owner. NULL when nothing in the message was redacted.
+ALTER TABLE messages ADD COLUMN pii_mapping_encrypted TEXT;
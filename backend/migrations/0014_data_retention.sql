@@ -0,0 +1,2 @@
+-- How long a user's chats are kept, in days; NULL keeps them forever (see retention.rs)
+ALTER TABLE users ADD COLUMN retention_days INTEGER CHECK (retention_days IN (30, 90, 365));
@@ -0,0 +1,11 @@
+-- Versions of the terms of use / legal disclaimer each user accepted (see consent.rs)
+CREATE TABLE user_consents (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    document VARCHAR(30) NOT NULL,
+    version VARCHAR(30) NOT NULL,
+    accepted_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    ip_address VARCHAR(64),
+    user_agent TEXT,
+    UNIQUE (user_id, document, version)
+);
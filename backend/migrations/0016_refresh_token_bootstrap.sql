@@ -0,0 +1,6 @@
+-- One-time exchange of a pre-refresh-token access token for a first refresh token (see
+-- refresh_tokens::issue_legacy_bootstrap). Users who already have a token family used theirs.
+ALTER TABLE users ADD COLUMN refresh_bootstrapped_at TIMESTAMP WITH TIME ZONE;
+
+UPDATE users SET refresh_bootstrapped_at = NOW()
+WHERE id IN (SELECT DISTINCT user_id FROM authentication_tokens WHERE token_type = 'jwt_refresh');
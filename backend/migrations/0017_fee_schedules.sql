@@ -0,0 +1,34 @@
+-- Tariffs the fee calculator used to carry in code (see fees.rs); from now on fee_schedule_items is
+-- the only source, maintained by admins. Procedures an admin already overrode are left alone.
+WITH court_tax_brackets (min_value, max_value, fixed_amount, percent, max_amount, bracket) AS (
+    VALUES
+        (0::BIGINT, 10000::BIGINT, 1900::BIGINT, 0.0::DOUBLE PRECISION, NULL::BIGINT, 0),
+        (10000, 50000, 3800, 0.0, NULL, 1),
+        (50000, 100000, 7600, 0.0, NULL, 2),
+        (100000, 200000, 11400, 0.0, NULL, 3),
+        (200000, 500000, 19000, 0.0, NULL, 4),
+        (500000, 1000000, 38000, 0.0, NULL, 5),
+        (1000000, NULL, 38000, 1.0, 390000, 6)
+),
+court_tax_items (procedure, item, item_position) AS (
+    VALUES
+        ('tuzba', 'Taksa na tužbu', 0),
+        ('tuzba', 'Taksa na presudu', 1),
+        ('zalba_presuda', 'Taksa na žalbu protiv presude', 0)
+)
+INSERT INTO fee_schedule_items
+    (procedure, item, fee_type, legal_basis, min_value, max_value, fixed_amount, percent, max_amount, position)
+SELECT i.procedure, i.item, 'court_tax', 'Zakon o sudskim taksama',
+       b.min_value, b.max_value, b.fixed_amount, b.percent, b.max_amount, i.item_position * 7 + b.bracket
+FROM court_tax_items i
+CROSS JOIN court_tax_brackets b
+WHERE NOT EXISTS (SELECT 1 FROM fee_schedule_items f WHERE f.procedure = i.procedure);
+
+INSERT INTO fee_schedule_items
+    (procedure, item, fee_type, legal_basis, min_value, max_value, fixed_amount, percent, max_amount, position)
+SELECT a.procedure, a.item, 'administrative_fee', 'Zakon o republičkim administrativnim taksama', 0, NULL, a.amount, 0, NULL, 0
+FROM (VALUES
+    ('upravni_zahtev', 'Republička administrativna taksa na zahtev', 360::BIGINT),
+    ('upravni_zalba', 'Republička administrativna taksa na žalbu', 560::BIGINT)
+) AS a (procedure, item, amount)
+WHERE NOT EXISTS (SELECT 1 FROM fee_schedule_items f WHERE f.procedure = a.procedure);
@@ -0,0 +1,5 @@
+-- Generated contracts now record version 1 in contract_versions (see contracts.rs); add it for
+-- contracts that were never edited. Edited ones get their current state recorded on the next edit.
+INSERT INTO contract_versions (file_id, version, fields, created_at)
+SELECT file_id, 1, fields, created_at FROM contract_documents WHERE version = 1
+ON CONFLICT DO NOTHING;
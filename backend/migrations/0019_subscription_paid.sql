@@ -0,0 +1,4 @@
+-- What the current subscription period was actually paid with (charges plus account credit applied,
+-- RSD). A plan change credits only the unused part of this, so periods activated without payment
+-- don't turn into account credit.
+ALTER TABLE users ADD COLUMN subscription_paid_rsd INTEGER NOT NULL DEFAULT 0 CHECK (subscription_paid_rsd >= 0);
@@ -0,0 +1,5 @@
+-- Provider's own event id (Stripe "evt_...", RevenueCat event.id). Providers redeliver an event
+-- until it is acknowledged, so a delivery whose id is already stored is skipped rather than
+-- processed - and charged or invoiced - twice.
+ALTER TABLE webhook_events ADD COLUMN external_id TEXT;
+ALTER TABLE webhook_events ADD CONSTRAINT webhook_events_source_external_id_key UNIQUE (source, external_id);
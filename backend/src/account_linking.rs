@@ -0,0 +1,275 @@
+// Linking identities: a user who registered with e-mail/password and later signed in with Google
+// (or the other way round) can end up with two accounts. POST /api/auth/link-provider takes a
+// token for each - proving the caller owns both - and merges the second account into the one the
+// request is signed in with. Chats (with their folders) and the subscription move over; the merged
+// row is kept with account_status 'merged' and merged_into set, so signing in with its identity or
+// a RevenueCat webhook for its ID resolves to the surviving account.
+use crate::audit_log::{AuditAction, AuditSource};
+use crate::models::ApiError;
+use crate::simple_auth::AuthAppState;
+use crate::subscriptions::{load_for_update, transition_in, Subscription, Transition};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+fn database_error(e: sqlx::Error) -> ApiError {
+    eprintln!("Account linking database error: {}", e);
+    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Greška baze podataka")
+}
+
+/// The account a user ID refers to now - the surviving account for one that was merged
+pub async fn resolve_user_id(pool: &PgPool, user_id: Uuid) -> Result<Uuid, sqlx::Error> {
+    let resolved: Option<Uuid> = sqlx::query_scalar("SELECT COALESCE(merged_into, id) FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(resolved.unwrap_or(user_id))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LinkProviderRequest {
+    pub token: String, // Access token of the identity to merge into the signed-in account
+}
+
+#[derive(Debug, Serialize)]
+pub struct LinkProviderResponse {
+    pub success: bool,
+    pub user_id: Uuid,
+    pub merged_user_id: Uuid,
+    pub migrated_chats: i64,
+    pub subscription_moved: bool,
+}
+
+/// Merge the account behind `token` into the account the request is signed in with
+pub async fn link_provider_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
+    headers: HeaderMap,
+    Json(request): Json<LinkProviderRequest>,
+) -> Result<Json<LinkProviderResponse>, ApiError> {
+    let user_id = crate::database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or_else(|| ApiError::new(StatusCode::UNAUTHORIZED, "INVALID_TOKEN", "Neispravan token"))?;
+
+    let other_id = crate::simple_auth::verify_any_token(request.token.trim(), &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Link provider: second identity not verified: {}", e);
+            ApiError::new(StatusCode::UNAUTHORIZED, "INVALID_LINK_TOKEN", "Drugi nalog nije potvrđen - prijavite se ponovo")
+        })?;
+    let other_id = resolve_user_id(&pool, other_id).await.map_err(database_error)?;
+
+    if other_id == user_id {
+        return Err(ApiError::new(StatusCode::CONFLICT, "ALREADY_LINKED", "Nalozi su već povezani"));
+    }
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+    let merge = merge_accounts(&mut tx, user_id, other_id).await.map_err(database_error)?;
+    let merge = match merge {
+        Ok(merge) => merge,
+        Err(refusal) => return Err(refusal.into_response()),
+    };
+    tx.commit().await.map_err(database_error)?;
+
+    if let Err(e) = crate::refresh_tokens::revoke_all(other_id, &pool).await {
+        eprintln!("Failed to revoke refresh tokens of merged account {}: {}", other_id, e);
+    }
+
+    crate::audit_log::record(
+        &pool,
+        user_id,
+        AuditAction::AccountLinked,
+        AuditSource::Request(&headers),
+        serde_json::json!({
+            "merged_user_id": other_id,
+            "migrated_chats": merge.migrated_chats,
+            "subscription_moved": merge.subscription_moved,
+        }),
+    )
+    .await;
+
+    println!("🔗 Merged account {} into {} ({} chats)", other_id, user_id, merge.migrated_chats);
+    Ok(Json(LinkProviderResponse {
+        success: true,
+        user_id,
+        merged_user_id: other_id,
+        migrated_chats: merge.migrated_chats,
+        subscription_moved: merge.subscription_moved,
+    }))
+}
+
+/// Why two accounts can't be merged
+#[derive(Debug, PartialEq)]
+pub enum MergeRefusal {
+    NotActive,
+    BothSubscribed,
+}
+
+impl MergeRefusal {
+    fn into_response(self) -> ApiError {
+        match self {
+            MergeRefusal::NotActive => ApiError::new(StatusCode::CONFLICT, "ACCOUNT_NOT_ACTIVE", "Nalog nije aktivan"),
+            MergeRefusal::BothSubscribed => ApiError::new(
+                StatusCode::CONFLICT,
+                "BOTH_SUBSCRIBED",
+                "Oba naloga imaju aktivnu pretplatu - otkažite jednu pre povezivanja",
+            ),
+        }
+    }
+}
+
+pub struct MergeOutcome {
+    pub migrated_chats: i64,
+    pub subscription_moved: bool,
+}
+
+/// Move the merged account's chats, folders and subscription onto the surviving account and mark
+/// it merged. Both rows stay locked until the caller's transaction ends.
+pub async fn merge_accounts(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    merged_id: Uuid,
+) -> Result<Result<MergeOutcome, MergeRefusal>, sqlx::Error> {
+    // Lock in a fixed order so two merges of the same pair can't deadlock
+    let active: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM (
+             SELECT id FROM users WHERE id IN ($1, $2) AND account_status = 'active' ORDER BY id FOR UPDATE
+         ) locked",
+    )
+    .bind(user_id)
+    .bind(merged_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if active != 2 {
+        return Ok(Err(MergeRefusal::NotActive));
+    }
+
+    let kept = load_for_update(&mut *conn, user_id).await?;
+    let merged = load_for_update(&mut *conn, merged_id).await?;
+    if kept.is_subscribed() && merged.is_subscribed() {
+        return Ok(Err(MergeRefusal::BothSubscribed));
+    }
+
+    let subscription_moved = merged.is_subscribed();
+    if subscription_moved {
+        move_subscription(&mut *conn, user_id, merged_id, merged).await?;
+    } else {
+        let trial = Transition::MergeTrial { trial_messages_remaining: merged.trial_messages_remaining };
+        transition_in(&mut *conn, user_id, &trial).await?;
+    }
+
+    // Bought messages and credit are the user's money - both carry over
+    sqlx::query(
+        "UPDATE users u SET
+            purchased_messages_remaining = u.purchased_messages_remaining + m.purchased_messages_remaining,
+            account_credit_rsd = u.account_credit_rsd + m.account_credit_rsd,
+            updated_at = NOW()
+         FROM users m
+         WHERE u.id = $1 AND m.id = $2",
+    )
+    .bind(user_id)
+    .bind(merged_id)
+    .execute(&mut *conn)
+    .await?;
+
+    let migrated_chats = move_chats(&mut *conn, user_id, merged_id).await?;
+
+    sqlx::query(
+        "UPDATE users SET account_status = 'merged', merged_into = $1,
+            purchased_messages_remaining = 0, account_credit_rsd = 0, subscription_paid_rsd = 0, updated_at = NOW()
+         WHERE id = $2",
+    )
+    .bind(user_id)
+    .bind(merged_id)
+    .execute(&mut *conn)
+    .await?;
+
+    // Accounts merged into the merged one earlier now point at the survivor as well
+    sqlx::query("UPDATE users SET merged_into = $1 WHERE merged_into = $2")
+        .bind(user_id)
+        .bind(merged_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(Ok(MergeOutcome { migrated_chats, subscription_moved }))
+}
+
+/// Move the subscription (with what its period was paid) and its store / Stripe identifiers to
+/// the surviving account and clear them on the merged one (stripe_subscription_id is unique, so it
+/// is cleared first)
+async fn move_subscription(conn: &mut PgConnection, user_id: Uuid, merged_id: Uuid, merged: Subscription) -> Result<(), sqlx::Error> {
+    transition_in(&mut *conn, user_id, &Transition::TakeOver { subscription: merged }).await?;
+    transition_in(&mut *conn, merged_id, &Transition::HandOver).await?;
+
+    let (stripe_customer_id, stripe_subscription_id): (Option<String>, Option<String>) = sqlx::query_as(
+        "SELECT stripe_customer_id, stripe_subscription_id FROM users WHERE id = $1",
+    )
+    .bind(merged_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "UPDATE users u SET
+            platform = m.platform,
+            revenuecat_subscriber_id = m.revenuecat_subscriber_id,
+            subscription_paid_rsd = m.subscription_paid_rsd
+         FROM users m
+         WHERE u.id = $1 AND m.id = $2",
+    )
+    .bind(user_id)
+    .bind(merged_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("UPDATE users SET stripe_customer_id = NULL, stripe_subscription_id = NULL WHERE id = $1")
+        .bind(merged_id)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query("UPDATE users SET stripe_customer_id = $2, stripe_subscription_id = $3 WHERE id = $1")
+        .bind(user_id)
+        .bind(stripe_customer_id)
+        .bind(stripe_subscription_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Move chats and folders. A folder whose name the surviving account already uses is folded into
+/// that folder. Returns how many chats were moved.
+async fn move_chats(conn: &mut PgConnection, user_id: Uuid, merged_id: Uuid) -> Result<i64, sqlx::Error> {
+    sqlx::query(
+        "UPDATE chats c SET folder_id = kept.id
+         FROM chat_folders merged
+         JOIN chat_folders kept ON kept.user_id = $1 AND kept.name = merged.name
+         WHERE c.folder_id = merged.id AND merged.user_id = $2",
+    )
+    .bind(user_id)
+    .bind(merged_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "UPDATE chat_folders SET user_id = $1, updated_at = NOW()
+         WHERE user_id = $2 AND name NOT IN (SELECT name FROM chat_folders WHERE user_id = $1)",
+    )
+    .bind(user_id)
+    .bind(merged_id)
+    .execute(&mut *conn)
+    .await?;
+
+    crate::pii::reseal_mappings(&mut *conn, merged_id, user_id).await?;
+    let moved = sqlx::query("UPDATE chats SET user_id = $1 WHERE user_id = $2")
+        .bind(user_id)
+        .bind(merged_id)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+    Ok(moved as i64)
+}
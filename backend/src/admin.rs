@@ -0,0 +1,28 @@
+use axum::http::HeaderMap;
+
+/// Header carrying the admin API key for operator-only endpoints
+pub const ADMIN_KEY_HEADER: &str = "X-Admin-Key";
+
+/// Check whether the request carries a valid admin API key.
+///
+/// Admin access is disabled entirely when ADMIN_API_KEY is not set.
+pub fn is_admin_request(headers: &HeaderMap) -> bool {
+    let Some(admin_key) = &crate::config::get().admin_api_key else {
+        return false;
+    };
+
+    let provided = headers
+        .get(ADMIN_KEY_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("");
+
+    constant_time_eq(provided.as_bytes(), admin_key.as_bytes())
+}
+
+/// Compare two byte strings without short-circuiting on the first mismatch
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,209 @@
+// Cache of LLM answers to standalone legal questions ("kolika je kazna za vožnju bez dozvole"),
+// keyed by a hash of the normalized question and everything else that went into the prompt (see
+// CacheScope), stored per plan (plans can be routed to different models).
+// Only the raw model answer and detected law are cached - article quotes, footnotes and warnings
+// are rebuilt from the current law cache on every hit.
+use crate::jurisdictions::Jurisdiction;
+use chrono::NaiveDate;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// How long a cached answer is served, per plan. Paid plans get fresher answers.
+fn ttl_hours(account_type: Option<&str>) -> i32 {
+    match account_type {
+        Some("trial_registered") | None => 72,
+        Some("individual") => 48,
+        _ => 24, // professional, team, premium
+    }
+}
+
+fn enabled() -> bool {
+    crate::config::get().answer_cache_enabled
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct CachedAnswer {
+    pub answer: String,
+    pub law_name: Option<String>,
+}
+
+/// Lowercase, fold diacritics and drop punctuation so trivially different phrasings share a key
+fn normalize_question(question: &str) -> String {
+    let mut folded = String::with_capacity(question.len());
+    for c in question.to_lowercase().chars() {
+        match c {
+            'č' | 'ć' => folded.push('c'),
+            'š' => folded.push('s'),
+            'ž' => folded.push('z'),
+            'đ' => folded.push_str("dj"),
+            c if c.is_alphanumeric() => folded.push(c),
+            _ => folded.push(' '),
+        }
+    }
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Everything besides the question that shapes an answer. The prompt carries the user's profile
+/// details and today's date and is built from the user's prompt profile, so a hit is only ever an
+/// answer generated for the same user, profile and day. Anonymous questions share one scope.
+#[derive(Debug, Clone)]
+pub struct CacheScope {
+    pub account_type: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub prompt_label: String,
+}
+
+impl CacheScope {
+    /// Scope for a question, or None when the user's answers must not be cached: with PII
+    /// redaction on, the answer may quote personal data the user asked to keep out of storage.
+    pub async fn resolve(
+        account_type: Option<&str>,
+        user_id: Option<Uuid>,
+        jurisdiction: Jurisdiction,
+        pool: &PgPool,
+    ) -> Option<Self> {
+        if let Some(user_id) = user_id {
+            match crate::pii::redaction_enabled(user_id, pool).await {
+                Ok(false) => {}
+                Ok(true) => return None,
+                Err(e) => {
+                    eprintln!("⚠️  Answer cache skipped, redaction setting unavailable: {}", e);
+                    return None;
+                }
+            }
+        }
+
+        let prompt = crate::prompt_profiles::resolve_system_prompt(account_type, user_id, jurisdiction, pool).await;
+        Some(Self {
+            account_type: account_type.map(str::to_string),
+            user_id,
+            prompt_label: prompt.label,
+        })
+    }
+
+    fn plan_key(&self) -> &str {
+        self.account_type.as_deref().unwrap_or("anonymous")
+    }
+}
+
+fn question_hash(question: &str, scope: &CacheScope, date: NaiveDate) -> String {
+    let user = scope.user_id.map(|id| id.to_string()).unwrap_or_default();
+    let key = format!("{}\n{}\n{}\n{}", normalize_question(question), user, scope.prompt_label, date);
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+fn today() -> NaiveDate {
+    chrono::Utc::now().date_naive()
+}
+
+/// Cached answer for the question, if one is still fresh
+pub async fn lookup(question: &str, scope: &CacheScope, pool: &PgPool) -> Option<CachedAnswer> {
+    if !enabled() {
+        return None;
+    }
+
+    let result = sqlx::query_as::<_, CachedAnswer>(
+        "UPDATE answer_cache SET hit_count = hit_count + 1, last_hit_at = NOW()
+         WHERE question_hash = $1 AND account_type = $2 AND expires_at > NOW()
+         RETURNING answer, law_name"
+    )
+    .bind(question_hash(question, scope, today()))
+    .bind(scope.plan_key())
+    .fetch_optional(pool)
+    .await;
+
+    match result {
+        Ok(cached) => cached,
+        Err(e) => {
+            eprintln!("⚠️  Answer cache lookup failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Store a fresh answer (never fails the request)
+pub async fn store(question: &str, scope: &CacheScope, answer: &str, law_name: Option<&str>, pool: &PgPool) {
+    if !enabled() {
+        return;
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO answer_cache (question_hash, account_type, question, answer, law_name, expires_at)
+         VALUES ($1, $2, $3, $4, $5, NOW() + make_interval(hours => $6))
+         ON CONFLICT (question_hash, account_type) DO UPDATE SET
+             question = EXCLUDED.question, answer = EXCLUDED.answer, law_name = EXCLUDED.law_name,
+             hit_count = 0, created_at = NOW(), expires_at = EXCLUDED.expires_at"
+    )
+    .bind(question_hash(question, scope, today()))
+    .bind(scope.plan_key())
+    .bind(question)
+    .bind(answer)
+    .bind(law_name)
+    .bind(ttl_hours(scope.account_type.as_deref()))
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("⚠️  Failed to cache answer: {}", e);
+    }
+}
+
+/// Drop cached answers based on a law whose text just changed
+pub async fn invalidate_law(law_name: &str, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<u64, String> {
+    let result = sqlx::query("DELETE FROM answer_cache WHERE law_name = $1")
+        .bind(law_name)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to invalidate cached answers: {}", e))?;
+
+    Ok(result.rows_affected())
+}
+
+/// Remove expired entries (daily cleanup job)
+pub async fn purge_expired(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM answer_cache WHERE expires_at < NOW()")
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(user_id: Option<Uuid>, prompt_label: &str) -> CacheScope {
+        CacheScope { account_type: Some("individual".to_string()), user_id, prompt_label: prompt_label.to_string() }
+    }
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+    }
+
+    #[test]
+    fn test_question_hash_ignores_case_and_punctuation() {
+        let scope = scope(None, "system:builtin");
+        assert_eq!(
+            question_hash("Kolika je kazna za vožnju bez dozvole?", &scope, day(1)),
+            question_hash("kolika je kazna za voznju  bez dozvole", &scope, day(1))
+        );
+        assert_ne!(
+            question_hash("Kolika je kazna za vožnju bez dozvole?", &scope, day(1)),
+            question_hash("Kolika je kazna za vožnju bez pojasa?", &scope, day(1))
+        );
+    }
+
+    #[test]
+    fn test_question_hash_is_scoped_to_user_profile_and_day() {
+        let question = "Kolika je kazna za vožnju bez dozvole?";
+        let alice = Some(Uuid::new_v4());
+        let bob = Some(Uuid::new_v4());
+        let base = question_hash(question, &scope(alice, "system:builtin"), day(1));
+
+        assert_eq!(base, question_hash(question, &scope(alice, "system:builtin"), day(1)));
+        assert_ne!(base, question_hash(question, &scope(bob, "system:builtin"), day(1)));
+        assert_ne!(base, question_hash(question, &scope(alice, "system:individual/b"), day(1)));
+        assert_ne!(base, question_hash(question, &scope(alice, "system:builtin"), day(2)));
+    }
+}
@@ -4,11 +4,20 @@ use axum::{
     http::{StatusCode, HeaderMap},
 };
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 use crate::models::*;
 use crate::database;
 use crate::scraper;
 use crate::laws;
+use crate::jurisdictions::Jurisdiction;
+use crate::llm_config::{self, LlmPurpose};
+use crate::llm_tools;
+use crate::openrouter_resilience;
+use crate::citation_audit::CitationOutcome;
+use crate::transcription;
+use crate::transliteration::ResponseScript;
 use sqlx::PgPool;
 
 // Helper function to safely find UTF-8 character boundary (stable Rust compatible)
@@ -54,9 +63,110 @@ struct OpenRouterChoice {
     message: OpenRouterMessage,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenRouterUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct OpenRouterResponse {
     choices: Vec<OpenRouterChoice>,
+    #[serde(default)]
+    usage: Option<OpenRouterUsage>,
+}
+
+// Answer request with tool calling (see llm_tools)
+#[derive(Debug, Serialize)]
+struct OpenRouterToolRequest<'a> {
+    model: &'a str,
+    messages: &'a [serde_json::Value],
+    temperature: f32,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    tools: &'a [serde_json::Value],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<&'static str>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterAssistantMessage {
+    #[serde(default)]
+    content: Option<String>, // null when the model only calls tools
+    #[serde(default)]
+    tool_calls: Option<Vec<serde_json::Value>>, // Kept raw so they can be echoed back verbatim
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterToolChoice {
+    message: OpenRouterAssistantMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterToolResponse {
+    choices: Vec<OpenRouterToolChoice>,
+    #[serde(default)]
+    usage: Option<OpenRouterUsage>,
+}
+
+// Token counts for an LLM call: (input, output, estimated)
+// Prefers the usage reported by OpenRouter, falls back to the 1 token ≈ 4 chars heuristic
+fn llm_token_counts(usage: Option<&OpenRouterUsage>, input_chars: usize, output_chars: usize) -> (u64, u64, bool) {
+    match usage {
+        Some(usage) if usage.prompt_tokens > 0 || usage.completion_tokens > 0 => {
+            (usage.prompt_tokens, usage.completion_tokens, false)
+        }
+        _ => ((input_chars / 4) as u64, (output_chars / 4) as u64, true),
+    }
+}
+
+/// Articles injected into the prompt by semantic retrieval
+const RETRIEVAL_TOP_K: i64 = 5;
+
+// Who an LLM call is made for - recorded in the llm_requests audit log
+#[derive(Clone, Copy)]
+struct LlmCallContext<'a> {
+    user_id: Option<Uuid>,
+    chat_id: Option<i64>,
+    account_type: Option<&'a str>, // Used to route plans to different models
+    jurisdiction: Jurisdiction, // Legal system the answer is for
+    pool: &'a PgPool,
+}
+
+// Write an LLM call to the audit log (never fails the request)
+async fn audit_llm_call(
+    ctx: LlmCallContext<'_>,
+    model: &str,
+    purpose: &str,
+    token_counts: (u64, u64, bool),
+    started_at: std::time::Instant,
+    error: Option<&str>,
+) -> Option<i64> {
+    let (input_tokens, output_tokens, tokens_estimated) = token_counts;
+    crate::metrics::record_openrouter_call(purpose, started_at.elapsed(), error.is_none());
+    crate::health::observe_openrouter(error.is_none());
+
+    let log = database::LlmRequestLog {
+        user_id: ctx.user_id,
+        chat_id: ctx.chat_id,
+        model,
+        purpose,
+        input_tokens,
+        output_tokens,
+        tokens_estimated,
+        cost_usd: database::llm_cost_from_tokens(model, input_tokens, output_tokens),
+        latency_ms: started_at.elapsed().as_millis(),
+        error,
+    };
+
+    match database::record_llm_request(log, ctx.pool).await {
+        Ok(id) => Some(id),
+        Err(e) => {
+            error!("Failed to record LLM request: {}", e);
+            None
+        }
+    }
 }
 
 // NEW: Process question with LLM free response (Phase 2)
@@ -64,11 +174,11 @@ async fn process_question_with_free_response(
     question: &str,
     recent_messages: &[&Message],
     document_content: Option<&str>,
-    user_id: Option<Uuid>,
-    pool: &PgPool,
+    retrieved_context: Option<&str>,
+    ctx: LlmCallContext<'_>,
     api_key: &str,
-) -> Result<String, String> {
-    println!("🔍 DEBUG: Processing question with LLM free response: '{}'", question);
+) -> Result<(String, Option<i64>), String> {
+    debug!("🔍 Processing question with LLM free response: '{}'", question);
 
     // Create conversation context with document content if provided
     let user_content = if let Some(doc_content) = document_content {
@@ -77,29 +187,68 @@ async fn process_question_with_free_response(
         question.to_string()
     };
 
+    // System prompt profile for the user's plan (and A/B variant)
+    let system_prompt = crate::prompt_profiles::resolve_system_prompt(ctx.account_type, ctx.user_id, ctx.jurisdiction, ctx.pool).await;
+
     // Use the existing create_conversation_messages function for consistency
-    let messages = create_conversation_messages(&user_content, document_content, recent_messages);
+    let mut messages = create_conversation_messages(&system_prompt.content, &user_content, document_content, recent_messages);
+
+    // Professional profile details so generated contracts can pre-fill the user's party and signature
+    if let Some(user_id) = ctx.user_id {
+        match crate::profile::document_profile_context(user_id, ctx.pool).await {
+            Ok(Some(profile_context)) => {
+                messages.insert(1, OpenRouterMessage {
+                    role: "system".to_string(),
+                    content: format!(
+                        "PODACI KORISNIKA (koristi ih kada korisnik u ugovoru nastupa kao strana ili potpisnik, ne pitaj ponovo za njih):\n{}",
+                        profile_context
+                    ),
+                });
+            }
+            Ok(None) => {}
+            Err(e) => warn!("⚠️  {}", e),
+        }
+    }
+
+    // Articles found by semantic retrieval, for questions that don't name the law or article
+    if let Some(retrieved_context) = retrieved_context {
+        messages.insert(1, OpenRouterMessage {
+            role: "system".to_string(),
+            content: retrieved_context.to_string(),
+        });
+    }
+
+    // Today's date and upcoming holidays, so deadlines aren't computed from the training date
+    if let Some(date_context) = crate::calendar::prompt_context(chrono::Utc::now()) {
+        messages.insert(1, OpenRouterMessage {
+            role: "system".to_string(),
+            content: date_context,
+        });
+    }
 
     // Use the existing call_openrouter_api function for consistency
-    println!("🔍 DEBUG: Making OpenRouter API call for free response...");
+    debug!("🔍 Making OpenRouter API call for free response...");
 
-    let llm_response = call_openrouter_api(api_key, messages, user_id, pool).await?;
+    let (llm_response, llm_request_id) = call_openrouter_api(api_key, messages, ctx).await?;
+    if let Some(llm_request_id) = llm_request_id {
+        crate::prompt_profiles::tag_llm_request(llm_request_id, &system_prompt.label, ctx.pool).await;
+    }
 
-    println!("🤖 LLM FREE RESPONSE LENGTH: {} chars", llm_response.len());
+    debug!("🤖 LLM FREE RESPONSE LENGTH: {} chars", llm_response.len());
     if llm_response.len() < 200 {
-        println!("🤖 LLM FREE RESPONSE: '{}'", llm_response);
+        debug!("🤖 LLM FREE RESPONSE: '{}'", llm_response);
     } else {
         // Safe UTF-8 slicing
         let safe_end = floor_char_boundary(&llm_response, 200);
-        println!("🤖 LLM FREE RESPONSE (first 200 chars): '{}'", &llm_response[..safe_end]);
+        debug!("🤖 LLM FREE RESPONSE (first 200 chars): '{}'", &llm_response[..safe_end]);
     }
 
-    Ok(llm_response)
+    Ok((llm_response, llm_request_id))
 }
 
 // Check if a question is related to Serbian law (KEPT per CLAUDE.md)
-async fn is_legal_question(question: &str, api_key: &str) -> Result<bool, String> {
-    println!("🔍 LEGAL CLASSIFICATION: Starting question classification");
+async fn is_legal_question(question: &str, api_key: &str, ctx: LlmCallContext<'_>) -> Result<(bool, Option<i64>), String> {
+    debug!("🔍 LEGAL CLASSIFICATION: Starting question classification");
 
     let classification_prompt = format!(
         r#"You are a legal classification expert. Your task is to determine if a question is related to law, legal procedures, or requires legal knowledge.
@@ -122,10 +271,12 @@ Respond with exactly one word: LEGAL or NOT_LEGAL"#,
     ];
 
     let request = OpenRouterRequest {
-        model: "google/gemini-2.5-flash".to_string(), // Much cheaper for simple classification
+        model: llm_config::resolve_model(LlmPurpose::Classification, ctx.account_type, ctx.pool).await,
         messages,
         temperature: 0.0, // Deterministic for classification
     };
+    let input_chars = request.messages.iter().map(|m| m.content.len()).sum();
+    let started_at = std::time::Instant::now();
 
     let client = reqwest::Client::new();
     let response = client
@@ -140,12 +291,18 @@ Respond with exactly one word: LEGAL or NOT_LEGAL"#,
     let response_text = response.text().await
         .map_err(|e| format!("Failed to read classification response: {}", e))?;
 
-    println!("🔧 CLASSIFICATION: Raw response text: {}", response_text);
+    debug!("🔧 CLASSIFICATION: Raw response text: {}", response_text);
 
-    let parsed_response: OpenRouterResponse = serde_json::from_str(&response_text)
-        .map_err(|e| format!("Failed to parse classification response: {} - Response: {}", e, response_text))?;
+    let parsed_response: OpenRouterResponse = match serde_json::from_str(&response_text) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let error = format!("Failed to parse classification response: {} - Response: {}", e, response_text);
+            audit_llm_call(ctx, &request.model, "classification", llm_token_counts(None, input_chars, 0), started_at, Some(&error)).await;
+            return Err(error);
+        }
+    };
 
-    println!("🔧 CLASSIFICATION: Parsed response choices count: {}", parsed_response.choices.len());
+    debug!("🔧 CLASSIFICATION: Parsed response choices count: {}", parsed_response.choices.len());
 
     let classification_result = parsed_response.choices
         .first()
@@ -155,7 +312,10 @@ Respond with exactly one word: LEGAL or NOT_LEGAL"#,
         .trim()
         .to_uppercase();
 
-    println!("🔧 CLASSIFICATION: LLM raw content: '{}'", classification_result);
+    debug!("🔧 CLASSIFICATION: LLM raw content: '{}'", classification_result);
+
+    let token_counts = llm_token_counts(parsed_response.usage.as_ref(), input_chars, classification_result.len());
+    let llm_request_id = audit_llm_call(ctx, &request.model, "classification", token_counts, started_at, None).await;
 
     let is_legal = if classification_result.contains("NOT") || classification_result.contains("NON") {
         // Explicit non-legal response
@@ -165,23 +325,129 @@ Respond with exactly one word: LEGAL or NOT_LEGAL"#,
         true
     } else {
         // Unexpected response - log it and default to true to avoid missing legal questions
-        println!("⚠️  CLASSIFICATION: Unexpected LLM response '{}', defaulting to legal for safety", classification_result);
+        warn!("⚠️  CLASSIFICATION: Unexpected LLM response '{}', defaulting to legal for safety", classification_result);
         true
     };
 
-    println!("✅ CLASSIFICATION: '{}' -> response: '{}' -> is_legal = {}", question, classification_result, is_legal);
+    info!(is_legal, response = %classification_result, "✅ Question classified");
+    debug!("✅ CLASSIFICATION: '{}' -> response: '{}' -> is_legal = {}", question, classification_result, is_legal);
+
+    Ok((is_legal, llm_request_id))
+}
+
+// Generate a concise Serbian chat title from the first exchange (uses the cheap classification model)
+async fn generate_chat_title(
+    question: &str,
+    answer: &str,
+    api_key: &str,
+    ctx: LlmCallContext<'_>,
+) -> Result<(String, Option<i64>), String> {
+    let answer_excerpt = &answer[..floor_char_boundary(answer, 500)];
+
+    let title_prompt = format!(
+        r#"Napiši kratak naslov (najviše 6 reči) za ovaj pravni razgovor na srpskom jeziku (latinica).
+
+PITANJE: "{}"
+
+ODGOVOR (početak): "{}"
+
+Vrati SAMO naslov, bez navodnika i bez tačke na kraju."#,
+        question, answer_excerpt
+    );
+
+    let request = OpenRouterRequest {
+        model: llm_config::resolve_model(LlmPurpose::Classification, ctx.account_type, ctx.pool).await,
+        messages: vec![OpenRouterMessage {
+            role: "user".to_string(),
+            content: title_prompt,
+        }],
+        temperature: 0.3,
+    };
+    let input_chars = request.messages.iter().map(|m| m.content.len()).sum();
+    let started_at = std::time::Instant::now();
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post("https://openrouter.ai/api/v1/chat/completions")
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Content-Type", "application/json")
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| format!("Chat title API error: {}", e))?;
+
+    let response_text = response.text().await
+        .map_err(|e| format!("Failed to read chat title response: {}", e))?;
+
+    let parsed_response: OpenRouterResponse = match serde_json::from_str(&response_text) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let error = format!("Failed to parse chat title response: {} - Response: {}", e, response_text);
+            audit_llm_call(ctx, &request.model, "chat_title", llm_token_counts(None, input_chars, 0), started_at, Some(&error)).await;
+            return Err(error);
+        }
+    };
+
+    let raw_title = parsed_response.choices
+        .first()
+        .ok_or("No chat title response received")?
+        .message
+        .content
+        .clone();
+
+    let token_counts = llm_token_counts(parsed_response.usage.as_ref(), input_chars, raw_title.len());
+    let llm_request_id = audit_llm_call(ctx, &request.model, "chat_title", token_counts, started_at, None).await;
+
+    let title = clean_chat_title(&raw_title).ok_or("Empty chat title generated")?;
+    debug!("🏷️ Generated chat title: '{}'", title);
+
+    Ok((title, llm_request_id))
+}
+
+// Keep only the first line, strip quotes/markdown and trailing punctuation, cap the length
+fn clean_chat_title(raw_title: &str) -> Option<String> {
+    let title = raw_title
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())?
+        .trim_start_matches("Naslov:")
+        .trim_matches(|c: char| c == '"' || c == '\'' || c == '*' || c == '#' || c == '„' || c == '“' || c == '”')
+        .trim_end_matches(['.', ':'])
+        .trim();
+
+    if title.is_empty() {
+        return None;
+    }
+
+    let title: String = title.chars().take(60).collect();
+    Some(title.trim().to_string())
+}
+
+async fn save_chat_title(chat_id: i64, title: &str, user_id: Option<Uuid>, pool: &PgPool) -> Result<(), String> {
+    // Titles are made from the question, so they may name the client
+    let title = crate::pii::prepare_title_for_storage(chat_id, title, pool).await?;
+    sqlx::query("UPDATE chats SET title = $1, updated_at = NOW() WHERE id = $2")
+        .bind(&title)
+        .bind(chat_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to update chat title: {}", e))?;
+
+    if let Some(user_id) = user_id {
+        crate::events::emit(user_id, crate::events::ChatEvent::ChatTitleChanged { chat_id, title });
+    }
 
-    Ok(is_legal)
+    Ok(())
 }
 
 // NEW: Article reference replacement system (Phase 3)
 
 // Detect which law is relevant for the question
-async fn detect_relevant_law_name(question: &str, api_key: &str) -> Result<String, String> {
-    println!("🔍 DEBUG: Detecting relevant law name for question: '{}'", question);
+async fn detect_relevant_law_name(question: &str, api_key: &str, ctx: LlmCallContext<'_>) -> Result<(String, Option<i64>), String> {
+    debug!("🔍 Detecting relevant law name for question: '{}'", question);
 
     let law_detection_prompt = format!(
-        r#"Analiziraj ovo pravno pitanje i odredi koji je jedan najrelevantniji srpski zakon.
+        r#"Analiziraj ovo pravno pitanje i odredi koji je jedan najrelevantniji {} zakon.
 
 PITANJE: "{}"
 
@@ -195,6 +461,7 @@ INSTRUKCIJE:
    - "Porodični zakon"
 
 Tvoj odgovor:"#,
+        crate::jurisdictions::law_adjective(ctx.jurisdiction),
         question
     );
 
@@ -206,10 +473,12 @@ Tvoj odgovor:"#,
     ];
 
     let request = OpenRouterRequest {
-        model: "google/gemini-2.5-flash".to_string(),
+        model: llm_config::resolve_model(LlmPurpose::LawDetection, ctx.account_type, ctx.pool).await,
         messages,
         temperature: 0.0,
     };
+    let input_chars = request.messages.iter().map(|m| m.content.len()).sum();
+    let started_at = std::time::Instant::now();
 
     let client = reqwest::Client::new();
     let response = client
@@ -224,8 +493,14 @@ Tvoj odgovor:"#,
     let response_text = response.text().await
         .map_err(|e| format!("Failed to read law detection response: {}", e))?;
 
-    let parsed_response: OpenRouterResponse = serde_json::from_str(&response_text)
-        .map_err(|e| format!("Failed to parse law detection response: {} - Response: {}", e, response_text))?;
+    let parsed_response: OpenRouterResponse = match serde_json::from_str(&response_text) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let error = format!("Failed to parse law detection response: {} - Response: {}", e, response_text);
+            audit_llm_call(ctx, &request.model, "law_detection", llm_token_counts(None, input_chars, 0), started_at, Some(&error)).await;
+            return Err(error);
+        }
+    };
 
     let detected_law_name = parsed_response.choices
         .first()
@@ -235,15 +510,18 @@ Tvoj odgovor:"#,
         .trim()
         .to_string();
 
-    println!("🔍 DEBUG: Detected law name: '{}'", detected_law_name);
-    Ok(detected_law_name)
+    let token_counts = llm_token_counts(parsed_response.usage.as_ref(), input_chars, detected_law_name.len());
+    let llm_request_id = audit_llm_call(ctx, &request.model, "law_detection", token_counts, started_at, None).await;
+
+    debug!("🔍 Detected law name: '{}'", detected_law_name);
+    Ok((detected_law_name, llm_request_id))
 }
 
 // Detect article references in LLM response (simplified - just look for Član X)
 fn detect_article_references_simple(text: &str) -> Vec<String> {
     use regex::Regex;
 
-    println!("🔍 DEBUG: Detecting simple article references in text");
+    debug!("🔍 Detecting simple article references in text");
 
     let mut article_numbers = Vec::new();
 
@@ -255,63 +533,231 @@ fn detect_article_references_simple(text: &str) -> Vec<String> {
 
         if !article_numbers.contains(&article_number) {
             article_numbers.push(article_number.clone());
-            println!("🔍 DEBUG: Found article reference: Član {}", article_number);
+            debug!("🔍 Found article reference: Član {}", article_number);
         }
     }
 
-    println!("🔍 DEBUG: Total article numbers found: {}", article_numbers.len());
+    debug!("🔍 Total article numbers found: {}", article_numbers.len());
     article_numbers
 }
 
-// Get cached article content from database with automatic caching
+// Get cached article content from database with automatic caching, falling back to
+// scraping the article's own page when it can't be extracted from the law text.
 // Returns: (article_content, actual_law_name_from_db)
-async fn get_cached_article(law_name: &str, article_number: &str, pool: &PgPool) -> Result<Option<(String, String)>, String> {
-    // Try to get from cache first
+pub async fn get_cached_article(law_name: &str, article_number: &str, pool: &PgPool) -> Result<Option<(String, String)>, String> {
+    if let Some(found) = lookup_cached_article(law_name, article_number, pool).await? {
+        return Ok(Some(found));
+    }
+
+    match fetch_single_article(law_name, article_number, pool).await {
+        Ok(Some(content)) => Ok(Some((format!("**Član {}**\n{}", article_number, content), law_name.to_string()))),
+        Ok(None) => Ok(None),
+        Err(e) => {
+            warn!("⚠️ Targeted scrape of Član {} of '{}' failed: {}", article_number, law_name, e);
+            Ok(None)
+        }
+    }
+}
+
+/// Hours before a failed targeted article scrape is retried
+const ARTICLE_MISS_RETRY_HOURS: i32 = 24;
+
+// Targeted scrape of a single article page (sources like paragraf.rs publish per-article pages).
+// Found articles are stored in law_articles with position -1 so they don't count as part of the
+// parsed law; misses are remembered so hallucinated articles don't trigger a scrape on every answer.
+async fn fetch_single_article(law_name: &str, article_number: &str, pool: &PgPool) -> Result<Option<String>, String> {
+    let clean_article_num = article_number.replace(".", "").replace("stav", "").trim().to_string();
+
+    let law = sqlx::query_as::<_, (i64, String)>(
+        "SELECT id, law_url FROM law_cache WHERE law_name = $1 AND expires_at > NOW()"
+    )
+    .bind(law_name)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to look up law: {}", e))?;
+    let Some((law_id, law_url)) = law else {
+        return Ok(None);
+    };
+
+    let Some(article_url) = crate::law_sources::source_for_url(&law_url).article_url(&law_url, &clean_article_num) else {
+        return Ok(None);
+    };
+
+    if let Some(last_article) = last_indexed_article(law_name, pool).await {
+        if article_exceeds_law(&clean_article_num, &last_article) {
+            return Ok(None);
+        }
+    }
+
+    let recently_missed: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM law_article_misses
+         WHERE law_id = $1 AND article_number = $2 AND checked_at > NOW() - make_interval(hours => $3))"
+    )
+    .bind(law_id)
+    .bind(&clean_article_num)
+    .bind(ARTICLE_MISS_RETRY_HOURS)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to check article misses: {}", e))?;
+    if recently_missed {
+        return Ok(None);
+    }
+
+    debug!("🎯 Fetching Član {} of '{}' from {}", clean_article_num, law_name, article_url);
+    let body = match scraper::scrape_law_page(&article_url).await {
+        // The page may hold neighbouring articles too - cut out just this one
+        Ok(page) => extract_article_from_law_text(&page.law.content, &clean_article_num)
+            .and_then(|quoted| quoted.split_once('\n').map(|(_, body)| body.trim().to_string()))
+            .filter(|body| !body.is_empty()),
+        Err(e) => {
+            warn!("⚠️ Article page fetch failed: {}", e);
+            None
+        }
+    };
+
+    let Some(body) = body else {
+        sqlx::query(
+            "INSERT INTO law_article_misses (law_id, article_number) VALUES ($1, $2)
+             ON CONFLICT (law_id, article_number) DO UPDATE SET checked_at = NOW()"
+        )
+        .bind(law_id)
+        .bind(&clean_article_num)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to record article miss: {}", e))?;
+        return Ok(None);
+    };
+
+    sqlx::query(
+        "INSERT INTO law_articles (law_id, article_number, content, position)
+         VALUES ($1, $2, $3, -1)
+         ON CONFLICT (law_id, article_number) DO NOTHING"
+    )
+    .bind(law_id)
+    .bind(&clean_article_num)
+    .bind(&body)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to cache fetched article: {}", e))?;
+
+    debug!("✅ Cached Član {} of '{}' from its article page", clean_article_num, law_name);
+    Ok(Some(body))
+}
+
+// Look an article up in the article index or the cached law text (fetching the law if needed)
+async fn lookup_cached_article(law_name: &str, article_number: &str, pool: &PgPool) -> Result<Option<(String, String)>, String> {
+    let clean_article_num = article_number.replace(".", "").replace("stav", "").trim().to_string();
+
+    // Indexed lookup in law_articles - (law_id, articles_indexed, article_content)
+    let indexed = sqlx::query_as::<_, (i64, Option<bool>, Option<String>)>(
+        "SELECT c.id, c.articles_indexed, a.content
+         FROM law_cache c
+         LEFT JOIN law_articles a ON a.law_id = c.id AND a.article_number = $2
+         WHERE c.law_name = $1 AND c.expires_at > NOW()
+         LIMIT 1"
+    )
+    .bind(law_name)
+    .bind(&clean_article_num)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to look up cached article: {}", e))?;
+
+    match indexed {
+        // Also matches single articles fetched from their own page for laws that didn't parse
+        Some((_, _, Some(content))) => {
+            debug!("✅ Found article {} of '{}' in article cache", article_number, law_name);
+            return Ok(Some((format!("**Član {}**\n{}", article_number, content), law_name.to_string())));
+        }
+        Some((_, Some(true), None)) => {
+            warn!("❌ Article {} not found in indexed law '{}'", article_number, law_name);
+            return Ok(None);
+        }
+        Some((law_id, None, _)) => {
+            // Cached before article indexing existed - index it in the background for next time
+            let law_pool = pool.clone();
+            tokio::spawn(async move {
+                if let Err(e) = backfill_law_articles(law_id, &law_pool).await {
+                    warn!("⚠️  Failed to index articles for law {}: {}", law_id, e);
+                }
+            });
+        }
+        Some((_, Some(false), _)) | None => {}
+    }
+
+    // Fall back to searching the whole law text
     match get_cached_law(law_name.to_string(), pool).await {
         Ok(Some(cached_law)) => {
-            println!("✅ DEBUG: Found '{}' in cache", law_name);
+            debug!("✅ Found '{}' in cache", law_name);
             // Extract specific article from law content
             let article_content = extract_article_from_law_text(&cached_law.content, article_number);
             // Return both article content and the actual law name from database
             Ok(article_content.map(|content| (content, cached_law.law_name.clone())))
         }
         Ok(None) => {
-            println!("⚠️ DEBUG: Law '{}' not found in cache, attempting to fetch and cache", law_name);
+            warn!("⚠️ Law '{}' not found in cache, attempting to fetch and cache", law_name);
 
             // Try to find law URL from hardcoded list for automatic caching
             if let Some(law_url) = try_get_law_url(law_name) {
-                println!("✅ DEBUG: Found URL for '{}': {}", law_name, law_url);
+                debug!("✅ Found URL for '{}': {}", law_name, law_url);
 
                 // Fetch and cache the law automatically
                 match get_law_content(law_name, &law_url, pool).await {
                     Ok(law_content) => {
-                        println!("✅ DEBUG: Successfully fetched and cached '{}'", law_name);
+                        debug!("✅ Successfully fetched and cached '{}'", law_name);
                         // Now extract the specific article
                         let article_content = extract_article_from_law_text(&law_content.content, article_number);
                         // Return both article content and the law title (which is the cached name)
                         Ok(article_content.map(|content| (content, law_content.title.clone())))
                     }
                     Err(e) => {
-                        println!("❌ DEBUG: Failed to fetch law content for '{}': {}", law_name, e);
+                        warn!("❌ Failed to fetch law content for '{}': {}", law_name, e);
                         Ok(None)
                     }
                 }
             } else {
-                println!("❌ DEBUG: No URL mapping found for law '{}'", law_name);
+                warn!("❌ No URL mapping found for law '{}'", law_name);
                 Ok(None)
             }
         }
         Err(e) => {
-            println!("❌ DEBUG: Error fetching cached law '{}': {}", law_name, e);
+            warn!("❌ Error fetching cached law '{}': {}", law_name, e);
             Err(e)
         }
     }
 }
 
+// Split an already cached (but unindexed) law into law_articles
+async fn backfill_law_articles(law_id: i64, pool: &PgPool) -> Result<(), String> {
+    let mut tx = pool.begin().await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let content: Option<String> = sqlx::query_scalar("SELECT content FROM law_cache WHERE id = $1 AND articles_indexed IS NULL FOR UPDATE")
+        .bind(law_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to load law content: {}", e))?;
+
+    // Another request got here first
+    let Some(content) = content else {
+        return Ok(());
+    };
+
+    let article_count = database::index_law_articles(law_id, &content, &mut tx).await?;
+    tx.commit().await
+        .map_err(|e| format!("Failed to commit law articles: {}", e))?;
+
+    debug!("✅ Indexed {} articles for law {}", article_count, law_id);
+    Ok(())
+}
+
 // Extract specific article content from law text
 fn extract_article_from_law_text(law_content: &str, article_number: &str) -> Option<String> {
     use regex::Regex;
 
+    // Some laws are published in Cyrillic - match "Član N" against the Latin text
+    let law_content = crate::transliteration::to_latin(law_content);
+    let law_content = law_content.as_ref();
+
     // Handle different article number formats
     let clean_article_num = article_number.replace(".", "").replace("stav", "").trim().to_string();
 
@@ -321,12 +767,12 @@ fn extract_article_from_law_text(law_content: &str, article_number: &str) -> Opt
     let pattern = match Regex::new(&pattern_str) {
         Ok(p) => p,
         Err(e) => {
-            println!("❌ DEBUG: Regex compilation failed: {}", e);
+            warn!("❌ Regex compilation failed: {}", e);
             return None;
         },
     };
 
-    println!("🔍 DEBUG: Looking for article {} using pattern: {}", clean_article_num, pattern_str);
+    debug!("🔍 Looking for article {} using pattern: {}", clean_article_num, pattern_str);
 
     // Debug: Show a sample of the law content around the expected article
     if let Some(start_pos) = law_content.find(&format!("Član {}", clean_article_num)) {
@@ -338,13 +784,13 @@ fn extract_article_from_law_text(law_content: &str, article_number: &str) -> Opt
         let safe_end = floor_char_boundary(law_content, sample_end);
         let sample = &law_content[safe_start..safe_end];
 
-        println!("🔍 DEBUG: Found 'Član {}' in law content. Context: '{}'", clean_article_num, sample);
+        debug!("🔍 Found 'Član {}' in law content. Context: '{}'", clean_article_num, sample);
     } else {
-        println!("❌ DEBUG: 'Član {}' not found in law content at all", clean_article_num);
+        warn!("❌ 'Član {}' not found in law content at all", clean_article_num);
         // Show first 200 chars to see the format - use char boundary safe method
         let safe_end = floor_char_boundary(law_content, 200.min(law_content.len()));
         let sample = &law_content[..safe_end];
-        println!("🔍 DEBUG: Law content sample: '{}'", sample);
+        debug!("🔍 Law content sample: '{}'", sample);
     }
 
     if let Some(cap) = pattern.captures(law_content) {
@@ -364,18 +810,38 @@ fn extract_article_from_law_text(law_content: &str, article_number: &str) -> Opt
 
         let article_content = article_content.trim();
         if !article_content.is_empty() {
-            println!("✅ DEBUG: Found article {} content: {} chars", article_number, article_content.len());
+            debug!("✅ Found article {} content: {} chars", article_number, article_content.len());
             return Some(format!("**Član {}**\n{}", article_number, article_content));
         }
     }
 
-    println!("❌ DEBUG: Article {} not found in law content", article_number);
+    warn!("❌ Article {} not found in law content", article_number);
     None
 }
 
+// Answer returned when the article fetch timed out: the model's text as-is, flagged for the UI
+fn answer_without_quotes(response: &str) -> QuestionResponse {
+    QuestionResponse {
+        answer: response.to_string(),
+        law_quotes: vec![],
+        law_name: None,
+        generated_contract: None,
+        is_fallback: false,
+        footnotes: vec![],
+        chat_title: None,
+        warnings: vec![ResponseWarning::new(
+            "quotes_unavailable",
+            "Tekst citiranih članova trenutno nije dostupan - proverite ih u važećem propisu.",
+        )],
+        citations: vec![],
+    }
+}
+
 // Replace article references with cached content using detected law name
-async fn replace_article_references_with_law(response: &str, detected_law_name: Option<&str>, pool: &PgPool) -> Result<(QuestionResponse, Option<String>), String> {
-    println!("🔍 DEBUG: Starting article replacement with detected law: {:?}", detected_law_name);
+// Also returns the lookup outcome of every cited article; the caller records them for the
+// citation audit once the final answer is settled (a self-corrected answer is looked up twice).
+async fn replace_article_references_with_law(response: &str, detected_law_name: Option<&str>, pool: &PgPool) -> Result<(QuestionResponse, Option<String>, Vec<(String, CitationOutcome)>), String> {
+    debug!("🔍 Starting article replacement with detected law: {:?}", detected_law_name);
 
     let article_numbers = detect_article_references_simple(response);
 
@@ -385,44 +851,96 @@ async fn replace_article_references_with_law(response: &str, detected_law_name:
             law_quotes: vec![],
             law_name: None,
             generated_contract: None,
-        }, None));
+            is_fallback: false,
+            footnotes: vec![],
+            chat_title: None,
+            warnings: vec![],
+            citations: vec![],
+        }, None, Vec::new()));
     }
 
     if detected_law_name.is_none() {
-        println!("⚠️ DEBUG: No law detected, cannot fetch articles");
+        warn!("⚠️ No law detected, cannot fetch articles");
         return Ok((QuestionResponse {
             answer: response.to_string(),
             law_quotes: vec![],
             law_name: None,
             generated_contract: None,
-        }, None));
+            is_fallback: false,
+            footnotes: vec![],
+            chat_title: None,
+            warnings: vec![],
+            citations: vec![],
+        }, None, Vec::new()));
     }
 
     let law_name = detected_law_name.unwrap();
     let mut law_quotes = Vec::new();
+    let mut citations = Vec::new();
+    let mut lookup_failed = Vec::new();
     let mut actual_law_name_from_db: Option<String> = None;
 
     for article_number in article_numbers {
+        let mut citation = CitationCheck {
+            article_number: article_number.clone(),
+            verified: false,
+            quote_index: None,
+            law_last_article: None,
+        };
+
         match get_cached_article(law_name, &article_number, pool).await {
             Ok(Some((article_content, db_law_name))) => {
+                citation.verified = true;
+                citation.quote_index = Some(law_quotes.len());
                 law_quotes.push(article_content);
                 // Capture the actual law name from database (same for all articles)
                 if actual_law_name_from_db.is_none() {
                     actual_law_name_from_db = Some(db_law_name.clone());
                 }
-                println!("✅ DEBUG: Added content for Član {} from {} (DB: {})", article_number, law_name, db_law_name);
+                debug!("✅ Added content for Član {} from {} (DB: {})", article_number, law_name, db_law_name);
             }
             Ok(None) => {
-                println!("⚠️ DEBUG: No content found for Član {} in '{}'", article_number, law_name);
+                warn!("⚠️ No content found for Član {} in '{}'", article_number, law_name);
             }
             Err(e) => {
-                println!("❌ DEBUG: Error fetching Član {}: {}", article_number, e);
+                warn!("❌ Error fetching Član {}: {}", article_number, e);
+                lookup_failed.push(article_number.clone());
+            }
+        }
+
+        citations.push(citation);
+    }
+
+    // Citation verification: flag cited articles the law doesn't have (likely hallucinated)
+    if citations.iter().any(|c| !c.verified) {
+        let last_article = last_indexed_article(law_name, pool).await;
+        for citation in citations.iter_mut().filter(|c| !c.verified) {
+            if let Some(last) = last_article.as_deref() {
+                if article_exceeds_law(&citation.article_number, last) {
+                    citation.law_last_article = Some(last.to_string());
+                }
             }
         }
     }
 
-    println!("✅ DEBUG: Article replacement complete. Answer: {} chars, Quotes: {}",
-             response.len(), law_quotes.len());
+    let outcomes: Vec<(String, CitationOutcome)> = citations
+        .iter()
+        .map(|c| {
+            let outcome = if c.verified {
+                CitationOutcome::Extracted
+            } else if c.law_last_article.is_some() {
+                CitationOutcome::BeyondLaw
+            } else if lookup_failed.contains(&c.article_number) {
+                CitationOutcome::Error
+            } else {
+                CitationOutcome::NotFound
+            };
+            (c.article_number.clone(), outcome)
+        })
+        .collect();
+
+    debug!("✅ Article replacement complete. Answer: {} chars, Quotes: {}, Unverified citations: {}",
+             response.len(), law_quotes.len(), citations.iter().filter(|c| !c.verified).count());
 
     // Return the actual law name from database if we successfully found articles
     let actual_law_name = if !law_quotes.is_empty() {
@@ -436,37 +954,234 @@ async fn replace_article_references_with_law(response: &str, detected_law_name:
         law_quotes,
         law_name: actual_law_name.clone(),
         generated_contract: None,
-    }, actual_law_name))
+        is_fallback: false,
+        footnotes: vec![],
+        chat_title: None,
+        warnings: vec![],
+        citations,
+    }, actual_law_name, outcomes))
+}
+
+// Last article (in document order) of an indexed cached law, e.g. "300"
+async fn last_indexed_article(law_name: &str, pool: &PgPool) -> Option<String> {
+    sqlx::query_scalar::<_, String>(
+        "SELECT a.article_number
+         FROM law_articles a
+         JOIN law_cache c ON c.id = a.law_id
+         WHERE c.law_name = $1 AND c.articles_indexed = true
+         ORDER BY a.position DESC
+         LIMIT 1"
+    )
+    .bind(law_name)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or_else(|e| {
+        warn!("⚠️ Failed to look up last article of '{}': {}", law_name, e);
+        None
+    })
+}
+
+// Whether a cited article number ("412", "12a") lies beyond the law's last article
+fn article_exceeds_law(cited: &str, last_article: &str) -> bool {
+    let leading_number = |number: &str| -> Option<u32> {
+        let digits: String = number.chars().take_while(|c| c.is_ascii_digit()).collect();
+        digits.parse().ok()
+    };
+
+    match (leading_number(cited), leading_number(last_article)) {
+        (Some(cited), Some(last)) => cited > last,
+        _ => false,
+    }
+}
+
+// Warning listing citations that could not be matched to an article of the law
+fn unverified_citations_warning(citations: &[CitationCheck], law_name: &str) -> Option<ResponseWarning> {
+    let unverified: Vec<String> = citations
+        .iter()
+        .filter(|c| !c.verified)
+        .map(|c| match c.law_last_article.as_deref() {
+            Some(last) => format!("Član {} (zakon ima {} članova)", c.article_number, last),
+            None => format!("Član {}", c.article_number),
+        })
+        .collect();
+
+    if unverified.is_empty() {
+        return None;
+    }
+
+    Some(ResponseWarning::new(
+        "citation_unverified",
+        format!(
+            "Sledeći navodi nisu pronađeni u tekstu zakona \"{}\" i mogu biti netačni: {}.",
+            law_name,
+            unverified.join(", ")
+        ),
+    ))
+}
+
+// Ask the model to rewrite an answer without the citations that don't exist in the law.
+// Enabled with CITATION_SELF_CORRECT=true since it costs an extra LLM call.
+async fn self_correct_citations(
+    answer: &str,
+    citations: &[CitationCheck],
+    law_name: &str,
+    api_key: &str,
+    ctx: LlmCallContext<'_>,
+) -> Result<(String, Option<i64>), String> {
+    let invalid: Vec<String> = citations
+        .iter()
+        .filter(|c| !c.verified)
+        .map(|c| format!("Član {}", c.article_number))
+        .collect();
+
+    let correction_prompt = format!(
+        r#"U sledećem odgovoru navedeni su članovi koji NE POSTOJE u zakonu "{}": {}.
+
+ODGOVOR:
+{}
+
+Prepiši odgovor tako da ukloniš ili ispraviš te navode. Ne izmišljaj nove brojeve članova - navedi član samo ako si siguran da postoji. Zadrži isti stil i format, vrati SAMO ispravljen odgovor."#,
+        law_name,
+        invalid.join(", "),
+        answer
+    );
+
+    let request = OpenRouterRequest {
+        model: llm_config::resolve_model(LlmPurpose::Answer, ctx.account_type, ctx.pool).await,
+        messages: vec![OpenRouterMessage {
+            role: "user".to_string(),
+            content: correction_prompt,
+        }],
+        temperature: 0.2,
+    };
+    let input_chars = request.messages.iter().map(|m| m.content.len()).sum();
+    let started_at = std::time::Instant::now();
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post("https://openrouter.ai/api/v1/chat/completions")
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Content-Type", "application/json")
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| format!("Citation correction API error: {}", e))?;
+
+    let response_text = response.text().await
+        .map_err(|e| format!("Failed to read citation correction response: {}", e))?;
+
+    let parsed_response: OpenRouterResponse = match serde_json::from_str(&response_text) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let error = format!("Failed to parse citation correction response: {} - Response: {}", e, response_text);
+            audit_llm_call(ctx, &request.model, "citation_correction", llm_token_counts(None, input_chars, 0), started_at, Some(&error)).await;
+            return Err(error);
+        }
+    };
+
+    let corrected = parsed_response.choices
+        .first()
+        .ok_or("No citation correction response received")?
+        .message
+        .content
+        .trim()
+        .to_string();
+
+    let token_counts = llm_token_counts(parsed_response.usage.as_ref(), input_chars, corrected.len());
+    let llm_request_id = audit_llm_call(ctx, &request.model, "citation_correction", token_counts, started_at, None).await;
+
+    if corrected.is_empty() {
+        return Err("Empty citation correction generated".to_string());
+    }
+
+    Ok((corrected, llm_request_id))
+}
+
+// Insert footnote markers ([1], [2]...) after article mentions in the answer, bound to law_quotes.
+// Markers are numbered in order of first mention. Quotes are formatted as "**Član N**\n..."
+// so the article number is read back from each quote.
+fn link_answer_footnotes(answer: &str, law_quotes: &[String]) -> (String, Vec<Footnote>) {
+    use regex::Regex;
+
+    let quote_header = Regex::new(r"^\*\*Član\s+([0-9]+[a-z]?)\*\*").unwrap();
+    let quote_articles: Vec<(String, usize)> = law_quotes
+        .iter()
+        .enumerate()
+        .filter_map(|(quote_index, quote)| {
+            quote_header.captures(quote).map(|cap| (cap[1].to_string(), quote_index))
+        })
+        .collect();
+
+    let mut footnotes: Vec<Footnote> = Vec::new();
+    if quote_articles.is_empty() {
+        return (answer.to_string(), footnotes);
+    }
+
+    // Match the full article number (with optional stav/tačka suffix) so "Član 17" never links "Član 179"
+    let mention_pattern = Regex::new(r"Član\s+([0-9]+[a-z]?)\b(?:\.?\s+stav\s+[0-9]+)?(?:\.?\s+tačka\s+[0-9]+)?\.?( \[[0-9]+\])?").unwrap();
+
+    let linked = mention_pattern.replace_all(answer, |cap: &regex::Captures| {
+        let mention = &cap[0];
+        // Already marked - leave untouched
+        if cap.get(2).is_some() {
+            return mention.to_string();
+        }
+
+        let article_number = &cap[1];
+        let marker = match footnotes.iter().find(|f| f.article_number == article_number) {
+            Some(footnote) => Some(footnote.marker),
+            None => quote_articles
+                .iter()
+                .find(|(number, _)| number == article_number)
+                .map(|(_, quote_index)| {
+                    let marker = footnotes.len() + 1;
+                    footnotes.push(Footnote {
+                        marker,
+                        quote_index: *quote_index,
+                        article_number: article_number.to_string(),
+                    });
+                    marker
+                }),
+        };
+
+        match marker {
+            Some(marker) => format!("{} [{}]", mention, marker),
+            None => mention.to_string(), // No quote for this article
+        }
+    }).to_string();
+
+    (linked, footnotes)
 }
 
 // Helper function to try to get law URL for common laws with flexible matching
 fn try_get_law_url(law_name: &str) -> Option<String> {
-    let all_laws = laws::get_serbian_laws();
+    let all_laws = laws::get_laws(laws::jurisdiction_of_law(law_name));
 
     // First try exact match
     if let Some(law) = all_laws.iter().find(|law| law.name == law_name) {
-        println!("✅ DEBUG: Exact match found for '{}'", law_name);
+        debug!("✅ Exact match found for '{}'", law_name);
         return Some(law.url.clone());
     }
 
-    // Try case-insensitive match
-    let law_name_lower = law_name.to_lowercase();
-    if let Some(law) = all_laws.iter().find(|law| law.name.to_lowercase() == law_name_lower) {
-        println!("✅ DEBUG: Case-insensitive match found for '{}'", law_name);
+    // Try case-insensitive match (a Cyrillic name from the LLM matches its Latin registry entry)
+    let normalize = |name: &str| crate::transliteration::to_latin(name).to_lowercase();
+    let law_name_lower = normalize(law_name);
+    if let Some(law) = all_laws.iter().find(|law| normalize(&law.name) == law_name_lower) {
+        debug!("✅ Case-insensitive match found for '{}'", law_name);
         return Some(law.url.clone());
     }
 
     // Try partial match (law name contains the search term or vice versa)
     if let Some(law) = all_laws.iter().find(|law|
-        law.name.to_lowercase().contains(&law_name_lower) ||
-        law_name_lower.contains(&law.name.to_lowercase())
+        normalize(&law.name).contains(&law_name_lower) ||
+        law_name_lower.contains(&normalize(&law.name))
     ) {
-        println!("✅ DEBUG: Partial match found for '{}' -> '{}'", law_name, law.name);
+        debug!("✅ Partial match found for '{}' -> '{}'", law_name, law.name);
         return Some(law.url.clone());
     }
 
-    println!("❌ DEBUG: No match found for law name '{}'", law_name);
-    println!("🔍 DEBUG: Available laws: {:?}", all_laws.iter().map(|l| &l.name).collect::<Vec<_>>());
+    warn!("❌ No match found for law name '{}'", law_name);
+    debug!("🔍 Available laws: {:?}", all_laws.iter().map(|l| &l.name).collect::<Vec<_>>());
     None
 }
 
@@ -476,251 +1191,1358 @@ fn try_get_law_url(law_name: &str) -> Option<String> {
 
 
 
-pub async fn ask_question_handler(
-    State((pool, openrouter_api_key, _openai_api_key, jwt_secret, supabase_jwt_secret)): State<AppState>,
-    headers: HeaderMap,
-    Json(request): Json<QuestionRequest>,
-) -> Result<ResponseJson<QuestionResponse>, StatusCode> {
-    println!("🚀 ================== NEW QUESTION REQUEST ==================");
-    println!("🔍 DEBUG: Received ask_question request");
-    println!("🔍 DEBUG: Request data: question='{}', law_name={:?}, law_url={:?}, chat_id={}, has_document_content={}", 
-        request.question, 
-        request.law_name, 
-        request.law_url, 
-        request.chat_id,
-        request.document_content.is_some()
-    );
-    
+// Maximum number of cached articles returned when the LLM is unavailable
+const FALLBACK_MAX_ARTICLES: usize = 3;
+
+// Common Serbian words that carry no meaning for article matching
+const FALLBACK_STOPWORDS: &[&str] = &[
+    "koji", "koja", "koje", "kako", "kada", "zašto", "zasto", "koliko", "može", "moze", "mogu",
+    "moram", "treba", "trebam", "imam", "biti", "bilo", "bila", "jesam", "nije", "nisam", "mene",
+    "meni", "moje", "moja", "svoj", "svoje", "neki", "neka", "neko", "ovaj", "samo", "zbog",
+    "prema", "posle", "nakon", "tokom", "molim", "pitanje", "zakon", "zakona", "zakonu", "član",
+];
+
+// Extract normalized keyword stems from the question for article matching.
+// Stems are truncated to 6 characters to tolerate Serbian case endings (otkaz/otkaza/otkazom).
+pub(crate) fn extract_question_keywords(question: &str) -> Vec<String> {
+    let mut keywords = Vec::new();
+
+    for word in question.to_lowercase().split(|c: char| !c.is_alphanumeric()) {
+        if word.chars().count() < 4 || FALLBACK_STOPWORDS.contains(&word) {
+            continue;
+        }
+
+        let stem: String = word.chars().take(6).collect();
+        if !keywords.contains(&stem) {
+            keywords.push(stem);
+        }
+    }
+
+    keywords
+}
+
+// (score, article_number, article_body)
+type ScoredArticle = (usize, String, String);
+
+// Score an article by keyword coverage - distinct matches weigh more than repeats
+pub(crate) fn score_article(article_body: &str, keywords: &[String]) -> usize {
+    let body_lower = article_body.to_lowercase();
+    let mut score = 0;
+
+    for keyword in keywords {
+        let occurrences = body_lower.matches(keyword.as_str()).count();
+        if occurrences > 0 {
+            score += 10 + occurrences.min(5);
+        }
+    }
+
+    score
+}
+
+// Build a non-AI response from cached articles matching the question keywords.
+// Used when the LLM call fails so the user still gets something useful instead of a 500.
+async fn build_cached_articles_fallback(question: &str, pool: &PgPool) -> Result<QuestionResponse, String> {
+    let keywords = extract_question_keywords(question);
+    info!("🛟 FALLBACK: Matching cached articles against keywords: {:?}", keywords);
+
+    // (law_name, top_articles, law_score)
+    let mut best_law: Option<(String, Vec<ScoredArticle>, usize)> = None;
+
+    if !keywords.is_empty() {
+        let patterns: Vec<String> = keywords.iter().map(|k| format!("%{}%", k)).collect();
+
+        // Articles were parsed when the laws were cached - match them directly instead of
+        // re-splitting whole law texts. Expired entries are still better than nothing while the LLM is down.
+        let rows = sqlx::query_as::<_, (String, String, String)>(
+            "SELECT c.law_name, a.article_number, a.content
+             FROM law_articles a
+             JOIN law_cache c ON c.id = a.law_id
+             WHERE a.position >= 0 AND a.content ILIKE ANY($1)
+             ORDER BY c.cached_at DESC, a.position
+             LIMIT 2000"
+        )
+        .bind(&patterns)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to search cached articles: {}", e))?;
+
+        let mut laws: Vec<(String, Vec<ScoredArticle>)> = Vec::new();
+        for (law_name, number, body) in rows {
+            let score = score_article(&body, &keywords);
+            if score == 0 {
+                continue;
+            }
+            match laws.iter_mut().find(|(name, _)| *name == law_name) {
+                Some((_, scored)) => scored.push((score, number, body)),
+                None => laws.push((law_name, vec![(score, number, body)])),
+            }
+        }
+
+        for (law_name, mut scored) in laws {
+            scored.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+            scored.truncate(FALLBACK_MAX_ARTICLES);
+
+            let law_score: usize = scored.iter().map(|(score, _, _)| score).sum();
+            let best_score = best_law.as_ref().map(|(_, _, score)| *score).unwrap_or(0);
+
+            if law_score > best_score {
+                best_law = Some((law_name, scored, law_score));
+            }
+        }
+    }
+
+    let notice = "⚠️ AI asistent je trenutno nedostupan. Izvinjavamo se zbog neprijatnosti.";
+    let llm_unavailable_warning = || ResponseWarning::new(
+        "llm_unavailable",
+        "AI asistent nije bio dostupan - prikazani su samo članovi pronađeni automatskom pretragom.",
+    );
+
+    let response = match best_law {
+        Some((law_name, articles, _)) => {
+            info!("🛟 FALLBACK: Returning {} cached articles from '{}'", articles.len(), law_name);
+            QuestionResponse {
+                answer: format!(
+                    "{}\n\nU nastavku su članovi iz zakona koji bi mogli biti relevantni za vaše pitanje. Ovo NIJE odgovor AI asistenta već automatska pretraga - molimo pokušajte ponovo za nekoliko minuta.",
+                    notice
+                ),
+                law_quotes: articles
+                    .into_iter()
+                    .map(|(_, number, body)| format!("**Član {}**\n{}", number, body))
+                    .collect(),
+                law_name: Some(law_name),
+                generated_contract: None,
+                is_fallback: true,
+                footnotes: vec![],
+                chat_title: None,
+                warnings: vec![llm_unavailable_warning()],
+                citations: vec![],
+            }
+        }
+        None => {
+            info!("🛟 FALLBACK: No cached articles matched the question");
+            QuestionResponse {
+                answer: format!(
+                    "{}\n\nNismo pronašli relevantne članove zakona u našoj bazi. Molimo pokušajte ponovo za nekoliko minuta.",
+                    notice
+                ),
+                law_quotes: vec![],
+                law_name: None,
+                generated_contract: None,
+                is_fallback: true,
+                footnotes: vec![],
+                chat_title: None,
+                warnings: vec![llm_unavailable_warning()],
+                citations: vec![],
+            }
+        }
+    };
+
+    Ok(response)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/question",
+    tag = "questions",
+    request_body = QuestionRequest,
+    responses(
+        (status = 200, description = "Answer with the quoted law articles", body = QuestionResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 402, description = "Trial or message limit reached", body = ErrorResponse),
+        (status = 403, description = "Terms of use not accepted (CONSENT_REQUIRED)", body = ErrorResponse),
+        (status = 422, description = "Request refused by moderation (REQUEST_REFUSED)", body = ErrorResponse),
+        (status = 429, description = "Rate limited", body = ErrorResponse),
+        (status = 503, description = "The model is unavailable", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn ask_question_handler(
+    State((pool, openrouter_api_key, openai_api_key, _, _)): State<AppState>,
+    headers: HeaderMap,
+    axum::Extension(crate::consent::VerifiedUser(user_id)): axum::Extension<crate::consent::VerifiedUser>,
+    Json(mut request): Json<QuestionRequest>,
+) -> Result<ResponseJson<QuestionResponse>, ApiError> {
+    info!(
+        chat_id = request.chat_id,
+        has_document = request.document_content.is_some() || request.document_id.is_some(),
+        manual_law = request.law_name.is_some(),
+        "🚀 New question request"
+    );
+    debug!("🔍 Request data: question='{}', law_name={:?}, law_url={:?}, chat_id={}, has_document_content={}", 
+        request.question, 
+        request.law_name, 
+        request.law_url, 
+        request.chat_id,
+        request.document_content.is_some()
+    );
+    
+
+    let is_manual_law_selection = request.law_name.is_some() && request.law_url.is_some();
+    if is_manual_law_selection {
+        debug!("⚡ MANUAL LAW SELECTION: User specified law, skipping auto-detection");
+    } else {
+        debug!("🤖 AUTO LAW DETECTION: Will use keyword-based law selection process");
+    }
+    
+    // Extract IP address from Fly.io headers (proper way for proxy environments)
+    let client_ip = extract_client_ip(&headers);
+
+    debug!("🔍 Client IP: {}", client_ip);
+
+    // User for usage tracking and limit checking, verified once by consent::require_consent
+    debug!("🔍 User info - user_id: {:?}", user_id);
+    if let Some(user_id) = user_id {
+        tracing::Span::current().record("user_id", tracing::field::display(user_id));
+    }
+
+    // Retried submission (Idempotency-Key): answer it once, replay the stored response afterwards
+    let idempotency_guard = match (user_id, crate::idempotency::key_from_headers(&headers)?) {
+        (Some(user_id), Some(key)) => {
+            let request_hash = crate::idempotency::fingerprint(&request);
+            match crate::idempotency::begin(user_id, &key, &request_hash, &pool).await? {
+                crate::idempotency::Begin::New(guard) => Some(guard),
+                crate::idempotency::Begin::Replay(response) => return Ok(ResponseJson(*response)),
+            }
+        }
+        _ => None,
+    };
+
+    // Resolve a server-side extracted document into document_content
+    let mut document_truncated = false;
+    if let Some(document_id) = request.document_id {
+        if request.document_content.is_none() {
+            let owner_id = user_id.ok_or(StatusCode::UNAUTHORIZED)?;
+            let (filename, text, truncated) = crate::documents::get_document_text(document_id, owner_id, &pool).await
+                .map_err(|e| {
+                    error!("{}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?
+                .ok_or(StatusCode::NOT_FOUND)?;
+
+            debug!("🔍 Using extracted document {} ({} chars)", document_id, text.len());
+            request.document_filename = request.document_filename.or(Some(filename));
+            request.document_content = Some(text);
+            document_truncated = truncated;
+        }
+    }
+
+    // Validate document upload permission for Professional/Team/Premium users only
+    if request.document_content.is_some() {
+        let user = database::get_user(user_id, &pool).await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        if let Some(user) = user {
+            if !user.can_upload_documents() {
+                error!("❌ SECURITY: User with account_type '{}' attempted document upload - BLOCKED", user.account_type);
+                return Err(StatusCode::FORBIDDEN.into());
+            }
+        } else {
+            error!("❌ SECURITY: Unregistered user attempted document upload - BLOCKED");
+            return Err(StatusCode::FORBIDDEN.into());
+        }
+    }
+
+    // Refuse requests for help with crimes, strip prompt injections from question and document
+    crate::moderation::screen_request(&mut request)?;
+
+    // Trial accounts: refuse devices/networks cycling through accounts for fresh trials
+    crate::trial_abuse::check_trial_device(user_id, &headers, &client_ip, &pool).await?;
+
+    // Check if user can send message (trial users need remaining messages, premium unlimited)
+    debug!("🔍 Checking if user can send message...");
+    match database::can_send_message(user_id, &pool).await {
+        Ok(allowance) => {
+            if let Err(e) = allowance.into_result("question") {
+                warn!("❌ User cannot send message - message limit or cost cap reached");
+                // HTTP 429, with a structured error in the body when the cost cap was hit
+                return Err(e);
+            }
+            debug!("✅ User can send message");
+        }
+        Err(e) => {
+            warn!("❌ Error checking message limits: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+        }
+    }
+
+    // Wait for a processing slot - clients poll /api/queue/status with client_request_id meanwhile
+    let queue_slot = crate::llm_queue::global()
+        .acquire(request.client_request_id.clone())
+        .await;
+    if let Err(e) = crate::llm_queue::record_queue_metrics(&queue_slot, user_id, &pool).await {
+        warn!("⚠️  {}", e);
+    }
+
+    // Process question with new free response system
+    debug!("🔍 Starting free response processing...");
+    let mut enhanced_response = run_question_pipeline(
+        &request,
+        user_id,
+        None,
+        &pool,
+        &openrouter_api_key,
+        &openai_api_key,
+    ).await?;
+
+    // Release the slot before the remaining bookkeeping
+    drop(queue_slot);
+
+    if document_truncated {
+        enhanced_response.warnings.push(ResponseWarning::new(
+            "document_truncated",
+            "Dokument je skraćen zbog dužine - analiziran je samo njegov početni deo.",
+        ));
+    }
+
+    debug!("✅ Free response processing successful");
+
+    // Decrement trial messages after successful message processing (skip for premium users)
+    let user = database::get_user(user_id, &pool).await
+        .map_err(|e| {
+            error!("Failed to get user for message decrement check: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if enhanced_response.is_fallback {
+        // Fallback answers are not AI responses - don't count them against the quota
+        debug!("🛟 Fallback response - skipping trial message decrement");
+    } else if let Some(user) = user {
+        if user.account_type != "premium" {
+            let request_key = idempotency_guard.as_ref().map(|guard| guard.key());
+            if let Err(e) = database::decrement_trial_message(user_id, request_key, &pool).await {
+                // Log error but don't fail the request since AI response was successful
+                warn!("⚠️  CRITICAL: Failed to decrement trial messages for user_id={:?}: {}", user_id, e);
+            } else {
+                debug!("✅ Successfully decremented trial message count for user_id={:?}", user_id);
+            }
+        } else {
+            debug!("✅ Premium user - skipping trial message decrement");
+        }
+    }
+
+    let script = match request.script {
+        Some(script) => script,
+        None => crate::transliteration::preferred_script(user_id, &pool).await,
+    };
+    crate::transliteration::apply_script(&mut enhanced_response, script);
+
+    if let Some(guard) = idempotency_guard {
+        guard.complete(&enhanced_response).await;
+    }
+
+    debug!("✅ Request processing completed successfully");
+    Ok(ResponseJson(enhanced_response))
+}
+
+/// Regenerations allowed per question, since they aren't charged against the trial
+const MAX_REGENERATIONS_PER_MESSAGE: i64 = 3;
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct RegenerateRequest {
+    #[serde(default)]
+    pub document_id: Option<Uuid>, // Document text isn't stored with messages - re-attach it for document questions
+    #[serde(default)]
+    pub client_request_id: Option<String>,
+    #[serde(default)]
+    pub script: Option<ResponseScript>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct RegenerationTarget {
+    chat_id: i64,
+    user_message_id: i64,
+    question: String,
+    has_document: Option<bool>,
+    document_filename: Option<String>,
+    regenerations: i64,
+}
+
+// Answer the question of an assistant message again. The old answer is kept but marked superseded;
+// the regeneration isn't counted against the trial.
+#[utoipa::path(
+    post,
+    path = "/api/messages/{message_id}/regenerate",
+    tag = "questions",
+    params(("message_id" = i64, Path, description = "Message id")),
+    request_body(content = Option<RegenerateRequest>),
+    responses(
+        (status = 200, description = "New answer to the same question", body = QuestionResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Message not found", body = ErrorResponse),
+        (status = 429, description = "Regeneration limit reached", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn regenerate_message_handler(
+    State((pool, openrouter_api_key, openai_api_key, _, _)): State<AppState>,
+    headers: HeaderMap,
+    axum::Extension(crate::consent::VerifiedUser(user_id)): axum::Extension<crate::consent::VerifiedUser>,
+    axum::extract::Path(message_id): axum::extract::Path<i64>,
+    payload: Option<Json<RegenerateRequest>>,
+) -> Result<ResponseJson<QuestionResponse>, ApiError> {
+    let user_id = user_id.ok_or(StatusCode::UNAUTHORIZED)?;
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+
+    // Only the chat's latest answer can be regenerated - later messages were built on it
+    let target = sqlx::query_as::<_, RegenerationTarget>(
+        "SELECT a.chat_id, q.id AS user_message_id, q.content AS question, q.has_document, q.document_filename,
+                (SELECT COUNT(*) FROM messages s
+                 WHERE s.chat_id = a.chat_id AND s.role = 'assistant' AND s.superseded_at IS NOT NULL AND s.id > q.id) AS regenerations
+         FROM messages a
+         JOIN chats c ON c.id = a.chat_id
+         JOIN LATERAL (
+             SELECT id, content, has_document, document_filename FROM messages
+             WHERE chat_id = a.chat_id AND role = 'user' AND superseded_at IS NULL AND created_at < a.created_at
+             ORDER BY created_at DESC LIMIT 1
+         ) q ON true
+         WHERE a.id = $1 AND a.role = 'assistant' AND a.superseded_at IS NULL
+           AND c.user_id = $2 AND c.deleted_at IS NULL
+           AND NOT EXISTS (SELECT 1 FROM messages later WHERE later.chat_id = a.chat_id AND later.created_at > a.created_at AND later.superseded_at IS NULL)"
+    )
+    .bind(message_id)
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to load message to regenerate: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    if target.regenerations >= MAX_REGENERATIONS_PER_MESSAGE {
+        return Err(StatusCode::TOO_MANY_REQUESTS.into());
+    }
+
+    // Regenerations aren't charged against the trial, but they need the same right to ask as a new
+    // question: an exhausted trial, expired plan, spend cap or trial-abuse block stops them too
+    let client_ip = extract_client_ip(&headers);
+    crate::trial_abuse::check_trial_device(Some(user_id), &headers, &client_ip, &pool).await?;
+    match database::can_send_message(Some(user_id), &pool).await {
+        Ok(allowance) => allowance.into_result("regenerate")?,
+        Err(e) => {
+            error!("Failed to check message limits: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+        }
+    }
+
+    let mut request = QuestionRequest {
+        question: target.question,
+        document_content: None,
+        document_filename: target.document_filename,
+        law_name: None,
+        law_url: None,
+        chat_id: target.chat_id,
+        client_request_id: payload.client_request_id,
+        document_id: payload.document_id,
+        script: payload.script,
+        jurisdiction: None,
+    };
+
+    if target.has_document.unwrap_or(false) {
+        let document_id = payload.document_id.ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+        let (_, text, _) = crate::documents::get_document_text(document_id, user_id, &pool).await
+            .map_err(|e| {
+                error!("{}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or(StatusCode::NOT_FOUND)?;
+        request.document_content = Some(text);
+    }
+    crate::moderation::screen_request(&mut request)?;
+
+    let queue_slot = crate::llm_queue::global()
+        .acquire(request.client_request_id.clone())
+        .await;
+    if let Err(e) = crate::llm_queue::record_queue_metrics(&queue_slot, Some(user_id), &pool).await {
+        warn!("⚠️  {}", e);
+    }
+
+    debug!("🔁 Regenerating assistant message {} in chat {}", message_id, request.chat_id);
+    let mut enhanced_response = run_question_pipeline(
+        &request,
+        Some(user_id),
+        Some(target.user_message_id),
+        &pool,
+        &openrouter_api_key,
+        &openai_api_key,
+    ).await?;
+    drop(queue_slot);
+
+    // The new answer is saved by now; hide the old one from the chat and from later history
+    if let Err(e) = sqlx::query("UPDATE messages SET superseded_at = NOW() WHERE id = $1 AND superseded_at IS NULL")
+        .bind(message_id)
+        .execute(&pool)
+        .await
+    {
+        warn!("⚠️  Failed to mark message {} as superseded: {}", message_id, e);
+    }
+
+    let script = match request.script {
+        Some(script) => script,
+        None => crate::transliteration::preferred_script(Some(user_id), &pool).await,
+    };
+    crate::transliteration::apply_script(&mut enhanced_response, script);
+
+    Ok(ResponseJson(enhanced_response))
+}
+
+/// Edits allowed per question - each one is a new LLM run
+const MAX_EDITS_PER_MESSAGE: i64 = 10;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct EditMessageRequest {
+    pub content: String,
+    #[serde(default)]
+    pub document_id: Option<Uuid>, // Re-attach the document for document questions, as with regeneration
+    #[serde(default)]
+    pub client_request_id: Option<String>,
+    #[serde(default)]
+    pub script: Option<ResponseScript>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EditMessageResponse {
+    pub message_id: i64,
+    pub response: QuestionResponse,
+    pub stale_message_ids: Vec<i64>, // Later answers that were based on the old question
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct EditTarget {
+    chat_id: i64,
+    content: String,
+    has_document: Option<bool>,
+    document_filename: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    edits: i64,
+}
+
+// Edit a user question and answer it again from that point of the chat. The previous text is kept
+// in message_revisions; the old answer is superseded and answers further down are marked stale.
+// Counted against the trial like a new question.
+#[utoipa::path(
+    put,
+    path = "/api/messages/{message_id}",
+    tag = "questions",
+    params(("message_id" = i64, Path, description = "Message id")),
+    request_body = EditMessageRequest,
+    responses(
+        (status = 200, description = "Answer to the edited question", body = EditMessageResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Message not found", body = ErrorResponse),
+        (status = 422, description = "Request refused by moderation (REQUEST_REFUSED)", body = ErrorResponse),
+        (status = 429, description = "Edit limit reached", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn edit_message_handler(
+    State((pool, openrouter_api_key, openai_api_key, _, _)): State<AppState>,
+    headers: HeaderMap,
+    axum::Extension(crate::consent::VerifiedUser(user_id)): axum::Extension<crate::consent::VerifiedUser>,
+    axum::extract::Path(message_id): axum::extract::Path<i64>,
+    Json(payload): Json<EditMessageRequest>,
+) -> Result<ResponseJson<EditMessageResponse>, ApiError> {
+    let user_id = user_id.ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let target = sqlx::query_as::<_, EditTarget>(
+        "SELECT m.chat_id, m.content, m.has_document, m.document_filename, m.created_at,
+                (SELECT COUNT(*) FROM message_revisions r WHERE r.message_id = m.id) AS edits
+         FROM messages m
+         JOIN chats c ON c.id = m.chat_id
+         WHERE m.id = $1 AND m.role = 'user' AND m.superseded_at IS NULL
+           AND c.user_id = $2 AND c.deleted_at IS NULL"
+    )
+    .bind(message_id)
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to load message to edit: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let content = payload.content.trim().to_string();
+    if content.is_empty() || content == target.content.trim() {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    if target.edits >= MAX_EDITS_PER_MESSAGE {
+        return Err(StatusCode::TOO_MANY_REQUESTS.into());
+    }
+
+    let client_ip = extract_client_ip(&headers);
+    crate::trial_abuse::check_trial_device(Some(user_id), &headers, &client_ip, &pool).await?;
+    match database::can_send_message(Some(user_id), &pool).await {
+        Ok(allowance) => allowance.into_result("question")?,
+        Err(e) => {
+            error!("Failed to check message limits: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+        }
+    }
+
+    let mut request = QuestionRequest {
+        question: content.clone(),
+        document_content: None,
+        document_filename: target.document_filename,
+        law_name: None,
+        law_url: None,
+        chat_id: target.chat_id,
+        client_request_id: payload.client_request_id,
+        document_id: payload.document_id,
+        script: payload.script,
+        jurisdiction: None,
+    };
+
+    if target.has_document.unwrap_or(false) {
+        let document_id = payload.document_id.ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+        let (_, text, _) = crate::documents::get_document_text(document_id, user_id, &pool).await
+            .map_err(|e| {
+                error!("{}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or(StatusCode::NOT_FOUND)?;
+        request.document_content = Some(text);
+    }
+    crate::moderation::screen_request(&mut request)?;
+
+    // Messages saved from here on belong to the re-run
+    let last_id_before = sqlx::query_scalar::<_, Option<i64>>("SELECT MAX(id) FROM messages WHERE chat_id = $1")
+        .bind(target.chat_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to read chat messages: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .unwrap_or(0);
+
+    let queue_slot = crate::llm_queue::global()
+        .acquire(request.client_request_id.clone())
+        .await;
+    if let Err(e) = crate::llm_queue::record_queue_metrics(&queue_slot, Some(user_id), &pool).await {
+        warn!("⚠️  {}", e);
+    }
+
+    // History is cut at the edited message, so later exchanges don't leak into the new answer.
+    // Nothing is changed until the new answer exists - a failed run leaves the chat as it was.
+    debug!("✏️  Re-running chat {} from edited message {}", request.chat_id, message_id);
+    let mut enhanced_response = run_question_pipeline(
+        &request,
+        Some(user_id),
+        Some(message_id),
+        &pool,
+        &openrouter_api_key,
+        &openai_api_key,
+    ).await?;
+    drop(queue_slot);
+
+    let (stored_content, pii_mapping) = crate::pii::prepare_for_storage(target.chat_id, content, &pool)
+        .await
+        .map_err(|e| {
+            error!("{}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let stale_message_ids = apply_message_edit(message_id, &target.content, &stored_content, pii_mapping.as_deref(), target.chat_id, target.created_at, last_id_before, &pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to save message edit: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if !enhanced_response.is_fallback {
+        if let Err(e) = database::decrement_trial_message(Some(user_id), None, &pool).await {
+            warn!("⚠️  Failed to decrement trial messages for user_id={}: {}", user_id, e);
+        }
+    }
+
+    let script = match request.script {
+        Some(script) => script,
+        None => crate::transliteration::preferred_script(Some(user_id), &pool).await,
+    };
+    crate::transliteration::apply_script(&mut enhanced_response, script);
+
+    Ok(ResponseJson(EditMessageResponse {
+        message_id,
+        response: enhanced_response,
+        stale_message_ids,
+    }))
+}
+
+/// Store the old text, supersede the edited question's previous answer, flag later answers as
+/// stale and move the new answer (saved at the end of the chat) right after the edited question.
+/// Returns the ids of the stale answers.
+#[allow(clippy::too_many_arguments)]
+async fn apply_message_edit(
+    message_id: i64,
+    old_content: &str,
+    new_content: &str,
+    pii_mapping: Option<&str>,
+    chat_id: i64,
+    edited_created_at: chrono::DateTime<chrono::Utc>,
+    last_id_before: i64,
+    pool: &PgPool,
+) -> Result<Vec<i64>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("INSERT INTO message_revisions (message_id, content) VALUES ($1, $2)")
+        .bind(message_id)
+        .bind(old_content)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE messages SET content = $1, pii_mapping_encrypted = $3, edited_at = NOW() WHERE id = $2")
+        .bind(new_content)
+        .bind(message_id)
+        .bind(pii_mapping)
+        .execute(&mut *tx)
+        .await?;
+
+    // The next question after the edited one bounds its previous answer(s)
+    let next_question_at = sqlx::query_scalar::<_, Option<chrono::DateTime<chrono::Utc>>>(
+        "SELECT MIN(created_at) FROM messages
+         WHERE chat_id = $1 AND role = 'user' AND superseded_at IS NULL AND created_at > $2"
+    )
+    .bind(chat_id)
+    .bind(edited_created_at)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "UPDATE messages SET superseded_at = NOW()
+         WHERE chat_id = $1 AND role = 'assistant' AND superseded_at IS NULL AND id <= $2
+           AND created_at > $3 AND ($4::TIMESTAMPTZ IS NULL OR created_at < $4)"
+    )
+    .bind(chat_id)
+    .bind(last_id_before)
+    .bind(edited_created_at)
+    .bind(next_question_at)
+    .execute(&mut *tx)
+    .await?;
+
+    let stale_message_ids = match next_question_at {
+        Some(next_question_at) => sqlx::query_scalar::<_, i64>(
+            "UPDATE messages SET stale_at = NOW()
+             WHERE chat_id = $1 AND role = 'assistant' AND superseded_at IS NULL AND id <= $2
+               AND created_at > $3
+             RETURNING id"
+        )
+        .bind(chat_id)
+        .bind(last_id_before)
+        .bind(next_question_at)
+        .fetch_all(&mut *tx)
+        .await?,
+        None => Vec::new(),
+    };
+
+    sqlx::query(
+        "UPDATE messages SET created_at = $3 + INTERVAL '1 millisecond'
+         WHERE chat_id = $1 AND role = 'assistant' AND id > $2"
+    )
+    .bind(chat_id)
+    .bind(last_id_before)
+    .bind(edited_created_at)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(stale_message_ids)
+}
+
+// Regenerate a chat title from its first exchange
+#[utoipa::path(
+    post,
+    path = "/api/chats/{chat_id}/auto-title",
+    tag = "chats",
+    params(("chat_id" = i64, Path, description = "Chat id")),
+    responses(
+        (status = 200, description = "Generated title", body = AutoTitleResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Chat not found or not owned by the user")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn auto_title_handler(
+    State((pool, openrouter_api_key, _openai_api_key, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    axum::extract::Path(chat_id): axum::extract::Path<i64>,
+) -> Result<ResponseJson<AutoTitleResponse>, StatusCode> {
+    let user_id = database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let chat_exists = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM chats WHERE id = $1 AND user_id = $2)"
+    )
+    .bind(chat_id)
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to verify chat ownership: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if !chat_exists {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let messages = get_messages(chat_id, &pool).await.map_err(|e| {
+        error!("{}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let question = messages.iter().find(|m| m.role == "user").ok_or(StatusCode::BAD_REQUEST)?;
+    let answer = messages.iter().find(|m| m.role == "assistant").map(|m| m.content.as_str()).unwrap_or("");
+
+    let account_type = database::get_user(Some(user_id), &pool).await
+        .ok()
+        .flatten()
+        .map(|user| user.account_type);
+    let llm_ctx = LlmCallContext {
+        user_id: Some(user_id),
+        chat_id: Some(chat_id),
+        account_type: account_type.as_deref(),
+        jurisdiction: crate::jurisdictions::chat_jurisdiction(chat_id, &pool).await,
+        pool: &pool,
+    };
+
+    let (title, _) = generate_chat_title(&question.content, answer, &openrouter_api_key, llm_ctx)
+        .await
+        .map_err(|e| {
+            warn!("❌ Chat title generation failed: {}", e);
+            StatusCode::BAD_GATEWAY
+        })?;
+
+    save_chat_title(chat_id, &title, Some(user_id), &pool).await.map_err(|e| {
+        error!("{}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(ResponseJson(AutoTitleResponse { chat_id, title }))
+}
+
+/// Answer a question from a device without an account (free daily question).
+/// Same pipeline as a chat's first exchange, minus history, documents, contracts and persistence.
+/// Returns the response and the message content to save if the device later registers.
+pub(crate) async fn answer_anonymous_question(
+    question: &str,
+    api_key: &str,
+    openai_api_key: &str,
+    pool: &PgPool,
+) -> Result<(QuestionResponse, String), String> {
+    let llm_ctx = LlmCallContext {
+        user_id: None,
+        chat_id: None,
+        account_type: None,
+        jurisdiction: Jurisdiction::Rs,
+        pool,
+    };
+
+    let cache_scope = crate::answer_cache::CacheScope::resolve(None, None, Jurisdiction::Rs, pool).await;
+    let cached_answer = match &cache_scope {
+        Some(scope) => crate::answer_cache::lookup(question, scope, pool).await,
+        None => None,
+    };
+    let is_legal = classify_unless_cached(question, cached_answer.is_some(), api_key, llm_ctx).await.0;
+
+    let (llm_response, detected_law_name) = if let Some(cached) = cached_answer {
+        (cached.answer, cached.law_name)
+    } else if is_legal {
+        let (retrieved_articles, retrieved_context) = retrieve_articles(question, Jurisdiction::Rs, openai_api_key, pool).await;
+
+        let (llm_response, _) = process_question_with_free_response(
+            question,
+            &[],
+            None,
+            retrieved_context.as_deref(),
+            llm_ctx,
+            api_key,
+        ).await?;
+
+        let detected_law_name = detect_answer_law(question, api_key, llm_ctx).await.0
+            .or_else(|| retrieved_articles.first().map(|a| a.law_name.clone()));
+
+        // Contract drafts aren't offered without an account, so those answers aren't cached
+        if let (None, Some(scope)) = (crate::contracts::detect_contract(&llm_response), &cache_scope) {
+            crate::answer_cache::store(question, scope, &llm_response, detected_law_name.as_deref(), pool).await;
+        }
+        (llm_response, detected_law_name)
+    } else {
+        (NON_LEGAL_REFUSAL.to_string(), None)
+    };
+
+    let (mut enhanced_response, actual_law_name, citation_outcomes) = replace_article_references_with_law(&llm_response, detected_law_name.as_deref(), pool).await?;
+    if let Some(law_name) = detected_law_name.as_deref() {
+        crate::citation_audit::record_citations(law_name, &citation_outcomes, pool).await;
+    }
+
+    if let Some((_, clean_response)) = crate::contracts::detect_contract(&llm_response) {
+        enhanced_response.answer = clean_response;
+    }
+
+    let response_content = finish_answer(
+        &mut enhanced_response,
+        is_legal,
+        detected_law_name.as_deref(),
+        actual_law_name.as_deref(),
+        pool,
+    ).await;
 
-    let is_manual_law_selection = request.law_name.is_some() && request.law_url.is_some();
-    if is_manual_law_selection {
-        println!("⚡ MANUAL LAW SELECTION: User specified law, skipping auto-detection");
-    } else {
-        println!("🤖 AUTO LAW DETECTION: Will use keyword-based law selection process");
-    }
-    
-    // Extract IP address from Fly.io headers (proper way for proxy environments)
-    let client_ip = extract_client_ip(&headers);
+    Ok((enhanced_response, response_content))
+}
 
-    println!("🔍 DEBUG: Client IP: {}", client_ip);
+// Steps shared by the chat pipeline and the anonymous free question
 
-    // Extract user info for usage tracking and limit checking with Supabase token support
-    println!("🔍 DEBUG: Extracting user info...");
-    let user_id = database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await;
-    println!("🔍 DEBUG: User info - user_id: {:?}", user_id);
+const NON_LEGAL_REFUSAL: &str = "Izvinjavam se, ali mogu da odgovorim samo na pitanja koja se odnose na srpsko pravo i zakonodavstvo. Molim vas da postavite pravno pitanje.";
 
-    // Validate document upload permission for Professional/Team/Premium users only
-    if request.document_content.is_some() {
-        let user = database::get_user(user_id, &pool).await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+/// Whether the question is legal, with the classification's llm_requests id. Cache hits skip the
+/// call (only legal answers are cached); a failed classification counts as legal so no legal
+/// question goes unanswered.
+async fn classify_unless_cached(question: &str, cached: bool, api_key: &str, ctx: LlmCallContext<'_>) -> (bool, Option<i64>) {
+    if cached {
+        return (true, None);
+    }
 
-        if let Some(user) = user {
-            if !user.can_upload_documents() {
-                eprintln!("❌ SECURITY: User with account_type '{}' attempted document upload - BLOCKED", user.account_type);
-                return Err(StatusCode::FORBIDDEN);
-            }
-        } else {
-            eprintln!("❌ SECURITY: Unregistered user attempted document upload - BLOCKED");
-            return Err(StatusCode::FORBIDDEN);
+    debug!("🔍 Classifying question...");
+    match is_legal_question(question, api_key, ctx).await {
+        Ok((legal, llm_request_id)) => {
+            debug!("🔍 Question classification: is_legal = {}", legal);
+            (legal, llm_request_id)
+        }
+        Err(e) => {
+            warn!("⚠️ Classification failed: {}, assuming legal for safety", e);
+            (true, None)
         }
     }
+}
 
-    // Check if user can send message (trial users need remaining messages, premium unlimited)
-    println!("🔍 DEBUG: Checking if user can send message...");
-    match database::can_send_message(user_id, &pool).await {
-        Ok(can_send) => {
-            if !can_send {
-                println!("❌ DEBUG: User cannot send message - trial limit exceeded");
-                // Return HTTP 429 with structured error in response body
-                return Err(StatusCode::TOO_MANY_REQUESTS);
-            }
-            println!("✅ DEBUG: User can send message");
+/// Cached articles semantically close to the question, from the jurisdiction's laws only
+/// (another legal system's articles would only mislead the answer), and the prompt context for them
+async fn retrieve_articles(
+    question: &str,
+    jurisdiction: Jurisdiction,
+    openai_api_key: &str,
+    pool: &PgPool,
+) -> (Vec<crate::retrieval::RetrievedArticle>, Option<String>) {
+    let retrieved_articles: Vec<_> = crate::retrieval::retrieve_relevant_articles(question, openai_api_key, RETRIEVAL_TOP_K, pool)
+        .await
+        .into_iter()
+        .filter(|article| laws::jurisdiction_of_law(&article.law_name) == jurisdiction)
+        .collect();
+    debug!("🔍 Semantic retrieval returned {} article(s)", retrieved_articles.len());
+    let retrieved_context = crate::retrieval::format_retrieved_context(&retrieved_articles);
+    (retrieved_articles, retrieved_context)
+}
+
+/// Law the question is about, or None when detection fails, with the llm_requests id.
+/// Outside Serbia only laws in the jurisdiction's registry count - anything else would resolve to
+/// a Serbian law.
+async fn detect_answer_law(question: &str, api_key: &str, ctx: LlmCallContext<'_>) -> (Option<String>, Option<i64>) {
+    match detect_relevant_law_name(question, api_key, ctx).await {
+        Ok((law_name, llm_request_id)) => {
+            debug!("✅ Detected law: '{}'", law_name);
+            let law_name = if ctx.jurisdiction == Jurisdiction::Rs {
+                Some(law_name)
+            } else {
+                laws::canonical_law_name(&law_name, ctx.jurisdiction)
+            };
+            (law_name, llm_request_id)
         }
         Err(e) => {
-            println!("❌ DEBUG: Error checking message limits: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            warn!("⚠️ Law name detection failed: {}, proceeding without specific law", e);
+            (None, None)
         }
     }
+}
 
-    // Process question with new free response system
-    println!("🔍 DEBUG: Starting free response processing...");
-    let enhanced_response = process_question_with_llm_guidance(
-        &request,
-        user_id,
-        &pool,
-        &openrouter_api_key,
-    ).await.map_err(|e| {
-        println!("❌ DEBUG: Free response processing failed: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+/// Add the caveats the UI shows next to the answer and link article mentions to their quotes.
+/// Returns the message content to store: the answer followed by the quoted articles.
+async fn finish_answer(
+    response: &mut QuestionResponse,
+    is_legal: bool,
+    detected_law_name: Option<&str>,
+    actual_law_name: Option<&str>,
+    pool: &PgPool,
+) -> String {
+    let quotes_unavailable = response.warnings.iter().any(|w| w.code == "quotes_unavailable");
+    if is_legal && response.law_quotes.is_empty() && response.generated_contract.is_none() && !quotes_unavailable {
+        response.warnings.push(ResponseWarning::new(
+            "answer_not_supported",
+            "Odgovor nije potkrepljen članom zakona - proverite ga u važećem propisu.",
+        ));
+    }
+    if let Some(law_name) = detected_law_name {
+        response.warnings.extend(unverified_citations_warning(&response.citations, law_name));
+    }
+    if let Some(law_name) = actual_law_name {
+        response.warnings.extend(crate::law_revalidation::law_freshness_warnings(law_name, pool).await);
+    }
 
-    println!("✅ DEBUG: Free response processing successful");
+    let (linked_answer, footnotes) = link_answer_footnotes(&response.answer, &response.law_quotes);
+    response.answer = linked_answer;
+    response.footnotes = footnotes;
 
-    // Decrement trial messages after successful message processing (skip for premium users)
-    let user = database::get_user(user_id, &pool).await
-        .map_err(|e| {
-            eprintln!("Failed to get user for message decrement check: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+    if response.law_quotes.is_empty() {
+        return response.answer.clone();
+    }
+    let reference_header = match actual_law_name {
+        Some(law_name) => format!("Reference: {}", law_name),
+        None => "Reference:".to_string(),
+    };
+    format!("{}\n\n{}\n{}", response.answer, reference_header, response.law_quotes.join("\n\n"))
+}
 
-    if let Some(user) = user {
-        if user.account_type != "premium" {
-            if let Err(e) = database::decrement_trial_message(user_id, &pool).await {
-                // Log error but don't fail the request since AI response was successful
-                eprintln!("⚠️  CRITICAL: Failed to decrement trial messages for user_id={:?}: {}", user_id, e);
-            } else {
-                println!("✅ DEBUG: Successfully decremented trial message count for user_id={:?}", user_id);
-            }
-        } else {
-            println!("✅ DEBUG: Premium user - skipping trial message decrement");
+// Run the question pipeline under the overall question deadline. A timeout is a 504; a dropped
+// future (client disconnect) cancels every upstream call still in flight.
+async fn run_question_pipeline(
+    request: &QuestionRequest,
+    user_id: Option<Uuid>,
+    regenerate_from: Option<i64>,
+    pool: &PgPool,
+    api_key: &str,
+    openai_api_key: &str,
+) -> Result<QuestionResponse, StatusCode> {
+    let guard = crate::question_timeout::PipelineGuard::new(request.chat_id);
+    let timeout = crate::question_timeout::question_timeout();
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    let result = tokio::time::timeout_at(
+        deadline,
+        process_question_with_llm_guidance(request, user_id, regenerate_from, deadline, pool, api_key, openai_api_key),
+    )
+    .await;
+    guard.finish();
+
+    match result {
+        Ok(Ok(response)) => Ok(response),
+        Ok(Err(e)) => {
+            warn!("❌ Question processing failed: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        Err(_) => {
+            warn!("⏱️  Question pipeline for chat {} timed out after {}s", request.chat_id, timeout.as_secs());
+            crate::metrics::record_question_timeout("pipeline");
+            Err(StatusCode::GATEWAY_TIMEOUT)
         }
     }
-
-    println!("✅ DEBUG: Request processing completed successfully");
-    Ok(ResponseJson(enhanced_response))
 }
 
 // NEW: Process question with free response and article replacement (Phase 4)
+// `regenerate_from` is the saved user message being answered again: it isn't saved a second
+// time and only the history before it is used. `deadline` bounds the article fetch step.
 async fn process_question_with_llm_guidance(
     request: &QuestionRequest,
     user_id: Option<Uuid>,
+    regenerate_from: Option<i64>,
+    deadline: tokio::time::Instant,
     pool: &PgPool,
     api_key: &str,
+    openai_api_key: &str,
 ) -> Result<QuestionResponse, String> {
     // Load recent conversation history for context
-    let all_messages = get_messages(request.chat_id, pool).await?;
+    let mut all_messages = get_messages(request.chat_id, pool).await?;
+    if let Some(user_message_id) = regenerate_from {
+        all_messages.retain(|m| m.id < user_message_id);
+    }
     let recent_messages: Vec<_> = all_messages.iter().rev().take(10).rev().collect();
 
-    println!("🔍 DEBUG: NEW FREE RESPONSE PROCESSING for question: '{}'", request.question);
-    println!("🔍 DEBUG: Has document: {}, doc_length: {}",
+    debug!("🔍 NEW FREE RESPONSE PROCESSING for question: '{}'", request.question);
+    debug!("🔍 Has document: {}, doc_length: {}",
         request.document_content.is_some(),
         request.document_content.as_ref().map(|d| d.len()).unwrap_or(0)
     );
 
 
     // Step 1: Add user message to database first
-    add_message(
-        request.chat_id,
-        "user".to_string(),
-        request.question.clone(),
-        None, // No specific law in free response mode
-        Some(request.document_content.is_some()),
-        request.document_filename.clone(),
-        None, // contract_file_id (only for assistant messages)
-        None, // contract_type (only for assistant messages)
-        None, // contract_filename (only for assistant messages)
+    if regenerate_from.is_none() {
+        add_message(
+            request.chat_id,
+            "user".to_string(),
+            request.question.clone(),
+            None, // No specific law in free response mode
+            Some(request.document_content.is_some()),
+            request.document_filename.clone(),
+            None, // contract_file_id (only for assistant messages)
+            None, // contract_type (only for assistant messages)
+            None, // contract_filename (only for assistant messages)
+            pool,
+        ).await?;
+    }
+
+    // Audit log rows for this question, linked to the assistant message once it is saved
+    let account_type = database::get_user(user_id, pool).await
+        .ok()
+        .flatten()
+        .map(|user| user.account_type);
+    let jurisdiction = match request.jurisdiction {
+        Some(jurisdiction) => jurisdiction,
+        None => crate::jurisdictions::chat_jurisdiction(request.chat_id, pool).await,
+    };
+    let llm_ctx = LlmCallContext {
+        user_id,
+        chat_id: Some(request.chat_id),
+        account_type: account_type.as_deref(),
+        jurisdiction,
         pool,
-    ).await?;
+    };
+    let mut llm_request_ids: Vec<i64> = Vec::new();
+
+    // Step 1.5: Standalone questions (no history, no document) can be served from the answer cache.
+    // Only legal answers are cached, so a hit skips classification too. A regeneration wants a new
+    // answer, so it bypasses the cache and keeps the chat title. The cache isn't keyed by
+    // jurisdiction, so only Serbian questions use it.
+    let is_first_exchange = all_messages.is_empty() && regenerate_from.is_none();
+    let cache_scope = if is_first_exchange && request.document_content.is_none() && jurisdiction == Jurisdiction::Rs {
+        crate::answer_cache::CacheScope::resolve(account_type.as_deref(), user_id, jurisdiction, pool).await
+    } else {
+        None
+    };
+    let cached_answer = match &cache_scope {
+        Some(scope) => crate::answer_cache::lookup(&request.question, scope, pool).await,
+        None => None,
+    };
 
     // Step 2: Classify question first (NOT optional!)
-    println!("🔍 DEBUG: Classifying question...");
-    let is_legal = match is_legal_question(&request.question, api_key).await {
-        Ok(legal) => {
-            println!("🔍 DEBUG: Question classification: is_legal = {}", legal);
-            legal
-        }
-        Err(e) => {
-            println!("⚠️ DEBUG: Classification failed: {}, assuming legal for safety", e);
-            true // Default to legal to avoid missing questions
-        }
+    let (is_legal, llm_request_id) = classify_unless_cached(&request.question, cached_answer.is_some(), api_key, llm_ctx).await;
+    llm_request_ids.extend(llm_request_id);
+
+    // Step 2.5: Semantic retrieval of relevant cached articles (skipped for cache hits)
+    let (retrieved_articles, retrieved_context) = if is_legal && cached_answer.is_none() {
+        retrieve_articles(&request.question, jurisdiction, openai_api_key, pool).await
+    } else {
+        (Vec::new(), None)
     };
 
     // Step 3: Branch based on classification
-    let llm_response = if is_legal {
+    let llm_response = if let Some(cached) = &cached_answer {
+        debug!("⚡ Answer cache hit - skipping LLM call");
+        cached.answer.clone()
+    } else if is_legal {
         // Legal question: Get LLM free response
-        println!("✅ DEBUG: Legal question - proceeding with free response");
-        process_question_with_free_response(
+        debug!("✅ Legal question - proceeding with free response");
+        match process_question_with_free_response(
             &request.question,
             &recent_messages,
             request.document_content.as_deref(),
-            user_id,
-            pool,
+            retrieved_context.as_deref(),
+            llm_ctx,
             api_key,
-        ).await?
+        ).await {
+            Ok((response, llm_request_id)) => {
+                llm_request_ids.extend(llm_request_id);
+                response
+            }
+            Err(e) => {
+                // LLM unavailable: degrade to cached articles instead of failing the request
+                warn!("❌ LLM call failed: {}, falling back to cached articles", e);
+                let fallback_response = build_cached_articles_fallback(&request.question, pool).await?;
+
+                let response_content = if !fallback_response.law_quotes.is_empty() {
+                    format!("{}\n\nReference: {}\n{}",
+                           fallback_response.answer,
+                           fallback_response.law_name.as_deref().unwrap_or(""),
+                           fallback_response.law_quotes.join("\n\n"))
+                } else {
+                    fallback_response.answer.clone()
+                };
+
+                let message_id = add_message(
+                    request.chat_id,
+                    "assistant".to_string(),
+                    response_content,
+                    fallback_response.law_name.clone(),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    pool,
+                ).await?;
+
+                if let Err(e) = database::link_llm_requests_to_message(&llm_request_ids, message_id, pool).await {
+                    error!("{}", e);
+                }
+
+                return Ok(fallback_response);
+            }
+        }
     } else {
         // Non-legal question: Return polite refusal
-        println!("❌ DEBUG: Non-legal question - returning refusal");
-        "Izvinjavam se, ali mogu da odgovorim samo na pitanja koja se odnose na srpsko pravo i zakonodavstvo. Molim vas da postavite pravno pitanje.".to_string()
+        warn!("❌ Non-legal question - returning refusal");
+        NON_LEGAL_REFUSAL.to_string()
     };
 
     // Step 3: Detect relevant law name from the question
-    let detected_law_name = if is_legal {
-        println!("🔍 DEBUG: Step 2 - Detecting relevant law name");
-        match detect_relevant_law_name(&request.question, api_key).await {
-            Ok(law_name) => {
-                println!("✅ DEBUG: Detected law: '{}'", law_name);
-                Some(law_name)
-            }
-            Err(e) => {
-                println!("⚠️ DEBUG: Law name detection failed: {}, proceeding without specific law", e);
-                None
+    // On the first exchange, generate the chat title concurrently with law detection
+    let title_future = async {
+        if is_first_exchange {
+            Some(generate_chat_title(&request.question, &llm_response, api_key, llm_ctx).await)
+        } else {
+            None
+        }
+    };
+    let law_future = async {
+        if let Some(cached) = &cached_answer {
+            (cached.law_name.clone(), None)
+        } else if is_legal {
+            detect_answer_law(&request.question, api_key, llm_ctx).await
+        } else {
+            (None, None)
+        }
+    };
+    let (title_result, (detected_law_name, llm_request_id)) = tokio::join!(title_future, law_future);
+    llm_request_ids.extend(llm_request_id);
+
+    let chat_title = match title_result {
+        Some(Ok((title, llm_request_id))) => {
+            llm_request_ids.extend(llm_request_id);
+            match save_chat_title(request.chat_id, &title, user_id, pool).await {
+                Ok(()) => Some(title),
+                Err(e) => {
+                    warn!("⚠️ {}", e);
+                    None
+                }
             }
         }
-    } else {
-        None
+        Some(Err(e)) => {
+            warn!("⚠️ Chat title generation failed: {}", e);
+            None
+        }
+        None => None,
     };
 
+    // Fall back to the law of the closest retrieved article
+    let detected_law_name = detected_law_name.or_else(|| retrieved_articles.first().map(|a| a.law_name.clone()));
+
     // Step 4: Replace article references with cached content using detected law
-    println!("🔍 DEBUG: LLM Response before article replacement: '{}'", llm_response);
-    let (mut enhanced_response, actual_law_name) = replace_article_references_with_law(&llm_response, detected_law_name.as_deref(), pool).await?;
-    println!("🔍 DEBUG: After article replacement - Answer: '{}', Quotes: {:?}, Law: {:?}",
+    debug!("🔍 LLM Response before article replacement: '{}'", llm_response);
+    // A hung scrape must not cost the whole answer - past the fetch deadline it's returned without quotes
+    let article_deadline = crate::question_timeout::article_fetch_deadline(deadline);
+    let (mut enhanced_response, mut actual_law_name, mut citation_outcomes) = match tokio::time::timeout_at(
+        article_deadline,
+        replace_article_references_with_law(&llm_response, detected_law_name.as_deref(), pool),
+    ).await {
+        Ok(result) => result?,
+        Err(_) => {
+            debug!("⏱️  Article fetch timed out - returning the answer without quotes");
+            crate::metrics::record_question_timeout("articles");
+            (answer_without_quotes(&llm_response), None, Vec::new())
+        }
+    };
+    debug!("🔍 After article replacement - Answer: '{}', Quotes: {:?}, Law: {:?}",
              enhanced_response.answer, enhanced_response.law_quotes, actual_law_name);
 
+    // Step 4.1: Optionally let the model fix citations that don't exist in the law
+    let self_correct = crate::config::get().llm.citation_self_correct;
+    let mut llm_response = llm_response;
+    if let (true, Some(law_name)) = (self_correct, detected_law_name.as_deref()) {
+        if enhanced_response.citations.iter().any(|c| !c.verified) {
+            match self_correct_citations(&llm_response, &enhanced_response.citations, law_name, api_key, llm_ctx).await {
+                Ok((corrected, llm_request_id)) => {
+                    llm_request_ids.extend(llm_request_id);
+                    debug!("✅ Answer rewritten to fix unverified citations");
+                    match tokio::time::timeout_at(crate::question_timeout::article_fetch_deadline(deadline), replace_article_references_with_law(&corrected, Some(law_name), pool)).await {
+                        Ok(result) => (enhanced_response, actual_law_name, citation_outcomes) = result?,
+                        Err(_) => {
+                            crate::metrics::record_question_timeout("articles");
+                            (enhanced_response, actual_law_name, citation_outcomes) = (answer_without_quotes(&corrected), None, Vec::new());
+                        }
+                    }
+                    llm_response = corrected;
+                }
+                Err(e) => warn!("⚠️ Citation self-correction failed: {}", e),
+            }
+        }
+    }
+    // Only the answer the user gets counts towards citation coverage
+    if let Some(law_name) = detected_law_name.as_deref() {
+        crate::citation_audit::record_citations(law_name, &citation_outcomes, pool).await;
+    }
+
     // Step 4.5: Check for generated contract
-    println!("🔍 DEBUG: Checking for contract in LLM response...");
+    debug!("🔍 Checking for contract in LLM response...");
+    let mut contract_text: Option<String> = None;
     if let Some((contract_content, clean_response)) = crate::contracts::detect_contract(&llm_response) {
-        println!("✅ DEBUG: Contract detected! Content length: {} chars", contract_content.len());
+        debug!("✅ Contract detected! Content length: {} chars", contract_content.len());
 
-        // Get API base URL from environment or use default
-        let api_base_url = std::env::var("API_BASE_URL")
-            .unwrap_or_else(|_| "https://norma-ai.fly.dev".to_string());
+        let api_base_url = &crate::config::get().api_base_url;
+
+        // The document is written in the user's script; the chat keeps the Latin text
+        let script = match request.script {
+            Some(script) => script,
+            None => crate::transliteration::preferred_script(user_id, pool).await,
+        };
 
         // Generate contract file
-        match crate::contracts::generate_contract_file(&contract_content, &api_base_url) {
+        match crate::contracts::generate_contract_file(&contract_content, api_base_url, script) {
             Ok(contract) => {
-                println!("✅ DEBUG: Contract file generated: {}", contract.filename);
+                debug!("✅ Contract file generated: {}", contract.filename);
+                crate::metrics::record_contract_generation(true);
+                // Keep the structured content so the user can edit fields without a new conversation
+                let file_id = contract.download_url.rsplit('/').next().and_then(|id| Uuid::parse_str(id).ok());
+                if let (Some(user_id), Some(file_id)) = (user_id, file_id) {
+                    if let Err(e) = crate::contracts::save_contract_template(file_id, user_id, request.chat_id, &contract_content, script, pool).await {
+                        warn!("⚠️  {}", e);
+                    }
+                }
                 enhanced_response.generated_contract = Some(contract);
                 // Update answer to use clean version (without contract markers)
                 enhanced_response.answer = clean_response;
+                contract_text = Some(contract_content);
             }
             Err(e) => {
-                println!("❌ DEBUG: Contract generation failed: {}", e);
+                warn!("❌ Contract generation failed: {}", e);
+                crate::metrics::record_contract_generation(false);
                 // Don't fail the request, just log the error
             }
         }
     } else {
-        println!("🔍 DEBUG: No contract detected in response");
+        debug!("🔍 No contract detected in response");
     }
 
-    println!("✅ DEBUG: Free response processing complete. Answer: {} chars, Quotes: {}",
-             enhanced_response.answer.len(), enhanced_response.law_quotes.len());
+    enhanced_response.chat_title = chat_title;
 
-    // Step 4: Add AI response to database
-    let response_content = if !enhanced_response.law_quotes.is_empty() {
-        let reference_header = if let Some(ref law_name) = actual_law_name {
-            format!("Reference: {}", law_name)
-        } else {
-            "Reference:".to_string()
-        };
+    // Contracts are personalized (profile pre-fill), so only plain answers go into the answer cache
+    if let (Some(scope), None, true, None) = (&cache_scope, &cached_answer, is_legal, &contract_text) {
+        crate::answer_cache::store(&request.question, scope, &llm_response, detected_law_name.as_deref(), pool).await;
+    }
 
-        format!("{}\n\n{}\n{}",
-               enhanced_response.answer,
-               reference_header,
-               enhanced_response.law_quotes.join("\n\n"))
-    } else {
-        enhanced_response.answer.clone()
-    };
+    // Step 4.7: Collect caveats the UI should show next to the answer
+    if let Some(user_id) = user_id {
+        // Conflict check: parties in the drafted contract or uploaded document vs. the team's adverse parties
+        let party_sources = [
+            (contract_text.as_deref(), "contract"),
+            (request.document_content.as_deref(), "document"),
+        ];
+        for (text, source) in party_sources {
+            let Some(text) = text else { continue };
+            match crate::conflicts::check_chat_parties(user_id, request.chat_id, text, source, pool).await {
+                Ok(conflict_warnings) => enhanced_response.warnings.extend(conflict_warnings),
+                Err(e) => warn!("⚠️  Conflict check failed: {}", e),
+            }
+        }
+    }
+    // Step 4.6: Other caveats, article mentions linked to their quotes, and the content to store
+    let response_content = finish_answer(
+        &mut enhanced_response,
+        is_legal,
+        detected_law_name.as_deref(),
+        actual_law_name.as_deref(),
+        pool,
+    ).await;
+
+    debug!("✅ Free response processing complete. Answer: {} chars, Quotes: {}",
+             enhanced_response.answer.len(), enhanced_response.law_quotes.len());
 
     // Step 5: Save assistant response to database with contract metadata if present
     let (contract_file_id, contract_type, contract_filename) = if let Some(ref contract) = enhanced_response.generated_contract {
         // Extract file_id from download_url (format: /api/contracts/{file_id})
-        let file_id = contract.download_url.split('/').last().unwrap_or("").to_string();
+        let file_id = contract.download_url.split('/').next_back().unwrap_or("").to_string();
         (Some(file_id), Some(contract.contract_type.clone()), Some(contract.filename.clone()))
     } else {
         (None, None, None)
     };
 
-    add_message(
+    let message_id = add_message(
         request.chat_id,
         "assistant".to_string(),
         response_content,
@@ -733,6 +2555,21 @@ async fn process_question_with_llm_guidance(
         pool,
     ).await?;
 
+    if let (Some(user_id), Some(contract)) = (user_id, enhanced_response.generated_contract.as_ref()) {
+        crate::events::emit(user_id, crate::events::ChatEvent::ContractReady {
+            chat_id: request.chat_id,
+            message_id,
+            contract_type: contract.contract_type.clone(),
+            filename: contract.filename.clone(),
+            download_url: contract.download_url.clone(),
+        });
+    }
+
+    // Link the audit log rows to the saved assistant message (don't fail the request)
+    if let Err(e) = database::link_llm_requests_to_message(&llm_request_ids, message_id, pool).await {
+        error!("{}", e);
+    }
+
     Ok(enhanced_response)
 }
 
@@ -740,7 +2577,7 @@ async fn process_question_with_llm_guidance(
 
 
 
-async fn get_law_content(
+pub async fn get_law_content(
     law_name: &str,
     law_url: &str,
     pool: &PgPool,
@@ -769,17 +2606,21 @@ async fn get_law_content(
 }
 
 async fn get_messages(chat_id: i64, pool: &PgPool) -> Result<Vec<Message>, String> {
-    let messages = sqlx::query_as::<_, Message>(
-        "SELECT id, chat_id, role, content, law_name, has_document, document_filename, contract_file_id, contract_type, contract_filename, created_at FROM messages WHERE chat_id = $1 ORDER BY created_at ASC"
+    let messages = crate::metrics::time_db_query(
+        "chat_history",
+        sqlx::query_as::<_, Message>(
+            "SELECT id, chat_id, role, content, law_name, has_document, document_filename, contract_file_id, contract_type, contract_filename, created_at FROM messages WHERE chat_id = $1 AND superseded_at IS NULL ORDER BY created_at ASC"
+        )
+        .bind(chat_id)
+        .fetch_all(pool),
     )
-    .bind(chat_id)
-    .fetch_all(pool)
     .await
     .map_err(|e| format!("Failed to fetch messages: {}", e))?;
 
     Ok(messages)
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn add_message(
     chat_id: i64,
     role: String,
@@ -791,38 +2632,53 @@ async fn add_message(
     contract_type: Option<String>,
     contract_filename: Option<String>,
     pool: &PgPool,
-) -> Result<(), String> {
+) -> Result<i64, String> {
+    let (content, pii_mapping) = crate::pii::prepare_for_storage(chat_id, content, pool).await?;
+
     // Insert the message
-    sqlx::query("INSERT INTO messages (chat_id, role, content, law_name, has_document, document_filename, contract_file_id, contract_type, contract_filename) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)")
-        .bind(chat_id)
-        .bind(role)
-        .bind(content)
-        .bind(law_name)
-        .bind(has_document.unwrap_or(false))
-        .bind(document_filename)
-        .bind(contract_file_id)
-        .bind(contract_type)
-        .bind(contract_filename)
-        .execute(pool)
-        .await
+    let message_id: i64 = crate::metrics::time_db_query(
+        "insert_message",
+        sqlx::query_scalar("INSERT INTO messages (chat_id, role, content, law_name, has_document, document_filename, contract_file_id, contract_type, contract_filename, pii_mapping_encrypted) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING id")
+            .bind(chat_id)
+            .bind(&role)
+            .bind(content)
+            .bind(law_name)
+            .bind(has_document.unwrap_or(false))
+            .bind(document_filename)
+            .bind(contract_file_id)
+            .bind(contract_type)
+            .bind(contract_filename)
+            .bind(pii_mapping)
+            .fetch_one(pool),
+    )
+    .await
         .map_err(|e| format!("Failed to add message: {}", e))?;
 
     // Update the chat's updated_at timestamp
-    sqlx::query("UPDATE chats SET updated_at = NOW() WHERE id = $1")
+    let owner_id: Option<Uuid> = sqlx::query_scalar("UPDATE chats SET updated_at = NOW() WHERE id = $1 RETURNING user_id")
         .bind(chat_id)
-        .execute(pool)
+        .fetch_optional(pool)
         .await
-        .map_err(|e| format!("Failed to update chat timestamp: {}", e))?;
+        .map_err(|e| format!("Failed to update chat timestamp: {}", e))?
+        .flatten();
 
-    Ok(())
+    // Other open windows/devices of the owner pick the message up without a refresh
+    if let Some(owner_id) = owner_id {
+        crate::events::emit(owner_id, crate::events::ChatEvent::MessageAdded { chat_id, message_id, role });
+    }
+
+    Ok(message_id)
 }
 
 async fn get_cached_law(law_name: String, pool: &PgPool) -> Result<Option<LawCache>, String> {
-    let cached_law = sqlx::query_as::<_, LawCache>(
-        "SELECT id, law_name, law_url, content, cached_at, expires_at FROM law_cache WHERE law_name = $1 AND expires_at > NOW() LIMIT 1"
+    let cached_law = crate::metrics::time_db_query(
+        "cached_law",
+        sqlx::query_as::<_, LawCache>(
+            "SELECT id, law_name, law_url, content, cached_at, expires_at FROM law_cache WHERE law_name = $1 AND expires_at > NOW() LIMIT 1"
+        )
+        .bind(law_name)
+        .fetch_optional(pool),
     )
-    .bind(law_name)
-    .fetch_optional(pool)
     .await
     .map_err(|e| format!("Failed to check cached law: {}", e))?;
     
@@ -830,48 +2686,14 @@ async fn get_cached_law(law_name: String, pool: &PgPool) -> Result<Option<LawCac
 }
 
 fn create_conversation_messages(
+    system_prompt: &str,
     current_question: &str,
     document_content: Option<&str>,
     recent_messages: &[&Message]
 ) -> Vec<OpenRouterMessage> {
     let mut messages = Vec::new();
 
-    // System message with legal instructions (FREE RESPONSE - simplified)
-    let system_prompt = r#"Ti si pravni asistent za srpsko zakonodavstvo sa mogućnošću generisanja ugovora.
-
-PRAVNA PITANJA - Odgovori KRATKO i DIREKTNO:
-1. Koristi znanje iz srpskog zakonodavstva
-2. Navedi konkretne kazne, iznose i rokove
-
-FORMAT:
-1. KRATAK odgovor
-2. Nova linija: "Reference:"
-3. U "Reference:" citiraj: Član X, Član Y, Član Z...
-
-GENERISANJE UGOVORA:
-Kada korisnik traži ugovor (npr. "Napravi ugovor o radu", "Treba mi ugovor o zakupu"):
-
-1. PRIKUPI SVE podatke (za ugovor o radu: poslodavac, zaposleni, pozicija, zarada, datum, trajanje)
-2. Kada imaš dovoljno informacija, generiši ugovor sa [CONTRACT_START] i [CONTRACT_END]:
-
-[CONTRACT_START]
-UGOVOR O RADU
-
-Zaključen između:
-1. [Poslodavac]
-2. [Zaposleni]
-
-Član 1. - PREDMET UGOVORA
-[Detalji...]
-
-[Ostali potrebni članovi...]
-
-U _______, dana _______
-Potpisi
-[CONTRACT_END]
-
-Nakon [CONTRACT_END] dodaj kratak komentar i preporuku za pravni pregled."#;
-    
+    // System message with legal and contract instructions (see prompt_profiles)
     messages.push(OpenRouterMessage {
         role: "system".to_string(),
         content: system_prompt.to_string(),
@@ -905,10 +2727,10 @@ Nakon [CONTRACT_END] dodaj kratak komentar i preporuku za pravni pregled."#;
     // Add current question (combine with document content for LLM only)
     let user_content = if let Some(doc_content) = document_content {
         let combined = format!("{}\n\n[Uploaded Document]\n{}", current_question, doc_content);
-        println!("🔍 Backend: Sending combined content to LLM: question='{}', doc_chars={}", current_question, doc_content.len());
+        debug!("🔍 Backend: Sending combined content to LLM: question='{}', doc_chars={}", current_question, doc_content.len());
         combined
     } else {
-        println!("🔍 Backend: Sending question only to LLM: '{}'", current_question);
+        debug!("🔍 Backend: Sending question only to LLM: '{}'", current_question);
         current_question.to_string()
     };
     
@@ -920,64 +2742,190 @@ Nakon [CONTRACT_END] dodaj kratak komentar i preporuku za pravni pregled."#;
     messages
 }
 
-async fn call_openrouter_api(
+// Single prompt for analysis pipelines outside the question flow (e.g. contract_review).
+// Uses the answer model with retries and fallback, and records the call in the audit log.
+pub(crate) async fn complete_prompt(
     api_key: &str,
-    messages: Vec<OpenRouterMessage>,
-    user_id: Option<Uuid>,
+    prompt: String,
+    audit_purpose: &str,
+    user_id: Uuid,
+    chat_id: Option<i64>,
+    account_type: Option<&str>,
     pool: &PgPool,
 ) -> Result<String, String> {
-    // Calculate input text length for cost estimation
-    let input_text: String = messages.iter()
-        .map(|m| m.content.clone())
-        .collect::<Vec<_>>()
-        .join(" ");
-    let input_chars = input_text.len();
+    let ctx = LlmCallContext { user_id: Some(user_id), chat_id, account_type, jurisdiction: Jurisdiction::default(), pool };
+    let primary_model = llm_config::resolve_model(LlmPurpose::Answer, account_type, pool).await;
+    let fallback_model = llm_config::resolve_fallback_model(LlmPurpose::Answer, &primary_model);
+
+    let input_chars = prompt.len();
+    let request = OpenRouterRequest {
+        model: primary_model.clone(),
+        messages: vec![OpenRouterMessage { role: "user".to_string(), content: prompt }],
+        temperature: 0.1, // Structured extraction - keep it close to deterministic
+    };
+    let body = serde_json::to_value(&request).map_err(|e| format!("Failed to serialize request: {}", e))?;
+    let started_at = std::time::Instant::now();
 
     let client = reqwest::Client::new();
+    let result = openrouter_resilience::post_chat_completion::<OpenRouterResponse>(
+        &client,
+        api_key,
+        &body,
+        &primary_model,
+        fallback_model.as_deref(),
+    )
+    .await;
 
-    let request = OpenRouterRequest {
-        model: "google/gemini-2.5-pro".to_string(),
-        messages,
-        temperature: 0.3,
+    let (response, model) = match result {
+        Ok(answered) => answered,
+        Err(e) => {
+            audit_llm_call(ctx, &primary_model, audit_purpose, llm_token_counts(None, input_chars, 0), started_at, Some(&e)).await;
+            return Err(e);
+        }
     };
 
-    let response = client
-        .post("https://openrouter.ai/api/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&request)
-        .send()
-        .await
-        .map_err(|e| format!("API request failed: {}", e))?;
+    // The call was billed even if it came back without a choice - count it towards the cost cap
+    let content = response.choices.first().map(|c| c.message.content.clone());
+    let token_counts = llm_token_counts(response.usage.as_ref(), input_chars, content.as_deref().map_or(0, str::len));
+    let llm_cost = database::llm_cost_from_tokens(&model, token_counts.0, token_counts.1);
+    if let Err(e) = database::track_llm_cost(Some(user_id), llm_cost, pool).await {
+        error!("Failed to track LLM cost: {}", e);
+    }
 
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("API error: {}", error_text));
+    match content {
+        Some(content) => {
+            audit_llm_call(ctx, &model, audit_purpose, token_counts, started_at, None).await;
+            Ok(content)
+        }
+        None => {
+            let e = "No response received".to_string();
+            audit_llm_call(ctx, &model, audit_purpose, token_counts, started_at, Some(&e)).await;
+            Err(e)
+        }
     }
+}
 
-    let openrouter_response: OpenRouterResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse API response: {}", e))?;
+async fn call_openrouter_api(
+    api_key: &str,
+    messages: Vec<OpenRouterMessage>,
+    ctx: LlmCallContext<'_>,
+) -> Result<(String, Option<i64>), String> {
+    // Sent as raw JSON so assistant tool calls and tool results can be appended between rounds
+    let mut messages: Vec<serde_json::Value> = messages
+        .iter()
+        .map(|m| serde_json::json!({ "role": m.role, "content": m.content }))
+        .collect();
 
-    let response_content = openrouter_response
-        .choices
-        .first()
-        .ok_or("No response from AI")?
-        .message
-        .content
-        .clone();
+    let client = reqwest::Client::new();
+    let primary_model = llm_config::resolve_model(LlmPurpose::Answer, ctx.account_type, ctx.pool).await;
+    let fallback_model = llm_config::resolve_fallback_model(LlmPurpose::Answer, &primary_model);
+    let mut model = primary_model;
+    let tools = if llm_tools::enabled() { llm_tools::definitions() } else { Vec::new() };
+
+    let started_at = std::time::Instant::now();
+    // Totals over all rounds; usage is only trusted if OpenRouter reported it for every round
+    let mut input_chars = 0;
+    let mut output_chars = 0;
+    let mut total_usage = Some(OpenRouterUsage { prompt_tokens: 0, completion_tokens: 0 });
+
+    // Errors break out of the loop rather than returning, so the rounds that did complete (and
+    // were billed by OpenRouter) are still recorded below
+    let mut round = 0;
+    let outcome: Result<String, String> = loop {
+        // Past the round limit the model must answer with the tool results it already has
+        let tool_choice = (!tools.is_empty() && round >= llm_tools::MAX_TOOL_ROUNDS).then_some("none");
+        let request = OpenRouterToolRequest {
+            model: &model,
+            messages: &messages,
+            temperature: 0.3,
+            tools: &tools,
+            tool_choice,
+        };
+        let round_input_chars = messages.iter().map(|m| m.to_string().len()).sum::<usize>();
+
+        let body = match serde_json::to_value(&request) {
+            Ok(body) => body,
+            Err(e) => break Err(format!("Failed to serialize API request: {}", e)),
+        };
+        let result: Result<(OpenRouterToolResponse, String), String> = openrouter_resilience::post_chat_completion(
+            &client,
+            api_key,
+            &body,
+            &model,
+            fallback_model.as_deref().filter(|fallback| *fallback != model),
+        )
+        .await;
+
+        let openrouter_response = match result {
+            Ok((response, used_model)) => {
+                // The fallback model is kept for the remaining tool rounds and billed as such
+                model = used_model;
+                response
+            }
+            Err(e) => break Err(e),
+        };
+
+        input_chars += round_input_chars;
+        total_usage = match (total_usage, openrouter_response.usage) {
+            (Some(total), Some(usage)) => Some(OpenRouterUsage {
+                prompt_tokens: total.prompt_tokens + usage.prompt_tokens,
+                completion_tokens: total.completion_tokens + usage.completion_tokens,
+            }),
+            _ => None,
+        };
+
+        let Some(choice) = openrouter_response.choices.into_iter().next() else {
+            break Err("No response from AI".to_string());
+        };
+        let message = choice.message;
+        let content = message.content.unwrap_or_default();
+        output_chars += content.len();
 
-    // Track LLM cost
-    let output_chars = response_content.len();
-    let estimated_cost = database::estimate_llm_cost(input_chars, output_chars);
+        let tool_calls = message.tool_calls.unwrap_or_default();
+        if tool_calls.is_empty() || tool_choice.is_some() {
+            break if content.trim().is_empty() { Err("Empty response from AI".to_string()) } else { Ok(content) };
+        }
+
+        info!("🛠️  Model requested {} tool call(s) in round {}", tool_calls.len(), round + 1);
+        let results = run_tool_calls(&tool_calls, ctx.pool).await;
+        messages.push(serde_json::json!({ "role": "assistant", "content": content, "tool_calls": tool_calls }));
+        messages.extend(results);
+        round += 1;
+    };
+
+    // Track LLM cost - use actual token usage from OpenRouter when available
+    let token_counts = llm_token_counts(total_usage.as_ref(), input_chars, output_chars);
+    let llm_cost = database::llm_cost_from_tokens(&model, token_counts.0, token_counts.1);
 
     // Log cost tracking (don't fail the request if logging fails)
-    if let Err(e) = database::track_llm_cost(user_id, estimated_cost, pool).await {
-        eprintln!("Failed to track LLM cost: {}", e);
+    if llm_cost > 0.0 {
+        if let Err(e) = database::track_llm_cost(ctx.user_id, llm_cost, ctx.pool).await {
+            error!("Failed to track LLM cost: {}", e);
+        }
+    }
+
+    match outcome {
+        Ok(response_content) => {
+            let llm_request_id = audit_llm_call(ctx, &model, "answer", token_counts, started_at, None).await;
+            Ok((response_content, llm_request_id))
+        }
+        Err(e) => {
+            audit_llm_call(ctx, &model, "answer", token_counts, started_at, Some(&e)).await;
+            Err(e)
+        }
     }
+}
 
-    Ok(response_content)
+// Run the tool calls of one assistant message, returning the "tool" messages with their results
+async fn run_tool_calls(tool_calls: &[serde_json::Value], pool: &PgPool) -> Vec<serde_json::Value> {
+    let mut results = Vec::with_capacity(tool_calls.len());
+    for call in tool_calls {
+        let name = call["function"]["name"].as_str().unwrap_or_default();
+        let arguments = call["function"]["arguments"].as_str().unwrap_or("{}");
+        let output = llm_tools::dispatch(name, arguments, pool).await;
+        results.push(serde_json::json!({ "role": "tool", "tool_call_id": call["id"], "content": output }));
+    }
+    results
 }
 
 fn parse_ai_response(response: &str) -> Result<QuestionResponse, String> {
@@ -992,7 +2940,7 @@ fn parse_ai_response(response: &str) -> Result<QuestionResponse, String> {
         let quotes_section = parts[1].trim();
         
         // DEBUG: Log the raw quotes section to see what LLM actually sent
-        println!("🔍 DEBUG: Raw quotes section from LLM: '{}'", quotes_section);
+        debug!("🔍 Raw quotes section from LLM: '{}'", quotes_section);
         
         // Parse quotes from the dedicated section - preserve complete articles
         let quotes = extract_complete_articles_from_section(quotes_section);
@@ -1028,6 +2976,11 @@ fn parse_ai_response(response: &str) -> Result<QuestionResponse, String> {
         law_quotes,
         law_name: None, // parse_ai_response doesn't have access to law_name (it's for parsing stored responses)
         generated_contract: None,
+        is_fallback: false,
+        footnotes: vec![],
+        chat_title: None,
+        warnings: vec![],
+        citations: vec![],
     })
 }
 
@@ -1050,7 +3003,7 @@ fn extract_quotes_from_text(text: &str) -> Vec<String> {
         
         // Add content to the appropriate article group
         article_groups.entry(base_article.clone())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(if content.is_empty() {
                 full_header.to_string()
             } else {
@@ -1061,19 +3014,19 @@ fn extract_quotes_from_text(text: &str) -> Vec<String> {
     // If no structured articles found, try bullet points
     if article_groups.is_empty() {
         let bullet_pattern = Regex::new(r"(?m)^\s*\*\s*\*\*([^*]+)\*\*[:\s]*(.*)$").unwrap();
+        let article_num_pattern = Regex::new(r"Član\s+(\d+)").unwrap();
         for cap in bullet_pattern.captures_iter(text) {
             let header = cap.get(1).unwrap().as_str().trim();
             let content = cap.get(2).unwrap().as_str().trim();
             
             if header.contains("Član") || header.contains("Stav") {
                 // Extract article number for grouping
-                let article_num_pattern = Regex::new(r"Član\s+(\d+)").unwrap();
                 if let Some(num_cap) = article_num_pattern.captures(header) {
                     let article_number = num_cap.get(1).unwrap().as_str();
                     let base_article = format!("Član {}", article_number);
                     
                     article_groups.entry(base_article)
-                        .or_insert_with(Vec::new)
+                        .or_default()
                         .push(if content.is_empty() {
                             format!("**{}**", header)
                         } else {
@@ -1082,7 +3035,7 @@ fn extract_quotes_from_text(text: &str) -> Vec<String> {
                 } else {
                     // Fallback for non-standard format
                     article_groups.entry(header.to_string())
-                        .or_insert_with(Vec::new)
+                        .or_default()
                         .push(if content.is_empty() {
                             format!("**{}**", header)
                         } else {
@@ -1109,7 +3062,7 @@ fn extract_quotes_from_text(text: &str) -> Vec<String> {
                 }
                 current_quote = line.to_string();
             } else if !current_quote.is_empty() && !line.is_empty() {
-                current_quote.push_str(" ");
+                current_quote.push(' ');
                 current_quote.push_str(line);
             }
         }
@@ -1132,81 +3085,67 @@ fn extract_quotes_from_text(text: &str) -> Vec<String> {
 }
 
 // Speech-to-text transcription endpoint
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct TranscribeResponse {
     text: String,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/transcribe",
+    tag = "questions",
+    request_body(content = Vec<u8>, description = "Raw audio (webm, ogg, mp3, m4a or wav)", content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "Transcribed text", body = TranscribeResponse),
+        (status = 400, description = "Unsupported or empty audio", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn transcribe_audio_handler(
     State((pool, _openrouter_api_key, openai_api_key, jwt_secret, supabase_jwt_secret)): State<AppState>,
     headers: HeaderMap,
     body: axum::body::Bytes,
-) -> Result<ResponseJson<TranscribeResponse>, StatusCode> {
-    println!("🎙️ ================== TRANSCRIPTION REQUEST ==================");
+) -> Result<ResponseJson<TranscribeResponse>, ApiError> {
+    info!("🎙️ New transcription request");
 
     // Extract user info for authorization with Supabase token support
     let user_id = database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await;
-    println!("🔍 DEBUG: Transcription request - user_id: {:?}", user_id);
+    debug!("🔍 Transcription request - user_id: {:?}", user_id);
 
     // Check if user can send message (same limits as regular messages)
     match database::can_send_message(user_id, &pool).await {
-        Ok(can_send) => {
-            if !can_send {
-                println!("❌ DEBUG: User cannot send message - trial limit exceeded");
-                return Err(StatusCode::TOO_MANY_REQUESTS);
+        Ok(allowance) => {
+            if let Err(e) = allowance.into_result("transcribe") {
+                warn!("❌ User cannot send message - message limit or cost cap reached");
+                return Err(e);
             }
-            println!("✅ DEBUG: User can use transcription");
+            debug!("✅ User can use transcription");
         }
         Err(e) => {
-            println!("❌ DEBUG: Error checking transcription limits: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            warn!("❌ Error checking transcription limits: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
         }
     }
     
-    // Create multipart form data for OpenAI API
-    let client = reqwest::Client::new();
-    
-    // Create form with audio file
-    let form = reqwest::multipart::Form::new()
-        .part("file", reqwest::multipart::Part::bytes(body.to_vec())
-            .file_name("recording.wav")
-            .mime_str("audio/wav").unwrap())
-        .text("model", "whisper-1")
-        .text("language", "sr"); // Serbian language
-    
-    println!("🔍 DEBUG: Sending audio to Whisper API...");
-    
-    let response = client
-        .post("https://api.openai.com/v1/audio/transcriptions")
-        .header("Authorization", format!("Bearer {}", openai_api_key))
-        .multipart(form)
-        .send()
-        .await
-        .map_err(|e| {
-            println!("❌ DEBUG: Whisper API request failed: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+    let content_type = headers.get(axum::http::header::CONTENT_TYPE).and_then(|v| v.to_str().ok());
+    let format = transcription::detect_audio_format(&body, content_type).ok_or_else(|| {
+        warn!("❌ Unsupported audio format (Content-Type: {:?}, {} bytes)", content_type, body.len());
+        StatusCode::UNSUPPORTED_MEDIA_TYPE
+    })?;
 
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        println!("❌ DEBUG: Whisper API error: {}", error_text);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
-    }
+    let transcriber = transcription::from_config(&openai_api_key);
+    debug!("🔍 Sending {} audio to {} transcription...", format.extension, transcriber.name());
 
-    let whisper_response: serde_json::Value = response
-        .json()
+    let transcribed_text = transcriber
+        .transcribe(body.to_vec(), format, transcription::LANGUAGE)
         .await
         .map_err(|e| {
-            println!("❌ DEBUG: Failed to parse Whisper response: {}", e);
+            warn!("❌ {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-    let transcribed_text = whisper_response["text"]
-        .as_str()
-        .unwrap_or("")
-        .to_string();
-
-    println!("✅ DEBUG: Transcription successful: '{}'", transcribed_text);
+    debug!("✅ Transcription successful: '{}'", transcribed_text);
 
     Ok(ResponseJson(TranscribeResponse {
         text: transcribed_text,
@@ -1217,7 +3156,7 @@ fn extract_complete_articles_from_section(text: &str) -> Vec<String> {
     // Split by **Član pattern to get complete article blocks
     let parts: Vec<&str> = text.split("**Član").collect();
     
-    println!("🔍 DEBUG: Split into {} parts", parts.len());
+    debug!("🔍 Split into {} parts", parts.len());
     
     let mut articles = Vec::new();
     
@@ -1227,12 +3166,12 @@ fn extract_complete_articles_from_section(text: &str) -> Vec<String> {
             continue;
         }
         
-        println!("🔍 DEBUG: Part {}: '{}'", i, part);
+        debug!("🔍 Part {}: '{}'", i, part);
         
         // Reconstruct the complete article with **Član prefix
         let complete_article = format!("**Član{}", part).trim().to_string();
         
-        println!("🔍 DEBUG: Reconstructed: '{}'", complete_article);
+        debug!("🔍 Reconstructed: '{}'", complete_article);
         
         if !complete_article.is_empty() {
             articles.push(complete_article);
@@ -1240,4 +3179,18 @@ fn extract_complete_articles_from_section(text: &str) -> Vec<String> {
     }
     
     articles
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_article_exceeds_law() {
+        assert!(article_exceeds_law("412", "300"));
+        assert!(article_exceeds_law("301a", "300"));
+        assert!(!article_exceeds_law("300a", "300"));
+        assert!(!article_exceeds_law("12", "300"));
+        assert!(!article_exceeds_law("12", "završne odredbe"));
+    }
+}
@@ -0,0 +1,156 @@
+// Audit trail of security-sensitive account actions (password changes, session revocations, plan
+// and subscription changes, account deletion/restoration). Each event keeps the IP and device it
+// came from so users can spot activity they don't recognize. Recording never fails the action
+// itself - a lost audit row is logged, not returned to the client.
+use crate::models::ApiError;
+use crate::simple_auth::AuthAppState;
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    PasswordChanged,
+    SessionRevoked,
+    AllSessionsRevoked,
+    SubscriptionCreated,
+    SubscriptionCancelled,
+    PlanChanged,
+    BillingPeriodChanged,
+    SubscriptionSynced, // Subscription state written from a RevenueCat webhook
+    AccountDeletionRequested,
+    AccountRestored,
+    AccountLinked, // Another account was merged into this one
+    ImpersonationStarted, // Support was issued a read-only token for this account
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::PasswordChanged => "password_changed",
+            AuditAction::SessionRevoked => "session_revoked",
+            AuditAction::AllSessionsRevoked => "all_sessions_revoked",
+            AuditAction::SubscriptionCreated => "subscription_created",
+            AuditAction::SubscriptionCancelled => "subscription_cancelled",
+            AuditAction::PlanChanged => "plan_changed",
+            AuditAction::BillingPeriodChanged => "billing_period_changed",
+            AuditAction::SubscriptionSynced => "subscription_synced",
+            AuditAction::AccountDeletionRequested => "account_deletion_requested",
+            AuditAction::AccountRestored => "account_restored",
+            AuditAction::AccountLinked => "account_linked",
+            AuditAction::ImpersonationStarted => "impersonation_started",
+        }
+    }
+}
+
+/// Who triggered the action: the user's own request, or a billing webhook
+pub enum AuditSource<'a> {
+    Request(&'a HeaderMap),
+    Webhook,
+}
+
+/// Record an account action. Errors are logged and swallowed.
+pub async fn record(pool: &PgPool, user_id: Uuid, action: AuditAction, source: AuditSource<'_>, details: serde_json::Value) {
+    let (actor, ip_address, device_session_id, user_agent) = match source {
+        AuditSource::Request(headers) => {
+            let header = |name: &str| headers.get(name).and_then(|h| h.to_str().ok()).map(|s| s.to_string());
+            (
+                "user",
+                Some(crate::api::extract_client_ip(headers)),
+                header("X-Device-Session-Id"),
+                header("User-Agent"),
+            )
+        }
+        AuditSource::Webhook => ("webhook", None, None, None),
+    };
+
+    let result = sqlx::query(
+        "INSERT INTO audit_events (user_id, action, actor, ip_address, device_session_id, user_agent, details)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)",
+    )
+    .bind(user_id)
+    .bind(action.as_str())
+    .bind(actor)
+    .bind(ip_address)
+    .bind(device_session_id)
+    .bind(user_agent)
+    .bind(details)
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("⚠️  Failed to record audit event {} for user {}: {}", action.as_str(), user_id, e);
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AuditEvent {
+    pub id: i64,
+    pub action: String,
+    pub actor: String, // user, webhook
+    pub ip_address: Option<String>,
+    pub device_session_id: Option<String>,
+    pub user_agent: Option<String>,
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditLogQuery {
+    pub limit: Option<i64>,
+    pub before: Option<i64>,   // Return events older than this id (pagination)
+    pub user_id: Option<Uuid>, // Admins only: whose log to read
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditLogResponse {
+    pub events: Vec<AuditEvent>,
+    pub next_before: Option<i64>, // Pass as `before` to get the next page; None on the last page
+}
+
+/// GET /api/auth/audit-log - the caller's account activity, newest first.
+/// With X-Admin-Key, `user_id` selects the account to review.
+pub async fn audit_log_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _)): State<AuthAppState>,
+    headers: HeaderMap,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<Json<AuditLogResponse>, ApiError> {
+    let user_id = match query.user_id {
+        Some(user_id) if crate::admin::is_admin_request(&headers) => user_id,
+        Some(_) => return Err(ApiError::new(StatusCode::FORBIDDEN, "FORBIDDEN", "Nemate pristup")),
+        None => crate::database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+            .await
+            .ok_or_else(|| ApiError::new(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "Niste autorizovani"))?,
+    };
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let events = sqlx::query_as::<_, AuditEvent>(
+        "SELECT id, action, actor, ip_address, device_session_id, user_agent, details, created_at
+         FROM audit_events
+         WHERE user_id = $1 AND ($2::BIGINT IS NULL OR id < $2)
+         ORDER BY id DESC
+         LIMIT $3",
+    )
+    .bind(user_id)
+    .bind(query.before)
+    .bind(limit)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to load audit log: {}", e);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Greška baze podataka")
+    })?;
+
+    let next_before = if events.len() as i64 == limit { events.last().map(|event| event.id) } else { None };
+
+    Ok(Json(AuditLogResponse { events, next_before }))
+}
@@ -0,0 +1,222 @@
+// Serbian legal calendar: local date in Belgrade and public holidays (Zakon o državnim i drugim
+// praznicima u Republici Srbiji). Injected into the answer prompt so deadlines are computed from
+// today's date rather than the model's training date, and exposed for the deadline calculator.
+use axum::{extract::Query, http::StatusCode, response::Json as ResponseJson};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+pub const TIMEZONE: &str = "Europe/Belgrade";
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Holiday {
+    pub date: NaiveDate,
+    pub name: &'static str,
+    pub moved_from: Option<NaiveDate>, // Set for the working day off when a holiday falls on Sunday
+}
+
+/// Last Sunday of a month (DST switches happen on these)
+fn last_sunday(year: i32, month: u32) -> NaiveDate {
+    let first_of_next = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    }
+    .expect("valid date");
+    let last_day = first_of_next - Duration::days(1);
+    last_day - Duration::days(last_day.weekday().num_days_from_sunday() as i64)
+}
+
+/// Belgrade UTC offset: CEST (+2) from the last Sunday of March to the last Sunday of October, 01:00 UTC
+fn belgrade_offset_hours(utc: DateTime<Utc>) -> i64 {
+    let year = utc.year();
+    let dst_start = last_sunday(year, 3).and_hms_opt(1, 0, 0).expect("valid time").and_utc();
+    let dst_end = last_sunday(year, 10).and_hms_opt(1, 0, 0).expect("valid time").and_utc();
+    if utc >= dst_start && utc < dst_end {
+        2
+    } else {
+        1
+    }
+}
+
+/// Today's date in Belgrade
+pub fn local_today(now: DateTime<Utc>) -> NaiveDate {
+    (now + Duration::hours(belgrade_offset_hours(now))).date_naive()
+}
+
+/// Orthodox Easter (Gregorian date), Meeus' Julian algorithm - valid 1900-2099
+pub fn orthodox_easter(year: i32) -> NaiveDate {
+    let a = year % 4;
+    let b = year % 7;
+    let c = year % 19;
+    let d = (19 * c + 15) % 30;
+    let e = (2 * a + 4 * b - d + 34) % 7;
+    let month = (d + e + 114) / 31;
+    let day = (d + e + 114) % 31 + 1;
+    let julian = NaiveDate::from_ymd_opt(year, month as u32, day as u32).expect("valid Easter date");
+    julian + Duration::days(13)
+}
+
+/// Non-working public holidays of a year, in date order
+pub fn public_holidays(year: i32) -> Vec<Holiday> {
+    let date = |month, day| NaiveDate::from_ymd_opt(year, month, day).expect("valid holiday date");
+    let easter = orthodox_easter(year);
+
+    let mut holidays = vec![
+        Holiday { date: date(1, 1), name: "Nova godina", moved_from: None },
+        Holiday { date: date(1, 2), name: "Nova godina", moved_from: None },
+        Holiday { date: date(1, 7), name: "Božić", moved_from: None },
+        Holiday { date: date(2, 15), name: "Sretenje - Dan državnosti", moved_from: None },
+        Holiday { date: date(2, 16), name: "Sretenje - Dan državnosti", moved_from: None },
+        Holiday { date: date(5, 1), name: "Praznik rada", moved_from: None },
+        Holiday { date: date(5, 2), name: "Praznik rada", moved_from: None },
+        Holiday { date: date(11, 11), name: "Dan primirja u Prvom svetskom ratu", moved_from: None },
+        Holiday { date: easter - Duration::days(2), name: "Veliki petak", moved_from: None },
+        Holiday { date: easter - Duration::days(1), name: "Velika subota", moved_from: None },
+        Holiday { date: easter, name: "Vaskrs", moved_from: None },
+        Holiday { date: easter + Duration::days(1), name: "Vaskršnji ponedeljak", moved_from: None },
+    ];
+
+    // State holidays falling on Sunday move to the first following working day (religious ones don't)
+    let movable = ["Nova godina", "Sretenje - Dan državnosti", "Praznik rada", "Dan primirja u Prvom svetskom ratu"];
+    let on_sunday: Vec<Holiday> = holidays
+        .iter()
+        .filter(|h| h.date.weekday() == Weekday::Sun && movable.contains(&h.name))
+        .cloned()
+        .collect();
+    for holiday in on_sunday {
+        let mut day = holiday.date + Duration::days(1);
+        while matches!(day.weekday(), Weekday::Sat | Weekday::Sun) || holidays.iter().any(|h| h.date == day) {
+            day += Duration::days(1);
+        }
+        holidays.push(Holiday { date: day, name: holiday.name, moved_from: Some(holiday.date) });
+    }
+
+    holidays.sort_by_key(|h| h.date);
+    holidays
+}
+
+/// Holidays from `from` (inclusive) within the next `days` days
+pub fn upcoming_holidays(from: NaiveDate, days: i64) -> Vec<Holiday> {
+    let until = from + Duration::days(days);
+    (from.year()..=until.year())
+        .flat_map(public_holidays)
+        .filter(|h| h.date >= from && h.date <= until)
+        .collect()
+}
+
+/// Weekends and public holidays are not working days (a deadline ending on one moves to the next working day)
+pub fn is_working_day(date: NaiveDate) -> bool {
+    !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+        && !public_holidays(date.year()).iter().any(|h| h.date == date)
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "ponedeljak",
+        Weekday::Tue => "utorak",
+        Weekday::Wed => "sreda",
+        Weekday::Thu => "četvrtak",
+        Weekday::Fri => "petak",
+        Weekday::Sat => "subota",
+        Weekday::Sun => "nedelja",
+    }
+}
+
+/// System prompt section with today's date and upcoming holidays.
+/// Disabled with PROMPT_DATE_CONTEXT=false.
+pub fn prompt_context(now: DateTime<Utc>) -> Option<String> {
+    let settings = &crate::config::get().llm;
+    if !settings.prompt_date_context {
+        return None;
+    }
+    let holiday_days = settings.prompt_calendar_days;
+
+    let today = local_today(now);
+    let mut context = format!(
+        "DANAŠNJI DATUM: {} ({}), vremenska zona {}. Sve rokove računaj od ovog datuma, ne od datuma na kome si treniran.",
+        today.format("%d.%m.%Y."),
+        weekday_name(today.weekday()),
+        TIMEZONE
+    );
+
+    let holidays = upcoming_holidays(today, holiday_days);
+    if !holidays.is_empty() {
+        let lines: Vec<String> = holidays
+            .iter()
+            .map(|h| match h.moved_from {
+                Some(from) => format!("- {} ({}) - neradni dan jer {} pada u nedelju {}", h.date.format("%d.%m.%Y."), weekday_name(h.date.weekday()), h.name, from.format("%d.%m.")),
+                None => format!("- {} ({}) - {}", h.date.format("%d.%m.%Y."), weekday_name(h.date.weekday()), h.name),
+            })
+            .collect();
+        context.push_str(&format!(
+            "\nNeradni praznici u narednih {} dana (rok koji ističe na praznik ili vikend pomera se na prvi naredni radni dan):\n{}",
+            holiday_days,
+            lines.join("\n")
+        ));
+    }
+
+    Some(context)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CalendarQuery {
+    pub year: Option<i32>, // All holidays of this year; otherwise the upcoming ones
+    pub days: Option<i64>, // Upcoming window, default 90
+}
+
+#[derive(Debug, Serialize)]
+pub struct CalendarResponse {
+    pub today: NaiveDate,
+    pub timezone: &'static str,
+    pub today_is_working_day: bool,
+    pub holidays: Vec<Holiday>,
+}
+
+/// Public calendar for the deadline calculator: today in Belgrade and non-working holidays
+pub async fn calendar_handler(
+    Query(query): Query<CalendarQuery>,
+) -> Result<ResponseJson<CalendarResponse>, StatusCode> {
+    let today = local_today(Utc::now());
+
+    let holidays = match query.year {
+        Some(year) if (1900..=2099).contains(&year) => public_holidays(year),
+        Some(_) => return Err(StatusCode::BAD_REQUEST),
+        None => upcoming_holidays(today, query.days.unwrap_or(90).clamp(1, 366)),
+    };
+
+    Ok(ResponseJson(CalendarResponse {
+        today,
+        timezone: TIMEZONE,
+        today_is_working_day: is_working_day(today),
+        holidays,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orthodox_easter() {
+        assert_eq!(orthodox_easter(2024), NaiveDate::from_ymd_opt(2024, 5, 5).unwrap());
+        assert_eq!(orthodox_easter(2025), NaiveDate::from_ymd_opt(2025, 4, 20).unwrap());
+        assert_eq!(orthodox_easter(2026), NaiveDate::from_ymd_opt(2026, 4, 12).unwrap());
+    }
+
+    #[test]
+    fn test_sunday_holiday_moves_to_next_working_day() {
+        // 1.1.2023 was a Sunday and 2.1. a holiday, so Tuesday 3.1. was off
+        let moved: Vec<Holiday> = public_holidays(2023).into_iter().filter(|h| h.moved_from.is_some()).collect();
+        assert_eq!(moved[0].date, NaiveDate::from_ymd_opt(2023, 1, 3).unwrap());
+        assert!(!is_working_day(NaiveDate::from_ymd_opt(2023, 1, 3).unwrap()));
+        assert!(is_working_day(NaiveDate::from_ymd_opt(2023, 1, 4).unwrap()));
+    }
+
+    #[test]
+    fn test_local_today_uses_belgrade_offset() {
+        let summer_night = DateTime::parse_from_rfc3339("2026-07-01T22:30:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(local_today(summer_night), NaiveDate::from_ymd_opt(2026, 7, 2).unwrap());
+        let winter_evening = DateTime::parse_from_rfc3339("2026-01-10T22:30:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(local_today(winter_evening), NaiveDate::from_ymd_opt(2026, 1, 10).unwrap());
+    }
+}
@@ -0,0 +1,55 @@
+// Chats created before an account was signed in move onto it when it is linked (link_user_handler):
+// guest chats of the device it signs in on (see guest.rs), and chats of a duplicate account with
+// the same e-mail in different letter case - left over from before e-mails were matched
+// case-insensitively. Duplicates are only merged for a verified e-mail, so registering with
+// someone else's address doesn't pull their chats.
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Move the device's guest chats and the duplicate accounts' chats onto the user, in one
+/// transaction. Returns how many chats were moved.
+pub async fn migrate_chats(
+    pool: &PgPool,
+    user_id: Uuid,
+    device_session_id: Option<&str>,
+    email: &str,
+    email_verified: bool,
+) -> Result<i64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let mut migrated = 0;
+
+    if let Some(device_session_id) = device_session_id.and_then(|id| Uuid::parse_str(id.trim()).ok()) {
+        migrated += crate::guest::claim_guest_chats(&mut tx, device_session_id, user_id).await?;
+    }
+
+    if email_verified {
+        let duplicate_ids: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT id FROM users WHERE LOWER(email) = LOWER($2) AND id <> $1 AND account_status <> 'deleted'",
+        )
+        .bind(user_id)
+        .bind(email)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for duplicate_id in &duplicate_ids {
+            crate::pii::reseal_mappings(&mut tx, *duplicate_id, user_id).await?;
+        }
+
+        // Folders stay with the account they belong to, so moved chats are unfiled
+        migrated += sqlx::query(
+            "UPDATE chats SET user_id = $1, folder_id = NULL, updated_at = NOW() WHERE user_id = ANY($2)",
+        )
+        .bind(user_id)
+        .bind(&duplicate_ids)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() as i64;
+    }
+
+    tx.commit().await?;
+
+    if migrated > 0 {
+        println!("📥 Moved {} chats onto user {}", migrated, user_id);
+    }
+    Ok(migrated)
+}
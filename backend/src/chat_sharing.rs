@@ -0,0 +1,271 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Json as ResponseJson, Response},
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::database;
+use crate::sessions::hash_token;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+const MAX_EXPIRY_DAYS: i64 = 365;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateShareRequest {
+    #[serde(default)]
+    pub allow_contract_download: bool,
+    pub expires_in_days: Option<i64>, // No expiry when omitted (until revoked)
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ChatShare {
+    pub id: i64,
+    pub chat_id: i64,
+    pub allow_contract_download: bool,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub view_count: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateShareResponse {
+    pub share: ChatShare,
+    pub share_url: String, // Contains the token - only returned here, it's stored hashed
+}
+
+/// Read-only transcript for the public view: no owner identity, no uploaded file names
+#[derive(Debug, Serialize)]
+pub struct SharedChat {
+    pub title: String,
+    pub shared_at: chrono::DateTime<chrono::Utc>,
+    pub messages: Vec<SharedMessage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SharedMessage {
+    pub role: String,
+    pub content: String,
+    pub law_name: Option<String>,
+    pub has_document: bool,
+    pub contract_type: Option<String>,
+    pub contract_download_url: Option<String>, // Only when the owner allowed contract downloads
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SharedMessageRow {
+    role: String,
+    content: String,
+    law_name: Option<String>,
+    has_document: Option<bool>,
+    contract_file_id: Option<String>,
+    contract_type: Option<String>,
+    created_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+fn share_url(token: &str) -> String {
+    format!("https://chat.normaai.rs/shared-chat.html?token={}", token)
+}
+
+/// Create a share link for a chat. A chat has one live link - creating a new one revokes the old.
+pub async fn create_share_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(chat_id): Path<i64>,
+    Json(request): Json<CreateShareRequest>,
+) -> Result<ResponseJson<CreateShareResponse>, StatusCode> {
+    let user_id = database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    if request.expires_in_days.is_some_and(|days| !(1..=MAX_EXPIRY_DAYS).contains(&days)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let owns_chat: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM chats WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL)"
+    )
+    .bind(chat_id)
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to check chat ownership: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !owns_chat {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let token: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        eprintln!("Failed to start share transaction: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    sqlx::query("UPDATE chat_shares SET revoked_at = NOW() WHERE chat_id = $1 AND revoked_at IS NULL")
+        .bind(chat_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to revoke previous share link: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let share = sqlx::query_as::<_, ChatShare>(
+        "INSERT INTO chat_shares (chat_id, user_id, token_hash, allow_contract_download, expires_at)
+         VALUES ($1, $2, $3, $4, CASE WHEN $5::INTEGER IS NULL THEN NULL ELSE NOW() + make_interval(days => $5::INTEGER) END)
+         RETURNING id, chat_id, allow_contract_download, expires_at, view_count, created_at"
+    )
+    .bind(chat_id)
+    .bind(user_id)
+    .bind(hash_token(&token))
+    .bind(request.allow_contract_download)
+    .bind(request.expires_in_days.map(|days| days as i32))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to create share link: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        eprintln!("Failed to commit share link: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    println!("🔗 Share link {} created for chat {}", share.id, chat_id);
+
+    Ok(ResponseJson(CreateShareResponse {
+        share,
+        share_url: share_url(&token),
+    }))
+}
+
+/// Current share link of a chat (without its token), 404 if the chat isn't shared
+pub async fn get_share_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(chat_id): Path<i64>,
+) -> Result<ResponseJson<ChatShare>, StatusCode> {
+    let user_id = database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    sqlx::query_as::<_, ChatShare>(
+        "SELECT id, chat_id, allow_contract_download, expires_at, view_count, created_at
+         FROM chat_shares
+         WHERE chat_id = $1 AND user_id = $2 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > NOW())"
+    )
+    .bind(chat_id)
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to load share link: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .map(ResponseJson)
+    .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Revoke the chat's share link
+pub async fn revoke_share_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(chat_id): Path<i64>,
+) -> Result<StatusCode, StatusCode> {
+    let user_id = database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let result = sqlx::query(
+        "UPDATE chat_shares SET revoked_at = NOW() WHERE chat_id = $1 AND user_id = $2 AND revoked_at IS NULL"
+    )
+    .bind(chat_id)
+    .bind(user_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to revoke share link: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    println!("🔗 Share link revoked for chat {}", chat_id);
+    Ok(StatusCode::OK)
+}
+
+/// Public: read-only transcript behind a share link. Revoked, expired or deleted chats are 404.
+pub async fn get_shared_chat_handler(
+    State((pool, _, _, _)): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Response, StatusCode> {
+    let share = sqlx::query_as::<_, (i64, bool, String, chrono::DateTime<chrono::Utc>)>(
+        "UPDATE chat_shares s SET view_count = s.view_count + 1, last_viewed_at = NOW()
+         FROM chats c
+         WHERE s.token_hash = $1 AND c.id = s.chat_id
+           AND s.revoked_at IS NULL AND (s.expires_at IS NULL OR s.expires_at > NOW())
+           AND c.deleted_at IS NULL
+         RETURNING s.chat_id, s.allow_contract_download, c.title, s.created_at"
+    )
+    .bind(hash_token(&token))
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to load shared chat: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let (chat_id, allow_contract_download, title, shared_at) = share;
+
+    let rows = sqlx::query_as::<_, SharedMessageRow>(
+        "SELECT role, content, law_name, has_document, contract_file_id, contract_type, created_at
+         FROM messages WHERE chat_id = $1 AND superseded_at IS NULL ORDER BY created_at ASC"
+    )
+    .bind(chat_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to load shared messages: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let api_base_url = &crate::config::get().api_base_url;
+    let messages = rows
+        .into_iter()
+        .map(|row| SharedMessage {
+            role: row.role,
+            content: row.content,
+            law_name: row.law_name,
+            has_document: row.has_document.unwrap_or(false),
+            contract_download_url: row
+                .contract_file_id
+                .filter(|_| allow_contract_download)
+                .map(|file_id| format!("{}/api/contracts/{}", api_base_url, file_id)),
+            contract_type: row.contract_type,
+            created_at: row.created_at,
+        })
+        .collect();
+
+    let mut response = ResponseJson(SharedChat { title, shared_at, messages }).into_response();
+    // Shared consultations shouldn't be cached by proxies, indexed, or leak the link via Referer
+    let headers = response.headers_mut();
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    headers.insert(header::REFERRER_POLICY, HeaderValue::from_static("no-referrer"));
+    headers.insert("x-robots-tag", HeaderValue::from_static("noindex, nofollow"));
+    Ok(response)
+}
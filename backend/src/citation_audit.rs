@@ -0,0 +1,197 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+/// Outcome of looking up one cited article
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CitationOutcome {
+    Extracted,
+    NotFound,  // Law was available but the article couldn't be extracted
+    BeyondLaw, // Article number past the law's last article - most likely hallucinated
+    Error,     // Lookup failed (scrape or database error)
+}
+
+impl CitationOutcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CitationOutcome::Extracted => "extracted",
+            CitationOutcome::NotFound => "not_found",
+            CitationOutcome::BeyondLaw => "beyond_law",
+            CitationOutcome::Error => "error",
+        }
+    }
+}
+
+/// Log the article lookups of one answer (never fails the request)
+pub async fn record_citations(law_name: &str, citations: &[(String, CitationOutcome)], pool: &PgPool) {
+    if citations.is_empty() {
+        return;
+    }
+
+    let article_numbers: Vec<&str> = citations.iter().map(|(number, _)| number.as_str()).collect();
+    let outcomes: Vec<&str> = citations.iter().map(|(_, outcome)| outcome.as_str()).collect();
+
+    let result = sqlx::query(
+        "INSERT INTO citation_events (law_name, article_number, outcome)
+         SELECT $1, article_number, outcome FROM UNNEST($2::TEXT[], $3::TEXT[]) AS t(article_number, outcome)"
+    )
+    .bind(law_name)
+    .bind(&article_numbers)
+    .bind(&outcomes)
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("⚠️  Failed to record citation events: {}", e);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CitationCoverageQuery {
+    pub days: Option<i32>,          // Look-back window, default 30
+    pub limit: Option<i64>,         // Laws in the report, default 50
+    pub articles_per_law: Option<usize>, // Top cited/failed articles listed per law, default 10
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArticleCount {
+    pub article_number: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LawCitationCoverage {
+    pub law_name: String,
+    pub citations: i64,
+    pub extracted: i64,
+    pub not_found: i64,
+    pub beyond_law: i64,
+    pub errors: i64,
+    pub extraction_rate: f64, // extracted / (citations - beyond_law); hallucinations aren't parser failures
+    pub top_cited: Vec<ArticleCount>,
+    pub top_failed: Vec<ArticleCount>, // not_found + error - candidates for parser fixes
+}
+
+#[derive(Debug, Serialize)]
+pub struct CitationCoverageReport {
+    pub days: i32,
+    pub total_citations: i64,
+    pub laws: Vec<LawCitationCoverage>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct LawTotals {
+    law_name: String,
+    citations: i64,
+    extracted: i64,
+    not_found: i64,
+    beyond_law: i64,
+    errors: i64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ArticleTotals {
+    law_name: String,
+    article_number: String,
+    citations: i64,
+    failed: i64,
+}
+
+fn extraction_rate(totals: &LawTotals) -> f64 {
+    let attempts = totals.citations - totals.beyond_law;
+    if attempts <= 0 {
+        return 1.0;
+    }
+    totals.extracted as f64 / attempts as f64
+}
+
+/// Admin: most cited laws/articles and which citations failed extraction, busiest laws first
+pub async fn citation_coverage_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<CitationCoverageQuery>,
+) -> Result<ResponseJson<CitationCoverageReport>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let days = query.days.unwrap_or(30).clamp(1, 365);
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let articles_per_law = query.articles_per_law.unwrap_or(10).clamp(1, 100);
+
+    let db_error = |e: sqlx::Error| {
+        eprintln!("Failed to build citation coverage report: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let reports = crate::db_routing::analytics(&pool);
+
+    let law_totals = sqlx::query_as::<_, LawTotals>(
+        "SELECT law_name,
+                COUNT(*) AS citations,
+                COUNT(*) FILTER (WHERE outcome = 'extracted') AS extracted,
+                COUNT(*) FILTER (WHERE outcome = 'not_found') AS not_found,
+                COUNT(*) FILTER (WHERE outcome = 'beyond_law') AS beyond_law,
+                COUNT(*) FILTER (WHERE outcome = 'error') AS errors
+         FROM citation_events
+         WHERE created_at > NOW() - make_interval(days => $1)
+         GROUP BY law_name
+         ORDER BY citations DESC
+         LIMIT $2"
+    )
+    .bind(days)
+    .bind(limit)
+    .fetch_all(reports)
+    .await
+    .map_err(db_error)?;
+
+    let law_names: Vec<&str> = law_totals.iter().map(|t| t.law_name.as_str()).collect();
+    let article_totals = sqlx::query_as::<_, ArticleTotals>(
+        "SELECT law_name, article_number,
+                COUNT(*) AS citations,
+                COUNT(*) FILTER (WHERE outcome IN ('not_found', 'error')) AS failed
+         FROM citation_events
+         WHERE created_at > NOW() - make_interval(days => $1) AND law_name = ANY($2)
+         GROUP BY law_name, article_number"
+    )
+    .bind(days)
+    .bind(&law_names)
+    .fetch_all(reports)
+    .await
+    .map_err(db_error)?;
+
+    let top_articles = |law_name: &str, count: fn(&ArticleTotals) -> i64| -> Vec<ArticleCount> {
+        let mut articles: Vec<ArticleCount> = article_totals
+            .iter()
+            .filter(|a| a.law_name == law_name && count(a) > 0)
+            .map(|a| ArticleCount { article_number: a.article_number.clone(), count: count(a) })
+            .collect();
+        articles.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.article_number.cmp(&b.article_number)));
+        articles.truncate(articles_per_law);
+        articles
+    };
+
+    let total_citations = law_totals.iter().map(|t| t.citations).sum();
+    let laws = law_totals
+        .iter()
+        .map(|totals| LawCitationCoverage {
+            law_name: totals.law_name.clone(),
+            citations: totals.citations,
+            extracted: totals.extracted,
+            not_found: totals.not_found,
+            beyond_law: totals.beyond_law,
+            errors: totals.errors,
+            extraction_rate: extraction_rate(totals),
+            top_cited: top_articles(&totals.law_name, |a| a.citations),
+            top_failed: top_articles(&totals.law_name, |a| a.failed),
+        })
+        .collect();
+
+    Ok(ResponseJson(CitationCoverageReport { days, total_citations, laws }))
+}
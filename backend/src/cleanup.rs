@@ -1,17 +1,25 @@
 use sqlx::PgPool;
 use std::sync::Arc;
 use tokio::time::{interval, Duration};
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info};
 use crate::database::{get_expired_deleted_users, permanently_delete_user};
 
-/// Background job to permanently delete users after 30-day grace period
-/// AND clean up expired sessions
+/// Background job to permanently delete users after 30-day grace period,
+/// clean up expired sessions, purge soft-deleted chats, chats past their retention window,
+/// unclaimed guest trials AND expired cached answers
 /// Runs once per day at startup time
-pub async fn start_cleanup_job(pool: Arc<PgPool>) {
+pub async fn start_cleanup_job(pool: Arc<PgPool>, shutdown: CancellationToken) {
     let mut interval = interval(Duration::from_secs(86400)); // 24 hours = 86400 seconds
 
     loop {
-        interval.tick().await;
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.cancelled() => {
+                info!("🛑 Cleanup job stopped");
+                return;
+            }
+        }
 
         info!("🗑️  Running daily cleanup jobs");
 
@@ -58,6 +66,60 @@ pub async fn start_cleanup_job(pool: Arc<PgPool>) {
             }
         }
 
+        // 3. Permanently delete chats after the 30-day restore window
+        info!("💬 Purging soft-deleted chats");
+        match crate::database::purge_deleted_chats(&pool).await {
+            Ok(count) => {
+                if count > 0 {
+                    info!("✅ Purged {} deleted chat(s)", count);
+                } else {
+                    info!("✅ No deleted chats to purge");
+                }
+            }
+            Err(e) => {
+                error!("❌ Failed to purge deleted chats: {}", e);
+            }
+        }
+
+        // 4. Drop expired cached answers
+        match crate::answer_cache::purge_expired(&pool).await {
+            Ok(count) => info!("✅ Purged {} expired cached answer(s)", count),
+            Err(e) => error!("❌ Failed to purge expired cached answers: {}", e),
+        }
+
+        // 5. Drop expired refresh tokens
+        match crate::refresh_tokens::purge_expired(&pool).await {
+            Ok(count) => info!("✅ Purged {} expired refresh token(s)", count),
+            Err(e) => error!("❌ Failed to purge expired refresh tokens: {}", e),
+        }
+
+        // 6. Drop expired data export archives
+        match crate::data_export::purge_expired(&pool).await {
+            Ok(count) => info!("✅ Purged {} expired data export(s)", count),
+            Err(e) => error!("❌ Failed to purge expired data exports: {}", e),
+        }
+
+        // 7. Drop old idempotency keys
+        match crate::idempotency::purge_expired(&pool).await {
+            Ok(count) => info!("✅ Purged {} expired idempotency key(s)", count),
+            Err(e) => error!("❌ Failed to purge expired idempotency keys: {}", e),
+        }
+
+        // 8. Delete chats, messages, documents and contracts past their owner's retention window
+        match crate::retention::purge_expired(&pool).await {
+            Ok(purged) => info!(
+                "✅ Purged {} chat(s), {} message(s), {} document(s) and {} contract(s) ({} file(s)) past retention",
+                purged.chats, purged.messages, purged.documents, purged.contracts, purged.contract_files
+            ),
+            Err(e) => error!("❌ Failed to purge chats past retention: {}", e),
+        }
+
+        // 9. Delete guest trials that were never registered
+        match crate::guest::purge_unclaimed(&pool).await {
+            Ok((guests, chats)) => info!("✅ Purged {} unclaimed guest session(s) and {} guest chat(s)", guests, chats),
+            Err(e) => error!("❌ Failed to purge unclaimed guest sessions: {}", e),
+        }
+
         info!("✅ Daily cleanup jobs completed");
     }
 }
@@ -0,0 +1,626 @@
+// Server configuration: API keys, URLs and secrets, read from the environment once at startup and
+// validated together, so a misconfigured deploy fails with the full list of missing or invalid
+// settings instead of the first one hit or a runtime error on some rarely used path. main installs
+// it with init(); handlers and jobs read it through get(). Per-feature tuning knobs (LLM_TOOLS,
+// PROMPT_CALENDAR_DAYS, ...) are read here too, with their defaults; invalid values are reported
+// like any other problem instead of silently falling back.
+use crate::cors_config::CorsConfig;
+use crate::llm_config::LlmPurpose;
+use crate::openrouter_resilience::RetryPolicy;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const DEFAULT_JWT_SECRET: &str = "default-jwt-secret-key-change-in-production";
+const DEFAULT_API_BASE_URL: &str = "https://norma-ai.fly.dev";
+const DEFAULT_PORT: u16 = 8080;
+/// Version of the terms of use currently in force (see consent.rs)
+const DEFAULT_TERMS_VERSION: &str = "2026-10-01";
+/// Fly.io waits kill_timeout (fly.toml) before SIGKILL - keep this a few seconds below it
+const DEFAULT_DRAIN_TIMEOUT_SECONDS: u64 = 25;
+const DEFAULT_QUESTION_TIMEOUT_SECONDS: u64 = 150;
+const DEFAULT_ARTICLE_FETCH_TIMEOUT_SECONDS: u64 = 25;
+const DEFAULT_PROMPT_CALENDAR_DAYS: i64 = 60;
+const DEFAULT_LAW_REVALIDATION_INTERVAL_HOURS: u64 = 6;
+const DEFAULT_LLM_MAX_CONCURRENT: usize = 8;
+const DEFAULT_TTS_MODEL: &str = "gpt-4o-mini-tts";
+const DEFAULT_TTS_VOICE: &str = "alloy";
+const DEFAULT_TRANSCRIPTION_MODEL: &str = "whisper-1";
+const DEFAULT_SELF_HOSTED_TRANSCRIPTION_MODEL: &str = "Systran/faster-whisper-large-v3";
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Clone)]
+pub struct Config {
+    pub database_url: String,
+    /// Optional read replica for analytics queries (see db_routing.rs)
+    pub database_replica_url: Option<String>,
+    pub openrouter_api_key: String,
+    pub openai_api_key: String,
+    pub resend_api_key: String,
+    /// Legacy (non-Supabase) auth tokens
+    pub jwt_secret: String,
+    /// Protects stored password reset / e-mail verification tokens (see secret_box.rs); required
+    /// unless JWT_SECRET is set, since the public default secret would protect nothing
+    pub token_encryption_key: Option<String>,
+    /// Encrypts enrolled two-factor secrets (see totp.rs); changing it invalidates enrolled 2FA
+    pub totp_encryption_key: String,
+    /// Encrypts the mappings that restore redacted personal data in messages (see pii.rs)
+    pub pii_encryption_key: Option<String>,
+    pub supabase_url: Option<String>,
+    pub supabase_jwt_secret: Option<String>,
+    /// Public URL of this server, used in download links (contracts, exports, shared chats)
+    pub api_base_url: String,
+    /// Admin endpoints are disabled without it
+    pub admin_api_key: Option<String>,
+    pub revenuecat_api_key: Option<String>,
+    /// Webhook signatures aren't verified without it
+    pub revenuecat_webhook_secret: Option<String>,
+    /// Bearer token for /metrics (disabled without it)
+    pub metrics_token: Option<String>,
+    /// Geolocation API for session IPs, "{ip}" is replaced (lookups are disabled without it, see
+    /// sessions.rs)
+    pub geoip_api_url: Option<String>,
+    /// Seller printed on invoices; invoices aren't issued without it (see invoices.rs)
+    pub invoice_issuer: Option<InvoiceIssuer>,
+    /// Card payments for web/desktop subscriptions; without it create_subscription activates plans
+    /// unpaid (see payments.rs)
+    pub stripe: Option<StripeConfig>,
+    pub cors: CorsConfig,
+    pub port: u16,
+    /// Terms of use users must accept before asking questions (TERMS_VERSION, see consent.rs)
+    pub terms_version: String,
+    /// Extra disposable e-mail domains on top of the built-in list (see trial_abuse.rs)
+    pub disposable_email_domains: Vec<String>,
+    /// Start even if the database schema is newer than this build (see migrations.rs)
+    pub allow_newer_schema: bool,
+    /// How long in-flight requests get to finish on shutdown
+    pub shutdown_drain_timeout: Duration,
+    pub law_revalidation_interval: Duration,
+    pub answer_cache_enabled: bool,
+    pub llm: LlmSettings,
+    pub speech: SpeechSettings,
+}
+
+#[derive(Debug, Clone)]
+pub struct LlmSettings {
+    /// Function calling in the answer step (off for models without it, see llm_tools.rs)
+    pub tools_enabled: bool,
+    /// Let the model fix citations that don't exist in the law (one extra call per answer)
+    pub citation_self_correct: bool,
+    /// Today's date and upcoming holidays in the system prompt, and how many days ahead to list
+    pub prompt_date_context: bool,
+    pub prompt_calendar_days: i64,
+    /// Questions answered at the same time (see llm_queue.rs)
+    pub max_concurrent: usize,
+    /// Deadline for a whole question and for its article fetch step (see question_timeout.rs)
+    pub question_timeout: Duration,
+    pub article_fetch_timeout: Duration,
+    pub retry: RetryPolicy,
+    /// LLM_MODEL_<PURPOSE>[_<PLAN>|_FALLBACK] and LLM_COST_CAP_USD_<PLAN> by variable name, trimmed
+    /// but kept when empty, since an empty value means "none" (see llm_config.rs, cost_caps.rs)
+    pub overrides: HashMap<String, String>,
+}
+
+impl LlmSettings {
+    pub fn override_value(&self, key: &str) -> Option<String> {
+        self.overrides.get(key).cloned()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SpeechSettings {
+    pub tts_model: String,
+    pub tts_voice: String,
+    pub transcription: TranscriptionProvider,
+}
+
+/// TRANSCRIPTION_PROVIDER (openai | self_hosted, default openai)
+#[derive(Debug, Clone, PartialEq)]
+pub enum TranscriptionProvider {
+    OpenAi { model: String },
+    SelfHosted { base_url: String, api_key: Option<String>, model: String },
+}
+
+#[derive(Clone)]
+pub struct StripeConfig {
+    pub secret_key: String,
+    pub webhook_secret: String,
+    /// Page Checkout returns the browser to, with ?checkout=success or ?checkout=cancelled
+    pub return_url: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct InvoiceIssuer {
+    pub name: String,
+    pub address: String,
+    pub pib: String,
+    pub maticni_broj: String,
+    pub bank_account: Option<String>,
+    /// Percent included in subscription prices; 0 for sellers outside the VAT system
+    pub vat_rate: u8,
+}
+
+// Keys and secrets stay out of logs
+impl std::fmt::Debug for Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Config")
+            .field("api_base_url", &self.api_base_url)
+            .field("supabase_url", &self.supabase_url)
+            .field("port", &self.port)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Config {
+    /// Read and validate every setting; the error lists all problems, one per entry
+    pub fn from_env_with(env: impl Fn(&str) -> Option<String>) -> Result<Config, Vec<String>> {
+        let mut problems = Vec::new();
+        // Set-but-empty counts as unset
+        let optional = |key: &str| env(key).map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+        let mut required = |key: &str| {
+            optional(key).unwrap_or_else(|| {
+                problems.push(format!("{} is required but not set", key));
+                String::new()
+            })
+        };
+
+        let database_url = required("DATABASE_URL");
+        let openrouter_api_key = required("OPENROUTER_API_KEY");
+        let openai_api_key = required("OPENAI_API_KEY");
+        let resend_api_key = required("RESEND_API_KEY");
+        let totp_encryption_key = required("TOTP_ENCRYPTION_KEY");
+
+        let mut url = |key: &str, value: Option<String>| {
+            value.map(|value| {
+                if !value.starts_with("http://") && !value.starts_with("https://") {
+                    problems.push(format!("{} must be an http(s) URL, got '{}'", key, value));
+                }
+                value.trim_end_matches('/').to_string()
+            })
+        };
+        let supabase_url = url("SUPABASE_URL", optional("SUPABASE_URL"));
+        let api_base_url = url("API_BASE_URL", Some(optional("API_BASE_URL").unwrap_or_else(|| DEFAULT_API_BASE_URL.to_string())))
+            .unwrap_or_default();
+
+        // Kept verbatim rather than through url(): some providers need the trailing slash
+        let geoip_api_url = optional("GEOIP_API_URL");
+        if let Some(template) = &geoip_api_url {
+            if !(template.starts_with("http://") || template.starts_with("https://")) || !template.contains("{ip}") {
+                problems.push(format!("GEOIP_API_URL must be an http(s) URL containing {{ip}}, got '{}'", template));
+            }
+        }
+
+        let invoice_issuer = invoice_issuer(&optional, &mut problems);
+        let stripe = stripe(&optional, &mut problems);
+        let cors = CorsConfig::from_env_with(&env).unwrap_or_else(|problem| {
+            problems.push(problem);
+            CorsConfig::default()
+        });
+        let llm = llm_settings(&env, &optional, &mut problems);
+        let speech = speech_settings(&optional, &mut problems);
+        let allow_newer_schema = flag(&optional, "ALLOW_NEWER_SCHEMA", false, &mut problems);
+        let answer_cache_enabled = flag(&optional, "ANSWER_CACHE_ENABLED", true, &mut problems);
+        let shutdown_drain_timeout =
+            Duration::from_secs(number(&optional, "SHUTDOWN_DRAIN_TIMEOUT_SECONDS", DEFAULT_DRAIN_TIMEOUT_SECONDS, 0, &mut problems));
+        let law_revalidation_interval = Duration::from_secs(
+            3600 * number(&optional, "LAW_REVALIDATION_INTERVAL_HOURS", DEFAULT_LAW_REVALIDATION_INTERVAL_HOURS, 1, &mut problems),
+        );
+
+        let jwt_secret = optional("JWT_SECRET").unwrap_or_else(|| DEFAULT_JWT_SECRET.to_string());
+        let token_encryption_key = optional("TOKEN_ENCRYPTION_KEY");
+        if token_encryption_key.is_none() && jwt_secret == DEFAULT_JWT_SECRET {
+            problems.push("TOKEN_ENCRYPTION_KEY is required when JWT_SECRET is not set".to_string());
+        }
+
+        let port = match optional("PORT") {
+            None => DEFAULT_PORT,
+            Some(value) => value.parse::<u16>().ok().filter(|port| *port > 0).unwrap_or_else(|| {
+                problems.push(format!("PORT must be a port number, got '{}'", value));
+                DEFAULT_PORT
+            }),
+        };
+
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+
+        Ok(Config {
+            database_url,
+            database_replica_url: optional("DATABASE_REPLICA_URL"),
+            openrouter_api_key,
+            openai_api_key,
+            resend_api_key,
+            jwt_secret,
+            token_encryption_key,
+            totp_encryption_key,
+            pii_encryption_key: optional("PII_ENCRYPTION_KEY"),
+            supabase_url,
+            supabase_jwt_secret: optional("SUPABASE_JWT_SECRET"),
+            api_base_url,
+            admin_api_key: optional("ADMIN_API_KEY"),
+            revenuecat_api_key: optional("REVENUECAT_API_KEY"),
+            revenuecat_webhook_secret: optional("REVENUECAT_WEBHOOK_SECRET"),
+            metrics_token: optional("METRICS_TOKEN"),
+            geoip_api_url,
+            invoice_issuer,
+            stripe,
+            cors,
+            port,
+            terms_version: optional("TERMS_VERSION").unwrap_or_else(|| DEFAULT_TERMS_VERSION.to_string()),
+            disposable_email_domains: optional("DISPOSABLE_EMAIL_DOMAINS")
+                .map(|domains| domains.split(',').map(|domain| domain.trim().to_lowercase()).filter(|domain| !domain.is_empty()).collect())
+                .unwrap_or_default(),
+            allow_newer_schema,
+            shutdown_drain_timeout,
+            law_revalidation_interval,
+            answer_cache_enabled,
+            llm,
+            speech,
+        })
+    }
+
+    pub fn from_env() -> Result<Config, Vec<String>> {
+        Self::from_env_with(|key| std::env::var(key).ok())
+    }
+
+    /// Settings that work but probably shouldn't in production, for the startup log
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.jwt_secret == DEFAULT_JWT_SECRET {
+            warnings.push("JWT_SECRET not set - using the insecure default secret".to_string());
+        }
+        if self.token_encryption_key.is_none() {
+            warnings.push("TOKEN_ENCRYPTION_KEY not set - stored auth tokens are protected with a key derived from JWT_SECRET".to_string());
+        }
+        if self.revenuecat_webhook_secret.is_none() {
+            warnings.push("REVENUECAT_WEBHOOK_SECRET not set - webhook signatures are not verified".to_string());
+        }
+        if self.invoice_issuer.is_none() {
+            warnings.push("INVOICE_ISSUER_* not set - no invoices are issued for subscription charges".to_string());
+        }
+        if self.stripe.is_none() {
+            warnings.push("STRIPE_* not set - web subscriptions are activated without payment".to_string());
+        }
+        if self.metrics_token.is_none() {
+            warnings.push("METRICS_TOKEN not set - /metrics is disabled".to_string());
+        }
+        warnings
+    }
+}
+
+/// INVOICE_ISSUER_NAME/ADDRESS/PIB/MATICNI_BROJ (all or none), INVOICE_ISSUER_BANK_ACCOUNT and
+/// INVOICE_VAT_RATE (percent, default 20)
+fn invoice_issuer(optional: &impl Fn(&str) -> Option<String>, problems: &mut Vec<String>) -> Option<InvoiceIssuer> {
+    const REQUIRED: [&str; 4] =
+        ["INVOICE_ISSUER_NAME", "INVOICE_ISSUER_ADDRESS", "INVOICE_ISSUER_PIB", "INVOICE_ISSUER_MATICNI_BROJ"];
+    let values = REQUIRED.map(optional);
+    let [Some(name), Some(address), Some(pib), Some(maticni_broj)] = values.clone() else {
+        if values.iter().any(Option::is_some) {
+            for (key, _) in REQUIRED.iter().zip(&values).filter(|(_, value)| value.is_none()) {
+                problems.push(format!("{} is required when invoicing is configured", key));
+            }
+        }
+        return None;
+    };
+
+    if !crate::invoices::is_valid_pib(&pib) {
+        problems.push(format!("INVOICE_ISSUER_PIB is not a valid PIB, got '{}'", pib));
+    }
+    if !crate::invoices::is_valid_maticni_broj(&maticni_broj) {
+        problems.push(format!("INVOICE_ISSUER_MATICNI_BROJ must be 8 digits, got '{}'", maticni_broj));
+    }
+    let vat_rate = match optional("INVOICE_VAT_RATE") {
+        None => 20,
+        Some(value) => value.parse::<u8>().ok().filter(|rate| *rate <= 100).unwrap_or_else(|| {
+            problems.push(format!("INVOICE_VAT_RATE must be a percentage, got '{}'", value));
+            20
+        }),
+    };
+
+    Some(InvoiceIssuer { name, address, pib, maticni_broj, bank_account: optional("INVOICE_ISSUER_BANK_ACCOUNT"), vat_rate })
+}
+
+/// STRIPE_SECRET_KEY, STRIPE_WEBHOOK_SECRET and STRIPE_RETURN_URL (all or none)
+fn stripe(optional: &impl Fn(&str) -> Option<String>, problems: &mut Vec<String>) -> Option<StripeConfig> {
+    const REQUIRED: [&str; 3] = ["STRIPE_SECRET_KEY", "STRIPE_WEBHOOK_SECRET", "STRIPE_RETURN_URL"];
+    let values = REQUIRED.map(optional);
+    let [Some(secret_key), Some(webhook_secret), Some(return_url)] = values.clone() else {
+        if values.iter().any(Option::is_some) {
+            for (key, _) in REQUIRED.iter().zip(&values).filter(|(_, value)| value.is_none()) {
+                problems.push(format!("{} is required when Stripe is configured", key));
+            }
+        }
+        return None;
+    };
+
+    if !return_url.starts_with("https://") && !return_url.starts_with("http://") {
+        problems.push(format!("STRIPE_RETURN_URL must be an http(s) URL, got '{}'", return_url));
+    }
+    Some(StripeConfig { secret_key, webhook_secret, return_url })
+}
+
+/// true/false (any case); unset means the default
+fn flag(optional: &impl Fn(&str) -> Option<String>, key: &str, default: bool, problems: &mut Vec<String>) -> bool {
+    match optional(key).map(|value| value.to_ascii_lowercase()) {
+        None => default,
+        Some(value) if value == "true" => true,
+        Some(value) if value == "false" => false,
+        Some(value) => {
+            problems.push(format!("{} must be true or false, got '{}'", key, value));
+            default
+        }
+    }
+}
+
+/// A number of at least `min`; unset means the default
+fn number<T: FromStr + PartialOrd + std::fmt::Display>(
+    optional: &impl Fn(&str) -> Option<String>,
+    key: &str,
+    default: T,
+    min: T,
+    problems: &mut Vec<String>,
+) -> T {
+    let Some(value) = optional(key) else {
+        return default;
+    };
+    match value.parse::<T>() {
+        Ok(number) if number >= min => number,
+        _ => {
+            problems.push(format!("{} must be a number of at least {}, got '{}'", key, min, value));
+            default
+        }
+    }
+}
+
+fn llm_settings(
+    env: &impl Fn(&str) -> Option<String>,
+    optional: &impl Fn(&str) -> Option<String>,
+    problems: &mut Vec<String>,
+) -> LlmSettings {
+    let defaults = RetryPolicy::default();
+    let retry = RetryPolicy {
+        max_retries: number(optional, "OPENROUTER_MAX_RETRIES", defaults.max_retries, 0, problems),
+        base_delay: Duration::from_millis(number(optional, "OPENROUTER_RETRY_BASE_MS", defaults.base_delay.as_millis() as u64, 0, problems)),
+        breaker_threshold: number(optional, "OPENROUTER_BREAKER_THRESHOLD", defaults.breaker_threshold, 1, problems),
+        breaker_cooldown: Duration::from_secs(number(
+            optional,
+            "OPENROUTER_BREAKER_COOLDOWN_SECS",
+            defaults.breaker_cooldown.as_secs(),
+            0,
+            problems,
+        )),
+    };
+
+    let mut override_keys = Vec::new();
+    for purpose in [LlmPurpose::Answer, LlmPurpose::Classification, LlmPurpose::LawDetection] {
+        let key = format!("LLM_MODEL_{}", purpose.as_str().to_uppercase());
+        override_keys.extend(crate::models::ACCOUNT_TYPES.iter().map(|plan| format!("{}_{}", key, plan.to_uppercase())));
+        override_keys.push(format!("{}_FALLBACK", key));
+        override_keys.push(key);
+    }
+    override_keys.extend(crate::models::ACCOUNT_TYPES.iter().map(|plan| format!("LLM_COST_CAP_USD_{}", plan.to_uppercase())));
+    let overrides = override_keys
+        .into_iter()
+        .filter_map(|key| env(&key).map(|value| (key, value.trim().to_string())))
+        .collect();
+
+    LlmSettings {
+        tools_enabled: flag(optional, "LLM_TOOLS", true, problems),
+        citation_self_correct: flag(optional, "CITATION_SELF_CORRECT", false, problems),
+        prompt_date_context: flag(optional, "PROMPT_DATE_CONTEXT", true, problems),
+        prompt_calendar_days: number(optional, "PROMPT_CALENDAR_DAYS", DEFAULT_PROMPT_CALENDAR_DAYS, 0, problems).min(366),
+        max_concurrent: number(optional, "LLM_MAX_CONCURRENT", DEFAULT_LLM_MAX_CONCURRENT, 1, problems),
+        question_timeout: Duration::from_secs(number(optional, "QUESTION_TIMEOUT_SECS", DEFAULT_QUESTION_TIMEOUT_SECONDS, 1, problems)),
+        article_fetch_timeout: Duration::from_secs(number(
+            optional,
+            "ARTICLE_FETCH_TIMEOUT_SECS",
+            DEFAULT_ARTICLE_FETCH_TIMEOUT_SECONDS,
+            1,
+            problems,
+        )),
+        retry,
+        overrides,
+    }
+}
+
+/// OPENAI_TTS_MODEL/VOICE and the transcription provider; self_hosted needs WHISPER_SELF_HOSTED_URL
+/// (plus optional WHISPER_SELF_HOSTED_API_KEY/MODEL)
+fn speech_settings(optional: &impl Fn(&str) -> Option<String>, problems: &mut Vec<String>) -> SpeechSettings {
+    let provider = optional("TRANSCRIPTION_PROVIDER").unwrap_or_default().to_lowercase();
+    let openai = || TranscriptionProvider::OpenAi {
+        model: optional("OPENAI_TRANSCRIPTION_MODEL").unwrap_or_else(|| DEFAULT_TRANSCRIPTION_MODEL.to_string()),
+    };
+    let transcription = match provider.as_str() {
+        "" | "openai" => openai(),
+        "self_hosted" | "self-hosted" | "whisper" => match optional("WHISPER_SELF_HOSTED_URL") {
+            Some(base_url) => TranscriptionProvider::SelfHosted {
+                base_url: base_url.trim_end_matches('/').to_string(),
+                api_key: optional("WHISPER_SELF_HOSTED_API_KEY"),
+                model: optional("WHISPER_SELF_HOSTED_MODEL").unwrap_or_else(|| DEFAULT_SELF_HOSTED_TRANSCRIPTION_MODEL.to_string()),
+            },
+            None => {
+                problems.push("WHISPER_SELF_HOSTED_URL is required when TRANSCRIPTION_PROVIDER=self_hosted".to_string());
+                openai()
+            }
+        },
+        other => {
+            problems.push(format!("TRANSCRIPTION_PROVIDER must be openai or self_hosted, got '{}'", other));
+            openai()
+        }
+    };
+
+    SpeechSettings {
+        tts_model: optional("OPENAI_TTS_MODEL").unwrap_or_else(|| DEFAULT_TTS_MODEL.to_string()),
+        tts_voice: optional("OPENAI_TTS_VOICE").unwrap_or_else(|| DEFAULT_TTS_VOICE.to_string()),
+        transcription,
+    }
+}
+
+/// Install the configuration loaded at startup
+pub fn init(config: Config) -> &'static Config {
+    CONFIG.get_or_init(|| config)
+}
+
+/// The configuration installed by main
+pub fn get() -> &'static Config {
+    CONFIG.get().expect("config::init must be called at startup")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(vars: &[(&str, &str)]) -> Result<Config, Vec<String>> {
+        let vars: Vec<(String, String)> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Config::from_env_with(|key| vars.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone()))
+    }
+
+    const REQUIRED: &[(&str, &str)] = &[
+        ("DATABASE_URL", "postgresql://localhost/normaai"),
+        ("OPENROUTER_API_KEY", "or-key"),
+        ("OPENAI_API_KEY", "oa-key"),
+        ("RESEND_API_KEY", "re-key"),
+        ("TOTP_ENCRYPTION_KEY", "totp-key"),
+        ("TOKEN_ENCRYPTION_KEY", "token-key"),
+    ];
+
+    #[test]
+    fn test_defaults() {
+        let config = config(REQUIRED).unwrap();
+        assert_eq!(config.port, 8080);
+        assert_eq!(config.api_base_url, "https://norma-ai.fly.dev");
+        assert_eq!(config.jwt_secret, DEFAULT_JWT_SECRET);
+        assert!(config.admin_api_key.is_none());
+        assert!(config.geoip_api_url.is_none());
+        assert_eq!(config.warnings().len(), 5);
+        assert!(config.invoice_issuer.is_none());
+        assert!(config.stripe.is_none());
+        assert_eq!(config.terms_version, DEFAULT_TERMS_VERSION);
+        assert!(config.answer_cache_enabled && config.llm.tools_enabled && !config.llm.citation_self_correct);
+        assert_eq!(config.llm.max_concurrent, 8);
+        assert_eq!(config.llm.retry.max_retries, 2);
+        assert!(config.llm.overrides.is_empty());
+        assert_eq!(config.speech.transcription, TranscriptionProvider::OpenAi { model: "whisper-1".to_string() });
+    }
+
+    #[test]
+    fn test_tuning_settings() {
+        let mut vars = REQUIRED.to_vec();
+        vars.extend([
+            ("TERMS_VERSION", " 2027-01-01 "),
+            ("ANSWER_CACHE_ENABLED", "FALSE"),
+            ("SHUTDOWN_DRAIN_TIMEOUT_SECONDS", "10"),
+            ("DISPOSABLE_EMAIL_DOMAINS", "Spam.example, ,trash.example"),
+            ("LLM_MODEL_ANSWER_PROFESSIONAL", "anthropic/claude-sonnet-4"),
+            ("LLM_MODEL_ANSWER_FALLBACK", ""),
+            ("LLM_COST_CAP_USD_TEAM", "none"),
+            ("TRANSCRIPTION_PROVIDER", "self_hosted"),
+            ("WHISPER_SELF_HOSTED_URL", "http://whisper.internal:8000/"),
+        ]);
+        let config = config(&vars).unwrap();
+        assert_eq!(config.terms_version, "2027-01-01");
+        assert!(!config.answer_cache_enabled);
+        assert_eq!(config.shutdown_drain_timeout, Duration::from_secs(10));
+        assert_eq!(config.disposable_email_domains, vec!["spam.example", "trash.example"]);
+        assert_eq!(config.llm.override_value("LLM_MODEL_ANSWER_PROFESSIONAL").as_deref(), Some("anthropic/claude-sonnet-4"));
+        assert_eq!(config.llm.override_value("LLM_MODEL_ANSWER_FALLBACK").as_deref(), Some("")); // Disables the fallback
+        assert_eq!(config.llm.override_value("LLM_COST_CAP_USD_TEAM").as_deref(), Some("none"));
+        assert_eq!(
+            config.speech.transcription,
+            TranscriptionProvider::SelfHosted {
+                base_url: "http://whisper.internal:8000".to_string(),
+                api_key: None,
+                model: DEFAULT_SELF_HOSTED_TRANSCRIPTION_MODEL.to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_tuning_settings() {
+        let mut invalid = REQUIRED.to_vec();
+        invalid.extend([
+            ("LLM_TOOLS", "off"),
+            ("LLM_MAX_CONCURRENT", "0"),
+            ("LAW_REVALIDATION_INTERVAL_HOURS", "soon"),
+            ("TRANSCRIPTION_PROVIDER", "self_hosted"),
+        ]);
+        let problems = config(&invalid).unwrap_err();
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+    }
+
+    #[test]
+    fn test_reports_every_problem() {
+        let problems = config(&[("OPENAI_API_KEY", "oa-key"), ("RESEND_API_KEY", "  "), ("PORT", "http"), ("API_BASE_URL", "norma-ai.fly.dev")])
+            .unwrap_err();
+        assert_eq!(problems.len(), 7, "{:?}", problems);
+        assert!(problems.iter().any(|problem| problem.starts_with("DATABASE_URL")));
+        assert!(problems.iter().any(|problem| problem.starts_with("TOKEN_ENCRYPTION_KEY")));
+        assert!(problems.iter().any(|problem| problem.starts_with("TOTP_ENCRYPTION_KEY")));
+        assert!(problems.iter().any(|problem| problem.starts_with("RESEND_API_KEY"))); // Blank is missing
+        assert!(problems.iter().any(|problem| problem.starts_with("PORT")));
+        assert!(problems.iter().any(|problem| problem.starts_with("API_BASE_URL")));
+    }
+
+    #[test]
+    fn test_token_key_needs_a_real_secret() {
+        let without_key: Vec<_> = REQUIRED.iter().copied().filter(|(key, _)| *key != "TOKEN_ENCRYPTION_KEY").collect();
+        assert!(config(&without_key).is_err());
+
+        let mut with_jwt_secret = without_key.clone();
+        with_jwt_secret.push(("JWT_SECRET", "a-real-secret"));
+        assert!(config(&with_jwt_secret).unwrap().token_encryption_key.is_none());
+    }
+
+    #[test]
+    fn test_optional_settings() {
+        let mut vars = REQUIRED.to_vec();
+        vars.extend([
+            ("API_BASE_URL", "https://api.normaai.rs/"),
+            ("ADMIN_API_KEY", ""),
+            ("REVENUECAT_API_KEY", "rc-key"),
+            ("GEOIP_API_URL", "https://ipapi.co/{ip}/json/"),
+        ]);
+        let config = config(&vars).unwrap();
+        assert_eq!(config.api_base_url, "https://api.normaai.rs");
+        assert_eq!(config.geoip_api_url.as_deref(), Some("https://ipapi.co/{ip}/json/"));
+        assert!(config.admin_api_key.is_none());
+        assert_eq!(config.revenuecat_api_key.as_deref(), Some("rc-key"));
+    }
+
+    #[test]
+    fn test_invoice_issuer() {
+        let mut vars = REQUIRED.to_vec();
+        vars.extend([
+            ("INVOICE_ISSUER_NAME", "Norma AI DOO"),
+            ("INVOICE_ISSUER_ADDRESS", "Bulevar oslobođenja 1, 21000 Novi Sad"),
+            ("INVOICE_ISSUER_PIB", "104052135"),
+            ("INVOICE_ISSUER_MATICNI_BROJ", "20084693"),
+        ]);
+        let issuer = config(&vars).unwrap().invoice_issuer.unwrap();
+        assert_eq!(issuer.vat_rate, 20);
+        assert!(issuer.bank_account.is_none());
+
+        vars.push(("INVOICE_VAT_RATE", "120"));
+        assert_eq!(config(&vars).unwrap_err().len(), 1);
+
+        // All or nothing
+        let mut partial = REQUIRED.to_vec();
+        partial.extend([("INVOICE_ISSUER_NAME", "Norma AI DOO"), ("INVOICE_ISSUER_PIB", "104052136")]);
+        let problems = config(&partial).unwrap_err();
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+    }
+
+    #[test]
+    fn test_stripe() {
+        let mut vars = REQUIRED.to_vec();
+        vars.extend([("STRIPE_SECRET_KEY", "sk_test_123"), ("STRIPE_WEBHOOK_SECRET", "whsec_123")]);
+        let problems = config(&vars).unwrap_err();
+        assert_eq!(problems, vec!["STRIPE_RETURN_URL is required when Stripe is configured".to_string()]);
+
+        vars.push(("STRIPE_RETURN_URL", "https://app.normaai.rs/pretplata"));
+        let stripe = config(&vars).unwrap().stripe.unwrap();
+        assert_eq!(stripe.return_url, "https://app.normaai.rs/pretplata");
+    }
+}
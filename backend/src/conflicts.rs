@@ -0,0 +1,383 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::database::verify_user_from_headers_async;
+use crate::models::ResponseWarning;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+/// Shorter names match too much unrelated text
+const MIN_PARTY_NAME_CHARS: usize = 3;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ConflictParty {
+    pub id: i64,
+    pub name: String,
+    pub role: String, // 'client' or 'adverse'
+    pub matter: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateConflictPartyRequest {
+    pub name: String,
+    pub role: String,
+    pub matter: Option<String>, // Predmet the party belongs to, e.g. "P-123/2024"
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ConflictAlert {
+    pub id: i64,
+    pub chat_id: i64,
+    pub party_id: i64,
+    pub party_name: String,
+    pub matter: Option<String>,
+    pub source: String, // 'contract' or 'document'
+    pub raised_by: Uuid,
+    pub status: String, // 'open', 'cleared', 'confirmed'
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub resolved_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveConflictAlertRequest {
+    pub status: String, // 'cleared' (no conflict) or 'confirmed' (conflict, matter declined)
+}
+
+/// Lowercase, fold Serbian diacritics and collapse everything else to single spaces,
+/// so "Petrović d.o.o." and "PETROVIC DOO" compare equal
+fn normalize_party_name(name: &str) -> String {
+    let mut folded = String::with_capacity(name.len());
+    for c in name.to_lowercase().chars() {
+        match c {
+            'č' | 'ć' => folded.push('c'),
+            'š' => folded.push('s'),
+            'ž' => folded.push('z'),
+            'đ' => folded.push_str("dj"),
+            '.' => {} // d.o.o. -> doo
+            c if c.is_alphanumeric() => folded.push(c),
+            _ => folded.push(' '),
+        }
+    }
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Registered parties whose (normalized) name appears as whole words in the text
+fn find_party_mentions<'a>(text: &str, parties: &'a [(i64, String)]) -> Vec<&'a (i64, String)> {
+    let haystack = format!(" {} ", normalize_party_name(text));
+    parties
+        .iter()
+        .filter(|(_, normalized)| {
+            normalized.len() >= MIN_PARTY_NAME_CHARS && haystack.contains(&format!(" {} ", normalized))
+        })
+        .collect()
+}
+
+/// The user's team and whether they administer it (the 'team' account that owns the plan)
+async fn user_team(user_id: Uuid, pool: &PgPool) -> Result<Option<(Uuid, bool)>, StatusCode> {
+    let team = sqlx::query_as::<_, (Option<Uuid>, String)>(
+        "SELECT team_id, account_type FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to load user team: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(match team {
+        Some((Some(team_id), account_type)) => Some((team_id, account_type == "team")),
+        _ => None,
+    })
+}
+
+async fn authenticate_team_member(
+    headers: &HeaderMap,
+    jwt_secret: &str,
+    supabase_jwt_secret: Option<&str>,
+    pool: &PgPool,
+) -> Result<(Uuid, Uuid, bool), StatusCode> {
+    let user_id = verify_user_from_headers_async(headers, jwt_secret, supabase_jwt_secret, pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let (team_id, is_admin) = user_team(user_id, pool).await?.ok_or(StatusCode::FORBIDDEN)?;
+    Ok((user_id, team_id, is_admin))
+}
+
+/// List the team's registered clients and adverse parties
+pub async fn list_parties_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ResponseJson<Vec<ConflictParty>>, StatusCode> {
+    let (_, team_id, _) = authenticate_team_member(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let parties = sqlx::query_as::<_, ConflictParty>(
+        "SELECT id, name, role, matter, created_by, created_at
+         FROM conflict_parties WHERE team_id = $1 ORDER BY name"
+    )
+    .bind(team_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to list conflict parties: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(ResponseJson(parties))
+}
+
+/// Register a client or adverse party for the team
+pub async fn create_party_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CreateConflictPartyRequest>,
+) -> Result<(StatusCode, ResponseJson<ConflictParty>), StatusCode> {
+    let (user_id, team_id, _) = authenticate_team_member(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let name = request.name.trim();
+    let normalized = normalize_party_name(name);
+    if normalized.len() < MIN_PARTY_NAME_CHARS || name.len() > 300 || !["client", "adverse"].contains(&request.role.as_str()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let party = sqlx::query_as::<_, ConflictParty>(
+        "INSERT INTO conflict_parties (team_id, name, normalized_name, role, matter, created_by)
+         VALUES ($1, $2, $3, $4, NULLIF($5, ''), $6)
+         RETURNING id, name, role, matter, created_by, created_at"
+    )
+    .bind(team_id)
+    .bind(name)
+    .bind(&normalized)
+    .bind(&request.role)
+    .bind(request.matter.as_deref().map(str::trim))
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to create conflict party: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok((StatusCode::CREATED, ResponseJson(party)))
+}
+
+/// Remove a registered party (team admin only)
+pub async fn delete_party_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(party_id): Path<i64>,
+) -> Result<StatusCode, StatusCode> {
+    let (_, team_id, is_admin) = authenticate_team_member(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+    if !is_admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let result = sqlx::query("DELETE FROM conflict_parties WHERE id = $1 AND team_id = $2")
+        .bind(party_id)
+        .bind(team_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to delete conflict party: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Potential conflicts raised in the team's chats (team admin only)
+pub async fn list_alerts_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ResponseJson<Vec<ConflictAlert>>, StatusCode> {
+    let (_, team_id, is_admin) = authenticate_team_member(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+    if !is_admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let alerts = sqlx::query_as::<_, ConflictAlert>(
+        "SELECT a.id, a.chat_id, a.party_id, p.name AS party_name, p.matter, a.source, a.raised_by,
+                a.status, a.created_at, a.resolved_at
+         FROM conflict_alerts a
+         JOIN conflict_parties p ON p.id = a.party_id
+         WHERE a.team_id = $1
+         ORDER BY (a.status = 'open') DESC, a.created_at DESC
+         LIMIT 200"
+    )
+    .bind(team_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to list conflict alerts: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(ResponseJson(alerts))
+}
+
+/// Team admin decision on a potential conflict
+pub async fn resolve_alert_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(alert_id): Path<i64>,
+    Json(request): Json<ResolveConflictAlertRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let (user_id, team_id, is_admin) = authenticate_team_member(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+    if !is_admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if !["cleared", "confirmed"].contains(&request.status.as_str()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let result = sqlx::query(
+        "UPDATE conflict_alerts SET status = $3, resolved_at = NOW(), resolved_by = $4
+         WHERE id = $1 AND team_id = $2"
+    )
+    .bind(alert_id)
+    .bind(team_id)
+    .bind(&request.status)
+    .bind(user_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to resolve conflict alert: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// Check a chat's contract or uploaded document for the team's adverse parties.
+/// New matches are recorded as open alerts and pushed to the team admins; the returned
+/// warnings are shown to the user before they continue with the matter.
+pub async fn check_chat_parties(
+    user_id: Uuid,
+    chat_id: i64,
+    text: &str,
+    source: &str,
+    pool: &PgPool,
+) -> Result<Vec<ResponseWarning>, String> {
+    let Some((team_id, _)) = user_team(user_id, pool).await.map_err(|_| "Failed to load user team".to_string())? else {
+        return Ok(Vec::new()); // Conflict checks are a team feature
+    };
+
+    let adverse: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT id, normalized_name FROM conflict_parties WHERE team_id = $1 AND role = 'adverse'"
+    )
+    .bind(team_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load adverse parties: {}", e))?;
+
+    let mentions = find_party_mentions(text, &adverse);
+    if mentions.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut warnings = Vec::new();
+    for (party_id, _) in mentions {
+        let (party_name, matter): (String, Option<String>) = sqlx::query_as(
+            "SELECT name, matter FROM conflict_parties WHERE id = $1"
+        )
+        .bind(party_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to load conflict party: {}", e))?;
+
+        // One alert per chat and party - re-asking in the same chat doesn't notify again
+        let alert_id: Option<i64> = sqlx::query_scalar(
+            "INSERT INTO conflict_alerts (team_id, chat_id, party_id, source, raised_by)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (chat_id, party_id) DO NOTHING
+             RETURNING id"
+        )
+        .bind(team_id)
+        .bind(chat_id)
+        .bind(party_id)
+        .bind(source)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to record conflict alert: {}", e))?;
+
+        if let Some(alert_id) = alert_id {
+            println!("⚠️  CONFLICT CHECK: '{}' matched in chat {} (alert {})", party_name, chat_id, alert_id);
+            notify_team_admins(team_id, alert_id, chat_id, &party_name, pool).await;
+        }
+
+        let matter_note = matter.map(|m| format!(" (predmet {})", m)).unwrap_or_default();
+        warnings.push(ResponseWarning::new(
+            "conflict_of_interest",
+            format!(
+                "Moguć sukob interesa: \"{}\" je registrovan kao suprotna strana{}. Administrator tima je obavešten - sačekajte proveru pre nastavka rada na predmetu.",
+                party_name, matter_note
+            ),
+        ));
+    }
+
+    Ok(warnings)
+}
+
+async fn notify_team_admins(team_id: Uuid, alert_id: i64, chat_id: i64, party_name: &str, pool: &PgPool) {
+    let admins: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT id FROM users WHERE team_id = $1 AND account_type = 'team' AND account_status = 'active'"
+    )
+    .bind(team_id)
+    .fetch_all(pool)
+    .await
+    .unwrap_or_else(|e| {
+        eprintln!("⚠️  Failed to load team admins: {}", e);
+        Vec::new()
+    });
+
+    for admin_id in admins {
+        crate::events::emit(admin_id, crate::events::ChatEvent::ConflictFlagged {
+            alert_id,
+            chat_id,
+            party_name: party_name.to_string(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_party_mentions() {
+        let parties = vec![
+            (1, normalize_party_name("Petrović d.o.o.")),
+            (2, normalize_party_name("Marko Đorđević")),
+            (3, normalize_party_name("Ana")),
+        ];
+
+        let text = "UGOVOR između PETROVIC DOO, Beograd i Marka Jovanovića";
+        let matched: Vec<i64> = find_party_mentions(text, &parties).iter().map(|(id, _)| *id).collect();
+        assert_eq!(matched, vec![1]);
+
+        let text = "Zakupac: marko djordjević, JMBG ...";
+        let matched: Vec<i64> = find_party_mentions(text, &parties).iter().map(|(id, _)| *id).collect();
+        assert_eq!(matched, vec![2]);
+
+        // Whole words only
+        assert!(find_party_mentions("Petrovićeva ulica 5", &parties).is_empty());
+    }
+}
@@ -0,0 +1,202 @@
+// Consent to the terms of use and legal disclaimer. Every signed-in user has to accept the current
+// version before getting answers: require_consent sits in front of the account routes that produce
+// one (questions, regenerated and edited answers, contract review, clause comparison) and answers 403
+// CONSENT_REQUIRED until POST /api/consent/accept records the version. The apps show the terms on
+// that error and retry once they are accepted. Anonymous answers are exempt: /api/question/free and
+// /api/guest/question have no account to record an acceptance against, and anonymous requests on the
+// gated routes pass through to the handler. Publishing new terms means
+// bumping TERMS_VERSION - everyone is asked again. Acceptances are kept with their time, IP and
+// user agent, as evidence of what the user agreed to.
+use crate::models::{ApiError, ErrorResponse};
+use crate::simple_auth::AuthAppState;
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// The document accepted - terms of use with the legal disclaimer
+const TERMS_DOCUMENT: &str = "terms";
+
+fn database_error(e: sqlx::Error) -> ApiError {
+    eprintln!("Consent database error: {}", e);
+    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Greška baze podataka")
+}
+
+/// Version of the terms currently in force (TERMS_VERSION)
+pub fn current_terms_version() -> String {
+    crate::config::get().terms_version.clone()
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConsentStatusResponse {
+    pub document: &'static str,
+    pub current_version: String,
+    pub accepted: bool, // The current version is accepted
+    pub accepted_version: Option<String>, // Latest version accepted, possibly an older one
+    pub accepted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AcceptConsentRequest {
+    pub version: String, // The version the user was shown
+}
+
+async fn consent_status(user_id: Uuid, pool: &PgPool) -> Result<ConsentStatusResponse, sqlx::Error> {
+    let latest: Option<(String, DateTime<Utc>)> = sqlx::query_as(
+        "SELECT version, accepted_at FROM user_consents WHERE user_id = $1 AND document = $2
+         ORDER BY accepted_at DESC LIMIT 1",
+    )
+    .bind(user_id)
+    .bind(TERMS_DOCUMENT)
+    .fetch_optional(pool)
+    .await?;
+
+    let current_version = current_terms_version();
+    let (accepted_version, accepted_at) = latest.unzip();
+    Ok(ConsentStatusResponse {
+        document: TERMS_DOCUMENT,
+        accepted: accepts_current(accepted_version.as_deref(), &current_version),
+        current_version,
+        accepted_version,
+        accepted_at,
+    })
+}
+
+fn accepts_current(accepted_version: Option<&str>, current_version: &str) -> bool {
+    accepted_version == Some(current_version)
+}
+
+/// Accepting a version the user wasn't shown proves nothing - the app reloads the terms
+fn check_offered_version(offered: &str, current_version: &str) -> Result<(), ApiError> {
+    if offered.trim() != current_version {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "CONSENT_VERSION_OUTDATED",
+            "Uslovi korišćenja su izmenjeni - pročitajte novu verziju",
+        ));
+    }
+    Ok(())
+}
+
+fn consent_required(current_version: &str) -> ApiError {
+    ApiError {
+        status: StatusCode::FORBIDDEN,
+        body: Some(ErrorResponse {
+            error: "CONSENT_REQUIRED".to_string(),
+            message: "Pre postavljanja pitanja potrebno je da prihvatite uslove korišćenja".to_string(),
+            details: Some(serde_json::json!({
+                "document": TERMS_DOCUMENT,
+                "version": current_version,
+            })),
+        }),
+    }
+}
+
+async fn has_accepted_current(user_id: Uuid, pool: &PgPool) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM user_consents WHERE user_id = $1 AND document = $2 AND version = $3)")
+        .bind(user_id)
+        .bind(TERMS_DOCUMENT)
+        .bind(current_terms_version())
+        .fetch_one(pool)
+        .await
+}
+
+/// GET /api/consent/status
+pub async fn consent_status_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
+    headers: HeaderMap,
+) -> Result<Json<ConsentStatusResponse>, ApiError> {
+    let user_id = crate::database::authenticate(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+    Ok(Json(consent_status(user_id, &pool).await.map_err(database_error)?))
+}
+
+/// POST /api/consent/accept - record acceptance of the current version
+pub async fn accept_consent_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
+    headers: HeaderMap,
+    Json(request): Json<AcceptConsentRequest>,
+) -> Result<Json<ConsentStatusResponse>, ApiError> {
+    let user_id = crate::database::authenticate(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    check_offered_version(&request.version, &current_terms_version())?;
+
+    sqlx::query(
+        "INSERT INTO user_consents (user_id, document, version, ip_address, user_agent)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (user_id, document, version) DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(TERMS_DOCUMENT)
+    .bind(request.version.trim())
+    .bind(crate::api::extract_client_ip(&headers))
+    .bind(headers.get("User-Agent").and_then(|h| h.to_str().ok()))
+    .execute(&pool)
+    .await
+    .map_err(database_error)?;
+
+    Ok(Json(consent_status(user_id, &pool).await.map_err(database_error)?))
+}
+
+/// The caller as require_consent verified them (None = anonymous). Gated handlers take it from the
+/// request extensions instead of verifying the token - and touching the session - a second time.
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedUser(pub Option<Uuid>);
+
+/// Middleware for answer-producing routes: signed-in users who haven't accepted the current terms are
+/// refused. Anonymous requests pass - the handler decides what they may do.
+pub async fn require_consent(State(pool): State<PgPool>, mut request: Request, next: Next) -> Response {
+    let config = crate::config::get();
+    let user_id = crate::database::verify_user_from_headers_async(
+        request.headers(),
+        &config.jwt_secret,
+        config.supabase_jwt_secret.as_deref(),
+        &pool,
+    )
+    .await;
+
+    if let Some(user_id) = user_id {
+        match has_accepted_current(user_id, &pool).await {
+            Ok(true) => {}
+            Ok(false) => return consent_required(&current_terms_version()).into_response(),
+            Err(e) => return database_error(e).into_response(),
+        }
+    }
+    request.extensions_mut().insert(VerifiedUser(user_id));
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_the_current_version_counts() {
+        assert!(accepts_current(Some("2026-03"), "2026-03"));
+        assert!(!accepts_current(Some("2025-11"), "2026-03"));
+        assert!(!accepts_current(None, "2026-03"));
+    }
+
+    #[test]
+    fn test_offered_version_must_be_current() {
+        assert!(check_offered_version(" 2026-03\n", "2026-03").is_ok());
+        let outdated = check_offered_version("2025-11", "2026-03").unwrap_err();
+        assert_eq!(outdated.status, StatusCode::CONFLICT);
+        assert_eq!(outdated.body.unwrap().error, "CONSENT_VERSION_OUTDATED");
+    }
+
+    #[test]
+    fn test_consent_required_names_the_version() {
+        let error = consent_required("2026-03");
+        assert_eq!(error.status, StatusCode::FORBIDDEN);
+        let body = error.body.unwrap();
+        assert_eq!(body.error, "CONSENT_REQUIRED");
+        assert_eq!(body.details.unwrap()["version"], "2026-03");
+    }
+}
@@ -0,0 +1,730 @@
+// Contract review: structured risk analysis of an uploaded contract under Serbian law.
+// Two prompts run in sequence - the first extracts the facts (parties, obligations, penalties),
+// the second judges them (unusual clauses, missing mandatory elements) - and the result is stored
+// in contract_reviews next to the chat it was requested from.
+//
+// Clause comparison checks a single clause (notice period, deposit, overtime...) against the
+// statutory default, quoting the article from law_cache rather than the model's recollection.
+use axum::{
+    extract::{Json, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::database;
+use crate::legal_parser::LawArticle;
+use crate::models::ApiError;
+
+type AppState = (PgPool, String, String, String, Option<String>); // (pool, openrouter_api_key, openai_api_key, jwt_secret, supabase_jwt_secret)
+
+/// Contract text beyond this is cut before prompting - both steps send the full text
+const MAX_REVIEW_CHARS: usize = 120_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "critical" | "kritično" | "kriticno" => Severity::Critical,
+            "high" | "visok" | "visoko" => Severity::High,
+            "low" | "nizak" | "nisko" => Severity::Low,
+            _ => Severity::Medium, // Unknown labels are neither ignored nor escalated
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractParty {
+    pub name: String,
+    #[serde(default)]
+    pub role: Option<String>, // e.g. "zakupodavac", "poslodavac"
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractObligation {
+    #[serde(default)]
+    pub party: Option<String>,
+    pub description: String,
+    #[serde(default)]
+    pub deadline: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractPenalty {
+    pub description: String,
+    #[serde(default)]
+    pub amount: Option<String>, // As written in the contract, e.g. "10% ugovorene cene"
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewFinding {
+    pub category: String, // 'unusual_clause', 'missing_element', 'penalty', 'obligation', 'other'
+    pub severity: Severity,
+    pub title: String,
+    pub description: String,
+    pub clause: Option<String>,         // Quoted or numbered clause the finding refers to
+    pub legal_basis: Option<String>,    // e.g. "Zakon o obligacionim odnosima, Član 270"
+    pub recommendation: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractReview {
+    pub id: i64,
+    pub chat_id: Option<i64>,
+    pub document_id: Option<Uuid>,
+    pub filename: Option<String>,
+    pub contract_type: Option<String>,
+    pub summary: String,
+    pub overall_risk: Severity,
+    pub parties: Vec<ContractParty>,
+    pub obligations: Vec<ContractObligation>,
+    pub penalties: Vec<ContractPenalty>,
+    pub findings: Vec<ReviewFinding>, // Most severe first
+    pub truncated: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContractReviewRequest {
+    pub chat_id: Option<i64>,
+    pub document_id: Option<Uuid>, // From /api/documents/extract
+    pub document_content: Option<String>, // Raw text, used when no document_id is sent
+    pub document_filename: Option<String>,
+    pub client_request_id: Option<String>,
+}
+
+// Step 1 output
+#[derive(Debug, Default, Deserialize)]
+struct ExtractedFacts {
+    #[serde(default)]
+    contract_type: Option<String>,
+    #[serde(default)]
+    parties: Vec<ContractParty>,
+    #[serde(default)]
+    obligations: Vec<ContractObligation>,
+    #[serde(default)]
+    penalties: Vec<ContractPenalty>,
+}
+
+// Step 2 output - severities are free text until normalized
+#[derive(Debug, Default, Deserialize)]
+struct RiskAnalysis {
+    #[serde(default)]
+    summary: String,
+    #[serde(default)]
+    findings: Vec<RawFinding>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawFinding {
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    severity: Option<String>,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    clause: Option<String>,
+    #[serde(default)]
+    legal_basis: Option<String>,
+    #[serde(default)]
+    recommendation: Option<String>,
+}
+
+const FINDING_CATEGORIES: &[&str] = &["unusual_clause", "missing_element", "penalty", "obligation", "other"];
+
+fn extraction_prompt(contract: &str) -> String {
+    format!(
+        r#"Ti si pravnik specijalizovan za ugovorno pravo Republike Srbije. Pročitaj ugovor i izvuci činjenice, bez ocenjivanja.
+
+UGOVOR:
+"""
+{}
+"""
+
+Vrati ISKLJUČIVO JSON objekat u ovom obliku:
+{{
+  "contract_type": "vrsta ugovora, npr. Ugovor o zakupu stana",
+  "parties": [{{"name": "naziv ugovorne strane", "role": "uloga u ugovoru"}}],
+  "obligations": [{{"party": "strana", "description": "obaveza", "deadline": "rok ili null"}}],
+  "penalties": [{{"description": "ugovorna kazna, penal ili posledica kršenja", "amount": "iznos kako je naveden ili null"}}]
+}}"#,
+        contract
+    )
+}
+
+fn risk_prompt(contract: &str, facts: &str) -> String {
+    format!(
+        r#"Ti si pravnik specijalizovan za ugovorno pravo Republike Srbije. Analiziraj rizike ugovora za klijenta.
+
+UGOVOR:
+"""
+{}
+"""
+
+IZVUČENE ČINJENICE:
+{}
+
+Proveri:
+1. Neuobičajene ili nepravične odredbe (jednostrane izmene, nesrazmerne kazne, odricanje od prava, nadležnost)
+2. Obavezne elemente koji nedostaju prema propisima Republike Srbije za ovu vrstu ugovora
+3. Ugovorne kazne i obaveze koje su rizične za klijenta
+4. Odredbe koje su ništave ili u suprotnosti sa prinudnim propisima
+
+Za svaki nalaz navedi ozbiljnost: low, medium, high ili critical.
+Kategorija je jedna od: unusual_clause, missing_element, penalty, obligation, other.
+Pravni osnov navodi u obliku "Naziv zakona, Član X" samo kada si siguran.
+
+Vrati ISKLJUČIVO JSON objekat u ovom obliku:
+{{
+  "summary": "kratak zaključak na srpskom jeziku (latinica)",
+  "findings": [{{"category": "...", "severity": "...", "title": "...", "description": "...", "clause": "odredba ili null", "legal_basis": "pravni osnov ili null", "recommendation": "preporuka ili null"}}]
+}}"#,
+        contract, facts
+    )
+}
+
+/// The JSON object in an LLM reply, without markdown fences or surrounding prose
+fn extract_json_object(raw: &str) -> Option<&str> {
+    let start = raw.find('{')?;
+    let end = raw.rfind('}')?;
+    (start < end).then(|| &raw[start..=end])
+}
+
+fn parse_reply<T: serde::de::DeserializeOwned>(raw: &str, step: &str) -> Result<T, StatusCode> {
+    extract_json_object(raw)
+        .and_then(|json| serde_json::from_str(json).ok())
+        .ok_or_else(|| {
+            eprintln!("❌ Contract review: unparseable {} reply: {}", step, raw.chars().take(500).collect::<String>());
+            StatusCode::BAD_GATEWAY
+        })
+}
+
+async fn check_message_quota(user_id: Uuid, endpoint: &'static str, pool: &PgPool) -> Result<(), ApiError> {
+    match database::can_send_message(Some(user_id), pool).await {
+        Ok(allowance) => allowance.into_result(endpoint),
+        Err(e) => {
+            eprintln!("Failed to check message limits: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into())
+        }
+    }
+}
+
+// Count a finished analysis against the trial (premium is unlimited)
+async fn charge_message(user_id: Uuid, account_type: &str, pool: &PgPool) {
+    if account_type != "premium" {
+        if let Err(e) = database::decrement_trial_message(Some(user_id), None, pool).await {
+            eprintln!("⚠️  CRITICAL: Failed to decrement trial messages for user_id={}: {}", user_id, e);
+        }
+    }
+}
+
+/// Normalize categories and severities, drop empty findings, most severe first
+fn normalize_findings(raw: Vec<RawFinding>) -> Vec<ReviewFinding> {
+    let non_empty = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty() && v != "null");
+
+    let mut findings: Vec<ReviewFinding> = raw
+        .into_iter()
+        .filter(|f| !f.title.trim().is_empty() || !f.description.trim().is_empty())
+        .map(|f| {
+            let category = f.category.as_deref().map(|c| c.trim().to_lowercase()).unwrap_or_default();
+            ReviewFinding {
+                category: if FINDING_CATEGORIES.contains(&category.as_str()) { category } else { "other".to_string() },
+                severity: Severity::parse(f.severity.as_deref().unwrap_or_default()),
+                title: f.title.trim().to_string(),
+                description: f.description.trim().to_string(),
+                clause: non_empty(f.clause),
+                legal_basis: non_empty(f.legal_basis),
+                recommendation: non_empty(f.recommendation),
+            }
+        })
+        .collect();
+
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+    findings
+}
+
+/// POST /api/contracts/review - analyze a contract and store the review with the chat
+pub async fn review_contract_handler(
+    State((pool, openrouter_api_key, _, _, _)): State<AppState>,
+    axum::Extension(crate::consent::VerifiedUser(user_id)): axum::Extension<crate::consent::VerifiedUser>,
+    Json(request): Json<ContractReviewRequest>,
+) -> Result<ResponseJson<ContractReview>, ApiError> {
+    println!("📑 ================== CONTRACT REVIEW REQUEST ==================");
+
+    let user_id = user_id.ok_or(StatusCode::UNAUTHORIZED)?;
+
+    // Same plans that can attach documents to questions
+    let user = database::get_user(Some(user_id), &pool).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    if !user.can_upload_documents() {
+        eprintln!("❌ SECURITY: User with account_type '{}' attempted contract review - BLOCKED", user.account_type);
+        return Err(StatusCode::FORBIDDEN.into());
+    }
+
+    check_chat_owner(request.chat_id, user_id, &pool).await?;
+
+    let (filename, text, mut truncated) = match request.document_id {
+        Some(document_id) => {
+            let (filename, text, truncated) = crate::documents::get_document_text(document_id, user_id, &pool).await
+                .map_err(|e| {
+                    eprintln!("{}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?
+                .ok_or(StatusCode::NOT_FOUND)?;
+            (Some(filename), text, truncated)
+        }
+        None => {
+            let text = request.document_content.clone().ok_or(StatusCode::BAD_REQUEST)?;
+            (request.document_filename.clone(), text, false)
+        }
+    };
+
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    let text: String = if text.chars().count() > MAX_REVIEW_CHARS {
+        truncated = true;
+        text.chars().take(MAX_REVIEW_CHARS).collect()
+    } else {
+        text.to_string()
+    };
+    // The contract is pasted into both prompts
+    let text = crate::moderation::screen_document(&text).into_owned();
+
+    // A review costs two answer-model calls - count it as a message
+    check_message_quota(user_id, "contract_review", &pool).await?;
+
+    let queue_slot = crate::llm_queue::global().acquire(request.client_request_id.clone()).await;
+    if let Err(e) = crate::llm_queue::record_queue_metrics(&queue_slot, Some(user_id), &pool).await {
+        eprintln!("⚠️  {}", e);
+    }
+
+    let complete = |prompt: String, purpose: &'static str| {
+        crate::api::complete_prompt(
+            &openrouter_api_key,
+            prompt,
+            purpose,
+            user_id,
+            request.chat_id,
+            Some(user.account_type.as_str()),
+            &pool,
+        )
+    };
+    let llm_error = |e: String| {
+        eprintln!("❌ Contract review LLM call failed: {}", e);
+        StatusCode::BAD_GATEWAY
+    };
+
+    println!("🔍 Contract review step 1/2: extracting parties, obligations and penalties ({} chars)", text.len());
+    let facts_reply = complete(extraction_prompt(&text), "contract_review_extract").await.map_err(llm_error)?;
+    let facts: ExtractedFacts = parse_reply(&facts_reply, "extraction")?;
+
+    println!("🔍 Contract review step 2/2: risk analysis");
+    let facts_json = extract_json_object(&facts_reply).unwrap_or("{}");
+    let risk_reply = complete(risk_prompt(&text, facts_json), "contract_review_risk").await.map_err(llm_error)?;
+    let analysis: RiskAnalysis = parse_reply(&risk_reply, "risk analysis")?;
+
+    drop(queue_slot);
+
+    let findings = normalize_findings(analysis.findings);
+    let overall_risk = findings.first().map(|f| f.severity).unwrap_or(Severity::Low);
+
+    let mut review = ContractReview {
+        id: 0,
+        chat_id: request.chat_id,
+        document_id: request.document_id,
+        filename,
+        contract_type: facts.contract_type.filter(|t| !t.trim().is_empty()),
+        summary: analysis.summary.trim().to_string(),
+        overall_risk,
+        parties: facts.parties,
+        obligations: facts.obligations,
+        penalties: facts.penalties,
+        findings,
+        truncated,
+        created_at: chrono::Utc::now(),
+    };
+
+    let (id, created_at): (i64, chrono::DateTime<chrono::Utc>) = sqlx::query_as(
+        "INSERT INTO contract_reviews (user_id, chat_id, document_id, filename, overall_risk, review)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING id, created_at"
+    )
+    .bind(user_id)
+    .bind(review.chat_id)
+    .bind(review.document_id)
+    .bind(&review.filename)
+    .bind(review.overall_risk.as_str())
+    .bind(sqlx::types::Json(&review))
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to store contract review: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    review.id = id;
+    review.created_at = created_at;
+
+    charge_message(user_id, &user.account_type, &pool).await;
+
+    println!(
+        "✅ Contract review {} stored: {} findings, overall risk {:?}",
+        review.id,
+        review.findings.len(),
+        review.overall_risk
+    );
+
+    Ok(ResponseJson(review))
+}
+
+/// GET /api/chats/:chat_id/contract-reviews - reviews stored with a chat, newest first
+pub async fn list_chat_reviews_handler(
+    State((pool, _, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(chat_id): Path<i64>,
+) -> Result<ResponseJson<Vec<ContractReview>>, StatusCode> {
+    let user_id = database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let rows = sqlx::query_as::<_, (i64, chrono::DateTime<chrono::Utc>, sqlx::types::Json<ContractReview>)>(
+        "SELECT id, created_at, review FROM contract_reviews
+         WHERE chat_id = $1 AND user_id = $2
+         ORDER BY created_at DESC"
+    )
+    .bind(chat_id)
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to list contract reviews: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let reviews = rows
+        .into_iter()
+        .map(|(id, created_at, sqlx::types::Json(mut review))| {
+            review.id = id;
+            review.created_at = created_at;
+            review
+        })
+        .collect();
+
+    Ok(ResponseJson(reviews))
+}
+
+/// Candidate articles offered to the model when the client doesn't name one
+const MAX_COMPARE_CANDIDATES: usize = 3;
+
+/// How a contract clause relates to the statutory default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComparisonOutcome {
+    MoreFavorable, // Gives the protected party more than the law requires
+    Equivalent,
+    LessFavorable, // Below the statutory minimum - usually void and replaced by the law
+    Unclear,
+}
+
+impl ComparisonOutcome {
+    fn parse(value: &str) -> Self {
+        match value.trim().to_lowercase().replace(['-', ' '], "_").as_str() {
+            "more_favorable" | "povoljnije" => ComparisonOutcome::MoreFavorable,
+            "equivalent" | "equal" | "isto" => ComparisonOutcome::Equivalent,
+            "less_favorable" | "nepovoljnije" => ComparisonOutcome::LessFavorable,
+            _ => ComparisonOutcome::Unclear,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClauseComparisonRequest {
+    pub clause: String,                 // Contract clause text, e.g. the notice period article
+    pub law_id: i32,                    // Registry law the clause is governed by (see /api/laws)
+    pub article_number: Option<String>, // Statutory article, picked from the law when omitted
+    pub perspective: Option<String>,    // Protected party, e.g. "zaposleni", "zakupac"
+    pub chat_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClauseComparison {
+    pub outcome: ComparisonOutcome,
+    pub law_name: String,
+    pub law_url: String,
+    pub article_number: String,
+    pub article_text: String,           // Quoted from law_cache
+    pub statutory_requirement: String,  // What the law prescribes, in one sentence
+    pub contract_term: String,          // What the clause provides
+    pub explanation: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawComparison {
+    #[serde(default)]
+    article_number: Option<String>,
+    #[serde(default)]
+    outcome: String,
+    #[serde(default)]
+    statutory_requirement: String,
+    #[serde(default)]
+    contract_term: String,
+    #[serde(default)]
+    explanation: String,
+}
+
+fn comparison_prompt(clause: &str, law_name: &str, articles: &[(String, String)], perspective: Option<&str>) -> String {
+    let articles_text = articles
+        .iter()
+        .map(|(number, body)| format!("Član {}\n{}", number, body))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    format!(
+        r#"Ti si pravnik specijalizovan za pravo Republike Srbije. Uporedi ugovornu odredbu sa zakonskim rešenjem.
+
+ODREDBA UGOVORA:
+"""
+{}
+"""
+
+ZAKON: {}
+{}
+
+Zaštićena strana: {}
+
+Utvrdi koji od navedenih članova uređuje isto pitanje i da li je odredba za zaštićenu stranu:
+- more_favorable: povoljnija od zakonskog minimuma
+- equivalent: ista kao zakonsko rešenje
+- less_favorable: nepovoljnija od zakonskog minimuma
+- unclear: navedeni članovi ne uređuju ovo pitanje ili se ne može utvrditi
+
+Vrati ISKLJUČIVO JSON objekat u ovom obliku:
+{{
+  "article_number": "broj člana iz liste",
+  "outcome": "more_favorable | equivalent | less_favorable | unclear",
+  "statutory_requirement": "šta zakon propisuje, jednom rečenicom",
+  "contract_term": "šta predviđa odredba, jednom rečenicom",
+  "explanation": "kratko obrazloženje na srpskom jeziku (latinica)"
+}}"#,
+        clause,
+        law_name,
+        articles_text,
+        perspective.unwrap_or("slabija ugovorna strana (zaposleni, zakupac, potrošač)")
+    )
+}
+
+/// Articles of the law most likely to govern the clause (keyword overlap), best first
+fn candidate_articles(articles: Vec<LawArticle>, clause: &str) -> Vec<(String, String)> {
+    let keywords = crate::api::extract_question_keywords(clause);
+    let mut scored: Vec<(usize, String, String)> = articles
+        .into_iter()
+        .map(|article| (crate::api::score_article(&article.body, &keywords), article.number, article.body))
+        .filter(|(score, _, _)| *score > 0)
+        .collect();
+    scored.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+
+    scored
+        .into_iter()
+        .take(MAX_COMPARE_CANDIDATES)
+        .map(|(_, number, body)| (number, body))
+        .collect()
+}
+
+fn clean_article_number(number: &str) -> String {
+    number.trim().trim_start_matches("Član").trim_start_matches("član").replace('.', "").trim().to_string()
+}
+
+/// The chat a review or comparison is attached to must be one of the user's own (404 otherwise)
+async fn check_chat_owner(chat_id: Option<i64>, user_id: Uuid, pool: &PgPool) -> Result<(), StatusCode> {
+    let Some(chat_id) = chat_id else {
+        return Ok(());
+    };
+
+    let owns_chat: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM chats WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL)"
+    )
+    .bind(chat_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to check chat ownership: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if owns_chat { Ok(()) } else { Err(StatusCode::NOT_FOUND) }
+}
+
+/// POST /api/contracts/compare - is a clause more or less favorable than the statutory default?
+pub async fn compare_clause_handler(
+    State((pool, openrouter_api_key, _, _, _)): State<AppState>,
+    axum::Extension(crate::consent::VerifiedUser(user_id)): axum::Extension<crate::consent::VerifiedUser>,
+    Json(request): Json<ClauseComparisonRequest>,
+) -> Result<ResponseJson<ClauseComparison>, ApiError> {
+    let user_id = user_id.ok_or(StatusCode::UNAUTHORIZED)?;
+    let user = database::get_user(Some(user_id), &pool).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let clause = request.clause.trim();
+    if clause.is_empty() || clause.chars().count() > 10_000 {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+
+    let law = crate::laws::find_law_by_id(request.law_id).ok_or(StatusCode::NOT_FOUND)?;
+    check_chat_owner(request.chat_id, user_id, &pool).await?;
+    check_message_quota(user_id, "clause_compare", &pool).await?;
+
+    let law_content = crate::api::get_law_content(&law.name, &law.url, &pool).await.map_err(|e| {
+        eprintln!("❌ Clause comparison: failed to load '{}': {}", law.name, e);
+        StatusCode::BAD_GATEWAY
+    })?;
+
+    // get_law_content cached (and indexed) the law; parse the text only if that produced no articles
+    let articles = match database::load_law_articles(&law.name, &pool).await {
+        Ok(Some(articles)) => articles,
+        Ok(None) => crate::legal_parser::parse_law(&law_content.content),
+        Err(e) => {
+            eprintln!("⚠️  Clause comparison: {}", e);
+            crate::legal_parser::parse_law(&law_content.content)
+        }
+    };
+
+    let candidates = match request.article_number.as_deref() {
+        Some(number) => {
+            let number = clean_article_number(number);
+            articles
+                .into_iter()
+                .filter(|article| article.number == number)
+                .take(1)
+                .map(|article| (article.number, article.body))
+                .collect::<Vec<_>>()
+        }
+        None => candidate_articles(articles, clause),
+    };
+    if candidates.is_empty() {
+        // Named article doesn't exist, or nothing in the law resembles the clause
+        return Err(StatusCode::NOT_FOUND.into());
+    }
+
+    // Both are pasted into the prompt
+    let clause = crate::moderation::screen_document(clause);
+    let perspective = request.perspective.as_deref().map(crate::moderation::screen_document);
+    let prompt = comparison_prompt(&clause, &law.name, &candidates, perspective.as_deref());
+    let reply = crate::api::complete_prompt(
+        &openrouter_api_key,
+        prompt,
+        "clause_compare",
+        user_id,
+        request.chat_id,
+        Some(user.account_type.as_str()),
+        &pool,
+    )
+    .await
+    .map_err(|e| {
+        eprintln!("❌ Clause comparison LLM call failed: {}", e);
+        StatusCode::BAD_GATEWAY
+    })?;
+    let raw: RawComparison = parse_reply(&reply, "clause comparison")?;
+
+    // Only cite an article we actually quoted - anything else makes the verdict unreliable
+    let chosen = raw.article_number.as_deref().map(clean_article_number);
+    let (outcome, (article_number, article_text)) =
+        match candidates.iter().find(|(number, _)| Some(number) == chosen.as_ref()) {
+            Some(article) => (ComparisonOutcome::parse(&raw.outcome), article.clone()),
+            None => (ComparisonOutcome::Unclear, candidates[0].clone()),
+        };
+
+    charge_message(user_id, &user.account_type, &pool).await;
+    println!("⚖️ Clause comparison against {} Član {}: {:?}", law.name, article_number, outcome);
+
+    Ok(ResponseJson(ClauseComparison {
+        outcome,
+        law_name: law.name,
+        law_url: law.url,
+        article_text: format!("**Član {}**\n{}", article_number, article_text),
+        article_number,
+        statutory_requirement: raw.statutory_requirement.trim().to_string(),
+        contract_term: raw.contract_term.trim().to_string(),
+        explanation: raw.explanation.trim().to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_json_object() {
+        let reply = "Evo analize:\n```json\n{\"summary\": \"ok\", \"findings\": []}\n```";
+        assert_eq!(extract_json_object(reply), Some("{\"summary\": \"ok\", \"findings\": []}"));
+        assert_eq!(extract_json_object("nema JSON-a"), None);
+
+        let analysis: RiskAnalysis = parse_reply(reply, "test").unwrap();
+        assert_eq!(analysis.summary, "ok");
+    }
+
+    #[test]
+    fn test_normalize_findings_orders_by_severity() {
+        let finding = |category: &str, severity: &str, title: &str| RawFinding {
+            category: Some(category.to_string()),
+            severity: Some(severity.to_string()),
+            title: title.to_string(),
+            description: String::new(),
+            clause: Some("null".to_string()),
+            legal_basis: None,
+            recommendation: Some(" Izmeniti ".to_string()),
+        };
+
+        let findings = normalize_findings(vec![
+            finding("penalty", "LOW", "Kazna"),
+            finding("missing_element", "critical", "Nema cene"),
+            finding("weird", "unknown", "Nadležnost"),
+            finding("other", "high", ""),
+        ]);
+
+        assert_eq!(findings.len(), 3); // Empty finding dropped
+        assert_eq!(findings[0].severity, Severity::Critical);
+        assert_eq!(findings[1].severity, Severity::Medium);
+        assert_eq!(findings[1].category, "other");
+        assert_eq!(findings[2].severity, Severity::Low);
+        assert_eq!(findings[0].clause, None);
+        assert_eq!(findings[0].recommendation.as_deref(), Some("Izmeniti"));
+    }
+
+    #[test]
+    fn test_comparison_outcome_and_candidates() {
+        assert_eq!(ComparisonOutcome::parse("Less-Favorable"), ComparisonOutcome::LessFavorable);
+        assert_eq!(ComparisonOutcome::parse("more favorable"), ComparisonOutcome::MoreFavorable);
+        assert_eq!(ComparisonOutcome::parse("n/a"), ComparisonOutcome::Unclear);
+        assert_eq!(clean_article_number("Član 189."), "189");
+
+        let law = "Član 1\nOvim zakonom uređuju se prava zaposlenih.\n\nČlan 189\nOtkazni rok ne može biti kraći od osam dana ni duži od 30 dana.\n\nČlan 190\nZarada se isplaćuje mesečno.";
+        let candidates = candidate_articles(crate::legal_parser::parse_law(law), "Otkazni rok iznosi tri dana od dana otkaza.");
+        assert_eq!(candidates.first().map(|(number, _)| number.as_str()), Some("189"));
+    }
+}
@@ -1,18 +1,31 @@
+use crate::database::verify_user_from_headers_async;
 use crate::models::GeneratedContract;
+use crate::transliteration::{in_script, to_latin, ResponseScript};
 use axum::{
-    extract::Path,
-    http::{header, StatusCode},
-    response::{IntoResponse, Response},
+    extract::{Json, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json as ResponseJson, Response},
 };
 use chrono::Utc;
 use docx_rs::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use sqlx::PgPool;
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::OnceLock;
 use uuid::Uuid;
 
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
 const CONTRACTS_DIR: &str = "/tmp/contracts";
 const CONTRACTS_EXPIRY_HOURS: i64 = 720; // 30 days
 
+/// Longest value accepted for a single template field
+const MAX_FIELD_VALUE_CHARS: usize = 500;
+
 /// Detect if LLM response contains a generated contract
 pub fn detect_contract(llm_response: &str) -> Option<(String, String)> {
     const START_MARKER: &str = "[CONTRACT_START]";
@@ -52,31 +65,43 @@ pub fn detect_contract(llm_response: &str) -> Option<(String, String)> {
 pub fn generate_contract_file(
     contract_content: &str,
     api_base_url: &str,
+    script: ResponseScript,
+) -> Result<GeneratedContract, String> {
+    // Generate unique file ID
+    let file_id = Uuid::new_v4();
+    write_contract_file(file_id, &get_contract_path(file_id), contract_content, api_base_url, script)
+}
+
+/// Write (or overwrite) the DOCX for a contract file ID at `filepath`
+fn write_contract_file(
+    file_id: Uuid,
+    filepath: &std::path::Path,
+    contract_content: &str,
+    api_base_url: &str,
+    script: ResponseScript,
 ) -> Result<GeneratedContract, String> {
     // Ensure contracts directory exists
     fs::create_dir_all(CONTRACTS_DIR)
         .map_err(|e| format!("Failed to create contracts directory: {}", e))?;
 
-    // Generate unique file ID
-    let file_id = Uuid::new_v4();
-
     // Detect contract type from first line
-    let contract_type = detect_contract_type(contract_content);
+    let contract_type = detect_contract_type(&to_latin(contract_content));
 
     // Create filename
     let timestamp = Utc::now().format("%Y-%m-%d");
     let safe_type = contract_type.replace(" ", "_").replace("/", "-");
     let filename = format!("{}_{}.docx", safe_type, timestamp);
 
-    // Write contract to file as Word document
-    let filepath = PathBuf::from(CONTRACTS_DIR).join(format!("{}.docx", file_id));
+    // Type and filename are detected on the Latin text; the document itself uses the user's script
+    let document_content = in_script(contract_content, script);
+    let document_type = in_script(&contract_type, script);
 
     // Create Word document with proper formatting
-    create_word_document(&filepath, contract_content, &contract_type)
+    create_word_document(filepath, &document_content, &document_type)
         .map_err(|e| format!("Failed to create Word document: {}", e))?;
 
     // Generate preview text
-    let preview_text = get_preview_text(contract_content);
+    let preview_text = get_preview_text(&document_content);
 
     // Build download URL
     let download_url = format!("{}/api/contracts/{}", api_base_url, file_id);
@@ -160,7 +185,7 @@ fn parse_markdown_bold(text: &str) -> Vec<(String, bool)> {
 
 /// Create Word document with proper formatting
 fn create_word_document(
-    filepath: &PathBuf,
+    filepath: &std::path::Path,
     content: &str,
     contract_type: &str,
 ) -> Result<(), String> {
@@ -257,7 +282,7 @@ fn create_word_document(
 
     let footer2 = Paragraph::new().add_run(
         Run::new()
-            .add_text(&format!("Datum generisanja: {}", timestamp))
+            .add_text(format!("Datum generisanja: {}", timestamp))
             .italic()
             .size(22), // 11pt
     );
@@ -297,16 +322,39 @@ pub fn get_contract_path(file_id: Uuid) -> PathBuf {
     PathBuf::from(CONTRACTS_DIR).join(format!("{}.docx", file_id))
 }
 
+/// Path of an earlier version, kept when an edit overwrites the contract file
+fn get_contract_version_path(file_id: Uuid, version: i32) -> PathBuf {
+    PathBuf::from(CONTRACTS_DIR).join(format!("{}_v{}.docx", file_id, version))
+}
+
 /// Check if contract file exists
 pub fn contract_exists(file_id: Uuid) -> bool {
     get_contract_path(file_id).exists()
 }
 
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DownloadContractQuery {
+    pub version: Option<i32>, // Earlier version of an edited contract; latest when omitted
+}
+
 /// Download contract endpoint handler
+#[utoipa::path(
+    get,
+    path = "/api/contracts/{file_id}",
+    tag = "contracts",
+    params(("file_id" = Uuid, Path, description = "Contract file id"), DownloadContractQuery),
+    responses(
+        (status = 200, description = "The contract as a DOCX file"),
+        (status = 400, description = "Invalid file id"),
+        (status = 404, description = "Contract or version not found")
+    )
+)]
 pub async fn download_contract_handler(
     Path(file_id): Path<String>,
+    Query(query): Query<DownloadContractQuery>,
 ) -> Result<Response, StatusCode> {
-    println!("📥 Contract download request: {}", file_id);
+    println!("📥 Contract download request: {} (version {:?})", file_id, query.version);
 
     // Parse UUID
     let file_uuid = Uuid::parse_str(&file_id).map_err(|_| {
@@ -314,14 +362,23 @@ pub async fn download_contract_handler(
         StatusCode::BAD_REQUEST
     })?;
 
+    // The latest version always lives at the plain path. Every version of an edited contract has
+    // its own file, so a missing version 1 means the contract was never edited.
+    let filepath = match query.version {
+        Some(version) if get_contract_version_path(file_uuid, version).exists() => {
+            get_contract_version_path(file_uuid, version)
+        }
+        None | Some(1) => get_contract_path(file_uuid),
+        Some(_) => return Err(StatusCode::NOT_FOUND),
+    };
+
     // Check if file exists
-    if !contract_exists(file_uuid) {
+    if !filepath.exists() {
         println!("❌ Contract not found: {}", file_id);
         return Err(StatusCode::NOT_FOUND);
     }
 
     // Read file
-    let filepath = get_contract_path(file_uuid);
     let content = fs::read(&filepath).map_err(|e| {
         println!("❌ Failed to read contract file: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
@@ -347,6 +404,384 @@ pub async fn download_contract_handler(
         .into_response())
 }
 
+/// Editable value in a generated contract: a placeholder the model left ("[Ime zaposlenog]",
+/// "________"), a date or an amount
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ContractField {
+    pub key: String,      // Stable identifier used in the template and in patches, e.g. "datum_1"
+    pub label: String,
+    pub value: String,    // Empty for unfilled placeholders
+    pub original: String, // Text the field replaced - rendered back while the value is empty
+}
+
+fn template_field_patterns() -> &'static [(Regex, &'static str); 4] {
+    static PATTERNS: OnceLock<[(Regex, &'static str); 4]> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            (Regex::new(r"\[([^\[\]\n]{2,60})\]").unwrap(), "placeholder"),
+            (Regex::new(r"_{4,}").unwrap(), "polje"),
+            (Regex::new(r"\b\d{1,2}\.\s?\d{1,2}\.\s?\d{4}\.?").unwrap(), "datum"),
+            (
+                Regex::new(r"\b\d{1,3}(?:\.\d{3})*(?:,\d{2})?\s?(?:dinara|din\.|RSD|EUR|evra)").unwrap(),
+                "iznos",
+            ),
+        ]
+    })
+}
+
+fn field_key_from_label(label: &str) -> String {
+    let key = to_latin(label)
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'č' | 'ć' => 'c',
+            'š' => 's',
+            'ž' => 'z',
+            'đ' => 'd',
+            c if c.is_ascii_alphanumeric() => c,
+            _ => '_',
+        })
+        .collect::<String>();
+    key.split('_').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("_")
+}
+
+/// Turn a contract into a template with {{key}} markers and its editable fields.
+/// Placeholders with the same label share one field, so filling it fills every occurrence.
+pub fn extract_template(content: &str) -> (String, Vec<ContractField>) {
+    let mut template = content.to_string();
+    let mut fields: Vec<ContractField> = Vec::new();
+    let mut counters: HashMap<&str, usize> = HashMap::new();
+
+    for (pattern, kind) in template_field_patterns() {
+        let mut result = String::with_capacity(template.len());
+        let mut last = 0;
+
+        for found in pattern.captures_iter(&template) {
+            let whole = found.get(0).unwrap();
+
+            let (key, label, value) = if *kind == "placeholder" {
+                let label = found[1].trim().to_string();
+                (field_key_from_label(&label), label, String::new())
+            } else {
+                let counter = counters.entry(kind).or_insert(0);
+                *counter += 1;
+                let label = format!("{} {}", capitalize(kind), counter);
+                let value = if *kind == "polje" { String::new() } else { whole.as_str().to_string() };
+                (format!("{}_{}", kind, counter), label, value)
+            };
+            if key.is_empty() {
+                continue;
+            }
+
+            if !fields.iter().any(|f| f.key == key) {
+                fields.push(ContractField { key: key.clone(), label, value, original: whole.as_str().to_string() });
+            }
+            result.push_str(&template[last..whole.start()]);
+            result.push_str(&format!("{{{{{}}}}}", key));
+            last = whole.end();
+        }
+
+        result.push_str(&template[last..]);
+        template = result;
+    }
+
+    (template, fields)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+/// Fill a template with field values (unfilled fields keep their original text)
+pub fn render_template(template: &str, fields: &[ContractField]) -> String {
+    fields.iter().fold(template.to_string(), |text, field| {
+        let value = if field.value.trim().is_empty() { &field.original } else { &field.value };
+        text.replace(&format!("{{{{{}}}}}", field.key), value)
+    })
+}
+
+/// Keep the structured content of a generated contract so it can be edited later
+pub async fn save_contract_template(
+    file_id: Uuid,
+    user_id: Uuid,
+    chat_id: i64,
+    content: &str,
+    script: ResponseScript,
+    pool: &PgPool,
+) -> Result<(), String> {
+    let (template, fields) = extract_template(content);
+
+    let mut tx = pool.begin().await.map_err(|e| format!("Failed to start transaction: {}", e))?;
+    sqlx::query(
+        "INSERT INTO contract_documents (file_id, user_id, chat_id, contract_type, script, template, fields)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)"
+    )
+    .bind(file_id)
+    .bind(user_id)
+    .bind(chat_id)
+    .bind(detect_contract_type(&to_latin(content)))
+    .bind(script.as_str())
+    .bind(&template)
+    .bind(sqlx::types::Json(&fields))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to store contract template: {}", e))?;
+
+    // The generated contract is version 1 in the history
+    sqlx::query("INSERT INTO contract_versions (file_id, version, fields) VALUES ($1, 1, $2)")
+        .bind(file_id)
+        .bind(sqlx::types::Json(&fields))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to record contract version: {}", e))?;
+
+    tx.commit().await.map_err(|e| format!("Failed to commit contract template: {}", e))
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ContractDocumentRow {
+    user_id: Uuid,
+    script: String,
+    template: String,
+    fields: sqlx::types::Json<Vec<ContractField>>,
+    version: i32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ContractFieldsResponse {
+    pub file_id: Uuid,
+    pub contract_type: String,
+    pub version: i32,
+    pub fields: Vec<ContractField>,
+    pub versions: Vec<ContractVersion>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow, ToSchema)]
+pub struct ContractVersion {
+    pub version: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateContractRequest {
+    pub fields: HashMap<String, String>, // key -> new value; an empty value clears the field
+    pub script: Option<ResponseScript>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UpdateContractResponse {
+    pub contract: GeneratedContract,
+    pub version: i32,
+    pub fields: Vec<ContractField>,
+}
+
+/// Apply a field patch, rejecting unknown keys and oversized values
+fn apply_field_patch(fields: &mut [ContractField], patch: &HashMap<String, String>) -> Result<(), String> {
+    for (key, value) in patch {
+        if value.chars().count() > MAX_FIELD_VALUE_CHARS {
+            return Err(format!("Value of '{}' is too long", key));
+        }
+        let field = fields.iter_mut().find(|f| &f.key == key).ok_or_else(|| format!("Unknown field '{}'", key))?;
+        field.value = value.trim().to_string();
+    }
+    Ok(())
+}
+
+async fn owned_contract(
+    file_id: Uuid,
+    headers: &HeaderMap,
+    jwt_secret: &str,
+    supabase_jwt_secret: Option<&str>,
+    pool: &PgPool,
+) -> Result<ContractDocumentRow, StatusCode> {
+    let user_id = verify_user_from_headers_async(headers, jwt_secret, supabase_jwt_secret, pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let contract = sqlx::query_as::<_, ContractDocumentRow>(
+        "SELECT user_id, script, template, fields, version FROM contract_documents WHERE file_id = $1"
+    )
+    .bind(file_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to load contract {}: {}", file_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Don't reveal other users' contracts
+    if contract.user_id != user_id {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(contract)
+}
+
+/// GET /api/contracts/:file_id/fields - editable fields and version history of a contract
+#[utoipa::path(
+    get,
+    path = "/api/contracts/{file_id}/fields",
+    tag = "contracts",
+    params(("file_id" = Uuid, Path, description = "Contract file id")),
+    responses(
+        (status = 200, description = "Editable fields and version history", body = ContractFieldsResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Contract not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn contract_fields_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(file_id): Path<Uuid>,
+) -> Result<ResponseJson<ContractFieldsResponse>, StatusCode> {
+    let contract = owned_contract(file_id, &headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let versions = sqlx::query_as::<_, ContractVersion>(
+        "SELECT version, created_at FROM contract_versions WHERE file_id = $1 ORDER BY version DESC"
+    )
+    .bind(file_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to list contract versions: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(ResponseJson(ContractFieldsResponse {
+        file_id,
+        contract_type: detect_contract_type(&contract.template),
+        version: contract.version,
+        fields: contract.fields.0,
+        versions,
+    }))
+}
+
+/// PUT /api/contracts/:file_id - patch fields and regenerate the DOCX as a new version.
+/// The previous file stays downloadable with ?version=N.
+#[utoipa::path(
+    put,
+    path = "/api/contracts/{file_id}",
+    tag = "contracts",
+    params(("file_id" = Uuid, Path, description = "Contract file id")),
+    request_body = UpdateContractRequest,
+    responses(
+        (status = 200, description = "Contract regenerated as a new version", body = UpdateContractResponse),
+        (status = 400, description = "Unknown field or value too long"),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Contract not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_contract_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(file_id): Path<Uuid>,
+    Json(request): Json<UpdateContractRequest>,
+) -> Result<ResponseJson<UpdateContractResponse>, StatusCode> {
+    owned_contract(file_id, &headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let db_error = |context: &'static str| {
+        move |e: sqlx::Error| {
+            eprintln!("Failed to {} contract {}: {}", context, file_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+
+    let mut tx = pool.begin().await.map_err(db_error("start editing"))?;
+
+    // Row lock serializes concurrent edits; the patch applies to the fields as of the lock
+    let contract = sqlx::query_as::<_, ContractDocumentRow>(
+        "SELECT user_id, script, template, fields, version FROM contract_documents WHERE file_id = $1 FOR UPDATE"
+    )
+    .bind(file_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error("lock"))?;
+
+    let previous_fields = contract.fields.0;
+    let mut fields = previous_fields.clone();
+    apply_field_patch(&mut fields, &request.fields).map_err(|e| {
+        println!("❌ Contract {} edit rejected: {}", file_id, e);
+        StatusCode::BAD_REQUEST
+    })?;
+    let script = request
+        .script
+        .or_else(|| ResponseScript::parse(&contract.script))
+        .unwrap_or_default();
+    let version = contract.version + 1;
+
+    // Contracts generated before version 1 was recorded get their current state as history
+    sqlx::query("INSERT INTO contract_versions (file_id, version, fields) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING")
+        .bind(file_id)
+        .bind(contract.version)
+        .bind(sqlx::types::Json(&previous_fields))
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("record the previous version of"))?;
+
+    sqlx::query("UPDATE contract_documents SET fields = $2, script = $3, version = $4, updated_at = NOW() WHERE file_id = $1")
+        .bind(file_id)
+        .bind(sqlx::types::Json(&fields))
+        .bind(script.as_str())
+        .bind(version)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("update"))?;
+
+    sqlx::query("INSERT INTO contract_versions (file_id, version, fields) VALUES ($1, $2, $3)")
+        .bind(file_id)
+        .bind(version)
+        .bind(sqlx::types::Json(&fields))
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("record the new version of"))?;
+
+    // The new version is written to its own file; the current one is only replaced once the edit
+    // is committed, so a failed edit leaves the contract as it was
+    let version_path = get_contract_version_path(file_id, version);
+    let api_base_url = &crate::config::get().api_base_url;
+    let generated = write_contract_file(file_id, &version_path, &render_template(&contract.template, &fields), api_base_url, script)
+        .map_err(|e| {
+            eprintln!("❌ Failed to regenerate contract {}: {}", file_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if let Err(e) = tx.commit().await {
+        let _ = fs::remove_file(&version_path);
+        return Err(db_error("commit the edit of")(e));
+    }
+
+    if let Err(e) = publish_contract_version(file_id, version) {
+        eprintln!("⚠️  Failed to publish contract {} version {}: {}", file_id, version, e);
+    }
+
+    println!("✏️ Contract {} edited -> version {}", file_id, version);
+
+    Ok(ResponseJson(UpdateContractResponse { contract: generated, version, fields }))
+}
+
+/// Make a committed version the one served at the plain path. The file being replaced is kept as
+/// the previous version (already there unless it is the generated original).
+fn publish_contract_version(file_id: Uuid, version: i32) -> std::io::Result<()> {
+    // A later edit already published (edits commit in order but may finish out of order)
+    if get_contract_version_path(file_id, version + 1).exists() {
+        return Ok(());
+    }
+
+    let current = get_contract_path(file_id);
+    let previous = get_contract_version_path(file_id, version - 1);
+    if current.exists() && !previous.exists() {
+        fs::copy(&current, &previous)?;
+    }
+
+    // Copy next to the target, then rename over it so downloads never see a partial file
+    let staging = PathBuf::from(CONTRACTS_DIR).join(format!("{}.docx.tmp", file_id));
+    fs::copy(get_contract_version_path(file_id, version), &staging)?;
+    fs::rename(&staging, &current)
+}
+
 /// Clean up old contract files (call periodically or on startup)
 pub fn cleanup_old_contracts() -> Result<usize, String> {
     let dir = PathBuf::from(CONTRACTS_DIR);
@@ -375,11 +810,9 @@ pub fn cleanup_old_contracts() -> Result<usize, String> {
                 let created_time = chrono::DateTime::<Utc>::from(created);
                 let age_hours = (now - created_time).num_hours();
 
-                if age_hours >= CONTRACTS_EXPIRY_HOURS {
-                    if fs::remove_file(&path).is_ok() {
-                        deleted_count += 1;
-                        println!("🗑️  Deleted expired contract: {:?}", path);
-                    }
+                if age_hours >= CONTRACTS_EXPIRY_HOURS && fs::remove_file(&path).is_ok() {
+                    deleted_count += 1;
+                    println!("🗑️  Deleted expired contract: {:?}", path);
                 }
             }
         }
@@ -392,6 +825,32 @@ pub fn cleanup_old_contracts() -> Result<usize, String> {
     Ok(deleted_count)
 }
 
+/// Whether a file in CONTRACTS_DIR belongs to the contract: the current file, an earlier version
+/// ({id}_v{N}.docx) or a leftover staging copy
+fn is_contract_file(name: &str, file_id: &str) -> bool {
+    name.strip_prefix(file_id).is_some_and(|rest| {
+        rest == ".docx"
+            || rest == ".docx.tmp"
+            || rest
+                .strip_prefix("_v")
+                .and_then(|version| version.strip_suffix(".docx"))
+                .is_some_and(|version| version.parse::<i32>().is_ok())
+    })
+}
+
+/// Delete all files of a contract (data retention). Returns how many were deleted.
+pub fn delete_contract_files(file_id: Uuid) -> usize {
+    let Ok(entries) = fs::read_dir(CONTRACTS_DIR) else {
+        return 0;
+    };
+    let file_id = file_id.to_string();
+    entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_str().is_some_and(|name| is_contract_file(name, &file_id)))
+        .filter(|entry| fs::remove_file(entry.path()).is_ok())
+        .count()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -404,7 +863,10 @@ mod tests {
         [CONTRACT_START]
         UGOVOR O RADU
 
-        Zaključen između...
+        Zaključen između poslodavca i zaposlenog.
+
+        Član 1. - PREDMET UGOVORA
+        Zaposleni zasniva radni odnos na neodređeno vreme.
         [CONTRACT_END]
 
         Ugovor je spreman.
@@ -424,4 +886,47 @@ mod tests {
         let contract_type = detect_contract_type(content);
         assert_eq!(contract_type, "UGOVOR O RADU NA NEODREĐENO VREME");
     }
+
+    #[test]
+    fn test_detect_contract_type_cyrillic() {
+        let content = "УГОВОР О ЗАКУПУ СТАНА\n\nЗакључен...";
+        assert_eq!(detect_contract_type(&to_latin(content)), "UGOVOR O ZAKUPU STANA");
+    }
+
+    #[test]
+    fn test_contract_template_round_trip() {
+        let content = "UGOVOR O RADU\n\nZaposleni [Ime i prezime] počinje sa radom 01.02.2025. godine.\nOsnovna zarada iznosi 120.000 dinara mesečno.\nPotpis: ________\nZaposleni [Ime i prezime] prihvata uslove.";
+        let (template, fields) = extract_template(content);
+
+        let keys: Vec<&str> = fields.iter().map(|f| f.key.as_str()).collect();
+        assert_eq!(keys, vec!["ime_i_prezime", "polje_1", "datum_1", "iznos_1"]);
+        assert_eq!(fields[2].value, "01.02.2025.");
+        assert!(template.contains("{{iznos_1}} mesečno"));
+        // Untouched template renders back to the original text
+        assert_eq!(render_template(&template, &fields), content);
+
+        let mut fields = fields;
+        let patch = HashMap::from([
+            ("ime_i_prezime".to_string(), "Petar Petrović".to_string()),
+            ("iznos_1".to_string(), "150.000 dinara".to_string()),
+        ]);
+        apply_field_patch(&mut fields, &patch).unwrap();
+        let rendered = render_template(&template, &fields);
+        assert!(rendered.contains("Zaposleni Petar Petrović počinje"));
+        assert!(rendered.contains("Zaposleni Petar Petrović prihvata"));
+        assert!(rendered.contains("150.000 dinara mesečno"));
+
+        let unknown = HashMap::from([("plata".to_string(), "1".to_string())]);
+        assert!(apply_field_patch(&mut fields, &unknown).is_err());
+    }
+
+    #[test]
+    fn test_is_contract_file() {
+        let id = "5f0c3e8a-2b1d-4c6e-9a7f-0d1e2f3a4b5c";
+        assert!(is_contract_file(&format!("{}.docx", id), id));
+        assert!(is_contract_file(&format!("{}_v3.docx", id), id));
+        assert!(is_contract_file(&format!("{}.docx.tmp", id), id));
+        assert!(!is_contract_file(&format!("{}_vx.docx", id), id));
+        assert!(!is_contract_file("6a1d4f9b-3c2e-4d7f-8b6a-1e2f3a4b5c6d.docx", id));
+    }
 }
@@ -0,0 +1,208 @@
+// Allowed CORS origins, from CORS_ALLOWED_ORIGINS (comma-separated) and/or CORS_ORIGINS_FILE (one
+// origin per line, # comments). Without either the built-in list below is used. An entry is an
+// exact origin ("https://chat.normaai.rs") or a wildcard subdomain pattern for preview deployments
+// ("https://*.preview.normaai.rs" - one or more labels in front of the suffix). "*" allows any
+// origin, which browsers reject together with credentials, so it requires
+// CORS_ALLOW_CREDENTIALS=false. Invalid entries stop the server at startup.
+use axum::http::HeaderValue;
+use tower_http::cors::AllowOrigin;
+
+/// Web app, Tauri desktop/mobile and local development
+const DEFAULT_ORIGINS: &[&str] = &[
+    "http://localhost:1420",   // Tauri dev
+    "https://tauri.localhost", // Tauri production (HTTPS)
+    "http://tauri.localhost",  // Tauri production (HTTP - Android/iOS)
+    "tauri://localhost",       // Tauri custom protocol
+    "https://chat.normaai.rs", // Production web
+    "http://localhost:5173",   // Vite dev
+    "http://localhost:3000",   // Alternative dev port
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum OriginPattern {
+    Any,
+    Exact(String),
+    // "https://*.preview.normaai.rs:8443" -> scheme "https", suffix ".preview.normaai.rs", port Some(8443)
+    Subdomain { scheme: String, suffix: String, port: Option<u16> },
+}
+
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    origins: Vec<OriginPattern>,
+    pub allow_credentials: bool,
+}
+
+/// (scheme, host, port) of an origin; None unless it is exactly scheme://host[:port]
+fn split_origin(origin: &str) -> Option<(&str, &str, Option<u16>)> {
+    let (scheme, authority) = origin.split_once("://")?;
+    if scheme.is_empty() || authority.is_empty() || authority.contains(['/', '?', '#', '@']) {
+        return None;
+    }
+    let valid_scheme = scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    // "[::1]" has colons but no port
+    match authority.rsplit_once(':').filter(|_| !authority.ends_with(']')) {
+        Some((host, port)) if valid_scheme && !host.is_empty() => Some((scheme, host, Some(port.parse().ok()?))),
+        None if valid_scheme => Some((scheme, authority, None)),
+        _ => None,
+    }
+}
+
+fn parse_pattern(entry: &str) -> Result<OriginPattern, String> {
+    if entry == "*" {
+        return Ok(OriginPattern::Any);
+    }
+    let lower = entry.to_ascii_lowercase();
+    let (scheme, host, port) =
+        split_origin(&lower).ok_or_else(|| format!("'{}' is not an origin (scheme://host[:port], no path)", entry))?;
+
+    match host.strip_prefix('*') {
+        Some(suffix) if suffix.starts_with('.') && suffix.len() > 1 && !suffix.contains('*') => {
+            Ok(OriginPattern::Subdomain { scheme: scheme.to_string(), suffix: suffix.to_string(), port })
+        }
+        Some(_) => Err(format!("'{}': a wildcard must be the whole leftmost label (https://*.example.com)", entry)),
+        None if host.contains('*') => Err(format!("'{}': a wildcard must be the whole leftmost label (https://*.example.com)", entry)),
+        None => Ok(OriginPattern::Exact(lower)),
+    }
+}
+
+impl OriginPattern {
+    fn matches(&self, origin: &str) -> bool {
+        match self {
+            OriginPattern::Any => true,
+            OriginPattern::Exact(allowed) => origin.eq_ignore_ascii_case(allowed),
+            OriginPattern::Subdomain { scheme, suffix, port } => {
+                let lower = origin.to_ascii_lowercase();
+                match split_origin(&lower) {
+                    Some((origin_scheme, host, origin_port)) => {
+                        origin_scheme == scheme
+                            && origin_port == *port
+                            && host.len() > suffix.len()
+                            && host.ends_with(suffix.as_str())
+                    }
+                    None => false,
+                }
+            }
+        }
+    }
+}
+
+/// The built-in origins
+impl Default for CorsConfig {
+    fn default() -> Self {
+        CorsConfig::from_env_with(|_| None).expect("built-in CORS origins are valid")
+    }
+}
+
+impl CorsConfig {
+    /// Read and validate the configuration (see the module comment)
+    pub fn from_env_with(env: impl Fn(&str) -> Option<String>) -> Result<CorsConfig, String> {
+        let mut entries: Vec<String> = Vec::new();
+
+        if let Some(list) = env("CORS_ALLOWED_ORIGINS") {
+            entries.extend(list.split(',').map(|entry| entry.trim().to_string()).filter(|entry| !entry.is_empty()));
+        }
+        if let Some(path) = env("CORS_ORIGINS_FILE").filter(|path| !path.trim().is_empty()) {
+            let contents = std::fs::read_to_string(path.trim())
+                .map_err(|e| format!("Failed to read CORS_ORIGINS_FILE {}: {}", path.trim(), e))?;
+            entries.extend(
+                contents
+                    .lines()
+                    .map(|line| line.split('#').next().unwrap_or("").trim().to_string())
+                    .filter(|line| !line.is_empty()),
+            );
+        }
+        if entries.is_empty() {
+            entries = DEFAULT_ORIGINS.iter().map(|origin| origin.to_string()).collect();
+        }
+
+        let allow_credentials = match env("CORS_ALLOW_CREDENTIALS").map(|value| value.trim().to_ascii_lowercase()) {
+            None => true,
+            Some(value) if value.is_empty() || value == "true" => true,
+            Some(value) if value == "false" => false,
+            Some(value) => return Err(format!("CORS_ALLOW_CREDENTIALS must be true or false, got '{}'", value)),
+        };
+
+        let origins = entries.iter().map(|entry| parse_pattern(entry)).collect::<Result<Vec<_>, _>>()?;
+        if allow_credentials && origins.contains(&OriginPattern::Any) {
+            return Err("CORS origin '*' can't be combined with credentials - set CORS_ALLOW_CREDENTIALS=false or list the origins".to_string());
+        }
+
+        Ok(CorsConfig { origins, allow_credentials })
+    }
+
+
+    pub fn is_allowed(&self, origin: &str) -> bool {
+        self.origins.iter().any(|pattern| pattern.matches(origin))
+    }
+
+    pub fn allow_origin(&self) -> AllowOrigin {
+        if self.origins.contains(&OriginPattern::Any) {
+            return AllowOrigin::any();
+        }
+        let config = self.clone();
+        AllowOrigin::predicate(move |origin: &HeaderValue, _| origin.to_str().is_ok_and(|origin| config.is_allowed(origin)))
+    }
+
+    /// For the startup log
+    pub fn describe(&self) -> String {
+        self.origins
+            .iter()
+            .map(|pattern| match pattern {
+                OriginPattern::Any => "*".to_string(),
+                OriginPattern::Exact(origin) => origin.clone(),
+                OriginPattern::Subdomain { scheme, suffix, port } => match port {
+                    Some(port) => format!("{}://*{}:{}", scheme, suffix, port),
+                    None => format!("{}://*{}", scheme, suffix),
+                },
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(vars: &[(&str, &str)]) -> Result<CorsConfig, String> {
+        let vars: Vec<(String, String)> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        CorsConfig::from_env_with(|key| vars.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone()))
+    }
+
+    #[test]
+    fn test_default_origins() {
+        let cors = config(&[]).unwrap();
+        assert!(cors.allow_credentials);
+        assert!(cors.is_allowed("https://chat.normaai.rs"));
+        assert!(cors.is_allowed("tauri://localhost"));
+        assert!(!cors.is_allowed("https://evil.example"));
+    }
+
+    #[test]
+    fn test_wildcard_subdomains() {
+        let cors = config(&[("CORS_ALLOWED_ORIGINS", "https://chat.normaai.rs, https://*.preview.normaai.rs")]).unwrap();
+        assert!(cors.is_allowed("https://pr-42.preview.normaai.rs"));
+        assert!(cors.is_allowed("https://a.b.preview.normaai.rs"));
+        assert!(cors.is_allowed("HTTPS://PR-42.Preview.NormaAI.rs"));
+        assert!(!cors.is_allowed("https://preview.normaai.rs"));
+        assert!(!cors.is_allowed("http://pr-42.preview.normaai.rs")); // Scheme must match
+        assert!(!cors.is_allowed("https://pr-42.preview.normaai.rs:8443")); // And the port
+        assert!(!cors.is_allowed("https://evilpreview.normaai.rs"));
+        assert!(!cors.is_allowed("https://localhost:5173")); // Replaces the defaults
+    }
+
+    #[test]
+    fn test_invalid_configuration() {
+        assert!(config(&[("CORS_ALLOWED_ORIGINS", "https://chat.normaai.rs/app")]).is_err());
+        assert!(config(&[("CORS_ALLOWED_ORIGINS", "chat.normaai.rs")]).is_err());
+        assert!(config(&[("CORS_ALLOWED_ORIGINS", "https://pr-*.normaai.rs")]).is_err());
+        assert!(config(&[("CORS_ALLOWED_ORIGINS", "https://*")]).is_err());
+        assert!(config(&[("CORS_ALLOWED_ORIGINS", "http://localhost:dev")]).is_err());
+        assert!(config(&[("CORS_ALLOW_CREDENTIALS", "yes")]).is_err());
+
+        // Any origin only without credentials
+        assert!(config(&[("CORS_ALLOWED_ORIGINS", "*")]).is_err());
+        let open = config(&[("CORS_ALLOWED_ORIGINS", "*"), ("CORS_ALLOW_CREDENTIALS", "false")]).unwrap();
+        assert!(open.is_allowed("https://anything.example"));
+    }
+}
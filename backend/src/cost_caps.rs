@@ -0,0 +1,381 @@
+// Monthly LLM spend caps. users.monthly_llm_cost_usd is accumulated by track_llm_cost; once it
+// reaches the cap for the user's plan, can_send_message refuses new questions until the next month.
+use axum::{
+    extract::{Json, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use chrono::{Datelike, TimeZone};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::models::ErrorResponse;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+/// How long plan caps loaded from the database are reused before re-reading
+const CAP_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Built-in monthly cap per plan in USD, used when neither the database nor the environment sets one
+fn default_cap(account_type: &str) -> Option<f64> {
+    match account_type {
+        "trial_registered" => Some(2.0),
+        "individual" => Some(10.0),
+        "professional" | "premium" => Some(50.0),
+        "team" => Some(150.0),
+        _ => Some(2.0),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PlanCostCap {
+    pub account_type: String,
+    pub monthly_cap_usd: Option<f64>, // NULL = no cap for the plan
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetPlanCostCapRequest {
+    pub account_type: String,
+    pub monthly_cap_usd: Option<f64>, // null lifts the cap for the plan
+    #[serde(default)]
+    pub reset: bool, // Remove the row and fall back to env / built-in default
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetUserCostCapRequest {
+    pub monthly_cap_usd: Option<f64>, // null = unlimited for this user
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserCostCapResponse {
+    pub user_id: Uuid,
+    pub account_type: String,
+    pub monthly_cap_usd: Option<f64>, // Effective cap after overrides
+    pub spent_usd: f64,
+    pub overridden: bool,
+}
+
+/// Returned with 429 when a user's spend reached their cap
+#[derive(Debug, Clone, Serialize)]
+pub struct CostCapReached {
+    pub cap_usd: f64,
+    pub spent_usd: f64,
+    pub resets_at: chrono::DateTime<chrono::Utc>, // Start of next month (UTC)
+}
+
+impl CostCapReached {
+    pub fn error_response(&self) -> ErrorResponse {
+        ErrorResponse {
+            error: "COST_CAP_REACHED".to_string(),
+            message: "Dostigli ste mesečni limit korišćenja - limit se obnavlja početkom sledećeg meseca".to_string(),
+            details: serde_json::to_value(self).ok(),
+        }
+    }
+}
+
+// (loaded_at, caps)
+type CachedCaps = Option<(Instant, Vec<PlanCostCap>)>;
+
+static CAP_CACHE: OnceLock<Mutex<CachedCaps>> = OnceLock::new();
+
+fn cap_cache() -> &'static Mutex<CachedCaps> {
+    CAP_CACHE.get_or_init(|| Mutex::new(None))
+}
+
+fn invalidate_cache() {
+    *cap_cache().lock().unwrap() = None;
+}
+
+async fn load_plan_caps(pool: &PgPool) -> Vec<PlanCostCap> {
+    if let Some((loaded_at, caps)) = cap_cache().lock().unwrap().as_ref() {
+        if loaded_at.elapsed() < CAP_CACHE_TTL {
+            return caps.clone();
+        }
+    }
+
+    match sqlx::query_as::<_, PlanCostCap>(
+        "SELECT account_type, monthly_cap_usd::FLOAT8 AS monthly_cap_usd, updated_at FROM llm_cost_caps"
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(caps) => {
+            *cap_cache().lock().unwrap() = Some((Instant::now(), caps.clone()));
+            caps
+        }
+        Err(e) => {
+            // Fall back to env/defaults rather than blocking every question
+            eprintln!("⚠️  Failed to load LLM cost caps: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Cap for a user, first match wins:
+/// 1. Admin override for the user (None there means unlimited)
+/// 2. llm_cost_caps row for the plan
+/// 3. LLM_COST_CAP_USD_<ACCOUNT_TYPE> env var (empty or "none" = unlimited)
+/// 4. Built-in default
+fn resolve_cap_from(
+    account_type: &str,
+    user_override: Option<Option<f64>>,
+    plan_caps: &[PlanCostCap],
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<f64> {
+    if let Some(cap) = user_override {
+        return cap;
+    }
+    if let Some(plan) = plan_caps.iter().find(|c| c.account_type == account_type) {
+        return plan.monthly_cap_usd;
+    }
+    if let Some(value) = env(&format!("LLM_COST_CAP_USD_{}", account_type.to_uppercase())) {
+        let value = value.trim();
+        if value.is_empty() || value.eq_ignore_ascii_case("none") {
+            return None;
+        }
+        if let Ok(cap) = value.parse::<f64>() {
+            return Some(cap);
+        }
+    }
+    default_cap(account_type)
+}
+
+/// First instant of the month after `now` (UTC)
+fn next_month_start(now: chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::Utc> {
+    let (year, month) = if now.month() == 12 { (now.year() + 1, 1) } else { (now.year(), now.month() + 1) };
+    chrono::Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single().unwrap_or(now)
+}
+
+// (spent this month, override row present, override cap)
+async fn user_spend_and_override(user_id: Uuid, pool: &PgPool) -> Result<(f64, Option<Option<f64>>), String> {
+    let current_month = chrono::Utc::now().format("%Y-%m").to_string();
+
+    let (spent, has_override, override_cap) = sqlx::query_as::<_, (f64, bool, Option<f64>)>(
+        "SELECT
+            CASE WHEN u.current_cost_month = $2 THEN COALESCE(u.monthly_llm_cost_usd, 0)::FLOAT8 ELSE 0 END,
+            o.user_id IS NOT NULL,
+            o.monthly_cap_usd::FLOAT8
+         FROM users u
+         LEFT JOIN user_cost_cap_overrides o ON o.user_id = u.id
+         WHERE u.id = $1"
+    )
+    .bind(user_id)
+    .bind(&current_month)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load LLM spend: {}", e))?
+    .unwrap_or((0.0, false, None));
+
+    Ok((spent, has_override.then_some(override_cap)))
+}
+
+/// Whether the user's spend this month reached their cap
+pub async fn check_cost_cap(user_id: Uuid, account_type: &str, pool: &PgPool) -> Result<Option<CostCapReached>, String> {
+    let (spent_usd, user_override) = user_spend_and_override(user_id, pool).await?;
+    let plan_caps = load_plan_caps(pool).await;
+
+    let Some(cap_usd) = resolve_cap_from(account_type, user_override, &plan_caps, |key| crate::config::get().llm.override_value(key)) else {
+        return Ok(None);
+    };
+
+    if spent_usd < cap_usd {
+        return Ok(None);
+    }
+
+    println!("💸 User {} reached the monthly LLM cost cap: ${:.2} of ${:.2}", user_id, spent_usd, cap_usd);
+    Ok(Some(CostCapReached { cap_usd, spent_usd, resets_at: next_month_start(chrono::Utc::now()) }))
+}
+
+/// Admin: list plan caps configured in the database
+pub async fn list_cost_caps_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ResponseJson<Vec<PlanCostCap>>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(ResponseJson(list_plan_caps(&pool).await?))
+}
+
+async fn list_plan_caps(pool: &PgPool) -> Result<Vec<PlanCostCap>, StatusCode> {
+    sqlx::query_as::<_, PlanCostCap>(
+        "SELECT account_type, monthly_cap_usd::FLOAT8 AS monthly_cap_usd, updated_at FROM llm_cost_caps ORDER BY account_type"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to list LLM cost caps: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Admin: set, lift or reset the monthly cap of a plan
+pub async fn set_cost_cap_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<SetPlanCostCapRequest>,
+) -> Result<ResponseJson<Vec<PlanCostCap>>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    if request.account_type.trim().is_empty() || request.monthly_cap_usd.is_some_and(|cap| !cap.is_finite() || cap < 0.0) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let result = if request.reset {
+        sqlx::query("DELETE FROM llm_cost_caps WHERE account_type = $1")
+            .bind(&request.account_type)
+            .execute(&pool)
+            .await
+    } else {
+        sqlx::query(
+            "INSERT INTO llm_cost_caps (account_type, monthly_cap_usd)
+             VALUES ($1, $2)
+             ON CONFLICT (account_type) DO UPDATE SET monthly_cap_usd = EXCLUDED.monthly_cap_usd, updated_at = NOW()"
+        )
+        .bind(&request.account_type)
+        .bind(request.monthly_cap_usd)
+        .execute(&pool)
+        .await
+    };
+
+    result.map_err(|e| {
+        eprintln!("Failed to update LLM cost cap: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    println!(
+        "✅ LLM cost cap updated: account_type={}, monthly_cap_usd={:?}, reset={}",
+        request.account_type, request.monthly_cap_usd, request.reset
+    );
+    invalidate_cache();
+
+    Ok(ResponseJson(list_plan_caps(&pool).await?))
+}
+
+/// Admin: override the cap of a single user (e.g. lift it for a known heavy user)
+pub async fn set_user_cost_cap_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Path(user_id): Path<Uuid>,
+    Json(request): Json<SetUserCostCapRequest>,
+) -> Result<ResponseJson<UserCostCapResponse>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    if request.monthly_cap_usd.is_some_and(|cap| !cap.is_finite() || cap < 0.0) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    sqlx::query(
+        "INSERT INTO user_cost_cap_overrides (user_id, monthly_cap_usd, reason)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (user_id) DO UPDATE SET monthly_cap_usd = EXCLUDED.monthly_cap_usd, reason = EXCLUDED.reason, updated_at = NOW()"
+    )
+    .bind(user_id)
+    .bind(request.monthly_cap_usd)
+    .bind(request.reason.as_deref())
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        // Foreign key violation - no such user
+        eprintln!("Failed to set user cost cap: {}", e);
+        StatusCode::NOT_FOUND
+    })?;
+
+    println!("✅ Cost cap override for user {}: {:?}", user_id, request.monthly_cap_usd);
+    Ok(ResponseJson(user_cost_cap(user_id, &pool).await?))
+}
+
+/// Admin: remove a user's override so the plan cap applies again
+pub async fn delete_user_cost_cap_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Path(user_id): Path<Uuid>,
+) -> Result<ResponseJson<UserCostCapResponse>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    sqlx::query("DELETE FROM user_cost_cap_overrides WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to remove user cost cap: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(ResponseJson(user_cost_cap(user_id, &pool).await?))
+}
+
+async fn user_cost_cap(user_id: Uuid, pool: &PgPool) -> Result<UserCostCapResponse, StatusCode> {
+    let account_type: String = sqlx::query_scalar("SELECT account_type FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to load user: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let (spent_usd, user_override) = user_spend_and_override(user_id, pool).await.map_err(|e| {
+        eprintln!("{}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let plan_caps = load_plan_caps(pool).await;
+
+    Ok(UserCostCapResponse {
+        user_id,
+        monthly_cap_usd: resolve_cap_from(&account_type, user_override, &plan_caps, |key| crate::config::get().llm.override_value(key)),
+        account_type,
+        spent_usd,
+        overridden: user_override.is_some(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan_cap(account_type: &str, cap: Option<f64>) -> PlanCostCap {
+        PlanCostCap { account_type: account_type.to_string(), monthly_cap_usd: cap, updated_at: chrono::Utc::now() }
+    }
+
+    #[test]
+    fn test_resolve_cap_precedence() {
+        let caps = vec![plan_cap("professional", Some(80.0)), plan_cap("team", None)];
+        let env = |key: &str| match key {
+            "LLM_COST_CAP_USD_INDIVIDUAL" => Some("15".to_string()),
+            "LLM_COST_CAP_USD_PREMIUM" => Some("none".to_string()),
+            _ => None,
+        };
+
+        // User override wins, including an unlimited one
+        assert_eq!(resolve_cap_from("professional", Some(Some(500.0)), &caps, env), Some(500.0));
+        assert_eq!(resolve_cap_from("professional", Some(None), &caps, env), None);
+        // Database row, including a lifted plan cap
+        assert_eq!(resolve_cap_from("professional", None, &caps, env), Some(80.0));
+        assert_eq!(resolve_cap_from("team", None, &caps, env), None);
+        // Env, then the built-in default
+        assert_eq!(resolve_cap_from("individual", None, &caps, env), Some(15.0));
+        assert_eq!(resolve_cap_from("premium", None, &caps, env), None);
+        assert_eq!(resolve_cap_from("trial_registered", None, &caps, env), Some(2.0));
+    }
+
+    #[test]
+    fn test_next_month_start() {
+        let mid_december = chrono::Utc.with_ymd_and_hms(2024, 12, 15, 10, 30, 0).unwrap();
+        assert_eq!(next_month_start(mid_december), chrono::Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap());
+        let end_of_january = chrono::Utc.with_ymd_and_hms(2025, 1, 31, 23, 59, 59).unwrap();
+        assert_eq!(next_month_start(end_of_january), chrono::Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap());
+    }
+}
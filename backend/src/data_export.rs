@@ -0,0 +1,542 @@
+// Account-wide data export: profile, chats with messages, contract and document metadata as JSON,
+// plus a DOCX transcript per chat, packed into one ZIP. Built in the background; the download link
+// (with a one-off token, since it's opened from an e-mail) is delivered by e-mail and a chat event.
+use crate::events::{self, ChatEvent};
+use crate::models::ApiError;
+use crate::sessions::hash_token;
+use crate::simple_auth::AuthAppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use docx_rs::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::io::Write;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+const EXPORTS_DIR: &str = "/tmp/exports";
+const EXPORT_EXPIRY_DAYS: i64 = 7;
+/// One export per day; a failed export can be retried right away
+const EXPORT_COOLDOWN_HOURS: i32 = 24;
+
+fn database_error(e: sqlx::Error) -> ApiError {
+    eprintln!("Data export database error: {}", e);
+    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Greška baze podataka")
+}
+
+fn export_path(export_id: Uuid) -> PathBuf {
+    PathBuf::from(EXPORTS_DIR).join(format!("{}.zip", export_id))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportStatusResponse {
+    pub export_id: Uuid,
+    pub status: String, // pending, ready, failed
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ExportRow {
+    id: Uuid,
+    status: String,
+    created_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<ExportRow> for ExportStatusResponse {
+    fn from(row: ExportRow) -> Self {
+        ExportStatusResponse {
+            export_id: row.id,
+            status: row.status,
+            created_at: row.created_at,
+            completed_at: row.completed_at,
+            expires_at: row.expires_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct ExportChat {
+    id: i64,
+    title: String,
+    archived: bool,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct ExportMessage {
+    id: i64,
+    chat_id: i64,
+    role: String,
+    content: String,
+    law_name: Option<String>,
+    document_filename: Option<String>,
+    contract_file_id: Option<String>,
+    contract_type: Option<String>,
+    contract_filename: Option<String>,
+    message_feedback: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct ExportDocument {
+    id: Uuid,
+    chat_id: Option<i64>,
+    filename: String,
+    document_type: String,
+    file_size: i64,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportContract {
+    message_id: i64,
+    chat_id: i64,
+    contract_type: Option<String>,
+    filename: Option<String>,
+    file_included: bool, // Generated files expire after 30 days
+    created_at: Option<DateTime<Utc>>,
+}
+
+/// Everything exported for one user, loaded before the archive is written
+struct UserData {
+    profile: serde_json::Value,
+    chats: Vec<ExportChat>,
+    messages: Vec<ExportMessage>,
+    documents: Vec<ExportDocument>,
+}
+
+async fn load_user_data(user_id: Uuid, pool: &PgPool) -> Result<UserData, String> {
+    // Row as JSON so new profile columns are exported without touching this module
+    let account: String = sqlx::query_scalar(
+        "SELECT (to_jsonb(u) - 'password_hash' - 'auth_user_id' - 'totp_secret_encrypted' - 'totp_last_used_step')::text FROM users u WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to load account: {}", e))?;
+
+    let professional: Option<String> = sqlx::query_scalar(
+        "SELECT (to_jsonb(p) - 'user_id')::text FROM user_profiles p WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load professional profile: {}", e))?;
+
+    let parse = |json: &str| serde_json::from_str::<serde_json::Value>(json)
+        .map_err(|e| format!("Failed to parse profile JSON: {}", e));
+    let profile = serde_json::json!({
+        "account": parse(&account)?,
+        "professional_profile": professional.as_deref().map(parse).transpose()?,
+    });
+
+    let mut chats = sqlx::query_as::<_, ExportChat>(
+        "SELECT id, title, archived, created_at, updated_at, deleted_at FROM chats WHERE user_id = $1 ORDER BY created_at"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load chats: {}", e))?;
+
+    let mut messages = sqlx::query_as::<_, ExportMessage>(
+        "SELECT m.id, m.chat_id, m.role, m.content, m.law_name, m.document_filename,
+                m.contract_file_id, m.contract_type, m.contract_filename, m.message_feedback, m.created_at
+         FROM messages m JOIN chats c ON c.id = m.chat_id
+         WHERE c.user_id = $1
+         ORDER BY m.chat_id, m.created_at, m.id"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load messages: {}", e))?;
+
+    // Stored messages are already redacted; this catches titles and messages saved before
+    // redaction was turned on
+    let redact = crate::pii::redaction_enabled(user_id, pool)
+        .await
+        .map_err(|e| format!("Failed to check PII redaction setting: {}", e))?;
+    if redact {
+        for chat in &mut chats {
+            chat.title = crate::pii::redact(&chat.title).0;
+        }
+        for message in &mut messages {
+            message.content = crate::pii::redact(&message.content).0;
+        }
+    }
+
+    let documents = sqlx::query_as::<_, ExportDocument>(
+        "SELECT id, chat_id, filename, document_type, file_size, created_at FROM documents WHERE user_id = $1 ORDER BY created_at"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load documents: {}", e))?;
+
+    Ok(UserData { profile, chats, messages, documents })
+}
+
+/// Title fragment safe for a file name inside the archive
+fn safe_filename(title: &str) -> String {
+    let cleaned: String = title
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+        .take(40)
+        .collect();
+    cleaned.trim_matches('_').to_string()
+}
+
+fn chat_transcript_docx(chat: &ExportChat, messages: &[&ExportMessage]) -> Result<Vec<u8>, String> {
+    let mut docx = Docx::new().add_paragraph(
+        Paragraph::new()
+            .add_run(Run::new().add_text(&chat.title).size(32).bold()) // 16pt
+            .align(AlignmentType::Center),
+    );
+
+    if let Some(created_at) = chat.created_at {
+        docx = docx.add_paragraph(
+            Paragraph::new()
+                .add_run(Run::new().add_text(format!("Započet {}", created_at.format("%d.%m.%Y. %H:%M"))).italic().size(20))
+                .align(AlignmentType::Center),
+        );
+    }
+
+    for message in messages {
+        let author = if message.role == "user" { "Vi" } else { "Norma AI" };
+        let time = message.created_at.map(|t| t.format(" (%d.%m.%Y. %H:%M)").to_string()).unwrap_or_default();
+
+        docx = docx.add_paragraph(Paragraph::new());
+        docx = docx.add_paragraph(
+            Paragraph::new().add_run(Run::new().add_text(format!("{}{}", author, time)).bold().size(22)),
+        );
+        if let Some(filename) = &message.document_filename {
+            docx = docx.add_paragraph(
+                Paragraph::new().add_run(Run::new().add_text(format!("Priložen dokument: {}", filename)).italic().size(20)),
+            );
+        }
+        for line in message.content.lines() {
+            docx = docx.add_paragraph(Paragraph::new().add_run(Run::new().add_text(line).size(22))); // 11pt
+        }
+    }
+
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    docx.build()
+        .pack(&mut buffer)
+        .map_err(|e| format!("Failed to write transcript: {}", e))?;
+    Ok(buffer.into_inner())
+}
+
+fn write_archive(path: &PathBuf, data: &UserData) -> Result<(), String> {
+    std::fs::create_dir_all(EXPORTS_DIR)
+        .map_err(|e| format!("Failed to create exports directory: {}", e))?;
+    let file = std::fs::File::create(path).map_err(|e| format!("Failed to create export file: {}", e))?;
+
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut add = |name: &str, bytes: &[u8]| -> Result<(), String> {
+        zip.start_file(name, options).map_err(|e| format!("Failed to add {}: {}", name, e))?;
+        zip.write_all(bytes).map_err(|e| format!("Failed to write {}: {}", name, e))
+    };
+
+    let contracts: Vec<ExportContract> = data
+        .messages
+        .iter()
+        .filter(|m| m.contract_file_id.is_some() || m.contract_filename.is_some())
+        .map(|m| ExportContract {
+            message_id: m.id,
+            chat_id: m.chat_id,
+            contract_type: m.contract_type.clone(),
+            filename: m.contract_filename.clone(),
+            file_included: contract_file(m).is_some(),
+            created_at: m.created_at,
+        })
+        .collect();
+
+    let chats_json: Vec<serde_json::Value> = data
+        .chats
+        .iter()
+        .map(|chat| {
+            let messages: Vec<&ExportMessage> = data.messages.iter().filter(|m| m.chat_id == chat.id).collect();
+            serde_json::json!({ "chat": chat, "messages": messages })
+        })
+        .collect();
+
+    add("README.txt", README.as_bytes())?;
+    add("profile.json", pretty_json(&data.profile)?.as_bytes())?;
+    add("chats.json", pretty_json(&chats_json)?.as_bytes())?;
+    add("contracts.json", pretty_json(&contracts)?.as_bytes())?;
+    add("documents.json", pretty_json(&data.documents)?.as_bytes())?;
+
+    for chat in &data.chats {
+        let messages: Vec<&ExportMessage> = data.messages.iter().filter(|m| m.chat_id == chat.id).collect();
+        let transcript = chat_transcript_docx(chat, &messages)?;
+        add(&format!("transcripts/{}_{}.docx", chat.id, safe_filename(&chat.title)), &transcript)?;
+    }
+
+    for message in &data.messages {
+        if let Some(path) = contract_file(message) {
+            let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read contract file: {}", e))?;
+            let name = message.contract_filename.clone().unwrap_or_else(|| format!("ugovor_{}.docx", message.id));
+            add(&format!("contracts/{}_{}", message.id, name), &bytes)?;
+        }
+    }
+
+    zip.finish().map_err(|e| format!("Failed to finish export archive: {}", e))?;
+    Ok(())
+}
+
+/// Generated contract file of a message, if it hasn't expired yet
+fn contract_file(message: &ExportMessage) -> Option<PathBuf> {
+    let file_id = Uuid::parse_str(message.contract_file_id.as_deref()?).ok()?;
+    crate::contracts::contract_exists(file_id).then(|| crate::contracts::get_contract_path(file_id))
+}
+
+fn pretty_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize export: {}", e))
+}
+
+const README: &str = "Norma AI - izvoz podataka naloga
+
+profile.json     podaci naloga i profesionalnog profila
+chats.json       svi razgovori sa porukama
+contracts.json   generisani ugovori (fajlovi u contracts/ ako još nisu istekli)
+documents.json   otpremljeni dokumenti (samo podaci o fajlu)
+transcripts/     svaki razgovor kao Word dokument
+";
+
+/// Build the archive and deliver the link. Runs detached from the request.
+async fn run_export(export_id: Uuid, user_id: Uuid, download_token: String, pool: PgPool, resend_api_key: String) {
+    let path = export_path(export_id);
+    let build = async {
+        let data = load_user_data(user_id, &pool).await?;
+        let archive_path = path.clone();
+        tokio::task::spawn_blocking(move || write_archive(&archive_path, &data))
+            .await
+            .unwrap_or_else(|e| Err(format!("Export task panicked: {}", e)))
+    };
+    // A half-written archive is useless - on shutdown the export fails and can be requested again
+    let result = tokio::select! {
+        result = build => result,
+        _ = crate::shutdown::token().cancelled_owned() => Err("interrupted".to_string()),
+    };
+
+    if let Err(e) = &result {
+        eprintln!("❌ Data export {} for user {} failed: {}", export_id, user_id, e);
+        let _ = std::fs::remove_file(&path);
+        if let Err(e) = sqlx::query("UPDATE data_exports SET status = 'failed', error = $2, completed_at = NOW() WHERE id = $1")
+            .bind(export_id)
+            .bind(e)
+            .execute(&pool)
+            .await
+        {
+            eprintln!("Failed to mark data export as failed: {}", e);
+        }
+        events::emit(user_id, ChatEvent::ExportFinished { export_id: export_id.to_string(), download_url: None, success: false });
+        return;
+    }
+
+    let email: Option<String> = match sqlx::query_scalar(
+        "UPDATE data_exports d SET status = 'ready', completed_at = NOW(), expires_at = NOW() + make_interval(days => $2)
+         FROM users u WHERE d.id = $1 AND u.id = d.user_id
+         RETURNING u.email"
+    )
+    .bind(export_id)
+    .bind(EXPORT_EXPIRY_DAYS as i32)
+    .fetch_optional(&pool)
+    .await
+    {
+        Ok(email) => email,
+        Err(e) => {
+            eprintln!("Failed to mark data export as ready: {}", e);
+            return;
+        }
+    };
+
+    let api_base_url = &crate::config::get().api_base_url;
+    let download_url = format!("{}/api/exports/{}/download?token={}", api_base_url, export_id, download_token);
+    println!("✅ Data export {} ready for user {}", export_id, user_id);
+
+    if let Some(email) = email {
+        if let Err(e) = crate::email_service::send_data_export_email(&resend_api_key, &email, &download_url, EXPORT_EXPIRY_DAYS).await {
+            eprintln!("Failed to send data export email: {}", e);
+        }
+    }
+    events::emit(user_id, ChatEvent::ExportFinished { export_id: export_id.to_string(), download_url: Some(download_url), success: true });
+}
+
+/// Start a data export for the authenticated user (202; the link arrives by e-mail when ready)
+pub async fn request_export_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, resend_api_key)): State<AuthAppState>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<ExportStatusResponse>), ApiError> {
+    let user_id = crate::database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or_else(|| ApiError::new(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "Neautorizovan pristup"))?;
+
+    let recent: bool = sqlx::query_scalar(
+        "SELECT EXISTS(
+             SELECT 1 FROM data_exports
+             WHERE user_id = $1 AND status <> 'failed'
+               AND (status = 'pending' OR created_at > NOW() - make_interval(hours => $2))
+         )"
+    )
+    .bind(user_id)
+    .bind(EXPORT_COOLDOWN_HOURS)
+    .fetch_one(&pool)
+    .await
+    .map_err(database_error)?;
+
+    if recent {
+        return Err(ApiError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "EXPORT_RATE_LIMITED",
+            "Izvoz podataka je već zatražen - link stiže na vaš email",
+        ));
+    }
+
+    let download_token: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(48)
+        .map(char::from)
+        .collect();
+
+    // The partial unique index admits one pending export per user even under parallel requests
+    let row = sqlx::query_as::<_, ExportRow>(
+        "INSERT INTO data_exports (user_id, download_token_hash) VALUES ($1, $2)
+         ON CONFLICT (user_id) WHERE status = 'pending' DO NOTHING
+         RETURNING id, status, created_at, completed_at, expires_at"
+    )
+    .bind(user_id)
+    .bind(hash_token(&download_token))
+    .fetch_optional(&pool)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| ApiError::new(
+        StatusCode::TOO_MANY_REQUESTS,
+        "EXPORT_RATE_LIMITED",
+        "Izvoz podataka je već zatražen - link stiže na vaš email",
+    ))?;
+
+    println!("📦 Data export {} requested by user {}", row.id, user_id);
+    tokio::spawn(run_export(row.id, user_id, download_token, pool.clone(), resend_api_key));
+
+    Ok((StatusCode::ACCEPTED, Json(row.into())))
+}
+
+/// Status of one of the user's exports
+pub async fn export_status_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _)): State<AuthAppState>,
+    headers: HeaderMap,
+    Path(export_id): Path<Uuid>,
+) -> Result<Json<ExportStatusResponse>, ApiError> {
+    let user_id = crate::database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or_else(|| ApiError::new(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "Neautorizovan pristup"))?;
+
+    sqlx::query_as::<_, ExportRow>(
+        "SELECT id, status, created_at, completed_at, expires_at FROM data_exports WHERE id = $1 AND user_id = $2"
+    )
+    .bind(export_id)
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(database_error)?
+    .map(|row| Json(row.into()))
+    .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "EXPORT_NOT_FOUND", "Izvoz nije pronađen"))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DownloadQuery {
+    pub token: String,
+}
+
+/// Download a finished export (opened from the e-mail link, so authorized by the link token)
+pub async fn download_export_handler(
+    State((pool, ..)): State<AuthAppState>,
+    Path(export_id): Path<Uuid>,
+    Query(query): Query<DownloadQuery>,
+) -> Result<Response, StatusCode> {
+    let available: bool = sqlx::query_scalar(
+        "SELECT EXISTS(
+             SELECT 1 FROM data_exports
+             WHERE id = $1 AND download_token_hash = $2 AND status = 'ready' AND expires_at > NOW()
+         )"
+    )
+    .bind(export_id)
+    .bind(hash_token(&query.token))
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Database error loading data export: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if !available {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let content = tokio::fs::read(export_path(export_id)).await.map_err(|e| {
+        eprintln!("Failed to read data export {}: {}", export_id, e);
+        StatusCode::NOT_FOUND
+    })?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"NormaAI_izvoz_{}.zip\"", Utc::now().format("%Y-%m-%d")),
+            ),
+        ],
+        content,
+    )
+        .into_response())
+}
+
+/// Delete expired export archives and fail exports interrupted by a restart
+pub async fn purge_expired(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    sqlx::query(
+        "UPDATE data_exports SET status = 'failed', error = 'interrupted', completed_at = NOW()
+         WHERE status = 'pending' AND created_at < NOW() - INTERVAL '1 hour'"
+    )
+    .execute(pool)
+    .await?;
+
+    let expired: Vec<Uuid> = sqlx::query_scalar(
+        "DELETE FROM data_exports
+         WHERE expires_at < NOW() OR (status = 'failed' AND created_at < NOW() - make_interval(days => $1))
+         RETURNING id"
+    )
+    .bind(EXPORT_EXPIRY_DAYS as i32)
+    .fetch_all(pool)
+    .await?;
+
+    for export_id in &expired {
+        let _ = std::fs::remove_file(export_path(*export_id));
+    }
+
+    Ok(expired.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_safe_filename() {
+        assert_eq!(safe_filename("Otkaz ugovora o radu?"), "Otkaz_ugovora_o_radu");
+        assert_eq!(safe_filename("../../etc/passwd"), "etc_passwd");
+    }
+}
@@ -1,11 +1,13 @@
 use crate::models::*;
+use crate::legal_parser::LawArticle;
 use crate::simple_auth::verify_any_token;
 use axum::{
-    extract::{Json, Path, State},
-    http::StatusCode,
-    response::Json as ResponseJson,
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json as ResponseJson, Response},
 };
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use sqlx::PgPool;
 use tracing::{error, info, warn};
 use uuid::Uuid;
@@ -36,6 +38,12 @@ pub async fn verify_user_from_headers_async(
         }
     };
 
+    // Support impersonation tokens aren't tied to one of the user's sessions (see impersonation.rs)
+    if crate::impersonation::is_impersonation_token(token, jwt_secret) {
+        info!(user_id = %user_id, "Impersonation token used");
+        return Some(user_id);
+    }
+
     // Extract device_session_id from headers for logging
     let device_session_id = headers
         .get("X-Device-Session-Id")
@@ -109,6 +117,19 @@ pub async fn verify_user_from_headers_async(
     }
 }
 
+/// Like verify_user_from_headers_async, for handlers returning ApiError: 401 INVALID_TOKEN when the
+/// caller isn't signed in
+pub async fn authenticate(
+    headers: &axum::http::HeaderMap,
+    jwt_secret: &str,
+    supabase_jwt_secret: Option<&str>,
+    pool: &PgPool,
+) -> Result<Uuid, ApiError> {
+    verify_user_from_headers_async(headers, jwt_secret, supabase_jwt_secret, pool)
+        .await
+        .ok_or_else(|| ApiError::new(axum::http::StatusCode::UNAUTHORIZED, "INVALID_TOKEN", "Neispravan token"))
+}
+
 /// Get user by ID from optimized schema
 pub async fn get_user(
     user_id: Option<Uuid>,
@@ -116,11 +137,14 @@ pub async fn get_user(
 ) -> Result<Option<crate::models::User>, sqlx::Error> {
     if let Some(user_id) = user_id {
         // First try to get active user
-        let user = sqlx::query_as::<_, crate::models::User>(
-            "SELECT * FROM users WHERE id = $1 AND account_status = 'active'",
+        let user = crate::metrics::time_db_query(
+            "get_user",
+            sqlx::query_as::<_, crate::models::User>(
+                "SELECT * FROM users WHERE id = $1 AND account_status = 'active'",
+            )
+            .bind(user_id)
+            .fetch_optional(pool),
         )
-        .bind(user_id)
-        .fetch_optional(pool)
         .await?;
 
         // If user found and active, return it
@@ -184,7 +208,8 @@ pub async fn get_user_status_optimized(
             "professional" | "team" | "premium" => None, // Unlimited
             "individual" => user.trial_messages_remaining, // 20 per month
             _ => user.trial_messages_remaining,          // Trial messages (5 for new registrations)
-        };
+        }
+        .map(|remaining| remaining + user.purchased_messages_remaining); // Message packs come on top
 
         // Count total messages sent by this user (for UI hints)
         let total_messages_sent: i32 = if let Some(uid) = user_id {
@@ -208,6 +233,10 @@ pub async fn get_user_status_optimized(
             email: Some(user.email.clone()),
             email_verified: user.email_verified,
             oauth_provider: user.oauth_provider.clone(),
+            name: user.name.clone(),
+            profile_picture_url: user.oauth_profile_picture_url.clone(),
+            ui_language: Some(user.ui_language.clone()),
+            response_script: Some(user.response_script.clone()),
             access_type: access_type.to_string(),
             account_type: user.account_type.clone(),
             trial_expires_at: None, // No time-based expiration
@@ -215,12 +244,14 @@ pub async fn get_user_status_optimized(
             subscription_expires_at: user.premium_expires_at,
             messages_used_today: 0, // Not used anymore
             messages_remaining,
+            purchased_messages_remaining: user.purchased_messages_remaining,
             total_messages_sent,
             // Include subscription fields
             subscription_type: user.subscription_type,
             subscription_started_at: user.subscription_started_at,
             next_billing_date: user.next_billing_date,
             subscription_status: user.subscription_status,
+            grace_period_ends_at: user.grace_period_ends_at,
         })
     } else {
         // No user found - user needs to register/login
@@ -230,6 +261,10 @@ pub async fn get_user_status_optimized(
             email: None,
             email_verified: false,
             oauth_provider: None,
+            name: None,
+            profile_picture_url: None,
+            ui_language: None,
+            response_script: None,
             access_type: "trial".to_string(),
             account_type: "trial_registered".to_string(), // Will be set on registration
             trial_expires_at: None,
@@ -237,347 +272,491 @@ pub async fn get_user_status_optimized(
             subscription_expires_at: None, // Alias for frontend
             messages_used_today: 0,        // Not used
             messages_remaining: None,      // No trial started yet
+            purchased_messages_remaining: 0,
             total_messages_sent: 0,        // No messages sent yet
             // No subscription data for unregistered users
             subscription_type: None,
             subscription_started_at: None,
             next_billing_date: None,
             subscription_status: None,
+            grace_period_ends_at: None,
         })
     }
 }
 
-pub async fn run_migrations(pool: &PgPool) -> Result<(), sqlx::Error> {
-    // Create optimized tables for new schema
-
-    // 1. Optimized Users table (combines users + subscriptions)
-    sqlx::query(r#"
-        CREATE TABLE IF NOT EXISTS users (
-            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
-            auth_user_id UUID UNIQUE,
-            email VARCHAR(255) UNIQUE NOT NULL,
-            password_hash VARCHAR(255) NOT NULL,
-            email_verified BOOLEAN DEFAULT false,
-            name VARCHAR(255),
-            oauth_provider VARCHAR(50),
-            oauth_profile_picture_url TEXT,
-            account_type VARCHAR(20) DEFAULT 'trial_registered' CHECK (account_type IN ('trial_registered', 'individual', 'professional', 'team', 'premium')),
-            account_status VARCHAR(20) DEFAULT 'active' CHECK (account_status IN ('active', 'suspended', 'deleted')),
-            deleted_at TIMESTAMP WITH TIME ZONE,
-            trial_started_at TIMESTAMP WITH TIME ZONE,
-            trial_expires_at TIMESTAMP WITH TIME ZONE,
-            trial_messages_remaining INTEGER DEFAULT 5,
-            premium_expires_at TIMESTAMP WITH TIME ZONE,
-            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
-            updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
-            last_login TIMESTAMP WITH TIME ZONE
-        )
-    "#)
-    .execute(pool)
-    .await?;
-
-    // 2. Authentication tokens table (replaces email_verification_tokens + password_reset_tokens)
-    sqlx::query(r#"
-        CREATE TABLE IF NOT EXISTS authentication_tokens (
-            id BIGSERIAL PRIMARY KEY,
-            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-            token_type VARCHAR(20) NOT NULL CHECK (token_type IN ('email_verification', 'password_reset', 'jwt_refresh')),
-            token VARCHAR(255) NOT NULL UNIQUE,
-            expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
-            used_at TIMESTAMP WITH TIME ZONE,
-            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
-        )
-    "#)
-    .execute(pool)
-    .await?;
-
-    // 3. User sessions table for device tracking and concurrent login limits
-    sqlx::query(r#"
-        CREATE TABLE IF NOT EXISTS user_sessions (
-            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
-            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-            session_token_hash VARCHAR(64) NOT NULL UNIQUE,
-            device_info JSONB,
-            ip_address INET,
-            created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
-            last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
-            expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
-            revoked BOOLEAN NOT NULL DEFAULT false
-        )
-    "#)
-    .execute(pool)
-    .await?;
-
-    // 4. Existing core tables
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS chats (
-            id BIGSERIAL PRIMARY KEY,
-            title TEXT NOT NULL,
-            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
-            updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
-        )
-    "#,
-    )
-    .execute(pool)
-    .await?;
+#[utoipa::path(
+    post,
+    path = "/api/chats",
+    tag = "chats",
+    request_body = CreateChatRequest,
+    responses(
+        (status = 200, description = "Chat created", body = CreateChatResponse),
+        (status = 401, description = "Missing or invalid token")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[axum::debug_handler]
+pub async fn create_chat_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<CreateChatRequest>,
+) -> Result<ResponseJson<CreateChatResponse>, StatusCode> {
+    // Verify user with Supabase token support
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
 
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS messages (
-            id BIGSERIAL PRIMARY KEY,
-            chat_id BIGINT NOT NULL REFERENCES chats(id) ON DELETE CASCADE,
-            role TEXT NOT NULL CHECK(role IN ('user', 'assistant')),
-            content TEXT NOT NULL,
-            law_name TEXT,
-            has_document BOOLEAN DEFAULT FALSE,
-            document_filename TEXT,
-            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
-        )
-    "#,
+    // Registered user: associate chat with user_id
+    let result = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO chats (title, user_id, jurisdiction) VALUES ($1, $2, $3) RETURNING id"
     )
-    .execute(pool)
-    .await?;
-
-    // Add has_document column to existing messages table (migration for existing databases)
-    sqlx::query("ALTER TABLE messages ADD COLUMN IF NOT EXISTS has_document BOOLEAN DEFAULT FALSE")
-        .execute(pool)
-        .await?;
-
-    // Add document_filename column to existing messages table (migration for existing databases)
-    sqlx::query("ALTER TABLE messages ADD COLUMN IF NOT EXISTS document_filename TEXT")
-        .execute(pool)
-        .await?;
+    .bind(&request.title)
+    .bind(user_id)
+    .bind(request.jurisdiction.unwrap_or_default().as_str())
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to create chat: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
-    // Add contract fields to messages table (migration for contract generation feature)
-    sqlx::query("ALTER TABLE messages ADD COLUMN IF NOT EXISTS contract_file_id TEXT")
-        .execute(pool)
-        .await?;
+    crate::events::emit(user_id, crate::events::ChatEvent::ChatCreated {
+        chat_id: result,
+        title: request.title,
+    });
 
-    sqlx::query("ALTER TABLE messages ADD COLUMN IF NOT EXISTS contract_type TEXT")
-        .execute(pool)
-        .await?;
+    Ok(ResponseJson(CreateChatResponse { id: result }))
+}
 
-    sqlx::query("ALTER TABLE messages ADD COLUMN IF NOT EXISTS contract_filename TEXT")
-        .execute(pool)
-        .await?;
+/// Maximum chats accepted in a single import
+const MAX_IMPORT_CHATS: usize = 500;
 
-    // Add message_feedback column for user feedback tracking
-    sqlx::query("ALTER TABLE messages ADD COLUMN IF NOT EXISTS message_feedback VARCHAR(20) CHECK (message_feedback IN ('positive', 'negative'))")
-        .execute(pool)
-        .await?;
+/// Maximum messages per imported chat
+const MAX_IMPORT_MESSAGES_PER_CHAT: usize = 2000;
 
-    // Add index for message_feedback for analytics queries
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_messages_feedback ON messages(message_feedback) WHERE message_feedback IS NOT NULL")
-        .execute(pool)
-        .await?;
+/// Map roles from other tools onto ours (messages.role only allows 'user' and 'assistant')
+fn normalize_import_role(role: &str) -> Option<&'static str> {
+    match role.trim().to_lowercase().as_str() {
+        "user" | "human" | "korisnik" => Some("user"),
+        "assistant" | "ai" | "bot" | "model" | "norma" => Some("assistant"),
+        _ => None,
+    }
+}
 
-    // Add cost tracking columns to existing users table (migration for existing databases)
-    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS monthly_llm_cost_usd DECIMAL(10,2) DEFAULT 0.00")
-        .execute(pool)
-        .await?;
+/// Import chats from a Norma export or the generic {title, messages} format
+#[utoipa::path(
+    post,
+    path = "/api/chats/import",
+    tag = "chats",
+    request_body = ChatImportRequest,
+    responses(
+        (status = 200, description = "Chats imported", body = ChatImportResponse),
+        (status = 400, description = "Nothing to import"),
+        (status = 401, description = "Missing or invalid token")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[axum::debug_handler]
+pub async fn import_chats_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<ChatImportRequest>,
+) -> Result<ResponseJson<ChatImportResponse>, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
 
-    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS current_cost_month VARCHAR(7) DEFAULT TO_CHAR(NOW(), 'YYYY-MM')")
-        .execute(pool)
-        .await?;
+    let chats = match request {
+        ChatImportRequest::Norma(export) => {
+            if export.format != "norma-chats" || export.version > 1 {
+                println!("❌ IMPORT: Unsupported export format {} v{}", export.format, export.version);
+                return Err(StatusCode::UNPROCESSABLE_ENTITY);
+            }
+            export.chats
+        }
+        ChatImportRequest::Many(chats) => chats,
+        ChatImportRequest::Single(chat) => vec![chat],
+    };
 
-    // Add team_id column for team plan support
-    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS team_id UUID")
-        .execute(pool)
-        .await?;
+    if chats.is_empty() || chats.len() > MAX_IMPORT_CHATS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if chats.iter().any(|c| c.messages.len() > MAX_IMPORT_MESSAGES_PER_CHAT) {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
 
-    // Add trial_messages_remaining column for clean trial implementation
-    sqlx::query(
-        "ALTER TABLE users ADD COLUMN IF NOT EXISTS trial_messages_remaining INTEGER DEFAULT 5",
-    )
-    .execute(pool)
-    .await?;
+    let redact = crate::pii::redaction_enabled(user_id, &pool).await.map_err(|e| {
+        eprintln!("Failed to check PII redaction setting: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
-    // Add auth_user_id column for Supabase integration (links to auth.users)
-    sqlx::query(
-        "ALTER TABLE users ADD COLUMN IF NOT EXISTS auth_user_id UUID UNIQUE",
-    )
-    .execute(pool)
-    .await?;
+    // All-or-nothing so a failed import doesn't leave half the chats behind
+    let mut tx = pool.begin().await.map_err(|e| {
+        eprintln!("Failed to start import transaction: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
-    // Add name column for user profiles (from OAuth or manual entry)
-    sqlx::query(
-        "ALTER TABLE users ADD COLUMN IF NOT EXISTS name VARCHAR(255)",
-    )
-    .execute(pool)
-    .await?;
+    let mut chat_ids = Vec::new();
+    let mut imported_messages = 0;
+    let mut skipped_messages = 0;
+
+    for chat in chats {
+        let title = chat
+            .title
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(|t| t.chars().take(200).collect::<String>())
+            .unwrap_or_else(|| "Uvezen razgovor".to_string());
+        let created_at = chat.created_at.unwrap_or_else(chrono::Utc::now);
+
+        let chat_id: i64 = sqlx::query_scalar(
+            "INSERT INTO chats (title, user_id, created_at, updated_at) VALUES ($1, $2, $3, $3) RETURNING id"
+        )
+        .bind(&title)
+        .bind(user_id)
+        .bind(created_at)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to create imported chat: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
 
-    // Add oauth_provider column to track OAuth login method
-    sqlx::query(
-        "ALTER TABLE users ADD COLUMN IF NOT EXISTS oauth_provider VARCHAR(50)",
-    )
-    .execute(pool)
-    .await?;
+        let mut last_message_at = created_at;
+        for (index, message) in chat.messages.into_iter().enumerate() {
+            let role = match normalize_import_role(&message.role) {
+                Some(role) if !message.content.trim().is_empty() => role,
+                _ => {
+                    skipped_messages += 1;
+                    continue;
+                }
+            };
+
+            // Keep original ordering even when the source has no timestamps
+            let message_at = message
+                .created_at
+                .unwrap_or_else(|| created_at + chrono::Duration::seconds(index as i64));
+            last_message_at = last_message_at.max(message_at);
+
+            let (content, pii_mapping) = if redact {
+                crate::pii::redact_for_user(user_id, &message.content).map_err(|e| {
+                    eprintln!("Failed to redact imported message: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?
+            } else {
+                (message.content, None)
+            };
+
+            sqlx::query(
+                "INSERT INTO messages (chat_id, role, content, law_name, pii_mapping_encrypted, created_at) VALUES ($1, $2, $3, $4, $5, $6)"
+            )
+            .bind(chat_id)
+            .bind(role)
+            .bind(content)
+            .bind(&message.law_name)
+            .bind(pii_mapping)
+            .bind(message_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                eprintln!("Failed to import message: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+            imported_messages += 1;
+        }
 
-    // Add oauth_profile_picture_url column for user avatars
-    sqlx::query(
-        "ALTER TABLE users ADD COLUMN IF NOT EXISTS oauth_profile_picture_url TEXT",
-    )
-    .execute(pool)
-    .await?;
+        sqlx::query("UPDATE chats SET updated_at = $1 WHERE id = $2")
+            .bind(last_message_at)
+            .bind(chat_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                eprintln!("Failed to update imported chat timestamp: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
 
-    // Add deleted_at column for soft delete functionality
-    sqlx::query(
-        "ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE",
-    )
-    .execute(pool)
-    .await?;
+        chat_ids.push(chat_id);
+    }
 
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS law_cache (
-            id BIGSERIAL PRIMARY KEY,
-            law_name TEXT UNIQUE NOT NULL,
-            law_url TEXT NOT NULL,
-            content TEXT NOT NULL,
-            cached_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
-            expires_at TIMESTAMP WITH TIME ZONE NOT NULL
-        )
-    "#,
-    )
-    .execute(pool)
-    .await?;
+    tx.commit().await.map_err(|e| {
+        eprintln!("Failed to commit chat import: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
-    // Create optimized indexes
-    // Users table indexes
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_users_email ON users(email)")
-        .execute(pool)
-        .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_users_account_type ON users(account_type)")
-        .execute(pool)
-        .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_users_trial_expires ON users(trial_expires_at) WHERE trial_expires_at IS NOT NULL")
-        .execute(pool)
-        .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_users_premium_expires ON users(premium_expires_at) WHERE premium_expires_at IS NOT NULL")
-        .execute(pool)
-        .await?;
-    sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_users_team_id ON users(team_id) WHERE team_id IS NOT NULL",
-    )
-    .execute(pool)
-    .await?;
+    println!(
+        "✅ IMPORT: user_id={} imported {} chats, {} messages ({} skipped)",
+        user_id, chat_ids.len(), imported_messages, skipped_messages
+    );
 
-    // Authentication tokens indexes
-    sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_auth_tokens_user_id ON authentication_tokens(user_id)",
-    )
-    .execute(pool)
-    .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_auth_tokens_token ON authentication_tokens(token)")
-        .execute(pool)
-        .await?;
-    sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_auth_tokens_type ON authentication_tokens(token_type)",
-    )
-    .execute(pool)
-    .await?;
-    sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_auth_tokens_expires ON authentication_tokens(expires_at)",
-    )
-    .execute(pool)
-    .await?;
+    Ok(ResponseJson(ChatImportResponse {
+        imported_chats: chat_ids.len(),
+        imported_messages,
+        skipped_messages,
+        chat_ids,
+    }))
+}
 
-    // User sessions indexes
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_user_id ON user_sessions(user_id)")
-        .execute(pool)
-        .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_token_hash ON user_sessions(session_token_hash)")
-        .execute(pool)
-        .await?;
-    // Partial index for active sessions (without NOW() which is non-immutable)
-    // We filter expires_at > NOW() in queries instead of in the index predicate
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_active ON user_sessions(user_id, last_seen_at DESC) WHERE revoked = false")
-        .execute(pool)
-        .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_cleanup ON user_sessions(expires_at) WHERE revoked = false")
-        .execute(pool)
-        .await?;
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
 
-    // Core table indexes
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_messages_chat_id ON messages(chat_id)")
-        .execute(pool)
-        .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_law_cache_name ON law_cache(law_name)")
-        .execute(pool)
-        .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_law_cache_expires ON law_cache(expires_at)")
-        .execute(pool)
-        .await?;
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_chats_user_id ON chats(user_id)")
-        .execute(pool)
-        .await?;
+// Chats are listed by updated_at, which changes as chats are used, so the cursor carries the
+// position ("<updated_at micros>_<id>") rather than just the id of the last chat seen
+fn chat_cursor(chat: &Chat) -> String {
+    format!("{}_{}", chat.updated_at.timestamp_micros(), chat.id)
+}
 
-    Ok(())
+fn parse_chat_cursor(cursor: &str) -> Option<(chrono::DateTime<chrono::Utc>, i64)> {
+    let (micros, id) = cursor.split_once('_')?;
+    Some((chrono::DateTime::from_timestamp_micros(micros.parse().ok()?)?, id.parse().ok()?))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/chats",
+    tag = "chats",
+    params(ChatListQuery),
+    responses(
+        (status = 200, description = "The user's chats - a bare array unless a pagination parameter is set", body = ChatListResponse),
+        (status = 401, description = "Missing or invalid token")
+    ),
+    security(("bearer_auth" = []))
+)]
 #[axum::debug_handler]
-pub async fn create_chat_handler(
+pub async fn get_chats_handler(
     State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
     headers: axum::http::HeaderMap,
-    Json(request): Json<CreateChatRequest>,
-) -> Result<ResponseJson<CreateChatResponse>, StatusCode> {
+    Query(filter): Query<ChatListQuery>,
+) -> Result<ResponseJson<ChatListResponse>, StatusCode> {
     // Verify user with Supabase token support
     let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
         .await
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
-    // Registered user: associate chat with user_id
-    let result = sqlx::query_scalar::<_, i64>(
-        "INSERT INTO chats (title, user_id) VALUES ($1, $2) RETURNING id"
+    if !filter.is_paginated() {
+        // Get chats by user_id, optionally only one folder (?folder_id=) or only unfiled chats (?unfiled=true)
+        let chats = crate::metrics::time_db_query(
+            "list_chats",
+            sqlx::query_as::<_, Chat>(
+                "SELECT id, title, user_id, folder_id, jurisdiction, created_at, updated_at
+                 FROM chats
+                 WHERE user_id = $1 AND archived = false AND deleted_at IS NULL
+                   AND ($2::BIGINT IS NULL OR folder_id = $2)
+                   AND (NOT $3 OR folder_id IS NULL)
+                 ORDER BY updated_at DESC"
+            )
+            .bind(user_id)
+            .bind(filter.folder_id)
+            .bind(filter.unfiled.unwrap_or(false))
+            .fetch_all(&pool),
+        )
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to fetch chats: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+        return Ok(ResponseJson(ChatListResponse::All(chats)));
+    }
+
+    let limit = filter.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let cursor = match filter.before.as_deref() {
+        Some(before) => Some(parse_chat_cursor(before).ok_or(StatusCode::BAD_REQUEST)?),
+        None => None,
+    };
+    let include_last_message = filter
+        .include
+        .as_deref()
+        .is_some_and(|include| include.split(',').any(|part| part.trim() == "last_message"));
+
+    // One row past the page tells whether another page follows
+    let mut chats = sqlx::query_as::<_, Chat>(
+        "SELECT id, title, user_id, folder_id, jurisdiction, created_at, updated_at
+         FROM chats
+         WHERE user_id = $1 AND archived = false AND deleted_at IS NULL
+           AND ($2::BIGINT IS NULL OR folder_id = $2)
+           AND (NOT $3 OR folder_id IS NULL)
+           AND ($4::TIMESTAMPTZ IS NULL OR (updated_at, id) < ($4, $5::BIGINT))
+         ORDER BY updated_at DESC, id DESC
+         LIMIT $6"
     )
-    .bind(request.title)
     .bind(user_id)
+    .bind(filter.folder_id)
+    .bind(filter.unfiled.unwrap_or(false))
+    .bind(cursor.map(|(updated_at, _)| updated_at))
+    .bind(cursor.map(|(_, id)| id))
+    .bind(limit + 1)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to fetch chats page: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let has_more = chats.len() as i64 > limit;
+    chats.truncate(limit as usize);
+    let next_before = if has_more { chats.last().map(chat_cursor) } else { None };
+
+    let total: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM chats
+         WHERE user_id = $1 AND archived = false AND deleted_at IS NULL
+           AND ($2::BIGINT IS NULL OR folder_id = $2)
+           AND (NOT $3 OR folder_id IS NULL)"
+    )
+    .bind(user_id)
+    .bind(filter.folder_id)
+    .bind(filter.unfiled.unwrap_or(false))
     .fetch_one(&pool)
     .await
     .map_err(|e| {
-        eprintln!("Failed to create chat: {}", e);
+        eprintln!("Failed to count chats: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    Ok(ResponseJson(CreateChatResponse { id: result }))
+    // Previews for the whole page in one query
+    let mut previews = std::collections::HashMap::new();
+    if include_last_message && !chats.is_empty() {
+        let chat_ids: Vec<i64> = chats.iter().map(|chat| chat.id).collect();
+        let rows = sqlx::query_as::<_, MessagePreview>(
+            "SELECT DISTINCT ON (chat_id) chat_id, role, LEFT(content, 200) AS content, created_at
+             FROM messages
+             WHERE chat_id = ANY($1) AND superseded_at IS NULL
+             ORDER BY chat_id, created_at DESC, id DESC"
+        )
+        .bind(&chat_ids)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to fetch last messages: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        previews.extend(rows.into_iter().map(|preview| (preview.chat_id, preview)));
+    }
+
+    let chats = chats
+        .into_iter()
+        .map(|chat| {
+            let last_message = previews.remove(&chat.id);
+            ChatListItem { chat, last_message }
+        })
+        .collect();
+
+    Ok(ResponseJson(ChatListResponse::Page(ChatPage { chats, total, next_before })))
 }
 
+/// Full-text search over the user's chat titles and messages
+#[utoipa::path(
+    get,
+    path = "/api/search",
+    tag = "chats",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Matching chats and messages", body = SearchResponse),
+        (status = 400, description = "Empty query"),
+        (status = 401, description = "Missing or invalid token")
+    ),
+    security(("bearer_auth" = []))
+)]
 #[axum::debug_handler]
-pub async fn get_chats_handler(
+pub async fn search_handler(
     State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
     headers: axum::http::HeaderMap,
-) -> Result<ResponseJson<Vec<Chat>>, StatusCode> {
-    // Verify user with Supabase token support
+    Query(query): Query<SearchQuery>,
+) -> Result<ResponseJson<SearchResponse>, StatusCode> {
     let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
         .await
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
-    // Get chats by user_id
-    let chats = sqlx::query_as::<_, Chat>(
-        "SELECT id, title, user_id, created_at, updated_at
-         FROM chats
-         WHERE user_id = $1
-         ORDER BY updated_at DESC"
+    let search_text = query.q.trim().to_string();
+    if search_text.is_empty() || search_text.len() > 200 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    // Ownership is enforced by joining through chats.user_id
+    let chats = crate::metrics::time_db_query(
+        "search_chats",
+        sqlx::query_as::<_, ChatSearchResult>(
+            r#"
+            SELECT c.id AS chat_id,
+                   c.title,
+                   ts_headline('simple', c.title, q, 'StartSel=**, StopSel=**, HighlightAll=true') AS title_highlighted,
+                   ts_rank(to_tsvector('simple', c.title), q) AS rank,
+                   c.updated_at
+            FROM chats c, websearch_to_tsquery('simple', $2) q
+            WHERE c.user_id = $1 AND c.deleted_at IS NULL AND to_tsvector('simple', c.title) @@ q
+            ORDER BY rank DESC, c.updated_at DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(user_id)
+        .bind(&search_text)
+        .bind(limit)
+        .fetch_all(&pool),
     )
-    .bind(user_id)
-    .fetch_all(&pool)
     .await
     .map_err(|e| {
-        eprintln!("Failed to fetch chats: {}", e);
+        eprintln!("Failed to search chats: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    Ok(ResponseJson(chats))
+    let messages = crate::metrics::time_db_query(
+        "search_messages",
+        sqlx::query_as::<_, MessageSearchResult>(
+            r#"
+            SELECT m.id AS message_id,
+                   m.chat_id,
+                   c.title AS chat_title,
+                   m.role,
+                   ts_headline('simple', m.content, q, 'StartSel=**, StopSel=**, MaxWords=35, MinWords=15, MaxFragments=2, FragmentDelimiter=" … "') AS snippet,
+                   ts_rank(to_tsvector('simple', m.content), q) AS rank,
+                   m.created_at
+            FROM messages m
+            JOIN chats c ON c.id = m.chat_id,
+                 websearch_to_tsquery('simple', $2) q
+            WHERE c.user_id = $1 AND c.deleted_at IS NULL AND m.superseded_at IS NULL
+              AND to_tsvector('simple', m.content) @@ q
+            ORDER BY rank DESC, m.created_at DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(user_id)
+        .bind(&search_text)
+        .bind(limit)
+        .fetch_all(&pool),
+    )
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to search messages: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(ResponseJson(SearchResponse {
+        query: search_text,
+        chats,
+        messages,
+    }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/chats/{chat_id}/messages",
+    tag = "messages",
+    params(("chat_id" = i64, Path, description = "Chat id"), MessageListQuery),
+    responses(
+        (status = 200, description = "Messages of the chat, oldest first", body = MessageListResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Chat not found or not owned by the user")
+    ),
+    security(("bearer_auth" = []))
+)]
 #[axum::debug_handler]
 pub async fn get_messages_handler(
     State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
     headers: axum::http::HeaderMap,
     Path(chat_id): Path<i64>,
-) -> Result<ResponseJson<Vec<Message>>, StatusCode> {
+    Query(page): Query<MessageListQuery>,
+) -> Result<ResponseJson<MessageListResponse>, StatusCode> {
     // Verify user with Supabase token support
     let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
         .await
@@ -600,21 +779,130 @@ pub async fn get_messages_handler(
         return Err(StatusCode::NOT_FOUND);
     }
 
-    // If ownership is verified, get the messages
-    let messages = sqlx::query_as::<_, Message>(
-        "SELECT id, chat_id, role, content, law_name, has_document, document_filename, contract_file_id, contract_type, contract_filename, message_feedback, created_at FROM messages WHERE chat_id = $1 ORDER BY created_at ASC"
+    if page.limit.is_none() && page.before.is_none() {
+        // If ownership is verified, get the messages
+        let rows = crate::metrics::time_db_query(
+            "list_messages",
+            sqlx::query_as::<_, MessageRow>(
+                "SELECT id, chat_id, role, content, law_name, has_document, document_filename, contract_file_id, contract_type, contract_filename, message_feedback, edited_at, stale_at, pii_mapping_encrypted, created_at FROM messages WHERE chat_id = $1 AND superseded_at IS NULL ORDER BY created_at ASC"
+            )
+            .bind(chat_id)
+            .fetch_all(&pool),
+        )
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to fetch messages: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+        let messages = crate::pii::restore_messages(user_id, rows);
+        return Ok(ResponseJson(MessageListResponse::All(messages)));
+    }
+
+    // Latest messages first, so the chat opens at the bottom and scrolling up loads older pages
+    let limit = page.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let mut rows = sqlx::query_as::<_, MessageRow>(
+        "SELECT id, chat_id, role, content, law_name, has_document, document_filename, contract_file_id, contract_type, contract_filename, message_feedback, edited_at, stale_at, pii_mapping_encrypted, created_at
+         FROM messages
+         WHERE chat_id = $1 AND superseded_at IS NULL
+           AND ($2::BIGINT IS NULL OR (created_at, id) < (SELECT created_at, id FROM messages WHERE id = $2 AND chat_id = $1))
+         ORDER BY created_at DESC, id DESC
+         LIMIT $3"
     )
     .bind(chat_id)
+    .bind(page.before)
+    .bind(limit + 1)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to fetch messages page: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+    rows.reverse();
+    let next_before = if has_more { rows.first().map(|row| row.message.id) } else { None };
+    let messages = crate::pii::restore_messages(user_id, rows);
+
+    let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages WHERE chat_id = $1 AND superseded_at IS NULL")
+        .bind(chat_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to count messages: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(ResponseJson(MessageListResponse::Page(MessagePage { messages, total, next_before })))
+}
+
+/// Earlier versions of an edited question, oldest first
+#[utoipa::path(
+    get,
+    path = "/api/messages/{message_id}/revisions",
+    tag = "messages",
+    params(("message_id" = i64, Path, description = "Message id")),
+    responses(
+        (status = 200, description = "Earlier versions of the question, oldest first", body = Vec<MessageRevision>),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Message not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[axum::debug_handler]
+pub async fn get_message_revisions_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Path(message_id): Path<i64>,
+) -> Result<ResponseJson<Vec<MessageRevision>>, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    // Verify the user owns the message's chat
+    let owns_message = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM messages m JOIN chats c ON c.id = m.chat_id WHERE m.id = $1 AND c.user_id = $2)"
+    )
+    .bind(message_id)
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to verify message ownership: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if !owns_message {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let revisions = sqlx::query_as::<_, MessageRevision>(
+        "SELECT id, content, edited_at FROM message_revisions WHERE message_id = $1 ORDER BY edited_at ASC"
+    )
+    .bind(message_id)
     .fetch_all(&pool)
     .await
     .map_err(|e| {
-        eprintln!("Failed to fetch messages: {}", e);
+        eprintln!("Failed to fetch message revisions: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    Ok(ResponseJson(messages))
+    Ok(ResponseJson(revisions))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/messages",
+    tag = "messages",
+    request_body = AddMessageRequest,
+    responses(
+        (status = 200, description = "Message stored"),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Chat not found or not owned by the user")
+    ),
+    security(("bearer_auth" = []))
+)]
 #[axum::debug_handler]
 pub async fn add_message_handler(
     State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
@@ -643,13 +931,21 @@ pub async fn add_message_handler(
         return Err(StatusCode::NOT_FOUND);
     }
 
+    let (content, pii_mapping) = crate::pii::prepare_for_storage(request.chat_id, request.content, &pool)
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
     // If ownership is verified, insert the message
-    sqlx::query("INSERT INTO messages (chat_id, role, content, law_name) VALUES ($1, $2, $3, $4)")
+    let message_id: i64 = sqlx::query_scalar("INSERT INTO messages (chat_id, role, content, law_name, pii_mapping_encrypted) VALUES ($1, $2, $3, $4, $5) RETURNING id")
         .bind(request.chat_id)
-        .bind(request.role)
-        .bind(request.content)
+        .bind(&request.role)
+        .bind(content)
         .bind(request.law_name)
-        .execute(&pool)
+        .bind(pii_mapping)
+        .fetch_one(&pool)
         .await
         .map_err(|e| {
             eprintln!("Failed to add message: {}", e);
@@ -666,9 +962,27 @@ pub async fn add_message_handler(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
+    crate::events::emit(user_id, crate::events::ChatEvent::MessageAdded {
+        chat_id: request.chat_id,
+        message_id,
+        role: request.role,
+    });
+
     Ok(StatusCode::OK)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/chats/{chat_id}",
+    tag = "chats",
+    params(("chat_id" = i64, Path, description = "Chat id")),
+    responses(
+        (status = 204, description = "Chat moved to trash, restorable for 30 days"),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Chat not found or not owned by the user")
+    ),
+    security(("bearer_auth" = []))
+)]
 #[axum::debug_handler]
 pub async fn delete_chat_handler(
     State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
@@ -680,8 +994,8 @@ pub async fn delete_chat_handler(
         .await
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
-    // Delete the chat only if the user owns it (CASCADE will automatically delete associated messages)
-    let result = sqlx::query("DELETE FROM chats WHERE id = $1 AND user_id = $2")
+    // Soft delete the chat only if the user owns it - purged after CHAT_PURGE_DAYS by the cleanup job
+    let result = sqlx::query("UPDATE chats SET deleted_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL")
         .bind(chat_id)
         .bind(user_id)
         .execute(&pool)
@@ -699,17 +1013,170 @@ pub async fn delete_chat_handler(
     Ok(StatusCode::OK)
 }
 
-#[derive(Deserialize)]
+/// Days a soft-deleted chat can be restored before it is permanently removed
+pub const CHAT_PURGE_DAYS: i32 = 30;
+
+#[utoipa::path(
+    post,
+    path = "/api/chats/{chat_id}/archive",
+    tag = "chats",
+    params(("chat_id" = i64, Path, description = "Chat id")),
+    responses(
+        (status = 204, description = "Chat archived"),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Chat not found or not owned by the user")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[axum::debug_handler]
+pub async fn archive_chat_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Path(chat_id): Path<i64>,
+) -> Result<StatusCode, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let result = sqlx::query(
+        "UPDATE chats SET archived = true, archived_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"
+    )
+    .bind(chat_id)
+    .bind(user_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to archive chat: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// Restore an archived or soft-deleted chat back to the main chat list
+#[utoipa::path(
+    post,
+    path = "/api/chats/{chat_id}/restore",
+    tag = "chats",
+    params(("chat_id" = i64, Path, description = "Chat id")),
+    responses(
+        (status = 204, description = "Chat restored"),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Chat not found or not owned by the user")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[axum::debug_handler]
+pub async fn restore_chat_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Path(chat_id): Path<i64>,
+) -> Result<StatusCode, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let result = sqlx::query(
+        "UPDATE chats SET archived = false, archived_at = NULL, deleted_at = NULL
+         WHERE id = $1 AND user_id = $2 AND (archived = true OR deleted_at IS NOT NULL)"
+    )
+    .bind(chat_id)
+    .bind(user_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to restore chat: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// List archived and recently deleted (still restorable) chats
+#[utoipa::path(
+    get,
+    path = "/api/chats/archived",
+    tag = "chats",
+    responses(
+        (status = 200, description = "Archived and recently deleted chats", body = Vec<ArchivedChat>),
+        (status = 401, description = "Missing or invalid token")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[axum::debug_handler]
+pub async fn get_archived_chats_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> Result<ResponseJson<Vec<ArchivedChat>>, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let chats = sqlx::query_as::<_, ArchivedChat>(
+        "SELECT id, title, archived, archived_at, deleted_at,
+                deleted_at + make_interval(days => $2) AS purge_at,
+                updated_at
+         FROM chats
+         WHERE user_id = $1 AND (archived = true OR deleted_at IS NOT NULL)
+         ORDER BY COALESCE(deleted_at, archived_at, updated_at) DESC"
+    )
+    .bind(user_id)
+    .bind(CHAT_PURGE_DAYS)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to fetch archived chats: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(ResponseJson(chats))
+}
+
+/// Permanently delete chats soft-deleted more than CHAT_PURGE_DAYS ago (messages cascade)
+pub async fn purge_deleted_chats(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "DELETE FROM chats WHERE deleted_at IS NOT NULL AND deleted_at < NOW() - make_interval(days => $1)"
+    )
+    .bind(CHAT_PURGE_DAYS)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateChatTitleRequest {
     pub title: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct UpdateChatTitleResponse {
     pub success: bool,
     pub message: String,
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/chats/{chat_id}/title",
+    tag = "chats",
+    params(("chat_id" = i64, Path, description = "Chat id")),
+    request_body = UpdateChatTitleRequest,
+    responses(
+        (status = 200, description = "Title updated", body = UpdateChatTitleResponse),
+        (status = 400, description = "Invalid title"),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Chat not found or not owned by the user")
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn update_chat_title_handler(
     State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
     headers: axum::http::HeaderMap,
@@ -740,16 +1207,32 @@ pub async fn update_chat_title_handler(
         return Err(StatusCode::NOT_FOUND);
     }
 
+    crate::events::emit(user_id, crate::events::ChatEvent::ChatTitleChanged {
+        chat_id,
+        title: request.title.clone(),
+    });
+
     Ok(ResponseJson(UpdateChatTitleResponse {
         success: true,
         message: "Chat title updated successfully".to_string(),
     }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/cached-law",
+    tag = "laws",
+    request_body = GetCachedLawRequest,
+    responses(
+        (status = 200, description = "The cached law, or null when it isn't cached", body = Option<LawCache>),
+        (status = 304, description = "Not modified since the ETag in If-None-Match")
+    )
+)]
 pub async fn get_cached_law_handler(
     State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
     Json(request): Json<GetCachedLawRequest>,
-) -> Result<ResponseJson<Option<LawCache>>, StatusCode> {
+) -> Result<Response, StatusCode> {
     let cached_law = sqlx::query_as::<_, LawCache>(
         "SELECT id, law_name, law_url, content, cached_at, expires_at FROM law_cache WHERE law_name = $1 AND expires_at > NOW() LIMIT 1"
     )
@@ -761,7 +1244,13 @@ pub async fn get_cached_law_handler(
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    Ok(ResponseJson(cached_law))
+    Ok(match cached_law {
+        Some(law) => {
+            let etag = crate::http_cache::law_etag(&law.law_name, law.cached_at);
+            crate::http_cache::conditional_json(&headers, &etag, Some(law))
+        }
+        None => ResponseJson(None::<LawCache>).into_response(),
+    })
 }
 
 pub async fn cache_law(
@@ -771,35 +1260,154 @@ pub async fn cache_law(
     expires_hours: i64,
     pool: &PgPool,
 ) -> Result<(), String> {
+    let mut tx = pool.begin().await
+        .map_err(|e| format!("Failed to start law cache transaction: {}", e))?;
+
     // Insert or replace the cached law with expiration calculation
-    sqlx::query("INSERT INTO law_cache (law_name, law_url, content, expires_at) VALUES ($1, $2, $3, NOW() + INTERVAL '1 hour' * $4) ON CONFLICT (law_name) DO UPDATE SET law_url = $2, content = $3, cached_at = NOW(), expires_at = NOW() + INTERVAL '1 hour' * $4")
-        .bind(law_name)
-        .bind(law_url)
-        .bind(content)
+    let law_id: i64 = sqlx::query_scalar("INSERT INTO law_cache (law_name, law_url, content, expires_at) VALUES ($1, $2, $3, NOW() + INTERVAL '1 hour' * $4) ON CONFLICT (law_name) DO UPDATE SET law_url = $2, content = $3, cached_at = NOW(), expires_at = NOW() + INTERVAL '1 hour' * $4 RETURNING id")
+        .bind(&law_name)
+        .bind(&law_url)
+        .bind(&content)
         .bind(expires_hours)
-        .execute(pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(|e| format!("Failed to cache law: {}", e))?;
 
+    let article_count = index_law_articles(law_id, &content, &mut tx).await?;
+    let amendment = crate::law_revalidation::record_law_version(law_id, &law_name, &law_url, &content, &mut tx).await?;
+
+    tx.commit().await
+        .map_err(|e| format!("Failed to commit law cache: {}", e))?;
+
+    // Subscribers only hear about the amendment once their notifications are visible
+    if let Some(notices) = amendment {
+        notices.announce();
+    }
+
+    println!("✅ DEBUG: Cached '{}' with {} indexed articles", law_name, article_count);
     Ok(())
 }
 
+/// Replace the law_articles rows of a cached law with freshly parsed articles.
+/// Laws that don't split into any articles stay unindexed so lookups keep using the raw text.
+pub async fn index_law_articles(
+    law_id: i64,
+    content: &str,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<usize, String> {
+    let articles = crate::legal_parser::parse_law(content);
+
+    sqlx::query("DELETE FROM law_articles WHERE law_id = $1")
+        .bind(law_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to clear law articles: {}", e))?;
+
+    let mut position = 0;
+    for article in &articles {
+        // Keep the first occurrence of a number (same as the text search did)
+        let result = sqlx::query(
+            "INSERT INTO law_articles (law_id, article_number, content, position, section)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (law_id, article_number) DO NOTHING"
+        )
+        .bind(law_id)
+        .bind(&article.number)
+        .bind(&article.body)
+        .bind(position)
+        .bind(&article.section)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to store law article: {}", e))?;
+
+        if result.rows_affected() > 0 {
+            position += 1;
+        }
+    }
+
+    sqlx::query("UPDATE law_cache SET articles_indexed = $2 WHERE id = $1")
+        .bind(law_id)
+        .bind(position > 0)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to mark law as indexed: {}", e))?;
+
+    Ok(position as usize)
+}
+
+/// Articles of a cached law as parsed at scrape time, in document order.
+/// None when the law isn't cached or didn't split into articles - callers parse the raw text then.
+pub async fn load_law_articles(law_name: &str, pool: &PgPool) -> Result<Option<Vec<LawArticle>>, String> {
+    let rows = crate::metrics::time_db_query(
+        "law_articles",
+        sqlx::query_as::<_, (String, Option<String>, String)>(
+            "SELECT a.article_number, a.section, a.content
+             FROM law_articles a
+             JOIN law_cache c ON c.id = a.law_id
+             WHERE c.law_name = $1 AND c.articles_indexed = true AND a.position >= 0
+             ORDER BY a.position"
+        )
+        .bind(law_name)
+        .fetch_all(pool),
+    )
+    .await
+    .map_err(|e| format!("Failed to load law articles: {}", e))?;
+
+    if rows.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(
+        rows.into_iter()
+            .map(|(number, section, body)| LawArticle { number, section, body })
+            .collect(),
+    ))
+}
+
 // ==================== USAGE TRACKING FUNCTIONS ====================
 
-/// Decrement trial message count for users with limited messages
+/// Decrement trial message count for users with limited messages. With an idempotency key (see
+/// idempotency.rs) the request is charged at most once, however many times the client retries it.
 pub async fn decrement_trial_message(
     user_id: Option<Uuid>,
+    request_key: Option<&str>,
     pool: &PgPool,
 ) -> Result<(), String> {
     let user_id = user_id.ok_or("User not authenticated".to_string())?;
 
-    // For registered users, decrement their trial_messages_remaining
+    let mut tx = pool.begin().await.map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    if let Some(request_key) = request_key {
+        let first_charge = sqlx::query(
+            "UPDATE idempotency_keys SET trial_charged = TRUE
+             WHERE user_id = $1 AND idempotency_key = $2 AND NOT trial_charged"
+        )
+        .bind(user_id)
+        .bind(request_key)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to mark request as charged: {}", e))?
+        .rows_affected() > 0;
+
+        if !first_charge {
+            println!("🔁 Request {} already charged for user {}", request_key, user_id);
+            return Ok(());
+        }
+    }
+
+    // For registered users, decrement their trial_messages_remaining, then purchased messages once
+    // those are used up
     let rows_affected = sqlx::query(
-        "UPDATE users SET trial_messages_remaining = trial_messages_remaining - 1, updated_at = NOW()
-         WHERE id = $1 AND account_type NOT IN ('professional', 'team', 'premium') AND trial_messages_remaining > 0"
+        "UPDATE users SET
+            trial_messages_remaining = CASE WHEN trial_messages_remaining > 0
+                THEN trial_messages_remaining - 1 ELSE trial_messages_remaining END,
+            purchased_messages_remaining = CASE WHEN trial_messages_remaining > 0
+                THEN purchased_messages_remaining ELSE purchased_messages_remaining - 1 END,
+            updated_at = NOW()
+         WHERE id = $1 AND account_type NOT IN ('professional', 'team', 'premium')
+           AND (trial_messages_remaining > 0 OR purchased_messages_remaining > 0)"
     )
     .bind(user_id)
-    .execute(pool)
+    .execute(&mut *tx)
     .await
     .map_err(|e| format!("Failed to decrement user trial messages: {}", e))?
     .rows_affected();
@@ -808,43 +1416,72 @@ pub async fn decrement_trial_message(
         return Err("No messages remaining or user has unlimited plan".to_string());
     }
 
+    tx.commit().await.map_err(|e| format!("Failed to commit message charge: {}", e))?;
+
     Ok(())
 }
 
+/// Whether a user may send another message, and why not
+#[derive(Debug)]
+pub enum MessageAllowance {
+    Allowed,
+    LimitReached,                                     // Trial / plan messages used up
+    CostCapReached(crate::cost_caps::CostCapReached), // Monthly LLM spend reached the plan cap
+}
+
+impl MessageAllowance {
+    /// Turn a refusal into the handler error (plain 429, or 429 with a COST_CAP_REACHED body)
+    pub fn into_result(self, endpoint: &'static str) -> Result<(), ApiError> {
+        match self {
+            MessageAllowance::Allowed => Ok(()),
+            MessageAllowance::LimitReached => {
+                crate::metrics::record_trial_rejection(endpoint);
+                Err(StatusCode::TOO_MANY_REQUESTS.into())
+            }
+            MessageAllowance::CostCapReached(reached) => {
+                crate::metrics::record_cost_cap_rejection(endpoint);
+                Err(ApiError { status: StatusCode::TOO_MANY_REQUESTS, body: Some(reached.error_response()) })
+            }
+        }
+    }
+}
+
 /// Check if user can send a message (has trial messages remaining or is premium)
 pub async fn can_send_message(
     user_id: Option<Uuid>,
     pool: &PgPool,
-) -> Result<bool, String> {
+) -> Result<MessageAllowance, String> {
     let user_id = user_id.ok_or("User not authenticated".to_string())?;
 
     // Auto-reset Individual plan users' monthly limits if needed
-    auto_reset_individual_monthly_limits(pool).await?;
+    crate::metrics::time_db_query("auto_reset_monthly_limits", auto_reset_individual_monthly_limits(pool)).await?;
 
     let user = get_user(Some(user_id), pool)
         .await
         .map_err(|e| format!("Failed to get user: {}", e))?;
 
-    if let Some(user) = user {
-        // Check if paid subscription expired
-        if let Some(expires_at) = user.premium_expires_at {
-            if expires_at < chrono::Utc::now() {
-                // Subscription expired - user reverts to trial behavior
-                // Note: During grace period, subscription_status is "active" so this won't trigger
-                return Ok(user.trial_messages_remaining.unwrap_or(0) > 0);
-            }
-        }
+    let Some(user) = user else {
+        return Ok(MessageAllowance::LimitReached);
+    };
 
+    let has_messages = match user.premium_expires_at {
+        // Subscription expired - user reverts to trial behavior
+        // Note: During a payment grace period premium_expires_at is when it ends (see dunning.rs)
+        Some(expires_at) if expires_at < chrono::Utc::now() => user.has_messages_left(),
         // Professional and Premium users have unlimited messages (if not expired)
         // Grace period users keep access until expiration
-        if matches!(user.account_type.as_str(), "professional" | "premium") {
-            return Ok(true);
-        }
+        _ if matches!(user.account_type.as_str(), "professional" | "premium") => true,
+        // Trial and Individual users must have messages remaining (plan or purchased)
+        _ => user.has_messages_left(),
+    };
+    if !has_messages {
+        return Ok(MessageAllowance::LimitReached);
+    }
 
-        // Trial and Individual users must have messages remaining
-        Ok(user.trial_messages_remaining.unwrap_or(0) > 0)
-    } else {
-        Ok(false)
+    // Unlimited plans are still bounded by their monthly LLM spend
+    match crate::cost_caps::check_cost_cap(user_id, &user.account_type, pool).await? {
+        Some(reached) => Ok(MessageAllowance::CostCapReached(reached)),
+        None => Ok(MessageAllowance::Allowed),
     }
 }
 
@@ -882,19 +1519,156 @@ pub async fn auto_reset_individual_monthly_limits(pool: &PgPool) -> Result<i64,
 
 // ==================== LLM COST TRACKING FUNCTIONS ====================
 
-/// Estimate LLM cost based on character count (rough approximation)
-pub fn estimate_llm_cost(input_chars: usize, output_chars: usize) -> f64 {
-    // Rough estimation: 1 token ≈ 4 characters
-    let input_tokens = input_chars / 4;
-    let output_tokens = output_chars / 4;
+/// Calculate LLM cost from token counts (actual usage from OpenRouter or chars/4 estimate)
+pub fn llm_cost_from_tokens(model: &str, input_tokens: u64, output_tokens: u64) -> f64 {
+    // (input, output) USD per million tokens
+    let (input_price, output_price) = match model {
+        "google/gemini-2.5-flash" => (0.30, 2.50),
+        // Gemini 2.5 Pro pricing: $1.25/M input tokens, $10/M output tokens
+        _ => (1.25, 10.0),
+    };
 
-    // Gemini 2.5 Pro pricing: $1.25/M input tokens, $10/M output tokens
-    let input_cost = (input_tokens as f64 / 1_000_000.0) * 1.25;
-    let output_cost = (output_tokens as f64 / 1_000_000.0) * 10.0;
+    let input_cost = (input_tokens as f64 / 1_000_000.0) * input_price;
+    let output_cost = (output_tokens as f64 / 1_000_000.0) * output_price;
 
     input_cost + output_cost
 }
 
+/// A single LLM call to be written to the llm_requests audit log
+pub struct LlmRequestLog<'a> {
+    pub user_id: Option<Uuid>,
+    pub chat_id: Option<i64>,
+    pub model: &'a str,
+    pub purpose: &'a str,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub tokens_estimated: bool,
+    pub cost_usd: f64,
+    pub latency_ms: u128,
+    pub error: Option<&'a str>,
+}
+
+/// Record an LLM call in the audit log, returning the row ID
+pub async fn record_llm_request(log: LlmRequestLog<'_>, pool: &PgPool) -> Result<i64, String> {
+    let status = if log.error.is_some() { "error" } else { "success" };
+
+    crate::metrics::time_db_query(
+        "record_llm_request",
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO llm_requests
+                (user_id, chat_id, model, purpose, input_tokens, output_tokens, tokens_estimated, cost_usd, latency_ms, status, error)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            RETURNING id
+            "#,
+        )
+        .bind(log.user_id)
+        .bind(log.chat_id)
+        .bind(log.model)
+        .bind(log.purpose)
+        .bind(log.input_tokens.min(i32::MAX as u64) as i32)
+        .bind(log.output_tokens.min(i32::MAX as u64) as i32)
+        .bind(log.tokens_estimated)
+        .bind(log.cost_usd)
+        .bind(log.latency_ms.min(i64::MAX as u128) as i64)
+        .bind(status)
+        .bind(log.error)
+        .fetch_one(pool),
+    )
+    .await
+    .map_err(|e| format!("Failed to record LLM request: {}", e))
+}
+
+/// Attach audit log rows to the assistant message they produced
+pub async fn link_llm_requests_to_message(
+    request_ids: &[i64],
+    message_id: i64,
+    pool: &PgPool,
+) -> Result<(), String> {
+    if request_ids.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query("UPDATE llm_requests SET message_id = $1 WHERE id = ANY($2)")
+        .bind(message_id)
+        .bind(request_ids)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to link LLM requests to message: {}", e))?;
+
+    Ok(())
+}
+
+/// Get the current user's LLM usage breakdown for this month
+#[utoipa::path(
+    get,
+    path = "/api/usage",
+    tag = "usage",
+    responses(
+        (status = 200, description = "LLM usage of the user this month", body = LlmUsageResponse),
+        (status = 401, description = "Missing or invalid token")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[axum::debug_handler]
+pub async fn get_llm_usage_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> Result<ResponseJson<LlmUsageResponse>, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    // Usage aggregates tolerate a few seconds of replica lag
+    let reports = crate::db_routing::analytics(&pool);
+
+    let by_model = sqlx::query_as::<_, LlmUsageByModel>(
+        r#"
+        SELECT model,
+               COUNT(*) AS requests,
+               COALESCE(SUM(input_tokens), 0)::BIGINT AS input_tokens,
+               COALESCE(SUM(output_tokens), 0)::BIGINT AS output_tokens,
+               COALESCE(SUM(cost_usd), 0)::DOUBLE PRECISION AS cost_usd
+        FROM llm_requests
+        WHERE user_id = $1 AND created_at >= date_trunc('month', NOW())
+        GROUP BY model
+        ORDER BY cost_usd DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(reports)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to fetch LLM usage by model: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let recent_requests = sqlx::query_as::<_, LlmRequestRecord>(
+        "SELECT id, chat_id, message_id, model, purpose, input_tokens, output_tokens, tokens_estimated, cost_usd, latency_ms, status, created_at
+         FROM llm_requests
+         WHERE user_id = $1
+         ORDER BY created_at DESC
+         LIMIT 50"
+    )
+    .bind(user_id)
+    .fetch_all(reports)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to fetch recent LLM requests: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(ResponseJson(LlmUsageResponse {
+        month: chrono::Utc::now().format("%Y-%m").to_string(),
+        total_requests: by_model.iter().map(|m| m.requests).sum(),
+        total_input_tokens: by_model.iter().map(|m| m.input_tokens).sum(),
+        total_output_tokens: by_model.iter().map(|m| m.output_tokens).sum(),
+        total_cost_usd: by_model.iter().map(|m| m.cost_usd).sum(),
+        by_model,
+        recent_requests,
+    }))
+}
+
 /// Track LLM usage cost for a user, automatically handling monthly resets
 pub async fn track_llm_cost(
     user_id: Option<Uuid>,
@@ -929,6 +1703,20 @@ pub async fn track_llm_cost(
 }
 
 /// Submit or update feedback for a message
+#[utoipa::path(
+    post,
+    path = "/api/messages/{message_id}/feedback",
+    tag = "messages",
+    params(("message_id" = i64, Path, description = "Message id")),
+    request_body = SubmitFeedbackRequest,
+    responses(
+        (status = 200, description = "Feedback saved", body = SubmitFeedbackResponse),
+        (status = 400, description = "Invalid feedback type"),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Message not found")
+    ),
+    security(("bearer_auth" = []))
+)]
 #[axum::debug_handler]
 pub async fn submit_message_feedback_handler(
     State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
@@ -1003,6 +1791,7 @@ pub async fn submit_message_feedback_handler(
         "UPDATE messages SET message_feedback = $1 WHERE id = $2"
     )
     .bind(&request.feedback_type)
+    .bind(message_id)
     .execute(&pool)
     .await
     .map_err(|e| {
@@ -1012,6 +1801,12 @@ pub async fn submit_message_feedback_handler(
 
     println!("✅ BACKEND: Feedback submitted successfully for message_id={}, updated={}", message_id, updated);
 
+    crate::events::emit(user_id, crate::events::ChatEvent::MessageFeedbackSaved {
+        chat_id,
+        message_id,
+        feedback_type: request.feedback_type.clone(),
+    });
+
     Ok(ResponseJson(crate::models::SubmitFeedbackResponse {
         success: true,
         message: "Feedback submitted successfully".to_string(),
@@ -1111,6 +1906,12 @@ pub async fn permanently_delete_user(
     .fetch_optional(pool)
     .await?;
 
+    // Identities of accounts merged into this one go with it
+    sqlx::query("DELETE FROM auth.users WHERE id IN (SELECT auth_user_id FROM users WHERE merged_into = $1)")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
     if let Some((Some(auth_id),)) = auth_user_id {
         // Delete from Supabase auth.users (cascades to users table via FK)
         sqlx::query("DELETE FROM auth.users WHERE id = $1")
@@ -1186,27 +1987,25 @@ pub async fn is_team_admin(
     Ok(false)
 }
 
-/// Cancel user's subscription (used during account deletion)
-/// Sets subscription to 'cancelled' but keeps access until billing period ends
-pub async fn cancel_subscription(
-    user_id: Uuid,
-    pool: &PgPool,
-) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        r#"
-        UPDATE users
-        SET premium_expires_at = next_billing_date,
-            subscription_type = NULL,
-            subscription_started_at = NULL,
-            next_billing_date = NULL,
-            subscription_status = 'cancelled',
-            updated_at = NOW()
-        WHERE id = $1
-        "#
-    )
-    .bind(user_id)
-    .execute(pool)
-    .await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(())
+    #[test]
+    fn test_chat_cursor_round_trip() {
+        let updated_at = chrono::DateTime::from_timestamp_micros(1_740_830_400_123_456).unwrap();
+        let chat = Chat {
+            id: 42,
+            title: "Otkaz ugovora o radu".to_string(),
+            user_id: None,
+            folder_id: None,
+            jurisdiction: "rs".to_string(),
+            created_at: updated_at,
+            updated_at,
+        };
+
+        assert_eq!(parse_chat_cursor(&chat_cursor(&chat)), Some((updated_at, 42)));
+        assert_eq!(parse_chat_cursor("42"), None);
+        assert_eq!(parse_chat_cursor("abc_42"), None);
+    }
 }
@@ -0,0 +1,83 @@
+// Routing of heavy read-only queries (admin reports, usage analytics) to an optional read replica.
+// With DATABASE_REPLICA_URL set they run on the replica so they don't take primary connections
+// from chat traffic; without it, or while the replica is unreachable, they use the primary pool.
+// Replica data can lag a few seconds, so only queries that tolerate that are routed - anything
+// read back right after a write stays on the primary.
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+static REPLICA: OnceLock<PgPool> = OnceLock::new();
+static REPLICA_HEALTHY: AtomicBool = AtomicBool::new(true);
+
+const HEALTH_CHECK_INTERVAL_SECS: u64 = 30;
+
+/// Set up the replica pool from DATABASE_REPLICA_URL. Connections are opened lazily, so a replica
+/// that is down at startup doesn't stop the server. Returns whether a replica is configured.
+pub fn init_replica(replica_url: Option<&str>) -> bool {
+    let Some(url) = replica_url.map(str::trim).filter(|url| !url.is_empty()) else {
+        return false;
+    };
+
+    // Same lifecycle settings as the primary pool (Fly.io auto-suspension), fewer connections
+    let pool = PgPoolOptions::new()
+        .max_connections(5)
+        .min_connections(0)
+        .acquire_timeout(std::time::Duration::from_secs(10))
+        .max_lifetime(std::time::Duration::from_secs(5 * 60))
+        .idle_timeout(Some(std::time::Duration::from_secs(2 * 60)))
+        .test_before_acquire(true)
+        .connect_lazy(url);
+
+    match pool {
+        Ok(pool) => REPLICA.set(pool).is_ok(),
+        Err(e) => {
+            warn!("⚠️  Invalid DATABASE_REPLICA_URL, analytics queries will use the primary: {}", e);
+            false
+        }
+    }
+}
+
+/// Pool for read-only analytics queries: the replica when configured and healthy, else the primary
+pub fn analytics(primary: &PgPool) -> &PgPool {
+    match REPLICA.get() {
+        Some(replica) if REPLICA_HEALTHY.load(Ordering::Relaxed) => replica,
+        _ => primary,
+    }
+}
+
+/// Probe the replica periodically so analytics fall back to the primary while it's unreachable
+pub async fn start_replica_health_job(shutdown: CancellationToken) {
+    let Some(replica) = REPLICA.get() else {
+        return;
+    };
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(HEALTH_CHECK_INTERVAL_SECS));
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.cancelled() => {
+                info!("🛑 Replica health job stopped");
+                return;
+            }
+        }
+
+        let probe = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            sqlx::query_scalar::<_, i32>("SELECT 1").fetch_one(replica),
+        )
+        .await;
+        let healthy = matches!(probe, Ok(Ok(_)));
+
+        if REPLICA_HEALTHY.swap(healthy, Ordering::Relaxed) != healthy {
+            if healthy {
+                info!("✅ Read replica reachable again - analytics queries back on the replica");
+            } else {
+                warn!("⚠️  Read replica unreachable - analytics queries use the primary until it recovers");
+            }
+        }
+    }
+}
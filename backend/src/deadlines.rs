@@ -0,0 +1,298 @@
+// Deadline calculator: procedural deadlines (rokovi za žalbu, prigovor, tužbu) and limitation periods
+// (zastarelost) computed with Serbian counting rules on top of the holiday calendar:
+// - the day of the event (dostavljanje, saznanje) is not counted
+// - month/year periods end on the day with the same number, or the month's last day if there is none
+// - procedural deadlines ending on a weekend or public holiday move to the next working day
+// Returned as a structured breakdown so the same computation can back a UI and an LLM tool call.
+use axum::{extract::Json, http::StatusCode, response::Json as ResponseJson};
+use chrono::{Datelike, Duration, Months, NaiveDate, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::calendar;
+use crate::models::ErrorResponse;
+
+/// Court summer vacation (sudski odmor) - non-urgent hearings aren't scheduled in this window
+const COURT_VACATION_START: (u32, u32) = (7, 15);
+const COURT_VACATION_END: (u32, u32) = (8, 15);
+
+/// Longest custom period accepted, in days (limitation periods go up to 10 years)
+const MAX_PERIOD_DAYS: i64 = 366 * 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeriodUnit {
+    Days,
+    Months,
+    Years,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcedureRule {
+    pub key: &'static str,
+    pub name: &'static str,
+    pub legal_basis: &'static str,
+    pub amount: u32,
+    pub unit: PeriodUnit,
+    pub counted_from: &'static str,
+    // Procedural deadlines move off non-working days; substantive periods (zastarelost) don't
+    pub moves_to_working_day: bool,
+}
+
+/// Deadlines the calculator knows by key
+pub const PROCEDURES: &[ProcedureRule] = &[
+    ProcedureRule { key: "zalba_parnica", name: "Žalba na presudu u parničnom postupku", legal_basis: "Zakon o parničnom postupku", amount: 15, unit: PeriodUnit::Days, counted_from: "dostavljanja prepisa presude", moves_to_working_day: true },
+    ProcedureRule { key: "zalba_radni_spor", name: "Žalba u radnom sporu", legal_basis: "Zakon o parničnom postupku", amount: 8, unit: PeriodUnit::Days, counted_from: "dostavljanja prepisa presude", moves_to_working_day: true },
+    ProcedureRule { key: "odgovor_na_tuzbu", name: "Odgovor na tužbu", legal_basis: "Zakon o parničnom postupku", amount: 30, unit: PeriodUnit::Days, counted_from: "dostavljanja tužbe", moves_to_working_day: true },
+    ProcedureRule { key: "revizija", name: "Revizija", legal_basis: "Zakon o parničnom postupku", amount: 30, unit: PeriodUnit::Days, counted_from: "dostavljanja drugostepene presude", moves_to_working_day: true },
+    ProcedureRule { key: "zalba_krivicni", name: "Žalba na presudu u krivičnom postupku", legal_basis: "Zakonik o krivičnom postupku", amount: 15, unit: PeriodUnit::Days, counted_from: "dostavljanja prepisa presude", moves_to_working_day: true },
+    ProcedureRule { key: "zalba_prekrsajni", name: "Žalba na presudu u prekršajnom postupku", legal_basis: "Zakon o prekršajima", amount: 8, unit: PeriodUnit::Days, counted_from: "dostavljanja presude", moves_to_working_day: true },
+    ProcedureRule { key: "zalba_upravni", name: "Žalba na rešenje u upravnom postupku", legal_basis: "Zakon o opštem upravnom postupku", amount: 15, unit: PeriodUnit::Days, counted_from: "dostavljanja rešenja", moves_to_working_day: true },
+    ProcedureRule { key: "tuzba_upravni_spor", name: "Tužba u upravnom sporu", legal_basis: "Zakon o upravnim sporovima", amount: 30, unit: PeriodUnit::Days, counted_from: "dostavljanja konačnog upravnog akta", moves_to_working_day: true },
+    ProcedureRule { key: "prigovor_izvrsenje", name: "Prigovor na rešenje o izvršenju", legal_basis: "Zakon o izvršenju i obezbeđenju", amount: 8, unit: PeriodUnit::Days, counted_from: "dostavljanja rešenja", moves_to_working_day: true },
+    ProcedureRule { key: "tuzba_radni_odnos", name: "Tužba za zaštitu prava iz radnog odnosa", legal_basis: "Zakon o radu", amount: 60, unit: PeriodUnit::Days, counted_from: "dostavljanja rešenja, odnosno saznanja za povredu prava", moves_to_working_day: true },
+    ProcedureRule { key: "zastarelost_opsta", name: "Opšti rok zastarelosti potraživanja", legal_basis: "Zakon o obligacionim odnosima", amount: 10, unit: PeriodUnit::Years, counted_from: "dospelosti potraživanja", moves_to_working_day: false },
+    ProcedureRule { key: "zastarelost_privredni_ugovori", name: "Zastarelost potraživanja iz ugovora u prometu robe i usluga", legal_basis: "Zakon o obligacionim odnosima", amount: 3, unit: PeriodUnit::Years, counted_from: "dospelosti potraživanja", moves_to_working_day: false },
+    ProcedureRule { key: "zastarelost_povremena_davanja", name: "Zastarelost povremenih potraživanja", legal_basis: "Zakon o obligacionim odnosima", amount: 3, unit: PeriodUnit::Years, counted_from: "dospelosti svakog pojedinačnog davanja", moves_to_working_day: false },
+    ProcedureRule { key: "zastarelost_naknada_stete", name: "Zastarelost potraživanja naknade štete (subjektivni rok)", legal_basis: "Zakon o obligacionim odnosima", amount: 3, unit: PeriodUnit::Years, counted_from: "saznanja za štetu i učinioca", moves_to_working_day: false },
+    ProcedureRule { key: "zastarelost_zakupnina", name: "Zastarelost potraživanja zakupnine", legal_basis: "Zakon o obligacionim odnosima", amount: 3, unit: PeriodUnit::Years, counted_from: "dospelosti zakupnine", moves_to_working_day: false },
+    ProcedureRule { key: "zastarelost_komunalne_usluge", name: "Zastarelost potraživanja za komunalne usluge", legal_basis: "Zakon o obligacionim odnosima", amount: 1, unit: PeriodUnit::Years, counted_from: "dospelosti potraživanja", moves_to_working_day: false },
+];
+
+#[derive(Debug, Deserialize)]
+pub struct DeadlineRequest {
+    pub event_date: NaiveDate,     // Dostavljanje / dospelost / saznanje - not counted itself
+    pub procedure: Option<String>, // Key from PROCEDURES; omit for a custom period
+    pub amount: Option<u32>,       // Custom period (overrides the procedure's period when set)
+    pub unit: Option<PeriodUnit>,
+    pub suspend_during_court_vacation: Option<bool>, // Don't count days of the court vacation
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SkippedDay {
+    pub date: NaiveDate,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeadlineBreakdown {
+    pub procedure: Option<String>,
+    pub name: String,
+    pub legal_basis: Option<String>,
+    pub period: String, // e.g. "15 dana"
+    pub event_date: NaiveDate,
+    pub counting_starts: NaiveDate, // First counted day
+    pub nominal_end: NaiveDate,     // Last day before any adjustment
+    pub deadline: NaiveDate,
+    pub deadline_weekday: &'static str,
+    pub court_vacation_days: i64, // Days added because the court vacation was not counted
+    pub skipped_days: Vec<SkippedDay>, // Non-working days the deadline was moved over
+    pub days_remaining: i64,      // From today (Belgrade); negative once expired
+    pub notes: Vec<String>,
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "ponedeljak",
+        Weekday::Tue => "utorak",
+        Weekday::Wed => "sreda",
+        Weekday::Thu => "četvrtak",
+        Weekday::Fri => "petak",
+        Weekday::Sat => "subota",
+        Weekday::Sun => "nedelja",
+    }
+}
+
+fn period_label(amount: u32, unit: PeriodUnit) -> String {
+    let word = match unit {
+        PeriodUnit::Days if amount % 10 == 1 && amount % 100 != 11 => "dan",
+        PeriodUnit::Days => "dana",
+        PeriodUnit::Months if amount % 10 == 1 && amount % 100 != 11 => "mesec",
+        PeriodUnit::Months if (2..=4).contains(&(amount % 10)) && !(12..=14).contains(&(amount % 100)) => "meseca",
+        PeriodUnit::Months => "meseci",
+        PeriodUnit::Years if amount % 10 == 1 && amount % 100 != 11 => "godina",
+        PeriodUnit::Years if (2..=4).contains(&(amount % 10)) && !(12..=14).contains(&(amount % 100)) => "godine",
+        PeriodUnit::Years => "godina",
+    };
+    format!("{} {}", amount, word)
+}
+
+fn in_court_vacation(date: NaiveDate) -> bool {
+    let day = (date.month(), date.day());
+    day >= COURT_VACATION_START && day <= COURT_VACATION_END
+}
+
+/// Court vacation days in (after, until]
+fn court_vacation_days_between(after: NaiveDate, until: NaiveDate) -> i64 {
+    after.iter_days().skip(1).take_while(|d| *d <= until).filter(|d| in_court_vacation(*d)).count() as i64
+}
+
+fn add_period(from: NaiveDate, amount: u32, unit: PeriodUnit) -> Option<NaiveDate> {
+    match unit {
+        PeriodUnit::Days => from.checked_add_signed(Duration::days(amount as i64)),
+        // chrono clamps to the last day of shorter months, which is the rule for month/year periods
+        PeriodUnit::Months => from.checked_add_months(Months::new(amount)),
+        PeriodUnit::Years => from.checked_add_months(Months::new(amount.checked_mul(12)?)),
+    }
+}
+
+fn non_working_reason(date: NaiveDate) -> String {
+    calendar::public_holidays(date.year())
+        .into_iter()
+        .find(|h| h.date == date)
+        .map(|h| h.name.to_string())
+        .unwrap_or_else(|| weekday_name(date.weekday()).to_string())
+}
+
+/// Compute a deadline. Errors are user-facing messages (bad procedure key or period).
+pub fn compute_deadline(request: &DeadlineRequest, today: NaiveDate) -> Result<DeadlineBreakdown, String> {
+    let rule = match request.procedure.as_deref() {
+        Some(key) => Some(PROCEDURES.iter().find(|p| p.key == key).ok_or_else(|| format!("Nepoznat postupak: {}", key))?),
+        None => None,
+    };
+
+    let amount = request.amount.or(rule.map(|r| r.amount)).ok_or("Navedite postupak ili trajanje roka")?;
+    let unit = request.unit.or(rule.map(|r| r.unit)).unwrap_or(PeriodUnit::Days);
+    if amount == 0 {
+        return Err("Trajanje roka mora biti veće od nule".to_string());
+    }
+
+    let nominal_end = add_period(request.event_date, amount, unit)
+        .filter(|end| (*end - request.event_date).num_days() <= MAX_PERIOD_DAYS)
+        .ok_or("Rok je predug")?;
+
+    let mut notes = Vec::new();
+    if let Some(rule) = rule {
+        notes.push(format!("Rok se računa od dana {}; taj dan se ne uračunava.", rule.counted_from));
+    } else {
+        notes.push("Dan događaja se ne uračunava u rok.".to_string());
+    }
+    if unit != PeriodUnit::Days {
+        notes.push("Rok određen u mesecima ili godinama ističe istog datuma kao dan događaja, odnosno poslednjeg dana u mesecu ako tog datuma nema.".to_string());
+    }
+
+    // Not counting the court vacation pushes the end out, which can reach into more vacation days
+    let mut deadline = nominal_end;
+    let mut court_vacation_days = 0;
+    if request.suspend_during_court_vacation.unwrap_or(false) {
+        loop {
+            let vacation_days = court_vacation_days_between(request.event_date, deadline);
+            let extended = nominal_end + Duration::days(vacation_days);
+            if extended == deadline {
+                break;
+            }
+            court_vacation_days = vacation_days;
+            deadline = extended;
+        }
+        if court_vacation_days > 0 {
+            notes.push(format!("Rok je produžen za {} dana sudskog odmora.", court_vacation_days));
+        }
+    }
+
+    let moves_to_working_day = rule.is_none_or(|r| r.moves_to_working_day);
+    let mut skipped_days = Vec::new();
+    if moves_to_working_day {
+        while !calendar::is_working_day(deadline) {
+            skipped_days.push(SkippedDay { date: deadline, reason: non_working_reason(deadline) });
+            deadline += Duration::days(1);
+        }
+        if !skipped_days.is_empty() {
+            notes.push("Poslednji dan roka pada na neradni dan, pa rok ističe prvog narednog radnog dana.".to_string());
+        }
+    } else if !calendar::is_working_day(deadline) {
+        notes.push("Rok zastarelosti se ne pomera zbog neradnog dana - preporučuje se preduzimanje radnje ranije.".to_string());
+    }
+
+    if !request.suspend_during_court_vacation.unwrap_or(false) && in_court_vacation(deadline) {
+        notes.push("Rok ističe za vreme sudskog odmora - rokovi teku i tada, a sudovi postupaju samo u hitnim predmetima.".to_string());
+    }
+
+    Ok(DeadlineBreakdown {
+        procedure: rule.map(|r| r.key.to_string()),
+        name: rule.map(|r| r.name.to_string()).unwrap_or_else(|| "Rok".to_string()),
+        legal_basis: rule.map(|r| r.legal_basis.to_string()),
+        period: period_label(amount, unit),
+        event_date: request.event_date,
+        counting_starts: request.event_date + Duration::days(1),
+        nominal_end,
+        deadline,
+        deadline_weekday: weekday_name(deadline.weekday()),
+        court_vacation_days,
+        skipped_days,
+        days_remaining: (deadline - today).num_days(),
+        notes,
+    })
+}
+
+/// Compute a deadline for an event date and procedure type
+pub async fn deadline_handler(
+    Json(request): Json<DeadlineRequest>,
+) -> Result<ResponseJson<DeadlineBreakdown>, (StatusCode, Json<ErrorResponse>)> {
+    compute_deadline(&request, calendar::local_today(Utc::now()))
+        .map(ResponseJson)
+        .map_err(|message| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse { error: "INVALID_DEADLINE".to_string(), message, details: None }),
+            )
+        })
+}
+
+/// Procedure types the calculator knows, for the UI picker
+pub async fn procedures_handler() -> ResponseJson<&'static [ProcedureRule]> {
+    ResponseJson(PROCEDURES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn request(event_date: NaiveDate, procedure: &str) -> DeadlineRequest {
+        DeadlineRequest {
+            event_date,
+            procedure: Some(procedure.to_string()),
+            amount: None,
+            unit: None,
+            suspend_during_court_vacation: None,
+        }
+    }
+
+    #[test]
+    fn test_deadline_moves_past_holidays_and_weekend() {
+        // Delivered 16.4.2025: 15 days end on Thursday 1.5. (Praznik rada, 1-2.5.) -> weekend -> Monday 5.5.
+        let result = compute_deadline(&request(date(2025, 4, 16), "zalba_parnica"), date(2025, 4, 16)).unwrap();
+        assert_eq!(result.nominal_end, date(2025, 5, 1));
+        assert_eq!(result.deadline, date(2025, 5, 5));
+        assert_eq!(result.skipped_days.len(), 4);
+        assert_eq!(result.skipped_days[0].reason, "Praznik rada");
+        assert_eq!(result.days_remaining, 19);
+    }
+
+    #[test]
+    fn test_limitation_period_does_not_move() {
+        // 3 years from 29.2.2024 ends on the last day of February
+        let result = compute_deadline(&request(date(2024, 2, 29), "zastarelost_privredni_ugovori"), date(2024, 3, 1)).unwrap();
+        assert_eq!(result.deadline, date(2027, 2, 28));
+        assert!(result.skipped_days.is_empty());
+        assert_eq!(result.period, "3 godine");
+    }
+
+    #[test]
+    fn test_court_vacation_extends_custom_period() {
+        let request = DeadlineRequest {
+            event_date: date(2026, 7, 10),
+            procedure: None,
+            amount: Some(10),
+            unit: Some(PeriodUnit::Days),
+            suspend_during_court_vacation: Some(true),
+        };
+        let result = compute_deadline(&request, date(2026, 7, 10)).unwrap();
+        // 4 days before the vacation (11-14.7.), the other 6 after it ends on 15.8.
+        assert_eq!(result.court_vacation_days, 32);
+        assert_eq!(result.deadline, date(2026, 8, 21));
+        assert!(compute_deadline(&DeadlineRequest { amount: Some(0), ..request }, date(2026, 7, 10)).is_err());
+    }
+}
@@ -0,0 +1,305 @@
+use axum::{
+    extract::{Json, Multipart, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::database;
+use crate::documents::{self, DocumentExtractResponse};
+
+type AppState = (PgPool, String, String, String, Option<String>); // (pool, openrouter_api_key, openai_api_key, jwt_secret, supabase_jwt_secret)
+
+/// Counterparty uploads are capped lower than the user's own uploads
+const MAX_REQUESTED_UPLOAD_BYTES: usize = 10 * 1024 * 1024; // 10MB
+
+const DEFAULT_EXPIRY_HOURS: i64 = 72;
+const MAX_EXPIRY_HOURS: i64 = 14 * 24;
+const MAX_UPLOADS_PER_REQUEST: i32 = 10;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateDocumentRequestRequest {
+    pub note: Option<String>,             // Shown to the counterparty on the upload page
+    pub expires_in_hours: Option<i64>,    // Default 72, max 14 days
+    pub max_uploads: Option<i32>,         // Default 1, max 10
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DocumentRequest {
+    pub id: Uuid,
+    pub chat_id: i64,
+    pub note: Option<String>,
+    pub max_uploads: i32,
+    pub uploads_received: i32,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmailTemplate {
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateDocumentRequestResponse {
+    pub request: DocumentRequest,
+    pub upload_url: String,
+    pub email_template: EmailTemplate, // Ready-to-send email the user can paste into their mail client
+}
+
+/// What the counterparty sees on the upload page - no chat content
+#[derive(Debug, Serialize)]
+pub struct PublicDocumentRequest {
+    pub requested_by: String,
+    pub note: Option<String>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub uploads_remaining: i32,
+    pub max_file_bytes: usize,
+    pub accepted_types: Vec<&'static str>,
+}
+
+fn upload_url(request_id: Uuid) -> String {
+    format!("https://chat.normaai.rs/upload-document.html?request={}", request_id)
+}
+
+fn build_email_template(requester: &str, note: Option<&str>, upload_url: &str, expires_at: chrono::DateTime<chrono::Utc>) -> EmailTemplate {
+    let note = note
+        .map(|n| format!("\n\nNapomena: {}", n))
+        .unwrap_or_default();
+
+    EmailTemplate {
+        subject: format!("Zahtev za dostavljanje dokumenta - {}", requester),
+        body: format!(
+            "Poštovani,\n\nmolim Vas da traženi dokument otpremite putem sledećeg bezbednog linka, umesto slanja kao prilog elektronske pošte:\n\n{}{}\n\nLink važi do {} (PDF, DOCX ili TXT, najviše {} MB).\n\nS poštovanjem,\n{}",
+            upload_url,
+            note,
+            expires_at.format("%d.%m.%Y. %H:%M"),
+            MAX_REQUESTED_UPLOAD_BYTES / (1024 * 1024),
+            requester
+        ),
+    }
+}
+
+/// Create an expiring upload link for a chat, plus an email template to send it with
+pub async fn create_document_request_handler(
+    State((pool, _, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(chat_id): Path<i64>,
+    Json(request): Json<CreateDocumentRequestRequest>,
+) -> Result<ResponseJson<CreateDocumentRequestResponse>, StatusCode> {
+    let user_id = database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    // Documents land in the user's document set, so the same plans that can upload can request
+    let user = database::get_user(Some(user_id), &pool).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    if !user.can_upload_documents() {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let owns_chat: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM chats WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL)"
+    )
+    .bind(chat_id)
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to check chat ownership: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !owns_chat {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let expires_in_hours = request.expires_in_hours.unwrap_or(DEFAULT_EXPIRY_HOURS);
+    let max_uploads = request.max_uploads.unwrap_or(1);
+    let note = request.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    if !(1..=MAX_EXPIRY_HOURS).contains(&expires_in_hours)
+        || !(1..=MAX_UPLOADS_PER_REQUEST).contains(&max_uploads)
+        || note.is_some_and(|n| n.len() > 1000)
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let document_request = sqlx::query_as::<_, DocumentRequest>(
+        "INSERT INTO document_requests (user_id, chat_id, note, max_uploads, expires_at)
+         VALUES ($1, $2, $3, $4, NOW() + make_interval(hours => $5))
+         RETURNING id, chat_id, note, max_uploads, uploads_received, expires_at, revoked_at, created_at"
+    )
+    .bind(user_id)
+    .bind(chat_id)
+    .bind(note)
+    .bind(max_uploads)
+    .bind(expires_in_hours as i32)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to create document request: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let upload_url = upload_url(document_request.id);
+    let requester = user.name.clone().unwrap_or_else(|| user.email.clone());
+    let email_template = build_email_template(&requester, note, &upload_url, document_request.expires_at);
+
+    println!("📨 Document request {} created for chat {}", document_request.id, chat_id);
+
+    Ok(ResponseJson(CreateDocumentRequestResponse {
+        request: document_request,
+        upload_url,
+        email_template,
+    }))
+}
+
+/// List upload links created for a chat
+pub async fn list_document_requests_handler(
+    State((pool, _, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(chat_id): Path<i64>,
+) -> Result<ResponseJson<Vec<DocumentRequest>>, StatusCode> {
+    let user_id = database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let requests = sqlx::query_as::<_, DocumentRequest>(
+        "SELECT id, chat_id, note, max_uploads, uploads_received, expires_at, revoked_at, created_at
+         FROM document_requests WHERE chat_id = $1 AND user_id = $2
+         ORDER BY created_at DESC"
+    )
+    .bind(chat_id)
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to list document requests: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(ResponseJson(requests))
+}
+
+/// Revoke an upload link before it expires
+pub async fn revoke_document_request_handler(
+    State((pool, _, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(request_id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let user_id = database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let result = sqlx::query(
+        "UPDATE document_requests SET revoked_at = NOW() WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+    )
+    .bind(request_id)
+    .bind(user_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to revoke document request: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// Public: details for the counterparty's upload page. Expired, revoked or used-up links are 404.
+pub async fn get_public_document_request_handler(
+    State((pool, _, _, _, _)): State<AppState>,
+    Path(request_id): Path<Uuid>,
+) -> Result<ResponseJson<PublicDocumentRequest>, StatusCode> {
+    let row = sqlx::query_as::<_, (Option<String>, String, Option<String>, chrono::DateTime<chrono::Utc>, i32)>(
+        "SELECT u.name, u.email, r.note, r.expires_at, r.max_uploads - r.uploads_received
+         FROM document_requests r
+         JOIN users u ON u.id = r.user_id
+         WHERE r.id = $1 AND r.revoked_at IS NULL AND r.expires_at > NOW() AND r.uploads_received < r.max_uploads"
+    )
+    .bind(request_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to load document request: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let (name, email, note, expires_at, uploads_remaining) = row;
+
+    Ok(ResponseJson(PublicDocumentRequest {
+        requested_by: name.unwrap_or(email),
+        note,
+        expires_at,
+        uploads_remaining,
+        max_file_bytes: MAX_REQUESTED_UPLOAD_BYTES,
+        accepted_types: vec!["pdf", "docx", "txt"],
+    }))
+}
+
+/// Public: counterparty upload through a document request link. The document is added to the
+/// requesting user's chat and they are notified over the events socket.
+pub async fn upload_requested_document_handler(
+    State((pool, _, _, _, _)): State<AppState>,
+    Path(request_id): Path<Uuid>,
+    mut multipart: Multipart,
+) -> Result<ResponseJson<DocumentExtractResponse>, StatusCode> {
+    // Claim an upload slot atomically so concurrent uploads can't exceed max_uploads. The link is
+    // checked before the body is read, so invalid links can't be used to push files at the server.
+    let claimed = sqlx::query_as::<_, (Uuid, i64)>(
+        "UPDATE document_requests SET uploads_received = uploads_received + 1
+         WHERE id = $1 AND revoked_at IS NULL AND expires_at > NOW() AND uploads_received < max_uploads
+         RETURNING user_id, chat_id"
+    )
+    .bind(request_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to claim document request upload: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let Some((user_id, chat_id)) = claimed else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    let stored = match documents::read_upload_field(&mut multipart, MAX_REQUESTED_UPLOAD_BYTES).await {
+        Ok((filename, content_type, bytes)) => {
+            documents::store_document(user_id, Some(chat_id), filename, content_type, bytes, MAX_REQUESTED_UPLOAD_BYTES, &pool).await
+        }
+        Err(status) => Err(status),
+    };
+    let document = match stored {
+        Ok(document) => document,
+        Err(status) => {
+            // Rejected files don't use up the link
+            let released = sqlx::query("UPDATE document_requests SET uploads_received = uploads_received - 1 WHERE id = $1")
+                .bind(request_id)
+                .execute(&pool)
+                .await;
+            if let Err(e) = released {
+                eprintln!("Failed to release document request upload: {}", e);
+            }
+            return Err(status);
+        }
+    };
+
+    println!("📨 Document request {}: received '{}' for chat {}", request_id, document.filename, chat_id);
+
+    crate::events::emit(user_id, crate::events::ChatEvent::DocumentReceived {
+        chat_id,
+        document_id: document.document_id,
+        filename: document.filename.clone(),
+    });
+
+    Ok(ResponseJson(document))
+}
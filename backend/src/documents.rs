@@ -0,0 +1,292 @@
+use axum::{
+    extract::{Multipart, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::io::Read;
+use uuid::Uuid;
+
+use crate::database;
+
+type AppState = (PgPool, String, String, String, Option<String>); // (pool, openrouter_api_key, openai_api_key, jwt_secret, supabase_jwt_secret)
+
+/// Maximum upload size accepted for extraction
+const MAX_DOCUMENT_BYTES: usize = 20 * 1024 * 1024; // 20MB
+
+/// Extracted text beyond this is truncated - more would not fit the LLM context anyway
+const MAX_EXTRACTED_CHARS: usize = 300_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DocumentKind {
+    Pdf,
+    Docx,
+    Text,
+}
+
+impl DocumentKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DocumentKind::Pdf => "pdf",
+            DocumentKind::Docx => "docx",
+            DocumentKind::Text => "txt",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocumentExtractResponse {
+    pub document_id: Uuid,
+    pub filename: String,
+    pub document_type: String,
+    pub char_count: usize,
+    pub truncated: bool,
+    pub preview: String, // First ~500 chars so the client can show what was extracted
+}
+
+/// Upload a PDF/DOCX/TXT file and extract its text server-side.
+/// Returns a document_id that can be sent with /api/question instead of raw document_content.
+pub async fn extract_document_handler(
+    State((pool, _, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Result<ResponseJson<DocumentExtractResponse>, StatusCode> {
+    println!("📄 ================== DOCUMENT EXTRACTION REQUEST ==================");
+
+    let user_id = database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    // Same permission as sending document_content with a question
+    let user = database::get_user(Some(user_id), &pool).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    if !user.can_upload_documents() {
+        eprintln!("❌ SECURITY: User with account_type '{}' attempted document extraction - BLOCKED", user.account_type);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let (filename, content_type, bytes) = read_upload_field(&mut multipart, MAX_DOCUMENT_BYTES).await?;
+    let document = store_document(user_id, None, filename, content_type, bytes, MAX_DOCUMENT_BYTES, &pool).await?;
+
+    Ok(ResponseJson(document))
+}
+
+/// Read the "file" field of a multipart upload: (filename, content_type, bytes). Stops reading with
+/// 413 once the file is larger than `max_bytes`, so oversized uploads aren't buffered.
+pub async fn read_upload_field(multipart: &mut Multipart, max_bytes: usize) -> Result<(String, Option<String>, Vec<u8>), StatusCode> {
+    while let Some(mut field) = multipart.next_field().await.map_err(|e| {
+        println!("❌ DEBUG: Invalid multipart body: {}", e);
+        StatusCode::BAD_REQUEST
+    })? {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let filename = field.file_name().unwrap_or("document").to_string();
+        let content_type = field.content_type().map(|c| c.to_string());
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.chunk().await.map_err(|e| {
+            println!("❌ DEBUG: Failed to read uploaded file: {}", e);
+            StatusCode::BAD_REQUEST
+        })? {
+            if bytes.len() + chunk.len() > max_bytes {
+                return Err(StatusCode::PAYLOAD_TOO_LARGE);
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        return Ok((filename, content_type, bytes));
+    }
+
+    Err(StatusCode::BAD_REQUEST)
+}
+
+/// Validate an uploaded PDF/DOCX/TXT file, extract its text and store it for the user
+/// (optionally attached to one of their chats)
+pub async fn store_document(
+    user_id: Uuid,
+    chat_id: Option<i64>,
+    filename: String,
+    content_type: Option<String>,
+    bytes: Vec<u8>,
+    max_bytes: usize,
+    pool: &PgPool,
+) -> Result<DocumentExtractResponse, StatusCode> {
+    if bytes.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if bytes.len() > max_bytes {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    let kind = detect_document_kind(&filename, content_type.as_deref(), &bytes)
+        .ok_or(StatusCode::UNSUPPORTED_MEDIA_TYPE)?;
+
+    println!("🔍 DEBUG: Extracting {} document '{}' ({} bytes)", kind.as_str(), filename, bytes.len());
+
+    let file_size = bytes.len();
+    // PDF/DOCX parsing is CPU-bound - keep it off the async runtime
+    let extracted = tokio::task::spawn_blocking(move || extract_text(kind, &bytes))
+        .await
+        .map_err(|e| {
+            // pdf-extract can panic on malformed files
+            println!("❌ DEBUG: Document extraction panicked: {}", e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        })?
+        .map_err(|e| {
+            println!("❌ DEBUG: Document extraction failed: {}", e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        })?;
+
+    let text = normalize_extracted_text(&extracted);
+    if text.is_empty() {
+        // Scanned PDFs without a text layer end up here
+        println!("❌ DEBUG: No text found in document '{}'", filename);
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let truncated = text.chars().count() > MAX_EXTRACTED_CHARS;
+    let text: String = if truncated {
+        text.chars().take(MAX_EXTRACTED_CHARS).collect()
+    } else {
+        text
+    };
+    let char_count = text.chars().count();
+
+    let document_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO documents (user_id, chat_id, filename, document_type, content_type, file_size, extracted_text, char_count, truncated)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+         RETURNING id"
+    )
+    .bind(user_id)
+    .bind(chat_id)
+    .bind(&filename)
+    .bind(kind.as_str())
+    .bind(&content_type)
+    .bind(file_size as i64)
+    .bind(&text)
+    .bind(char_count as i32)
+    .bind(truncated)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to store extracted document: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    println!("✅ DEBUG: Document {} extracted: {} chars (truncated: {})", document_id, char_count, truncated);
+
+    Ok(DocumentExtractResponse {
+        document_id,
+        filename,
+        document_type: kind.as_str().to_string(),
+        char_count,
+        truncated,
+        preview: text.chars().take(500).collect(),
+    })
+}
+
+/// Load the extracted text of a document owned by the user: (filename, text, truncated)
+pub async fn get_document_text(
+    document_id: Uuid,
+    user_id: Uuid,
+    pool: &PgPool,
+) -> Result<Option<(String, String, bool)>, String> {
+    sqlx::query_as::<_, (String, String, bool)>(
+        "SELECT filename, extracted_text, truncated FROM documents WHERE id = $1 AND user_id = $2"
+    )
+    .bind(document_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load document: {}", e))
+}
+
+fn detect_document_kind(filename: &str, content_type: Option<&str>, bytes: &[u8]) -> Option<DocumentKind> {
+    // Magic bytes first - clients are not always accurate about names and types
+    if bytes.starts_with(b"%PDF") {
+        return Some(DocumentKind::Pdf);
+    }
+
+    let extension = filename.rsplit('.').next().unwrap_or("").to_lowercase();
+    let content_type = content_type.unwrap_or("");
+
+    if bytes.starts_with(b"PK")
+        && (extension == "docx" || content_type.contains("wordprocessingml"))
+    {
+        return Some(DocumentKind::Docx);
+    }
+
+    if extension == "txt" || content_type.starts_with("text/plain") {
+        return Some(DocumentKind::Text);
+    }
+
+    None
+}
+
+fn extract_text(kind: DocumentKind, bytes: &[u8]) -> Result<String, String> {
+    match kind {
+        DocumentKind::Pdf => pdf_extract::extract_text_from_mem(bytes)
+            .map_err(|e| format!("Failed to extract PDF text: {}", e)),
+        DocumentKind::Docx => extract_docx_text(bytes),
+        DocumentKind::Text => Ok(String::from_utf8_lossy(bytes).to_string()),
+    }
+}
+
+/// Extract paragraph text from word/document.xml inside a DOCX archive
+fn extract_docx_text(bytes: &[u8]) -> Result<String, String> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))
+        .map_err(|e| format!("Invalid DOCX archive: {}", e))?;
+
+    let mut document_xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .map_err(|e| format!("DOCX is missing word/document.xml: {}", e))?
+        .read_to_string(&mut document_xml)
+        .map_err(|e| format!("Failed to read DOCX content: {}", e))?;
+
+    Ok(docx_xml_to_text(&document_xml))
+}
+
+fn docx_xml_to_text(xml: &str) -> String {
+    use regex::Regex;
+
+    // Paragraph ends and explicit breaks become newlines, tabs become tabs, other XML tags are dropped
+    let paragraph_end = Regex::new(r"</w:p>|<w:br\s*/>|<w:cr\s*/>").unwrap();
+    let tab = Regex::new(r"<w:tab\s*/>").unwrap();
+    let tag = Regex::new(r"<[^>]+>").unwrap();
+
+    let text = paragraph_end.replace_all(xml, "\n");
+    let text = tab.replace_all(&text, "\t");
+    let text = tag.replace_all(&text, "");
+
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Trim trailing whitespace and collapse runs of blank lines left over by extraction
+fn normalize_extracted_text(text: &str) -> String {
+    let mut result = String::new();
+    let mut blank_lines = 0;
+
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            blank_lines += 1;
+            if blank_lines > 1 {
+                continue;
+            }
+        } else {
+            blank_lines = 0;
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+
+    result.trim().to_string()
+}
@@ -0,0 +1,191 @@
+// Dunning: renewals whose payment failed. When the store reports a billing problem (RevenueCat
+// BILLING_ISSUE, or a subscriber in the store's own grace period) the subscription moves to
+// 'grace' and the plan stays usable for GRACE_PERIOD_DAYS, or until the store's grace period ends
+// if that is later, while the charge is retried. If it still isn't paid by then the dunning job
+// moves the subscription to 'past_due' and the account to the free tier. A successful renewal at
+// any point makes it 'active' again. Each step is announced in-app and by e-mail (kind 'billing').
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::notifications::{self, NotificationKind};
+use crate::subscriptions::{SubscriptionState, Transition};
+
+/// Minimum time a plan stays usable after a failed renewal
+pub const GRACE_PERIOD_DAYS: i64 = 7;
+
+const DUNNING_JOB_INTERVAL_SECS: u64 = 60 * 60;
+
+/// When a grace period starting now ends
+pub fn grace_period_end(now: DateTime<Utc>, store_expires_at: Option<DateTime<Utc>>) -> DateTime<Utc> {
+    let minimum = now + Duration::days(GRACE_PERIOD_DAYS);
+    store_expires_at.map_or(minimum, |expires_at| expires_at.max(minimum))
+}
+
+/// Subscription state from what the store reports, given the grace period already running (if
+/// any) and returning when the grace period ends while there is one
+pub fn resolve_state(
+    is_active: bool,
+    billing_issue: bool,
+    store_expires_at: Option<DateTime<Utc>>,
+    grace_ends_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> (SubscriptionState, Option<DateTime<Utc>>) {
+    if is_active && !billing_issue {
+        return (SubscriptionState::Active, None); // Paid (again)
+    }
+    if billing_issue || grace_ends_at.is_some() {
+        let ends_at = grace_ends_at.unwrap_or_else(|| grace_period_end(now, store_expires_at));
+        let state = if now < ends_at { SubscriptionState::Grace } else { SubscriptionState::PastDue };
+        return (state, Some(ends_at));
+    }
+    if store_expires_at.is_some() {
+        (SubscriptionState::Expired, None)
+    } else {
+        (SubscriptionState::Cancelled, None)
+    }
+}
+
+/// Notify the user of a dunning step - entering grace, dropping to the free tier, or recovering
+/// from either. Other transitions aren't dunning's to announce.
+pub async fn announce(
+    pool: &PgPool,
+    user_id: Uuid,
+    previous: Option<&str>,
+    state: SubscriptionState,
+    grace_ends_at: Option<DateTime<Utc>>,
+) {
+    if previous == Some(state.as_str()) {
+        return;
+    }
+    let was_dunning = matches!(previous, Some("grace") | Some("past_due"));
+
+    let access_until = grace_ends_at.map(|ends_at| crate::calendar::local_today(ends_at).format("%d.%m.%Y.").to_string());
+    let (title, body) = match state {
+        SubscriptionState::Grace => (
+            "Naplata pretplate nije uspela",
+            format!(
+                "Plan ostaje aktivan do {}. Proverite način plaćanja da pretplata ne bi bila obustavljena.",
+                access_until.as_deref().unwrap_or("isteka dodatnog perioda")
+            ),
+        ),
+        SubscriptionState::PastDue => (
+            "Pretplata je obustavljena",
+            "Plaćanje nije uspelo ni nakon dodatnog perioda. Plan se vraća čim plaćanje uspe.".to_string(),
+        ),
+        SubscriptionState::Active if was_dunning => ("Plaćanje je uspelo", "Vaša pretplata je ponovo aktivna.".to_string()),
+        _ => return,
+    };
+
+    let channels = notifications::notify(
+        pool,
+        user_id,
+        NotificationKind::Billing,
+        title,
+        Some(&body),
+        Some(serde_json::json!({ "subscription_status": state.as_str(), "grace_period_ends_at": grace_ends_at })),
+    )
+    .await;
+    if !channels.email || state == SubscriptionState::Active {
+        return;
+    }
+
+    let email: Option<String> = sqlx::query_scalar("SELECT email FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    let Some(email) = email else {
+        return;
+    };
+    let resend_api_key = &crate::config::get().resend_api_key;
+    let sent = match state {
+        SubscriptionState::Grace => {
+            crate::email_service::send_payment_failed_email(resend_api_key, &email, access_until.as_deref().unwrap_or("-")).await
+        }
+        _ => crate::email_service::send_subscription_suspended_email(resend_api_key, &email).await,
+    };
+    if let Err(e) = sent {
+        error!(user_id = %user_id, "❌ Failed to send dunning e-mail: {:?}", e);
+    }
+}
+
+/// Move subscriptions whose grace period ended unpaid to past_due and their accounts to the free
+/// tier. Returns how many.
+pub async fn expire_grace_periods(pool: &PgPool) -> Result<usize, sqlx::Error> {
+    let due: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT id FROM users WHERE subscription_status = 'grace' AND grace_period_ends_at <= NOW()",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut expired = 0;
+    for user_id in due {
+        let (previous, next) = crate::subscriptions::transition(pool, user_id, &Transition::PastDue).await?;
+        if previous.subscription_status == next.subscription_status {
+            continue; // Paid in the meantime
+        }
+        announce(pool, user_id, previous.subscription_status.as_deref(), SubscriptionState::PastDue, next.grace_period_ends_at).await;
+        expired += 1;
+    }
+    Ok(expired)
+}
+
+/// Background job that ends expired grace periods
+pub async fn start_dunning_job(pool: std::sync::Arc<PgPool>, shutdown: CancellationToken) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(DUNNING_JOB_INTERVAL_SECS));
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.cancelled() => {
+                info!("🛑 Dunning job stopped");
+                return;
+            }
+        }
+
+        match expire_grace_periods(&pool).await {
+            Ok(0) => {}
+            Ok(count) => info!("💳 {} subscription(s) past due after the grace period", count),
+            Err(e) => error!("❌ Failed to expire grace periods: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_billing_issue_starts_grace() {
+        let now = Utc::now();
+        let (state, ends_at) = resolve_state(true, true, Some(now + Duration::days(2)), None, now);
+        assert_eq!(state, SubscriptionState::Grace);
+        assert_eq!(ends_at, Some(now + Duration::days(GRACE_PERIOD_DAYS)));
+
+        // A longer store grace period wins
+        let store_grace = now + Duration::days(16);
+        assert_eq!(resolve_state(true, true, Some(store_grace), None, now).1, Some(store_grace));
+    }
+
+    #[test]
+    fn test_grace_survives_store_expiry_until_it_ends() {
+        let now = Utc::now();
+        let running = Some(now + Duration::days(3));
+        assert_eq!(resolve_state(false, false, Some(now), running, now), (SubscriptionState::Grace, running));
+
+        let ended = Some(now - Duration::hours(1));
+        assert_eq!(resolve_state(false, false, Some(now), ended, now), (SubscriptionState::PastDue, ended));
+        assert!(!SubscriptionState::PastDue.has_access());
+    }
+
+    #[test]
+    fn test_payment_recovers() {
+        let now = Utc::now();
+        assert_eq!(resolve_state(true, false, None, Some(now - Duration::days(1)), now), (SubscriptionState::Active, None));
+        assert_eq!(resolve_state(false, false, Some(now), None, now).0, SubscriptionState::Expired);
+        assert_eq!(resolve_state(false, false, None, None, now).0, SubscriptionState::Cancelled);
+    }
+}
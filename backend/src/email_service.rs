@@ -189,8 +189,6 @@ pub async fn send_verification_email(
     email: &str,
     verification_token: &str,
 ) -> Result<String, Error> {
-    let resend = Resend::new(resend_api_key);
-
     let verification_url = format!(
         "https://chat.normaai.rs/verify-email.html?token={}",
         verification_token
@@ -238,23 +236,11 @@ pub async fn send_verification_email(
     );
 
     let html = get_email_template(&email_content, "Potvrdite vašu email adresu za Norma AI");
+    let id = send_email(resend_api_key, email, "Potvrdite vašu email adresu - Norma AI", &html).await?;
 
-    // Construct email using CreateEmailBaseOptions
-    let email_payload = CreateEmailBaseOptions::new(
-        FROM_EMAIL,
-        vec![email],
-        "Potvrdite vašu email adresu - Norma AI"
-    )
-    .with_html(&html);
-
-    let result = resend
-        .emails
-        .send(email_payload)
-        .await?;
-
-    println!("✅ Verification email sent to: {} (ID: {})", email, result.id);
+    println!("✅ Verification email sent to: {} (ID: {})", email, id);
 
-    Ok(result.id.to_string())
+    Ok(id)
 }
 
 /// Send password reset email
@@ -263,8 +249,6 @@ pub async fn send_password_reset_email(
     email: &str,
     reset_token: &str,
 ) -> Result<String, Error> {
-    let resend = Resend::new(resend_api_key);
-
     let reset_url = format!(
         "https://chat.normaai.rs/reset-password.html?token={}",
         reset_token
@@ -313,24 +297,279 @@ pub async fn send_password_reset_email(
     );
 
     let html = get_email_template(&email_content, "Resetujte vašu Norma AI lozinku");
+    let id = send_email(resend_api_key, email, "Resetovanje lozinke - Norma AI", &html).await?;
 
-    // Construct email using CreateEmailBaseOptions
-    let email_payload = CreateEmailBaseOptions::new(
-        FROM_EMAIL,
-        vec![email],
-        "Resetovanje lozinke - Norma AI"
-    )
-    .with_html(&html);
+    println!("✅ Password reset email sent to: {} (ID: {})", email, id);
+
+    Ok(id)
+}
+
+/// Send notice that the account is scheduled for deletion, with the grace period deadline
+pub async fn send_account_deletion_email(
+    resend_api_key: &str,
+    email: &str,
+    grace_period_ends: chrono::DateTime<chrono::Utc>,
+) -> Result<String, Error> {
+    let deadline = grace_period_ends.format("%d.%m.%Y.").to_string();
+
+    let email_content = format!(
+        r#"
+      <h1 class="email-title">Vaš nalog je zakazan za brisanje</h1>
+
+      <p class="email-text">
+        Primili smo vaš zahtev za brisanje Norma AI naloga. Nalog i svi vaši razgovori biće trajno obrisani <strong>{}</strong>.
+      </p>
+
+      <p class="email-text">
+        Ako se predomislite, dovoljno je da se ponovo prijavite pre tog datuma - vaš nalog će biti automatski vraćen.
+      </p>
+
+      <div style="text-align: center;">
+        <a href="https://chat.normaai.rs" class="email-button">
+          Prijavite se
+        </a>
+      </div>
+
+      <div class="info-box">
+        <p class="info-box-text">
+          <strong>Napomena:</strong> Nakon isteka roka od 30 dana brisanje se ne može opozvati.
+        </p>
+      </div>
+
+      <div class="email-divider"></div>
+
+      <p class="email-text" style="font-size: 14px; color: {};">
+        Ako niste vi zatražili brisanje naloga, odmah se prijavite i promenite lozinku.
+      </p>
+    "#,
+        deadline, TEXT_MUTED
+    );
+
+    let html = get_email_template(&email_content, "Vaš Norma AI nalog je zakazan za brisanje");
+    let id = send_email(resend_api_key, email, "Brisanje naloga - Norma AI", &html).await?;
+
+    println!("✅ Account deletion email sent to: {} (ID: {})", email, id);
+
+    Ok(id)
+}
+
+/// Send confirmation that a deleted account was restored within the grace period
+pub async fn send_account_restored_email(
+    resend_api_key: &str,
+    email: &str,
+) -> Result<String, Error> {
+    let email_content = format!(
+        r#"
+      <h1 class="email-title">Vaš nalog je vraćen</h1>
+
+      <p class="email-text">
+        Brisanje vašeg Norma AI naloga je otkazano. Nalog i svi vaši razgovori su ponovo dostupni.
+      </p>
+
+      <div style="text-align: center;">
+        <a href="https://chat.normaai.rs" class="email-button">
+          Otvorite Norma AI
+        </a>
+      </div>
+
+      <div class="email-divider"></div>
+
+      <p class="email-text" style="font-size: 14px; color: {};">
+        Ako niste vi vratili nalog, odmah promenite lozinku i odjavite ostale uređaje u podešavanjima naloga.
+      </p>
+    "#,
+        TEXT_MUTED
+    );
+
+    let html = get_email_template(&email_content, "Vaš Norma AI nalog je vraćen");
+    let id = send_email(resend_api_key, email, "Nalog je vraćen - Norma AI", &html).await?;
+
+    println!("✅ Account restored email sent to: {} (ID: {})", email, id);
+
+    Ok(id)
+}
+
+/// Send an alert about a sign-in from a device the account hasn't used before, with a one-click revoke link
+pub async fn send_new_device_email(
+    resend_api_key: &str,
+    email: &str,
+    device: &str,
+    ip_address: &str,
+    revoke_token: &str,
+) -> Result<String, Error> {
+    let revoke_url = format!("https://chat.normaai.rs/revoke-session.html?token={}", revoke_token);
+    let signed_in_at = chrono::Utc::now().format("%d.%m.%Y. %H:%M UTC").to_string();
+
+    let email_content = format!(
+        r#"
+      <h1 class="email-title">Nova prijava na vaš nalog</h1>
+
+      <p class="email-text">
+        Vaš Norma AI nalog je upravo korišćen na uređaju sa kog se ranije niste prijavljivali.
+      </p>
+
+      <div class="info-box">
+        <p class="info-box-text">
+          <strong>Uređaj:</strong> {}<br>
+          <strong>IP adresa:</strong> {}<br>
+          <strong>Vreme:</strong> {}
+        </p>
+      </div>
+
+      <p class="email-text">
+        Ako ste to bili vi, ne morate ništa da radite. Ako niste, odjavite taj uređaj i promenite lozinku:
+      </p>
+
+      <div style="text-align: center;">
+        <a href="{}" class="email-button">
+          Odjavi ovaj uređaj
+        </a>
+      </div>
 
-    let result = resend
-        .emails
-        .send(email_payload)
-        .await?;
+      <div class="email-divider"></div>
+
+      <p class="email-text" style="font-size: 14px; color: {};">
+        Link važi 7 dana i odjavljuje samo uređaj naveden iznad.
+      </p>
+    "#,
+        device, ip_address, signed_in_at, revoke_url, TEXT_MUTED
+    );
+
+    let html = get_email_template(&email_content, "Prijava sa novog uređaja na vaš Norma AI nalog");
+    let id = send_email(resend_api_key, email, "Nova prijava na nalog - Norma AI", &html).await?;
+
+    println!("✅ New device email sent to: {} (ID: {})", email, id);
+
+    Ok(id)
+}
+
+/// Tell a subscriber their renewal payment failed and how long the plan stays available
+pub async fn send_payment_failed_email(
+    resend_api_key: &str,
+    email: &str,
+    access_until: &str,
+) -> Result<String, Error> {
+    let email_content = format!(
+        r#"
+      <h1 class="email-title">Naplata pretplate nije uspela</h1>
+
+      <p class="email-text">
+        Nismo uspeli da naplatimo obnovu vaše Norma AI pretplate. Pokušaćemo ponovo u narednim danima.
+      </p>
+
+      <div class="info-box">
+        <p class="info-box-text">
+          Vaš plan ostaje aktivan do <strong>{}</strong>. Ako plaćanje do tada ne uspe, nalog prelazi na besplatni plan.
+        </p>
+      </div>
+
+      <p class="email-text">
+        Proverite način plaćanja u prodavnici aplikacija (App Store ili Google Play) ili u podešavanjima pretplate.
+      </p>
 
-    println!(
-        "✅ Password reset email sent to: {} (ID: {})",
-        email, result.id
+      <div style="text-align: center;">
+        <a href="https://chat.normaai.rs" class="email-button">
+          Otvorite Norma AI
+        </a>
+      </div>
+    "#,
+        access_until
     );
 
+    let html = get_email_template(&email_content, "Naplata vaše Norma AI pretplate nije uspela");
+    let id = send_email(resend_api_key, email, "Naplata pretplate nije uspela - Norma AI", &html).await?;
+
+    println!("✅ Payment failed email sent to: {} (ID: {})", email, id);
+
+    Ok(id)
+}
+
+/// Tell a subscriber the grace period ended without payment and the plan was suspended
+pub async fn send_subscription_suspended_email(
+    resend_api_key: &str,
+    email: &str,
+) -> Result<String, Error> {
+    let email_content = format!(
+        r#"
+      <h1 class="email-title">Pretplata je obustavljena</h1>
+
+      <p class="email-text">
+        Obnova vaše Norma AI pretplate nije plaćena ni nakon dodatnog perioda, pa je nalog prebačen na besplatni plan.
+        Vaši razgovori i dokumenti su sačuvani.
+      </p>
+
+      <p class="email-text">
+        Čim plaćanje uspe, plan se automatski vraća.
+      </p>
+
+      <div style="text-align: center;">
+        <a href="https://chat.normaai.rs" class="email-button">
+          Otvorite Norma AI
+        </a>
+      </div>
+
+      <div class="email-divider"></div>
+
+      <p class="email-text" style="font-size: 14px; color: {};">
+        Ako mislite da je došlo do greške, odgovorite na ovaj e-mail.
+      </p>
+    "#,
+        TEXT_MUTED
+    );
+
+    let html = get_email_template(&email_content, "Vaša Norma AI pretplata je obustavljena");
+    let id = send_email(resend_api_key, email, "Pretplata je obustavljena - Norma AI", &html).await?;
+
+    println!("✅ Subscription suspended email sent to: {} (ID: {})", email, id);
+
+    Ok(id)
+}
+
+pub async fn send_data_export_email(
+    resend_api_key: &str,
+    email: &str,
+    download_url: &str,
+    expires_in_days: i64,
+) -> Result<String, Error> {
+    let email_content = format!(
+        r#"
+      <h1 class="email-title">Vaši podaci su spremni</h1>
+
+      <p class="email-text">
+        Izvoz podataka vašeg Norma AI naloga je završen. Arhiva sadrži vaše razgovore, podatke o ugovorima
+        i dokumentima i podatke profila.
+      </p>
+
+      <div style="text-align: center;">
+        <a href="{}" class="email-button">
+          Preuzmite podatke
+        </a>
+      </div>
+
+      <div class="email-divider"></div>
+
+      <p class="email-text" style="font-size: 14px; color: {};">
+        Link važi {} dana. Ne prosleđujte ga - svako ko ima link može preuzeti vaše podatke.
+      </p>
+    "#,
+        download_url, TEXT_MUTED, expires_in_days
+    );
+
+    let html = get_email_template(&email_content, "Izvoz podataka vašeg Norma AI naloga je spreman");
+    let id = send_email(resend_api_key, email, "Izvoz podataka je spreman - Norma AI", &html).await?;
+
+    println!("✅ Data export email sent to: {} (ID: {})", email, id);
+
+    Ok(id)
+}
+
+/// Send one branded HTML email via Resend, returning the Resend message ID
+async fn send_email(resend_api_key: &str, email: &str, subject: &str, html: &str) -> Result<String, Error> {
+    let resend = Resend::new(resend_api_key);
+
+    let email_payload = CreateEmailBaseOptions::new(FROM_EMAIL, vec![email], subject).with_html(html);
+
+    let result = resend.emails.send(email_payload).await?;
+
     Ok(result.id.to_string())
 }
@@ -0,0 +1,203 @@
+use axum::{
+    extract::{
+        ws::{close_code, CloseFrame, Message as WsMessage, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::database;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+/// Buffered events per user before slow connections start missing them
+const EVENT_BUFFER_SIZE: usize = 64;
+
+/// Chat-level events pushed to every open window/device of the user
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatEvent {
+    ChatCreated {
+        chat_id: i64,
+        title: String,
+    },
+    // A question, answer or manually saved message was stored in a chat
+    MessageAdded {
+        chat_id: i64,
+        message_id: i64,
+        role: String,
+    },
+    ChatTitleChanged {
+        chat_id: i64,
+        title: String,
+    },
+    MessageFeedbackSaved {
+        chat_id: i64,
+        message_id: i64,
+        feedback_type: String,
+    },
+    ContractReady {
+        chat_id: i64,
+        message_id: i64,
+        contract_type: String,
+        filename: String,
+        download_url: String,
+    },
+    // A counterparty uploaded a document through a document request link
+    DocumentReceived {
+        chat_id: i64,
+        document_id: Uuid,
+        filename: String,
+    },
+    // Sent to team admins when a chat mentions a registered adverse party
+    ConflictFlagged {
+        alert_id: i64,
+        chat_id: i64,
+        party_name: String,
+    },
+    ExportFinished {
+        export_id: String,
+        download_url: Option<String>,
+        success: bool,
+    },
+    // A law the user subscribed to has a new consolidated text
+    LawAmended {
+        notification_id: i64,
+        law_name: String,
+        title: String,
+    },
+    // A notification was added to the user's feed (for the unread badge)
+    NotificationCreated {
+        notification_id: i64,
+        kind: String,
+        title: String,
+    },
+    // The account was just signed in on a device it hasn't used before
+    NewDeviceLogin {
+        session_id: Uuid,
+        device: String,
+        ip_address: Option<String>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct EventEnvelope<'a> {
+    #[serde(flatten)]
+    event: &'a ChatEvent,
+    emitted_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    // Browsers can't set Authorization on WebSocket requests, so the token comes in the query
+    pub token: Option<String>,
+}
+
+static SUBSCRIBERS: OnceLock<Mutex<HashMap<Uuid, broadcast::Sender<ChatEvent>>>> = OnceLock::new();
+
+fn subscribers() -> &'static Mutex<HashMap<Uuid, broadcast::Sender<ChatEvent>>> {
+    SUBSCRIBERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn subscribe(user_id: Uuid) -> broadcast::Receiver<ChatEvent> {
+    let mut subscribers = subscribers().lock().unwrap();
+    subscribers
+        .entry(user_id)
+        .or_insert_with(|| broadcast::channel(EVENT_BUFFER_SIZE).0)
+        .subscribe()
+}
+
+/// Drop the user's channel once their last connection is gone
+fn release(user_id: Uuid) {
+    let mut subscribers = subscribers().lock().unwrap();
+    if subscribers.get(&user_id).is_some_and(|tx| tx.receiver_count() == 0) {
+        subscribers.remove(&user_id);
+    }
+}
+
+/// Publish an event to all connected clients of the user (no-op if none are connected)
+pub fn emit(user_id: Uuid, event: ChatEvent) {
+    let subscribers = subscribers().lock().unwrap();
+    if let Some(tx) = subscribers.get(&user_id) {
+        let _ = tx.send(event);
+    }
+}
+
+/// WebSocket endpoint streaming chat events for the authenticated user
+pub async fn events_ws_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    mut headers: HeaderMap,
+    Query(query): Query<EventsQuery>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
+    if let Some(token) = query.token {
+        let value = HeaderValue::from_str(&format!("Bearer {}", token)).map_err(|_| StatusCode::BAD_REQUEST)?;
+        headers.insert(axum::http::header::AUTHORIZATION, value);
+    }
+
+    let user_id = database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, user_id)))
+}
+
+async fn handle_socket(mut socket: WebSocket, user_id: Uuid) {
+    let mut rx = subscribe(user_id);
+    println!("🔌 EVENTS: user_id={} connected", user_id);
+
+    let shutdown = crate::shutdown::token();
+    loop {
+        tokio::select! {
+            // Tell the client to reconnect (to another instance) instead of leaving it hanging
+            _ = shutdown.cancelled() => {
+                let frame = CloseFrame { code: close_code::AWAY, reason: "Server se restartuje".into() };
+                let _ = socket.send(WsMessage::Close(Some(frame))).await;
+                break;
+            }
+            event = rx.recv() => {
+                match event {
+                    Ok(event) => {
+                        let envelope = EventEnvelope { event: &event, emitted_at: chrono::Utc::now() };
+                        let payload = match serde_json::to_string(&envelope) {
+                            Ok(payload) => payload,
+                            Err(e) => {
+                                eprintln!("Failed to serialize chat event: {}", e);
+                                continue;
+                            }
+                        };
+                        if socket.send(WsMessage::Text(payload)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        println!("⚠️ EVENTS: user_id={} lagged, skipped {} events", user_id, skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            incoming = socket.recv() => {
+                match incoming {
+                    Some(Ok(WsMessage::Ping(data))) => {
+                        if socket.send(WsMessage::Pong(data)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(WsMessage::Close(_))) | None | Some(Err(_)) => break,
+                    Some(Ok(_)) => {} // Clients don't send anything meaningful on this channel
+                }
+            }
+        }
+    }
+
+    drop(rx);
+    release(user_id);
+    println!("🔌 EVENTS: user_id={} disconnected", user_id);
+}
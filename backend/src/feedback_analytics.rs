@@ -0,0 +1,154 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+#[derive(Debug, Deserialize)]
+pub struct FeedbackStatsQuery {
+    pub weeks: Option<i32>,      // Look-back window, default 12
+    pub law_name: Option<String>, // Narrow everything to one law
+    pub limit: Option<i64>,      // Downvoted answers listed, default 20
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct FeedbackCounts {
+    pub rated: i64,
+    pub positive: i64,
+    pub negative: i64,
+    pub answers: i64, // All assistant answers in the window, rated or not
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LawFeedback {
+    pub law_name: Option<String>, // None = answers without a detected law
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub counts: FeedbackCounts,
+    pub negative_ratio: Option<f64>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct WeeklyFeedback {
+    pub week_start: chrono::DateTime<chrono::Utc>,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub counts: FeedbackCounts,
+    pub negative_ratio: Option<f64>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DownvotedAnswer {
+    pub message_id: i64,
+    pub chat_id: i64,
+    pub law_name: Option<String>,
+    pub question: Option<String>, // User message the answer replied to
+    pub answer: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeedbackStatsResponse {
+    pub weeks: i32,
+    pub totals: FeedbackCounts,
+    pub by_law: Vec<LawFeedback>,
+    pub by_week: Vec<WeeklyFeedback>,
+    pub downvoted: Vec<DownvotedAnswer>,
+}
+
+/// Admin: positive/negative feedback ratios per detected law and per week (by answer date),
+/// plus the latest downvoted answers with their questions, to spot prompt regressions
+pub async fn feedback_stats_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<FeedbackStatsQuery>,
+) -> Result<ResponseJson<FeedbackStatsResponse>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let weeks = query.weeks.unwrap_or(12).clamp(1, 104);
+    let limit = query.limit.unwrap_or(20).clamp(1, 200);
+
+    let db_error = |e: sqlx::Error| {
+        eprintln!("Failed to build feedback stats: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    // Scans every answer in the window - runs on the read replica when there is one
+    let reports = crate::db_routing::analytics(&pool);
+
+    // Shared filter: assistant answers in the window, optionally for one law
+    const ANSWERS: &str = "role = 'assistant'
+        AND created_at > NOW() - make_interval(weeks => $1)
+        AND ($2::TEXT IS NULL OR law_name = $2)";
+    const COUNTS: &str = "COUNT(*) FILTER (WHERE message_feedback IS NOT NULL) AS rated,
+        COUNT(*) FILTER (WHERE message_feedback = 'positive') AS positive,
+        COUNT(*) FILTER (WHERE message_feedback = 'negative') AS negative,
+        COUNT(*) AS answers";
+    const NEGATIVE_RATIO: &str = "COUNT(*) FILTER (WHERE message_feedback = 'negative')::FLOAT8
+        / NULLIF(COUNT(*) FILTER (WHERE message_feedback IS NOT NULL), 0) AS negative_ratio";
+
+    let totals = sqlx::query_as::<_, FeedbackCounts>(&format!(
+        "SELECT {} FROM messages WHERE {}",
+        COUNTS, ANSWERS
+    ))
+    .bind(weeks)
+    .bind(&query.law_name)
+    .fetch_one(reports)
+    .await
+    .map_err(db_error)?;
+
+    let by_law = sqlx::query_as::<_, LawFeedback>(&format!(
+        "SELECT law_name, {}, {} FROM messages WHERE {}
+         GROUP BY law_name
+         HAVING COUNT(*) FILTER (WHERE message_feedback IS NOT NULL) > 0
+         ORDER BY negative DESC, rated DESC",
+        COUNTS, NEGATIVE_RATIO, ANSWERS
+    ))
+    .bind(weeks)
+    .bind(&query.law_name)
+    .fetch_all(reports)
+    .await
+    .map_err(db_error)?;
+
+    let by_week = sqlx::query_as::<_, WeeklyFeedback>(&format!(
+        "SELECT date_trunc('week', created_at) AS week_start, {}, {} FROM messages WHERE {}
+         GROUP BY week_start
+         ORDER BY week_start",
+        COUNTS, NEGATIVE_RATIO, ANSWERS
+    ))
+    .bind(weeks)
+    .bind(&query.law_name)
+    .fetch_all(reports)
+    .await
+    .map_err(db_error)?;
+
+    let downvoted = sqlx::query_as::<_, DownvotedAnswer>(
+        "SELECT m.id AS message_id, m.chat_id, m.law_name, q.content AS question, m.content AS answer, m.created_at
+         FROM messages m
+         LEFT JOIN LATERAL (
+             SELECT content FROM messages
+             WHERE chat_id = m.chat_id AND role = 'user' AND created_at <= m.created_at
+             ORDER BY created_at DESC
+             LIMIT 1
+         ) q ON true
+         WHERE m.role = 'assistant' AND m.message_feedback = 'negative'
+           AND m.created_at > NOW() - make_interval(weeks => $1)
+           AND ($2::TEXT IS NULL OR m.law_name = $2)
+         ORDER BY m.created_at DESC
+         LIMIT $3"
+    )
+    .bind(weeks)
+    .bind(&query.law_name)
+    .bind(limit)
+    .fetch_all(reports)
+    .await
+    .map_err(db_error)?;
+
+    Ok(ResponseJson(FeedbackStatsResponse { weeks, totals, by_law, by_week, downvoted }))
+}
@@ -0,0 +1,381 @@
+// Court taxes (Zakon o sudskim taksama) and administrative fees (Zakon o republičkim administrativnim
+// taksama) for a procedure and claim value, itemized.
+// Tariffs are indexed regularly, so they live in fee_schedule_items (seeded by migration 0017) and are
+// maintained by admins without a redeploy.
+use axum::{
+    extract::{Json, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::models::ErrorResponse;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+/// How long fee schedules loaded from the database are reused before re-reading
+const SCHEDULE_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Claim values above this are rejected (dinars)
+const MAX_CLAIM_VALUE: f64 = 1e13;
+
+/// One bracket of a fee item. A claim value in (min_value, max_value] pays
+/// fixed_amount + percent of the value above min_value, capped at max_amount.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct FeeScheduleItem {
+    pub procedure: String,
+    pub item: String,     // e.g. "Taksa na tužbu"
+    pub fee_type: String, // "court_tax" | "administrative_fee"
+    pub legal_basis: Option<String>,
+    pub min_value: i64,
+    pub max_value: Option<i64>, // None = no upper bound
+    pub fixed_amount: i64,
+    pub percent: f64,
+    pub max_amount: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeeProcedure {
+    pub key: &'static str,
+    pub name: &'static str,
+    pub needs_claim_value: bool,
+}
+
+/// Procedures the calculator knows (their schedules come from fee_schedule_items)
+pub const PROCEDURES: &[FeeProcedure] = &[
+    FeeProcedure { key: "tuzba", name: "Tužba u parničnom postupku (sa taksom na presudu)", needs_claim_value: true },
+    FeeProcedure { key: "zalba_presuda", name: "Žalba protiv presude", needs_claim_value: true },
+    FeeProcedure { key: "upravni_zahtev", name: "Zahtev organu uprave", needs_claim_value: false },
+    FeeProcedure { key: "upravni_zalba", name: "Žalba u upravnom postupku", needs_claim_value: false },
+];
+
+#[derive(Debug, Deserialize)]
+pub struct CourtFeeRequest {
+    pub procedure: String,
+    pub claim_value: Option<f64>, // Vrednost predmeta spora in dinars
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeeLine {
+    pub item: String,
+    pub fee_type: String,
+    pub legal_basis: Option<String>,
+    pub amount: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CourtFeeBreakdown {
+    pub procedure: String,
+    pub name: String,
+    pub claim_value: Option<f64>,
+    pub items: Vec<FeeLine>,
+    pub total: i64,
+    pub currency: &'static str,
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetFeeScheduleRequest {
+    pub procedure: String,
+    pub items: Vec<FeeScheduleItem>, // Replaces the procedure's schedule
+}
+
+// (loaded_at, items)
+type CachedSchedules = Option<(Instant, Vec<FeeScheduleItem>)>;
+
+static SCHEDULE_CACHE: OnceLock<Mutex<CachedSchedules>> = OnceLock::new();
+
+fn schedule_cache() -> &'static Mutex<CachedSchedules> {
+    SCHEDULE_CACHE.get_or_init(|| Mutex::new(None))
+}
+
+fn invalidate_cache() {
+    *schedule_cache().lock().unwrap() = None;
+}
+
+const SELECT_ITEMS: &str = "SELECT procedure, item, fee_type, legal_basis, min_value, max_value, fixed_amount, percent, max_amount
+     FROM fee_schedule_items ORDER BY procedure, position, min_value";
+
+async fn load_schedules(pool: &PgPool) -> Result<Vec<FeeScheduleItem>, FeeError> {
+    if let Some((loaded_at, items)) = schedule_cache().lock().unwrap().as_ref() {
+        if loaded_at.elapsed() < SCHEDULE_CACHE_TTL {
+            return Ok(items.clone());
+        }
+    }
+
+    let items = sqlx::query_as::<_, FeeScheduleItem>(SELECT_ITEMS)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| FeeError::Unavailable(format!("Failed to load fee schedules: {}", e)))?;
+    *schedule_cache().lock().unwrap() = Some((Instant::now(), items.clone()));
+    Ok(items)
+}
+
+/// Amount of one bracket for a claim value, in whole dinars
+fn bracket_amount(item: &FeeScheduleItem, claim_value: f64) -> i64 {
+    let above_min = (claim_value - item.min_value as f64).max(0.0);
+    let amount = item.fixed_amount as f64 + above_min * item.percent / 100.0;
+    let amount = amount.round() as i64;
+    item.max_amount.map_or(amount, |max| amount.min(max))
+}
+
+fn bracket_matches(item: &FeeScheduleItem, claim_value: f64) -> bool {
+    let above_min = if item.min_value == 0 { claim_value >= 0.0 } else { claim_value > item.min_value as f64 };
+    above_min && item.max_value.is_none_or(|max| claim_value <= max as f64)
+}
+
+#[derive(Debug)]
+pub enum FeeError {
+    Invalid(String),     // User-facing: unknown procedure, missing or bad claim value
+    Unavailable(String), // The schedule couldn't be loaded or the procedure has none
+}
+
+impl FeeError {
+    pub fn message(&self) -> &str {
+        match self {
+            FeeError::Invalid(message) | FeeError::Unavailable(message) => message,
+        }
+    }
+}
+
+/// Compute itemized fees
+pub async fn compute_fees(request: &CourtFeeRequest, pool: &PgPool) -> Result<CourtFeeBreakdown, FeeError> {
+    let schedules = load_schedules(pool).await?;
+    compute_fees_from(request, &schedules)
+}
+
+fn compute_fees_from(request: &CourtFeeRequest, schedules: &[FeeScheduleItem]) -> Result<CourtFeeBreakdown, FeeError> {
+    let procedure = PROCEDURES
+        .iter()
+        .find(|p| p.key == request.procedure)
+        .ok_or_else(|| FeeError::Invalid(format!("Nepoznat postupak: {}", request.procedure)))?;
+
+    let claim_value = match request.claim_value {
+        Some(value) if !(0.0..=MAX_CLAIM_VALUE).contains(&value) => {
+            return Err(FeeError::Invalid("Neispravna vrednost predmeta spora".to_string()))
+        }
+        Some(value) => Some(value),
+        None if procedure.needs_claim_value => return Err(FeeError::Invalid("Navedite vrednost predmeta spora".to_string())),
+        None => None,
+    };
+
+    let items: Vec<&FeeScheduleItem> = schedules.iter().filter(|i| i.procedure == procedure.key).collect();
+    if items.is_empty() {
+        return Err(FeeError::Unavailable(format!("No fee schedule for procedure '{}'", procedure.key)));
+    }
+
+    // Brackets of the same item are alternatives - keep the first matching one per item, in schedule order
+    let value = claim_value.unwrap_or(0.0);
+    let mut lines: Vec<FeeLine> = Vec::new();
+    for item in items.into_iter().filter(|i| bracket_matches(i, value)) {
+        if lines.iter().any(|line| line.item == item.item) {
+            continue;
+        }
+        lines.push(FeeLine {
+            item: item.item.clone(),
+            fee_type: item.fee_type.clone(),
+            legal_basis: item.legal_basis.clone(),
+            amount: bracket_amount(item, value),
+        });
+    }
+
+    let mut notes = Vec::new();
+    if procedure.key == "tuzba" {
+        notes.push("Taksa na presudu dospeva tek kada bude doneta prvostepena presuda.".to_string());
+    }
+
+    Ok(CourtFeeBreakdown {
+        procedure: procedure.key.to_string(),
+        name: procedure.name.to_string(),
+        claim_value,
+        total: lines.iter().map(|line| line.amount).sum(),
+        items: lines,
+        currency: "RSD",
+        notes,
+    })
+}
+
+/// Compute court taxes and administrative fees for a procedure and claim value
+pub async fn court_fees_handler(
+    State((pool, _, _, _)): State<AppState>,
+    Json(request): Json<CourtFeeRequest>,
+) -> Result<ResponseJson<CourtFeeBreakdown>, (StatusCode, Json<ErrorResponse>)> {
+    compute_fees(&request, &pool).await.map(ResponseJson).map_err(|e| match e {
+        FeeError::Invalid(message) => (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "INVALID_FEE_REQUEST".to_string(), message, details: None }),
+        ),
+        FeeError::Unavailable(reason) => {
+            eprintln!("❌ Fee calculation failed: {}", reason);
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ErrorResponse {
+                    error: "FEE_SCHEDULE_UNAVAILABLE".to_string(),
+                    message: "Tarifa trenutno nije dostupna. Pokušajte ponovo kasnije.".to_string(),
+                    details: None,
+                }),
+            )
+        }
+    })
+}
+
+/// Procedure types the fee calculator knows, for the UI picker
+pub async fn fee_procedures_handler() -> ResponseJson<&'static [FeeProcedure]> {
+    ResponseJson(PROCEDURES)
+}
+
+/// Admin: list fee schedule overrides
+pub async fn list_fee_schedules_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ResponseJson<Vec<FeeScheduleItem>>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(ResponseJson(list_schedules(&pool).await?))
+}
+
+async fn list_schedules(pool: &PgPool) -> Result<Vec<FeeScheduleItem>, StatusCode> {
+    sqlx::query_as::<_, FeeScheduleItem>(SELECT_ITEMS)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to list fee schedules: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Admin: replace the fee schedule of a procedure (e.g. after tariff indexation)
+pub async fn set_fee_schedule_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<SetFeeScheduleRequest>,
+) -> Result<ResponseJson<Vec<FeeScheduleItem>>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    // Every procedure needs a schedule - there is no built-in one to fall back to
+    if !PROCEDURES.iter().any(|p| p.key == request.procedure) || request.items.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let valid = request.items.iter().all(|item| {
+        item.procedure == request.procedure
+            && !item.item.trim().is_empty()
+            && matches!(item.fee_type.as_str(), "court_tax" | "administrative_fee")
+            && item.min_value >= 0
+            && item.max_value.is_none_or(|max| max > item.min_value)
+            && item.fixed_amount >= 0
+            && item.percent >= 0.0
+    });
+    if !valid {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let db_error = |e: sqlx::Error| {
+        eprintln!("Failed to update fee schedule: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    sqlx::query("DELETE FROM fee_schedule_items WHERE procedure = $1")
+        .bind(&request.procedure)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+    for (position, item) in request.items.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO fee_schedule_items
+                (procedure, item, fee_type, legal_basis, min_value, max_value, fixed_amount, percent, max_amount, position)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)"
+        )
+        .bind(&item.procedure)
+        .bind(item.item.trim())
+        .bind(&item.fee_type)
+        .bind(&item.legal_basis)
+        .bind(item.min_value)
+        .bind(item.max_value)
+        .bind(item.fixed_amount)
+        .bind(item.percent)
+        .bind(item.max_amount)
+        .bind(position as i32)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    }
+    tx.commit().await.map_err(db_error)?;
+
+    println!("✅ Fee schedule updated: procedure={}, items={}", request.procedure, request.items.len());
+    invalidate_cache();
+
+    Ok(ResponseJson(list_schedules(&pool).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(procedure: &str, claim_value: Option<f64>) -> CourtFeeRequest {
+        CourtFeeRequest { procedure: procedure.to_string(), claim_value }
+    }
+
+    fn item(procedure: &str, item: &str, min_value: i64, max_value: Option<i64>, fixed_amount: i64, percent: f64, max_amount: Option<i64>) -> FeeScheduleItem {
+        FeeScheduleItem {
+            procedure: procedure.to_string(),
+            item: item.to_string(),
+            fee_type: "court_tax".to_string(),
+            legal_basis: None,
+            min_value,
+            max_value,
+            fixed_amount,
+            percent,
+            max_amount,
+        }
+    }
+
+    // The brackets seeded by migration 0017
+    fn court_tax(procedure: &str, name: &str) -> Vec<FeeScheduleItem> {
+        vec![
+            item(procedure, name, 0, Some(10_000), 1_900, 0.0, None),
+            item(procedure, name, 10_000, Some(50_000), 3_800, 0.0, None),
+            item(procedure, name, 500_000, Some(1_000_000), 38_000, 0.0, None),
+            item(procedure, name, 1_000_000, None, 38_000, 1.0, Some(390_000)),
+        ]
+    }
+
+    #[test]
+    fn test_court_tax_brackets() {
+        let schedules = [
+            court_tax("tuzba", "Taksa na tužbu"),
+            court_tax("tuzba", "Taksa na presudu"),
+            court_tax("zalba_presuda", "Taksa na žalbu protiv presude"),
+        ]
+        .concat();
+
+        let small = compute_fees_from(&request("zalba_presuda", Some(10_000.0)), &schedules).unwrap();
+        assert_eq!(small.total, 1_900);
+
+        // 38.000 + 1% of the 2.000.000 above one million
+        let large = compute_fees_from(&request("tuzba", Some(3_000_000.0)), &schedules).unwrap();
+        assert_eq!(large.items.len(), 2);
+        assert_eq!(large.items[0].amount, 58_000);
+        assert_eq!(large.total, 116_000);
+
+        // Capped
+        let huge = compute_fees_from(&request("zalba_presuda", Some(1e9)), &schedules).unwrap();
+        assert_eq!(huge.total, 390_000);
+
+        assert!(matches!(compute_fees_from(&request("tuzba", None), &schedules), Err(FeeError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_missing_schedule_is_an_error() {
+        let result = compute_fees_from(&request("upravni_zalba", None), &court_tax("tuzba", "Taksa na tužbu"));
+        assert!(matches!(result, Err(FeeError::Unavailable(_))));
+    }
+}
@@ -0,0 +1,237 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::database::verify_user_from_headers_async;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+const MAX_FOLDER_NAME_CHARS: usize = 100;
+const MAX_FOLDERS_PER_USER: i64 = 200;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ChatFolder {
+    pub id: i64,
+    pub name: String,
+    pub chat_count: i64, // Active (not archived or deleted) chats in the folder
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FolderRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssignFolderRequest {
+    pub folder_id: Option<i64>, // None removes the chat from its folder
+}
+
+/// Trimmed folder name, or None if empty or too long
+fn clean_folder_name(name: &str) -> Option<String> {
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!name.is_empty() && name.chars().count() <= MAX_FOLDER_NAME_CHARS).then_some(name)
+}
+
+/// List the user's folders with chat counts
+pub async fn list_folders_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ResponseJson<Vec<ChatFolder>>, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let folders = sqlx::query_as::<_, ChatFolder>(
+        "SELECT f.id, f.name, COUNT(c.id) AS chat_count, f.created_at, f.updated_at
+         FROM chat_folders f
+         LEFT JOIN chats c ON c.folder_id = f.id AND c.archived = false AND c.deleted_at IS NULL
+         WHERE f.user_id = $1
+         GROUP BY f.id
+         ORDER BY LOWER(f.name)"
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to fetch chat folders: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(ResponseJson(folders))
+}
+
+/// Create a folder. 409 if the user already has one with that name.
+pub async fn create_folder_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<FolderRequest>,
+) -> Result<(StatusCode, ResponseJson<ChatFolder>), StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let name = clean_folder_name(&request.name).ok_or(StatusCode::BAD_REQUEST)?;
+
+    let folder_count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM chat_folders WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to count chat folders: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if folder_count >= MAX_FOLDERS_PER_USER {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let folder = sqlx::query_as::<_, ChatFolder>(
+        "INSERT INTO chat_folders (user_id, name) VALUES ($1, $2)
+         ON CONFLICT (user_id, name) DO NOTHING
+         RETURNING id, name, 0::BIGINT AS chat_count, created_at, updated_at"
+    )
+    .bind(user_id)
+    .bind(&name)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to create chat folder: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::CONFLICT)?;
+
+    Ok((StatusCode::CREATED, ResponseJson(folder)))
+}
+
+/// Rename a folder the user owns
+pub async fn rename_folder_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(folder_id): Path<i64>,
+    Json(request): Json<FolderRequest>,
+) -> Result<ResponseJson<ChatFolder>, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let name = clean_folder_name(&request.name).ok_or(StatusCode::BAD_REQUEST)?;
+
+    let folder = sqlx::query_as::<_, ChatFolder>(
+        "UPDATE chat_folders f SET name = $1, updated_at = NOW()
+         WHERE f.id = $2 AND f.user_id = $3
+         RETURNING f.id, f.name,
+                   (SELECT COUNT(*) FROM chats c
+                    WHERE c.folder_id = f.id AND c.archived = false AND c.deleted_at IS NULL) AS chat_count,
+                   f.created_at, f.updated_at"
+    )
+    .bind(&name)
+    .bind(folder_id)
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| match e.as_database_error() {
+        // Another folder of this user already has the name
+        Some(db_error) if db_error.is_unique_violation() => StatusCode::CONFLICT,
+        _ => {
+            eprintln!("Failed to rename chat folder: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(ResponseJson(folder))
+}
+
+/// Delete a folder the user owns. Its chats are kept and become unfiled.
+pub async fn delete_folder_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(folder_id): Path<i64>,
+) -> Result<StatusCode, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let result = sqlx::query("DELETE FROM chat_folders WHERE id = $1 AND user_id = $2")
+        .bind(folder_id)
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to delete chat folder: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Move a chat into a folder (or out of it with folder_id: null). Both must belong to the user.
+pub async fn assign_chat_folder_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(chat_id): Path<i64>,
+    Json(request): Json<AssignFolderRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    if let Some(folder_id) = request.folder_id {
+        let owns_folder = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM chat_folders WHERE id = $1 AND user_id = $2)"
+        )
+        .bind(folder_id)
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to check chat folder ownership: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+        if !owns_folder {
+            // Folder not found or user doesn't own it
+            return Err(StatusCode::NOT_FOUND);
+        }
+    }
+
+    // Update the chat only if the user owns it
+    let result = sqlx::query("UPDATE chats SET folder_id = $1 WHERE id = $2 AND user_id = $3 AND deleted_at IS NULL")
+        .bind(request.folder_id)
+        .bind(chat_id)
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to assign chat folder: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if result.rows_affected() == 0 {
+        // Chat not found or user doesn't own it
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_folder_name() {
+        assert_eq!(clean_folder_name("  Klijent   Petrović  "), Some("Klijent Petrović".to_string()));
+        assert_eq!(clean_folder_name("   "), None);
+        assert_eq!(clean_folder_name(&"a".repeat(MAX_FOLDER_NAME_CHARS + 1)), None);
+    }
+}
@@ -0,0 +1,264 @@
+// Free daily question without registration: one answered question per device session per UTC day,
+// with a per-IP cap on top so clearing storage/rotating device ids doesn't give unlimited answers.
+// The answer is not saved to a chat - the account later registered on the device claims it, and it
+// counts as the first of that account's trial messages.
+use crate::models::{ApiError, ErrorResponse, QuestionResponse};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+    Json,
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+type AppState = (PgPool, String, String, String, Option<String>); // (pool, openrouter_api_key, openai_api_key, jwt_secret, supabase_jwt_secret)
+
+/// Free questions one IP address can use per day, across all device sessions behind it
+const FREE_QUESTIONS_PER_IP_PER_DAY: i64 = 3;
+
+const MAX_FREE_QUESTION_CHARS: usize = 1000;
+
+/// Unclaimed free questions older than this aren't imported into a new account
+const CLAIM_WINDOW_DAYS: i32 = 7;
+
+#[derive(Debug, Deserialize)]
+pub struct FreeQuestionRequest {
+    pub question: String,
+    #[serde(default)]
+    pub script: crate::transliteration::ResponseScript,
+}
+
+fn database_error(e: sqlx::Error) -> ApiError {
+    eprintln!("Free question database error: {}", e);
+    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Greška baze podataka")
+}
+
+/// Reserve today's free question for the device. Returns None when the device or its IP
+/// already used up today's allowance. Serialized per IP so parallel requests can't race the cap.
+async fn reserve_free_question(
+    device_session_id: Uuid,
+    ip_address: &str,
+    question: &str,
+    pool: &PgPool,
+) -> Result<Option<i64>, sqlx::Error> {
+    let today = chrono::Utc::now().date_naive();
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('free_question:' || $1))")
+        .bind(ip_address)
+        .execute(&mut *tx)
+        .await?;
+
+    let used_by_ip: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM anonymous_free_questions WHERE ip_address = $1 AND question_date = $2"
+    )
+    .bind(ip_address)
+    .bind(today)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if used_by_ip >= FREE_QUESTIONS_PER_IP_PER_DAY {
+        return Ok(None);
+    }
+
+    let reservation_id: Option<i64> = sqlx::query_scalar(
+        "INSERT INTO anonymous_free_questions (device_session_id, ip_address, question_date, question)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (device_session_id, question_date) DO NOTHING
+         RETURNING id"
+    )
+    .bind(device_session_id)
+    .bind(ip_address)
+    .bind(today)
+    .bind(question)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(reservation_id)
+}
+
+/// Answer one legal question for a device without an account
+pub async fn free_question_handler(
+    State((pool, openrouter_api_key, openai_api_key, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<FreeQuestionRequest>,
+) -> Result<ResponseJson<QuestionResponse>, ApiError> {
+    // Signed-in users ask through their chats so the question counts against their plan
+    if crate::database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .is_some()
+    {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "USE_ACCOUNT",
+            "Prijavljeni ste - postavite pitanje u okviru svog naloga",
+        ));
+    }
+
+    let device_session_id = headers
+        .get("X-Device-Session-Id")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| Uuid::parse_str(s.trim()).ok())
+        .ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, "DEVICE_SESSION_REQUIRED", "Nedostaje identifikator uređaja"))?;
+
+    let question = request.question.trim();
+    if question.is_empty() || question.chars().count() > MAX_FREE_QUESTION_CHARS {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "INVALID_QUESTION",
+            "Pitanje mora imati između 1 i 1000 karaktera",
+        ));
+    }
+
+    let question = crate::moderation::screen_question(question)
+        .map_err(|category| category.error())?;
+    let question = question.as_str();
+
+    // A device that was ever signed in belongs to an account - its trial already covers it
+    let device_has_account: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM user_sessions WHERE device_info->>'session_id' = $1)"
+    )
+    .bind(device_session_id.to_string())
+    .fetch_one(&pool)
+    .await
+    .map_err(database_error)?;
+
+    if device_has_account {
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "LOGIN_TO_CONTINUE",
+            "Na ovom uređaju već postoji nalog - prijavite se da biste nastavili",
+        ));
+    }
+
+    let ip_address = crate::api::extract_client_ip(&headers);
+    let reservation_id = reserve_free_question(device_session_id, &ip_address, question, &pool)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| {
+            crate::metrics::record_trial_rejection("free_question");
+            ApiError {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                body: Some(ErrorResponse {
+                    error: "REGISTER_TO_CONTINUE".to_string(),
+                    message: "Iskoristili ste besplatno pitanje za danas - registrujte se da biste nastavili".to_string(),
+                    details: Some(serde_json::json!({"free_questions_per_day": 1})),
+                }),
+            }
+        })?;
+
+    let (mut response, stored_content) = match crate::api::answer_anonymous_question(question, &openrouter_api_key, &openai_api_key, &pool).await {
+        Ok(answer) => answer,
+        Err(e) => {
+            eprintln!("❌ Free question answer failed: {}", e);
+            // Give the allowance back - the device didn't get an answer
+            if let Err(e) = sqlx::query("DELETE FROM anonymous_free_questions WHERE id = $1")
+                .bind(reservation_id)
+                .execute(&pool)
+                .await
+            {
+                eprintln!("Failed to release free question reservation: {}", e);
+            }
+            return Err(ApiError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "AI_UNAVAILABLE",
+                "Odgovor trenutno nije dostupan - pokušajte ponovo",
+            ));
+        }
+    };
+
+    if let Err(e) = sqlx::query("UPDATE anonymous_free_questions SET answer = $2, law_name = $3 WHERE id = $1")
+        .bind(reservation_id)
+        .bind(&stored_content)
+        .bind(&response.law_name)
+        .execute(&pool)
+        .await
+    {
+        eprintln!("Failed to save free question answer: {}", e);
+    }
+
+    crate::transliteration::apply_script(&mut response, request.script);
+
+    Ok(ResponseJson(response))
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ClaimedQuestion {
+    question: String,
+    answer: String,
+    law_name: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Move a device's unclaimed free questions into chats of the account just registered on it.
+/// Each one is deducted from the new trial, so registering doesn't reset the free allowance.
+/// Returns how many chats were imported.
+pub async fn claim_free_questions(device_session_id: &str, user_id: Uuid, pool: &PgPool) -> Result<i64, String> {
+    let Ok(device_session_id) = Uuid::parse_str(device_session_id.trim()) else {
+        return Ok(0);
+    };
+
+    let mut tx = pool.begin().await
+        .map_err(|e| format!("Failed to start free question claim: {}", e))?;
+
+    let claimed = sqlx::query_as::<_, ClaimedQuestion>(
+        "UPDATE anonymous_free_questions SET claimed_by = $2
+         WHERE device_session_id = $1 AND claimed_by IS NULL AND answer IS NOT NULL
+           AND created_at > NOW() - make_interval(days => $3)
+         RETURNING question, answer, law_name, created_at"
+    )
+    .bind(device_session_id)
+    .bind(user_id)
+    .bind(CLAIM_WINDOW_DAYS)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to claim free questions: {}", e))?;
+
+    if claimed.is_empty() {
+        return Ok(0);
+    }
+
+    for free_question in &claimed {
+        let title: String = free_question.question.chars().take(60).collect();
+        let chat_id: i64 = sqlx::query_scalar(
+            "INSERT INTO chats (title, user_id, created_at, updated_at) VALUES ($1, $2, $3, $3) RETURNING id"
+        )
+        .bind(&title)
+        .bind(user_id)
+        .bind(free_question.created_at)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create chat for free question: {}", e))?;
+
+        sqlx::query(
+            "INSERT INTO messages (chat_id, role, content, law_name, created_at)
+             VALUES ($1, 'user', $2, NULL, $4), ($1, 'assistant', $3, $5, $4 + INTERVAL '1 second')"
+        )
+        .bind(chat_id)
+        .bind(&free_question.question)
+        .bind(&free_question.answer)
+        .bind(free_question.created_at)
+        .bind(&free_question.law_name)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to import free question messages: {}", e))?;
+    }
+
+    sqlx::query(
+        "UPDATE users SET trial_messages_remaining = GREATEST(COALESCE(trial_messages_remaining, 0) - $2, 0), updated_at = NOW()
+         WHERE id = $1 AND account_type = 'trial_registered'"
+    )
+    .bind(user_id)
+    .bind(claimed.len() as i32)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to deduct free questions from trial: {}", e))?;
+
+    tx.commit().await
+        .map_err(|e| format!("Failed to commit free question claim: {}", e))?;
+
+    Ok(claimed.len() as i64)
+}
@@ -0,0 +1,427 @@
+// Guest trial: a device without an account (identified by X-Device-Session-Id) gets
+// GUEST_MESSAGES answered questions, saved to chats owned by the device rather than a user. When an
+// account is registered or signed in on the device it takes the chats over (see chat_migration.rs),
+// and the guest record is closed so the device can't start a second guest trial.
+// Guest questions are answered one at a time, without chat history, documents or contracts.
+// Guests that never register are deleted with their chats by the daily cleanup job.
+use crate::models::{ApiError, Chat, ErrorResponse, Message, QuestionResponse};
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+type AppState = (PgPool, String, String, String, Option<String>); // (pool, openrouter_api_key, openai_api_key, jwt_secret, supabase_jwt_secret)
+
+/// Messages a guest device can send before it has to register
+pub const GUEST_MESSAGES: i32 = 3;
+
+/// New guest records one IP address can open per day, so rotating device ids doesn't give unlimited
+/// answers. Mobile carriers put many subscribers behind one CGNAT address, so this only catches
+/// bulk use (the same trade-off as the trial network limit in trial_abuse.rs).
+const GUEST_SESSIONS_PER_IP_PER_DAY: i64 = 15;
+
+/// Days an unclaimed guest record and its chats are kept after the device was last seen
+const UNCLAIMED_GUEST_RETENTION_DAYS: i32 = 30;
+
+const MAX_GUEST_QUESTION_CHARS: usize = 1000;
+
+fn database_error(e: sqlx::Error) -> ApiError {
+    eprintln!("Guest session database error: {}", e);
+    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Greška baze podataka")
+}
+
+fn register_to_continue() -> ApiError {
+    crate::metrics::record_trial_rejection("guest");
+    ApiError {
+        status: StatusCode::TOO_MANY_REQUESTS,
+        body: Some(ErrorResponse {
+            error: "REGISTER_TO_CONTINUE".to_string(),
+            message: "Iskoristili ste besplatne poruke - registrujte se da biste nastavili".to_string(),
+            details: Some(serde_json::json!({"guest_messages": GUEST_MESSAGES})),
+        }),
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct GuestSession {
+    messages_remaining: i32,
+    claimed_by: Option<Uuid>,
+}
+
+/// The guest record for the requesting device, created on first use. Refused for signed-in
+/// requests and for devices that already belong to an account.
+async fn guest_device(
+    headers: &HeaderMap,
+    jwt_secret: &str,
+    supabase_jwt_secret: Option<&str>,
+    pool: &PgPool,
+) -> Result<(Uuid, i32), ApiError> {
+    // Signed-in users ask through their chats so the question counts against their plan
+    if crate::database::verify_user_from_headers_async(headers, jwt_secret, supabase_jwt_secret, pool)
+        .await
+        .is_some()
+    {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "USE_ACCOUNT",
+            "Prijavljeni ste - postavite pitanje u okviru svog naloga",
+        ));
+    }
+
+    let device_session_id = headers
+        .get("X-Device-Session-Id")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| Uuid::parse_str(s.trim()).ok())
+        .ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, "DEVICE_SESSION_REQUIRED", "Nedostaje identifikator uređaja"))?;
+
+    let login_to_continue = || {
+        ApiError::new(
+            StatusCode::FORBIDDEN,
+            "LOGIN_TO_CONTINUE",
+            "Na ovom uređaju već postoji nalog - prijavite se da biste nastavili",
+        )
+    };
+
+    // A device that was ever signed in belongs to an account - its trial already covers it
+    let device_has_account: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM user_sessions WHERE device_info->>'session_id' = $1)"
+    )
+    .bind(device_session_id.to_string())
+    .fetch_one(pool)
+    .await
+    .map_err(database_error)?;
+
+    if device_has_account {
+        return Err(login_to_continue());
+    }
+
+    let ip_address = crate::api::extract_client_ip(headers);
+    let guest = get_or_create_guest(device_session_id, &ip_address, pool)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(register_to_continue)?;
+
+    if guest.claimed_by.is_some() {
+        return Err(login_to_continue());
+    }
+    Ok((device_session_id, guest.messages_remaining))
+}
+
+/// Look up the device's guest record, opening one when the IP is still under its daily cap.
+/// Serialized per IP so parallel requests can't race the cap.
+async fn get_or_create_guest(device_session_id: Uuid, ip_address: &str, pool: &PgPool) -> Result<Option<GuestSession>, sqlx::Error> {
+    let existing = sqlx::query_as::<_, GuestSession>(
+        "UPDATE guest_sessions SET last_seen_at = NOW() WHERE device_session_id = $1
+         RETURNING messages_remaining, claimed_by"
+    )
+    .bind(device_session_id)
+    .fetch_optional(pool)
+    .await?;
+    if existing.is_some() {
+        return Ok(existing);
+    }
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('guest_session:' || $1))")
+        .bind(ip_address)
+        .execute(&mut *tx)
+        .await?;
+
+    let opened_by_ip: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM guest_sessions WHERE ip_address = $1 AND created_at > NOW() - INTERVAL '1 day'"
+    )
+    .bind(ip_address)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if opened_by_ip >= GUEST_SESSIONS_PER_IP_PER_DAY {
+        return Ok(None);
+    }
+
+    let guest = sqlx::query_as::<_, GuestSession>(
+        "INSERT INTO guest_sessions (device_session_id, ip_address, messages_remaining)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (device_session_id) DO UPDATE SET last_seen_at = NOW()
+         RETURNING messages_remaining, claimed_by"
+    )
+    .bind(device_session_id)
+    .bind(ip_address)
+    .bind(GUEST_MESSAGES)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some(guest))
+}
+
+#[derive(Debug, Serialize)]
+pub struct GuestStatusResponse {
+    pub messages_remaining: i32,
+    pub guest_messages: i32,
+}
+
+/// Messages the device has left as a guest
+pub async fn guest_status_handler(
+    State((pool, _, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ResponseJson<GuestStatusResponse>, ApiError> {
+    let (_, messages_remaining) = guest_device(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+    Ok(ResponseJson(GuestStatusResponse {
+        messages_remaining,
+        guest_messages: GUEST_MESSAGES,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GuestQuestionRequest {
+    pub question: String,
+    pub chat_id: Option<i64>, // None starts a new chat
+    #[serde(default)]
+    pub script: crate::transliteration::ResponseScript,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GuestQuestionResponse {
+    pub chat_id: i64,
+    pub messages_remaining: i32,
+    #[serde(flatten)]
+    pub response: QuestionResponse,
+}
+
+/// Answer a guest's question and save the exchange to one of the device's chats
+pub async fn guest_question_handler(
+    State((pool, openrouter_api_key, openai_api_key, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<GuestQuestionRequest>,
+) -> Result<ResponseJson<GuestQuestionResponse>, ApiError> {
+    let (device_session_id, _) = guest_device(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let question = request.question.trim();
+    if question.is_empty() || question.chars().count() > MAX_GUEST_QUESTION_CHARS {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "INVALID_QUESTION",
+            "Pitanje mora imati između 1 i 1000 karaktera",
+        ));
+    }
+
+    let question = crate::moderation::screen_question(question)
+        .map_err(|category| category.error())?;
+    let question = question.as_str();
+
+    if let Some(chat_id) = request.chat_id {
+        let owns_chat: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM chats WHERE id = $1 AND guest_device_session_id = $2 AND deleted_at IS NULL)"
+        )
+        .bind(chat_id)
+        .bind(device_session_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(database_error)?;
+        if !owns_chat {
+            return Err(ApiError::new(StatusCode::NOT_FOUND, "CHAT_NOT_FOUND", "Razgovor nije pronađen"));
+        }
+    }
+
+    // Take the message up front so parallel requests can't overspend the allowance
+    let messages_remaining: i32 = sqlx::query_scalar(
+        "UPDATE guest_sessions SET messages_remaining = messages_remaining - 1, last_seen_at = NOW()
+         WHERE device_session_id = $1 AND claimed_by IS NULL AND messages_remaining > 0
+         RETURNING messages_remaining"
+    )
+    .bind(device_session_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(register_to_continue)?;
+
+    let (mut response, stored_content) = match crate::api::answer_anonymous_question(question, &openrouter_api_key, &openai_api_key, &pool).await {
+        Ok(answer) => answer,
+        Err(e) => {
+            eprintln!("❌ Guest question answer failed: {}", e);
+            // Give the message back - the device didn't get an answer
+            if let Err(e) = sqlx::query("UPDATE guest_sessions SET messages_remaining = messages_remaining + 1 WHERE device_session_id = $1")
+                .bind(device_session_id)
+                .execute(&pool)
+                .await
+            {
+                eprintln!("Failed to refund guest message: {}", e);
+            }
+            return Err(ApiError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "AI_UNAVAILABLE",
+                "Odgovor trenutno nije dostupan - pokušajte ponovo",
+            ));
+        }
+    };
+
+    let chat_id = save_guest_exchange(device_session_id, request.chat_id, question, &stored_content, response.law_name.as_deref(), &pool)
+        .await
+        .map_err(database_error)?;
+
+    crate::transliteration::apply_script(&mut response, request.script);
+
+    Ok(ResponseJson(GuestQuestionResponse {
+        chat_id,
+        messages_remaining,
+        response,
+    }))
+}
+
+/// Store a question and its answer, starting a new guest chat when there is none yet
+async fn save_guest_exchange(
+    device_session_id: Uuid,
+    chat_id: Option<i64>,
+    question: &str,
+    answer: &str,
+    law_name: Option<&str>,
+    pool: &PgPool,
+) -> Result<i64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let chat_id = match chat_id {
+        Some(chat_id) => {
+            sqlx::query("UPDATE chats SET updated_at = NOW() WHERE id = $1")
+                .bind(chat_id)
+                .execute(&mut *tx)
+                .await?;
+            chat_id
+        }
+        None => {
+            let title: String = question.chars().take(60).collect();
+            sqlx::query_scalar(
+                "INSERT INTO chats (title, guest_device_session_id, created_at, updated_at) VALUES ($1, $2, NOW(), NOW()) RETURNING id"
+            )
+            .bind(&title)
+            .bind(device_session_id)
+            .fetch_one(&mut *tx)
+            .await?
+        }
+    };
+
+    sqlx::query(
+        "INSERT INTO messages (chat_id, role, content, law_name, created_at)
+         VALUES ($1, 'user', $2, NULL, NOW()), ($1, 'assistant', $3, $4, NOW() + INTERVAL '1 second')"
+    )
+    .bind(chat_id)
+    .bind(question)
+    .bind(answer)
+    .bind(law_name)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(chat_id)
+}
+
+/// The device's guest chats, most recent first
+pub async fn guest_chats_handler(
+    State((pool, _, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ResponseJson<Vec<Chat>>, ApiError> {
+    let (device_session_id, _) = guest_device(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let chats = sqlx::query_as::<_, Chat>(
+        "SELECT id, title, user_id, folder_id, jurisdiction, created_at, updated_at
+         FROM chats
+         WHERE guest_device_session_id = $1 AND deleted_at IS NULL
+         ORDER BY updated_at DESC, id DESC"
+    )
+    .bind(device_session_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(database_error)?;
+
+    Ok(ResponseJson(chats))
+}
+
+/// Messages of one of the device's guest chats
+pub async fn guest_messages_handler(
+    State((pool, _, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(chat_id): Path<i64>,
+) -> Result<ResponseJson<Vec<Message>>, ApiError> {
+    let (device_session_id, _) = guest_device(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let messages = sqlx::query_as::<_, Message>(
+        "SELECT m.id, m.chat_id, m.role, m.content, m.law_name, m.has_document, m.document_filename, m.contract_file_id,
+                m.contract_type, m.contract_filename, m.message_feedback, m.edited_at, m.stale_at, m.created_at
+         FROM messages m
+         JOIN chats c ON c.id = m.chat_id
+         WHERE m.chat_id = $1 AND c.guest_device_session_id = $2 AND c.deleted_at IS NULL AND m.superseded_at IS NULL
+         ORDER BY m.created_at ASC"
+    )
+    .bind(chat_id)
+    .bind(device_session_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(database_error)?;
+
+    if messages.is_empty() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "CHAT_NOT_FOUND", "Razgovor nije pronađen"));
+    }
+    Ok(ResponseJson(messages))
+}
+
+/// Delete guest records (and their chats) of devices that stopped asking without ever registering.
+/// Claimed records are kept so the device still can't start a second guest trial.
+pub async fn purge_unclaimed(pool: &PgPool) -> Result<(u64, u64), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let chats = sqlx::query(
+        "DELETE FROM chats
+         WHERE user_id IS NULL AND guest_device_session_id IN (
+             SELECT device_session_id FROM guest_sessions
+             WHERE claimed_by IS NULL AND last_seen_at < NOW() - make_interval(days => $1)
+         )"
+    )
+    .bind(UNCLAIMED_GUEST_RETENTION_DAYS)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    let guests = sqlx::query(
+        "DELETE FROM guest_sessions WHERE claimed_by IS NULL AND last_seen_at < NOW() - make_interval(days => $1)"
+    )
+    .bind(UNCLAIMED_GUEST_RETENTION_DAYS)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    tx.commit().await?;
+    Ok((guests, chats))
+}
+
+/// Hand the device's guest chats to an account signed in on it and close the guest record.
+/// Guest messages are a separate allowance, so the account's trial is not reduced.
+/// Returns how many chats were moved.
+pub async fn claim_guest_chats(conn: &mut PgConnection, device_session_id: Uuid, user_id: Uuid) -> Result<i64, sqlx::Error> {
+    let claimed = sqlx::query(
+        "UPDATE guest_sessions SET claimed_by = $2, claimed_at = NOW()
+         WHERE device_session_id = $1 AND claimed_by IS NULL"
+    )
+    .bind(device_session_id)
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    if claimed == 0 {
+        return Ok(0);
+    }
+
+    let moved = sqlx::query(
+        "UPDATE chats SET user_id = $2, guest_device_session_id = NULL
+         WHERE guest_device_session_id = $1"
+    )
+    .bind(device_session_id)
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    Ok(moved as i64)
+}
@@ -0,0 +1,179 @@
+// Readiness checks for /health/ready. /health stays a static liveness probe; readiness verifies the
+// database and our upstreams. OpenRouter and law-scrape status come from the outcome of recent real
+// calls, and are only probed directly when there has been no traffic for a while.
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json as ResponseJson,
+};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Observations older than this are refreshed with a direct probe
+const RECENT_WINDOW_SECONDS: i64 = 120;
+const PROBE_TIMEOUT_SECONDS: u64 = 5;
+const DB_TIMEOUT_SECONDS: u64 = 3;
+
+const OPENROUTER_PROBE_URL: &str = "https://openrouter.ai/api/v1/auth/key";
+const LAW_SOURCE_PROBE_URL: &str = "https://www.paragraf.rs/";
+
+/// Outcome of the last call to an upstream (unix seconds, 0 = never)
+struct Observation {
+    at: AtomicI64,
+    ok: AtomicBool,
+}
+
+impl Observation {
+    const fn new() -> Self {
+        Self { at: AtomicI64::new(0), ok: AtomicBool::new(false) }
+    }
+
+    fn record(&self, success: bool) {
+        self.ok.store(success, Ordering::Relaxed);
+        self.at.store(chrono::Utc::now().timestamp(), Ordering::Relaxed);
+    }
+
+    /// (success, age in seconds) of the last call, if it's recent enough to trust
+    fn recent(&self) -> Option<(bool, i64)> {
+        let at = self.at.load(Ordering::Relaxed);
+        let age = chrono::Utc::now().timestamp() - at;
+        (at > 0 && age <= RECENT_WINDOW_SECONDS).then(|| (self.ok.load(Ordering::Relaxed), age))
+    }
+}
+
+static OPENROUTER: Observation = Observation::new();
+static LAW_SCRAPE: Observation = Observation::new();
+
+pub fn observe_openrouter(success: bool) {
+    OPENROUTER.record(success);
+}
+
+pub fn observe_law_scrape(success: bool) {
+    LAW_SCRAPE.record(success);
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependencyStatus {
+    pub status: &'static str, // "ok" | "fail"
+    pub source: &'static str, // "query" | "observed" | "probe"
+    pub latency_ms: Option<u128>,
+    pub age_seconds: Option<i64>, // For observed results: how long ago the last real call was
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    pub status: &'static str, // "ok" | "degraded" | "fail"
+    pub database: DependencyStatus,
+    pub openrouter: DependencyStatus,
+    pub law_sources: DependencyStatus,
+}
+
+/// GET /health/ready - 503 when the database is unreachable (the machine can't serve anything),
+/// 200 with status "degraded" when only an upstream is failing (answers fall back to cached articles).
+pub async fn readiness_handler(
+    State((pool, openrouter_api_key)): State<(PgPool, String)>,
+) -> (StatusCode, ResponseJson<ReadinessResponse>) {
+    let (database, openrouter, law_sources) = tokio::join!(
+        check_database(&pool),
+        check_upstream(&OPENROUTER, OPENROUTER_PROBE_URL, Some(&openrouter_api_key)),
+        check_upstream(&LAW_SCRAPE, LAW_SOURCE_PROBE_URL, None),
+    );
+
+    let (code, status) = if database.status != "ok" {
+        (StatusCode::SERVICE_UNAVAILABLE, "fail")
+    } else if openrouter.status != "ok" || law_sources.status != "ok" {
+        (StatusCode::OK, "degraded")
+    } else {
+        (StatusCode::OK, "ok")
+    };
+
+    if status != "ok" {
+        println!("⚠️  Readiness {}: db={} openrouter={} law_sources={}", status, database.status, openrouter.status, law_sources.status);
+    }
+
+    (code, ResponseJson(ReadinessResponse { status, database, openrouter, law_sources }))
+}
+
+async fn check_database(pool: &PgPool) -> DependencyStatus {
+    let started_at = Instant::now();
+    let result = tokio::time::timeout(
+        Duration::from_secs(DB_TIMEOUT_SECONDS),
+        sqlx::query_scalar::<_, i32>("SELECT 1").fetch_one(pool),
+    )
+    .await;
+
+    let error = match result {
+        Ok(Ok(_)) => None,
+        Ok(Err(e)) => Some(format!("Database query failed: {}", e)),
+        Err(_) => Some(format!("Database did not respond within {}s", DB_TIMEOUT_SECONDS)),
+    };
+
+    DependencyStatus {
+        status: if error.is_none() { "ok" } else { "fail" },
+        source: "query",
+        latency_ms: Some(started_at.elapsed().as_millis()),
+        age_seconds: None,
+        error,
+    }
+}
+
+/// Use the last real call when recent, otherwise probe the upstream and remember the result
+async fn check_upstream(observation: &Observation, probe_url: &str, bearer: Option<&str>) -> DependencyStatus {
+    if let Some((ok, age)) = observation.recent() {
+        return DependencyStatus {
+            status: if ok { "ok" } else { "fail" },
+            source: "observed",
+            latency_ms: None,
+            age_seconds: Some(age),
+            error: (!ok).then(|| "Last request to this upstream failed".to_string()),
+        };
+    }
+
+    let started_at = Instant::now();
+    let error = probe(probe_url, bearer).await.err();
+    observation.record(error.is_none());
+
+    DependencyStatus {
+        status: if error.is_none() { "ok" } else { "fail" },
+        source: "probe",
+        latency_ms: Some(started_at.elapsed().as_millis()),
+        age_seconds: None,
+        error,
+    }
+}
+
+async fn probe(url: &str, bearer: Option<&str>) -> Result<(), String> {
+    let mut request = reqwest::Client::new()
+        .get(url)
+        .timeout(Duration::from_secs(PROBE_TIMEOUT_SECONDS))
+        .header("User-Agent", "Mozilla/5.0 (compatible; NormaAI/1.0; +https://chat.normaai.rs)");
+    if let Some(token) = bearer {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let response = request.send().await.map_err(|e| format!("Probe failed: {}", e))?;
+    if response.status().is_server_error() || response.status() == StatusCode::UNAUTHORIZED {
+        return Err(format!("Probe returned HTTP {}", response.status()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observation_recent_window() {
+        let observation = Observation::new();
+        assert_eq!(observation.recent(), None);
+
+        observation.record(false);
+        assert_eq!(observation.recent(), Some((false, 0)));
+
+        observation.at.store(chrono::Utc::now().timestamp() - RECENT_WINDOW_SECONDS - 1, Ordering::Relaxed);
+        assert_eq!(observation.recent(), None);
+    }
+}
@@ -0,0 +1,82 @@
+// Conditional requests for law texts. Cached laws are several megabytes and rarely change, so their
+// responses carry an ETag derived from law_cache.cached_at; a client that sends it back in
+// If-None-Match gets 304 Not Modified instead of the whole text until the law is re-cached.
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Weak ETag for a cached law version - weak because the bytes differ with content encoding
+pub fn law_etag(law_name: &str, cached_at: chrono::DateTime<chrono::Utc>) -> String {
+    let digest = Sha256::digest(format!("{}:{}", law_name, cached_at.timestamp_micros()).as_bytes());
+    format!("W/\"{}\"", &format!("{:x}", digest)[..32])
+}
+
+/// Whether If-None-Match names the current ETag (weak comparison, "*" matches anything)
+pub fn is_not_modified(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag))
+}
+
+/// JSON response tagged with the ETag, or an empty 304 when the client already has this version
+pub fn conditional_json<T: Serialize>(headers: &HeaderMap, etag: &str, body: T) -> Response {
+    let Ok(etag_value) = HeaderValue::from_str(etag) else {
+        return Json(body).into_response();
+    };
+    // Clients may keep the law but must revalidate before using it
+    let cache_headers = [
+        (header::ETAG, etag_value),
+        (header::CACHE_CONTROL, HeaderValue::from_static("private, no-cache")),
+    ];
+
+    if is_not_modified(headers, etag) {
+        (StatusCode::NOT_MODIFIED, cache_headers).into_response()
+    } else {
+        (cache_headers, Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_law_etag_changes_when_recached() {
+        let cached_at = chrono::Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+        let etag = law_etag("Zakon o radu", cached_at);
+
+        assert!(etag.starts_with("W/\"") && etag.ends_with('"'));
+        assert_eq!(etag, law_etag("Zakon o radu", cached_at));
+        assert_ne!(etag, law_etag("Zakon o radu", cached_at + chrono::Duration::seconds(1)));
+        assert_ne!(etag, law_etag("Zakon o porezu na dohodak građana", cached_at));
+    }
+
+    #[test]
+    fn test_is_not_modified() {
+        let etag = "W/\"abc123\"";
+        let mut headers = HeaderMap::new();
+        assert!(!is_not_modified(&headers, etag));
+
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"old\", W/\"abc123\""));
+        assert!(is_not_modified(&headers, etag));
+
+        // Weak comparison - a strong tag with the same opaque value matches
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"abc123\""));
+        assert!(is_not_modified(&headers, etag));
+
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert!(is_not_modified(&headers, etag));
+
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("W/\"other\""));
+        assert!(!is_not_modified(&headers, etag));
+    }
+}
@@ -0,0 +1,356 @@
+// Localized error messages. Handlers write ErrorResponse messages in Serbian Latin; the
+// localize_errors middleware rewrites the message of JSON error bodies into the language the
+// client asks for in Accept-Language - Cyrillic by transliteration, English from a catalog keyed by
+// the error code. The `error` code is never changed, so clients keep matching on it. A code missing
+// from the catalog keeps its Serbian message. VALIDATION_ERROR bodies list each invalid field with
+// its validator code, and are localized field by field.
+use crate::models::ErrorResponse;
+use crate::request_id::patch_error_body;
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+    Json,
+};
+use serde_json::{json, Map, Value};
+use validator::{ValidationError, ValidationErrors};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    SrLatn,
+    SrCyrl,
+    En,
+}
+
+impl Locale {
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Locale::SrLatn => "sr-Latn",
+            Locale::SrCyrl => "sr-Cyrl",
+            Locale::En => "en",
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Locale> {
+        let tag = tag.trim().to_lowercase();
+        let primary = tag.split(['-', '_']).next().unwrap_or_default();
+        match primary {
+            "sr" if tag.contains("cyrl") => Some(Locale::SrCyrl),
+            // Bosnian, Croatian and Montenegrin readers get the Latin messages
+            "sr" | "sh" | "bs" | "hr" | "cnr" => Some(Locale::SrLatn),
+            "en" => Some(Locale::En),
+            _ => None,
+        }
+    }
+
+    /// The supported language the client prefers most (by q-value), Serbian Latin by default
+    pub fn from_accept_language(header: &str) -> Locale {
+        let mut ranges: Vec<(&str, f32)> = header
+            .split(',')
+            .map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next().unwrap_or_default().trim();
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .and_then(|q| q.trim().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                (tag, quality)
+            })
+            .filter(|(_, quality)| *quality > 0.0)
+            .collect();
+        // Stable, so equal weights keep the client's order
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranges
+            .into_iter()
+            .find_map(|(tag, _)| Locale::from_tag(tag))
+            .unwrap_or(Locale::SrLatn)
+    }
+}
+
+/// English message for an error code
+fn english(code: &str) -> Option<&'static str> {
+    Some(match code {
+        "ACCOUNT_INACTIVE" | "ACCOUNT_NOT_ACTIVE" => "The account is not active",
+        "ACCOUNT_PERMANENTLY_DELETED" => "This account has been permanently deleted",
+        "AI_UNAVAILABLE" => "An answer is not available right now - please try again",
+        "ALREADY_LINKED" => "The accounts are already linked",
+        "AVATAR_TOO_LARGE" => "The image can be at most 2 MB",
+        "BOTH_SUBSCRIBED" => "Both accounts have an active subscription - cancel one before linking",
+        "CHAT_NOT_FOUND" => "Chat not found",
+        "CHECKOUT_REQUIRED" => "The subscription is paid by card",
+        "COMPANY_DETAILS_REQUIRED" => "A company invoice needs the name, address, tax ID (PIB) and registration number",
+        "CONFIRMATION_REQUIRED" => "Confirming the account deletion is required",
+        "CONSENT_REQUIRED" => "Please accept the terms of use before asking questions",
+        "CONSENT_VERSION_OUTDATED" => "The terms of use have changed - please read the new version",
+        "COST_CAP_REACHED" => "You have reached your monthly usage limit - it resets at the start of next month",
+        "DATABASE_ERROR" => "A database error occurred",
+        "DELETE_ERROR" => "The account could not be deleted",
+        "DEVICE_SESSION_REQUIRED" => "The device identifier is missing",
+        "EMAIL_NOT_VERIFIED" => "Verify your e-mail address first",
+        "EXPORT_NOT_FOUND" => "Export not found",
+        "EXPORT_RATE_LIMITED" => "A data export has already been requested - the link is on its way to your e-mail",
+        "FORBIDDEN" => "You don't have access",
+        "GRACE_PERIOD_EXPIRED" => "The account recovery period has expired or the account was not scheduled for deletion",
+        "HASH_ERROR" => "The password could not be processed",
+        "IDEMPOTENCY_KEY_REUSED" => "The Idempotency-Key has already been used for a different request",
+        "IMPERSONATION_READ_ONLY" => "Support account view only covers chats and account status",
+        "INVALID_AVATAR" => "The image must be PNG, JPEG or WebP",
+        "INVALID_BILLING_PERIOD" => "Invalid billing period",
+        "INVALID_DEADLINE" => "The deadline cannot be calculated from the given data",
+        "INVALID_FEE_REQUEST" => "The fee cannot be calculated from the given data",
+        "INVALID_IDEMPOTENCY_KEY" => "Invalid Idempotency-Key",
+        "INVALID_LINK_TOKEN" => "The other account could not be verified - please sign in again",
+        "INVALID_PICTURE_URL" => "The image URL must start with https://",
+        "INVALID_PLAN" | "INVALID_PLAN_TYPE" => "Unsupported plan",
+        "INVALID_PLAN_COMBINATION" => "Invalid combination of plan and billing period",
+        "INVALID_QUESTION" => "The question must be between 1 and 1000 characters",
+        "INVALID_REFRESH_TOKEN" => "Invalid or expired refresh token",
+        "INVALID_SESSION_ID" => "Invalid session ID",
+        "INVALID_TOKEN" => "Invalid or expired token",
+        "INVALID_TWO_FACTOR_CODE" => "Invalid two-factor authentication code",
+        "INVALID_UPLOAD" => "No file was uploaded",
+        "INVALID_USER_ID" => "Invalid user ID",
+        "INVOICE_NOT_FOUND" => "Invoice not found",
+        "LOGIN_TO_CONTINUE" => "An account already exists on this device - sign in to continue",
+        "MISSING_TOKEN" | "NO_TOKEN" => "Token not found",
+        "NOT_FOUND" => "Not found",
+        "PAYMENTS_UNAVAILABLE" => "Card payments are not available right now",
+        "PAYMENT_PROVIDER_ERROR" => "The payment provider returned an error - please try again",
+        "PII_REDACTION_UNAVAILABLE" => "Personal data redaction is not available right now",
+        "PROMO_CODE_ALREADY_USED" => "You have already used this promo code",
+        "PROMO_CODE_EXPIRED" => "The promo code has expired",
+        "PROMO_CODE_INVALID" => "The promo code does not exist",
+        "PROMO_CODE_USED_UP" => "The promo code has been used up",
+        "REASON_REQUIRED" => "Please give a reason for viewing the account",
+        "REFERRAL_CODE_INVALID" => "The referral code does not exist",
+        "REFERRAL_NOT_ALLOWED" => "A referral code can only be redeemed once, within 14 days of registering",
+        "REFERRAL_OWN_CODE" => "You can't redeem your own code",
+        "REFRESH_TOKEN_REQUIRED" => "A refresh token is required",
+        "REFRESH_TOKEN_REUSED" => "The session was ended for security reasons - please sign in again",
+        "REGISTER_TO_CONTINUE" => "You have used your free questions - register to continue",
+        "REQUEST_IN_PROGRESS" => "The question is still being processed - try again in a few moments",
+        "REQUEST_REFUSED" => "I can't help with this request because it concerns committing an unlawful act. I can explain what the law says about this area.",
+        "RESTORE_ERROR" => "The account could not be restored",
+        "SESSION_NOT_FOUND" => "Session not found",
+        "SESSION_REVOKE_ERROR" => "The sessions could not be revoked",
+        "STATUS_ERROR" => "The user status could not be loaded",
+        "SUBSCRIPTION_CANCEL_ERROR" => "The subscription could not be cancelled",
+        "SUPABASE_NOT_CONFIGURED" => "Supabase is not configured",
+        "TEAM_ADMIN" => "You can't delete your account while you are a team administrator. Transfer team ownership first.",
+        "TOKEN_ERROR" => "The token could not be generated",
+        "TOKEN_EXPIRED_OR_USED" => "The token has expired or has already been used",
+        "TOO_MANY_CODE_ATTEMPTS" => "Too many codes tried - please try again later",
+        "TRIAL_ABUSE_DETECTED" => "The free trial is not available for this account",
+        "TWO_FACTOR_ALREADY_ENABLED" => "Two-factor authentication is already enabled",
+        "TWO_FACTOR_ERROR" => "The code could not be verified",
+        "TWO_FACTOR_NOT_ENABLED" => "Two-factor authentication is not enabled",
+        "TWO_FACTOR_NOT_SETUP" => "Start the two-factor authentication setup first",
+        "TWO_FACTOR_REQUIRED" => "Enter your two-factor authentication code",
+        "UNAUTHORIZED" => "Not authorized",
+        "USER_NOT_FOUND" => "User not found",
+        "USE_ACCOUNT" => "You are signed in - ask the question in your account",
+        "VALIDATION_ERROR" => "The submitted data is not valid",
+        _ => return None,
+    })
+}
+
+/// English message for a field validator code (the `code` of a #[validate] rule)
+fn english_validation(code: &str) -> Option<&'static str> {
+    Some(match code {
+        "address_too_long" => "The address can be at most 300 characters",
+        "billing_name_too_long" => "The name can be at most 200 characters",
+        "city_too_long" => "The city can be at most 100 characters",
+        "invalid_bar_number" => "The bar registration number is not valid",
+        "invalid_email" => "The e-mail address is not valid",
+        "invalid_maticni_broj" => "The registration number (matični broj) must have 8 digits",
+        "invalid_pib" => "The tax ID (PIB) is not valid",
+        "invalid_token" => "The token is not valid",
+        "name_too_long" => "The name can be at most 100 characters",
+        "office_name_too_long" => "The office name can be at most 200 characters",
+        "password_length" => "The password must have between 8 and 128 characters",
+        "password_too_short" => "The password must have at least 8 characters",
+        "picture_url_too_long" => "The picture URL is too long",
+        "postal_code_too_long" => "The postal code is too long",
+        "signature_too_long" => "The signature can be at most 1000 characters",
+        "title_too_long" => "The title can be at most 100 characters",
+        "weak_password" => "The password must contain upper and lower case letters, a digit and a special character",
+        _ => return None,
+    })
+}
+
+/// Error for a custom field validator: a stable code plus the Serbian message
+pub fn field_error(code: &'static str, message: &'static str) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(message.into());
+    error
+}
+
+/// 400 VALIDATION_ERROR listing the first failed rule of each field. `details.fields` maps the
+/// field to its validator code and Serbian message, so the message can be localized per field.
+pub fn validation_error(errors: &ValidationErrors) -> (StatusCode, Json<ErrorResponse>) {
+    let mut fields: Vec<(&str, &ValidationError)> = errors
+        .field_errors()
+        .into_iter()
+        .filter_map(|(field, errors)| errors.first().map(|error| (field, error)))
+        .collect();
+    fields.sort_by_key(|(field, _)| *field);
+
+    let mut details = Map::new();
+    let mut messages = Vec::new();
+    for (field, error) in fields {
+        let message = error.message.as_deref().unwrap_or("Neispravna vrednost").to_string();
+        messages.push(message.clone());
+        details.insert(field.to_string(), json!({ "code": error.code, "message": message }));
+    }
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse {
+            error: "VALIDATION_ERROR".to_string(),
+            message: messages.join("; "),
+            details: Some(json!({ "fields": details })),
+        }),
+    )
+}
+
+/// Localize the per-field messages of a VALIDATION_ERROR body in place; returns the joined message,
+/// or None (body untouched) when there are no field details or a field has no translation
+fn localize_fields(details: Option<&mut Value>, locale: Locale) -> Option<String> {
+    let fields = details?.get_mut("fields")?.as_object_mut()?;
+    let localized = fields
+        .values()
+        .map(|field| {
+            let message = field.get("message")?.as_str()?;
+            Some(match locale {
+                Locale::SrLatn => message.to_string(),
+                Locale::SrCyrl => crate::transliteration::to_cyrillic(message),
+                Locale::En => english_validation(field.get("code")?.as_str()?)?.to_string(),
+            })
+        })
+        .collect::<Option<Vec<String>>>()?;
+    if localized.is_empty() {
+        return None;
+    }
+    for (field, message) in fields.values_mut().zip(&localized) {
+        field["message"] = Value::String(message.clone());
+    }
+    Some(localized.join("; "))
+}
+
+/// The message for an error code in a locale, from the Serbian Latin original
+pub fn localize_message(locale: Locale, code: &str, message: &str) -> String {
+    match locale {
+        Locale::SrLatn => message.to_string(),
+        Locale::SrCyrl => crate::transliteration::to_cyrillic(message),
+        Locale::En => english(code).map(str::to_string).unwrap_or_else(|| message.to_string()),
+    }
+}
+
+/// Localize the message of an ErrorResponse-shaped body; other JSON is left alone
+fn localize_body(body: &mut Value, locale: Locale) -> bool {
+    let Some(object) = body.as_object_mut() else {
+        return false;
+    };
+    let (Some(Value::String(code)), Some(Value::String(message))) = (object.get("error"), object.get("message")) else {
+        return false;
+    };
+    let (code, message) = (code.clone(), message.clone());
+    let fields = if code == "VALIDATION_ERROR" {
+        localize_fields(object.get_mut("details"), locale)
+    } else {
+        None
+    };
+    let localized = fields.unwrap_or_else(|| localize_message(locale, &code, &message));
+    object.insert("message".to_string(), Value::String(localized));
+    true
+}
+
+/// Middleware localizing ErrorResponse messages per Accept-Language
+pub async fn localize_errors(request: Request, next: Next) -> Response {
+    let locale = request
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .map(Locale::from_accept_language)
+        .unwrap_or(Locale::SrLatn);
+    let response = next.run(request).await;
+    if locale == Locale::SrLatn {
+        return response; // Messages are written in Serbian Latin
+    }
+
+    let mut response = patch_error_body(response, |body| localize_body(body, locale)).await;
+    if response.status().is_client_error() || response.status().is_server_error() {
+        response
+            .headers_mut()
+            .insert(header::CONTENT_LANGUAGE, HeaderValue::from_static(locale.tag()));
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accept_language() {
+        assert_eq!(Locale::from_accept_language("en-US,en;q=0.9"), Locale::En);
+        assert_eq!(Locale::from_accept_language("sr-Cyrl-RS"), Locale::SrCyrl);
+        assert_eq!(Locale::from_accept_language("sr-RS,sr;q=0.9,en;q=0.8"), Locale::SrLatn);
+        assert_eq!(Locale::from_accept_language("de-DE,en;q=0.5,sr-Cyrl;q=0.7"), Locale::SrCyrl);
+        assert_eq!(Locale::from_accept_language("fr, en;q=0"), Locale::SrLatn);
+        assert_eq!(Locale::from_accept_language(""), Locale::SrLatn);
+    }
+
+    #[test]
+    fn test_validation_error_per_field() {
+        use validator::Validate;
+
+        #[derive(Validate)]
+        struct Form {
+            #[validate(length(max = 100, code = "name_too_long", message = "Ime može imati najviše 100 karaktera"))]
+            name: String,
+            #[validate(email(code = "invalid_email", message = "Neispravna e-mail adresa"))]
+            email: String,
+        }
+
+        let form = Form { name: "P".repeat(101), email: "petar".to_string() };
+        let (status, Json(error)) = validation_error(&form.validate().unwrap_err());
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.message, "Neispravna e-mail adresa; Ime može imati najviše 100 karaktera");
+
+        let mut body = serde_json::to_value(&error).unwrap();
+        assert!(localize_body(&mut body, Locale::En));
+        assert_eq!(body["error"], "VALIDATION_ERROR");
+        assert_eq!(body["message"], "The e-mail address is not valid; The name can be at most 100 characters");
+        assert_eq!(body["details"]["fields"]["email"]["message"], "The e-mail address is not valid");
+
+        let mut body = serde_json::to_value(&error).unwrap();
+        assert!(localize_body(&mut body, Locale::SrCyrl));
+        assert_eq!(body["details"]["fields"]["name"]["message"], "Име може имати највише 100 карактера");
+
+        // Without field details the generic message is used
+        let mut body = json!({ "error": "VALIDATION_ERROR", "message": "Podaci nisu validni" });
+        assert!(localize_body(&mut body, Locale::En));
+        assert_eq!(body["message"], "The submitted data is not valid");
+    }
+
+    #[test]
+    fn test_localize_body_keeps_code() {
+        let mut body = json!({ "error": "CHAT_NOT_FOUND", "message": "Razgovor nije pronađen", "details": null });
+        assert!(localize_body(&mut body, Locale::En));
+        assert_eq!(body["error"], "CHAT_NOT_FOUND");
+        assert_eq!(body["message"], "Chat not found");
+
+        let mut body = json!({ "error": "CHAT_NOT_FOUND", "message": "Razgovor nije pronađen" });
+        assert!(localize_body(&mut body, Locale::SrCyrl));
+        assert_eq!(body["message"], "Разговор није пронађен");
+
+        // Unknown codes keep the Serbian message
+        let mut body = json!({ "error": "SOMETHING_NEW", "message": "Nešto novo" });
+        assert!(localize_body(&mut body, Locale::En));
+        assert_eq!(body["message"], "Nešto novo");
+
+        let mut other = json!({ "success": false });
+        assert!(!localize_body(&mut other, Locale::En));
+    }
+}
@@ -0,0 +1,235 @@
+// Idempotency keys for question submission. Mobile clients on flaky networks retry /api/question
+// with the same Idempotency-Key header; the first request is answered normally and its response
+// stored, retries get that response back instead of a second answer (and a second trial charge).
+// A key reused with a different request body is rejected rather than silently replayed.
+use crate::models::{ApiError, ErrorResponse, QuestionRequest, QuestionResponse};
+use axum::http::{HeaderMap, StatusCode};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+const MAX_KEY_LENGTH: usize = 255;
+
+/// Keys are kept this long; a retry after that is treated as a new question
+const KEY_RETENTION_HOURS: i32 = 24;
+
+fn error(status: StatusCode, error: &str, message: &str) -> ApiError {
+    ApiError {
+        status,
+        body: Some(ErrorResponse {
+            error: error.to_string(),
+            message: message.to_string(),
+            details: None,
+        }),
+    }
+}
+
+/// The Idempotency-Key header, if the client sent one (1-255 visible ASCII characters)
+pub fn key_from_headers(headers: &HeaderMap) -> Result<Option<String>, ApiError> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+    match value.to_str().map(str::trim) {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH && key.bytes().all(|b| b.is_ascii_graphic()) => {
+            Ok(Some(key.to_string()))
+        }
+        _ => Err(error(StatusCode::BAD_REQUEST, "INVALID_IDEMPOTENCY_KEY", "Neispravan Idempotency-Key")),
+    }
+}
+
+/// Fingerprint of what was asked; the polling ticket differs between retries and is left out
+pub fn fingerprint(request: &QuestionRequest) -> String {
+    let canonical = serde_json::json!({
+        "question": request.question,
+        "chat_id": request.chat_id,
+        "law_name": request.law_name,
+        "law_url": request.law_url,
+        "document_id": request.document_id,
+        "document_content": request.document_content,
+        "document_filename": request.document_filename,
+        "script": request.script,
+        "jurisdiction": request.jurisdiction,
+    });
+    format!("{:x}", Sha256::digest(canonical.to_string().as_bytes()))
+}
+
+pub enum Begin {
+    New(IdempotencyGuard), // First time this key is seen - answer the question
+    Replay(Box<QuestionResponse>),
+}
+
+/// Claim the key for this request, or return the stored response of the original one
+pub async fn begin(user_id: Uuid, key: &str, request_hash: &str, pool: &PgPool) -> Result<Begin, ApiError> {
+    let database_error = |e: sqlx::Error| {
+        eprintln!("Idempotency key database error: {}", e);
+        ApiError::from(StatusCode::INTERNAL_SERVER_ERROR)
+    };
+
+    // A 'processing' row older than the question deadline belongs to a request that died without
+    // cleaning up; the retry takes it over
+    let stale_after_secs = crate::question_timeout::question_timeout().as_secs() as i64 + 30;
+    let claimed: Option<bool> = sqlx::query_scalar(
+        "INSERT INTO idempotency_keys (user_id, idempotency_key, request_hash)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (user_id, idempotency_key) DO UPDATE
+            SET created_at = NOW(), status = 'processing', trial_charged = FALSE
+            WHERE idempotency_keys.status = 'processing'
+              AND idempotency_keys.request_hash = EXCLUDED.request_hash
+              AND idempotency_keys.created_at < NOW() - make_interval(secs => $4)
+         RETURNING TRUE",
+    )
+    .bind(user_id)
+    .bind(key)
+    .bind(request_hash)
+    .bind(stale_after_secs as f64)
+    .fetch_optional(pool)
+    .await
+    .map_err(database_error)?;
+
+    if claimed.is_some() {
+        return Ok(Begin::New(IdempotencyGuard {
+            pool: pool.clone(),
+            user_id,
+            key: key.to_string(),
+            completed: false,
+        }));
+    }
+
+    let (stored_hash, status, response): (String, String, Option<sqlx::types::Json<QuestionResponse>>) = sqlx::query_as(
+        "SELECT request_hash, status, response FROM idempotency_keys WHERE user_id = $1 AND idempotency_key = $2",
+    )
+    .bind(user_id)
+    .bind(key)
+    .fetch_one(pool)
+    .await
+    .map_err(database_error)?;
+
+    if stored_hash != request_hash {
+        return Err(error(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "IDEMPOTENCY_KEY_REUSED",
+            "Idempotency-Key je već korišćen za drugi zahtev",
+        ));
+    }
+    match (status.as_str(), response) {
+        ("completed", Some(response)) => {
+            println!("🔁 Replaying stored answer for idempotency key {} (user {})", key, user_id);
+            crate::metrics::record_idempotent_replay();
+            Ok(Begin::Replay(Box::new(response.0)))
+        }
+        _ => Err(error(
+            StatusCode::CONFLICT,
+            "REQUEST_IN_PROGRESS",
+            "Pitanje se još obrađuje, pokušajte ponovo za nekoliko trenutaka",
+        )),
+    }
+}
+
+/// Holds a claimed key while the question is answered. Dropped without `complete` (error, timeout,
+/// client disconnect) it releases the key so the client's retry is processed from scratch.
+pub struct IdempotencyGuard {
+    pool: PgPool,
+    user_id: Uuid,
+    key: String,
+    completed: bool,
+}
+
+impl IdempotencyGuard {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Store the response returned to the client for replays
+    pub async fn complete(mut self, response: &QuestionResponse) {
+        let result = sqlx::query(
+            "UPDATE idempotency_keys SET status = 'completed', response = $3, completed_at = NOW()
+             WHERE user_id = $1 AND idempotency_key = $2",
+        )
+        .bind(self.user_id)
+        .bind(&self.key)
+        .bind(sqlx::types::Json(response))
+        .execute(&self.pool)
+        .await;
+
+        match result {
+            Ok(_) => self.completed = true,
+            Err(e) => eprintln!("⚠️  Failed to store response for idempotency key {}: {}", self.key, e),
+        }
+    }
+}
+
+impl Drop for IdempotencyGuard {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+        let pool = self.pool.clone();
+        let user_id = self.user_id;
+        let key = std::mem::take(&mut self.key);
+        tokio::spawn(async move {
+            let result = sqlx::query(
+                "DELETE FROM idempotency_keys WHERE user_id = $1 AND idempotency_key = $2 AND status = 'processing'",
+            )
+            .bind(user_id)
+            .bind(&key)
+            .execute(&pool)
+            .await;
+            if let Err(e) = result {
+                eprintln!("⚠️  Failed to release idempotency key {}: {}", key, e);
+            }
+        });
+    }
+}
+
+/// Delete keys past the retention window
+pub async fn purge_expired(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM idempotency_keys WHERE created_at < NOW() - make_interval(hours => $1)")
+        .bind(KEY_RETENTION_HOURS)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn request(question: &str, ticket: Option<&str>) -> QuestionRequest {
+        QuestionRequest {
+            question: question.to_string(),
+            document_content: None,
+            document_filename: None,
+            law_name: None,
+            law_url: None,
+            chat_id: 7,
+            client_request_id: ticket.map(str::to_string),
+            document_id: None,
+            script: None,
+            jurisdiction: None,
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_polling_ticket() {
+        assert_eq!(fingerprint(&request("Otkazni rok?", Some("a"))), fingerprint(&request("Otkazni rok?", Some("b"))));
+        assert_ne!(fingerprint(&request("Otkazni rok?", None)), fingerprint(&request("Godišnji odmor?", None)));
+    }
+
+    #[test]
+    fn test_key_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert!(matches!(key_from_headers(&headers), Ok(None)));
+
+        headers.insert(IDEMPOTENCY_KEY_HEADER, HeaderValue::from_static(" 3f2a-41b7 "));
+        assert_eq!(key_from_headers(&headers).unwrap().as_deref(), Some("3f2a-41b7"));
+
+        headers.insert(IDEMPOTENCY_KEY_HEADER, HeaderValue::from_static("two words"));
+        assert!(key_from_headers(&headers).is_err());
+
+        headers.insert(IDEMPOTENCY_KEY_HEADER, HeaderValue::from_str(&"k".repeat(256)).unwrap());
+        assert!(key_from_headers(&headers).is_err());
+    }
+}
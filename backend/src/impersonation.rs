@@ -0,0 +1,228 @@
+// Support impersonation: an admin (X-Admin-Key) can get a short-lived token for a user's account
+// to see their chats and status exactly as the apps do, through the normal endpoints. The token is
+// a regular custom JWT for the user with extra claims: who is impersonating, and a banner text the
+// frontend shows for as long as it is in use. It only reads chats and account status -
+// enforce_read_only refuses anything else, including GETs with side effects such as creating a
+// referral code - and isn't tied to one of the user's sessions. Every token issued is written to
+// the user's audit log, so the user can see support looked at their account.
+use crate::audit_log::{AuditAction, AuditSource};
+use crate::models::ApiError;
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+const IMPERSONATION_TOKEN_PURPOSE: &str = "impersonation";
+const IMPERSONATION_TOKEN_TTL_MINUTES: i64 = 15;
+
+/// Claims of an impersonation token - a superset of simple_auth::Claims, so the normal
+/// authentication accepts it as a token for the user
+#[derive(Debug, Serialize, Deserialize)]
+struct ImpersonationClaims {
+    sub: String, // Impersonated user
+    email: String,
+    exp: usize,
+    iat: usize,
+    purpose: String,
+    impersonated_by: String,
+    banner: String, // Shown by the frontend while the token is in use
+}
+
+fn impersonation_claims(token: &str, jwt_secret: &str) -> Option<ImpersonationClaims> {
+    let claims = decode::<ImpersonationClaims>(token, &DecodingKey::from_secret(jwt_secret.as_ref()), &Validation::default())
+        .ok()?
+        .claims;
+    (claims.purpose == IMPERSONATION_TOKEN_PURPOSE).then_some(claims)
+}
+
+/// Whether a bearer token is a (valid) impersonation token
+pub fn is_impersonation_token(token: &str, jwt_secret: &str) -> bool {
+    impersonation_claims(token, jwt_secret).is_some()
+}
+
+/// Routes an impersonation token may read: the chats and the account's status
+fn impersonation_allows(method: &Method, path: &str) -> bool {
+    if !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return false;
+    }
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+    matches!(
+        segments.as_slice(),
+        ["", "api", "auth", "user-status"]
+            | ["", "api", "subscription", "status"]
+            | ["", "api", "chats"]
+            | ["", "api", "chats", "archived"]
+            | ["", "api", "chats", _, "messages"]
+            | ["", "api", "messages", _, "revisions"]
+            | ["", "api", "folders"]
+    )
+}
+
+/// Middleware limiting requests made with an impersonation token to reading chats and status
+pub async fn enforce_read_only(request: Request, next: Next) -> Response {
+    if !impersonation_allows(request.method(), request.uri().path()) {
+        let token = request
+            .headers()
+            .get("Authorization")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "));
+        if let Some(token) = token {
+            if is_impersonation_token(token, &crate::config::get().jwt_secret) {
+                return ApiError::new(
+                    StatusCode::FORBIDDEN,
+                    "IMPERSONATION_READ_ONLY",
+                    "Pregled naloga podrške obuhvata samo razgovore i status naloga",
+                )
+                .into_response();
+            }
+        }
+    }
+    next.run(request).await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImpersonateRequest {
+    pub user_id: Uuid,
+    pub reason: String,                // Support ticket or report being investigated
+    pub support_agent: Option<String>, // Who is looking, for the audit log
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImpersonateResponse {
+    pub token: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub user_id: Uuid,
+    pub email: String,
+    pub banner: String,
+}
+
+/// POST /api/admin/impersonate - a read-only token for a user's account
+pub async fn impersonate_handler(
+    State((pool, _, jwt_secret, _)): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<ImpersonateRequest>,
+) -> Result<Json<ImpersonateResponse>, ApiError> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(ApiError::new(StatusCode::FORBIDDEN, "FORBIDDEN", "Nemate pristup"));
+    }
+
+    let reason = request.reason.trim();
+    if reason.is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "REASON_REQUIRED", "Navedite razlog pregleda naloga"));
+    }
+
+    let email: String = sqlx::query_scalar("SELECT email FROM users WHERE id = $1")
+        .bind(request.user_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to load user to impersonate: {}", e);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Greška baze podataka")
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "USER_NOT_FOUND", "Korisnik nije pronađen"))?;
+
+    let impersonated_by = request
+        .support_agent
+        .as_deref()
+        .map(str::trim)
+        .filter(|agent| !agent.is_empty())
+        .unwrap_or("admin")
+        .to_string();
+    let now = chrono::Utc::now();
+    let expires_at = now + chrono::Duration::minutes(IMPERSONATION_TOKEN_TTL_MINUTES);
+    let banner = format!("Podrška ({}) pregleda nalog {} - samo za čitanje", impersonated_by, email);
+
+    let claims = ImpersonationClaims {
+        sub: request.user_id.to_string(),
+        email: email.clone(),
+        exp: expires_at.timestamp() as usize,
+        iat: now.timestamp() as usize,
+        purpose: IMPERSONATION_TOKEN_PURPOSE.to_string(),
+        impersonated_by: impersonated_by.clone(),
+        banner: banner.clone(),
+    };
+    let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(jwt_secret.as_ref())).map_err(|e| {
+        eprintln!("Impersonation token generation failed: {}", e);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "TOKEN_ERROR", "Greška generisanja tokena")
+    })?;
+
+    crate::audit_log::record(
+        &pool,
+        request.user_id,
+        AuditAction::ImpersonationStarted,
+        AuditSource::Request(&headers),
+        serde_json::json!({
+            "impersonated_by": impersonated_by,
+            "reason": reason,
+            "expires_at": expires_at,
+        }),
+    )
+    .await;
+    println!("🕵️ {} impersonating user {} until {} ({})", impersonated_by, request.user_id, expires_at, reason);
+
+    Ok(Json(ImpersonateResponse {
+        token,
+        expires_at,
+        user_id: request.user_id,
+        email,
+        banner,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(purpose: &str, secret: &str) -> String {
+        let now = chrono::Utc::now().timestamp() as usize;
+        let claims = ImpersonationClaims {
+            sub: Uuid::new_v4().to_string(),
+            email: "user@example.com".to_string(),
+            exp: now + 600,
+            iat: now,
+            purpose: purpose.to_string(),
+            impersonated_by: "admin".to_string(),
+            banner: "banner".to_string(),
+        };
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_ref())).unwrap()
+    }
+
+    #[test]
+    fn test_impersonation_token_is_a_user_token() {
+        let token = token(IMPERSONATION_TOKEN_PURPOSE, "secret");
+        assert!(is_impersonation_token(&token, "secret"));
+        assert!(!is_impersonation_token(&token, "other-secret"));
+        // The normal authentication accepts it for the impersonated user
+        let claims = crate::simple_auth::verify_token(&token, "secret").unwrap();
+        assert_eq!(claims.email, "user@example.com");
+    }
+
+    #[test]
+    fn test_impersonation_allows_chats_and_status() {
+        assert!(impersonation_allows(&Method::GET, "/api/chats"));
+        assert!(impersonation_allows(&Method::GET, "/api/chats/42/messages"));
+        assert!(impersonation_allows(&Method::GET, "/api/subscription/status"));
+        // Writes, and reads with side effects or outside chats and status
+        assert!(!impersonation_allows(&Method::POST, "/api/chats"));
+        assert!(!impersonation_allows(&Method::DELETE, "/api/chats/42"));
+        assert!(!impersonation_allows(&Method::GET, "/api/referrals"));
+        assert!(!impersonation_allows(&Method::GET, "/api/billing/invoices"));
+        assert!(!impersonation_allows(&Method::GET, "/api/chats/42/share"));
+    }
+
+    #[test]
+    fn test_regular_tokens_are_not_impersonation_tokens() {
+        let regular = crate::simple_auth::generate_token(Uuid::new_v4(), "user@example.com", "secret").unwrap();
+        assert!(!is_impersonation_token(&regular, "secret"));
+        assert!(!is_impersonation_token(&token("revoke_session", "secret"), "secret"));
+    }
+}
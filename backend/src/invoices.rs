@@ -0,0 +1,701 @@
+// Invoices (računi) for card-paid subscriptions. Every charge the payment provider confirms - a
+// Stripe web subscription or a RevenueCat purchase/renewal through Stripe - gets an invoice numbered without gaps per year
+// ("2026-000123"), with the buyer's billing profile (PIB and matični broj for companies) copied in
+// and the PDF rendered once and stored, so issued invoices never change. App Store and Google Play
+// are the merchant of record for in-app purchases and issue their own receipts. Invoices are only
+// issued when the seller is configured (INVOICE_ISSUER_*, see config.rs). Prices include VAT.
+use crate::config::InvoiceIssuer;
+use crate::models::{ApiError, ErrorResponse};
+use crate::simple_auth::AuthAppState;
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, Stream, StringFormat};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+/// PIB: 9 digits, the last one an ISO 7064 MOD 11,10 check digit
+pub fn is_valid_pib(pib: &str) -> bool {
+    if pib.len() != 9 || !pib.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    let digits: Vec<u32> = pib.bytes().map(|b| (b - b'0') as u32).collect();
+    let mut sum = 10;
+    for digit in &digits[..8] {
+        sum = (sum + digit) % 10;
+        if sum == 0 {
+            sum = 10;
+        }
+        sum = (sum * 2) % 11;
+    }
+    (11 - sum) % 10 == digits[8]
+}
+
+/// Matični broj of a legal entity: 8 digits
+pub fn is_valid_maticni_broj(maticni_broj: &str) -> bool {
+    maticni_broj.len() == 8 && maticni_broj.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn validate_pib(pib: &str) -> Result<(), ValidationError> {
+    if pib.trim().is_empty() || is_valid_pib(pib.trim()) {
+        Ok(())
+    } else {
+        Err(crate::i18n::field_error("invalid_pib", "Neispravan PIB"))
+    }
+}
+
+fn validate_maticni_broj(maticni_broj: &str) -> Result<(), ValidationError> {
+    if maticni_broj.trim().is_empty() || is_valid_maticni_broj(maticni_broj.trim()) {
+        Ok(())
+    } else {
+        Err(crate::i18n::field_error("invalid_maticni_broj", "Matični broj mora imati 8 cifara"))
+    }
+}
+
+/// "2026-000123"
+pub fn invoice_number(year: i32, sequence: i32) -> String {
+    format!("{}-{:06}", year, sequence)
+}
+
+/// Invoice line for a subscription: "Norma AI Professional pretplata (mesečno)"
+pub fn subscription_description(plan: &str, billing_period: &str) -> String {
+    let plan = match plan {
+        "individual" => "Individual",
+        "team" => "Team",
+        _ => "Professional",
+    };
+    let period = if billing_period == "yearly" { "godišnje" } else { "mesečno" };
+    format!("Norma AI {} pretplata ({})", plan, period)
+}
+
+/// (base, VAT) of a VAT-inclusive amount, in minor units
+pub fn vat_split(total_minor: i64, vat_rate: u8) -> (i64, i64) {
+    let divisor = 100 + vat_rate as i64;
+    let base = (total_minor * 200 + divisor) / (2 * divisor); // Rounded half up
+    (base, total_minor - base)
+}
+
+/// "3.400,00 RSD"
+pub fn format_amount(minor: i64, currency: &str) -> String {
+    let sign = if minor < 0 { "-" } else { "" };
+    let whole = (minor.abs() / 100).to_string();
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i).is_multiple_of(3) {
+            grouped.push('.');
+        }
+        grouped.push(digit);
+    }
+    format!("{}{},{:02} {}", sign, grouped, minor.abs() % 100, currency)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CustomerType {
+    #[default]
+    Individual,
+    Company,
+}
+
+impl CustomerType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CustomerType::Individual => "individual",
+            CustomerType::Company => "company",
+        }
+    }
+}
+
+/// Buyer details as printed on an invoice; also the billing-profile request and response body
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct BillingProfile {
+    #[serde(default)]
+    pub customer_type: CustomerType,
+    #[validate(length(max = 200, code = "billing_name_too_long", message = "Naziv može imati najviše 200 karaktera"))]
+    pub name: Option<String>,
+    #[validate(length(max = 300, code = "address_too_long", message = "Adresa može imati najviše 300 karaktera"))]
+    pub address: Option<String>,
+    #[validate(length(max = 100, code = "city_too_long", message = "Mesto može imati najviše 100 karaktera"))]
+    pub city: Option<String>,
+    #[validate(length(max = 10, code = "postal_code_too_long", message = "Poštanski broj je predugačak"))]
+    pub postal_code: Option<String>,
+    #[validate(custom = "validate_pib")]
+    pub pib: Option<String>,
+    #[validate(custom = "validate_maticni_broj")]
+    pub maticni_broj: Option<String>,
+    #[validate(email(code = "invalid_email", message = "Neispravna e-mail adresa"))]
+    pub email: Option<String>, // Where invoices are addressed, if not the account e-mail
+}
+
+impl BillingProfile {
+    /// Trimmed, with empty fields unset
+    fn normalized(mut self) -> Self {
+        for field in [
+            &mut self.name,
+            &mut self.address,
+            &mut self.city,
+            &mut self.postal_code,
+            &mut self.pib,
+            &mut self.maticni_broj,
+            &mut self.email,
+        ] {
+            *field = field.take().map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+        }
+        self
+    }
+
+    /// A company invoice is only valid with the company's name, address, PIB and matični broj
+    fn missing_company_fields(&self) -> Vec<&'static str> {
+        if self.customer_type != CustomerType::Company {
+            return Vec::new();
+        }
+        [("name", &self.name), ("address", &self.address), ("pib", &self.pib), ("maticni_broj", &self.maticni_broj)]
+            .into_iter()
+            .filter(|(_, value)| value.is_none())
+            .map(|(field, _)| field)
+            .collect()
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct BillingProfileRow {
+    customer_type: String,
+    name: Option<String>,
+    address: Option<String>,
+    city: Option<String>,
+    postal_code: Option<String>,
+    pib: Option<String>,
+    maticni_broj: Option<String>,
+    email: Option<String>,
+}
+
+impl From<BillingProfileRow> for BillingProfile {
+    fn from(row: BillingProfileRow) -> Self {
+        BillingProfile {
+            customer_type: if row.customer_type == "company" { CustomerType::Company } else { CustomerType::Individual },
+            name: row.name,
+            address: row.address,
+            city: row.city,
+            postal_code: row.postal_code,
+            pib: row.pib,
+            maticni_broj: row.maticni_broj,
+            email: row.email,
+        }
+    }
+}
+
+async fn load_billing_profile<'e>(
+    executor: impl sqlx::PgExecutor<'e>,
+    user_id: Uuid,
+) -> Result<Option<BillingProfile>, sqlx::Error> {
+    let row = sqlx::query_as::<_, BillingProfileRow>(
+        "SELECT customer_type, name, address, city, postal_code, pib, maticni_broj, email
+         FROM billing_profiles WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await?;
+    Ok(row.map(BillingProfile::from))
+}
+
+/// A successful subscription charge to invoice
+#[derive(Debug, Clone)]
+pub struct Charge<'a> {
+    pub description: String,
+    pub plan: Option<&'a str>,
+    pub billing_period: Option<&'a str>,
+    pub total_minor: i64, // VAT included
+    pub currency: &'a str,
+    pub paid_at: DateTime<Utc>,
+    pub source: &'a str, // 'revenuecat' or 'stripe'
+    /// The payment provider's charge - only confirmed payments are invoiced
+    pub external_id: &'a str,
+}
+
+/// Everything printed on an invoice
+struct InvoiceDocument<'a> {
+    number: &'a str,
+    issued_on: NaiveDate,
+    paid_on: NaiveDate,
+    buyer: &'a BillingProfile,
+    description: &'a str,
+    total_minor: i64,
+    currency: &'a str,
+}
+
+/// Issue the invoice for a charge: the next number, the buyer's billing profile (or the account's
+/// name and e-mail without one) and the rendered PDF, in one transaction. Returns the invoice ID,
+/// or None when invoicing isn't configured or the charge was already invoiced.
+pub async fn record_charge(pool: &PgPool, user_id: Uuid, charge: Charge<'_>) -> Result<Option<Uuid>, String> {
+    let Some(issuer) = crate::config::get().invoice_issuer.as_ref() else {
+        return Ok(None);
+    };
+
+    let mut tx = pool.begin().await.map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let buyer = match load_billing_profile(&mut *tx, user_id).await.map_err(|e| e.to_string())? {
+        Some(profile) => profile,
+        None => {
+            let (name, email): (Option<String>, String) = sqlx::query_as("SELECT name, email FROM users WHERE id = $1")
+                .bind(user_id)
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(|e| format!("Failed to load user: {}", e))?;
+            BillingProfile { name, email: Some(email), ..Default::default() }
+        }
+    };
+
+    let issued_on = crate::calendar::local_today(Utc::now());
+    let year = chrono::Datelike::year(&issued_on);
+    // The counter row stays locked until commit, so concurrent invoices queue up and a rolled back
+    // one gives its number back
+    let sequence: i32 = sqlx::query_scalar(
+        "INSERT INTO invoice_sequences (year, last_number) VALUES ($1, 1)
+         ON CONFLICT (year) DO UPDATE SET last_number = invoice_sequences.last_number + 1
+         RETURNING last_number",
+    )
+    .bind(year)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to number invoice: {}", e))?;
+    let number = invoice_number(year, sequence);
+
+    let pdf = render_pdf(
+        issuer,
+        &InvoiceDocument {
+            number: &number,
+            issued_on,
+            paid_on: crate::calendar::local_today(charge.paid_at),
+            buyer: &buyer,
+            description: &charge.description,
+            total_minor: charge.total_minor,
+            currency: charge.currency,
+        },
+    )?;
+
+    let invoice_id: Option<Uuid> = sqlx::query_scalar(
+        "INSERT INTO invoices (user_id, number, year, sequence, description, plan, billing_period,
+                               total_minor, currency, vat_rate, buyer, source, external_id, pdf)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+         ON CONFLICT (external_id) WHERE external_id IS NOT NULL DO NOTHING
+         RETURNING id",
+    )
+    .bind(user_id)
+    .bind(&number)
+    .bind(year)
+    .bind(sequence)
+    .bind(&charge.description)
+    .bind(charge.plan)
+    .bind(charge.billing_period)
+    .bind(charge.total_minor)
+    .bind(charge.currency)
+    .bind(issuer.vat_rate as i16)
+    .bind(sqlx::types::Json(&buyer))
+    .bind(charge.source)
+    .bind(charge.external_id)
+    .bind(&pdf)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to store invoice: {}", e))?;
+
+    // Already invoiced - dropping the transaction releases the number
+    let Some(invoice_id) = invoice_id else {
+        return Ok(None);
+    };
+    tx.commit().await.map_err(|e| format!("Failed to commit invoice: {}", e))?;
+
+    println!("🧾 Issued invoice {} for user {} ({})", number, user_id, format_amount(charge.total_minor, charge.currency));
+    Ok(Some(invoice_id))
+}
+
+// Helvetica with WinAnsiEncoding covers Latin-1 and š/ž; the other Serbian Latin letters take
+// over control codes that never occur in text
+const SERBIAN_GLYPHS: [(char, u8, &str); 6] = [
+    ('č', 1, "ccaron"),
+    ('Č', 2, "Ccaron"),
+    ('ć', 3, "cacute"),
+    ('Ć', 4, "Cacute"),
+    ('đ', 5, "dcroat"),
+    ('Đ', 6, "Dcroat"),
+];
+
+/// Text as bytes in the invoice fonts' encoding; characters it lacks become '?'
+fn encode_text(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            'š' => 0x9A,
+            'Š' => 0x8A,
+            'ž' => 0x9E,
+            'Ž' => 0x8E,
+            '–' => 0x96,
+            '€' => 0x80,
+            _ => match SERBIAN_GLYPHS.iter().find(|(glyph, _, _)| *glyph == c) {
+                Some((_, code, _)) => *code,
+                None if (' '..='~').contains(&c) || ('\u{A0}'..='\u{FF}').contains(&c) => c as u8,
+                None => b'?',
+            },
+        })
+        .collect()
+}
+
+/// Content stream operations for one A4 page
+#[derive(Default)]
+struct PageContent {
+    operations: Vec<Operation>,
+}
+
+impl PageContent {
+    fn text(&mut self, x: f32, y: f32, size: f32, bold: bool, text: &str) {
+        self.operations.extend([
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec![if bold { "F2" } else { "F1" }.into(), size.into()]),
+            Operation::new("Td", vec![x.into(), y.into()]),
+            Operation::new("Tj", vec![Object::String(encode_text(text), StringFormat::Literal)]),
+            Operation::new("ET", vec![]),
+        ]);
+    }
+
+    fn rule(&mut self, y: f32) {
+        self.operations.extend([
+            Operation::new("w", vec![0.5.into()]),
+            Operation::new("m", vec![50.into(), y.into()]),
+            Operation::new("l", vec![545.into(), y.into()]),
+            Operation::new("S", vec![]),
+        ]);
+    }
+}
+
+fn render_pdf(issuer: &InvoiceIssuer, invoice: &InvoiceDocument) -> Result<Vec<u8>, String> {
+    let mut page = PageContent::default();
+    let date = |day: NaiveDate| day.format("%d.%m.%Y.").to_string();
+
+    page.text(50.0, 780.0, 18.0, true, &format!("RAČUN br. {}", invoice.number));
+    page.text(50.0, 760.0, 10.0, false, &format!("Datum izdavanja: {}", date(invoice.issued_on)));
+    page.text(50.0, 746.0, 10.0, false, &format!("Datum prometa: {}", date(invoice.paid_on)));
+
+    let mut seller = vec![issuer.name.clone(), issuer.address.clone(), format!("PIB: {}", issuer.pib)];
+    seller.push(format!("Matični broj: {}", issuer.maticni_broj));
+    if let Some(account) = &issuer.bank_account {
+        seller.push(format!("Tekući račun: {}", account));
+    }
+
+    let buyer = invoice.buyer;
+    let mut customer: Vec<String> = [buyer.name.clone(), buyer.address.clone()].into_iter().flatten().collect();
+    match (&buyer.postal_code, &buyer.city) {
+        (Some(postal_code), Some(city)) => customer.push(format!("{} {}", postal_code, city)),
+        (None, Some(city)) => customer.push(city.clone()),
+        _ => {}
+    }
+    if let Some(pib) = &buyer.pib {
+        customer.push(format!("PIB: {}", pib));
+    }
+    if let Some(maticni_broj) = &buyer.maticni_broj {
+        customer.push(format!("Matični broj: {}", maticni_broj));
+    }
+    if let Some(email) = &buyer.email {
+        customer.push(email.clone());
+    }
+
+    page.text(50.0, 710.0, 10.0, true, "Izdavalac");
+    page.text(310.0, 710.0, 10.0, true, "Kupac");
+    for (i, line) in seller.iter().enumerate() {
+        page.text(50.0, 694.0 - 14.0 * i as f32, 10.0, false, line);
+    }
+    for (i, line) in customer.iter().enumerate() {
+        page.text(310.0, 694.0 - 14.0 * i as f32, 10.0, false, line);
+    }
+
+    let top = 694.0 - 14.0 * seller.len().max(customer.len()) as f32 - 20.0;
+    page.text(50.0, top, 10.0, true, "Opis");
+    page.text(440.0, top, 10.0, true, "Iznos");
+    page.rule(top - 6.0);
+    page.text(50.0, top - 22.0, 10.0, false, invoice.description);
+    page.text(440.0, top - 22.0, 10.0, false, &format_amount(invoice.total_minor, invoice.currency));
+    page.rule(top - 32.0);
+
+    let (base, vat) = vat_split(invoice.total_minor, issuer.vat_rate);
+    let totals = [
+        ("Osnovica".to_string(), format_amount(base, invoice.currency), false),
+        (format!("PDV {}%", issuer.vat_rate), format_amount(vat, invoice.currency), false),
+        ("Ukupno".to_string(), format_amount(invoice.total_minor, invoice.currency), true),
+    ];
+    for (i, (label, amount, bold)) in totals.iter().enumerate() {
+        let y = top - 52.0 - 16.0 * i as f32;
+        page.text(310.0, y, 10.0, *bold, label);
+        page.text(440.0, y, 10.0, *bold, amount);
+    }
+
+    let mut notes = vec!["Plaćeno platnom karticom.".to_string()];
+    if issuer.vat_rate == 0 {
+        notes.push("Izdavalac nije u sistemu PDV-a - PDV nije obračunat (član 33. Zakona o PDV-u).".to_string());
+    }
+    notes.push("Račun je izdat elektronski i punovažan je bez pečata i potpisa.".to_string());
+    for (i, note) in notes.iter().enumerate() {
+        page.text(50.0, top - 120.0 - 14.0 * i as f32, 9.0, false, note);
+    }
+
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+
+    let differences: Vec<Object> = SERBIAN_GLYPHS
+        .iter()
+        .flat_map(|(_, code, name)| [Object::Integer(*code as i64), Object::Name(name.as_bytes().to_vec())])
+        .collect();
+    let encoding_id = doc.add_object(dictionary! {
+        "Type" => "Encoding",
+        "BaseEncoding" => "WinAnsiEncoding",
+        "Differences" => differences,
+    });
+    let font = |base_font: &str| {
+        dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => Object::Name(base_font.as_bytes().to_vec()),
+            "Encoding" => encoding_id,
+        }
+    };
+    let regular_id = doc.add_object(font("Helvetica"));
+    let bold_id = doc.add_object(font("Helvetica-Bold"));
+    let resources_id = doc.add_object(dictionary! {
+        "Font" => dictionary! { "F1" => regular_id, "F2" => bold_id },
+    });
+
+    let content = Content { operations: page.operations }.encode().map_err(|e| format!("Failed to render invoice: {}", e))?;
+    let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "Contents" => content_id,
+    });
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![page_id.into()],
+            "Count" => 1,
+            "Resources" => resources_id,
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.compress();
+
+    let mut pdf = Vec::new();
+    doc.save_to(&mut pdf).map_err(|e| format!("Failed to render invoice: {}", e))?;
+    Ok(pdf)
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct InvoiceSummary {
+    pub id: Uuid,
+    pub number: String,
+    pub issued_at: DateTime<Utc>,
+    pub description: String,
+    pub total_minor: i64,
+    pub currency: String,
+    pub vat_rate: i16,
+    #[sqlx(skip)]
+    pub pdf_url: String,
+}
+
+fn database_error(e: sqlx::Error) -> ApiError {
+    eprintln!("Billing database error: {}", e);
+    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Greška pri učitavanju podataka za naplatu")
+}
+
+/// The authenticated user's billing profile (an empty individual profile if none was saved)
+pub async fn get_billing_profile_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
+    headers: HeaderMap,
+) -> Result<Json<BillingProfile>, ApiError> {
+    let user_id = crate::database::authenticate(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let profile = load_billing_profile(&pool, user_id).await.map_err(database_error)?;
+    Ok(Json(profile.unwrap_or_default()))
+}
+
+/// Replace the billing profile used for future invoices. Issued invoices keep the details they
+/// were issued with.
+pub async fn update_billing_profile_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
+    headers: HeaderMap,
+    Json(payload): Json<BillingProfile>,
+) -> Result<Json<BillingProfile>, ApiError> {
+    let profile = payload.normalized();
+    profile.validate().map_err(|e| crate::i18n::validation_error(&e))?;
+    let missing = profile.missing_company_fields();
+    if !missing.is_empty() {
+        return Err(ApiError {
+            status: StatusCode::BAD_REQUEST,
+            body: Some(ErrorResponse {
+                error: "COMPANY_DETAILS_REQUIRED".to_string(),
+                message: "Za račun na firmu potrebni su naziv, adresa, PIB i matični broj".to_string(),
+                details: Some(serde_json::json!({ "missing": missing })),
+            }),
+        });
+    }
+
+    let user_id = crate::database::authenticate(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    sqlx::query(
+        "INSERT INTO billing_profiles (user_id, customer_type, name, address, city, postal_code, pib, maticni_broj, email)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+         ON CONFLICT (user_id) DO UPDATE SET
+             customer_type = EXCLUDED.customer_type, name = EXCLUDED.name, address = EXCLUDED.address,
+             city = EXCLUDED.city, postal_code = EXCLUDED.postal_code, pib = EXCLUDED.pib,
+             maticni_broj = EXCLUDED.maticni_broj, email = EXCLUDED.email, updated_at = NOW()",
+    )
+    .bind(user_id)
+    .bind(profile.customer_type.as_str())
+    .bind(&profile.name)
+    .bind(&profile.address)
+    .bind(&profile.city)
+    .bind(&profile.postal_code)
+    .bind(&profile.pib)
+    .bind(&profile.maticni_broj)
+    .bind(&profile.email)
+    .execute(&pool)
+    .await
+    .map_err(database_error)?;
+
+    println!("✅ Billing profile updated for user {}", user_id);
+
+    Ok(Json(profile))
+}
+
+/// The authenticated user's invoices, newest first
+pub async fn list_invoices_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<InvoiceSummary>>, ApiError> {
+    let user_id = crate::database::authenticate(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let mut invoices = sqlx::query_as::<_, InvoiceSummary>(
+        "SELECT id, number, issued_at, description, total_minor, currency, vat_rate
+         FROM invoices WHERE user_id = $1
+         ORDER BY issued_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(database_error)?;
+
+    let api_base_url = &crate::config::get().api_base_url;
+    for invoice in &mut invoices {
+        invoice.pdf_url = format!("{}/api/billing/invoices/{}/pdf", api_base_url, invoice.id);
+    }
+
+    Ok(Json(invoices))
+}
+
+/// Download an invoice PDF
+pub async fn download_invoice_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
+    headers: HeaderMap,
+    Path(invoice_id): Path<Uuid>,
+) -> Result<Response, ApiError> {
+    let user_id = crate::database::authenticate(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let invoice: Option<(String, Vec<u8>)> =
+        sqlx::query_as("SELECT number, pdf FROM invoices WHERE id = $1 AND user_id = $2")
+            .bind(invoice_id)
+            .bind(user_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(database_error)?;
+    let (number, pdf) =
+        invoice.ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "INVOICE_NOT_FOUND", "Račun nije pronađen"))?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"NormaAI_racun_{}.pdf\"", number)),
+        ],
+        pdf,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pib_check_digit() {
+        assert!(is_valid_pib("104052135"));
+        assert!(is_valid_pib("100002887"));
+        assert!(!is_valid_pib("104052136"));
+        assert!(!is_valid_pib("10405213"));
+        assert!(!is_valid_pib("10405213a"));
+        assert!(is_valid_maticni_broj("20084693"));
+        assert!(!is_valid_maticni_broj("2008469"));
+    }
+
+    #[test]
+    fn test_amounts() {
+        assert_eq!(invoice_number(2026, 123), "2026-000123");
+        assert_eq!(subscription_description("team", "yearly"), "Norma AI Team pretplata (godišnje)");
+        assert_eq!(format_amount(340000, "RSD"), "3.400,00 RSD");
+        assert_eq!(format_amount(24900000, "RSD"), "249.000,00 RSD");
+        assert_eq!(format_amount(999, "EUR"), "9,99 EUR");
+
+        // 3.400,00 with 20% VAT included = 2.833,33 + 566,67
+        assert_eq!(vat_split(340000, 20), (283333, 56667));
+        assert_eq!(vat_split(340000, 0), (340000, 0));
+    }
+
+    #[test]
+    fn test_company_profile_requires_tax_ids() {
+        let profile = BillingProfile {
+            customer_type: CustomerType::Company,
+            name: Some("Advokatska kancelarija Petrović".to_string()),
+            pib: Some(" ".to_string()),
+            ..Default::default()
+        }
+        .normalized();
+        assert_eq!(profile.missing_company_fields(), vec!["address", "pib", "maticni_broj"]);
+        assert!(BillingProfile::default().missing_company_fields().is_empty());
+    }
+
+    #[test]
+    fn test_render_pdf() {
+        let issuer = InvoiceIssuer {
+            name: "Norma AI DOO".to_string(),
+            address: "Bulevar oslobođenja 1, Novi Sad".to_string(),
+            pib: "104052135".to_string(),
+            maticni_broj: "20084693".to_string(),
+            bank_account: None,
+            vat_rate: 20,
+        };
+        let buyer = BillingProfile { name: Some("Đorđe Čolić".to_string()), ..Default::default() };
+        let day = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+        let pdf = render_pdf(
+            &issuer,
+            &InvoiceDocument {
+                number: "2026-000001",
+                issued_on: day,
+                paid_on: day,
+                buyer: &buyer,
+                description: "Norma AI Professional pretplata (mesečno)",
+                total_minor: 640000,
+                currency: "RSD",
+            },
+        )
+        .unwrap();
+        assert!(pdf.starts_with(b"%PDF-1.5"));
+
+        assert_eq!(encode_text("Đorđe Čolić š ж"), b"\x06or\x05e \x02oli\x03 \x9a ?");
+    }
+}
@@ -0,0 +1,105 @@
+// Legal systems the assistant can answer for. Serbia is the default and the only one with the full
+// law registry; the neighbouring systems reuse the same pipeline with their own law registry
+// (laws::get_laws), answering prompt and law sources (law_sources::sources_for).
+use axum::response::Json as ResponseJson;
+use serde::Serialize;
+use sqlx::PgPool;
+
+pub use norma_api_types::Jurisdiction;
+
+/// Adjective used in the law detection prompt ("najrelevantniji ___ zakon")
+pub fn law_adjective(jurisdiction: Jurisdiction) -> &'static str {
+    match jurisdiction {
+        Jurisdiction::Rs => "srpski",
+        Jurisdiction::Hr => "hrvatski",
+        Jurisdiction::Ba => "bosanskohercegovački",
+        Jurisdiction::Bg => "bugarski",
+    }
+}
+
+/// Built-in answering instructions. Serbia uses prompt_profiles (admin-editable); the others
+/// use these. The "Reference:" / "Član X" format is kept in every language because the
+/// answer parser and article lookup rely on it.
+pub fn system_prompt(jurisdiction: Jurisdiction) -> Option<&'static str> {
+    match jurisdiction {
+        Jurisdiction::Rs => None,
+        Jurisdiction::Hr => Some(HR_SYSTEM_PROMPT),
+        Jurisdiction::Ba => Some(BA_SYSTEM_PROMPT),
+        Jurisdiction::Bg => Some(BG_SYSTEM_PROMPT),
+    }
+}
+
+const HR_SYSTEM_PROMPT: &str = r#"Ti si pravni asistent za hrvatsko zakonodavstvo sa mogućnošću generiranja ugovora.
+
+PRAVNA PITANJA - Odgovori KRATKO i IZRAVNO:
+1. Koristi isključivo propise Republike Hrvatske (Narodne novine), ne srpske propise
+2. Navedi konkretne kazne, iznose i rokove
+3. Odgovaraj na hrvatskom jeziku
+
+FORMAT:
+1. KRATAK odgovor
+2. Nova linija: "Reference:"
+3. U "Reference:" citiraj članke u obliku: Član X, Član Y, Član Z..."#;
+
+const BA_SYSTEM_PROMPT: &str = r#"Ti si pravni asistent za zakonodavstvo Bosne i Hercegovine sa mogućnošću generisanja ugovora.
+
+PRAVNA PITANJA - Odgovori KRATKO i DIREKTNO:
+1. Koristi propise Bosne i Hercegovine; kada se propisi entiteta razlikuju (Federacija BiH, Republika Srpska, Brčko distrikt), navedi na koji se entitet odgovor odnosi
+2. Navedi konkretne kazne, iznose i rokove
+3. Odgovaraj na bosanskom jeziku
+
+FORMAT:
+1. KRATAK odgovor
+2. Nova linija: "Reference:"
+3. U "Reference:" citiraj: Član X, Član Y, Član Z..."#;
+
+const BG_SYSTEM_PROMPT: &str = r#"Ти си правен асистент за българското законодателство с възможност за изготвяне на договори.
+
+ПРАВНИ ВЪПРОСИ - Отговаряй КРАТКО и ДИРЕКТНО:
+1. Използвай само действащото българско законодателство
+2. Посочвай конкретни наказания, суми и срокове
+3. Отговаряй на български език
+
+ФОРМАТ:
+1. КРАТЪК отговор
+2. Нов ред: "Reference:"
+3. В "Reference:" цитирай членовете във вида: Član X, Član Y, Član Z..."#;
+
+#[derive(Debug, Serialize)]
+pub struct JurisdictionInfo {
+    pub code: Jurisdiction,
+    pub name: &'static str,
+    pub law_count: usize,           // Laws in the registry
+    pub sources: Vec<&'static str>, // Sites its laws are scraped from
+}
+
+/// GET /api/jurisdictions - legal systems a chat can be created for
+pub async fn list_jurisdictions_handler() -> ResponseJson<Vec<JurisdictionInfo>> {
+    let jurisdictions = Jurisdiction::ALL
+        .into_iter()
+        .map(|jurisdiction| JurisdictionInfo {
+            code: jurisdiction,
+            name: jurisdiction.country_name(),
+            law_count: crate::laws::get_laws(jurisdiction).len(),
+            sources: crate::law_sources::sources_for(jurisdiction).iter().map(|source| source.name()).collect(),
+        })
+        .collect();
+
+    ResponseJson(jurisdictions)
+}
+
+/// Jurisdiction a chat was created for
+pub async fn chat_jurisdiction(chat_id: i64, pool: &PgPool) -> Jurisdiction {
+    match sqlx::query_scalar::<_, String>("SELECT jurisdiction FROM chats WHERE id = $1")
+        .bind(chat_id)
+        .fetch_optional(pool)
+        .await
+    {
+        Ok(value) => value.as_deref().and_then(Jurisdiction::parse).unwrap_or_default(),
+        Err(e) => {
+            eprintln!("⚠️  Failed to load jurisdiction of chat {}: {}", chat_id, e);
+            Jurisdiction::default()
+        }
+    }
+}
+
@@ -0,0 +1,307 @@
+// Law browsing: lets users read laws directly instead of only through chat answers.
+// The law list comes from the jurisdiction's registry (laws::get_laws); table of contents and articles are served from
+// law_cache / law_articles, scraping the law on a cache miss just like citation lookups do.
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::database::verify_user_from_headers_async;
+use crate::jurisdictions::Jurisdiction;
+use crate::models::SerbianLaw;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+/// Longest line treated as an article title (the short heading above "Član N")
+const MAX_ARTICLE_TITLE_CHARS: usize = 120;
+
+#[derive(Debug, Deserialize)]
+pub struct LawListQuery {
+    pub q: Option<String>, // Case-insensitive filter on the law name
+    pub jurisdiction: Option<Jurisdiction>, // Defaults to Serbia
+}
+
+#[derive(Debug, Serialize)]
+pub struct LawListItem {
+    pub id: i32,
+    pub name: String,
+    pub url: String,
+    pub cache_status: &'static str, // "cached" | "expired" | "not_cached"
+    pub cached_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub article_count: Option<i64>, // Articles parsed from the cached text
+}
+
+#[derive(Debug, Serialize)]
+pub struct LawToc {
+    pub id: i32,
+    pub name: String,
+    pub article_count: usize,
+    pub entries: Vec<TocEntry>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct TocEntry {
+    pub kind: &'static str, // "part" | "chapter" | "section" | "article"
+    pub title: Option<String>,
+    pub article_number: Option<String>, // Set for articles
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TocEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LawArticle {
+    pub law_id: i32,
+    pub law_name: String,
+    pub article_number: String,
+    pub content: String, // "**Član N**\n..." - same formatting as quotes in answers
+}
+
+fn find_law(law_id: i32) -> Option<SerbianLaw> {
+    crate::laws::find_law_by_id(law_id)
+}
+
+/// List browsable laws with their cache status
+pub async fn list_laws_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<LawListQuery>,
+) -> Result<ResponseJson<Vec<LawListItem>>, StatusCode> {
+    verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let filter = query.q.as_deref().map(|q| q.trim().to_lowercase()).filter(|q| !q.is_empty());
+    let laws: Vec<SerbianLaw> = crate::laws::get_laws(query.jurisdiction.unwrap_or_default())
+        .into_iter()
+        .filter(|law| filter.as_ref().is_none_or(|q| law.name.to_lowercase().contains(q)))
+        .collect();
+
+    let names: Vec<String> = laws.iter().map(|law| law.name.clone()).collect();
+    // (law_name, cached_at, expired, article_count)
+    let cached = sqlx::query_as::<_, (String, Option<chrono::DateTime<chrono::Utc>>, bool, i64)>(
+        "SELECT c.law_name, c.cached_at, c.expires_at <= NOW(),
+                (SELECT COUNT(*) FROM law_articles a WHERE a.law_id = c.id AND a.position >= 0)
+         FROM law_cache c
+         WHERE c.law_name = ANY($1)"
+    )
+    .bind(&names)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to fetch law cache status: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let items = laws
+        .into_iter()
+        .map(|law| {
+            let cache = cached.iter().find(|(name, _, _, _)| *name == law.name);
+            LawListItem {
+                cache_status: match cache {
+                    Some((_, _, false, _)) => "cached",
+                    Some((_, _, true, _)) => "expired",
+                    None => "not_cached",
+                },
+                cached_at: cache.and_then(|(_, cached_at, _, _)| *cached_at),
+                article_count: cache.map(|(_, _, _, count)| *count),
+                id: law.id,
+                name: law.name,
+                url: law.url,
+            }
+        })
+        .collect();
+
+    Ok(ResponseJson(items))
+}
+
+/// Table of contents of a law (parts, chapters, sections and articles), scraping it if not cached
+pub async fn law_toc_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(law_id): Path<i32>,
+) -> Result<ResponseJson<LawToc>, StatusCode> {
+    verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let law = find_law(law_id).ok_or(StatusCode::NOT_FOUND)?;
+    let content = crate::api::get_law_content(&law.name, &law.url, &pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to load law '{}' for table of contents: {}", law.name, e);
+            StatusCode::BAD_GATEWAY
+        })?;
+
+    let entries = parse_toc(&content.content);
+    Ok(ResponseJson(LawToc {
+        id: law.id,
+        name: law.name,
+        article_count: count_articles(&entries),
+        entries,
+    }))
+}
+
+/// Formatted text of a single article, scraping the law (or the article page) on a cache miss
+pub async fn law_article_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path((law_id, article_number)): Path<(i32, String)>,
+) -> Result<ResponseJson<LawArticle>, StatusCode> {
+    verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let article_number = article_number.trim().to_lowercase();
+    let valid_number = Regex::new(r"^\d{1,4}[a-z]?$").unwrap();
+    if !valid_number.is_match(&article_number) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let law = find_law(law_id).ok_or(StatusCode::NOT_FOUND)?;
+    let (content, _) = crate::api::get_cached_article(&law.name, &article_number, &pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to load Član {} of '{}': {}", article_number, law.name, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(ResponseJson(LawArticle {
+        law_id: law.id,
+        law_name: law.name,
+        article_number,
+        content,
+    }))
+}
+
+fn count_articles(entries: &[TocEntry]) -> usize {
+    entries
+        .iter()
+        .map(|entry| usize::from(entry.kind == "article") + count_articles(&entry.children))
+        .sum()
+}
+
+/// Structural heading level: 1 = part (DEO), 2 = chapter (GLAVA / "II NAZIV" / all caps), 3 = section (ODELJAK)
+fn heading_level(line: &str) -> Option<(u8, &'static str)> {
+    let upper = line.to_uppercase();
+    let first_word = upper.split_whitespace().next().unwrap_or("");
+
+    if first_word == "ODELJAK" {
+        return Some((3, "section"));
+    }
+    if first_word == "GLAVA" {
+        return Some((2, "chapter"));
+    }
+
+    let letters: Vec<char> = line.chars().filter(|c| c.is_alphabetic()).collect();
+    let all_caps = letters.len() >= 3 && letters.iter().all(|c| c.is_uppercase());
+    // The law's own title ("ZAKON O RADU") is all caps too but isn't part of the structure
+    if !all_caps || first_word.starts_with("ZAKON") {
+        return None;
+    }
+
+    if upper.split_whitespace().any(|word| word == "DEO") {
+        Some((1, "part"))
+    } else {
+        Some((2, "chapter"))
+    }
+}
+
+/// Short line directly above "Član N" that names the article (e.g. "Predmet zakona")
+fn is_article_title(line: &str) -> bool {
+    line.chars().count() <= MAX_ARTICLE_TITLE_CHARS
+        && !line.ends_with(['.', ',', ';', ':'])
+        && !line.starts_with('(')
+        && line.chars().next().is_some_and(|c| c.is_alphabetic())
+}
+
+/// Parse cleaned law text into a tree of parts, chapters, sections and articles
+pub fn parse_toc(law_content: &str) -> Vec<TocEntry> {
+    let article_header = Regex::new(r"^Član\s+(\d+[a-z]?)\b").unwrap();
+
+    let mut root: Vec<TocEntry> = Vec::new();
+    // Open headings, outermost first, with their level
+    let mut stack: Vec<(u8, TocEntry)> = Vec::new();
+    let mut pending_title: Option<String> = None;
+
+    // Attach a finished entry to the innermost open heading (or the root)
+    fn attach(stack: &mut [(u8, TocEntry)], root: &mut Vec<TocEntry>, entry: TocEntry) {
+        match stack.last_mut() {
+            Some((_, parent)) => parent.children.push(entry),
+            None => root.push(entry),
+        }
+    }
+
+    for line in law_content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(cap) = article_header.captures(line) {
+            let entry = TocEntry {
+                kind: "article",
+                title: pending_title.take(),
+                article_number: Some(cap[1].to_string()),
+                children: Vec::new(),
+            };
+            attach(&mut stack, &mut root, entry);
+        } else if let Some((level, kind)) = heading_level(line) {
+            pending_title = None;
+            while stack.last().is_some_and(|(open_level, _)| *open_level >= level) {
+                let (_, closed) = stack.pop().unwrap();
+                attach(&mut stack, &mut root, closed);
+            }
+            stack.push((level, TocEntry { kind, title: Some(line.to_string()), article_number: None, children: Vec::new() }));
+        } else if is_article_title(line) {
+            pending_title = Some(line.to_string());
+        } else {
+            pending_title = None;
+        }
+    }
+
+    while let Some((_, closed)) = stack.pop() {
+        attach(&mut stack, &mut root, closed);
+    }
+
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_toc_builds_tree() {
+        let law = "ZAKON O RADU\n\
+            DEO PRVI\n\
+            I OSNOVNE ODREDBE\n\
+            Predmet zakona\n\
+            Član 1\n\
+            Prava, obaveze i odgovornosti iz radnog odnosa uređuju se ovim zakonom.\n\
+            Član 2\n\
+            Odredbe ovog zakona primenjuju se na zaposlene.\n\
+            II ZAPOŠLJAVANJE\n\
+            Član 3\n\
+            Zaposleni ima pravo na zaradu.\n";
+
+        let toc = parse_toc(law);
+        assert_eq!(toc.len(), 1);
+        assert_eq!(toc[0].kind, "part");
+
+        let chapters = &toc[0].children;
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].title.as_deref(), Some("I OSNOVNE ODREDBE"));
+        assert_eq!(chapters[0].children[0].article_number.as_deref(), Some("1"));
+        assert_eq!(chapters[0].children[0].title.as_deref(), Some("Predmet zakona"));
+        // Body sentences ending in a period aren't mistaken for titles
+        assert_eq!(chapters[0].children[1].title, None);
+        assert_eq!(chapters[1].children[0].article_number.as_deref(), Some("3"));
+        assert_eq!(count_articles(&toc), 3);
+    }
+
+    #[test]
+    fn test_parse_toc_without_headings() {
+        let toc = parse_toc("Član 1\nTekst.\nČlan 1a\nTekst.");
+        assert_eq!(toc.iter().map(|e| e.article_number.clone().unwrap()).collect::<Vec<_>>(), vec!["1", "1a"]);
+    }
+}
@@ -0,0 +1,305 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::laws;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+/// Upper bound on laws per preload job
+const MAX_LAWS_PER_JOB: usize = 200;
+
+/// Pause between scrapes so a large preload doesn't hammer the source site
+const DELAY_BETWEEN_SCRAPES_MS: u64 = 1000;
+
+/// Hours preloaded laws stay cached (same as on-demand caching)
+const PRELOAD_CACHE_HOURS: i64 = 24;
+
+#[derive(Debug, Deserialize)]
+pub struct PreloadLawsRequest {
+    pub law_ids: Vec<i32>, // IDs from the law registry (laws.rs)
+    #[serde(default)]
+    pub force: bool, // Re-scrape even if the law is already cached
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PreloadJob {
+    pub id: Uuid,
+    pub status: String, // 'running', 'completed', 'completed_with_errors', 'interrupted'
+    pub force: bool,
+    pub total: i32,
+    pub processed: i32,
+    pub failed: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PreloadItem {
+    pub law_id: i32,
+    pub law_name: String,
+    pub status: String, // 'pending', 'cached', 'skipped', 'failed'
+    pub article_count: Option<i32>,
+    pub error: Option<String>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreloadJobResponse {
+    pub job: PreloadJob,
+    pub items: Vec<PreloadItem>,
+    pub unknown_law_ids: Vec<i32>, // Only set when the job is created
+}
+
+/// Admin: start a background job that caches (or re-scrapes) the given laws
+pub async fn start_preload_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<PreloadLawsRequest>,
+) -> Result<(StatusCode, ResponseJson<PreloadJobResponse>), StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let registry = laws::get_serbian_laws();
+    let mut selected = Vec::new();
+    let mut unknown_law_ids = Vec::new();
+    for law_id in &request.law_ids {
+        if selected.iter().any(|(id, _, _)| id == law_id) {
+            continue;
+        }
+        match registry.iter().find(|law| law.id == *law_id) {
+            Some(law) => selected.push((law.id, law.name.clone(), law.url.clone())),
+            None => unknown_law_ids.push(*law_id),
+        }
+    }
+
+    if selected.is_empty() || selected.len() > MAX_LAWS_PER_JOB {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let job_id = create_job(&selected, request.force, &pool).await.map_err(|e| {
+        eprintln!("{}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    println!("📚 LAW PRELOAD: Job {} started for {} law(s) (force: {})", job_id, selected.len(), request.force);
+
+    let job_pool = pool.clone();
+    let force = request.force;
+    tokio::spawn(async move {
+        run_preload_job(job_id, selected, force, &job_pool).await;
+    });
+
+    let mut response = load_job(job_id, &pool).await?.ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+    response.unknown_law_ids = unknown_law_ids;
+
+    Ok((StatusCode::ACCEPTED, ResponseJson(response)))
+}
+
+/// Admin: progress and per-law results of a preload job
+pub async fn get_preload_status_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Path(job_id): Path<Uuid>,
+) -> Result<ResponseJson<PreloadJobResponse>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let response = load_job(job_id, &pool).await?.ok_or(StatusCode::NOT_FOUND)?;
+    Ok(ResponseJson(response))
+}
+
+async fn create_job(laws: &[(i32, String, String)], force: bool, pool: &PgPool) -> Result<Uuid, String> {
+    let mut tx = pool.begin().await
+        .map_err(|e| format!("Failed to start preload transaction: {}", e))?;
+
+    let job_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO law_preload_jobs (force, total) VALUES ($1, $2) RETURNING id"
+    )
+    .bind(force)
+    .bind(laws.len() as i32)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create preload job: {}", e))?;
+
+    for (position, (law_id, law_name, _)) in laws.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO law_preload_items (job_id, law_id, law_name, position) VALUES ($1, $2, $3, $4)"
+        )
+        .bind(job_id)
+        .bind(law_id)
+        .bind(law_name)
+        .bind(position as i32)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create preload item: {}", e))?;
+    }
+
+    tx.commit().await
+        .map_err(|e| format!("Failed to commit preload job: {}", e))?;
+
+    Ok(job_id)
+}
+
+async fn load_job(job_id: Uuid, pool: &PgPool) -> Result<Option<PreloadJobResponse>, StatusCode> {
+    let db_error = |e: sqlx::Error| {
+        eprintln!("Failed to load preload job: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let job = sqlx::query_as::<_, PreloadJob>(
+        "SELECT id, status, force, total, processed, failed, created_at, finished_at
+         FROM law_preload_jobs WHERE id = $1"
+    )
+    .bind(job_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error)?;
+
+    let Some(job) = job else {
+        return Ok(None);
+    };
+
+    let items = sqlx::query_as::<_, PreloadItem>(
+        "SELECT law_id, law_name, status, article_count, error, finished_at
+         FROM law_preload_items WHERE job_id = $1 ORDER BY position"
+    )
+    .bind(job_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)?;
+
+    Ok(Some(PreloadJobResponse { job, items, unknown_law_ids: Vec::new() }))
+}
+
+async fn run_preload_job(job_id: Uuid, laws: Vec<(i32, String, String)>, force: bool, pool: &PgPool) {
+    let shutdown = crate::shutdown::token();
+    for (index, (law_id, law_name, law_url)) in laws.iter().enumerate() {
+        let result = tokio::select! {
+            result = preload_law(law_name, law_url, force, pool) => result,
+            _ = shutdown.cancelled() => {
+                mark_job_interrupted(job_id, pool).await;
+                return;
+            }
+        };
+
+        let (status, article_count, error) = match &result {
+            Ok(Some(article_count)) => ("cached", Some(*article_count), None),
+            Ok(None) => ("skipped", None, None),
+            Err(e) => ("failed", None, Some(e.as_str())),
+        };
+        println!("📚 LAW PRELOAD: [{}/{}] '{}' -> {}", index + 1, laws.len(), law_name, status);
+
+        let update = sqlx::query(
+            "WITH item AS (
+                 UPDATE law_preload_items SET status = $3, article_count = $4, error = $5, finished_at = NOW()
+                 WHERE job_id = $1 AND law_id = $2
+             )
+             UPDATE law_preload_jobs
+             SET processed = processed + 1, failed = failed + CASE WHEN $3 = 'failed' THEN 1 ELSE 0 END
+             WHERE id = $1"
+        )
+        .bind(job_id)
+        .bind(law_id)
+        .bind(status)
+        .bind(article_count)
+        .bind(error)
+        .execute(pool)
+        .await;
+        if let Err(e) = update {
+            eprintln!("Failed to record preload progress for job {}: {}", job_id, e);
+        }
+
+        // Only wait when we actually hit the source
+        if status != "skipped" && index + 1 < laws.len() {
+            tokio::select! {
+                _ = tokio::time::sleep(std::time::Duration::from_millis(DELAY_BETWEEN_SCRAPES_MS)) => {}
+                _ = shutdown.cancelled() => {
+                    mark_job_interrupted(job_id, pool).await;
+                    return;
+                }
+            }
+        }
+    }
+
+    let finished = sqlx::query(
+        "UPDATE law_preload_jobs
+         SET status = CASE WHEN failed > 0 THEN 'completed_with_errors' ELSE 'completed' END, finished_at = NOW()
+         WHERE id = $1"
+    )
+    .bind(job_id)
+    .execute(pool)
+    .await;
+    if let Err(e) = finished {
+        eprintln!("Failed to finish preload job {}: {}", job_id, e);
+    }
+
+    println!("📚 LAW PRELOAD: Job {} finished", job_id);
+}
+
+async fn mark_job_interrupted(job_id: Uuid, pool: &PgPool) {
+    println!("📚 LAW PRELOAD: Job {} interrupted by shutdown", job_id);
+    if let Err(e) = sqlx::query("UPDATE law_preload_jobs SET status = 'interrupted', finished_at = NOW() WHERE id = $1")
+        .bind(job_id)
+        .execute(pool)
+        .await
+    {
+        eprintln!("Failed to mark preload job {} as interrupted: {}", job_id, e);
+    }
+}
+
+/// Jobs run in-process, so anything still 'running' at startup was cut off by a restart
+pub async fn mark_interrupted_jobs(pool: &PgPool) -> Result<u64, String> {
+    let result = sqlx::query(
+        "UPDATE law_preload_jobs SET status = 'interrupted', finished_at = NOW() WHERE status = 'running'"
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to mark interrupted preload jobs: {}", e))?;
+
+    Ok(result.rows_affected())
+}
+
+/// Cache a single law. Returns the number of indexed articles, or None if it was already cached.
+async fn preload_law(law_name: &str, law_url: &str, force: bool, pool: &PgPool) -> Result<Option<i32>, String> {
+    if !force {
+        let cached: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM law_cache WHERE law_name = $1 AND expires_at > NOW())"
+        )
+        .bind(law_name)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to check law cache: {}", e))?;
+
+        if cached {
+            return Ok(None);
+        }
+    }
+
+    let page = crate::scraper::scrape_law_with_fallback(law_url, pool).await?;
+    crate::database::cache_law(
+        law_name.to_string(),
+        law_url.to_string(),
+        page.law.content,
+        PRELOAD_CACHE_HOURS,
+        pool,
+    )
+    .await?;
+
+    let article_count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM law_articles a JOIN law_cache c ON c.id = a.law_id WHERE c.law_name = $1"
+    )
+    .bind(law_name)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to count law articles: {}", e))?;
+
+    Ok(Some(article_count as i32))
+}
@@ -0,0 +1,400 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::models::ResponseWarning;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+/// Laws checked per job run - keeps the scraping load on the source site low
+const REVALIDATION_BATCH_SIZE: i64 = 20;
+
+/// Hours a law stays cached after the source confirmed it is unchanged
+const VERIFIED_CACHE_HOURS: i64 = 24;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LawVersion {
+    pub id: i64,
+    pub law_name: String,
+    pub law_url: String,
+    pub content_hash: String,
+    pub gazette_version: Option<String>,
+    pub previous_hash: Option<String>,
+    pub detected_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LawVersionsQuery {
+    pub law_name: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct LawToRevalidate {
+    id: i64,
+    law_name: String,
+    law_url: String,
+    content_hash: Option<String>,
+    gazette_version: Option<String>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+pub fn law_content_hash(content: &str) -> String {
+    let digest = Sha256::digest(content.as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Extract the "Sl. glasnik RS" issue list from the law header, e.g.
+/// `("Sl. glasnik RS", br. 24/2005, 61/2005 i 54/2009)` -> `Sl. glasnik RS, br. 24/2005, 61/2005, 54/2009`.
+/// Every amendment adds an issue, so this changes whenever the consolidated text should.
+pub fn extract_gazette_version(content: &str) -> Option<String> {
+    use regex::Regex;
+
+    let header = Regex::new(r#"(?i)Sl\.\s*glasnik\s+(RS|SRJ|SRS)"?\s*,?\s*br\.?([^)\n]*)"#).unwrap();
+    let issue = Regex::new(r"\d+/\d{2,4}").unwrap();
+
+    // Only look at the top of the law - the body cites other gazette issues too
+    let top: String = content.chars().take(3000).collect();
+    let cap = header.captures(&top)?;
+    let issues: Vec<&str> = issue.find_iter(&cap[2]).map(|m| m.as_str()).collect();
+    if issues.is_empty() {
+        return None;
+    }
+
+    Some(format!("Sl. glasnik {}, br. {}", cap[1].to_uppercase(), issues.join(", ")))
+}
+
+/// Store the content hash/version of a freshly cached law and log a law_versions row if it changed.
+/// When a previously cached law changed, returns the subscriber notices to announce after commit.
+pub async fn record_law_version(
+    law_id: i64,
+    law_name: &str,
+    law_url: &str,
+    content: &str,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<Option<crate::law_subscriptions::AmendmentNotices>, String> {
+    let content_hash = law_content_hash(content);
+    let gazette_version = extract_gazette_version(content);
+
+    sqlx::query(
+        "UPDATE law_cache SET content_hash = $2, gazette_version = $3, last_checked_at = NOW() WHERE id = $1"
+    )
+    .bind(law_id)
+    .bind(&content_hash)
+    .bind(&gazette_version)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to store law version: {}", e))?;
+
+    let previous_hash: Option<String> = sqlx::query_scalar(
+        "SELECT content_hash FROM law_versions WHERE law_name = $1 ORDER BY detected_at DESC, id DESC LIMIT 1"
+    )
+    .bind(law_name)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to load previous law version: {}", e))?;
+
+    if previous_hash.as_deref() == Some(content_hash.as_str()) {
+        return Ok(None);
+    }
+
+    sqlx::query(
+        "INSERT INTO law_versions (law_name, law_url, content_hash, gazette_version, previous_hash)
+         VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(law_name)
+    .bind(law_url)
+    .bind(&content_hash)
+    .bind(&gazette_version)
+    .bind(&previous_hash)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to log law version: {}", e))?;
+
+    if previous_hash.is_none() {
+        return Ok(None);
+    }
+
+    println!("📜 LAW UPDATE: '{}' changed (version: {:?})", law_name, gazette_version);
+    // Cached answers may cite the old text
+    crate::answer_cache::invalidate_law(law_name, tx).await?;
+    let notices = crate::law_subscriptions::notify_subscribers(law_name, gazette_version.as_deref(), tx).await?;
+    Ok(Some(notices))
+}
+
+/// Background job that re-checks cached laws against their source page.
+/// Unchanged laws get their cache extended, changed ones are re-scraped (or invalidated if that fails).
+pub async fn start_law_revalidation_job(pool: Arc<PgPool>, shutdown: CancellationToken) {
+    let revalidation_interval = crate::config::get().law_revalidation_interval;
+
+    // Check for due laws more often than the interval so batches stay small
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(15 * 60));
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.cancelled() => {
+                info!("🛑 Law revalidation job stopped");
+                return;
+            }
+        }
+
+        let due_laws = sqlx::query_as::<_, LawToRevalidate>(
+            "SELECT id, law_name, law_url, content_hash, gazette_version, etag, last_modified
+             FROM law_cache
+             WHERE last_checked_at IS NULL OR last_checked_at < NOW() - make_interval(hours => $1)
+             ORDER BY last_checked_at ASC NULLS FIRST
+             LIMIT $2"
+        )
+        .bind((revalidation_interval.as_secs() / 3600) as i32)
+        .bind(REVALIDATION_BATCH_SIZE)
+        .fetch_all(pool.as_ref())
+        .await;
+
+        match due_laws {
+            Ok(laws) => {
+                if !laws.is_empty() {
+                    info!("📜 Revalidating {} cached law(s) against source", laws.len());
+                }
+                for law in laws {
+                    if let Err(e) = revalidate_law(&law, &pool).await {
+                        error!("❌ Failed to revalidate law '{}': {}", law.law_name, e);
+                    }
+                }
+            }
+            Err(e) => {
+                error!("❌ Failed to fetch laws due for revalidation: {}", e);
+            }
+        }
+    }
+}
+
+async fn revalidate_law(law: &LawToRevalidate, pool: &PgPool) -> Result<(), String> {
+    // Cheap HEAD first - if the server's validators match, skip downloading the page
+    if law.etag.is_some() || law.last_modified.is_some() {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(20))
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+        if let Ok(response) = client.head(&law.law_url).send().await {
+            let header = |name: reqwest::header::HeaderName| {
+                response.headers().get(name).and_then(|v| v.to_str().ok()).map(String::from)
+            };
+            let etag = header(reqwest::header::ETAG);
+            let last_modified = header(reqwest::header::LAST_MODIFIED);
+
+            let etag_matches = etag.is_some() && etag == law.etag;
+            let last_modified_matches = last_modified.is_some() && last_modified == law.last_modified;
+            if response.status().is_success() && (etag_matches || last_modified_matches) {
+                return mark_law_unchanged(law.id, etag, last_modified, pool).await;
+            }
+        }
+    }
+
+    let page = match crate::scraper::scrape_law_page(&law.law_url).await {
+        Ok(page) => page,
+        Err(e) => {
+            // Can't confirm the cached text is current - let it expire normally and check again next interval
+            warn!("⚠️  Could not re-scrape '{}': {}", law.law_name, e);
+            sqlx::query("UPDATE law_cache SET last_checked_at = NOW() WHERE id = $1")
+                .bind(law.id)
+                .execute(pool)
+                .await
+                .map_err(|e| format!("Failed to update law check time: {}", e))?;
+            return Ok(());
+        }
+    };
+
+    let new_hash = law_content_hash(&page.law.content);
+    if law.content_hash.as_deref() == Some(new_hash.as_str()) {
+        return mark_law_unchanged(law.id, page.etag, page.last_modified, pool).await;
+    }
+
+    let new_version = extract_gazette_version(&page.law.content);
+    info!(
+        "📜 Law '{}' changed at source (version {:?} -> {:?}), refreshing cache",
+        law.law_name, law.gazette_version, new_version
+    );
+
+    if page.law.content.trim().is_empty() {
+        // Source returned something unusable - drop the stale copy so it's re-fetched on demand
+        return invalidate_law(law.id, pool).await;
+    }
+
+    if let Err(e) = crate::database::cache_law(
+        law.law_name.clone(),
+        law.law_url.clone(),
+        page.law.content,
+        VERIFIED_CACHE_HOURS,
+        pool,
+    )
+    .await
+    {
+        warn!("⚠️  Failed to re-cache '{}', invalidating: {}", law.law_name, e);
+        return invalidate_law(law.id, pool).await;
+    }
+
+    sqlx::query("UPDATE law_cache SET etag = $2, last_modified = $3 WHERE id = $1")
+        .bind(law.id)
+        .bind(&page.etag)
+        .bind(&page.last_modified)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to store law validators: {}", e))?;
+
+    Ok(())
+}
+
+async fn mark_law_unchanged(
+    law_id: i64,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    pool: &PgPool,
+) -> Result<(), String> {
+    sqlx::query(
+        "UPDATE law_cache
+         SET last_checked_at = NOW(),
+             expires_at = GREATEST(expires_at, NOW() + INTERVAL '1 hour' * $4),
+             etag = COALESCE($2, etag),
+             last_modified = COALESCE($3, last_modified)
+         WHERE id = $1"
+    )
+    .bind(law_id)
+    .bind(etag)
+    .bind(last_modified)
+    .bind(VERIFIED_CACHE_HOURS)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to mark law as unchanged: {}", e))?;
+
+    Ok(())
+}
+
+async fn invalidate_law(law_id: i64, pool: &PgPool) -> Result<(), String> {
+    sqlx::query("UPDATE law_cache SET expires_at = NOW(), last_checked_at = NOW() WHERE id = $1")
+        .bind(law_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to invalidate law: {}", e))?;
+
+    Ok(())
+}
+
+/// Amendments detected within this many days are flagged on answers that use the law
+const RECENT_AMENDMENT_DAYS: i32 = 30;
+
+/// Laws not confirmed against the source for this long are flagged as possibly stale
+const STALE_CHECK_HOURS: i32 = 48;
+
+/// Caveats about the cached text of a law: recently amended at the source, or not re-checked lately
+pub async fn law_freshness_warnings(law_name: &str, pool: &PgPool) -> Vec<ResponseWarning> {
+    // (gazette_version, recently_amended_at, stale)
+    let status = sqlx::query_as::<_, (Option<String>, Option<chrono::DateTime<chrono::Utc>>, bool)>(
+        "SELECT c.gazette_version,
+                (SELECT MAX(v.detected_at) FROM law_versions v
+                 WHERE v.law_name = c.law_name AND v.previous_hash IS NOT NULL
+                   AND v.detected_at > NOW() - make_interval(days => $2)),
+                COALESCE(c.last_checked_at, c.cached_at) < NOW() - make_interval(hours => $3)
+         FROM law_cache c
+         WHERE c.law_name = $1"
+    )
+    .bind(law_name)
+    .bind(RECENT_AMENDMENT_DAYS)
+    .bind(STALE_CHECK_HOURS)
+    .fetch_optional(pool)
+    .await;
+
+    let (gazette_version, amended_at, stale) = match status {
+        Ok(Some(status)) => status,
+        Ok(None) => return Vec::new(),
+        Err(e) => {
+            eprintln!("⚠️  Failed to check law freshness for '{}': {}", law_name, e);
+            return Vec::new();
+        }
+    };
+
+    let mut warnings = Vec::new();
+    if let Some(amended_at) = amended_at {
+        let version = gazette_version.map(|v| format!(" ({})", v)).unwrap_or_default();
+        warnings.push(ResponseWarning::new(
+            "law_recently_amended",
+            format!(
+                "Zakon je menjan {}{} - proverite da li odgovor uzima u obzir poslednje izmene.",
+                amended_at.format("%d.%m.%Y."),
+                version
+            ),
+        ));
+    }
+    if stale {
+        warnings.push(ResponseWarning::new(
+            "law_not_verified",
+            "Tekst zakona nije skoro proveren na izvoru - moguće je da je zakon menjan posle poslednjeg keširanja.",
+        ));
+    }
+
+    warnings
+}
+
+/// Admin: law version history, newest first
+pub async fn list_law_versions_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<LawVersionsQuery>,
+) -> Result<ResponseJson<Vec<LawVersion>>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+
+    let versions = sqlx::query_as::<_, LawVersion>(
+        "SELECT id, law_name, law_url, content_hash, gazette_version, previous_hash, detected_at
+         FROM law_versions
+         WHERE $1::TEXT IS NULL OR law_name = $1
+         ORDER BY detected_at DESC, id DESC
+         LIMIT $2"
+    )
+    .bind(query.law_name)
+    .bind(limit)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to list law versions: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(ResponseJson(versions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_gazette_version() {
+        let content = "ZAKON O RADU\n(\"Sl. glasnik RS\", br. 24/2005, 61/2005, 54/2009 i 32/2013)\n\nČlan 1\nOvim zakonom...";
+        assert_eq!(
+            extract_gazette_version(content).as_deref(),
+            Some("Sl. glasnik RS, br. 24/2005, 61/2005, 54/2009, 32/2013")
+        );
+
+        assert_eq!(extract_gazette_version("ZAKON O RADU\n\nČlan 1\nOvim zakonom..."), None);
+    }
+
+    #[test]
+    fn test_law_content_hash_is_stable() {
+        assert_eq!(law_content_hash("Član 1"), law_content_hash("Član 1"));
+        assert_ne!(law_content_hash("Član 1"), law_content_hash("Član 2"));
+        assert_eq!(law_content_hash("").len(), 64);
+    }
+}
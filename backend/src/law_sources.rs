@@ -0,0 +1,350 @@
+use axum::{
+    extract::{Json, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::jurisdictions::Jurisdiction;
+use crate::models::LawContent;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+/// A website law texts can be scraped from. Each site has its own markup, so the scraper
+/// picks the source by the URL's domain and lets it describe where the law text lives.
+pub trait LawSource: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Legal system whose laws the site publishes
+    fn jurisdiction(&self) -> Jurisdiction {
+        Jurisdiction::Rs
+    }
+
+    /// Whether this source serves pages on the given host (e.g. "www.paragraf.rs")
+    fn handles_host(&self, host: &str) -> bool;
+
+    /// CSS selector for the law title
+    fn title_selector(&self) -> &'static str;
+
+    /// CSS selectors for the element holding the law text, tried in order
+    fn content_selectors(&self) -> &'static [&'static str];
+
+    /// Pages that load fine but contain a captcha or access-denied notice instead of the law.
+    /// Only short pages count - real law pages can embed a captcha widget (e.g. newsletter forms).
+    fn is_blocked_page(&self, html: &str) -> bool {
+        if html.len() > 20_000 {
+            return false;
+        }
+        let lower = html.to_lowercase();
+        lower.contains("captcha") || lower.contains("access denied") || lower.contains("pristup odbijen")
+    }
+
+    fn parse(&self, html: &str) -> Result<LawContent, String> {
+        crate::scraper::parse_law_content(html, self.title_selector(), self.content_selectors())
+    }
+
+    /// Page holding a single article of the law, for sources that publish one.
+    /// Used when an article can't be extracted from the full law text.
+    fn article_url(&self, _law_url: &str, _article_number: &str) -> Option<String> {
+        None
+    }
+}
+
+/// paragraf.rs - consolidated (prečišćeni) texts, the default source of the law registry
+pub struct Paragraf;
+
+impl LawSource for Paragraf {
+    fn name(&self) -> &'static str {
+        "paragraf.rs"
+    }
+
+    fn handles_host(&self, host: &str) -> bool {
+        host == "paragraf.rs" || host.ends_with(".paragraf.rs")
+    }
+
+    fn title_selector(&self) -> &'static str {
+        "h1, .naslov, .title"
+    }
+
+    fn content_selectors(&self) -> &'static [&'static str] {
+        &[".sadrzaj", ".content", ".zakon-content", "#content", "article", "main", ".main-content"]
+    }
+
+    // .../propisi/zakon_o_radu.html -> .../propisi/zakon_o_radu/clan-12.html
+    fn article_url(&self, law_url: &str, article_number: &str) -> Option<String> {
+        let base = law_url.split(['#', '?']).next()?.strip_suffix(".html")?;
+        Some(format!("{}/clan-{}.html", base, article_number.to_lowercase()))
+    }
+}
+
+/// pravno-informacioni-sistem.rs - the official legal information system (Službeni glasnik).
+/// Documents are Word-exported HTML, so the text sits in WordSection containers.
+pub struct PravnoInformacioniSistem;
+
+impl LawSource for PravnoInformacioniSistem {
+    fn name(&self) -> &'static str {
+        "pravno-informacioni-sistem.rs"
+    }
+
+    fn handles_host(&self, host: &str) -> bool {
+        host == "pravno-informacioni-sistem.rs" || host.ends_with(".pravno-informacioni-sistem.rs")
+    }
+
+    fn title_selector(&self) -> &'static str {
+        ".naslov, .Naslov, h1, title"
+    }
+
+    fn content_selectors(&self) -> &'static [&'static str] {
+        &[".WordSection1", "#doc", ".doc-content", "#content", "article", "main"]
+    }
+}
+
+/// zakon.hr - consolidated Croatian laws (pročišćeni tekstovi)
+pub struct ZakonHr;
+
+impl LawSource for ZakonHr {
+    fn name(&self) -> &'static str {
+        "zakon.hr"
+    }
+
+    fn jurisdiction(&self) -> Jurisdiction {
+        Jurisdiction::Hr
+    }
+
+    fn handles_host(&self, host: &str) -> bool {
+        host == "zakon.hr" || host.ends_with(".zakon.hr")
+    }
+
+    fn title_selector(&self) -> &'static str {
+        "h1, .naslov, title"
+    }
+
+    fn content_selectors(&self) -> &'static [&'static str] {
+        &[".zakon-sadrzaj", ".tekst", "#content", "article", "main"]
+    }
+}
+
+/// paragraf.ba - Bosnian laws (state, entity and Brčko district level), same site layout as paragraf.rs
+pub struct ParagrafBa;
+
+impl LawSource for ParagrafBa {
+    fn name(&self) -> &'static str {
+        "paragraf.ba"
+    }
+
+    fn jurisdiction(&self) -> Jurisdiction {
+        Jurisdiction::Ba
+    }
+
+    fn handles_host(&self, host: &str) -> bool {
+        host == "paragraf.ba" || host.ends_with(".paragraf.ba")
+    }
+
+    fn title_selector(&self) -> &'static str {
+        Paragraf.title_selector()
+    }
+
+    fn content_selectors(&self) -> &'static [&'static str] {
+        Paragraf.content_selectors()
+    }
+}
+
+/// lex.bg - Bulgarian laws in force
+pub struct LexBg;
+
+impl LawSource for LexBg {
+    fn name(&self) -> &'static str {
+        "lex.bg"
+    }
+
+    fn jurisdiction(&self) -> Jurisdiction {
+        Jurisdiction::Bg
+    }
+
+    fn handles_host(&self, host: &str) -> bool {
+        host == "lex.bg" || host.ends_with(".lex.bg")
+    }
+
+    fn title_selector(&self) -> &'static str {
+        "h1, .title, title"
+    }
+
+    fn content_selectors(&self) -> &'static [&'static str] {
+        &[".boxi", "#DocumentContent", "#content", "article", "main"]
+    }
+}
+
+static PARAGRAF: Paragraf = Paragraf;
+static PRAVNO_INFORMACIONI_SISTEM: PravnoInformacioniSistem = PravnoInformacioniSistem;
+static ZAKON_HR: ZakonHr = ZakonHr;
+static PARAGRAF_BA: ParagrafBa = ParagrafBa;
+static LEX_BG: LexBg = LexBg;
+
+fn sources() -> [&'static dyn LawSource; 5] {
+    [&PARAGRAF, &PRAVNO_INFORMACIONI_SISTEM, &ZAKON_HR, &PARAGRAF_BA, &LEX_BG]
+}
+
+/// Sources publishing the laws of a jurisdiction, default source first
+pub fn sources_for(jurisdiction: Jurisdiction) -> Vec<&'static dyn LawSource> {
+    sources().into_iter().filter(|source| source.jurisdiction() == jurisdiction).collect()
+}
+
+/// Pick the source for a URL by domain. Unknown domains use the paragraf.rs parser,
+/// whose generic selectors (article, main, #content) work on most sites.
+pub fn source_for_url(url: &str) -> &'static dyn LawSource {
+    let host = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+        .unwrap_or_default();
+
+    sources()
+        .into_iter()
+        .find(|source| source.handles_host(&host))
+        .unwrap_or(&PARAGRAF)
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LawSourceAlternate {
+    pub primary_url: String,
+    pub alternate_url: String,
+    pub priority: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetLawSourceAlternateRequest {
+    pub primary_url: String,
+    pub alternate_url: String,
+    pub priority: Option<i32>, // Lower is tried first
+    #[serde(default)]
+    pub remove: bool,
+}
+
+/// Alternate URLs (usually on another source site) for the same law, in the order to try them
+pub async fn alternate_urls(primary_url: &str, pool: &PgPool) -> Vec<String> {
+    sqlx::query_scalar::<_, String>(
+        "SELECT alternate_url FROM law_source_alternates WHERE primary_url = $1 ORDER BY priority, created_at"
+    )
+    .bind(primary_url)
+    .fetch_all(pool)
+    .await
+    .unwrap_or_else(|e| {
+        eprintln!("⚠️  Failed to load alternate law sources: {}", e);
+        Vec::new()
+    })
+}
+
+/// Admin: list registered alternate law sources
+pub async fn list_law_sources_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ResponseJson<Vec<LawSourceAlternate>>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(ResponseJson(list_alternates(&pool).await?))
+}
+
+async fn list_alternates(pool: &PgPool) -> Result<Vec<LawSourceAlternate>, StatusCode> {
+    sqlx::query_as::<_, LawSourceAlternate>(
+        "SELECT primary_url, alternate_url, priority, created_at FROM law_source_alternates ORDER BY primary_url, priority"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to list alternate law sources: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Admin: register (or remove) an alternate URL to scrape when a law's primary source fails
+pub async fn set_law_source_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<SetLawSourceAlternateRequest>,
+) -> Result<ResponseJson<Vec<LawSourceAlternate>>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let valid_url = |url: &str| reqwest::Url::parse(url).is_ok_and(|u| u.scheme() == "https" || u.scheme() == "http");
+    if !valid_url(&request.primary_url) || !valid_url(&request.alternate_url) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let result = if request.remove {
+        sqlx::query("DELETE FROM law_source_alternates WHERE primary_url = $1 AND alternate_url = $2")
+            .bind(&request.primary_url)
+            .bind(&request.alternate_url)
+            .execute(&pool)
+            .await
+    } else {
+        sqlx::query(
+            "INSERT INTO law_source_alternates (primary_url, alternate_url, priority)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (primary_url, alternate_url) DO UPDATE SET priority = EXCLUDED.priority"
+        )
+        .bind(&request.primary_url)
+        .bind(&request.alternate_url)
+        .bind(request.priority.unwrap_or(0))
+        .execute(&pool)
+        .await
+    };
+
+    result.map_err(|e| {
+        eprintln!("Failed to update alternate law source: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    println!(
+        "✅ Law source alternate {}: {} -> {} ({})",
+        if request.remove { "removed" } else { "saved" },
+        request.primary_url,
+        request.alternate_url,
+        source_for_url(&request.alternate_url).name()
+    );
+
+    Ok(ResponseJson(list_alternates(&pool).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_for_url_by_domain() {
+        assert_eq!(source_for_url("https://www.paragraf.rs/propisi/zakon_o_radu.html").name(), "paragraf.rs");
+        assert_eq!(
+            source_for_url("https://www.pravno-informacioni-sistem.rs/SlGlasnikPortal/eli/rep/sgrs/skupstina/zakon/2005/24/1/reg").name(),
+            "pravno-informacioni-sistem.rs"
+        );
+        // Unknown domains and garbage fall back to the default parser
+        assert_eq!(source_for_url("https://example.com/zakon").name(), "paragraf.rs");
+        assert_eq!(source_for_url("not a url").name(), "paragraf.rs");
+    }
+
+    #[test]
+    fn test_registry_urls_map_to_jurisdiction_sources() {
+        for jurisdiction in Jurisdiction::ALL {
+            assert!(!sources_for(jurisdiction).is_empty());
+            if jurisdiction == Jurisdiction::Rs {
+                continue; // Serbian registry is large and all on paragraf.rs
+            }
+            for law in crate::laws::get_laws(jurisdiction) {
+                assert_eq!(source_for_url(&law.url).jurisdiction(), jurisdiction, "{}", law.url);
+            }
+        }
+    }
+
+    #[test]
+    fn test_article_url() {
+        assert_eq!(
+            Paragraf.article_url("https://www.paragraf.rs/propisi/zakon_o_radu.html", "12a").as_deref(),
+            Some("https://www.paragraf.rs/propisi/zakon_o_radu/clan-12a.html")
+        );
+        assert_eq!(Paragraf.article_url("https://www.paragraf.rs/propisi/", "12"), None);
+        assert_eq!(PravnoInformacioniSistem.article_url("https://www.pravno-informacioni-sistem.rs/x", "12"), None);
+    }
+}
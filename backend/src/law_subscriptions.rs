@@ -0,0 +1,261 @@
+// Law change monitoring: users subscribe to laws they care about and get a notification
+// ("Zakon o radu izmenjen") when the revalidation job detects a new consolidated text at the source.
+// Detection itself lives in law_revalidation; this module keeps subscribed laws in the cache so the
+// revalidation job watches them, and fans detected amendments out to subscribers' notification
+// feeds (notifications.rs).
+use axum::{
+    extract::{Json, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::database::verify_user_from_headers_async;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+const MAX_SUBSCRIPTIONS_PER_USER: i64 = 100;
+
+/// Subscribed laws that aren't cached yet, fetched per job run
+const SUBSCRIPTION_FETCH_BATCH_SIZE: i64 = 5;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LawSubscription {
+    pub law_id: i32,
+    pub law_name: String,
+    pub gazette_version: Option<String>, // Version currently in the cache, if cached
+    pub last_checked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubscribeRequest {
+    pub law_id: i32,
+}
+
+/// Notification title for an amended law, e.g. "Zakon o radu izmenjen"
+fn amendment_title(law_name: &str) -> String {
+    format!("{} izmenjen", law_name)
+}
+
+fn amendment_body(gazette_version: Option<&str>) -> String {
+    match gazette_version {
+        Some(version) => format!("Na izvoru je objavljen novi prečišćen tekst ({}).", version),
+        None => "Na izvoru je objavljen novi prečišćen tekst.".to_string(),
+    }
+}
+
+/// Subscribers notified about an amended law. Announced over the event socket with `announce`
+/// only after the transaction that created the notifications has committed.
+#[derive(Debug)]
+pub struct AmendmentNotices {
+    law_name: String,
+    title: String,
+    notified: Vec<(Uuid, i64)>, // (user_id, notification_id)
+}
+
+impl AmendmentNotices {
+    pub fn announce(self) {
+        for (user_id, notification_id) in self.notified {
+            crate::events::emit(user_id, crate::events::ChatEvent::LawAmended {
+                notification_id,
+                law_name: self.law_name.clone(),
+                title: self.title.clone(),
+            });
+            crate::notifications::emit_created(user_id, notification_id, crate::notifications::NotificationKind::LawAmended, &self.title);
+        }
+    }
+}
+
+/// Create a notification for every subscriber of an amended law.
+/// Runs inside the transaction that records the new law version.
+pub async fn notify_subscribers(
+    law_name: &str,
+    gazette_version: Option<&str>,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<AmendmentNotices, String> {
+    let title = amendment_title(law_name);
+    let body = amendment_body(gazette_version);
+
+    // Subscribers who turned law notifications off are skipped
+    let notified = sqlx::query_as::<_, (Uuid, i64)>(
+        "INSERT INTO user_notifications (user_id, kind, title, body, law_name)
+         SELECT s.user_id, 'law_amended', $2, $3, s.law_name
+         FROM law_subscriptions s
+         WHERE s.law_name = $1
+           AND NOT EXISTS (
+               SELECT 1 FROM notification_preferences p
+               WHERE p.user_id = s.user_id AND p.kind = 'law_amended' AND NOT p.in_app
+           )
+         RETURNING user_id, id"
+    )
+    .bind(law_name)
+    .bind(&title)
+    .bind(&body)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to notify law subscribers: {}", e))?;
+
+    if !notified.is_empty() {
+        println!("🔔 Notified {} subscriber(s) about amended '{}'", notified.len(), law_name);
+    }
+
+    Ok(AmendmentNotices { law_name: law_name.to_string(), title, notified })
+}
+
+/// Background job that caches subscribed laws nobody has asked about yet,
+/// so the revalidation job starts watching them for amendments.
+pub async fn start_law_subscription_job(pool: Arc<PgPool>, shutdown: CancellationToken) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(30 * 60));
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.cancelled() => {
+                info!("🛑 Law subscription job stopped");
+                return;
+            }
+        }
+
+        let uncached = sqlx::query_scalar::<_, i32>(
+            "SELECT DISTINCT s.law_id FROM law_subscriptions s
+             WHERE NOT EXISTS (SELECT 1 FROM law_cache c WHERE c.law_name = s.law_name)
+             LIMIT $1"
+        )
+        .bind(SUBSCRIPTION_FETCH_BATCH_SIZE)
+        .fetch_all(pool.as_ref())
+        .await;
+
+        let law_ids = match uncached {
+            Ok(law_ids) => law_ids,
+            Err(e) => {
+                error!("❌ Failed to fetch uncached subscribed laws: {}", e);
+                continue;
+            }
+        };
+
+        for law_id in law_ids {
+            let Some(law) = crate::laws::find_law_by_id(law_id) else {
+                continue;
+            };
+            match crate::api::get_law_content(&law.name, &law.url, &pool).await {
+                Ok(_) => info!("🔔 Cached subscribed law '{}' for change monitoring", law.name),
+                Err(e) => warn!("⚠️  Failed to cache subscribed law '{}': {}", law.name, e),
+            }
+        }
+    }
+}
+
+/// List the laws the user is subscribed to
+pub async fn list_subscriptions_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ResponseJson<Vec<LawSubscription>>, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    Ok(ResponseJson(fetch_subscriptions(user_id, &pool).await?))
+}
+
+async fn fetch_subscriptions(user_id: Uuid, pool: &PgPool) -> Result<Vec<LawSubscription>, StatusCode> {
+    sqlx::query_as::<_, LawSubscription>(
+        "SELECT s.law_id, s.law_name, c.gazette_version, c.last_checked_at, s.created_at
+         FROM law_subscriptions s
+         LEFT JOIN law_cache c ON c.law_name = s.law_name
+         WHERE s.user_id = $1
+         ORDER BY s.law_name"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to fetch law subscriptions: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Subscribe to amendments of a law (idempotent)
+pub async fn subscribe_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<SubscribeRequest>,
+) -> Result<ResponseJson<Vec<LawSubscription>>, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let law = crate::laws::find_law_by_id(request.law_id).ok_or(StatusCode::NOT_FOUND)?;
+
+    let subscription_count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM law_subscriptions WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to count law subscriptions: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if subscription_count >= MAX_SUBSCRIPTIONS_PER_USER {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    sqlx::query(
+        "INSERT INTO law_subscriptions (user_id, law_id, law_name) VALUES ($1, $2, $3)
+         ON CONFLICT (user_id, law_id) DO NOTHING"
+    )
+    .bind(user_id)
+    .bind(law.id)
+    .bind(&law.name)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to subscribe to law: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(ResponseJson(fetch_subscriptions(user_id, &pool).await?))
+}
+
+/// Unsubscribe from a law
+pub async fn unsubscribe_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(law_id): Path<i32>,
+) -> Result<StatusCode, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let result = sqlx::query("DELETE FROM law_subscriptions WHERE user_id = $1 AND law_id = $2")
+        .bind(user_id)
+        .bind(law_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to unsubscribe from law: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amendment_notification_text() {
+        assert_eq!(amendment_title("Zakon o radu"), "Zakon o radu izmenjen");
+        assert!(amendment_body(Some("Sl. glasnik RS, br. 24/2005, 32/2013")).contains("32/2013"));
+        assert!(!amendment_body(None).contains('('));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::jurisdictions::Jurisdiction;
 use crate::models::SerbianLaw;
 
 pub fn get_serbian_laws() -> Vec<SerbianLaw> {
@@ -1573,4 +1574,121 @@ pub fn get_serbian_laws() -> Vec<SerbianLaw> {
         SerbianLaw { id: 1568, name: "Zakon O Žičarama Za Transport Lica".to_string(), url: "https://www.paragraf.rs/propisi/zakon-o-zicarama-za-transport-lica.html".to_string() },
         SerbianLaw { id: 1569, name: "Zakon O Zvaničnoj Statistici".to_string(), url: "https://www.paragraf.rs/propisi/zakon-o-zvanicnoj-statistici-republike-srbije.html".to_string() },
     ]
-}
\ No newline at end of file
+}
+
+// Registries outside Serbia start with the core laws. Their names carry the country in brackets
+// ("Zakon o radu (Hrvatska)") so they never collide with a Serbian law of the same name in law_cache.
+// IDs are offset per jurisdiction: 10000+ Croatia, 20000+ Bosnia and Herzegovina, 30000+ Bulgaria.
+fn foreign_law(jurisdiction: Jurisdiction, id: i32, name: &str, url: &str) -> SerbianLaw {
+    SerbianLaw { id, name: format!("{} ({})", name, jurisdiction.country_name()), url: url.to_string() }
+}
+
+fn croatian_laws() -> Vec<SerbianLaw> {
+    let law = |id, name, url| foreign_law(Jurisdiction::Hr, id, name, url);
+    vec![
+        law(10001, "Zakon o radu", "https://www.zakon.hr/z/307/Zakon-o-radu"),
+        law(10002, "Zakon o obveznim odnosima", "https://www.zakon.hr/z/75/Zakon-o-obveznim-odnosima"),
+        law(10003, "Kazneni zakon", "https://www.zakon.hr/z/98/Kazneni-zakon"),
+        law(10004, "Zakon o parničnom postupku", "https://www.zakon.hr/z/134/Zakon-o-parni%C4%8Dnom-postupku"),
+        law(10005, "Obiteljski zakon", "https://www.zakon.hr/z/88/Obiteljski-zakon"),
+        law(10006, "Zakon o trgovačkim društvima", "https://www.zakon.hr/z/546/Zakon-o-trgova%C4%8Dkim-dru%C5%A1tvima"),
+        law(10007, "Zakon o nasljeđivanju", "https://www.zakon.hr/z/145/Zakon-o-naslje%C4%91ivanju"),
+        law(10008, "Zakon o sigurnosti prometa na cestama", "https://www.zakon.hr/z/78/Zakon-o-sigurnosti-prometa-na-cestama"),
+    ]
+}
+
+fn bosnian_laws() -> Vec<SerbianLaw> {
+    let law = |id, name, url| foreign_law(Jurisdiction::Ba, id, name, url);
+    vec![
+        law(20001, "Zakon o radu Federacije BiH", "https://www.paragraf.ba/propisi/fbih/zakon-o-radu.html"),
+        law(20002, "Zakon o obligacionim odnosima", "https://www.paragraf.ba/propisi/fbih/zakon-o-obligacionim-odnosima.html"),
+        law(20003, "Krivični zakon Federacije BiH", "https://www.paragraf.ba/propisi/fbih/krivicni-zakon-federacije-bosne-i-hercegovine.html"),
+        law(20004, "Zakon o parničnom postupku Federacije BiH", "https://www.paragraf.ba/propisi/fbih/zakon-o-parnicnom-postupku.html"),
+    ]
+}
+
+fn bulgarian_laws() -> Vec<SerbianLaw> {
+    let law = |id, name, url| foreign_law(Jurisdiction::Bg, id, name, url);
+    vec![
+        law(30001, "Кодекс на труда", "https://lex.bg/laws/ldoc/1594373121"),
+        law(30002, "Закон за задълженията и договорите", "https://lex.bg/laws/ldoc/2121934337"),
+        law(30003, "Наказателен кодекс", "https://lex.bg/laws/ldoc/1589654529"),
+        law(30004, "Граждански процесуален кодекс", "https://lex.bg/laws/ldoc/2135558368"),
+        law(30005, "Семеен кодекс", "https://lex.bg/laws/ldoc/2135637484"),
+    ]
+}
+
+/// Law registry of a jurisdiction
+pub fn get_laws(jurisdiction: Jurisdiction) -> Vec<SerbianLaw> {
+    match jurisdiction {
+        Jurisdiction::Rs => get_serbian_laws(),
+        Jurisdiction::Hr => croatian_laws(),
+        Jurisdiction::Ba => bosnian_laws(),
+        Jurisdiction::Bg => bulgarian_laws(),
+    }
+}
+
+/// Registry law by ID, in any jurisdiction
+pub fn find_law_by_id(law_id: i32) -> Option<SerbianLaw> {
+    let jurisdiction = match law_id {
+        10000..=19999 => Jurisdiction::Hr,
+        20000..=29999 => Jurisdiction::Ba,
+        30000..=39999 => Jurisdiction::Bg,
+        _ => Jurisdiction::Rs,
+    };
+    get_laws(jurisdiction).into_iter().find(|law| law.id == law_id)
+}
+
+/// Jurisdiction a cached law belongs to - laws outside the non-Serbian registries are Serbian
+pub fn jurisdiction_of_law(law_name: &str) -> Jurisdiction {
+    [Jurisdiction::Hr, Jurisdiction::Ba, Jurisdiction::Bg]
+        .into_iter()
+        .find(|jurisdiction| get_laws(*jurisdiction).iter().any(|law| law.name == law_name))
+        .unwrap_or_default()
+}
+
+/// Registry name for a law name detected by the model in a non-Serbian chat (the model answers
+/// "Zakon o radu", the registry has "Zakon o radu (Hrvatska)"). Serbian names are used as-is.
+pub fn canonical_law_name(detected: &str, jurisdiction: Jurisdiction) -> Option<String> {
+    if jurisdiction == Jurisdiction::Rs {
+        return None;
+    }
+
+    let detected = detected.trim().trim_matches('"').to_lowercase();
+    let suffix = format!(" ({})", jurisdiction.country_name().to_lowercase());
+    let laws = get_laws(jurisdiction);
+    let bare_name = |law: &SerbianLaw| law.name.to_lowercase().trim_end_matches(suffix.as_str()).to_string();
+
+    laws.iter()
+        .find(|law| law.name.to_lowercase() == detected || bare_name(law) == detected)
+        .or_else(|| laws.iter().find(|law| detected.contains(&bare_name(law)) || bare_name(law).contains(&detected)))
+        .map(|law| law.name.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_ids_are_unique_and_found() {
+        let all: Vec<SerbianLaw> = Jurisdiction::ALL.into_iter().flat_map(get_laws).collect();
+        let mut ids: Vec<i32> = all.iter().map(|law| law.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), all.len());
+
+        assert_eq!(find_law_by_id(10001).unwrap().name, "Zakon o radu (Hrvatska)");
+        assert_eq!(find_law_by_id(8).unwrap().name, "Zakon o radu");
+    }
+
+    #[test]
+    fn test_canonical_law_name_per_jurisdiction() {
+        assert_eq!(canonical_law_name("Zakon o radu", Jurisdiction::Hr).as_deref(), Some("Zakon o radu (Hrvatska)"));
+        assert_eq!(canonical_law_name("\"kazneni zakon\"", Jurisdiction::Hr).as_deref(), Some("Kazneni zakon (Hrvatska)"));
+        assert_eq!(canonical_law_name("Кодекс на труда", Jurisdiction::Bg).as_deref(), Some("Кодекс на труда (Bugarska)"));
+        assert_eq!(canonical_law_name("Zakon o radu", Jurisdiction::Rs), None);
+
+        assert_eq!(jurisdiction_of_law("Obiteljski zakon (Hrvatska)"), Jurisdiction::Hr);
+        assert_eq!(jurisdiction_of_law("Porodični zakon"), Jurisdiction::Rs);
+    }
+}
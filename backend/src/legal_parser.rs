@@ -1,11 +1,102 @@
-// Complex Serbian legal grammar parsing removed
-// Now using simplified LLM-guided approach in api.rs for semantic legal document understanding
-
-// This file previously contained:
-// - SerbianLegalGrammarParser struct with regex patterns for Serbian legal references
-// - Complex penalty amount parsing
-// - Structured article classification 
-// - Cross-reference extraction logic
-//
-// All of this functionality has been replaced with LLM-guided semantic search
-// which is more flexible and handles natural language variations better
\ No newline at end of file
+// Structural parsing of law texts into articles, done once when a law is scraped and cached
+// (database::index_law_articles) instead of on every question. Each article keeps the section it
+// belongs to ("I. OSNOVNE ODREDBE"); section headings are not part of any article body.
+use regex::Regex;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LawArticle {
+    pub number: String,          // "12", "12a"
+    pub section: Option<String>, // Heading of the part/chapter the article is in
+    pub body: String,
+}
+
+fn article_header() -> &'static Regex {
+    static HEADER: OnceLock<Regex> = OnceLock::new();
+    HEADER.get_or_init(|| Regex::new(r"^Član\s+(\d+[a-z]?)\b").unwrap())
+}
+
+/// Part/chapter headings are written in capitals ("DRUGI DEO", "III. RADNO VREME")
+fn is_section_heading(line: &str) -> bool {
+    let letters: Vec<char> = line.chars().filter(|c| c.is_alphabetic()).collect();
+    letters.len() >= 6 && letters.iter().all(|c| c.is_uppercase()) && line.chars().count() <= 150
+}
+
+// (number, section, body lines) of the article being read
+type PendingArticle<'a> = (String, Option<String>, Vec<&'a str>);
+
+fn finish(article: Option<PendingArticle>, articles: &mut Vec<LawArticle>) {
+    if let Some((number, section, lines)) = article {
+        let body = lines.join("\n").trim().to_string();
+        if !body.is_empty() {
+            articles.push(LawArticle { number, section, body });
+        }
+    }
+}
+
+/// Split a cleaned law text (Latin or Cyrillic) into its articles, in order of appearance
+pub fn parse_law(content: &str) -> Vec<LawArticle> {
+    // Some laws are published in Cyrillic - match "Član N" against the Latin text
+    let content = crate::transliteration::to_latin(content);
+
+    let mut articles = Vec::new();
+    let mut section: Option<String> = None;
+    let mut previous_was_heading = false;
+    let mut current: Option<PendingArticle> = None;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+
+        if let Some(caps) = article_header().captures(line) {
+            finish(current.take(), &mut articles);
+            let rest = line[caps.get(0).unwrap().end()..].trim_start_matches('.').trim();
+            let lines = if rest.is_empty() { Vec::new() } else { vec![rest] };
+            current = Some((caps[1].to_string(), section.clone(), lines));
+            previous_was_heading = false;
+        } else if is_section_heading(trimmed) {
+            // Headings split over adjacent lines ("GLAVA II" / "RADNO VREME") are joined
+            section = match (previous_was_heading, section.take()) {
+                (true, Some(previous)) => Some(format!("{} - {}", previous, trimmed)),
+                _ => Some(trimmed.to_string()),
+            };
+            previous_was_heading = true;
+        } else {
+            previous_was_heading = false;
+            if let Some((_, _, lines)) = current.as_mut() {
+                lines.push(line);
+            }
+        }
+    }
+    finish(current.take(), &mut articles);
+
+    articles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_law_articles_and_sections() {
+        let content = "ZAKON O RADU\n\nI. OSNOVNE ODREDBE\n\nPredmet\n\nČlan 1.\nOvim zakonom uređuju se prava.\n\nČlan 2\nOdredbe ovog zakona primenjuju se na zaposlene.\n\nGLAVA II\nRADNO VREME\n\nČlan 2a\nPuno radno vreme iznosi 40 časova.\n\nČlan 3\n";
+        let articles = parse_law(content);
+
+        assert_eq!(articles.len(), 3); // Član 3 has no body
+        assert_eq!(articles[0].number, "1");
+        assert_eq!(articles[0].section.as_deref(), Some("I. OSNOVNE ODREDBE"));
+        assert_eq!(articles[0].body, "Ovim zakonom uređuju se prava.");
+        // The next chapter's heading doesn't end up in the previous article
+        assert_eq!(articles[1].body, "Odredbe ovog zakona primenjuju se na zaposlene.");
+        assert_eq!(articles[2].number, "2a");
+        assert_eq!(articles[2].section.as_deref(), Some("GLAVA II - RADNO VREME"));
+    }
+
+    #[test]
+    fn test_parse_law_cyrillic() {
+        let articles = parse_law("ОСНОВНЕ ОДРЕДБЕ\n\nЧлан 5.\nПослодавац је дужан да исплати зараду.");
+        assert_eq!(articles.len(), 1);
+        assert_eq!(articles[0].number, "5");
+        assert_eq!(articles[0].section.as_deref(), Some("OSNOVNE ODREDBE"));
+        assert_eq!(articles[0].body, "Poslodavac je dužan da isplati zaradu.");
+    }
+}
@@ -0,0 +1,313 @@
+use axum::{
+    extract::{Json, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+/// How long model configuration loaded from the database is reused before re-reading
+const CONFIG_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Which step of the question pipeline a model is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmPurpose {
+    Answer,
+    Classification,
+    LawDetection,
+}
+
+impl LlmPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LlmPurpose::Answer => "answer",
+            LlmPurpose::Classification => "classification",
+            LlmPurpose::LawDetection => "law_detection",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "answer" => Some(LlmPurpose::Answer),
+            "classification" => Some(LlmPurpose::Classification),
+            "law_detection" => Some(LlmPurpose::LawDetection),
+            _ => None,
+        }
+    }
+
+    /// Built-in default used when neither the database nor the environment configures a model
+    fn default_model(&self) -> &'static str {
+        match self {
+            LlmPurpose::Answer => "google/gemini-2.5-pro",
+            LlmPurpose::Classification => "google/gemini-2.5-flash", // Much cheaper for simple classification
+            LlmPurpose::LawDetection => "google/gemini-2.5-flash",
+        }
+    }
+
+    /// Built-in model tried when the primary one is rate limited or failing
+    fn default_fallback_model(&self) -> Option<&'static str> {
+        match self {
+            LlmPurpose::Answer => Some("google/gemini-2.5-flash"),
+            LlmPurpose::Classification | LlmPurpose::LawDetection => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct LlmModelConfig {
+    pub purpose: String,
+    pub account_type: Option<String>, // NULL = applies to all plans
+    pub model: String,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetLlmModelRequest {
+    pub purpose: String,
+    pub account_type: Option<String>,
+    pub model: Option<String>, // None removes the override
+}
+
+// (loaded_at, configs)
+type CachedConfigs = Option<(Instant, Vec<LlmModelConfig>)>;
+
+static CONFIG_CACHE: OnceLock<Mutex<CachedConfigs>> = OnceLock::new();
+
+fn config_cache() -> &'static Mutex<CachedConfigs> {
+    CONFIG_CACHE.get_or_init(|| Mutex::new(None))
+}
+
+fn invalidate_cache() {
+    *config_cache().lock().unwrap() = None;
+}
+
+async fn load_model_configs(pool: &PgPool) -> Vec<LlmModelConfig> {
+    if let Some((loaded_at, configs)) = config_cache().lock().unwrap().as_ref() {
+        if loaded_at.elapsed() < CONFIG_CACHE_TTL {
+            return configs.clone();
+        }
+    }
+
+    match sqlx::query_as::<_, LlmModelConfig>(
+        "SELECT purpose, account_type, model, updated_at FROM llm_model_config"
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(configs) => {
+            *config_cache().lock().unwrap() = Some((Instant::now(), configs.clone()));
+            configs
+        }
+        Err(e) => {
+            // Fall back to env/defaults rather than failing the question
+            eprintln!("⚠️  Failed to load LLM model config: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Resolve the model for a pipeline step and plan.
+///
+/// Lookup order (first match wins):
+/// 1. llm_model_config row for (purpose, account_type)
+/// 2. llm_model_config row for (purpose, any plan)
+/// 3. LLM_MODEL_<PURPOSE>_<ACCOUNT_TYPE> env var, e.g. LLM_MODEL_ANSWER_PROFESSIONAL
+/// 4. LLM_MODEL_<PURPOSE> env var, e.g. LLM_MODEL_CLASSIFICATION
+/// 5. Built-in default
+pub async fn resolve_model(purpose: LlmPurpose, account_type: Option<&str>, pool: &PgPool) -> String {
+    let configs = load_model_configs(pool).await;
+    resolve_model_from(purpose, account_type, &configs, |key| crate::config::get().llm.override_value(key))
+}
+
+fn resolve_model_from(
+    purpose: LlmPurpose,
+    account_type: Option<&str>,
+    configs: &[LlmModelConfig],
+    env: impl Fn(&str) -> Option<String>,
+) -> String {
+    let for_purpose = |plan: Option<&str>| {
+        configs
+            .iter()
+            .find(|c| c.purpose == purpose.as_str() && c.account_type.as_deref() == plan)
+            .map(|c| c.model.clone())
+    };
+
+    if let Some(model) = account_type.and_then(|plan| for_purpose(Some(plan))) {
+        return model;
+    }
+    if let Some(model) = for_purpose(None) {
+        return model;
+    }
+
+    let env_key = format!("LLM_MODEL_{}", purpose.as_str().to_uppercase());
+    if let Some(plan) = account_type {
+        if let Some(model) = env(&format!("{}_{}", env_key, plan.to_uppercase())).filter(|m| !m.is_empty()) {
+            return model;
+        }
+    }
+    if let Some(model) = env(&env_key).filter(|m| !m.is_empty()) {
+        return model;
+    }
+
+    purpose.default_model().to_string()
+}
+
+/// Secondary model for a pipeline step: LLM_MODEL_<PURPOSE>_FALLBACK, else the built-in one.
+/// Setting the variable to an empty string disables the fallback.
+pub fn resolve_fallback_model(purpose: LlmPurpose, primary: &str) -> Option<String> {
+    resolve_fallback_model_from(purpose, primary, |key| crate::config::get().llm.override_value(key))
+}
+
+fn resolve_fallback_model_from(
+    purpose: LlmPurpose,
+    primary: &str,
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let env_key = format!("LLM_MODEL_{}_FALLBACK", purpose.as_str().to_uppercase());
+    let fallback = match env(&env_key) {
+        Some(model) => model,
+        None => purpose.default_fallback_model()?.to_string(),
+    };
+    // Falling back to the model that just failed would only repeat the failure
+    (!fallback.is_empty() && fallback != primary).then_some(fallback)
+}
+
+/// Admin: list configured model overrides
+pub async fn list_llm_models_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ResponseJson<Vec<LlmModelConfig>>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(ResponseJson(list_model_configs(&pool).await?))
+}
+
+async fn list_model_configs(pool: &PgPool) -> Result<Vec<LlmModelConfig>, StatusCode> {
+    sqlx::query_as::<_, LlmModelConfig>(
+        "SELECT purpose, account_type, model, updated_at FROM llm_model_config ORDER BY purpose, account_type NULLS FIRST"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to list LLM model config: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Admin: set or remove the model for a pipeline step (optionally for a single plan)
+pub async fn set_llm_model_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<SetLlmModelRequest>,
+) -> Result<ResponseJson<Vec<LlmModelConfig>>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    if LlmPurpose::parse(&request.purpose).is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let result = match request.model.as_deref().map(str::trim) {
+        Some(model) if !model.is_empty() => {
+            sqlx::query(
+                "INSERT INTO llm_model_config (purpose, account_type, model)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (purpose, COALESCE(account_type, ''))
+                 DO UPDATE SET model = EXCLUDED.model, updated_at = NOW()"
+            )
+            .bind(&request.purpose)
+            .bind(&request.account_type)
+            .bind(model)
+            .execute(&pool)
+            .await
+        }
+        _ => {
+            sqlx::query(
+                "DELETE FROM llm_model_config WHERE purpose = $1 AND account_type IS NOT DISTINCT FROM $2"
+            )
+            .bind(&request.purpose)
+            .bind(&request.account_type)
+            .execute(&pool)
+            .await
+        }
+    };
+
+    result.map_err(|e| {
+        eprintln!("Failed to update LLM model config: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    println!(
+        "✅ LLM model config updated: purpose={}, account_type={:?}, model={:?}",
+        request.purpose, request.account_type, request.model
+    );
+    invalidate_cache();
+
+    Ok(ResponseJson(list_model_configs(&pool).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(purpose: &str, account_type: Option<&str>, model: &str) -> LlmModelConfig {
+        LlmModelConfig {
+            purpose: purpose.to_string(),
+            account_type: account_type.map(str::to_string),
+            model: model.to_string(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_model_precedence() {
+        let configs = vec![
+            config("answer", None, "db/all-plans"),
+            config("answer", Some("professional"), "db/professional"),
+        ];
+        let env = |key: &str| match key {
+            "LLM_MODEL_ANSWER_TEAM" => Some("env/team".to_string()),
+            "LLM_MODEL_CLASSIFICATION" => Some("env/classification".to_string()),
+            _ => None,
+        };
+
+        // Plan-specific DB row wins over everything
+        assert_eq!(resolve_model_from(LlmPurpose::Answer, Some("professional"), &configs, env), "db/professional");
+        // Generic DB row wins over env for other plans
+        assert_eq!(resolve_model_from(LlmPurpose::Answer, Some("team"), &configs, env), "db/all-plans");
+        // Env is used when the DB has nothing for the purpose
+        assert_eq!(resolve_model_from(LlmPurpose::Classification, Some("team"), &configs, env), "env/classification");
+        // Built-in default as last resort
+        assert_eq!(resolve_model_from(LlmPurpose::LawDetection, None, &configs, env), "google/gemini-2.5-flash");
+    }
+
+    #[test]
+    fn test_resolve_fallback_model() {
+        let none = |_: &str| None;
+        assert_eq!(resolve_fallback_model_from(LlmPurpose::Answer, "google/gemini-2.5-pro", none).as_deref(), Some("google/gemini-2.5-flash"));
+        // Never the primary model itself, and no built-in fallback for the cheap steps
+        assert_eq!(resolve_fallback_model_from(LlmPurpose::Answer, "google/gemini-2.5-flash", none), None);
+        assert_eq!(resolve_fallback_model_from(LlmPurpose::Classification, "google/gemini-2.5-flash", none), None);
+
+        let disabled = |key: &str| (key == "LLM_MODEL_ANSWER_FALLBACK").then(String::new);
+        assert_eq!(resolve_fallback_model_from(LlmPurpose::Answer, "google/gemini-2.5-pro", disabled), None);
+    }
+
+    #[test]
+    fn test_resolve_model_env_per_plan() {
+        let env = |key: &str| match key {
+            "LLM_MODEL_ANSWER_TEAM" => Some("env/team".to_string()),
+            _ => None,
+        };
+
+        assert_eq!(resolve_model_from(LlmPurpose::Answer, Some("team"), &[], env), "env/team");
+        assert_eq!(resolve_model_from(LlmPurpose::Answer, Some("individual"), &[], env), "google/gemini-2.5-pro");
+    }
+}
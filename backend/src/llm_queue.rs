@@ -0,0 +1,245 @@
+use axum::{
+    extract::Query,
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use uuid::Uuid;
+
+/// Initial guess for how long one question takes, until we have real measurements
+const INITIAL_AVG_SERVICE_MS: f64 = 15_000.0;
+
+/// Weight of the newest sample in the moving average of service time
+const SERVICE_TIME_SMOOTHING: f64 = 0.2;
+
+/// Concurrency limiter for LLM-backed question processing.
+///
+/// Requests beyond the limit wait in FIFO order (tokio's Semaphore is fair);
+/// the queue keeps track of waiting tickets so clients can poll their position.
+pub struct LlmQueue {
+    semaphore: Semaphore,
+    max_concurrent: usize,
+    waiting: Mutex<VecDeque<String>>,
+    processing: Mutex<HashSet<String>>,
+    avg_service_ms: Mutex<f64>,
+}
+
+/// A held processing slot - releases the permit and records service time on drop
+pub struct QueueSlot {
+    _permit: SemaphorePermit<'static>,
+    queue: &'static LlmQueue,
+    pub ticket: String,
+    pub queue_position: usize,
+    pub in_flight_at_enqueue: usize,
+    pub wait_ms: u128,
+    admitted_at: Instant,
+}
+
+/// Removes a ticket from the waiting list if the request is cancelled while queued
+struct WaitingGuard<'a> {
+    queue: &'a LlmQueue,
+    ticket: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QueueStatusQuery {
+    pub ticket: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueueStatusResponse {
+    pub status: String, // 'queued', 'processing', 'unknown' (or 'idle' when no ticket given)
+    pub position: Option<usize>,
+    pub queue_length: usize,
+    pub in_flight: usize,
+    pub max_concurrent: usize,
+    pub estimated_wait_seconds: u64,
+}
+
+static LLM_QUEUE: OnceLock<LlmQueue> = OnceLock::new();
+
+/// Get the process-wide LLM queue
+pub fn global() -> &'static LlmQueue {
+    LLM_QUEUE.get_or_init(|| {
+        LlmQueue::new(crate::config::get().llm.max_concurrent)
+    })
+}
+
+impl LlmQueue {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            semaphore: Semaphore::new(max_concurrent),
+            max_concurrent,
+            waiting: Mutex::new(VecDeque::new()),
+            processing: Mutex::new(HashSet::new()),
+            avg_service_ms: Mutex::new(INITIAL_AVG_SERVICE_MS),
+        }
+    }
+
+    /// Wait for a processing slot. `ticket` is the client-supplied request ID used for polling.
+    pub async fn acquire(&'static self, ticket: Option<String>) -> QueueSlot {
+        let ticket = ticket
+            .filter(|t| !t.is_empty() && t.len() <= 100)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let enqueued_at = Instant::now();
+        let in_flight_at_enqueue = self.in_flight();
+
+        let queue_position = {
+            let mut waiting = self.waiting.lock().unwrap();
+            waiting.push_back(ticket.clone());
+            waiting.len()
+        };
+
+        let permit = {
+            let _guard = WaitingGuard { queue: self, ticket: &ticket };
+            self.semaphore
+                .acquire()
+                .await
+                .expect("LLM queue semaphore is never closed")
+        };
+
+        self.processing.lock().unwrap().insert(ticket.clone());
+
+        let wait_ms = enqueued_at.elapsed().as_millis();
+        if wait_ms > 1000 {
+            println!("⏳ LLM QUEUE: Ticket {} waited {} ms (position {})", ticket, wait_ms, queue_position);
+        }
+
+        QueueSlot {
+            _permit: permit,
+            queue: self,
+            ticket,
+            queue_position,
+            in_flight_at_enqueue,
+            wait_ms,
+            admitted_at: Instant::now(),
+        }
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.max_concurrent - self.semaphore.available_permits()
+    }
+
+    pub fn queue_length(&self) -> usize {
+        self.waiting.lock().unwrap().len()
+    }
+
+    /// Estimated wait for the request at `position` in the queue (1-based)
+    pub fn estimated_wait_ms(&self, position: usize) -> u64 {
+        let avg_service_ms = *self.avg_service_ms.lock().unwrap();
+        estimate_wait_ms(position, self.in_flight(), self.max_concurrent, avg_service_ms)
+    }
+
+    pub fn status(&self, ticket: Option<&str>) -> QueueStatusResponse {
+        let queue_length = self.queue_length();
+        let in_flight = self.in_flight();
+
+        let (status, position) = match ticket {
+            Some(ticket) => {
+                let position = self
+                    .waiting
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .position(|t| t == ticket)
+                    .map(|idx| idx + 1);
+
+                match position {
+                    Some(position) => ("queued", Some(position)),
+                    None if self.processing.lock().unwrap().contains(ticket) => ("processing", None),
+                    None => ("unknown", None),
+                }
+            }
+            None => ("idle", None),
+        };
+
+        // Without a ticket, report the wait a new request would see
+        let wait_position = match (status, position) {
+            ("queued", Some(position)) => position,
+            ("idle", _) => queue_length + 1,
+            _ => 0,
+        };
+        let estimated_wait_ms = if wait_position == 0 { 0 } else { self.estimated_wait_ms(wait_position) };
+
+        QueueStatusResponse {
+            status: status.to_string(),
+            position,
+            queue_length,
+            in_flight,
+            max_concurrent: self.max_concurrent,
+            estimated_wait_seconds: estimated_wait_ms.div_ceil(1000),
+        }
+    }
+
+    fn record_service_time(&self, service_ms: f64) {
+        let mut avg = self.avg_service_ms.lock().unwrap();
+        *avg = *avg * (1.0 - SERVICE_TIME_SMOOTHING) + service_ms * SERVICE_TIME_SMOOTHING;
+    }
+}
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        let mut waiting = self.queue.waiting.lock().unwrap();
+        if let Some(idx) = waiting.iter().position(|t| t == self.ticket) {
+            waiting.remove(idx);
+        }
+    }
+}
+
+impl Drop for QueueSlot {
+    fn drop(&mut self) {
+        self.queue.processing.lock().unwrap().remove(&self.ticket);
+        self.queue
+            .record_service_time(self.admitted_at.elapsed().as_millis() as f64);
+    }
+}
+
+/// Estimate the wait for a request at `position` (1-based) given current load.
+/// Free slots are used first; after that each "round" of max_concurrent requests takes one
+/// average service time.
+pub fn estimate_wait_ms(position: usize, in_flight: usize, max_concurrent: usize, avg_service_ms: f64) -> u64 {
+    if max_concurrent == 0 {
+        return 0;
+    }
+
+    let free_slots = max_concurrent.saturating_sub(in_flight);
+    if position <= free_slots {
+        return 0;
+    }
+
+    let rounds = (position - free_slots).div_ceil(max_concurrent);
+    (rounds as f64 * avg_service_ms) as u64
+}
+
+/// Persist queue metrics for a processed request so concurrency limits can be tuned
+pub async fn record_queue_metrics(
+    slot: &QueueSlot,
+    user_id: Option<Uuid>,
+    pool: &PgPool,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO llm_queue_metrics (user_id, queue_position, in_flight, max_concurrent, wait_ms)
+         VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(user_id)
+    .bind(slot.queue_position as i32)
+    .bind(slot.in_flight_at_enqueue as i32)
+    .bind(slot.queue.max_concurrent as i32)
+    .bind(slot.wait_ms.min(i64::MAX as u128) as i64)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record queue metrics: {}", e))?;
+
+    Ok(())
+}
+
+/// Poll queue position and ETA for a question (ticket = client_request_id sent with the question)
+pub async fn queue_status_handler(
+    Query(query): Query<QueueStatusQuery>,
+) -> ResponseJson<QueueStatusResponse> {
+    ResponseJson(global().status(query.ticket.as_deref()))
+}
@@ -0,0 +1,172 @@
+// Backend tools the answer model can call mid-generation (OpenAI-style function calling via OpenRouter).
+// api::call_openrouter_api advertises these definitions, runs the requested calls through dispatch()
+// and sends the results back until the model produces its final answer.
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::time::Duration;
+
+/// Tool calling rounds before the model is asked to answer without tools
+pub const MAX_TOOL_ROUNDS: usize = 4;
+
+/// Tool output sent back to the model is cut to this many characters
+const MAX_TOOL_RESULT_CHARS: usize = 8000;
+
+// Per-tool timeouts - a slow tool returns an error result instead of stalling the answer
+const FETCH_ARTICLE_TIMEOUT: Duration = Duration::from_secs(20); // May scrape the law on a cache miss
+const DEADLINE_TIMEOUT: Duration = Duration::from_secs(2);
+const COURT_FEE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Tool calling is on unless LLM_TOOLS=false (for models without function calling support)
+pub fn enabled() -> bool {
+    crate::config::get().llm.tools_enabled
+}
+
+#[derive(Debug, Deserialize)]
+struct FetchArticleArgs {
+    law_name: String,
+    article_number: String,
+}
+
+/// Tool definitions in the OpenAI `tools` format
+pub fn definitions() -> Vec<Value> {
+    let procedures: Vec<&str> = crate::deadlines::PROCEDURES.iter().map(|p| p.key).collect();
+    let fee_procedures: Vec<&str> = crate::fees::PROCEDURES.iter().map(|p| p.key).collect();
+
+    vec![
+        json!({
+            "type": "function",
+            "function": {
+                "name": "fetch_article",
+                "description": "Vraća tačan tekst jednog člana srpskog zakona iz baze propisa. Koristi pre citiranja člana čiji tekst ne znaš pouzdano.",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "law_name": { "type": "string", "description": "Pun naziv zakona, npr. \"Zakon o radu\"" },
+                        "article_number": { "type": "string", "description": "Broj člana, npr. \"179\" ili \"12a\"" }
+                    },
+                    "required": ["law_name", "article_number"]
+                }
+            }
+        }),
+        json!({
+            "type": "function",
+            "function": {
+                "name": "deadline_calculator",
+                "description": "Računa procesni rok ili rok zastarelosti po srpskim pravilima (dan događaja se ne računa, neradni dani i praznici, sudski odmor). Koristi uvek kada treba navesti datum isteka roka.",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "event_date": { "type": "string", "description": "Datum događaja (dostavljanje, dospelost, saznanje) u formatu YYYY-MM-DD" },
+                        "procedure": { "type": "string", "enum": procedures, "description": "Vrsta roka; izostavi za proizvoljan rok" },
+                        "amount": { "type": "integer", "description": "Trajanje proizvoljnog roka" },
+                        "unit": { "type": "string", "enum": ["days", "months", "years"] },
+                        "suspend_during_court_vacation": { "type": "boolean" }
+                    },
+                    "required": ["event_date"]
+                }
+            }
+        }),
+        json!({
+            "type": "function",
+            "function": {
+                "name": "court_fee_calculator",
+                "description": "Računa sudske takse i republičke administrativne takse za postupak i vrednost predmeta spora, po stavkama. Koristi kada korisnik pita koliko košta postupak ili taksa.",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "procedure": { "type": "string", "enum": fee_procedures },
+                        "claim_value": { "type": "number", "description": "Vrednost predmeta spora u dinarima" }
+                    },
+                    "required": ["procedure"]
+                }
+            }
+        }),
+    ]
+}
+
+/// Run one tool call and return its result as text for the model (errors are results too)
+pub async fn dispatch(name: &str, arguments: &str, pool: &PgPool) -> String {
+    let started_at = std::time::Instant::now();
+    let result = match name {
+        "fetch_article" => with_timeout(FETCH_ARTICLE_TIMEOUT, fetch_article(arguments, pool)).await,
+        "deadline_calculator" => with_timeout(DEADLINE_TIMEOUT, async { deadline_calculator(arguments) }).await,
+        "court_fee_calculator" => with_timeout(COURT_FEE_TIMEOUT, court_fee_calculator(arguments, pool)).await,
+        _ => Err(format!("Nepoznat alat: {}", name)),
+    };
+
+    println!(
+        "🛠️  Tool {} finished in {}ms ({})",
+        name,
+        started_at.elapsed().as_millis(),
+        if result.is_ok() { "ok" } else { "error" }
+    );
+
+    let output = match result {
+        Ok(output) => output,
+        Err(e) => json!({ "error": e }).to_string(),
+    };
+    truncate_chars(output, MAX_TOOL_RESULT_CHARS)
+}
+
+async fn with_timeout(
+    timeout: Duration,
+    future: impl std::future::Future<Output = Result<String, String>>,
+) -> Result<String, String> {
+    tokio::time::timeout(timeout, future)
+        .await
+        .unwrap_or_else(|_| Err(format!("Alat nije odgovorio u roku od {} s", timeout.as_secs())))
+}
+
+fn truncate_chars(text: String, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+async fn fetch_article(arguments: &str, pool: &PgPool) -> Result<String, String> {
+    let args: FetchArticleArgs = serde_json::from_str(arguments).map_err(|e| format!("Neispravni argumenti: {}", e))?;
+
+    match crate::api::get_cached_article(&args.law_name, &args.article_number, pool).await? {
+        Some((content, law_name)) => Ok(json!({ "law_name": law_name, "article": content }).to_string()),
+        None => Err(format!("Član {} nije pronađen u zakonu \"{}\"", args.article_number, args.law_name)),
+    }
+}
+
+fn deadline_calculator(arguments: &str) -> Result<String, String> {
+    let request: crate::deadlines::DeadlineRequest =
+        serde_json::from_str(arguments).map_err(|e| format!("Neispravni argumenti: {}", e))?;
+
+    let today = crate::calendar::local_today(chrono::Utc::now());
+    let breakdown = crate::deadlines::compute_deadline(&request, today)?;
+    serde_json::to_string(&breakdown).map_err(|e| format!("Failed to serialize deadline: {}", e))
+}
+
+async fn court_fee_calculator(arguments: &str, pool: &PgPool) -> Result<String, String> {
+    let request: crate::fees::CourtFeeRequest =
+        serde_json::from_str(arguments).map_err(|e| format!("Neispravni argumenti: {}", e))?;
+
+    let breakdown = crate::fees::compute_fees(&request, pool).await.map_err(|e| e.message().to_string())?;
+    serde_json::to_string(&breakdown).map_err(|e| format!("Failed to serialize fees: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadline_tool_returns_breakdown_or_error() {
+        let output = deadline_calculator(r#"{"event_date": "2025-04-16", "procedure": "zalba_parnica"}"#).unwrap();
+        let value: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["deadline"], "2025-05-05");
+
+        assert!(deadline_calculator(r#"{"event_date": "16.4.2025."}"#).is_err());
+    }
+
+    #[test]
+    fn test_truncate_chars_respects_char_boundaries() {
+        assert_eq!(truncate_chars("član".to_string(), 2), "čl…");
+        assert_eq!(truncate_chars("član".to_string(), 10), "član");
+    }
+}
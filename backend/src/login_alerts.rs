@@ -0,0 +1,250 @@
+// Sign-in alerts for unknown devices. Every device (X-Device-Session-Id) an account signs in from
+// is remembered in known_devices; the first sign-in from a new one sends an e-mail and an in-app
+// event with a one-click link that revokes just that session. The link carries a signed,
+// short-lived token, so it works from the e-mail without the user being signed in.
+use crate::audit_log::{AuditAction, AuditSource};
+use crate::events::{self, ChatEvent};
+use crate::models::ErrorResponse;
+use crate::sessions::DeviceInfo;
+use crate::simple_auth::AuthAppState;
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const REVOKE_TOKEN_PURPOSE: &str = "revoke_session";
+const REVOKE_TOKEN_TTL_DAYS: i64 = 7;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RevokeClaims {
+    sub: String, // Session to revoke
+    uid: String, // Its owner
+    purpose: String,
+    exp: usize,
+    iat: usize,
+}
+
+fn create_revoke_token(session_id: Uuid, user_id: Uuid, jwt_secret: &str) -> Result<String, String> {
+    let now = chrono::Utc::now();
+    let claims = RevokeClaims {
+        sub: session_id.to_string(),
+        uid: user_id.to_string(),
+        purpose: REVOKE_TOKEN_PURPOSE.to_string(),
+        exp: (now + chrono::Duration::days(REVOKE_TOKEN_TTL_DAYS)).timestamp() as usize,
+        iat: now.timestamp() as usize,
+    };
+
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(jwt_secret.as_ref()))
+        .map_err(|e| format!("Revoke token generation failed: {}", e))
+}
+
+/// (session_id, user_id) from a revoke link token; None when forged, expired or another kind of token
+fn verify_revoke_token(token: &str, jwt_secret: &str) -> Option<(Uuid, Uuid)> {
+    let claims = decode::<RevokeClaims>(token, &DecodingKey::from_secret(jwt_secret.as_ref()), &Validation::default())
+        .ok()?
+        .claims;
+    if claims.purpose != REVOKE_TOKEN_PURPOSE {
+        return None;
+    }
+    Some((Uuid::parse_str(&claims.sub).ok()?, Uuid::parse_str(&claims.uid).ok()?))
+}
+
+/// Remember the device and report whether the sign-in warrants an alert: the device is new to the
+/// account and the account already had other devices (an account's very first device isn't news).
+/// Devices with sessions from before known_devices existed count as known.
+pub async fn register_device(pool: &PgPool, user_id: Uuid, device: &DeviceInfo) -> Result<bool, sqlx::Error> {
+    let Some(device_session_id) = device.session_id.as_deref() else {
+        return Ok(false);
+    };
+
+    let (already_known, has_other_devices): (bool, bool) = sqlx::query_as(
+        "SELECT
+            EXISTS(SELECT 1 FROM known_devices WHERE user_id = $1 AND device_session_id = $2)
+              OR EXISTS(SELECT 1 FROM user_sessions WHERE user_id = $1 AND device_info->>'session_id' = $2),
+            EXISTS(SELECT 1 FROM known_devices WHERE user_id = $1)
+              OR EXISTS(SELECT 1 FROM user_sessions WHERE user_id = $1)",
+    )
+    .bind(user_id)
+    .bind(device_session_id)
+    .fetch_one(pool)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO known_devices (user_id, device_session_id, device_name, os)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (user_id, device_session_id)
+         DO UPDATE SET device_name = EXCLUDED.device_name, os = EXCLUDED.os, last_seen_at = NOW()",
+    )
+    .bind(user_id)
+    .bind(device_session_id)
+    .bind(&device.name)
+    .bind(&device.os)
+    .execute(pool)
+    .await?;
+
+    Ok(!already_known && has_other_devices)
+}
+
+/// Human-readable device description for the alert ("Web Browser (Chrome, Windows)")
+fn describe_device(device: &DeviceInfo) -> String {
+    let name = device.name.as_deref().unwrap_or("Nepoznat uređaj");
+    let details: Vec<&str> = [device.browser.as_deref(), device.os.as_deref()].into_iter().flatten().collect();
+    if details.is_empty() {
+        name.to_string()
+    } else {
+        format!("{} ({})", name, details.join(", "))
+    }
+}
+
+/// E-mail and in-app alert about a sign-in from a new device, on the channels the user keeps on.
+/// Failures are logged only.
+#[allow(clippy::too_many_arguments)]
+pub async fn send_new_device_alert(
+    pool: &PgPool,
+    resend_api_key: &str,
+    jwt_secret: &str,
+    email: &str,
+    user_id: Uuid,
+    session_id: Uuid,
+    device: &DeviceInfo,
+    ip_address: Option<std::net::IpAddr>,
+) {
+    let device_description = describe_device(device);
+    let ip_address = ip_address.map(|ip| ip.to_string());
+
+    events::emit(
+        user_id,
+        ChatEvent::NewDeviceLogin {
+            session_id,
+            device: device_description.clone(),
+            ip_address: ip_address.clone(),
+        },
+    );
+
+    let channels = crate::notifications::notify(
+        pool,
+        user_id,
+        crate::notifications::NotificationKind::NewDevice,
+        "Prijava sa novog uređaja",
+        Some(&format!("{} · IP adresa: {}", device_description, ip_address.as_deref().unwrap_or("nepoznata"))),
+        Some(serde_json::json!({ "session_id": session_id })),
+    )
+    .await;
+    if !channels.email {
+        return;
+    }
+
+    let token = match create_revoke_token(session_id, user_id, jwt_secret) {
+        Ok(token) => token,
+        Err(e) => {
+            eprintln!("⚠️  {}", e);
+            return;
+        }
+    };
+    if let Err(e) = crate::email_service::send_new_device_email(
+        resend_api_key,
+        email,
+        &device_description,
+        ip_address.as_deref().unwrap_or("nepoznata"),
+        &token,
+    )
+    .await
+    {
+        eprintln!("❌ Failed to send new device email: {:?}", e);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevokeLinkRequest {
+    pub token: String,
+}
+
+/// POST /api/auth/sessions/revoke-link - revoke the session named in a new-device alert link
+pub async fn revoke_link_handler(
+    State((pool, _, jwt_secret, _, _, _)): State<AuthAppState>,
+    headers: HeaderMap,
+    Json(request): Json<RevokeLinkRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let (session_id, user_id) = verify_revoke_token(&request.token, &jwt_secret).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "INVALID_TOKEN".to_string(),
+                message: "Link je neispravan ili je istekao".to_string(),
+                details: None,
+            }),
+        )
+    })?;
+
+    let revoked = crate::sessions::revoke_session(&pool, session_id, user_id).await.map_err(|e| {
+        eprintln!("Failed to revoke session from alert link: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "DATABASE_ERROR".to_string(),
+                message: "Greška revokacije sesije".to_string(),
+                details: None,
+            }),
+        )
+    })?;
+
+    // Clicking the link twice is fine - the session is gone either way
+    if revoked {
+        crate::audit_log::record(
+            &pool,
+            user_id,
+            AuditAction::SessionRevoked,
+            AuditSource::Request(&headers),
+            serde_json::json!({ "session_id": session_id, "via": "new_device_alert" }),
+        )
+        .await;
+    }
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": if revoked {
+            "Uređaj je odjavljen. Preporučujemo da promenite lozinku."
+        } else {
+            "Ovaj uređaj je već odjavljen."
+        }
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_revoke_token_round_trip() {
+        let session_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+        let token = create_revoke_token(session_id, user_id, "secret").unwrap();
+
+        assert_eq!(verify_revoke_token(&token, "secret"), Some((session_id, user_id)));
+        assert_eq!(verify_revoke_token(&token, "other-secret"), None);
+
+        // A regular access token signed with the same secret doesn't revoke anything
+        let access_token = crate::simple_auth::generate_token(user_id, "ana@example.com", "secret").unwrap();
+        assert_eq!(verify_revoke_token(&access_token, "secret"), None);
+    }
+
+    #[test]
+    fn test_describe_device() {
+        let device = DeviceInfo {
+            session_id: None,
+            name: Some("Web Browser".to_string()),
+            os: Some("Windows".to_string()),
+            browser: Some("Chrome".to_string()),
+            app_version: None,
+        };
+        assert_eq!(describe_device(&device), "Web Browser (Chrome, Windows)");
+
+        let unknown = DeviceInfo { session_id: None, name: None, os: None, browser: None, app_version: None };
+        assert_eq!(describe_device(&unknown), "Nepoznat uređaj");
+    }
+}
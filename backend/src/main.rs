@@ -11,18 +11,87 @@ mod sessions;
 mod email_service;
 mod revenuecat;
 mod webhooks;
+mod admin;
+mod llm_queue;
+mod llm_config;
+mod documents;
+mod events;
+mod profile;
+mod law_revalidation;
+mod law_preload;
+mod law_sources;
+mod conflicts;
+mod consent;
+mod document_requests;
+mod answer_cache;
+mod citation_audit;
+mod retention;
+mod retrieval;
+mod feedback_analytics;
+mod free_question;
+mod guest;
+mod chat_migration;
+mod account_linking;
+mod impersonation;
+mod moderation;
+mod transliteration;
+mod refresh_tokens;
+mod calendar;
+mod data_export;
+mod totp;
+mod chat_sharing;
+mod transcription;
+mod speech;
+mod shutdown;
+mod metrics;
+mod health;
+mod i18n;
+mod folders;
+mod prompt_profiles;
+mod law_browser;
+mod law_subscriptions;
+mod deadlines;
+mod llm_tools;
+mod fees;
+mod openrouter_resilience;
+mod question_timeout;
+mod jurisdictions;
+mod contract_review;
+mod cost_caps;
+mod trial_abuse;
+mod audit_log;
+mod login_alerts;
+mod idempotency;
+mod http_cache;
+mod migrations;
+mod db_routing;
+mod request_id;
+mod cors_config;
+mod config;
+mod secret_box;
+mod notifications;
+mod invoices;
+mod pricing;
+mod dunning;
+mod promo_codes;
+mod referrals;
+mod payments;
+mod pii;
+mod openapi;
+mod subscriptions;
+mod message_packs;
 
 use axum::{
-    routing::{get, post, put, delete},
+    routing::{get, post, put, patch, delete},
     Router,
     extract::DefaultBodyLimit,
-    http::{Method, HeaderValue},
+    http::Method,
 };
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 use sqlx::postgres::PgPoolOptions;
-use std::{env, sync::Arc};
-use tracing_subscriber;
+use std::{future::IntoFuture, sync::Arc};
 
 async fn health_check() -> &'static str {
     "OK"
@@ -32,25 +101,36 @@ async fn health_check() -> &'static str {
 #[tokio::main]
 async fn main() {
     // Initialize tracing
-    tracing_subscriber::fmt::init();
-
-    // Get environment variables
-    let database_url = env::var("DATABASE_URL")
-        .expect("DATABASE_URL environment variable must be set");
-    let openrouter_api_key = env::var("OPENROUTER_API_KEY")
-        .expect("OPENROUTER_API_KEY environment variable must be set");
-    let openai_api_key = env::var("OPENAI_API_KEY")
-        .expect("OPENAI_API_KEY environment variable must be set");
-    let jwt_secret = env::var("JWT_SECRET")
-        .unwrap_or_else(|_| "default-jwt-secret-key-change-in-production".to_string());
+    // RUST_LOG overrides; info by default so request events show up in production logs
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .init();
+    metrics::init();
+
+    // Load and validate configuration - every missing/invalid setting is reported at once
+    let config = config::init(config::Config::from_env().unwrap_or_else(|problems| {
+        eprintln!("❌ Invalid configuration:");
+        for problem in &problems {
+            eprintln!("   - {}", problem);
+        }
+        std::process::exit(1);
+    }));
+    for warning in config.warnings() {
+        println!("⚠️  {}", warning);
+    }
+    let openrouter_api_key = config.openrouter_api_key.clone();
+    let openai_api_key = config.openai_api_key.clone();
+    let jwt_secret = config.jwt_secret.clone();
 
     // Supabase configuration (optional - for social login and unified auth)
-    let supabase_url = env::var("SUPABASE_URL").ok();
-    let supabase_jwt_secret = env::var("SUPABASE_JWT_SECRET").ok();
+    let supabase_url = config.supabase_url.clone();
+    let supabase_jwt_secret = config.supabase_jwt_secret.clone();
 
     // Resend API key for email service
-    let resend_api_key = env::var("RESEND_API_KEY")
-        .expect("RESEND_API_KEY environment variable must be set");
+    let resend_api_key = config.resend_api_key.clone();
 
     // Connect to database with optimized pool settings for Fly.io auto-suspension
     // IMPORTANT: Use Supabase's Transaction pooler (port 6543) for auto-suspend compatibility
@@ -61,14 +141,37 @@ async fn main() {
         .max_lifetime(std::time::Duration::from_secs(5 * 60))   // Recycle connections every 5 min
         .idle_timeout(Some(std::time::Duration::from_secs(2 * 60))) // Close idle after 2 min (before suspend)
         .test_before_acquire(true)                              // Health check before reusing
-        .connect(&database_url)
+        .connect(&config.database_url)
         .await
         .expect("Failed to connect to database");
 
     // Run migrations
-    database::run_migrations(&pool).await
+    migrations::run(&pool).await
         .expect("Failed to run migrations");
 
+    if let Err(e) = pii::check_key_configured(&pool).await {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
+
+    // Reset/verification tokens from before they were stored encrypted
+    match models::AuthenticationToken::protect_legacy_tokens(&pool).await {
+        Ok(count) if count > 0 => println!("🔐 Encrypted {} plain-text authentication token(s)", count),
+        Ok(_) => {}
+        Err(e) => println!("⚠️  {}", e),
+    }
+
+    // Optional read replica for heavy analytics/report queries (see db_routing.rs)
+    if db_routing::init_replica(config.database_replica_url.as_deref()) {
+        println!("📚 Read replica configured for analytics queries");
+    }
+
+    match law_preload::mark_interrupted_jobs(&pool).await {
+        Ok(count) if count > 0 => println!("⚠️  Marked {} interrupted law preload job(s)", count),
+        Ok(_) => {}
+        Err(e) => println!("⚠️  {}", e),
+    }
+
     // Law cache is now on-demand - no need for startup preloading
     // Laws are cached for 24 hours when users ask about them
     println!("✅ Server ready - laws will be cached on-demand as users ask about them");
@@ -80,34 +183,79 @@ async fn main() {
         Err(e) => println!("⚠️  Contract cleanup warning: {}", e),
     }
 
+    // Cancelled on SIGTERM/SIGINT - background jobs stop at their next tick
+    let shutdown = shutdown::token();
+    tokio::spawn(shutdown::listen_for_signals(shutdown.clone()));
+    let mut background_jobs = Vec::new();
+
     // Start background cleanup job for deleted users (30-day grace period)
     let cleanup_pool = Arc::new(pool.clone());
-    tokio::spawn(async move {
-        cleanup::start_cleanup_job(cleanup_pool).await;
-    });
+    background_jobs.push(("Cleanup job", tokio::spawn(cleanup::start_cleanup_job(cleanup_pool, shutdown.clone()))));
     println!("🗑️  Started user deletion cleanup job (runs daily)");
 
-    // Configure CORS - allow requests from web app, Tauri desktop, and mobile apps
+    // Start background job embedding cached law articles for semantic retrieval
+    let embedding_pool = Arc::new(pool.clone());
+    let embedding_api_key = openai_api_key.clone();
+    background_jobs.push((
+        "Embedding job",
+        tokio::spawn(retrieval::start_embedding_job(embedding_pool, embedding_api_key, shutdown.clone())),
+    ));
+    println!("🧭 Started law article embedding job (runs every 10 minutes)");
+
+    // Start background retry job for failed RevenueCat webhook events
+    let webhook_pool = Arc::new(pool.clone());
+    let webhook_api_key = openrouter_api_key.clone();
+    background_jobs.push((
+        "Webhook retry job",
+        tokio::spawn(webhooks::start_webhook_retry_job(webhook_pool, webhook_api_key, shutdown.clone())),
+    ));
+    println!("🔁 Started webhook retry job (runs every minute)");
+
+    // Start background job that re-checks cached laws against their source for amendments
+    let law_pool = Arc::new(pool.clone());
+    background_jobs.push((
+        "Law revalidation job",
+        tokio::spawn(law_revalidation::start_law_revalidation_job(law_pool, shutdown.clone())),
+    ));
+    println!("📜 Started law revalidation job (checks sources every 15 minutes)");
+
+    // Start background job that caches subscribed laws so revalidation watches them for amendments
+    let subscription_pool = Arc::new(pool.clone());
+    background_jobs.push((
+        "Law subscription job",
+        tokio::spawn(law_subscriptions::start_law_subscription_job(subscription_pool, shutdown.clone())),
+    ));
+    println!("🔔 Started law subscription job (runs every 30 minutes)");
+
+    // Start background job ending payment grace periods for renewals that stayed unpaid
+    let dunning_pool = Arc::new(pool.clone());
+    background_jobs.push(("Dunning job", tokio::spawn(dunning::start_dunning_job(dunning_pool, shutdown.clone()))));
+    println!("💳 Started dunning job (runs hourly)");
+
+    // Start background health check of the read replica (no-op without DATABASE_REPLICA_URL)
+    background_jobs.push(("Replica health job", tokio::spawn(db_routing::start_replica_health_job(shutdown.clone()))));
+
+    // Configure CORS - allowed origins come from CORS_ALLOWED_ORIGINS / CORS_ORIGINS_FILE (see cors_config.rs)
     // Note: When using allow_credentials(true), we CANNOT use Any for headers
     // We must specify allowed headers explicitly (CORS security requirement)
+    let cors_config = &config.cors;
+    println!("🌐 CORS origins: {} (credentials: {})", cors_config.describe(), cors_config.allow_credentials);
     let cors = CorsLayer::new()
-        .allow_origin([
-            "http://localhost:1420".parse::<HeaderValue>().unwrap(), // Tauri dev
-            "https://tauri.localhost".parse::<HeaderValue>().unwrap(), // Tauri production (HTTPS)
-            "http://tauri.localhost".parse::<HeaderValue>().unwrap(), // Tauri production (HTTP - Android/iOS)
-            "tauri://localhost".parse::<HeaderValue>().unwrap(), // Tauri custom protocol
-            "https://chat.normaai.rs".parse::<HeaderValue>().unwrap(), // Production web
-            "http://localhost:5173".parse::<HeaderValue>().unwrap(), // Vite dev
-            "http://localhost:3000".parse::<HeaderValue>().unwrap(), // Alternative dev port
-        ])
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
+        .allow_origin(cors_config.allow_origin())
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE, Method::OPTIONS])
         .allow_headers([
             axum::http::header::CONTENT_TYPE,
             axum::http::header::AUTHORIZATION,
             axum::http::header::ACCEPT,
             axum::http::header::HeaderName::from_static("x-device-session-id"), // Custom header for session deduplication
+            axum::http::header::HeaderName::from_static("x-admin-key"), // Admin endpoints
+            axum::http::header::HeaderName::from_static("idempotency-key"), // Safe retries of question submission
+            axum::http::header::IF_NONE_MATCH, // Revalidating cached law texts
+            axum::http::header::RANGE, // Seeking in cached answer audio
+            request_id::REQUEST_ID_HEADER, // Client-supplied correlation id
         ])
-        .allow_credentials(true); // Required for Authorization header support
+        .expose_headers([axum::http::header::ETAG, axum::http::header::CONTENT_RANGE, request_id::REQUEST_ID_HEADER])
+        .allow_credentials(cors_config.allow_credentials); // Required for Authorization header support
 
     // Complete auth and subscription routes
     let auth_routes = Router::new()
@@ -121,15 +269,43 @@ async fn main() {
         .route("/api/auth/verify-email", post(simple_auth::verify_email_handler))
         .route("/api/auth/logout", post(simple_auth::logout_handler))
         .route("/api/auth/user-status", get(simple_auth::user_status_handler))
+        .route("/api/profile", get(profile::get_profile_handler))
+        .route("/api/profile", patch(profile::update_profile_handler))
+        .route("/api/auth/profile", get(profile::get_profile_handler))
+        .route("/api/auth/profile", put(profile::update_profile_handler))
+        .route("/api/auth/profile/avatar", post(profile::upload_avatar_handler))
+        .route("/api/auth/profile/avatar", delete(profile::delete_avatar_handler))
+        // Terms of use / disclaimer acceptance (required before asking questions)
+        .route("/api/consent/status", get(consent::consent_status_handler))
+        .route("/api/consent/accept", post(consent::accept_consent_handler))
+        .route("/api/avatars/:user_id", get(profile::get_avatar_handler))
+        // Billing details and invoices for card subscriptions
+        .route("/api/billing/profile", get(invoices::get_billing_profile_handler))
+        .route("/api/billing/profile", put(invoices::update_billing_profile_handler))
+        .route("/api/billing/invoices", get(invoices::list_invoices_handler))
+        .route("/api/billing/invoices/:invoice_id/pdf", get(invoices::download_invoice_handler))
         // Session management endpoints
         .route("/api/auth/sessions", get(simple_auth::get_sessions_handler))
         .route("/api/auth/sessions/revoke", post(simple_auth::revoke_session_handler))
         .route("/api/auth/sessions/revoke-all", post(simple_auth::revoke_all_sessions_handler))
+        // One-click revoke from a new-device e-mail (signed token, no sign-in needed)
+        .route("/api/auth/sessions/revoke-link", post(login_alerts::revoke_link_handler))
+        // Account activity (security-sensitive actions)
+        .route("/api/auth/audit-log", get(audit_log::audit_log_handler))
         // Password change endpoint
         .route("/api/auth/change-password", post(simple_auth::change_password_handler))
+        // Two-factor authentication (TOTP)
+        .route("/api/auth/2fa/setup", post(simple_auth::two_factor_setup_handler))
+        .route("/api/auth/2fa/verify", post(simple_auth::two_factor_verify_handler))
+        .route("/api/auth/2fa/disable", post(simple_auth::two_factor_disable_handler))
         // Account deletion endpoints
         .route("/api/auth/delete-account", post(simple_auth::request_delete_account_handler))
         .route("/api/auth/restore-account", post(simple_auth::restore_account_handler))
+        .route("/api/auth/link-provider", post(account_linking::link_provider_handler))
+        // Data export (download link is token-authorized, it's opened from the e-mail)
+        .route("/api/auth/export-data", post(data_export::request_export_handler))
+        .route("/api/auth/export-data/:export_id", get(data_export::export_status_handler))
+        .route("/api/exports/:export_id/download", get(data_export::download_export_handler))
         // Subscription endpoints
         .route("/api/subscription/create", post(simple_auth::create_subscription_handler))
         .route("/api/subscription/status", get(simple_auth::subscription_status_handler))
@@ -138,6 +314,11 @@ async fn main() {
         .route("/api/subscription/billing-period", put(simple_auth::change_billing_period_handler))
         .route("/api/subscription/link-purchase", post(webhooks::link_purchase))
         .route("/api/subscription/verify", post(webhooks::verify_subscription))
+        .route("/api/message-packs/purchase", post(message_packs::purchase_message_pack_handler))
+        .route("/api/payments/checkout", post(payments::create_checkout_handler))
+        .route("/api/promo-codes/validate", post(promo_codes::validate_promo_code_handler))
+        .route("/api/referrals", get(referrals::get_referral_handler))
+        .route("/api/referrals/redeem", post(referrals::redeem_referral_handler))
         .with_state((
             pool.clone(),
             openrouter_api_key.clone(),
@@ -151,19 +332,105 @@ async fn main() {
     let database_routes = Router::new()
         .route("/api/chats", get(database::get_chats_handler))
         .route("/api/chats", post(database::create_chat_handler))
+        .route("/api/chats/import", post(database::import_chats_handler))
+        .route("/api/chats/archived", get(database::get_archived_chats_handler))
+        .route("/api/chats/:chat_id/archive", post(database::archive_chat_handler))
+        .route("/api/chats/:chat_id/share", post(chat_sharing::create_share_handler))
+        .route("/api/chats/:chat_id/share", get(chat_sharing::get_share_handler))
+        .route("/api/chats/:chat_id/share", delete(chat_sharing::revoke_share_handler))
+        .route("/api/shared/:token", get(chat_sharing::get_shared_chat_handler))
+        .route("/api/chats/:chat_id/restore", post(database::restore_chat_handler))
         .route("/api/chats/:chat_id", delete(database::delete_chat_handler))
         .route("/api/chats/:chat_id/title", put(database::update_chat_title_handler))
         .route("/api/chats/:chat_id/messages", get(database::get_messages_handler))
+        .route("/api/chats/:chat_id/folder", put(folders::assign_chat_folder_handler))
+        .route("/api/folders", get(folders::list_folders_handler))
+        .route("/api/folders", post(folders::create_folder_handler))
+        .route("/api/folders/:folder_id", put(folders::rename_folder_handler))
+        .route("/api/folders/:folder_id", delete(folders::delete_folder_handler))
         .route("/api/messages", post(database::add_message_handler))
         .route("/api/messages/:message_id/feedback", post(database::submit_message_feedback_handler))
+        .route("/api/messages/:message_id/revisions", get(database::get_message_revisions_handler))
         .route("/api/law-content", post(scraper::fetch_law_content_handler))
         .route("/api/cached-law", post(database::get_cached_law_handler))
+        .route("/api/jurisdictions", get(jurisdictions::list_jurisdictions_handler))
+        .route("/api/laws", get(law_browser::list_laws_handler))
+        .route("/api/laws/:law_id/toc", get(law_browser::law_toc_handler))
+        .route("/api/laws/:law_id/articles/:article_number", get(law_browser::law_article_handler))
+        .route("/api/law-subscriptions", get(law_subscriptions::list_subscriptions_handler))
+        .route("/api/law-subscriptions", post(law_subscriptions::subscribe_handler))
+        .route("/api/law-subscriptions/:law_id", delete(law_subscriptions::unsubscribe_handler))
+        .route("/api/notifications", get(notifications::list_notifications_handler))
+        .route("/api/notifications/read-all", post(notifications::mark_all_notifications_read_handler))
+        .route("/api/notifications/preferences", get(notifications::get_preferences_handler))
+        .route("/api/notifications/preferences", put(notifications::update_preferences_handler))
+        .route("/api/notifications/:notification_id/read", post(notifications::mark_notification_read_handler))
+        .route("/api/search", get(database::search_handler))
+        .route("/api/calendar", get(calendar::calendar_handler))
+        .route("/api/contracts/:file_id", put(contracts::update_contract_handler))
+        .route("/api/contracts/:file_id/fields", get(contracts::contract_fields_handler))
+        .route("/api/tools/deadline", post(deadlines::deadline_handler))
+        .route("/api/tools/deadline/procedures", get(deadlines::procedures_handler))
+        .route("/api/tools/court-fees", post(fees::court_fees_handler))
+        .route("/api/tools/court-fees/procedures", get(fees::fee_procedures_handler))
+        .route("/api/events/ws", get(events::events_ws_handler))
+        .route("/api/ws", get(events::events_ws_handler)) // Cross-device chat sync (same per-user channel)
+        .route("/api/usage", get(database::get_llm_usage_handler))
+        .route("/api/admin/llm-models", get(llm_config::list_llm_models_handler))
+        .route("/api/admin/llm-models", put(llm_config::set_llm_model_handler))
+        .route("/api/admin/prompt-profiles", get(prompt_profiles::list_prompt_profiles_handler))
+        .route("/api/admin/prompt-profiles", put(prompt_profiles::set_prompt_profile_handler))
+        .route("/api/admin/cost-caps", get(cost_caps::list_cost_caps_handler))
+        .route("/api/admin/cost-caps", put(cost_caps::set_cost_cap_handler))
+        .route("/api/admin/users/:user_id/cost-cap", put(cost_caps::set_user_cost_cap_handler))
+        .route("/api/admin/users/:user_id/cost-cap", delete(cost_caps::delete_user_cost_cap_handler))
+        .route("/api/admin/fee-schedules", get(fees::list_fee_schedules_handler))
+        .route("/api/admin/fee-schedules", put(fees::set_fee_schedule_handler))
+        .route("/api/admin/law-versions", get(law_revalidation::list_law_versions_handler))
+        .route("/api/conflicts/parties", get(conflicts::list_parties_handler))
+        .route("/api/conflicts/parties", post(conflicts::create_party_handler))
+        .route("/api/conflicts/parties/:party_id", delete(conflicts::delete_party_handler))
+        .route("/api/conflicts/alerts", get(conflicts::list_alerts_handler))
+        .route("/api/conflicts/alerts/:alert_id/resolve", post(conflicts::resolve_alert_handler))
+        .route("/api/admin/citation-coverage", get(citation_audit::citation_coverage_handler))
+        .route("/api/admin/feedback/stats", get(feedback_analytics::feedback_stats_handler))
+        .route("/api/admin/law-sources", get(law_sources::list_law_sources_handler))
+        .route("/api/admin/law-sources", put(law_sources::set_law_source_handler))
+        .route("/api/admin/laws/preload", post(law_preload::start_preload_handler))
+        .route("/api/admin/laws/preload/:job_id", get(law_preload::get_preload_status_handler))
+        .route("/api/admin/promo-codes", get(promo_codes::list_promo_codes_handler))
+        .route("/api/admin/promo-codes", post(promo_codes::create_promo_codes_handler))
+        .route("/api/admin/promo-codes/:code", delete(promo_codes::deactivate_promo_code_handler))
+        .route("/api/admin/impersonate", post(impersonation::impersonate_handler))
+        .route("/api/admin/retention/report", get(retention::retention_report_handler))
         .with_state((pool.clone(), openrouter_api_key.clone(), jwt_secret.clone(), supabase_jwt_secret.clone()));
 
+    // Account routes producing an answer need the current terms accepted; the layer also hands the
+    // verified user to the handler (see consent.rs)
+    let consent_layer = || axum::middleware::from_fn_with_state(pool.clone(), consent::require_consent);
+
     // API routes that need OpenAI key (5-element state with Supabase JWT secret)
     let api_routes = Router::new()
-        .route("/api/question", post(api::ask_question_handler))
+        .route("/api/question", post(api::ask_question_handler).route_layer(consent_layer()))
+        .route("/api/question/free", post(free_question::free_question_handler))
+        .route("/api/guest/status", get(guest::guest_status_handler))
+        .route("/api/guest/question", post(guest::guest_question_handler))
+        .route("/api/guest/chats", get(guest::guest_chats_handler))
+        .route("/api/guest/chats/:chat_id/messages", get(guest::guest_messages_handler))
         .route("/api/transcribe", post(api::transcribe_audio_handler))
+        .route("/api/synthesize", post(speech::synthesize_handler))
+        .route("/api/documents/extract", post(documents::extract_document_handler))
+        .route("/api/chats/:chat_id/document-requests", post(document_requests::create_document_request_handler))
+        .route("/api/chats/:chat_id/document-requests", get(document_requests::list_document_requests_handler))
+        .route("/api/document-requests/:request_id", delete(document_requests::revoke_document_request_handler))
+        .route("/api/document-requests/:request_id/public", get(document_requests::get_public_document_request_handler))
+        .route("/api/document-requests/:request_id/upload", post(document_requests::upload_requested_document_handler))
+        .route("/api/contracts/review", post(contract_review::review_contract_handler).route_layer(consent_layer()))
+        .route("/api/contracts/compare", post(contract_review::compare_clause_handler).route_layer(consent_layer()))
+        .route("/api/chats/:chat_id/contract-reviews", get(contract_review::list_chat_reviews_handler))
+        .route("/api/chats/:chat_id/auto-title", post(api::auto_title_handler))
+        .route("/api/messages/:message_id/regenerate", post(api::regenerate_message_handler).route_layer(consent_layer()))
+        .route("/api/messages/:message_id", put(api::edit_message_handler).route_layer(consent_layer()))
         .with_state((pool.clone(), openrouter_api_key.clone(), openai_api_key, jwt_secret.clone(), supabase_jwt_secret.clone()));
 
     // Contract download route (no auth required - files are UUID-based)
@@ -173,35 +440,98 @@ async fn main() {
     // Webhook routes (no auth - verified via signature)
     let webhook_routes = Router::new()
         .route("/api/webhooks/revenuecat", post(webhooks::handle_revenuecat_webhook))
+        .route("/api/webhooks/stripe", post(payments::handle_stripe_webhook))
+        // Admin endpoints (verified via X-Admin-Key)
+        .route("/api/admin/webhooks", get(webhooks::list_webhook_events))
+        .route("/api/admin/webhooks/:event_id/replay", post(webhooks::replay_webhook_event))
         .with_state((
-            pool,
-            openrouter_api_key,
+            pool.clone(),
+            openrouter_api_key.clone(),
             jwt_secret,
             supabase_url,
             supabase_jwt_secret,
             resend_api_key,
         ));
 
+    // Prometheus scrape endpoint (optionally protected by METRICS_TOKEN)
+    let metrics_routes = Router::new()
+        .route("/metrics", get(metrics::metrics_handler))
+        .with_state(pool.clone());
+
+    // Readiness check for Fly.io: database plus OpenRouter and law source reachability
+    let health_routes = Router::new()
+        .route("/health/ready", get(health::readiness_handler))
+        .with_state((pool.clone(), openrouter_api_key.clone()));
+
+    // API documentation: OpenAPI spec and Swagger UI
+    let docs_routes = Router::new()
+        .route("/api/docs", get(openapi::swagger_ui_handler))
+        .route("/api/docs/openapi.json", get(openapi::openapi_json_handler));
+
     // Combine routes
     let app = Router::new()
         .route("/health", get(health_check))
+        .route("/api/queue/status", get(llm_queue::queue_status_handler))
         .route("/debug", get(|| async { "Debug endpoint working!" }))
         .merge(auth_routes)
         .merge(database_routes)
         .merge(api_routes)
         .merge(contract_routes)
         .merge(webhook_routes)
+        .merge(metrics_routes)
+        .merge(health_routes)
+        .merge(docs_routes)
+        .route_layer(axum::middleware::from_fn(metrics::track_requests))
         // .layer(axum::middleware::from_fn(request_logger)) // Disabled - only enable for debugging
+        .layer(axum::middleware::from_fn(impersonation::enforce_read_only))
+        .layer(axum::middleware::from_fn(i18n::localize_errors))
+        .layer(axum::middleware::from_fn(request_id::attach_to_errors))
         .layer(cors)
-        .layer(TraceLayer::new_for_http())
+        // gzip/brotli for clients that accept it - law texts are several MB of plain text.
+        // The default predicate skips SSE streams, images and tiny bodies.
+        .layer(CompressionLayer::new())
+        .layer(TraceLayer::new_for_http().make_span_with(request_id::make_span::<axum::body::Body>))
+        // Outermost, so the id exists before the trace span is created and reaches every response
+        .layer(request_id::propagate_request_id_layer())
+        .layer(request_id::set_request_id_layer())
         .layer(DefaultBodyLimit::max(50 * 1024 * 1024)); // 50MB max body size
 
-    let port = env::var("PORT").unwrap_or_else(|_| "8080".to_string());
+    let port = config.port;
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await.unwrap();
     
     println!("🚀 Server running on http://0.0.0.0:{}", port);
     
-    // Serve with connection info for IP extraction
-    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await.unwrap();
-}
+    // Serve with connection info for IP extraction. On shutdown the listener closes and
+    // in-flight requests are drained for up to SHUTDOWN_DRAIN_TIMEOUT_SECONDS.
+    let mut server = tokio::spawn(
+        axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+            .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+            .into_future(),
+    );
+
+    tokio::select! {
+        result = &mut server => {
+            shutdown.cancel();
+            match result {
+                Ok(Ok(())) => println!("🛑 Server stopped"),
+                Ok(Err(e)) => eprintln!("❌ Server error: {}", e),
+                Err(e) => eprintln!("❌ Server task failed: {}", e),
+            }
+            return;
+        }
+        _ = shutdown.cancelled() => {}
+    }
+
+    let drain_timeout = shutdown::drain_timeout();
+    let deadline = tokio::time::Instant::now() + drain_timeout;
+    println!("🛑 Shutting down - draining in-flight requests (up to {}s)", drain_timeout.as_secs());
 
+    match tokio::time::timeout_at(deadline, server).await {
+        Ok(_) => println!("✅ All in-flight requests completed"),
+        Err(_) => println!("⚠️  Drain timeout reached - exiting with requests still in flight"),
+    }
+
+    shutdown::join_background_jobs(background_jobs, deadline).await;
+    pool.close().await;
+    println!("👋 Shutdown complete");
+}
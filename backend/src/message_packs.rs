@@ -0,0 +1,131 @@
+// Consumable message packs: extra messages bought once, without a subscription. They are kept in
+// users.purchased_messages_remaining, apart from the trial / plan allowance, so monthly resets and
+// plan changes never take them away, and they are only spent once that allowance is used up.
+// A pack is credited either from the RevenueCat NON_RENEWING_PURCHASE webhook or by the app right
+// after the purchase - whichever comes first; the store transaction ID keeps it to one credit.
+use crate::models::ApiError;
+use crate::revenuecat::{product_id_to_message_pack, RevenueCatClient, Subscriber};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+type AppState = (PgPool, String, String, Option<String>, Option<String>, String); // (pool, api_key, jwt_secret, supabase_url, supabase_jwt_secret, resend_api_key)
+
+fn database_error(e: sqlx::Error) -> ApiError {
+    eprintln!("Message pack database error: {}", e);
+    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Greška baze podataka")
+}
+
+/// Add a pack's messages to the user, once per store transaction. Returns false when the
+/// transaction was already credited.
+pub async fn credit_pack(
+    pool: &PgPool,
+    user_id: Uuid,
+    product_id: &str,
+    messages: i32,
+    source: &str,
+    external_id: &str,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let recorded = sqlx::query(
+        "INSERT INTO message_pack_purchases (user_id, product_id, messages, source, external_id)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (external_id) DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(product_id)
+    .bind(messages)
+    .bind(source)
+    .bind(external_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    if recorded == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        "UPDATE users SET purchased_messages_remaining = purchased_messages_remaining + $2, updated_at = NOW()
+         WHERE id = $1",
+    )
+    .bind(user_id)
+    .bind(messages)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    println!("🛒 Credited {} purchased messages ({}) to user {}", messages, product_id, user_id);
+    Ok(true)
+}
+
+/// Credit every message pack on the RevenueCat subscriber that hasn't been credited yet,
+/// returning how many messages were added
+pub async fn credit_from_subscriber(pool: &PgPool, user_id: Uuid, subscriber: &Subscriber) -> Result<i32, sqlx::Error> {
+    let mut credited = 0;
+    for (product_id, purchases) in &subscriber.non_subscriptions {
+        let Some(messages) = product_id_to_message_pack(product_id) else {
+            continue;
+        };
+        for purchase in purchases {
+            let external_id = purchase.store_transaction_id.as_deref().unwrap_or(&purchase.id);
+            if credit_pack(pool, user_id, product_id, messages, "app", external_id).await? {
+                credited += messages;
+            }
+        }
+    }
+    Ok(credited)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PurchaseMessagePackRequest {
+    pub receipt_token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PurchaseMessagePackResponse {
+    pub success: bool,
+    pub messages_credited: i32, // 0 when the webhook already credited the purchase
+    pub purchased_messages_remaining: i32,
+}
+
+/// Called by the app after a message pack purchase: links the receipt in RevenueCat and credits
+/// the packs found on the subscriber
+pub async fn purchase_message_pack_handler(
+    State((pool, api_key, jwt_secret, _, supabase_jwt_secret, _)): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<PurchaseMessagePackRequest>,
+) -> Result<Json<PurchaseMessagePackResponse>, ApiError> {
+    let user_id = crate::database::authenticate(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let api_key = crate::config::get().revenuecat_api_key.clone().unwrap_or(api_key);
+    let subscriber_info = RevenueCatClient::new(api_key)
+        .link_purchase_to_user(&user_id.to_string(), &request.receipt_token, false)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to link message pack purchase for user {}: {}", user_id, e);
+            ApiError::new(StatusCode::BAD_GATEWAY, "PAYMENT_PROVIDER_ERROR", "Kupovina nije potvrđena, pokušajte ponovo")
+        })?;
+
+    let messages_credited = credit_from_subscriber(&pool, user_id, &subscriber_info.subscriber)
+        .await
+        .map_err(database_error)?;
+
+    let purchased_messages_remaining: i32 = sqlx::query_scalar("SELECT purchased_messages_remaining FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(database_error)?;
+
+    Ok(Json(PurchaseMessagePackResponse {
+        success: true,
+        messages_credited,
+        purchased_messages_remaining,
+    }))
+}
@@ -0,0 +1,172 @@
+// Prometheus metrics. The recorder is installed once at startup; instrumented code uses the
+// record_* helpers below so metric names and labels stay in one place. Scraped from GET /metrics.
+use axum::{
+    extract::{MatchedPath, Request},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics::{counter, gauge, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use sqlx::PgPool;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+static PROMETHEUS: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// LLM answers take tens of seconds, DB queries milliseconds - one bucket set covers both
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0];
+
+/// Install the global recorder. Safe to call once; later calls are ignored.
+pub fn init() {
+    if PROMETHEUS.get().is_some() {
+        return;
+    }
+    let recorder = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), LATENCY_BUCKETS)
+        .and_then(|builder| builder.install_recorder());
+    match recorder {
+        Ok(handle) => {
+            let _ = PROMETHEUS.set(handle);
+        }
+        Err(e) => eprintln!("⚠️  Failed to install Prometheus recorder: {}", e),
+    }
+}
+
+/// GET /metrics - Prometheus text format. Requires `Authorization: Bearer $METRICS_TOKEN`; without
+/// a configured token the endpoint doesn't exist (404).
+pub async fn metrics_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let token = crate::config::get().metrics_token.as_deref().ok_or(StatusCode::NOT_FOUND)?;
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if provided != Some(token) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let handle = PROMETHEUS.get().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+
+    gauge!("db_pool_connections").set(pool.size() as f64);
+    gauge!("db_pool_idle_connections").set(pool.num_idle() as f64);
+
+    handle.run_upkeep();
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        handle.render(),
+    )
+        .into_response())
+}
+
+/// Request count and latency per route template (e.g. /api/chats/:chat_id, not the raw path)
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let started_at = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    let labels = [("method", method), ("route", route), ("status", status)];
+    counter!("http_requests_total", &labels).increment(1);
+    histogram!("http_request_duration_seconds", &labels).record(started_at.elapsed().as_secs_f64());
+
+    response
+}
+
+/// One OpenRouter chat completion (answer, classification, law detection, ...)
+pub fn record_openrouter_call(purpose: &str, elapsed: Duration, success: bool) {
+    let labels = [("purpose", purpose.to_string()), ("outcome", outcome(success))];
+    counter!("openrouter_requests_total", &labels).increment(1);
+    histogram!("openrouter_request_duration_seconds", &labels).record(elapsed.as_secs_f64());
+}
+
+/// A retried OpenRouter attempt (429, 5xx or network error)
+pub fn record_openrouter_retry(model: &str) {
+    counter!("openrouter_retries_total", "model" => model.to_string()).increment(1);
+}
+
+/// A request that moved from its primary model to the fallback model
+pub fn record_openrouter_fallback(from: &str, to: &str) {
+    counter!("openrouter_fallbacks_total", "from" => from.to_string(), "to" => to.to_string()).increment(1);
+}
+
+/// Circuit breaker state per model: 0 = closed, 1 = open, 2 = half-open
+pub fn set_openrouter_circuit_state(model: &str, state: f64) {
+    gauge!("openrouter_circuit_state", "model" => model.to_string()).set(state);
+}
+
+/// A question step that ran past its deadline ("pipeline" or "articles")
+pub fn record_question_timeout(stage: &'static str) {
+    counter!("question_timeouts_total", "stage" => stage).increment(1);
+}
+
+/// A question pipeline dropped before finishing, e.g. because the client disconnected
+pub fn record_question_cancelled() {
+    counter!("question_cancellations_total").increment(1);
+}
+
+/// One law page fetch + parse from a source site
+pub fn record_scrape(source: &'static str, elapsed: Duration, success: bool) {
+    let labels = [("source", source.to_string()), ("outcome", outcome(success))];
+    counter!("law_scrapes_total", &labels).increment(1);
+    histogram!("law_scrape_duration_seconds", &labels).record(elapsed.as_secs_f64());
+}
+
+/// Time a database query under a short stable name
+pub async fn time_db_query<T>(query: &'static str, future: impl std::future::Future<Output = T>) -> T {
+    let started_at = Instant::now();
+    let result = future.await;
+    histogram!("db_query_duration_seconds", "query" => query).record(started_at.elapsed().as_secs_f64());
+    result
+}
+
+/// A question/transcription refused because the user's message allowance is used up
+pub fn record_trial_rejection(endpoint: &'static str) {
+    counter!("trial_rejections_total", "endpoint" => endpoint).increment(1);
+}
+
+/// A moderation outcome (action: "refused", "injection_neutralized"), see moderation.rs
+pub fn record_moderation(action: &'static str) {
+    counter!("moderation_actions_total", "action" => action).increment(1);
+}
+
+/// Values replaced with placeholders in a message before it was stored, see pii.rs
+pub fn record_pii_redaction(values: usize) {
+    counter!("pii_values_redacted_total").increment(values as u64);
+}
+
+/// A trial question refused by the abuse heuristics (reason: trial_abuse::TrialAbuseReason::code)
+pub fn record_trial_abuse_rejection(reason: &'static str) {
+    counter!("trial_abuse_rejections_total", "reason" => reason).increment(1);
+}
+
+/// A retried question answered from its stored response (Idempotency-Key)
+pub fn record_idempotent_replay() {
+    counter!("idempotent_replays_total").increment(1);
+}
+
+/// A request refused because the user's monthly LLM spend reached its cap
+pub fn record_cost_cap_rejection(endpoint: &'static str) {
+    counter!("cost_cap_rejections_total", "endpoint" => endpoint).increment(1);
+}
+
+pub fn record_contract_generation(success: bool) {
+    counter!("contract_generations_total", "outcome" => outcome(success)).increment(1);
+}
+
+/// Final state of a RevenueCat webhook processing attempt: processed, failed (will retry) or dead
+pub fn record_webhook_outcome(outcome: &'static str) {
+    counter!("webhook_events_total", "outcome" => outcome).increment(1);
+}
+
+fn outcome(success: bool) -> String {
+    if success { "success" } else { "error" }.to_string()
+}
@@ -0,0 +1,95 @@
+// Versioned schema migrations (backend/migrations, embedded at compile time). 0001 is the schema the
+// old run_migrations built from IF NOT EXISTS statements on every start; databases created that way
+// have no migration history and are baselined by applying 0001 once, which is a no-op on an
+// up-to-date schema. Everything after the baseline is an ordinary numbered migration, applied
+// exactly once and in order, so it can drop, rename and backfill.
+use sqlx::migrate::Migrator;
+use sqlx::PgPool;
+
+fn migrator() -> Migrator {
+    let mut migrator = sqlx::migrate!("./migrations");
+    // Migrations from a newer build are reported by check_schema_version instead of failing in sqlx
+    migrator.set_ignore_missing(true);
+    migrator
+}
+
+/// Highest migration version this build ships
+fn latest_known_version(migrator: &Migrator) -> i64 {
+    migrator.iter().map(|migration| migration.version).max().unwrap_or(0)
+}
+
+async fn table_exists(pool: &PgPool, table: &str) -> Result<bool, String> {
+    sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+        .bind(table)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to inspect schema: {}", e))
+}
+
+/// Highest migration applied to the database (0 before the first run)
+pub async fn schema_version(pool: &PgPool) -> Result<i64, String> {
+    sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM _sqlx_migrations WHERE success")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to read schema version: {}", e))
+}
+
+/// A database migrated by a newer build may no longer have what this build uses (after rolling a
+/// deploy back, say). Refuse to start unless ALLOW_NEWER_SCHEMA says the newer migrations are
+/// known to be backward compatible.
+fn check_schema_version(
+    database_version: i64,
+    known_version: i64,
+    allow_newer_schema: bool,
+) -> Result<(), String> {
+    if database_version <= known_version {
+        return Ok(());
+    }
+    if allow_newer_schema {
+        println!(
+            "⚠️  Database schema version {} is newer than this build ({}) - continuing because ALLOW_NEWER_SCHEMA=true",
+            database_version, known_version
+        );
+        return Ok(());
+    }
+    Err(format!(
+        "Database schema version {} is newer than this build ({}). Deploy a newer build, or set ALLOW_NEWER_SCHEMA=true if the newer migrations are backward compatible.",
+        database_version, known_version
+    ))
+}
+
+/// Bring the schema up to date at startup; returns the resulting schema version
+pub async fn run(pool: &PgPool) -> Result<i64, String> {
+    let migrator = migrator();
+
+    if !table_exists(pool, "_sqlx_migrations").await? && table_exists(pool, "users").await? {
+        println!("📦 Existing database without migration history - baselining it with migration 0001");
+    }
+
+    migrator.run(pool).await.map_err(|e| format!("Migration failed: {}", e))?;
+
+    let version = schema_version(pool).await?;
+    check_schema_version(version, latest_known_version(&migrator), crate::config::get().allow_newer_schema)?;
+    println!("✅ Database schema at version {}", version);
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_are_numbered_in_sequence() {
+        let versions: Vec<i64> = migrator().iter().map(|migration| migration.version).collect();
+        assert_eq!(versions.first(), Some(&1)); // The baseline
+        assert!(versions.windows(2).all(|pair| pair[1] == pair[0] + 1), "gap in migration numbers: {:?}", versions);
+    }
+
+    #[test]
+    fn test_check_schema_version() {
+        assert!(check_schema_version(3, 3, false).is_ok());
+        assert!(check_schema_version(2, 3, false).is_ok());
+        assert!(check_schema_version(4, 3, false).is_err());
+        assert!(check_schema_version(4, 3, true).is_ok());
+    }
+}
@@ -2,137 +2,67 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
-pub struct Chat {
-    pub id: i64,
-    pub title: String,
-    pub user_id: Option<Uuid>,
-    pub created_at: chrono::DateTime<chrono::Utc>,
-    pub updated_at: chrono::DateTime<chrono::Utc>,
-}
-
-#[derive(Debug, Serialize, Deserialize, FromRow)]
-pub struct Message {
-    pub id: i64,
-    pub chat_id: i64,
-    pub role: String,
-    pub content: String,
-    pub law_name: Option<String>,
-    pub has_document: Option<bool>,
-    pub document_filename: Option<String>,
-    pub contract_file_id: Option<String>,
-    pub contract_type: Option<String>,
-    pub contract_filename: Option<String>,
-    pub message_feedback: Option<String>,
-    pub created_at: chrono::DateTime<chrono::Utc>,
-}
-
-#[derive(Debug, Serialize, Deserialize, FromRow)]
-pub struct LawCache {
-    pub id: i64,
-    pub law_name: String,
-    pub law_url: String,
-    pub content: String,
-    pub cached_at: chrono::DateTime<chrono::Utc>,
-    pub expires_at: chrono::DateTime<chrono::Utc>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateChatRequest {
-    pub title: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateChatResponse {
-    pub id: i64,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AddMessageRequest {
-    pub chat_id: i64,
-    pub role: String,
-    pub content: String,
-    pub law_name: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SubmitFeedbackRequest {
-    pub feedback_type: String, // 'positive' or 'negative'
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SubmitFeedbackResponse {
-    pub success: bool,
-    pub message: String,
-    pub updated: bool, // true if feedback was changed from previous value
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GetCachedLawRequest {
-    pub law_name: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct LawContent {
-    pub title: String,
-    pub content: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct FetchLawContentRequest {
-    pub url: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct QuestionRequest {
-    pub question: String,
-    pub document_content: Option<String>, // Extracted document text
-    pub document_filename: Option<String>, // Original filename
-    pub law_name: Option<String>, // Optional - will be auto-detected if not provided
-    pub law_url: Option<String>, // Optional - will be auto-detected if not provided
-    pub chat_id: i64,
+// Request/response models are in the norma-api-types crate (api-types/), shared with the apps
+pub use norma_api_types::*;
+
+/// Handler error: a bare status code, or a status with an ErrorResponse body the client can act on.
+/// Plain StatusCode errors convert into it, so `?` keeps working in handlers that return it.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: axum::http::StatusCode,
+    pub body: Option<ErrorResponse>,
+}
+
+impl ApiError {
+    /// Error with an ErrorResponse body (error code + user-facing message)
+    pub fn new(status: axum::http::StatusCode, error: &str, message: &str) -> Self {
+        ApiError {
+            status,
+            body: Some(ErrorResponse { error: error.to_string(), message: message.to_string(), details: None }),
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GeneratedContract {
-    pub filename: String,
-    pub download_url: String,
-    pub contract_type: String,
-    pub preview_text: String,
-    pub created_at: chrono::DateTime<chrono::Utc>,
+impl From<axum::http::StatusCode> for ApiError {
+    fn from(status: axum::http::StatusCode) -> Self {
+        ApiError { status, body: None }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct QuestionResponse {
-    pub answer: String,
-    pub law_quotes: Vec<String>,
-    pub law_name: Option<String>,
-    pub generated_contract: Option<GeneratedContract>,
+/// Errors from helpers that still build the older (StatusCode, Json<ErrorResponse>) form
+impl From<(axum::http::StatusCode, axum::Json<ErrorResponse>)> for ApiError {
+    fn from((status, axum::Json(body)): (axum::http::StatusCode, axum::Json<ErrorResponse>)) -> Self {
+        ApiError { status, body: Some(body) }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SerbianLaw {
-    pub id: i32,
-    pub name: String,
-    pub url: String,
+/// For handlers that still return (StatusCode, Json<ErrorResponse>); a bare status gets a generic body
+impl From<ApiError> for (axum::http::StatusCode, axum::Json<ErrorResponse>) {
+    fn from(e: ApiError) -> Self {
+        let body = e.body.unwrap_or_else(|| ErrorResponse {
+            error: "ERROR".to_string(),
+            message: e.status.canonical_reason().unwrap_or("Greška").to_string(),
+            details: None,
+        });
+        (e.status, axum::Json(body))
+    }
 }
 
-// Authentication Models
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AuthResponse {
-    pub success: bool,
-    pub user_id: Option<Uuid>,
-    pub access_token: Option<String>,
-    pub refresh_token: Option<String>,
-    pub migrated_chats: Option<i64>,
-    pub message: String,
+impl axum::response::IntoResponse for ApiError {
+    fn into_response(self) -> axum::response::Response {
+        match self.body {
+            Some(body) => (self.status, axum::Json(body)).into_response(),
+            None => self.status.into_response(),
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub message: String,
-    pub details: Option<serde_json::Value>,
+/// A messages row with the columns that stay in the backend
+#[derive(Debug, FromRow)]
+pub struct MessageRow {
+    #[sqlx(flatten)]
+    pub message: Message,
+    pub pii_mapping_encrypted: Option<String>, // Restores redacted values for the owner, see pii.rs
 }
 
 // Optimized User Model (combines users + subscriptions)
@@ -158,12 +88,19 @@ pub struct User {
     pub subscription_type: Option<String>, // 'monthly', 'yearly'
     pub subscription_started_at: Option<chrono::DateTime<chrono::Utc>>,
     pub next_billing_date: Option<chrono::DateTime<chrono::Utc>>,
-    pub subscription_status: Option<String>, // 'active', 'cancelled', 'expired'
+    pub subscription_status: Option<String>, // 'active', 'grace', 'past_due', 'cancelled', 'expired'
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub last_login: Option<chrono::DateTime<chrono::Utc>>,
+    pub ui_language: String, // 'sr', 'en'
+    pub response_script: String, // 'latin', 'cyrillic'
+    pub grace_period_ends_at: Option<chrono::DateTime<chrono::Utc>>, // Set while a failed renewal is being retried
+    pub purchased_messages_remaining: i32, // Bought in message packs, spent after trial_messages_remaining
 }
 
+/// Every value of users.account_type (see the CHECK constraint in the baseline migration)
+pub const ACCOUNT_TYPES: &[&str] = &["trial_registered", "individual", "professional", "team", "premium"];
+
 impl User {
     pub fn is_registered(&self) -> bool {
         matches!(self.account_type.as_str(), "trial_registered" | "individual" | "professional" | "team" | "premium")
@@ -172,10 +109,17 @@ impl User {
     pub fn can_upload_documents(&self) -> bool {
         matches!(self.account_type.as_str(), "professional" | "team" | "premium")
     }
+
+    /// Whether the user has trial / plan messages or purchased messages left
+    pub fn has_messages_left(&self) -> bool {
+        self.trial_messages_remaining.unwrap_or(0) > 0 || self.purchased_messages_remaining > 0
+    }
 }
 
 // Unified Authentication Token Model (replaces email_verification_tokens + password_reset_tokens)
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+// Rows store an HMAC of the token for lookup and the token encrypted, never the token itself
+// (see secret_box.rs); `token` here is the decrypted value.
+#[derive(Debug, Serialize, Deserialize)]
 pub struct AuthenticationToken {
     pub id: i64,
     pub user_id: Uuid,
@@ -186,6 +130,17 @@ pub struct AuthenticationToken {
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+#[derive(FromRow)]
+struct StoredAuthenticationToken {
+    id: i64,
+    user_id: Uuid,
+    token_type: String,
+    token_encrypted: String,
+    expires_at: chrono::DateTime<chrono::Utc>,
+    used_at: Option<chrono::DateTime<chrono::Utc>>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
 impl AuthenticationToken {
     pub async fn create(
         pool: &sqlx::Pool<sqlx::Postgres>,
@@ -194,12 +149,17 @@ impl AuthenticationToken {
         token: String,
         expires_at: chrono::DateTime<chrono::Utc>,
     ) -> Result<(), sqlx::Error> {
+        let (lookup_key, encryption_key) = crate::secret_box::token_keys();
+        let token_encrypted = crate::secret_box::seal(&encryption_key, token.as_bytes(), token_type.as_bytes())
+            .map_err(|e| sqlx::Error::Protocol(format!("Failed to encrypt token: {}", e)))?;
+
         sqlx::query(
-            "INSERT INTO authentication_tokens (user_id, token_type, token, expires_at) VALUES ($1, $2, $3, $4)"
+            "INSERT INTO authentication_tokens (user_id, token_type, token_hash, token_encrypted, expires_at) VALUES ($1, $2, $3, $4, $5)"
         )
         .bind(user_id)
         .bind(token_type)
-        .bind(token)
+        .bind(crate::secret_box::lookup_hash(&lookup_key, &token))
+        .bind(token_encrypted)
         .bind(expires_at)
         .execute(pool)
         .await?;
@@ -212,19 +172,41 @@ impl AuthenticationToken {
         token: &str,
         token_type: &str,
     ) -> Result<Option<Self>, sqlx::Error> {
-        sqlx::query_as::<_, Self>(
-            "SELECT id, user_id, token_type, token, expires_at, used_at, created_at FROM authentication_tokens WHERE token = $1 AND token_type = $2"
+        let (lookup_key, encryption_key) = crate::secret_box::token_keys();
+        let stored = sqlx::query_as::<_, StoredAuthenticationToken>(
+            "SELECT id, user_id, token_type, token_encrypted, expires_at, used_at, created_at
+             FROM authentication_tokens WHERE token_hash = $1 AND token_type = $2"
         )
-        .bind(token)
+        .bind(crate::secret_box::lookup_hash(&lookup_key, token))
         .bind(token_type)
         .fetch_optional(pool)
-        .await
+        .await?;
+
+        // The ciphertext must open to the presented token - a row copied in from elsewhere doesn't
+        let Some(stored) = stored else {
+            return Ok(None);
+        };
+        let decrypted = crate::secret_box::open(&encryption_key, &stored.token_encrypted, stored.token_type.as_bytes());
+        match decrypted {
+            Ok(decrypted) if decrypted == token.as_bytes() => Ok(Some(AuthenticationToken {
+                id: stored.id,
+                user_id: stored.user_id,
+                token_type: stored.token_type,
+                token: token.to_string(),
+                expires_at: stored.expires_at,
+                used_at: stored.used_at,
+                created_at: stored.created_at,
+            })),
+            _ => {
+                eprintln!("⚠️  SECURITY: Authentication token {} doesn't decrypt to the presented token", stored.id);
+                Ok(None)
+            }
+        }
     }
 
     pub async fn mark_as_used(&self, pool: &sqlx::Pool<sqlx::Postgres>) -> Result<(), sqlx::Error> {
-        sqlx::query("UPDATE authentication_tokens SET used_at = NOW() WHERE token = $1 AND token_type = $2")
-            .bind(&self.token)
-            .bind(&self.token_type)
+        sqlx::query("UPDATE authentication_tokens SET used_at = NOW() WHERE id = $1")
+            .bind(self.id)
             .execute(pool)
             .await?;
 
@@ -234,65 +216,33 @@ impl AuthenticationToken {
     pub fn is_valid(&self) -> bool {
         self.used_at.is_none() && chrono::Utc::now() < self.expires_at
     }
-}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UserStatusResponse {
-    pub is_authenticated: bool,
-    pub user_id: Option<Uuid>,
-    pub email: Option<String>,
-    pub email_verified: bool, // Email verification status
-    pub oauth_provider: Option<String>, // 'google', 'apple', NULL for email/password
-    pub access_type: String, // "trial", "individual", "professional", "team", "premium" - for frontend compatibility
-    pub account_type: String, // "trial_registered", "individual", "professional", "team", "premium" - internal use
-    pub trial_expires_at: Option<chrono::DateTime<chrono::Utc>>,
-    pub premium_expires_at: Option<chrono::DateTime<chrono::Utc>>,
-    pub subscription_expires_at: Option<chrono::DateTime<chrono::Utc>>, // Alias for frontend compatibility
-    pub messages_used_today: i32, // Deprecated, always 0
-    pub messages_remaining: Option<i32>, // None for premium (unlimited)
-    pub total_messages_sent: i32, // Total number of user messages ever sent (for UI hints)
-    // New subscription details
-    pub subscription_type: Option<String>, // "monthly", "yearly"
-    pub subscription_started_at: Option<chrono::DateTime<chrono::Utc>>,
-    pub next_billing_date: Option<chrono::DateTime<chrono::Utc>>,
-    pub subscription_status: Option<String>, // "active", "cancelled", "expired"
-}
-
-
-// Complex parsing models removed - using simplified LLM-guided approach
-
-
-// Password Reset Response
-#[derive(Debug, Serialize, Deserialize)]
-pub struct PasswordResetResponse {
-    pub success: bool,
-    pub message: String,
-}
-
-// Email Verification Response
-#[derive(Debug, Serialize, Deserialize)]
-pub struct VerificationEmailResponse {
-    pub success: bool,
-    pub message: String,
-}
-
-// Account Deletion Models
-#[derive(Debug, Deserialize)]
-pub struct DeleteAccountRequest {
-    pub confirmation: bool, // Must be true
-}
-
-#[derive(Debug, Serialize)]
-pub struct DeleteAccountResponse {
-    pub success: bool,
-    pub message: String,
-    pub grace_period_ends: Option<String>, // ISO 8601 date
-}
-
-#[derive(Debug, Serialize)]
-pub struct RestoreAccountResponse {
-    pub success: bool,
-    pub message: String,
-    pub user_status: UserStatusResponse,
+    /// Convert reset/verification tokens stored in plain text before token_hash existed (run at
+    /// startup - the conversion needs the application key). Returns how many rows were converted.
+    pub async fn protect_legacy_tokens(pool: &sqlx::Pool<sqlx::Postgres>) -> Result<u64, String> {
+        let legacy: Vec<(i64, String, String)> = sqlx::query_as(
+            "SELECT id, token_type, token FROM authentication_tokens
+             WHERE token_type <> 'jwt_refresh' AND token_hash IS NULL AND token IS NOT NULL"
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load legacy authentication tokens: {}", e))?;
+
+        let (lookup_key, encryption_key) = crate::secret_box::token_keys();
+        let mut converted = 0;
+        for (id, token_type, token) in legacy {
+            let token_encrypted = crate::secret_box::seal(&encryption_key, token.as_bytes(), token_type.as_bytes())?;
+            converted += sqlx::query(
+                "UPDATE authentication_tokens SET token_hash = $2, token_encrypted = $3, token = NULL WHERE id = $1"
+            )
+            .bind(id)
+            .bind(crate::secret_box::lookup_hash(&lookup_key, &token))
+            .bind(token_encrypted)
+            .execute(pool)
+            .await
+            .map_err(|e| format!("Failed to protect authentication token {}: {}", id, e))?
+            .rows_affected();
+        }
+        Ok(converted)
+    }
 }
-
@@ -0,0 +1,389 @@
+// In-app notification feed and per-user delivery preferences. Producers - law amendments
+// (law_subscriptions), subscription changes (webhooks), failed payments (dunning) and new-device
+// sign-ins (login_alerts) - go through notify(), which stores the in-app notification if the user
+// wants it and tells the producer whether to send its e-mail. Every stored notification is also
+// pushed over the events socket so open clients can update their unread badge.
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::database::verify_user_from_headers_async;
+use crate::events::{self, ChatEvent};
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    LawAmended,
+    Subscription,
+    NewDevice,
+    Billing, // Failed payments (dunning.rs)
+}
+
+impl NotificationKind {
+    pub const ALL: [NotificationKind; 4] = [
+        NotificationKind::LawAmended,
+        NotificationKind::Subscription,
+        NotificationKind::NewDevice,
+        NotificationKind::Billing,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::LawAmended => "law_amended",
+            NotificationKind::Subscription => "subscription",
+            NotificationKind::NewDevice => "new_device",
+            NotificationKind::Billing => "billing",
+        }
+    }
+
+    /// Kinds whose producer also sends an e-mail
+    fn has_email(&self) -> bool {
+        matches!(self, NotificationKind::NewDevice | NotificationKind::Billing)
+    }
+
+    fn defaults(&self) -> Channels {
+        Channels { in_app: true, email: self.has_email() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Channels {
+    pub in_app: bool,
+    pub email: bool,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct UserNotification {
+    pub id: i64,
+    pub kind: String, // "law_amended", "subscription", "new_device", "billing"
+    pub title: String,
+    pub body: Option<String>,
+    pub law_name: Option<String>,
+    pub data: Option<serde_json::Value>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub read_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationFeed {
+    pub notifications: Vec<UserNotification>,
+    pub unread_count: i64,
+    pub unread_by_kind: HashMap<String, i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotificationsQuery {
+    pub unread: Option<bool>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationPreference {
+    pub kind: NotificationKind,
+    pub in_app: bool,
+    pub email: Option<bool>, // None when the kind has no e-mail
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChannelsUpdate {
+    pub in_app: Option<bool>,
+    pub email: Option<bool>,
+}
+
+/// Kinds left out are unchanged, as are channels left out of a kind
+#[derive(Debug, Deserialize)]
+pub struct UpdatePreferencesRequest {
+    pub preferences: HashMap<NotificationKind, ChannelsUpdate>,
+}
+
+/// The user's channels for a kind (its defaults unless changed)
+pub async fn channels_for(user_id: Uuid, kind: NotificationKind, pool: &PgPool) -> Channels {
+    let stored: Option<(bool, bool)> =
+        sqlx::query_as("SELECT in_app, email FROM notification_preferences WHERE user_id = $1 AND kind = $2")
+            .bind(user_id)
+            .bind(kind.as_str())
+            .fetch_optional(pool)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("⚠️  Failed to load notification preferences: {}", e);
+                None
+            });
+
+    match stored {
+        Some((in_app, email)) => Channels { in_app, email: email && kind.has_email() },
+        None => kind.defaults(),
+    }
+}
+
+/// Store an in-app notification unless the user turned the kind off. Returns the user's channels
+/// so the producer knows whether to send its e-mail. Failures are logged only.
+pub async fn notify(
+    pool: &PgPool,
+    user_id: Uuid,
+    kind: NotificationKind,
+    title: &str,
+    body: Option<&str>,
+    data: Option<serde_json::Value>,
+) -> Channels {
+    let channels = channels_for(user_id, kind, pool).await;
+    if !channels.in_app {
+        return channels;
+    }
+
+    let inserted = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO user_notifications (user_id, kind, title, body, data) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+    )
+    .bind(user_id)
+    .bind(kind.as_str())
+    .bind(title)
+    .bind(body)
+    .bind(data)
+    .fetch_one(pool)
+    .await;
+
+    match inserted {
+        Ok(notification_id) => emit_created(user_id, notification_id, kind, title),
+        Err(e) => eprintln!("⚠️  Failed to store {} notification for user {}: {}", kind.as_str(), user_id, e),
+    }
+    channels
+}
+
+pub fn emit_created(user_id: Uuid, notification_id: i64, kind: NotificationKind, title: &str) {
+    events::emit(
+        user_id,
+        ChatEvent::NotificationCreated { notification_id, kind: kind.as_str().to_string(), title: title.to_string() },
+    );
+}
+
+/// In-app notification for a RevenueCat event type; None for events users don't need to hear about.
+/// Billing issues are announced by dunning.rs as they move through the grace period.
+pub fn subscription_notification(event_type: &str) -> Option<(&'static str, Option<&'static str>)> {
+    match event_type {
+        "INITIAL_PURCHASE" => Some(("Pretplata je aktivirana", None)),
+        "RENEWAL" => Some(("Pretplata je obnovljena", None)),
+        "PRODUCT_CHANGE" => Some(("Plan pretplate je promenjen", None)),
+        "UNCANCELLATION" => Some(("Pretplata je ponovo aktivna", None)),
+        "CANCELLATION" => Some(("Pretplata je otkazana", Some("Pristup ostaje do isteka plaćenog perioda."))),
+        "EXPIRATION" => Some(("Pretplata je istekla", None)),
+        _ => None,
+    }
+}
+
+/// Notifications feed, newest first, with unread counts
+pub async fn list_notifications_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<NotificationsQuery>,
+) -> Result<ResponseJson<NotificationFeed>, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+
+    let notifications = sqlx::query_as::<_, UserNotification>(
+        "SELECT id, kind, title, body, law_name, data, created_at, read_at
+         FROM user_notifications
+         WHERE user_id = $1 AND (NOT $2 OR read_at IS NULL)
+         ORDER BY created_at DESC, id DESC
+         LIMIT $3"
+    )
+    .bind(user_id)
+    .bind(query.unread.unwrap_or(false))
+    .bind(limit)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to fetch notifications: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let unread_by_kind: HashMap<String, i64> = sqlx::query_as::<_, (String, i64)>(
+        "SELECT kind, COUNT(*) FROM user_notifications WHERE user_id = $1 AND read_at IS NULL GROUP BY kind"
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to count unread notifications: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .into_iter()
+    .collect();
+
+    Ok(ResponseJson(NotificationFeed {
+        notifications,
+        unread_count: unread_by_kind.values().sum(),
+        unread_by_kind,
+    }))
+}
+
+/// Mark one notification as read
+pub async fn mark_notification_read_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Path(notification_id): Path<i64>,
+) -> Result<StatusCode, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let result = sqlx::query(
+        "UPDATE user_notifications SET read_at = COALESCE(read_at, NOW()) WHERE id = $1 AND user_id = $2"
+    )
+    .bind(notification_id)
+    .bind(user_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to mark notification as read: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// Mark all of the user's notifications as read
+pub async fn mark_all_notifications_read_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+) -> Result<StatusCode, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    sqlx::query("UPDATE user_notifications SET read_at = NOW() WHERE user_id = $1 AND read_at IS NULL")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to mark notifications as read: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(StatusCode::OK)
+}
+
+async fn load_preferences(user_id: Uuid, pool: &PgPool) -> Result<Vec<NotificationPreference>, sqlx::Error> {
+    let stored: HashMap<String, (bool, bool)> =
+        sqlx::query_as::<_, (String, bool, bool)>("SELECT kind, in_app, email FROM notification_preferences WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|(kind, in_app, email)| (kind, (in_app, email)))
+            .collect();
+
+    Ok(NotificationKind::ALL
+        .into_iter()
+        .map(|kind| {
+            let defaults = kind.defaults();
+            let (in_app, email) = stored.get(kind.as_str()).copied().unwrap_or((defaults.in_app, defaults.email));
+            NotificationPreference { kind, in_app, email: kind.has_email().then_some(email) }
+        })
+        .collect())
+}
+
+/// Delivery channels for every notification kind
+pub async fn get_preferences_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ResponseJson<Vec<NotificationPreference>>, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let preferences = load_preferences(user_id, &pool).await.map_err(|e| {
+        eprintln!("Failed to load notification preferences: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(ResponseJson(preferences))
+}
+
+/// Change delivery channels; returns the resulting preferences for every kind
+pub async fn update_preferences_handler(
+    State((pool, _, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<UpdatePreferencesRequest>,
+) -> Result<ResponseJson<Vec<NotificationPreference>>, StatusCode> {
+    let user_id = verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    // Turning on a channel a kind doesn't have is a client error, not a silent no-op
+    if request.preferences.iter().any(|(kind, update)| update.email == Some(true) && !kind.has_email()) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let current: HashMap<NotificationKind, NotificationPreference> =
+        load_preferences(user_id, &pool)
+            .await
+            .map_err(|e| {
+                eprintln!("Failed to load notification preferences: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .into_iter()
+            .map(|preference| (preference.kind, preference))
+            .collect();
+
+    let mut tx = pool.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    for (kind, update) in &request.preferences {
+        let Some(existing) = current.get(kind) else { continue };
+        sqlx::query(
+            "INSERT INTO notification_preferences (user_id, kind, in_app, email) VALUES ($1, $2, $3, $4)
+             ON CONFLICT (user_id, kind) DO UPDATE SET in_app = EXCLUDED.in_app, email = EXCLUDED.email, updated_at = NOW()"
+        )
+        .bind(user_id)
+        .bind(kind.as_str())
+        .bind(update.in_app.unwrap_or(existing.in_app))
+        .bind(update.email.or(existing.email).unwrap_or(false))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to save notification preferences: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    }
+    tx.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let preferences = load_preferences(user_id, &pool).await.map_err(|e| {
+        eprintln!("Failed to load notification preferences: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(ResponseJson(preferences))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_names_match_serde() {
+        // as_str is what's stored in user_notifications.kind and notification_preferences.kind
+        for kind in NotificationKind::ALL {
+            assert_eq!(serde_json::to_value(kind).unwrap(), serde_json::json!(kind.as_str()));
+        }
+        assert!(serde_json::from_str::<NotificationKind>("\"marketing\"").is_err());
+    }
+
+    #[test]
+    fn test_subscription_notification() {
+        assert_eq!(subscription_notification("RENEWAL").map(|(title, _)| title), Some("Pretplata je obnovljena"));
+        assert!(subscription_notification("CANCELLATION").and_then(|(_, body)| body).is_some());
+        assert_eq!(subscription_notification("BILLING_ISSUE"), None);
+        assert_eq!(subscription_notification("TEST"), None);
+        assert_eq!(subscription_notification("TRANSFER"), None);
+    }
+}
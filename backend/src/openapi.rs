@@ -0,0 +1,237 @@
+// OpenAPI description of the public API, generated from the #[utoipa::path] annotations on the
+// handlers and the ToSchema derives on the request/response models. Served as JSON at
+// /api/docs/openapi.json and browsable with Swagger UI at /api/docs (assets from a CDN, so the
+// binary doesn't embed them). Both the custom JWTs and Supabase access tokens are sent as
+// "Authorization: Bearer ..." - that's the bearer_auth scheme.
+use crate::{api, contracts, database, models, simple_auth};
+use axum::{response::Html, Json};
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Norma AI API",
+        description = "Pravni asistent za zakone Srbije i regiona. Greške vraćaju ErrorResponse sa stabilnim `error` kodom; `message` je lokalizovan prema Accept-Language."
+    ),
+    paths(
+        simple_auth::link_user_handler,
+        simple_auth::check_provider_handler,
+        simple_auth::user_status_handler,
+        simple_auth::refresh_handler,
+        simple_auth::forgot_password_handler,
+        simple_auth::reset_password_handler,
+        simple_auth::request_email_verification_handler,
+        simple_auth::verify_email_handler,
+        simple_auth::logout_handler,
+        simple_auth::change_password_handler,
+        simple_auth::two_factor_setup_handler,
+        simple_auth::two_factor_verify_handler,
+        simple_auth::two_factor_disable_handler,
+        simple_auth::request_delete_account_handler,
+        simple_auth::restore_account_handler,
+        simple_auth::get_sessions_handler,
+        simple_auth::revoke_session_handler,
+        simple_auth::revoke_all_sessions_handler,
+        simple_auth::create_subscription_handler,
+        simple_auth::subscription_status_handler,
+        simple_auth::cancel_subscription_handler,
+        simple_auth::change_plan_handler,
+        simple_auth::change_billing_period_handler,
+        database::get_chats_handler,
+        database::create_chat_handler,
+        database::import_chats_handler,
+        database::get_archived_chats_handler,
+        database::archive_chat_handler,
+        database::restore_chat_handler,
+        database::delete_chat_handler,
+        database::update_chat_title_handler,
+        database::get_messages_handler,
+        database::add_message_handler,
+        database::submit_message_feedback_handler,
+        database::get_message_revisions_handler,
+        database::get_cached_law_handler,
+        database::search_handler,
+        database::get_llm_usage_handler,
+        api::ask_question_handler,
+        api::regenerate_message_handler,
+        api::edit_message_handler,
+        api::auto_title_handler,
+        api::transcribe_audio_handler,
+        contracts::download_contract_handler,
+        contracts::contract_fields_handler,
+        contracts::update_contract_handler,
+    ),
+    components(schemas(
+        models::ErrorResponse,
+        models::AuthResponse,
+        models::UserStatusResponse,
+        models::PasswordResetResponse,
+        models::VerificationEmailResponse,
+        models::DeleteAccountRequest,
+        models::DeleteAccountResponse,
+        models::RestoreAccountResponse,
+        models::Chat,
+        models::ChatListItem,
+        models::ChatPage,
+        models::ChatListResponse,
+        models::MessagePreview,
+        models::Message,
+        models::MessagePage,
+        models::MessageListResponse,
+        models::MessageRevision,
+        models::CreateChatRequest,
+        models::CreateChatResponse,
+        models::AddMessageRequest,
+        models::SubmitFeedbackRequest,
+        models::SubmitFeedbackResponse,
+        models::GetCachedLawRequest,
+        models::LawCache,
+        models::QuestionRequest,
+        models::QuestionResponse,
+        models::GeneratedContract,
+        models::CitationCheck,
+        models::ResponseWarning,
+        models::Footnote,
+        models::LlmRequestRecord,
+        models::LlmUsageByModel,
+        models::LlmUsageResponse,
+        models::ChatSearchResult,
+        models::MessageSearchResult,
+        models::SearchResponse,
+        models::NormaChatExport,
+        models::ImportedChat,
+        models::ImportedMessage,
+        models::ChatImportRequest,
+        models::ChatImportResponse,
+        models::AutoTitleResponse,
+        models::ArchivedChat,
+        simple_auth::LinkUserRequest,
+        simple_auth::CheckProviderRequest,
+        simple_auth::CheckProviderResponse,
+        simple_auth::ForgotPasswordRequest,
+        simple_auth::ResetPasswordRequest,
+        simple_auth::VerifyEmailRequest,
+        simple_auth::RefreshRequest,
+        simple_auth::LogoutRequest,
+        simple_auth::CreateSubscriptionRequest,
+        simple_auth::MessageResponse,
+        simple_auth::SubscriptionResponse,
+        simple_auth::ChangePlanRequest,
+        simple_auth::ChangeBillingPeriodRequest,
+        simple_auth::SessionResponse,
+        simple_auth::RevokeSessionRequest,
+        simple_auth::ChangePasswordRequest,
+        simple_auth::TwoFactorCodeRequest,
+        database::UpdateChatTitleRequest,
+        database::UpdateChatTitleResponse,
+        api::RegenerateRequest,
+        api::EditMessageRequest,
+        api::EditMessageResponse,
+        api::TranscribeResponse,
+        contracts::ContractField,
+        contracts::ContractVersion,
+        contracts::ContractFieldsResponse,
+        contracts::UpdateContractRequest,
+        contracts::UpdateContractResponse,
+        crate::jurisdictions::Jurisdiction,
+        crate::transliteration::ResponseScript,
+        crate::pricing::Proration,
+        crate::promo_codes::AppliedPromoCode,
+        crate::promo_codes::DiscountType,
+        crate::sessions::GeoLocation,
+    )),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "auth", description = "Prijava, tokeni, lozinka, 2FA i brisanje naloga"),
+        (name = "sessions", description = "Aktivne sesije korisnika"),
+        (name = "subscription", description = "Pretplata i promena plana"),
+        (name = "chats", description = "Razgovori"),
+        (name = "messages", description = "Poruke u razgovorima"),
+        (name = "questions", description = "Pitanja, ponovljeni i izmenjeni odgovori, diktiranje"),
+        (name = "contracts", description = "Generisani ugovori"),
+        (name = "laws", description = "Keširani zakoni"),
+        (name = "usage", description = "Potrošnja LLM-a"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Registers the bearer_auth scheme the paths refer to
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some("Custom JWT from /api/auth/refresh or a Supabase access token"))
+                    .build(),
+            ),
+        );
+    }
+}
+
+const SWAGGER_UI_VERSION: &str = "5.17.14";
+
+/// GET /api/docs/openapi.json
+pub async fn openapi_json_handler() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// GET /api/docs - Swagger UI for the spec above
+pub async fn swagger_ui_handler() -> Html<String> {
+    Html(format!(
+        r##"<!DOCTYPE html>
+<html lang="sr">
+<head>
+  <meta charset="utf-8">
+  <title>Norma AI API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@{version}/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@{version}/swagger-ui-bundle.js" crossorigin></script>
+  <script>
+    window.ui = SwaggerUIBundle({{ url: "/api/docs/openapi.json", dom_id: "#swagger-ui", persistAuthorization: true }});
+  </script>
+</body>
+</html>"##,
+        version = SWAGGER_UI_VERSION
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_covers_api() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        for path in ["/api/question", "/api/chats/{chat_id}/messages", "/api/auth/refresh", "/api/contracts/{file_id}"] {
+            assert!(spec["paths"][path].is_object(), "missing {}", path);
+        }
+        for schema in ["ErrorResponse", "QuestionRequest", "QuestionResponse", "ChatListResponse", "Jurisdiction"] {
+            assert!(spec["components"]["schemas"][schema].is_object(), "missing schema {}", schema);
+        }
+        assert_eq!(spec["components"]["securitySchemes"]["bearer_auth"]["scheme"], "bearer");
+        assert_eq!(spec["paths"]["/api/chats"]["get"]["security"][0]["bearer_auth"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_schema_references_resolve() {
+        // Every $ref must point at a registered schema, or Swagger UI shows broken models
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let schemas = spec["components"]["schemas"].as_object().unwrap();
+        let text = spec.to_string();
+        for reference in text.split("\"#/components/schemas/").skip(1) {
+            let name = reference.split('"').next().unwrap();
+            assert!(schemas.contains_key(name), "unresolved $ref {}", name);
+        }
+    }
+}
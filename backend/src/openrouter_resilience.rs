@@ -0,0 +1,288 @@
+// Resilience layer for OpenRouter chat completions: retries with jittered exponential backoff on
+// 429 / 5xx / network errors, fallback to a secondary model once the primary one keeps failing,
+// and a per-model circuit breaker so a provider outage stops costing every question the full
+// retry budget. Breaker state is exported as the openrouter_circuit_state gauge.
+use rand::Rng;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const OPENROUTER_CHAT_URL: &str = "https://openrouter.ai/api/v1/chat/completions";
+
+// Defaults, overridable with OPENROUTER_MAX_RETRIES / OPENROUTER_RETRY_BASE_MS /
+// OPENROUTER_BREAKER_THRESHOLD / OPENROUTER_BREAKER_COOLDOWN_SECS
+const DEFAULT_MAX_RETRIES: u32 = 2;
+const DEFAULT_RETRY_BASE_MS: u64 = 500;
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(8);
+const DEFAULT_BREAKER_THRESHOLD: u32 = 5;
+const DEFAULT_BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32, // Retries per model after the first attempt
+    pub base_delay: Duration,
+    pub breaker_threshold: u32, // Consecutive failures that open the circuit
+    pub breaker_cooldown: Duration,
+}
+
+/// Overridden with OPENROUTER_MAX_RETRIES, OPENROUTER_RETRY_BASE_MS, OPENROUTER_BREAKER_THRESHOLD and
+/// OPENROUTER_BREAKER_COOLDOWN_SECS (see config.rs)
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: DEFAULT_MAX_RETRIES,
+            base_delay: Duration::from_millis(DEFAULT_RETRY_BASE_MS),
+            breaker_threshold: DEFAULT_BREAKER_THRESHOLD,
+            breaker_cooldown: DEFAULT_BREAKER_COOLDOWN,
+        }
+    }
+}
+
+impl RetryPolicy {
+
+    /// Full-jitter exponential backoff: random delay in [0, base * 2^attempt], capped
+    fn backoff(&self, attempt: u32, jitter: f64) -> Duration {
+        let ceiling = self.base_delay.saturating_mul(2u32.saturating_pow(attempt)).min(MAX_RETRY_DELAY);
+        ceiling.mul_f64(jitter.clamp(0.0, 1.0))
+    }
+}
+
+/// How a failed attempt should be handled
+#[derive(Debug, PartialEq)]
+enum Failure {
+    Retryable(String), // 429, 5xx, network errors, garbled 200 bodies - worth retrying or falling back
+    Fatal(String),     // Bad request, auth - retrying won't help
+}
+
+fn is_retryable_status(status: u16) -> bool {
+    status == 429 || status >= 500
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CircuitState {
+    Closed,
+    Open { until: Instant },
+    HalfOpen { until: Instant }, // Cooldown passed, one trial request is in flight until `until`
+}
+
+impl CircuitState {
+    fn metric_value(&self) -> f64 {
+        match self {
+            CircuitState::Closed => 0.0,
+            CircuitState::Open { .. } => 1.0,
+            CircuitState::HalfOpen { .. } => 2.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Breaker {
+    state: CircuitState,
+    consecutive_failures: u32,
+}
+
+impl Breaker {
+    fn new() -> Self {
+        Breaker { state: CircuitState::Closed, consecutive_failures: 0 }
+    }
+
+    /// Whether a request may be sent now; moves an expired open circuit to half-open.
+    /// A trial that never reports back (cancelled request) stops blocking after another cooldown.
+    fn allow(&mut self, now: Instant, policy: &RetryPolicy) -> bool {
+        match self.state {
+            CircuitState::Closed => true,
+            CircuitState::Open { until } | CircuitState::HalfOpen { until } if now >= until => {
+                self.state = CircuitState::HalfOpen { until: now + policy.breaker_cooldown };
+                true
+            }
+            CircuitState::Open { .. } | CircuitState::HalfOpen { .. } => false,
+        }
+    }
+
+    fn on_success(&mut self) {
+        self.state = CircuitState::Closed;
+        self.consecutive_failures = 0;
+    }
+
+    fn on_failure(&mut self, now: Instant, policy: &RetryPolicy) {
+        self.consecutive_failures += 1;
+        // A failed half-open trial re-opens immediately
+        if matches!(self.state, CircuitState::HalfOpen { .. }) || self.consecutive_failures >= policy.breaker_threshold {
+            self.state = CircuitState::Open { until: now + policy.breaker_cooldown };
+        }
+    }
+}
+
+static BREAKERS: OnceLock<Mutex<HashMap<String, Breaker>>> = OnceLock::new();
+
+fn with_breaker<T>(model: &str, f: impl FnOnce(&mut Breaker) -> T) -> T {
+    let mut breakers = BREAKERS.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap();
+    let breaker = breakers.entry(model.to_string()).or_insert_with(Breaker::new);
+    let result = f(breaker);
+    crate::metrics::set_openrouter_circuit_state(model, breaker.state.metric_value());
+    result
+}
+
+/// POST a chat completion, retrying and falling back as needed.
+///
+/// `body` is the full request JSON; its "model" field is set per attempt. Returns the parsed
+/// response and the model that produced it (the fallback model when the primary failed).
+pub async fn post_chat_completion<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    api_key: &str,
+    body: &serde_json::Value,
+    primary_model: &str,
+    fallback_model: Option<&str>,
+) -> Result<(T, String), String> {
+    let policy = crate::config::get().llm.retry;
+    let mut last_error = String::from("OpenRouter circuit open");
+
+    for (index, model) in std::iter::once(primary_model).chain(fallback_model).enumerate() {
+        if index > 0 {
+            println!("🔀 Falling back from {} to {}: {}", primary_model, model, last_error);
+            crate::metrics::record_openrouter_fallback(primary_model, model);
+        }
+
+        let mut body = body.clone();
+        body["model"] = serde_json::Value::String(model.to_string());
+
+        for attempt in 0..=policy.max_retries {
+            if !with_breaker(model, |breaker| breaker.allow(Instant::now(), &policy)) {
+                last_error = format!("OpenRouter circuit open for {}", model);
+                break;
+            }
+
+            match send_once(client, api_key, &body).await {
+                Ok(response) => {
+                    with_breaker(model, Breaker::on_success);
+                    return Ok((response, model.to_string()));
+                }
+                Err(Failure::Fatal(e)) => {
+                    // The provider answered - don't count it against the circuit
+                    with_breaker(model, Breaker::on_success);
+                    return Err(e);
+                }
+                Err(Failure::Retryable(e)) => {
+                    with_breaker(model, |breaker| breaker.on_failure(Instant::now(), &policy));
+                    last_error = e;
+                }
+            }
+
+            if attempt < policy.max_retries {
+                let delay = policy.backoff(attempt, rand::thread_rng().gen::<f64>());
+                eprintln!(
+                    "⚠️  OpenRouter attempt {} with {} failed ({}), retrying in {}ms",
+                    attempt + 1,
+                    model,
+                    last_error,
+                    delay.as_millis()
+                );
+                crate::metrics::record_openrouter_retry(model);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+
+    Err(last_error)
+}
+
+async fn send_once<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    api_key: &str,
+    body: &serde_json::Value,
+) -> Result<T, Failure> {
+    let response = client
+        .post(OPENROUTER_CHAT_URL)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Content-Type", "application/json")
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| Failure::Retryable(format!("API request failed: {}", e)))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        let e = format!("API error ({}): {}", status.as_u16(), error_text);
+        return Err(if is_retryable_status(status.as_u16()) { Failure::Retryable(e) } else { Failure::Fatal(e) });
+    }
+
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| Failure::Retryable(format!("Failed to read API response: {}", e)))?;
+    parse_body(&bytes)
+}
+
+/// A 200 whose body isn't a completion (truncated, an HTML error page from a proxy) is a provider
+/// failure like a 5xx: it counts against the circuit and is retried
+fn parse_body<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Failure> {
+    serde_json::from_slice(bytes).map_err(|e| Failure::Retryable(format!("Failed to parse API response: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 2,
+            base_delay: Duration::from_millis(500),
+            breaker_threshold: 3,
+            breaker_cooldown: Duration::from_secs(30),
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let policy = policy();
+        assert_eq!(policy.backoff(0, 1.0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2, 1.0), Duration::from_secs(2));
+        assert_eq!(policy.backoff(10, 1.0), MAX_RETRY_DELAY);
+        assert_eq!(policy.backoff(2, 0.5), Duration::from_secs(1));
+        assert_eq!(policy.backoff(1, 0.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_retryable_statuses() {
+        assert!(is_retryable_status(429));
+        assert!(is_retryable_status(502));
+        assert!(!is_retryable_status(400));
+        assert!(!is_retryable_status(401));
+    }
+
+    #[test]
+    fn test_unparseable_body_is_retryable() {
+        let garbled = parse_body::<serde_json::Value>(b"<html>Bad gateway</html>");
+        assert!(matches!(garbled, Err(Failure::Retryable(_))));
+        assert!(parse_body::<serde_json::Value>(br#"{"choices": []}"#).is_ok());
+    }
+
+    #[test]
+    fn test_breaker_opens_and_recovers() {
+        let policy = policy();
+        let now = Instant::now();
+        let mut breaker = Breaker::new();
+
+        for _ in 0..2 {
+            breaker.on_failure(now, &policy);
+        }
+        assert!(breaker.allow(now, &policy));
+        breaker.on_failure(now, &policy);
+        assert!(!breaker.allow(now, &policy));
+
+        // After the cooldown one trial request goes through; its failure re-opens the circuit
+        let later = now + policy.breaker_cooldown;
+        assert!(breaker.allow(later, &policy));
+        assert!(!breaker.allow(later, &policy));
+        breaker.on_failure(later, &policy);
+        assert!(!breaker.allow(later, &policy));
+
+        let much_later = later + policy.breaker_cooldown;
+        assert!(breaker.allow(much_later, &policy));
+        breaker.on_success();
+        assert_eq!(breaker.state, CircuitState::Closed);
+        assert!(breaker.allow(much_later, &policy));
+    }
+}
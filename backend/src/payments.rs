@@ -0,0 +1,840 @@
+// Card payments for web and desktop subscriptions through Stripe Checkout. The client asks for a
+// Checkout session and sends the browser to it; Stripe's webhooks then drive the subscription the
+// same way RevenueCat's do. Webhooks are stored in webhook_events (source 'stripe') and processed
+// by the same retry/dead-letter queue, ending in webhooks::update_user_subscription:
+// - checkout.session.completed: activate the plan, redeem the promo code, invoice the first charge
+// - invoice.paid: extend a renewed subscription and invoice the charge, also for the prorated
+//   difference charged when change_stripe_subscription moves it to another plan
+// - invoice.payment_failed: start the payment grace period (dunning.rs)
+// - customer.subscription.deleted: the subscription ended (cancelled, or unpaid after retries)
+// Stripe's event id is stored with each one, so a redelivered event is not applied twice.
+// Without STRIPE_* settings the endpoints are unavailable and create_subscription keeps activating
+// plans without payment, for development.
+use crate::config::StripeConfig;
+use crate::models::ApiError;
+use crate::revenuecat::SubscriptionStatus;
+use crate::simple_auth::AuthAppState;
+use crate::webhooks::{WebhookEventRecord, WebhookProcessError, WebhookResponse};
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use data_encoding::HEXLOWER_PERMISSIVE;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+type AppState = (PgPool, String, String, Option<String>, Option<String>, String); // (pool, api_key, jwt_secret, supabase_url, supabase_jwt_secret, resend_api_key)
+
+const STRIPE_API_BASE: &str = "https://api.stripe.com/v1";
+
+/// Oldest webhook signature timestamp accepted, against replayed deliveries
+const SIGNATURE_TOLERANCE_SECS: i64 = 300;
+
+/// Plan a subscription is sold as (premium was migrated to professional)
+pub fn account_type_for_plan(plan_id: &str) -> &'static str {
+    match plan_id {
+        "individual" => "individual",
+        "team" => "team",
+        _ => "professional",
+    }
+}
+
+/// Check a Stripe-Signature header ("t=<unix time>,v1=<hex HMAC-SHA256 of 't.payload'>"); any of
+/// several v1 signatures may match while the signing secret is being rolled
+pub fn verify_signature(header: &str, payload: &[u8], secret: &str, now: i64) -> bool {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signatures.push(value),
+            _ => {}
+        }
+    }
+    let Some(timestamp) = timestamp else {
+        return false;
+    };
+    if (now - timestamp).abs() > SIGNATURE_TOLERANCE_SECS {
+        return false;
+    }
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(payload);
+    signatures.iter().any(|signature| {
+        HEXLOWER_PERMISSIVE
+            .decode(signature.as_bytes())
+            .is_ok_and(|signature| mac.clone().verify_slice(&signature).is_ok())
+    })
+}
+
+/// The few Stripe API calls the subscription flow makes (form-encoded, as Stripe expects)
+pub struct StripeClient {
+    secret_key: String,
+    client: reqwest::Client,
+}
+
+impl StripeClient {
+    pub fn new(config: &StripeConfig) -> Self {
+        Self {
+            secret_key: config.secret_key.clone(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn post(&self, path: &str, form: &[(String, String)]) -> Result<serde_json::Value, String> {
+        let request = self.client.post(format!("{}{}", STRIPE_API_BASE, path)).form(form);
+        self.send(request).await?.ok_or_else(|| format!("Stripe returned 404 for {}", path))
+    }
+
+    /// GET an object, or None if Stripe doesn't know it
+    async fn get(&self, path: &str) -> Result<Option<serde_json::Value>, String> {
+        self.send(self.client.get(format!("{}{}", STRIPE_API_BASE, path))).await
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Option<serde_json::Value>, String> {
+        let response = request
+            .bearer_auth(&self.secret_key)
+            .send()
+            .await
+            .map_err(|e| format!("Stripe request failed: {}", e))?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let body: serde_json::Value = response.json().await.map_err(|e| format!("Invalid Stripe response: {}", e))?;
+        if !status.is_success() {
+            let message = body["error"]["message"].as_str().unwrap_or("unknown error");
+            return Err(format!("Stripe returned {}: {}", status, message));
+        }
+        Ok(Some(body))
+    }
+
+    /// The coupon taking a promo code's discount off the first charge. There is one per code and
+    /// discount, created the first time it's needed and reused by every later checkout.
+    async fn coupon_for_promo_code(&self, code: &str, discount_rsd: i32) -> Result<String, String> {
+        let id = coupon_id(code, discount_rsd);
+        let path = format!("/coupons/{}", id);
+        if self.get(&path).await?.is_some() {
+            return Ok(id);
+        }
+        let created = self
+            .post(
+                "/coupons",
+                &[
+                    ("id".to_string(), id.clone()),
+                    ("name".to_string(), code.to_string()),
+                    ("amount_off".to_string(), (discount_rsd as i64 * 100).to_string()),
+                    ("currency".to_string(), "rsd".to_string()),
+                    ("duration".to_string(), "once".to_string()),
+                ],
+            )
+            .await;
+        match created {
+            Ok(_) => Ok(id),
+            // Created by a concurrent checkout in the meantime
+            Err(e) => match self.get(&path).await? {
+                Some(_) => Ok(id),
+                None => Err(e),
+            },
+        }
+    }
+
+    async fn create_checkout_session(&self, form: &[(String, String)]) -> Result<(String, String), String> {
+        let session = self.post("/checkout/sessions", form).await?;
+        match (session["id"].as_str(), session["url"].as_str()) {
+            (Some(id), Some(url)) => Ok((id.to_string(), url.to_string())),
+            _ => Err("Stripe checkout session without an id or url".to_string()),
+        }
+    }
+
+    /// Move a subscription's item to a new price now. Stripe charges the prorated difference right
+    /// away and rejects the change when that charge fails, leaving the subscription as it was.
+    async fn change_price(
+        &self,
+        subscription_id: &str,
+        price_rsd: i32,
+        interval: &str,
+        plan: &str,
+        billing_period: &str,
+    ) -> Result<serde_json::Value, String> {
+        let path = format!("/subscriptions/{}", subscription_id);
+        let subscription = self
+            .get(&path)
+            .await?
+            .ok_or_else(|| format!("Stripe subscription {} not found", subscription_id))?;
+        let item = &subscription["items"]["data"][0];
+        let (Some(item_id), Some(product)) = (item["id"].as_str(), item["price"]["product"].as_str()) else {
+            return Err(format!("Stripe subscription {} without an item", subscription_id));
+        };
+
+        self.post(
+            &path,
+            &[
+                ("items[0][id]".to_string(), item_id.to_string()),
+                ("items[0][price_data][currency]".to_string(), "rsd".to_string()),
+                ("items[0][price_data][product]".to_string(), product.to_string()),
+                ("items[0][price_data][unit_amount]".to_string(), (price_rsd as i64 * 100).to_string()),
+                ("items[0][price_data][recurring][interval]".to_string(), interval.to_string()),
+                ("proration_behavior".to_string(), "always_invoice".to_string()),
+                ("payment_behavior".to_string(), "error_if_incomplete".to_string()),
+                ("cancel_at_period_end".to_string(), "false".to_string()),
+                ("metadata[plan]".to_string(), plan.to_string()),
+                ("metadata[billing_period]".to_string(), billing_period.to_string()),
+            ],
+        )
+        .await
+    }
+
+    /// Stop renewing; access lasts until the paid period ends
+    async fn cancel_at_period_end(&self, subscription_id: &str) -> Result<(), String> {
+        self.post(
+            &format!("/subscriptions/{}", subscription_id),
+            &[("cancel_at_period_end".to_string(), "true".to_string())],
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+/// Stripe id of a promo code's coupon (codes are letters, digits and dashes)
+fn coupon_id(code: &str, discount_rsd: i32) -> String {
+    format!("promo-{}-{}", code.to_lowercase(), discount_rsd)
+}
+
+/// Stop renewing the user's Stripe subscription, if they pay through Stripe
+pub async fn cancel_stripe_subscription(pool: &PgPool, user_id: Uuid) -> Result<(), String> {
+    let Some(stripe) = crate::config::get().stripe.as_ref() else {
+        return Ok(());
+    };
+    let subscription_id: Option<String> = sqlx::query_scalar("SELECT stripe_subscription_id FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .flatten();
+    match subscription_id {
+        Some(subscription_id) => StripeClient::new(stripe).cancel_at_period_end(&subscription_id).await,
+        None => Ok(()),
+    }
+}
+
+/// Switch the user's Stripe subscription to another plan or billing period. The prorated
+/// difference is charged by Stripe (and invoiced by its invoice.paid webhook); the plan only
+/// changes here once that charge went through. Returns the end of the paid period, or None when
+/// the user doesn't pay through Stripe.
+pub async fn change_stripe_subscription(
+    pool: &PgPool,
+    user_id: Uuid,
+    plan: &str,
+    billing_period: &str,
+    price_rsd: i32,
+) -> Result<Option<DateTime<Utc>>, String> {
+    let Some(stripe) = crate::config::get().stripe.as_ref() else {
+        return Ok(None);
+    };
+    let interval = match billing_period {
+        "monthly" => "month",
+        "yearly" => "year",
+        _ => return Err(format!("Unsupported billing period {}", billing_period)),
+    };
+    let subscription_id: Option<String> = sqlx::query_scalar("SELECT stripe_subscription_id FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .flatten();
+    let Some(subscription_id) = subscription_id else {
+        return Ok(None);
+    };
+
+    let account_type = account_type_for_plan(plan);
+    let subscription = StripeClient::new(stripe)
+        .change_price(&subscription_id, price_rsd, interval, account_type, billing_period)
+        .await?;
+    // Newer API versions keep the period on the item
+    let period_end = subscription["current_period_end"]
+        .as_i64()
+        .or_else(|| subscription["items"]["data"][0]["current_period_end"].as_i64())
+        .and_then(timestamp)
+        .unwrap_or_else(|| Utc::now() + crate::pricing::billing_period_length(billing_period));
+
+    let status = SubscriptionStatus {
+        account_type: account_type.to_string(),
+        subscription_type: Some(billing_period.to_string()),
+        expires_at: Some(period_end),
+        is_active: true,
+        platform: Some("web".to_string()),
+        in_grace_period: false,
+    };
+    crate::webhooks::update_user_subscription(pool, user_id, &status, false).await?;
+    Ok(Some(period_end))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckoutRequest {
+    pub plan_id: String,        // "individual", "professional", "team"
+    pub billing_period: String, // "monthly" or "yearly"
+    pub promo_code: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckoutResponse {
+    pub session_id: String,
+    pub checkout_url: String, // Stripe-hosted payment page to open
+    pub price_rsd: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub promo_code: Option<crate::promo_codes::AppliedPromoCode>, // Taken off the first charge
+}
+
+/// Start paying for a plan: a Stripe Checkout session for a recurring subscription at the plan's
+/// list price. The plan is activated by the checkout.session.completed webhook, not here.
+pub async fn create_checkout_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
+    headers: HeaderMap,
+    Json(request): Json<CheckoutRequest>,
+) -> Result<Json<CheckoutResponse>, ApiError> {
+    let stripe = crate::config::get().stripe.as_ref().ok_or_else(|| {
+        ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "PAYMENTS_UNAVAILABLE", "Plaćanje karticom trenutno nije dostupno")
+    })?;
+    let user_id = crate::database::authenticate(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let (interval, price_rsd) = match (
+        request.billing_period.as_str(),
+        crate::pricing::plan_price_rsd(&request.plan_id, &request.billing_period),
+    ) {
+        ("monthly", Some(price)) => ("month", price),
+        ("yearly", Some(price)) => ("year", price),
+        _ => return Err(ApiError::new(StatusCode::BAD_REQUEST, "INVALID_PLAN", "Nepodržan plan ili tip naplate")),
+    };
+    let account_type = account_type_for_plan(&request.plan_id);
+
+    let database_error = |e: sqlx::Error| {
+        error!("Checkout database error: {}", e);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Greška baze podataka")
+    };
+    let stripe_error = |e: String| {
+        error!(user_id = %user_id, "Stripe checkout failed: {}", e);
+        ApiError::new(StatusCode::BAD_GATEWAY, "PAYMENT_PROVIDER_ERROR", "Greška pri pokretanju plaćanja")
+    };
+
+    // Checked now so the user sees the discount; redeemed once the checkout completes
+    let promo_code = match request.promo_code.as_deref().filter(|code| !code.trim().is_empty()) {
+        Some(code) => {
+            let mut conn = pool.acquire().await.map_err(database_error)?;
+            Some(crate::promo_codes::check(&mut conn, user_id, code, price_rsd).await?)
+        }
+        None => None,
+    };
+
+    let (email, customer_id): (String, Option<String>) =
+        sqlx::query_as("SELECT email, stripe_customer_id FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await
+            .map_err(database_error)?;
+
+    let client = StripeClient::new(stripe);
+    let mut form: Vec<(String, String)> = vec![
+        ("mode".into(), "subscription".into()),
+        ("success_url".into(), format!("{}?checkout=success&session_id={{CHECKOUT_SESSION_ID}}", stripe.return_url)),
+        ("cancel_url".into(), format!("{}?checkout=cancelled", stripe.return_url)),
+        ("client_reference_id".into(), user_id.to_string()),
+        ("line_items[0][quantity]".into(), "1".into()),
+        ("line_items[0][price_data][currency]".into(), "rsd".into()),
+        ("line_items[0][price_data][unit_amount]".into(), (price_rsd as i64 * 100).to_string()),
+        ("line_items[0][price_data][recurring][interval]".into(), interval.into()),
+        (
+            "line_items[0][price_data][product_data][name]".into(),
+            crate::invoices::subscription_description(account_type, &request.billing_period),
+        ),
+        ("metadata[plan]".into(), account_type.into()),
+        ("metadata[billing_period]".into(), request.billing_period.clone()),
+        ("subscription_data[metadata][user_id]".into(), user_id.to_string()),
+    ];
+    match customer_id {
+        Some(customer_id) => form.push(("customer".into(), customer_id)),
+        None => form.push(("customer_email".into(), email)),
+    }
+    if let Some(promo_code) = &promo_code {
+        if promo_code.discount_rsd > 0 {
+            let coupon = client.coupon_for_promo_code(&promo_code.code, promo_code.discount_rsd).await.map_err(stripe_error)?;
+            form.push(("discounts[0][coupon]".into(), coupon));
+        }
+        form.push(("metadata[promo_code]".into(), promo_code.code.clone()));
+    }
+
+    let (session_id, checkout_url) = client.create_checkout_session(&form).await.map_err(stripe_error)?;
+    info!(user_id = %user_id, plan = account_type, billing_period = %request.billing_period, "Created Stripe checkout session {}", session_id);
+
+    Ok(Json(CheckoutResponse { session_id, checkout_url, price_rsd, promo_code }))
+}
+
+/// Receive a Stripe webhook: verify its signature, persist it and process it in the background
+pub async fn handle_stripe_webhook(
+    State((pool, api_key, _, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<WebhookResponse>, (StatusCode, String)> {
+    let Some(stripe) = crate::config::get().stripe.as_ref() else {
+        return Err((StatusCode::NOT_FOUND, "Stripe is not configured".to_string()));
+    };
+
+    let signature = headers.get("Stripe-Signature").and_then(|h| h.to_str().ok()).unwrap_or("");
+    if !verify_signature(signature, &body, &stripe.webhook_secret, Utc::now().timestamp()) {
+        warn!("Invalid Stripe webhook signature");
+        return Err((StatusCode::UNAUTHORIZED, "Invalid webhook signature".to_string()));
+    }
+
+    let payload: serde_json::Value =
+        serde_json::from_slice(&body).map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid webhook payload: {}", e)))?;
+    let event_type = payload["type"].as_str();
+    let user_id = payload["data"]["object"]["client_reference_id"].as_str();
+    info!("Received Stripe webhook: event_type={:?}, id={:?}", event_type, payload["id"].as_str());
+
+    let external_id = payload["id"].as_str();
+    let stored = crate::webhooks::store_webhook_event(&pool, "stripe", external_id, event_type, user_id, &payload)
+        .await
+        .map_err(|e| {
+            error!("Failed to persist webhook event: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to persist webhook event: {}", e))
+        })?;
+    let Some(event_id) = stored else {
+        info!("Ignoring redelivered Stripe webhook {:?}", external_id);
+        return Ok(Json(WebhookResponse { success: true, message: "Webhook already received".to_string() }));
+    };
+
+    tokio::spawn(async move {
+        crate::webhooks::process_webhook_event(&pool, &api_key, event_id).await;
+    });
+
+    Ok(Json(WebhookResponse {
+        success: true,
+        message: format!("Webhook accepted (event {})", event_id),
+    }))
+}
+
+// ==================== WEBHOOK EVENT PROCESSING ====================
+
+#[derive(Debug, Deserialize)]
+struct StripeEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    created: i64,
+    data: StripeEventData,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeEventData {
+    object: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckoutSession {
+    id: String,
+    client_reference_id: Option<String>,
+    customer: Option<String>,
+    subscription: Option<String>,
+    amount_total: Option<i64>,
+    currency: Option<String>,
+    #[serde(default)]
+    metadata: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeInvoice {
+    id: String,
+    subscription: Option<String>,
+    parent: Option<serde_json::Value>, // Where newer API versions put the subscription
+    billing_reason: Option<String>,
+    amount_paid: i64,
+    currency: String,
+    lines: InvoiceLines,
+}
+
+#[derive(Debug, Deserialize)]
+struct InvoiceLines {
+    data: Vec<InvoiceLine>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InvoiceLine {
+    period: InvoicePeriod,
+}
+
+#[derive(Debug, Deserialize)]
+struct InvoicePeriod {
+    start: i64,
+    end: i64,
+}
+
+impl StripeInvoice {
+    fn subscription_id(&self) -> Option<&str> {
+        self.subscription.as_deref().or_else(|| {
+            self.parent
+                .as_ref()
+                .and_then(|parent| parent["subscription_details"]["subscription"].as_str())
+        })
+    }
+
+    fn period(&self) -> Option<&InvoicePeriod> {
+        self.lines.data.first().map(|line| &line.period)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeSubscription {
+    id: String,
+    ended_at: Option<i64>,
+}
+
+/// The user paying for a Stripe subscription, with their current plan
+#[derive(sqlx::FromRow)]
+struct SubscribedUser {
+    id: Uuid,
+    account_type: String,
+    subscription_type: Option<String>,
+}
+
+async fn user_for_subscription(pool: &PgPool, subscription_id: &str) -> Result<Option<SubscribedUser>, WebhookProcessError> {
+    sqlx::query_as::<_, SubscribedUser>("SELECT id, account_type, subscription_type FROM users WHERE stripe_subscription_id = $1")
+        .bind(subscription_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| WebhookProcessError::Retryable(format!("Database error: {}", e)))
+}
+
+fn timestamp(seconds: i64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(seconds, 0)
+}
+
+fn parse_object<T: serde::de::DeserializeOwned>(object: &serde_json::Value) -> Result<T, WebhookProcessError> {
+    serde_json::from_value(object.clone()).map_err(|e| WebhookProcessError::Permanent(format!("Invalid Stripe object: {}", e)))
+}
+
+/// Invoice a Stripe charge of a subscription plan, keyed by the Stripe object that was paid
+fn stripe_charge<'a>(
+    plan: &'a str,
+    billing_period: &'a str,
+    total_minor: i64,
+    currency: &'a str,
+    paid_at: DateTime<Utc>,
+    external_id: &'a str,
+) -> crate::invoices::Charge<'a> {
+    crate::invoices::Charge {
+        description: crate::invoices::subscription_description(plan, billing_period),
+        plan: Some(plan),
+        billing_period: Some(billing_period),
+        total_minor,
+        currency,
+        paid_at,
+        source: "stripe",
+        external_id,
+    }
+}
+
+async fn invoice_charge(pool: &PgPool, user_id: Uuid, charge: crate::invoices::Charge<'_>) -> Result<(), WebhookProcessError> {
+    if charge.total_minor <= 0 {
+        return Ok(());
+    }
+    crate::invoices::record_charge(pool, user_id, charge)
+        .await
+        .map(|_| ())
+        .map_err(|e| WebhookProcessError::Retryable(format!("Failed to issue invoice: {}", e)))
+}
+
+/// Apply a stored Stripe event to the user's subscription; events the flow doesn't use are
+/// acknowledged and ignored
+pub async fn process_stripe_event(pool: &PgPool, record: &WebhookEventRecord) -> Result<(), WebhookProcessError> {
+    let event: StripeEvent = serde_json::from_value(record.payload.clone())
+        .map_err(|e| WebhookProcessError::Permanent(format!("Invalid webhook payload: {}", e)))?;
+    let created = timestamp(event.created).unwrap_or_else(Utc::now);
+
+    let (user_id, details) = match event.event_type.as_str() {
+        "checkout.session.completed" => {
+            let session: CheckoutSession = parse_object(&event.data.object)?;
+            checkout_completed(pool, record.id, &session, created).await?
+        }
+        "invoice.paid" => {
+            let invoice: StripeInvoice = parse_object(&event.data.object)?;
+            match invoice_paid(pool, record.id, &invoice, created).await? {
+                Some(result) => result,
+                None => return Ok(()),
+            }
+        }
+        "invoice.payment_failed" => {
+            let invoice: StripeInvoice = parse_object(&event.data.object)?;
+            match payment_failed(pool, &invoice).await? {
+                Some(result) => result,
+                None => return Ok(()),
+            }
+        }
+        "customer.subscription.deleted" => {
+            let subscription: StripeSubscription = parse_object(&event.data.object)?;
+            match subscription_deleted(pool, record.id, &subscription, created).await? {
+                Some(result) => result,
+                None => return Ok(()),
+            }
+        }
+        _ => return Ok(()),
+    };
+
+    crate::audit_log::record(
+        pool,
+        user_id,
+        crate::audit_log::AuditAction::SubscriptionSynced,
+        crate::audit_log::AuditSource::Webhook,
+        serde_json::json!({ "webhook_event_id": record.id, "event_type": event.event_type, "details": details }),
+    )
+    .await;
+    info!(user_id = %user_id, event_type = %event.event_type, "Updated user subscription from Stripe webhook");
+    Ok(())
+}
+
+async fn checkout_completed(
+    pool: &PgPool,
+    event_id: i64,
+    session: &CheckoutSession,
+    created: DateTime<Utc>,
+) -> Result<(Uuid, serde_json::Value), WebhookProcessError> {
+    let user_id = session
+        .client_reference_id
+        .as_deref()
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .ok_or_else(|| WebhookProcessError::Permanent(format!("Checkout session {} without a user", session.id)))?;
+    let (Some(plan), Some(billing_period)) = (session.metadata.get("plan"), session.metadata.get("billing_period")) else {
+        return Err(WebhookProcessError::Permanent(format!("Checkout session {} without a plan", session.id)));
+    };
+    let account_type = account_type_for_plan(plan);
+
+    let status = SubscriptionStatus {
+        account_type: account_type.to_string(),
+        subscription_type: Some(billing_period.clone()),
+        expires_at: Some(created + crate::pricing::billing_period_length(billing_period)),
+        is_active: true,
+        platform: Some("web".to_string()),
+        in_grace_period: false,
+    };
+    crate::webhooks::update_user_subscription(pool, user_id, &status, false)
+        .await
+        .map_err(|e| WebhookProcessError::Retryable(format!("Failed to update user: {}", e)))?;
+
+    sqlx::query(
+        "UPDATE users SET
+            stripe_customer_id = COALESCE($2, stripe_customer_id),
+            stripe_subscription_id = COALESCE($3, stripe_subscription_id)
+         WHERE id = $1",
+    )
+    .bind(user_id)
+    .bind(session.customer.as_deref())
+    .bind(session.subscription.as_deref())
+    .execute(pool)
+    .await
+    .map_err(|e| WebhookProcessError::Retryable(format!("Failed to store Stripe ids: {}", e)))?;
+
+    if let Some(code) = session.metadata.get("promo_code") {
+        redeem_promo_code(pool, user_id, code, account_type, billing_period).await?;
+    }
+
+    crate::webhooks::notify_subscription_change(pool, user_id, event_id, "Pretplata je aktivirana", None).await;
+
+    let currency = session.currency.as_deref().unwrap_or("rsd").to_uppercase();
+    let charge = stripe_charge(account_type, billing_period, session.amount_total.unwrap_or(0), &currency, created, &session.id);
+    invoice_charge(pool, user_id, charge).await?;
+
+    Ok((user_id, serde_json::json!({ "plan": account_type, "billing_period": billing_period })))
+}
+
+/// Count the promo code Stripe already discounted. A code used up (or replayed) in the meantime is
+/// only logged - the customer has paid the discounted price either way.
+async fn redeem_promo_code(
+    pool: &PgPool,
+    user_id: Uuid,
+    code: &str,
+    plan: &str,
+    billing_period: &str,
+) -> Result<(), WebhookProcessError> {
+    let price_rsd = crate::pricing::plan_price_rsd(plan, billing_period).unwrap_or(0);
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| WebhookProcessError::Retryable(format!("Database error: {}", e)))?;
+    match crate::promo_codes::redeem(&mut tx, user_id, code, plan, billing_period, price_rsd).await {
+        Ok(_) => tx
+            .commit()
+            .await
+            .map_err(|e| WebhookProcessError::Retryable(format!("Database error: {}", e))),
+        Err(crate::promo_codes::PromoCodeError::Database(e)) => Err(WebhookProcessError::Retryable(format!("Database error: {}", e))),
+        Err(e) => {
+            warn!(user_id = %user_id, "Promo code {} not redeemed after checkout: {}", code, e.code());
+            Ok(())
+        }
+    }
+}
+
+/// A renewal or a plan change (billing_reason subscription_update) was charged. The first invoice
+/// of a subscription is handled with its checkout session.
+async fn invoice_paid(
+    pool: &PgPool,
+    event_id: i64,
+    invoice: &StripeInvoice,
+    created: DateTime<Utc>,
+) -> Result<Option<(Uuid, serde_json::Value)>, WebhookProcessError> {
+    if invoice.billing_reason.as_deref() == Some("subscription_create") {
+        return Ok(None);
+    }
+    let Some(subscription_id) = invoice.subscription_id() else {
+        return Ok(None); // Not a subscription charge
+    };
+    // The checkout may not have been processed yet - retried until it is
+    let user = user_for_subscription(pool, subscription_id)
+        .await?
+        .ok_or_else(|| WebhookProcessError::Retryable(format!("No user for Stripe subscription {}", subscription_id)))?;
+    let billing_period = user.subscription_type.clone().unwrap_or_else(|| "monthly".to_string());
+
+    let status = SubscriptionStatus {
+        account_type: user.account_type.clone(),
+        subscription_type: Some(billing_period.clone()),
+        expires_at: invoice.period().and_then(|period| timestamp(period.end)),
+        is_active: true,
+        platform: Some("web".to_string()),
+        in_grace_period: false,
+    };
+    crate::webhooks::update_user_subscription(pool, user.id, &status, false)
+        .await
+        .map_err(|e| WebhookProcessError::Retryable(format!("Failed to update user: {}", e)))?;
+
+    let notification = match invoice.billing_reason.as_deref() {
+        Some("subscription_update") => "Promena plana je naplaćena",
+        _ => "Pretplata je obnovljena",
+    };
+    crate::webhooks::notify_subscription_change(pool, user.id, event_id, notification, None).await;
+    let currency = invoice.currency.to_uppercase();
+    let charge = stripe_charge(&user.account_type, &billing_period, invoice.amount_paid, &currency, created, &invoice.id);
+    invoice_charge(pool, user.id, charge).await?;
+
+    Ok(Some((user.id, serde_json::json!({ "invoice": invoice.id, "expires_at": status.expires_at }))))
+}
+
+/// A renewal charge failed; Stripe keeps retrying it while the user is in the grace period
+async fn payment_failed(pool: &PgPool, invoice: &StripeInvoice) -> Result<Option<(Uuid, serde_json::Value)>, WebhookProcessError> {
+    let Some(subscription_id) = invoice.subscription_id() else {
+        return Ok(None);
+    };
+    let Some(user) = user_for_subscription(pool, subscription_id).await? else {
+        return Ok(None); // The first charge failed at checkout - nothing was activated
+    };
+
+    let status = SubscriptionStatus {
+        account_type: user.account_type.clone(),
+        subscription_type: user.subscription_type.clone(),
+        expires_at: invoice.period().and_then(|period| timestamp(period.start)), // End of what was paid
+        is_active: false,
+        platform: Some("web".to_string()),
+        in_grace_period: true,
+    };
+    crate::webhooks::update_user_subscription(pool, user.id, &status, true)
+        .await
+        .map_err(|e| WebhookProcessError::Retryable(format!("Failed to update user: {}", e)))?;
+
+    Ok(Some((user.id, serde_json::json!({ "invoice": invoice.id, "billing_issue": true }))))
+}
+
+/// The subscription ended - cancelled at the end of its period, or given up on after failed retries
+async fn subscription_deleted(
+    pool: &PgPool,
+    event_id: i64,
+    subscription: &StripeSubscription,
+    created: DateTime<Utc>,
+) -> Result<Option<(Uuid, serde_json::Value)>, WebhookProcessError> {
+    let Some(user) = user_for_subscription(pool, &subscription.id).await? else {
+        return Ok(None);
+    };
+
+    let status = SubscriptionStatus {
+        account_type: user.account_type.clone(),
+        subscription_type: user.subscription_type.clone(),
+        expires_at: Some(subscription.ended_at.and_then(timestamp).unwrap_or(created)),
+        is_active: false,
+        platform: Some("web".to_string()),
+        in_grace_period: false,
+    };
+    crate::webhooks::update_user_subscription(pool, user.id, &status, false)
+        .await
+        .map_err(|e| WebhookProcessError::Retryable(format!("Failed to update user: {}", e)))?;
+
+    crate::webhooks::notify_subscription_change(pool, user.id, event_id, "Pretplata je istekla", None).await;
+    Ok(Some((user.id, serde_json::json!({ "subscription": subscription.id, "ended": true }))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(payload: &[u8], secret: &str, timestamp: i64) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(format!("{}.", timestamp).as_bytes());
+        mac.update(payload);
+        format!("t={},v1={}", timestamp, data_encoding::HEXLOWER.encode(&mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let payload = br#"{"id":"evt_1","type":"invoice.paid"}"#;
+        let now = 1_760_000_000;
+        let header = sign(payload, "whsec_test", now);
+        assert!(verify_signature(&header, payload, "whsec_test", now + 10));
+        assert!(!verify_signature(&header, payload, "whsec_other", now));
+        assert!(!verify_signature(&header, br#"{"id":"evt_2"}"#, "whsec_test", now));
+        // Too old
+        assert!(!verify_signature(&header, payload, "whsec_test", now + SIGNATURE_TOLERANCE_SECS + 1));
+        // Any of several signatures may match
+        let rolled = format!("{},v1=00ff", header);
+        assert!(verify_signature(&rolled, payload, "whsec_test", now));
+        assert!(!verify_signature("v1=00ff", payload, "whsec_test", now));
+    }
+
+    #[test]
+    fn test_invoice_subscription_id() {
+        let invoice = |extra: serde_json::Value| {
+            let mut object = serde_json::json!({
+                "id": "in_1",
+                "amount_paid": 340000,
+                "currency": "rsd",
+                "lines": { "data": [{ "period": { "start": 1_760_000_000, "end": 1_762_592_000 } }] },
+            });
+            object.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            serde_json::from_value::<StripeInvoice>(object).unwrap()
+        };
+
+        assert_eq!(invoice(serde_json::json!({ "subscription": "sub_1" })).subscription_id(), Some("sub_1"));
+        let newer = invoice(serde_json::json!({ "parent": { "subscription_details": { "subscription": "sub_2" } } }));
+        assert_eq!(newer.subscription_id(), Some("sub_2"));
+        assert_eq!(newer.period().unwrap().end, 1_762_592_000);
+        assert_eq!(invoice(serde_json::json!({})).subscription_id(), None);
+    }
+
+    #[test]
+    fn test_coupon_id() {
+        assert_eq!(coupon_id("LETO-2026", 500), "promo-leto-2026-500");
+        // Same code, different discount (a percentage of another price) - a separate coupon
+        assert_ne!(coupon_id("LETO-2026", 500), coupon_id("LETO-2026", 1000));
+    }
+
+    #[test]
+    fn test_account_type_for_plan() {
+        assert_eq!(account_type_for_plan("individual"), "individual");
+        assert_eq!(account_type_for_plan("premium"), "professional");
+        assert_eq!(account_type_for_plan("team"), "team");
+    }
+}
@@ -0,0 +1,330 @@
+// Opt-in redaction of personal data in stored messages, for firms that can't keep client data in
+// plaintext on our servers. With users.pii_redaction_enabled set, names, JMBGs and addresses in a
+// message are replaced with placeholders ([OSOBA_1], [JMBG_1], [ADRESA_1]) before it is saved. The
+// placeholder -> value mapping is stored next to the message, encrypted with a key derived for the
+// chat's owner, and reversed only when the owner reads the chat. Everything else reading stored
+// messages (answer history, shared chats, data exports) sees the placeholders. Generated chat
+// titles are redacted too, without a mapping. Redaction needs PII_ENCRYPTION_KEY: it can't be
+// turned on without one, and the server won't start without it while any user has it on.
+use crate::models::{Message, MessageRow};
+use crate::secret_box;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PiiKind {
+    Jmbg,
+    Address,
+    Person,
+}
+
+impl PiiKind {
+    fn label(&self) -> &'static str {
+        match self {
+            PiiKind::Jmbg => "JMBG",
+            PiiKind::Address => "ADRESA",
+            PiiKind::Person => "OSOBA",
+        }
+    }
+}
+
+/// Rules in the order they run - addresses before names, so "Kneza Miloša 5" stays one value.
+/// A `value` group narrows what is replaced to part of the match (the name after "klijent").
+const RULES: &[(PiiKind, &str)] = &[
+    (PiiKind::Jmbg, r"\b(?P<value>\d{13})\b"),
+    (
+        PiiKind::Address,
+        r"(?i:\b(ulic[aieu]|ul\.|bulevar\w*|bul\.|trg\w*|улиц[аеиу]|ул\.|булевар\w*|трг\w*))\s+(?P<value>([\p{L}.]+\s+){1,4}?(br\.\s*|бр\.\s*)?\d+[\p{L}]?(/\d+)?)",
+    ),
+    // After a word naming a party to a case: "klijent Marko", "tuženi Jovan Petrović"
+    (
+        PiiKind::Person,
+        r"(?i:\b(gospodin\w*|gospođ\w*|g\.|gđa\.?|klijent\w*|tužil\w*|tužen\w*|okrivljen\w*|oštećen\w*|svedo\w*|imenom|zove\s+se|господин\w*|госпођ\w*|клијент\w*|тужил\w*|тужен\w*|окривљен\w*|оштећен\w*|сведо\w*|именом))\s+(?P<value>\p{Lu}\p{Ll}+(\s+\p{Lu}\p{Ll}+)?)",
+    ),
+    // First name followed by a typical surname: "Marko Petrović", "Ани Јовановић"
+    (PiiKind::Person, r"\b(?P<value>\p{Lu}\p{Ll}+\s+\p{Lu}\p{Ll}*(ić|ић)(a|u|em|а|у|ем)?)\b"),
+];
+
+fn rules() -> &'static [(PiiKind, Regex)] {
+    static PATTERNS: OnceLock<Vec<(PiiKind, Regex)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| RULES.iter().map(|(kind, pattern)| (*kind, Regex::new(pattern).unwrap())).collect())
+}
+
+/// JMBG control digit (mod 11), so order numbers and other 13-digit numbers aren't redacted
+fn is_valid_jmbg(digits: &str) -> bool {
+    let digits: Vec<u32> = digits.chars().filter_map(|c| c.to_digit(10)).collect();
+    if digits.len() != 13 {
+        return false;
+    }
+    let weighted: u32 = (0..6).map(|i| (7 - i as u32) * (digits[i] + digits[i + 6])).sum();
+    let control = match 11 - weighted % 11 {
+        10 | 11 => 0,
+        control => control,
+    };
+    control == digits[12]
+}
+
+/// Placeholder -> original value for one message
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PiiMapping(BTreeMap<String, String>);
+
+impl PiiMapping {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The placeholder for a value - the same one each time the value repeats in the message
+    fn placeholder_for(&mut self, kind: PiiKind, value: &str) -> String {
+        if let Some((placeholder, _)) = self.0.iter().find(|(_, original)| original.as_str() == value) {
+            return placeholder.clone();
+        }
+        let prefix = format!("[{}_", kind.label());
+        let number = self.0.keys().filter(|placeholder| placeholder.starts_with(&prefix)).count() + 1;
+        let placeholder = format!("{}{}]", prefix, number);
+        self.0.insert(placeholder.clone(), value.to_string());
+        placeholder
+    }
+
+    /// Put the original values back
+    pub fn restore(&self, text: &str) -> String {
+        self.0
+            .iter()
+            .fold(text.to_string(), |text, (placeholder, original)| text.replace(placeholder, original))
+    }
+}
+
+/// Replace names, JMBGs and addresses with placeholders
+pub fn redact(text: &str) -> (String, PiiMapping) {
+    let mut mapping = PiiMapping::default();
+    let mut redacted = text.to_string();
+    for (kind, pattern) in rules() {
+        redacted = pattern
+            .replace_all(&redacted, |captures: &Captures| {
+                let whole = captures.get(0).unwrap();
+                let value = captures.name("value").unwrap_or(whole);
+                if *kind == PiiKind::Jmbg && !is_valid_jmbg(value.as_str()) {
+                    return whole.as_str().to_string();
+                }
+                let start = value.start() - whole.start();
+                let end = value.end() - whole.start();
+                format!(
+                    "{}{}{}",
+                    &whole.as_str()[..start],
+                    mapping.placeholder_for(*kind, value.as_str()),
+                    &whole.as_str()[end..]
+                )
+            })
+            .into_owned();
+    }
+    (redacted, mapping)
+}
+
+fn derive_user_key(key_material: &str, user_id: Uuid) -> secret_box::Key {
+    secret_box::derive_key("normaai-pii", &format!("{}:{}", key_material, user_id))
+}
+
+/// PII_ENCRYPTION_KEY with the user's ID mixed in
+fn user_key(user_id: Uuid) -> Result<secret_box::Key, String> {
+    let key_material = crate::config::get()
+        .pii_encryption_key
+        .as_deref()
+        .ok_or_else(|| "PII_ENCRYPTION_KEY is not set".to_string())?;
+    Ok(derive_user_key(key_material, user_id))
+}
+
+/// Key of mappings sealed before PII_ENCRYPTION_KEY was required, derived from JWT_SECRET. They
+/// are still opened with it; nothing is sealed with it any more.
+fn legacy_user_key(user_id: Uuid) -> secret_box::Key {
+    derive_user_key(&crate::config::get().jwt_secret, user_id)
+}
+
+/// Whether redaction can be turned on
+pub fn available() -> bool {
+    crate::config::get().pii_encryption_key.is_some()
+}
+
+/// Refuse to run without PII_ENCRYPTION_KEY while any user has redaction on - their mappings
+/// couldn't be sealed
+pub async fn check_key_configured(pool: &PgPool) -> Result<(), String> {
+    if available() {
+        return Ok(());
+    }
+    let enabled: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE pii_redaction_enabled")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to check PII redaction settings: {}", e))?;
+    if enabled > 0 {
+        return Err(format!("PII_ENCRYPTION_KEY is required: {} user(s) have personal data redaction on", enabled));
+    }
+    Ok(())
+}
+
+fn seal_with(key: &secret_box::Key, user_id: Uuid, mapping: &PiiMapping) -> Result<String, String> {
+    let json = serde_json::to_vec(mapping).map_err(|e| format!("Failed to serialize PII mapping: {}", e))?;
+    secret_box::seal(key, &json, user_id.as_bytes())
+}
+
+fn open_with(key: &secret_box::Key, user_id: Uuid, stored: &str) -> Result<PiiMapping, String> {
+    let json = secret_box::open(key, stored, user_id.as_bytes())?;
+    serde_json::from_slice(&json).map_err(|e| format!("Invalid PII mapping: {}", e))
+}
+
+pub fn seal_mapping(user_id: Uuid, mapping: &PiiMapping) -> Result<String, String> {
+    seal_with(&user_key(user_id)?, user_id, mapping)
+}
+
+pub fn open_mapping(user_id: Uuid, stored: &str) -> Result<PiiMapping, String> {
+    match user_key(user_id).and_then(|key| open_with(&key, user_id, stored)) {
+        Ok(mapping) => Ok(mapping),
+        Err(e) => open_with(&legacy_user_key(user_id), user_id, stored).map_err(|_| e),
+    }
+}
+
+pub async fn redaction_enabled(user_id: Uuid, pool: &PgPool) -> Result<bool, sqlx::Error> {
+    let enabled: Option<bool> = sqlx::query_scalar("SELECT pii_redaction_enabled FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(enabled.unwrap_or(false))
+}
+
+/// Redacted content and its sealed mapping (None when nothing was found)
+pub fn redact_for_user(user_id: Uuid, content: &str) -> Result<(String, Option<String>), String> {
+    let (redacted, mapping) = redact(content);
+    if mapping.is_empty() {
+        return Ok((redacted, None));
+    }
+    crate::metrics::record_pii_redaction(mapping.0.len());
+    Ok((redacted, Some(seal_mapping(user_id, &mapping)?)))
+}
+
+/// What to store for a message in a chat: the content as is, or redacted with its sealed mapping
+/// when the chat's owner has redaction on
+pub async fn prepare_for_storage(chat_id: i64, content: String, pool: &PgPool) -> Result<(String, Option<String>), String> {
+    let owner: Option<Uuid> = sqlx::query_scalar(
+        "SELECT u.id FROM chats c JOIN users u ON u.id = c.user_id WHERE c.id = $1 AND u.pii_redaction_enabled",
+    )
+    .bind(chat_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to check PII redaction setting: {}", e))?;
+
+    match owner {
+        Some(owner) => redact_for_user(owner, &content),
+        None => Ok((content, None)),
+    }
+}
+
+/// A generated chat title to store: redacted when the chat's owner has redaction on. Titles keep
+/// their placeholders - there is no mapping to restore them from.
+pub async fn prepare_title_for_storage(chat_id: i64, title: &str, pool: &PgPool) -> Result<String, String> {
+    let redacting: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM chats c JOIN users u ON u.id = c.user_id WHERE c.id = $1 AND u.pii_redaction_enabled)",
+    )
+    .bind(chat_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to check PII redaction setting: {}", e))?;
+
+    Ok(if redacting { redact(title).0 } else { title.to_string() })
+}
+
+/// Put redacted values back into messages the owner reads. A mapping that can't be opened
+/// (PII_ENCRYPTION_KEY changed) leaves the placeholders.
+pub fn restore_messages(user_id: Uuid, rows: Vec<MessageRow>) -> Vec<Message> {
+    rows.into_iter()
+        .map(|row| {
+            let mut message = row.message;
+            if let Some(stored) = row.pii_mapping_encrypted {
+                match open_mapping(user_id, &stored) {
+                    Ok(mapping) => message.content = mapping.restore(&message.content),
+                    Err(e) => eprintln!("Failed to open PII mapping of message {}: {}", message.id, e),
+                }
+            }
+            message
+        })
+        .collect()
+}
+
+/// Re-seal the mappings of a user's chats for the user they are about to move to (account
+/// merges), since each mapping is sealed with its owner's key
+pub async fn reseal_mappings(conn: &mut PgConnection, from_user_id: Uuid, to_user_id: Uuid) -> Result<(), sqlx::Error> {
+    let sealed: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT m.id, m.pii_mapping_encrypted FROM messages m JOIN chats c ON c.id = m.chat_id
+         WHERE c.user_id = $1 AND m.pii_mapping_encrypted IS NOT NULL",
+    )
+    .bind(from_user_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for (message_id, stored) in sealed {
+        let resealed = open_mapping(from_user_id, &stored).and_then(|mapping| seal_mapping(to_user_id, &mapping));
+        match resealed {
+            Ok(resealed) => {
+                sqlx::query("UPDATE messages SET pii_mapping_encrypted = $2 WHERE id = $1")
+                    .bind(message_id)
+                    .bind(resealed)
+                    .execute(&mut *conn)
+                    .await?;
+            }
+            Err(e) => eprintln!("Failed to re-seal PII mapping of message {}: {}", message_id, e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jmbg_control_digit() {
+        assert!(is_valid_jmbg("0101990710008"));
+        assert!(!is_valid_jmbg("0101990710009"));
+        assert!(!is_valid_jmbg("123"));
+    }
+
+    #[test]
+    fn test_redacts_and_restores() {
+        let text = "Klijent Marko, JMBG 0101990710008, živi u ulici Kneza Miloša 5. Tuženi je Jovan Petrović, a Marko traži naknadu od Jovana Petrovića.";
+        let (redacted, mapping) = redact(text);
+        assert!(!redacted.contains("0101990710008"));
+        assert!(!redacted.contains("Kneza Miloša"));
+        assert!(!redacted.contains("Jovan Petrović"));
+        assert!(redacted.contains("Klijent [OSOBA_1]"));
+        assert!(redacted.contains("JMBG [JMBG_1]"));
+        assert!(redacted.contains("ulici [ADRESA_1]"));
+        assert_eq!(mapping.restore(&redacted), text);
+    }
+
+    #[test]
+    fn test_repeated_value_keeps_its_placeholder() {
+        let (redacted, mapping) = redact("Ana Marković je tužila Ana Marković?");
+        assert_eq!(redacted, "[OSOBA_1] je tužila [OSOBA_1]?");
+        assert_eq!(mapping.0.len(), 1);
+    }
+
+    #[test]
+    fn test_leaves_legal_text_alone() {
+        let text = "Prema članu 12. Zakona o radu, Republika Srbija propisuje otkazni rok. Broj predmeta 1234567890123.";
+        let (redacted, mapping) = redact(text);
+        assert_eq!(redacted, text);
+        assert!(mapping.is_empty());
+    }
+
+    #[test]
+    fn test_mapping_is_sealed_per_user() {
+        let mut mapping = PiiMapping::default();
+        mapping.placeholder_for(PiiKind::Person, "Marko Petrović");
+        let (user_id, other_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let key = derive_user_key("secret", user_id);
+        let sealed = seal_with(&key, user_id, &mapping).unwrap();
+        assert_eq!(open_with(&key, user_id, &sealed).unwrap(), mapping);
+        assert!(open_with(&derive_user_key("secret", other_id), other_id, &sealed).is_err());
+        assert!(open_with(&key, other_id, &sealed).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+// Web subscription prices and proration of mid-cycle plan changes. Switching plans starts a new
+// billing period right away; the unused part of what the old period was paid with
+// (users.subscription_paid_rsd) is credited against the new one. An upgrade charges the
+// difference, a downgrade leaves a remainder that is kept as account credit
+// (users.account_credit_rsd) and taken off the next charge. Nothing is credited for a period that
+// wasn't paid for.
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// List price in RSD (VAT included) of a plan for a billing period
+pub fn plan_price_rsd(plan: &str, billing_period: &str) -> Option<i32> {
+    match (plan, billing_period) {
+        ("individual", "monthly") => Some(3400),
+        ("individual", "yearly") => Some(34000),
+        ("professional" | "premium", "monthly") => Some(6400), // Premium was migrated to professional
+        ("professional" | "premium", "yearly") => Some(64000),
+        ("team", "monthly") => Some(24900), // Base team price
+        ("team", "yearly") => Some(249000),
+        _ => None,
+    }
+}
+
+pub fn billing_period_length(billing_period: &str) -> Duration {
+    if billing_period == "yearly" {
+        Duration::days(365)
+    } else {
+        Duration::days(30)
+    }
+}
+
+/// The paid period a plan change interrupts
+#[derive(Debug, Clone)]
+pub struct CurrentPeriod {
+    pub plan: String,
+    pub billing_period: String,
+    pub started_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub paid_rsd: i32, // Charged for it, plus account credit applied
+}
+
+/// How a plan change was priced, returned with the new subscription
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct Proration {
+    pub previous_plan: Option<String>,
+    pub previous_billing_period: Option<String>,
+    pub unused_days: i64,
+    pub unused_credit_rsd: i32,      // Value of the unused part of the old period
+    pub account_credit_used_rsd: i32, // Existing account credit applied
+    pub new_price_rsd: i32,
+    pub amount_due_rsd: i32,
+    pub account_credit_rsd: i32, // Balance after the change
+}
+
+impl Proration {
+    /// Part of the new price covered by credit, i.e. what the new period is paid with before any charge
+    pub fn covered_rsd(&self) -> i32 {
+        self.new_price_rsd - self.amount_due_rsd
+    }
+}
+
+/// Price a switch to a plan costing `new_price_rsd` at `now`. Without a current paid period (or
+/// once it has ended) there's nothing to credit beyond the existing account credit.
+pub fn prorate(current: Option<&CurrentPeriod>, new_price_rsd: i32, account_credit_rsd: i32, now: DateTime<Utc>) -> Proration {
+    let (unused_credit_rsd, unused_days) = current
+        .and_then(|period| {
+            let price = period.paid_rsd.max(0) as i64;
+            let length = (period.ends_at - period.started_at).num_seconds();
+            if length <= 0 {
+                return None;
+            }
+            let remaining = (period.ends_at - now).num_seconds().clamp(0, length);
+            // Rounded to the nearest dinar
+            let credit = (2 * price * remaining + length) / (2 * length);
+            Some((credit as i32, remaining / 86_400))
+        })
+        .unwrap_or((0, 0));
+
+    let account_credit = account_credit_rsd.max(0);
+    let account_credit_used_rsd = account_credit.min((new_price_rsd - unused_credit_rsd).max(0));
+
+    Proration {
+        previous_plan: current.map(|period| period.plan.clone()),
+        previous_billing_period: current.map(|period| period.billing_period.clone()),
+        unused_days,
+        unused_credit_rsd,
+        account_credit_used_rsd,
+        new_price_rsd,
+        amount_due_rsd: (new_price_rsd - unused_credit_rsd - account_credit_used_rsd).max(0),
+        account_credit_rsd: account_credit - account_credit_used_rsd + (unused_credit_rsd - new_price_rsd).max(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A period paid at the plan's list price
+    fn period(plan: &str, billing_period: &str, days_used: i64, now: DateTime<Utc>) -> CurrentPeriod {
+        let started_at = now - Duration::days(days_used);
+        CurrentPeriod {
+            plan: plan.to_string(),
+            billing_period: billing_period.to_string(),
+            started_at,
+            ends_at: started_at + billing_period_length(billing_period),
+            paid_rsd: plan_price_rsd(plan, billing_period).unwrap_or(0),
+        }
+    }
+
+    #[test]
+    fn test_upgrade_charges_the_difference() {
+        let now = Utc::now();
+        // 10 of 30 days used: 2/3 of 3400 = 2267 credited against 6400
+        let proration = prorate(Some(&period("individual", "monthly", 10, now)), 6400, 0, now);
+        assert_eq!(proration.unused_days, 20);
+        assert_eq!(proration.unused_credit_rsd, 2267);
+        assert_eq!(proration.amount_due_rsd, 4133);
+        assert_eq!(proration.account_credit_rsd, 0);
+        assert_eq!(proration.covered_rsd(), 2267);
+    }
+
+    #[test]
+    fn test_downgrade_keeps_the_rest_as_credit() {
+        let now = Utc::now();
+        // Half of a yearly professional plan (32000) is worth more than a month of individual
+        let proration = prorate(Some(&period("professional", "yearly", 182, now)), 3400, 500, now);
+        assert_eq!(proration.amount_due_rsd, 0);
+        assert_eq!(proration.account_credit_used_rsd, 0);
+        assert_eq!(proration.account_credit_rsd, proration.unused_credit_rsd + 500 - 3400);
+
+        // Existing credit is used before anything is charged
+        let proration = prorate(None, 3400, 1000, now);
+        assert_eq!((proration.account_credit_used_rsd, proration.amount_due_rsd, proration.account_credit_rsd), (1000, 2400, 0));
+    }
+
+    #[test]
+    fn test_no_credit_for_ended_or_unpaid_periods() {
+        let now = Utc::now();
+        assert_eq!(prorate(Some(&period("individual", "monthly", 45, now)), 6400, 0, now).amount_due_rsd, 6400);
+        assert_eq!(prorate(Some(&period("trial_registered", "monthly", 5, now)), 6400, 0, now).unused_credit_rsd, 0);
+
+        // Activated without payment: switching to a cheaper plan leaves no credit
+        let unpaid = CurrentPeriod { paid_rsd: 0, ..period("professional", "yearly", 10, now) };
+        let proration = prorate(Some(&unpaid), 3400, 0, now);
+        assert_eq!((proration.unused_credit_rsd, proration.account_credit_rsd), (0, 0));
+    }
+}
@@ -0,0 +1,487 @@
+// User profile: display name/avatar, interface language and answer script, privacy settings (PII
+// redaction, chat retention), plus professional details (title, firm, bar number, signature block)
+// used to pre-fill contract parties and document letterheads. Uploaded avatars are stored in
+// user_avatars and served from /api/avatars/:user_id.
+use crate::models::{ApiError, ErrorResponse};
+use crate::retention::RetentionPolicy;
+use crate::simple_auth::{sync_supabase_profile, AuthAppState};
+use crate::transliteration::ResponseScript;
+use axum::{
+    extract::{Multipart, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+// Bar numbers (broj upisa u imenik advokata) are short alphanumeric identifiers, e.g. "12345" or "BG-1234/2015"
+fn validate_bar_number(bar_number: &str) -> Result<(), ValidationError> {
+    let bar_number = bar_number.trim();
+    if bar_number.is_empty() {
+        return Ok(()); // Empty clears the field
+    }
+
+    let valid_chars = bar_number
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '/' | ' '));
+    if bar_number.len() > 30 || !valid_chars || !bar_number.chars().any(|c| c.is_ascii_digit()) {
+        return Err(crate::i18n::field_error("invalid_bar_number", "Neispravan broj upisa u imenik advokata"));
+    }
+    Ok(())
+}
+
+/// Largest avatar accepted - clients resize before uploading
+const MAX_AVATAR_BYTES: usize = 2 * 1024 * 1024;
+
+/// Interface languages the apps are translated to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UiLanguage {
+    Sr,
+    En,
+}
+
+impl UiLanguage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UiLanguage::Sr => "sr",
+            UiLanguage::En => "en",
+        }
+    }
+}
+
+/// Content type of a PNG, JPEG or WebP image, judged by its signature rather than the upload's claim
+fn detect_avatar_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.len() > 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// Which professional fields may appear on generated contracts and exports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileVisibility {
+    pub professional_title: bool,
+    pub firm_name: bool,
+    pub bar_number: bool,
+    pub signature_block: bool,
+}
+
+impl Default for ProfileVisibility {
+    fn default() -> Self {
+        Self {
+            professional_title: true,
+            firm_name: true,
+            bar_number: false, // Opt-in - not every document should carry the bar registration
+            signature_block: true,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateProfileRequest {
+    // Omitted = unchanged. For name/picture an empty string resets to the OAuth provider value,
+    // for professional fields it clears them.
+    #[validate(length(max = 100, code = "name_too_long", message = "Ime može imati najviše 100 karaktera"))]
+    pub name: Option<String>,
+    #[validate(length(max = 2048, code = "picture_url_too_long", message = "URL slike je predugačak"))]
+    pub profile_picture_url: Option<String>,
+    #[validate(length(max = 100, code = "title_too_long", message = "Zvanje može imati najviše 100 karaktera"))]
+    pub professional_title: Option<String>, // e.g. "Advokat", "Pravni savetnik"
+    #[validate(length(max = 200, code = "office_name_too_long", message = "Naziv kancelarije može imati najviše 200 karaktera"))]
+    pub firm_name: Option<String>,
+    #[validate(custom = "validate_bar_number")]
+    pub bar_number: Option<String>,
+    #[validate(length(max = 1000, code = "signature_too_long", message = "Potpis može imati najviše 1000 karaktera"))]
+    pub signature_block: Option<String>,
+    pub visibility: Option<ProfileVisibility>,
+    pub response_script: Option<ResponseScript>, // Script answers are shown in
+    pub ui_language: Option<UiLanguage>,
+    pub pii_redaction: Option<bool>, // Store messages with names, JMBGs and addresses redacted
+    pub retention: Option<RetentionPolicy>, // How long chats are kept
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ProfileResponse {
+    pub user_id: Uuid,
+    pub email: String,
+    pub name: Option<String>,
+    pub profile_picture_url: Option<String>,
+    pub oauth_provider: Option<String>,
+    pub name_overridden: bool,
+    pub avatar_overridden: bool,
+    pub professional_title: Option<String>,
+    pub firm_name: Option<String>,
+    pub bar_number: Option<String>,
+    pub signature_block: Option<String>,
+    #[sqlx(json)]
+    pub visibility: ProfileVisibility,
+    pub response_script: String, // 'latin' or 'cyrillic'
+    pub ui_language: String, // 'sr' or 'en'
+    pub pii_redaction: bool,
+    pub retention: String, // '30_days', '90_days', '365_days' or 'forever'
+}
+
+fn database_error(e: sqlx::Error) -> ApiError {
+    eprintln!("Profile database error: {}", e);
+    ApiError {
+        status: StatusCode::INTERNAL_SERVER_ERROR,
+        body: Some(ErrorResponse {
+            error: "DATABASE_ERROR".to_string(),
+            message: "Greška ažuriranja profila".to_string(),
+            details: Some(serde_json::json!({"details": e.to_string()})),
+        }),
+    }
+}
+
+async fn load_profile(user_id: Uuid, pool: &PgPool) -> Result<ProfileResponse, sqlx::Error> {
+    sqlx::query_as::<_, ProfileResponse>(
+        "SELECT u.id AS user_id, u.email, u.name, u.oauth_profile_picture_url AS profile_picture_url,
+                u.oauth_provider, u.name_overridden, u.avatar_overridden,
+                p.professional_title, p.firm_name, p.bar_number, p.signature_block,
+                COALESCE(p.visibility, '{}'::jsonb) AS visibility, u.response_script, u.ui_language,
+                u.pii_redaction_enabled AS pii_redaction,
+                COALESCE(u.retention_days::TEXT || '_days', 'forever') AS retention
+         FROM users u
+         LEFT JOIN user_profiles p ON p.user_id = u.id
+         WHERE u.id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
+
+/// Get the authenticated user's profile
+pub async fn get_profile_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
+    headers: HeaderMap,
+) -> Result<Json<ProfileResponse>, ApiError> {
+    let user_id = crate::database::authenticate(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    Ok(Json(load_profile(user_id, &pool).await.map_err(database_error)?))
+}
+
+/// Edit profile fields. Edited name/avatar stop being synced from the OAuth provider.
+pub async fn update_profile_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateProfileRequest>,
+) -> Result<Json<ProfileResponse>, ApiError> {
+    payload.validate().map_err(|e| crate::i18n::validation_error(&e))?;
+
+    let user_id = crate::database::authenticate(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let name = payload.name.as_deref().map(str::trim);
+    let picture = payload.profile_picture_url.as_deref().map(str::trim);
+
+    if let Some(url) = picture.filter(|u| !u.is_empty()) {
+        if !url.starts_with("https://") {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_PICTURE_URL",
+                "URL slike mora počinjati sa https://",
+            ));
+        }
+    }
+
+    // The mappings of redacted messages are sealed with PII_ENCRYPTION_KEY
+    if payload.pii_redaction == Some(true) && !crate::pii::available() {
+        return Err(ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "PII_REDACTION_UNAVAILABLE",
+            "Zaštita ličnih podataka trenutno nije dostupna",
+        ));
+    }
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+
+    // NULL = leave unchanged, '' = reset to provider value, otherwise manual override
+    if name.is_some() || picture.is_some() {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET name = CASE WHEN $2::TEXT IS NULL THEN name WHEN $2 = '' THEN name ELSE $2 END,
+                name_overridden = CASE WHEN $2::TEXT IS NULL THEN name_overridden ELSE $2 <> '' END,
+                oauth_profile_picture_url = CASE WHEN $3::TEXT IS NULL THEN oauth_profile_picture_url WHEN $3 = '' THEN NULL ELSE $3 END,
+                avatar_overridden = CASE WHEN $3::TEXT IS NULL THEN avatar_overridden ELSE $3 <> '' END,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(name)
+        .bind(picture)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+    }
+
+    // A new picture URL (or a reset) replaces an uploaded avatar
+    if picture.is_some() {
+        sqlx::query("DELETE FROM user_avatars WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(database_error)?;
+    }
+
+    if let Some(script) = payload.response_script {
+        sqlx::query("UPDATE users SET response_script = $2, updated_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .bind(script.as_str())
+            .execute(&mut *tx)
+            .await
+            .map_err(database_error)?;
+    }
+
+    if let Some(language) = payload.ui_language {
+        sqlx::query("UPDATE users SET ui_language = $2, updated_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .bind(language.as_str())
+            .execute(&mut *tx)
+            .await
+            .map_err(database_error)?;
+    }
+
+    if let Some(enabled) = payload.pii_redaction {
+        sqlx::query("UPDATE users SET pii_redaction_enabled = $2, updated_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .bind(enabled)
+            .execute(&mut *tx)
+            .await
+            .map_err(database_error)?;
+    }
+
+    if let Some(retention) = payload.retention {
+        sqlx::query("UPDATE users SET retention_days = $2, updated_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .bind(retention.days())
+            .execute(&mut *tx)
+            .await
+            .map_err(database_error)?;
+    }
+
+    let professional_title = payload.professional_title.as_deref().map(str::trim);
+    let firm_name = payload.firm_name.as_deref().map(str::trim);
+    let bar_number = payload.bar_number.as_deref().map(str::trim);
+    let signature_block = payload.signature_block.as_deref().map(str::trim);
+
+    // NULL = leave unchanged, '' = clear
+    if professional_title.is_some()
+        || firm_name.is_some()
+        || bar_number.is_some()
+        || signature_block.is_some()
+        || payload.visibility.is_some()
+    {
+        sqlx::query(
+            r#"
+            INSERT INTO user_profiles (user_id, professional_title, firm_name, bar_number, signature_block, visibility)
+            VALUES ($1, NULLIF($2, ''), NULLIF($3, ''), NULLIF($4, ''), NULLIF($5, ''), COALESCE($6::JSONB, '{}'::jsonb))
+            ON CONFLICT (user_id) DO UPDATE SET
+                professional_title = CASE WHEN $2::TEXT IS NULL THEN user_profiles.professional_title ELSE NULLIF($2, '') END,
+                firm_name = CASE WHEN $3::TEXT IS NULL THEN user_profiles.firm_name ELSE NULLIF($3, '') END,
+                bar_number = CASE WHEN $4::TEXT IS NULL THEN user_profiles.bar_number ELSE NULLIF($4, '') END,
+                signature_block = CASE WHEN $5::TEXT IS NULL THEN user_profiles.signature_block ELSE NULLIF($5, '') END,
+                visibility = COALESCE($6::JSONB, user_profiles.visibility),
+                updated_at = NOW()
+            "#,
+        )
+        .bind(user_id)
+        .bind(professional_title)
+        .bind(firm_name)
+        .bind(bar_number)
+        .bind(signature_block)
+        .bind(payload.visibility.as_ref().map(sqlx::types::Json))
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+    }
+
+    tx.commit().await.map_err(database_error)?;
+
+    // Resetting a field re-reads it from the provider right away
+    if name == Some("") || picture == Some("") {
+        if let Err(e) = sync_supabase_profile(user_id, true, &pool).await {
+            eprintln!("⚠️  Failed to sync Supabase profile for user {}: {}", user_id, e);
+        }
+    }
+
+    println!("✅ Profile updated for user {}", user_id);
+
+    Ok(Json(load_profile(user_id, &pool).await.map_err(database_error)?))
+}
+
+/// Upload an avatar (multipart "file": PNG, JPEG or WebP up to 2MB). Like an edited picture URL
+/// it stops the avatar being synced from the OAuth provider.
+pub async fn upload_avatar_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Result<Json<ProfileResponse>, ApiError> {
+    let user_id = crate::database::authenticate(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let (_, _, bytes) = crate::documents::read_upload_field(&mut multipart, MAX_AVATAR_BYTES)
+        .await
+        .map_err(|status| match status {
+            StatusCode::PAYLOAD_TOO_LARGE => ApiError::new(status, "AVATAR_TOO_LARGE", "Slika može imati najviše 2 MB"),
+            _ => ApiError::new(status, "INVALID_UPLOAD", "Fajl nije poslat"),
+        })?;
+    let content_type = detect_avatar_type(&bytes).ok_or_else(|| {
+        ApiError::new(StatusCode::UNSUPPORTED_MEDIA_TYPE, "INVALID_AVATAR", "Slika mora biti PNG, JPEG ili WebP")
+    })?;
+
+    // The version parameter makes clients drop a cached older avatar
+    let url = format!(
+        "{}/api/avatars/{}?v={}",
+        crate::config::get().api_base_url,
+        user_id,
+        chrono::Utc::now().timestamp()
+    );
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+    sqlx::query(
+        "INSERT INTO user_avatars (user_id, content_type, image) VALUES ($1, $2, $3)
+         ON CONFLICT (user_id) DO UPDATE SET content_type = EXCLUDED.content_type, image = EXCLUDED.image, updated_at = NOW()",
+    )
+    .bind(user_id)
+    .bind(content_type)
+    .bind(&bytes)
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+    sqlx::query(
+        "UPDATE users SET oauth_profile_picture_url = $2, avatar_overridden = true, updated_at = NOW() WHERE id = $1",
+    )
+    .bind(user_id)
+    .bind(&url)
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+    tx.commit().await.map_err(database_error)?;
+
+    println!("✅ Avatar uploaded for user {} ({} bytes)", user_id, bytes.len());
+
+    Ok(Json(load_profile(user_id, &pool).await.map_err(database_error)?))
+}
+
+/// Remove the uploaded avatar and go back to the OAuth provider's picture
+pub async fn delete_avatar_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
+    headers: HeaderMap,
+) -> Result<Json<ProfileResponse>, ApiError> {
+    let user_id = crate::database::authenticate(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+    sqlx::query("DELETE FROM user_avatars WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+    sqlx::query(
+        "UPDATE users SET oauth_profile_picture_url = NULL, avatar_overridden = false, updated_at = NOW() WHERE id = $1",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+    tx.commit().await.map_err(database_error)?;
+
+    if let Err(e) = sync_supabase_profile(user_id, true, &pool).await {
+        eprintln!("⚠️  Failed to sync Supabase profile for user {}: {}", user_id, e);
+    }
+
+    Ok(Json(load_profile(user_id, &pool).await.map_err(database_error)?))
+}
+
+/// Public: an uploaded avatar. Shown wherever the user's picture is (shared chats included), so it
+/// isn't behind auth; the URL changes with every upload, so it can be cached for long.
+pub async fn get_avatar_handler(
+    State((pool, _, _, _, _, _)): State<AuthAppState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Response, StatusCode> {
+    let avatar: Option<(String, Vec<u8>)> =
+        sqlx::query_as("SELECT content_type, image FROM user_avatars WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| {
+                eprintln!("Failed to load avatar: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    let (content_type, image) = avatar.ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, "public, max-age=86400".to_string()),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        image,
+    )
+        .into_response())
+}
+
+/// Profile details the user allowed on generated documents, formatted as context for the LLM
+/// so contract parties and signatures can be pre-filled. None if there is nothing to share.
+pub async fn document_profile_context(user_id: Uuid, pool: &PgPool) -> Result<Option<String>, String> {
+    let profile = load_profile(user_id, pool)
+        .await
+        .map_err(|e| format!("Failed to load profile: {}", e))?;
+    let visibility = &profile.visibility;
+
+    let mut lines = Vec::new();
+    if let Some(title) = profile.professional_title.as_deref().filter(|_| visibility.professional_title) {
+        lines.push(format!("Zvanje: {}", title));
+    }
+    if let Some(firm) = profile.firm_name.as_deref().filter(|_| visibility.firm_name) {
+        lines.push(format!("Kancelarija/firma: {}", firm));
+    }
+    if let Some(bar_number) = profile.bar_number.as_deref().filter(|_| visibility.bar_number) {
+        lines.push(format!("Broj upisa u imenik advokata: {}", bar_number));
+    }
+    if let Some(signature) = profile.signature_block.as_deref().filter(|_| visibility.signature_block) {
+        lines.push(format!("Blok za potpis:\n{}", signature));
+    }
+
+    // A bare name isn't worth the extra prompt tokens
+    if lines.is_empty() {
+        return Ok(None);
+    }
+    if let Some(name) = profile.name.as_deref().filter(|n| !n.is_empty()) {
+        lines.insert(0, format!("Ime i prezime: {}", name));
+    }
+
+    Ok(Some(lines.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_bar_number() {
+        assert!(validate_bar_number("12345").is_ok());
+        assert!(validate_bar_number("BG-1234/2015").is_ok());
+        assert!(validate_bar_number("").is_ok());
+        assert!(validate_bar_number("advokat").is_err());
+        assert!(validate_bar_number("123; DROP").is_err());
+    }
+
+    #[test]
+    fn test_detect_avatar_type() {
+        assert_eq!(detect_avatar_type(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("image/png"));
+        assert_eq!(detect_avatar_type(&[0xFF, 0xD8, 0xFF, 0xE0, 0, 0x10]), Some("image/jpeg"));
+        assert_eq!(detect_avatar_type(b"RIFF\x24\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(detect_avatar_type(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"), None); // Scriptable
+        assert_eq!(detect_avatar_type(b"GIF89a"), None);
+    }
+}
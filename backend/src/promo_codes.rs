@@ -0,0 +1,512 @@
+// Promo codes: a percent or fixed RSD discount on a web subscription, minted by admins per
+// campaign. A code can expire and have a total use limit, and each user can use it once. The
+// client checks a code with /api/promo-codes/validate and sends it with create_subscription,
+// which redeems it in the same transaction that activates the plan. Checking codes is throttled per
+// user (together with referral codes) so they can't be guessed by trying them all.
+use crate::models::ApiError;
+use crate::simple_auth::AuthAppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+/// Characters of generated codes - no 0/O or 1/I to misread
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// Most codes one admin request may mint
+const MAX_CODES_PER_REQUEST: u32 = 1000;
+
+/// Promo and referral codes one user may try per CODE_ATTEMPT_WINDOW
+const MAX_CODE_ATTEMPTS: usize = 20;
+const CODE_ATTEMPT_WINDOW: Duration = Duration::from_secs(60 * 60);
+const CODE_ATTEMPTS_MAX_USERS: usize = 10_000;
+
+static CODE_ATTEMPTS: OnceLock<Mutex<HashMap<Uuid, Vec<Instant>>>> = OnceLock::new();
+
+/// Record an attempt in `attempts` unless the user is out of them for the window
+fn allow_attempt(attempts: &mut Vec<Instant>, now: Instant) -> bool {
+    attempts.retain(|at| now.duration_since(*at) < CODE_ATTEMPT_WINDOW);
+    if attempts.len() >= MAX_CODE_ATTEMPTS {
+        return false;
+    }
+    attempts.push(now);
+    true
+}
+
+/// Count a promo or referral code attempt; 429 once the user has tried too many
+pub fn throttle_code_attempts(user_id: Uuid) -> Result<(), ApiError> {
+    let now = Instant::now();
+    let mut attempts = CODE_ATTEMPTS.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap();
+    if attempts.len() >= CODE_ATTEMPTS_MAX_USERS {
+        attempts.retain(|_, times| times.last().is_some_and(|at| now.duration_since(*at) < CODE_ATTEMPT_WINDOW));
+    }
+    if allow_attempt(attempts.entry(user_id).or_default(), now) {
+        Ok(())
+    } else {
+        Err(ApiError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "TOO_MANY_CODE_ATTEMPTS",
+            "Previše pokušaja unosa koda - pokušajte ponovo za sat vremena",
+        ))
+    }
+}
+
+/// Random code of `len` characters from CODE_ALPHABET
+pub fn random_code(len: usize) -> String {
+    let mut rng = rand::thread_rng();
+    (0..len).map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char).collect()
+}
+
+/// Codes are matched case-insensitively and stored uppercase
+pub fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscountType {
+    Percent,
+    Fixed,
+}
+
+impl DiscountType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiscountType::Percent => "percent",
+            DiscountType::Fixed => "fixed",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        if value == "percent" {
+            DiscountType::Percent
+        } else {
+            DiscountType::Fixed
+        }
+    }
+}
+
+/// RSD taken off `price_rsd`; a percentage is rounded to the nearest dinar and a fixed discount
+/// never exceeds the price
+pub fn discount_rsd(discount_type: DiscountType, discount_value: i32, price_rsd: i32) -> i32 {
+    let price = price_rsd.max(0) as i64;
+    let discount = match discount_type {
+        DiscountType::Percent => (price * discount_value.clamp(0, 100) as i64 + 50) / 100,
+        DiscountType::Fixed => (discount_value.max(0) as i64).min(price),
+    };
+    discount as i32
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PromoCode {
+    #[serde(skip)]
+    pub id: Uuid,
+    pub code: String,
+    pub campaign: Option<String>,
+    pub discount_type: String,
+    pub discount_value: i32,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub max_uses: Option<i32>,
+    pub uses: i32,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Why a code can't be used
+#[derive(Debug)]
+pub enum PromoCodeError {
+    Unknown, // No such code, or deactivated
+    Expired,
+    UsedUp,
+    AlreadyUsed, // By this user
+    Database(sqlx::Error),
+}
+
+impl PromoCodeError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            PromoCodeError::Unknown => "PROMO_CODE_INVALID",
+            PromoCodeError::Expired => "PROMO_CODE_EXPIRED",
+            PromoCodeError::UsedUp => "PROMO_CODE_USED_UP",
+            PromoCodeError::AlreadyUsed => "PROMO_CODE_ALREADY_USED",
+            PromoCodeError::Database(_) => "DATABASE_ERROR",
+        }
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            PromoCodeError::Unknown => "Promo kod ne postoji",
+            PromoCodeError::Expired => "Promo kod je istekao",
+            PromoCodeError::UsedUp => "Promo kod je već iskorišćen najveći dozvoljeni broj puta",
+            PromoCodeError::AlreadyUsed => "Već ste iskoristili ovaj promo kod",
+            PromoCodeError::Database(_) => "Greška pri proveri promo koda",
+        }
+    }
+}
+
+impl From<PromoCodeError> for ApiError {
+    fn from(e: PromoCodeError) -> Self {
+        let status = match &e {
+            PromoCodeError::Unknown => StatusCode::NOT_FOUND,
+            PromoCodeError::Database(db_error) => {
+                eprintln!("Promo code database error: {}", db_error);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            _ => StatusCode::CONFLICT,
+        };
+        ApiError::new(status, e.code(), e.message())
+    }
+}
+
+impl From<sqlx::Error> for PromoCodeError {
+    fn from(e: sqlx::Error) -> Self {
+        PromoCodeError::Database(e)
+    }
+}
+
+/// Whether `promo` can still be used by someone who has (or hasn't) used it before
+fn check_usable(promo: &PromoCode, already_used: bool, now: DateTime<Utc>) -> Result<(), PromoCodeError> {
+    if !promo.active {
+        return Err(PromoCodeError::Unknown);
+    }
+    if promo.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(PromoCodeError::Expired);
+    }
+    if promo.max_uses.is_some_and(|max_uses| promo.uses >= max_uses) {
+        return Err(PromoCodeError::UsedUp);
+    }
+    if already_used {
+        return Err(PromoCodeError::AlreadyUsed);
+    }
+    Ok(())
+}
+
+/// A code applied to a price, returned with the subscription
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AppliedPromoCode {
+    pub code: String,
+    pub discount_type: DiscountType,
+    pub discount_value: i32,
+    pub discount_rsd: i32,
+}
+
+impl AppliedPromoCode {
+    fn new(promo: &PromoCode, price_rsd: i32) -> Self {
+        let discount_type = DiscountType::parse(&promo.discount_type);
+        AppliedPromoCode {
+            code: promo.code.clone(),
+            discount_type,
+            discount_value: promo.discount_value,
+            discount_rsd: discount_rsd(discount_type, promo.discount_value, price_rsd),
+        }
+    }
+}
+
+async fn load_usable(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    code: &str,
+    lock: bool,
+) -> Result<PromoCode, PromoCodeError> {
+    let query = if lock {
+        "SELECT * FROM promo_codes WHERE code = $1 FOR UPDATE"
+    } else {
+        "SELECT * FROM promo_codes WHERE code = $1"
+    };
+    let promo = sqlx::query_as::<_, PromoCode>(query)
+        .bind(normalize_code(code))
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or(PromoCodeError::Unknown)?;
+
+    let already_used: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM promo_code_redemptions WHERE promo_code_id = $1 AND user_id = $2)",
+    )
+    .bind(promo.id)
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    check_usable(&promo, already_used, Utc::now())?;
+    Ok(promo)
+}
+
+/// Check a code against a subscription costing `price_rsd` without using it
+pub async fn check(conn: &mut PgConnection, user_id: Uuid, code: &str, price_rsd: i32) -> Result<AppliedPromoCode, PromoCodeError> {
+    let promo = load_usable(conn, user_id, code, false).await?;
+    Ok(AppliedPromoCode::new(&promo, price_rsd))
+}
+
+/// Use a code towards a subscription costing `price_rsd`. Runs in the caller's transaction so the
+/// use is only counted if the subscription goes through.
+pub async fn redeem(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    code: &str,
+    plan: &str,
+    billing_period: &str,
+    price_rsd: i32,
+) -> Result<AppliedPromoCode, PromoCodeError> {
+    // Locked so concurrent redemptions can't go over max_uses
+    let promo = load_usable(tx, user_id, code, true).await?;
+    let applied = AppliedPromoCode::new(&promo, price_rsd);
+
+    sqlx::query("UPDATE promo_codes SET uses = uses + 1 WHERE id = $1")
+        .bind(promo.id)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query(
+        "INSERT INTO promo_code_redemptions (promo_code_id, user_id, plan, billing_period, discount_rsd)
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(promo.id)
+    .bind(user_id)
+    .bind(plan)
+    .bind(billing_period)
+    .bind(applied.discount_rsd)
+    .execute(&mut **tx)
+    .await?;
+
+    println!("🏷️ User {} redeemed promo code {} (-{} RSD)", user_id, applied.code, applied.discount_rsd);
+    Ok(applied)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ValidatePromoCodeRequest {
+    pub code: String,
+    pub plan_id: String,
+    pub billing_period: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PromoCodeQuote {
+    #[serde(flatten)]
+    pub promo_code: AppliedPromoCode,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub price_rsd: i32,
+    pub final_price_rsd: i32,
+}
+
+/// Check a code against the plan the user is about to buy
+pub async fn validate_promo_code_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
+    headers: HeaderMap,
+    Json(request): Json<ValidatePromoCodeRequest>,
+) -> Result<Json<PromoCodeQuote>, ApiError> {
+    let user_id = crate::database::authenticate(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let price_rsd = crate::pricing::plan_price_rsd(&request.plan_id, &request.billing_period)
+        .ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, "INVALID_PLAN", "Nepodržan plan ili tip naplate"))?;
+    throttle_code_attempts(user_id)?;
+
+    let mut conn = pool.acquire().await.map_err(PromoCodeError::Database)?;
+    let promo = load_usable(&mut conn, user_id, &request.code, false).await?;
+    let promo_code = AppliedPromoCode::new(&promo, price_rsd);
+
+    Ok(Json(PromoCodeQuote {
+        final_price_rsd: price_rsd - promo_code.discount_rsd,
+        promo_code,
+        expires_at: promo.expires_at,
+        price_rsd,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListPromoCodesQuery {
+    pub campaign: Option<String>,
+}
+
+/// Admin: list codes, optionally of one campaign
+pub async fn list_promo_codes_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ListPromoCodesQuery>,
+) -> Result<Json<Vec<PromoCode>>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    sqlx::query_as::<_, PromoCode>(
+        "SELECT * FROM promo_codes WHERE $1::TEXT IS NULL OR campaign = $1 ORDER BY created_at DESC, code",
+    )
+    .bind(query.campaign.as_deref())
+    .fetch_all(&pool)
+    .await
+    .map(Json)
+    .map_err(|e| {
+        eprintln!("Failed to list promo codes: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePromoCodesRequest {
+    pub code: Option<String>, // A memorable code ("PRAVNIK2026"); generated when missing
+    pub count: Option<u32>,   // Generated codes to mint, e.g. single-use codes for a mailing
+    pub prefix: Option<String>,
+    pub campaign: Option<String>,
+    pub discount_type: DiscountType,
+    pub discount_value: i32,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub max_uses: Option<i32>,
+}
+
+/// Admin: mint a campaign's codes - one named code, or `count` generated ones
+pub async fn create_promo_codes_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CreatePromoCodesRequest>,
+) -> Result<(StatusCode, Json<Vec<PromoCode>>), StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let count = request.count.unwrap_or(1);
+    let code = request.code.as_deref().map(normalize_code);
+    let prefix = request.prefix.as_deref().map(normalize_code).unwrap_or_default();
+    let valid = (1..=MAX_CODES_PER_REQUEST).contains(&count)
+        && (code.is_none() || count == 1)
+        && code.as_ref().is_none_or(|code| (3..=40).contains(&code.len()) && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        && prefix.len() <= 20
+        && prefix.chars().all(|c| c.is_ascii_alphanumeric())
+        && request.discount_value > 0
+        && (request.discount_type == DiscountType::Fixed || request.discount_value <= 100)
+        && request.max_uses.is_none_or(|max_uses| max_uses > 0);
+    if !valid {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let codes: Vec<String> = match code {
+        Some(code) => vec![code],
+        None => (0..count)
+            .map(|_| if prefix.is_empty() { random_code(8) } else { format!("{}-{}", prefix, random_code(8)) })
+            .collect(),
+    };
+
+    // Generated codes that happen to exist already are skipped rather than failing the batch
+    let created = sqlx::query_as::<_, PromoCode>(
+        "INSERT INTO promo_codes (code, campaign, discount_type, discount_value, expires_at, max_uses)
+         SELECT code, $2, $3, $4, $5, $6 FROM UNNEST($1::TEXT[]) AS code
+         ON CONFLICT (code) DO NOTHING
+         RETURNING *",
+    )
+    .bind(&codes)
+    .bind(request.campaign.as_deref())
+    .bind(request.discount_type.as_str())
+    .bind(request.discount_value)
+    .bind(request.expires_at)
+    .bind(request.max_uses)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to create promo codes: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if created.is_empty() {
+        return Err(StatusCode::CONFLICT); // The named code exists
+    }
+
+    println!(
+        "✅ Minted {} promo code(s): campaign={:?}, {} {}",
+        created.len(),
+        request.campaign,
+        request.discount_value,
+        request.discount_type.as_str()
+    );
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// Admin: stop a code from being used; past redemptions stay
+pub async fn deactivate_promo_code_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Path(code): Path<String>,
+) -> Result<Json<PromoCode>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    sqlx::query_as::<_, PromoCode>("UPDATE promo_codes SET active = FALSE WHERE code = $1 RETURNING *")
+        .bind(normalize_code(&code))
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to deactivate promo code: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn promo(max_uses: Option<i32>, uses: i32, expires_at: Option<DateTime<Utc>>) -> PromoCode {
+        PromoCode {
+            id: Uuid::new_v4(),
+            code: "PRAVNIK".to_string(),
+            campaign: None,
+            discount_type: "percent".to_string(),
+            discount_value: 20,
+            expires_at,
+            max_uses,
+            uses,
+            active: true,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_discount_rsd() {
+        assert_eq!(discount_rsd(DiscountType::Percent, 20, 3400), 680);
+        assert_eq!(discount_rsd(DiscountType::Percent, 15, 3410), 512); // 511.5 rounds up
+        assert_eq!(discount_rsd(DiscountType::Fixed, 1000, 6400), 1000);
+        assert_eq!(discount_rsd(DiscountType::Fixed, 10000, 6400), 6400);
+    }
+
+    #[test]
+    fn test_check_usable() {
+        let now = Utc::now();
+        assert!(check_usable(&promo(Some(10), 9, Some(now + chrono::Duration::days(1))), false, now).is_ok());
+        assert!(matches!(check_usable(&promo(Some(10), 10, None), false, now), Err(PromoCodeError::UsedUp)));
+        assert!(matches!(check_usable(&promo(None, 0, Some(now)), false, now), Err(PromoCodeError::Expired)));
+        assert!(matches!(check_usable(&promo(None, 3, None), true, now), Err(PromoCodeError::AlreadyUsed)));
+
+        let inactive = PromoCode { active: false, ..promo(None, 0, None) };
+        assert!(matches!(check_usable(&inactive, false, now), Err(PromoCodeError::Unknown)));
+    }
+
+    #[test]
+    fn test_code_attempts_are_limited_per_window() {
+        let start = Instant::now();
+        let mut attempts = Vec::new();
+        for _ in 0..MAX_CODE_ATTEMPTS {
+            assert!(allow_attempt(&mut attempts, start));
+        }
+        assert!(!allow_attempt(&mut attempts, start + Duration::from_secs(60)));
+        // Attempts older than the window no longer count
+        assert!(allow_attempt(&mut attempts, start + CODE_ATTEMPT_WINDOW));
+    }
+
+    #[test]
+    fn test_generated_codes() {
+        let code = random_code(8);
+        assert_eq!(code.len(), 8);
+        assert!(code.bytes().all(|b| CODE_ALPHABET.contains(&b)));
+        assert_eq!(normalize_code(" pravnik-2026 "), "PRAVNIK-2026");
+    }
+}
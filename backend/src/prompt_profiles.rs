@@ -0,0 +1,426 @@
+use axum::{
+    extract::{Json, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::jurisdictions::Jurisdiction;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+/// How long prompt profiles loaded from the database are reused before re-reading
+const PROFILE_CACHE_TTL: Duration = Duration::from_secs(60);
+
+const DEFAULT_VARIANT: &str = "default";
+const MAX_VARIANT_CHARS: usize = 50;
+
+/// Legal answering instructions (built-in default for the "system" prompt)
+const DEFAULT_SYSTEM_PROMPT: &str = r#"Ti si pravni asistent za srpsko zakonodavstvo sa mogućnošću generisanja ugovora.
+
+PRAVNA PITANJA - Odgovori KRATKO i DIREKTNO:
+1. Koristi znanje iz srpskog zakonodavstva
+2. Navedi konkretne kazne, iznose i rokove
+
+FORMAT:
+1. KRATAK odgovor
+2. Nova linija: "Reference:"
+3. U "Reference:" citiraj: Član X, Član Y, Član Z..."#;
+
+/// Contract generation instructions (built-in default for the "contract" prompt)
+const DEFAULT_CONTRACT_PROMPT: &str = r#"GENERISANJE UGOVORA:
+Kada korisnik traži ugovor (npr. "Napravi ugovor o radu", "Treba mi ugovor o zakupu"):
+
+1. PRIKUPI SVE podatke (za ugovor o radu: poslodavac, zaposleni, pozicija, zarada, datum, trajanje)
+2. Kada imaš dovoljno informacija, generiši ugovor sa [CONTRACT_START] i [CONTRACT_END]:
+
+[CONTRACT_START]
+UGOVOR O RADU
+
+Zaključen između:
+1. [Poslodavac]
+2. [Zaposleni]
+
+Član 1. - PREDMET UGOVORA
+[Detalji...]
+
+[Ostali potrebni članovi...]
+
+U _______, dana _______
+Potpisi
+[CONTRACT_END]
+
+Nakon [CONTRACT_END] dodaj kratak komentar i preporuku za pravni pregled."#;
+
+/// Which part of the system prompt a profile replaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    System,
+    Contract,
+}
+
+impl PromptKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PromptKind::System => "system",
+            PromptKind::Contract => "contract",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "system" => Some(PromptKind::System),
+            "contract" => Some(PromptKind::Contract),
+            _ => None,
+        }
+    }
+
+    fn default_content(&self) -> &'static str {
+        match self {
+            PromptKind::System => DEFAULT_SYSTEM_PROMPT,
+            PromptKind::Contract => DEFAULT_CONTRACT_PROMPT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PromptProfile {
+    pub kind: String,
+    pub account_type: Option<String>, // NULL = applies to all plans
+    pub variant: String,
+    pub content: String,
+    pub weight: i32, // Relative share of users for A/B variants; 0 disables the variant
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetPromptProfileRequest {
+    pub kind: String,
+    pub account_type: Option<String>,
+    pub variant: Option<String>, // Defaults to "default"
+    pub content: Option<String>, // None removes the profile
+    pub weight: Option<i32>,
+}
+
+/// The prompt used for one request, and which profiles it came from (for the LLM audit log)
+#[derive(Debug, Clone)]
+pub struct ResolvedPrompt {
+    pub content: String,
+    pub label: String, // e.g. "system:professional/b,contract:default"
+}
+
+// (loaded_at, profiles)
+type CachedProfiles = Option<(Instant, Vec<PromptProfile>)>;
+
+static PROFILE_CACHE: OnceLock<Mutex<CachedProfiles>> = OnceLock::new();
+
+fn profile_cache() -> &'static Mutex<CachedProfiles> {
+    PROFILE_CACHE.get_or_init(|| Mutex::new(None))
+}
+
+fn invalidate_cache() {
+    *profile_cache().lock().unwrap() = None;
+}
+
+async fn load_profiles(pool: &PgPool) -> Vec<PromptProfile> {
+    if let Some((loaded_at, profiles)) = profile_cache().lock().unwrap().as_ref() {
+        if loaded_at.elapsed() < PROFILE_CACHE_TTL {
+            return profiles.clone();
+        }
+    }
+
+    match sqlx::query_as::<_, PromptProfile>(
+        "SELECT kind, account_type, variant, content, weight, updated_at FROM prompt_profiles ORDER BY variant"
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(profiles) => {
+            *profile_cache().lock().unwrap() = Some((Instant::now(), profiles.clone()));
+            profiles
+        }
+        Err(e) => {
+            // Fall back to the built-in prompt rather than failing the question
+            eprintln!("⚠️  Failed to load prompt profiles: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Build the system prompt for a request.
+///
+/// Each part (legal instructions, contract instructions) is resolved separately:
+/// 1. prompt_profiles rows for (kind, account_type)
+/// 2. prompt_profiles rows for (kind, any plan)
+/// 3. Built-in default
+///
+/// When several variants match, one is picked by weight, stable per user so a user
+/// stays in the same A/B group across questions.
+///
+/// Profiles are written for Serbian law: outside Serbia the legal instructions always come from
+/// the jurisdiction's built-in prompt, only the contract part is resolved as above.
+pub async fn resolve_system_prompt(
+    account_type: Option<&str>,
+    user_id: Option<Uuid>,
+    jurisdiction: Jurisdiction,
+    pool: &PgPool,
+) -> ResolvedPrompt {
+    let profiles = load_profiles(pool).await;
+    resolve_system_prompt_from(account_type, user_id, jurisdiction, &profiles)
+}
+
+fn resolve_system_prompt_from(
+    account_type: Option<&str>,
+    user_id: Option<Uuid>,
+    jurisdiction: Jurisdiction,
+    profiles: &[PromptProfile],
+) -> ResolvedPrompt {
+    let parts: Vec<(String, String)> = [PromptKind::System, PromptKind::Contract]
+        .iter()
+        .map(|kind| match (*kind, crate::jurisdictions::system_prompt(jurisdiction)) {
+            (PromptKind::System, Some(prompt)) => (prompt.to_string(), format!("system:{}", jurisdiction.as_str())),
+            _ => resolve_part(*kind, account_type, user_id, profiles),
+        })
+        .collect();
+
+    ResolvedPrompt {
+        content: parts.iter().map(|(content, _)| content.trim()).filter(|c| !c.is_empty()).collect::<Vec<_>>().join("\n\n"),
+        label: parts.iter().map(|(_, label)| label.as_str()).collect::<Vec<_>>().join(","),
+    }
+}
+
+// (content, label) of one prompt part from the profiles or the built-in default
+fn resolve_part(kind: PromptKind, account_type: Option<&str>, user_id: Option<Uuid>, profiles: &[PromptProfile]) -> (String, String) {
+    match select_profile(kind, account_type, user_id, profiles) {
+        Some(profile) => {
+            let plan = profile.account_type.as_deref().map(|p| format!("{}/", p)).unwrap_or_default();
+            (profile.content.clone(), format!("{}:{}{}", kind.as_str(), plan, profile.variant))
+        }
+        None => (kind.default_content().to_string(), format!("{}:builtin", kind.as_str())),
+    }
+}
+
+fn select_profile<'a>(
+    kind: PromptKind,
+    account_type: Option<&str>,
+    user_id: Option<Uuid>,
+    profiles: &'a [PromptProfile],
+) -> Option<&'a PromptProfile> {
+    let candidates_for = |plan: Option<&str>| -> Vec<&'a PromptProfile> {
+        profiles
+            .iter()
+            .filter(|p| p.kind == kind.as_str() && p.account_type.as_deref() == plan && p.weight > 0)
+            .collect()
+    };
+
+    let mut candidates = account_type.map(|plan| candidates_for(Some(plan))).unwrap_or_default();
+    if candidates.is_empty() {
+        candidates = candidates_for(None);
+    }
+    if candidates.is_empty() {
+        return None;
+    }
+
+    let total_weight: u64 = candidates.iter().map(|p| p.weight as u64).sum();
+    let mut bucket = ab_bucket(user_id, kind) % total_weight;
+    for profile in &candidates {
+        if bucket < profile.weight as u64 {
+            return Some(profile);
+        }
+        bucket -= profile.weight as u64;
+    }
+    candidates.last().copied()
+}
+
+/// Stable per-user number for weighted variant selection (anonymous requests share one bucket)
+fn ab_bucket(user_id: Option<Uuid>, kind: PromptKind) -> u64 {
+    let key = format!("{}:{}", user_id.map(|id| id.to_string()).unwrap_or_default(), kind.as_str());
+    let digest = Sha256::digest(key.as_bytes());
+    u64::from_be_bytes(digest[..8].try_into().unwrap())
+}
+
+/// Record which prompt profiles an answer was generated with
+pub async fn tag_llm_request(llm_request_id: i64, label: &str, pool: &PgPool) {
+    if let Err(e) = sqlx::query("UPDATE llm_requests SET prompt_profile = $1 WHERE id = $2")
+        .bind(label)
+        .bind(llm_request_id)
+        .execute(pool)
+        .await
+    {
+        eprintln!("Failed to record prompt profile for LLM request {}: {}", llm_request_id, e);
+    }
+}
+
+/// Admin: list prompt profiles
+pub async fn list_prompt_profiles_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ResponseJson<Vec<PromptProfile>>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(ResponseJson(list_profiles(&pool).await?))
+}
+
+async fn list_profiles(pool: &PgPool) -> Result<Vec<PromptProfile>, StatusCode> {
+    sqlx::query_as::<_, PromptProfile>(
+        "SELECT kind, account_type, variant, content, weight, updated_at FROM prompt_profiles
+         ORDER BY kind, account_type NULLS FIRST, variant"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to list prompt profiles: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// A profile applies to all plans (None) or to one existing plan - a typo would silently never match
+fn is_valid_account_type(account_type: Option<&str>) -> bool {
+    account_type.is_none_or(|plan| crate::models::ACCOUNT_TYPES.contains(&plan))
+}
+
+/// Admin: create, update or remove a prompt profile (optionally for a single plan and A/B variant)
+pub async fn set_prompt_profile_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<SetPromptProfileRequest>,
+) -> Result<ResponseJson<Vec<PromptProfile>>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    if PromptKind::parse(&request.kind).is_none() || !is_valid_account_type(request.account_type.as_deref()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let variant = request.variant.as_deref().map(str::trim).filter(|v| !v.is_empty()).unwrap_or(DEFAULT_VARIANT);
+    if variant.chars().count() > MAX_VARIANT_CHARS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let weight = request.weight.unwrap_or(100);
+    if weight < 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let result = match request.content.as_deref().map(str::trim) {
+        Some(content) if !content.is_empty() => {
+            sqlx::query(
+                "INSERT INTO prompt_profiles (kind, account_type, variant, content, weight)
+                 VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT (kind, COALESCE(account_type, ''), variant)
+                 DO UPDATE SET content = EXCLUDED.content, weight = EXCLUDED.weight, updated_at = NOW()"
+            )
+            .bind(&request.kind)
+            .bind(&request.account_type)
+            .bind(variant)
+            .bind(content)
+            .bind(weight)
+            .execute(&pool)
+            .await
+        }
+        _ => {
+            sqlx::query(
+                "DELETE FROM prompt_profiles WHERE kind = $1 AND account_type IS NOT DISTINCT FROM $2 AND variant = $3"
+            )
+            .bind(&request.kind)
+            .bind(&request.account_type)
+            .bind(variant)
+            .execute(&pool)
+            .await
+        }
+    };
+
+    result.map_err(|e| {
+        eprintln!("Failed to update prompt profile: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    println!(
+        "✅ Prompt profile updated: kind={}, account_type={:?}, variant={}, removed={}",
+        request.kind, request.account_type, variant, request.content.is_none()
+    );
+    invalidate_cache();
+
+    Ok(ResponseJson(list_profiles(&pool).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(kind: &str, account_type: Option<&str>, variant: &str, weight: i32) -> PromptProfile {
+        PromptProfile {
+            kind: kind.to_string(),
+            account_type: account_type.map(str::to_string),
+            variant: variant.to_string(),
+            content: format!("{} {}", kind, variant),
+            weight,
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_system_prompt_precedence() {
+        let profiles = vec![
+            profile("system", None, "default", 100),
+            profile("system", Some("trial_registered"), "short", 100),
+        ];
+
+        // Plan-specific row wins; contract part falls back to the built-in prompt
+        let trial = resolve_system_prompt_from(Some("trial_registered"), None, Jurisdiction::Rs, &profiles);
+        assert!(trial.content.starts_with("system short"));
+        assert!(trial.content.contains("[CONTRACT_START]"));
+        assert_eq!(trial.label, "system:trial_registered/short,contract:builtin");
+
+        // Other plans use the generic row
+        let professional = resolve_system_prompt_from(Some("professional"), None, Jurisdiction::Rs, &profiles);
+        assert_eq!(professional.label, "system:default,contract:builtin");
+
+        // No profiles at all reproduces the built-in prompt
+        let builtin = resolve_system_prompt_from(None, None, Jurisdiction::Rs, &[]);
+        assert!(builtin.content.starts_with("Ti si pravni asistent"));
+
+        // Outside Serbia the legal part is the jurisdiction's own prompt, profiles or not
+        let croatian = resolve_system_prompt_from(Some("trial_registered"), None, Jurisdiction::Hr, &profiles);
+        assert!(croatian.content.contains("hrvatsko zakonodavstvo"));
+        assert_eq!(croatian.label, "system:hr,contract:builtin");
+    }
+
+    #[test]
+    fn test_profile_account_type_must_be_a_plan() {
+        assert!(is_valid_account_type(None));
+        assert!(is_valid_account_type(Some("trial_registered")));
+        assert!(is_valid_account_type(Some("professional")));
+        assert!(!is_valid_account_type(Some("trial")));
+        assert!(!is_valid_account_type(Some("")));
+    }
+
+    #[test]
+    fn test_ab_variant_selection_is_stable_and_weighted() {
+        let profiles = vec![
+            profile("system", None, "a", 50),
+            profile("system", None, "b", 50),
+            profile("system", None, "disabled", 0),
+        ];
+
+        let mut counts = std::collections::HashMap::new();
+        for _ in 0..200 {
+            let user_id = Some(Uuid::new_v4());
+            let first = select_profile(PromptKind::System, None, user_id, &profiles).unwrap();
+            let second = select_profile(PromptKind::System, None, user_id, &profiles).unwrap();
+            assert_eq!(first.variant, second.variant);
+            *counts.entry(first.variant.clone()).or_insert(0) += 1;
+        }
+
+        assert!(counts.get("a").copied().unwrap_or(0) > 0);
+        assert!(counts.get("b").copied().unwrap_or(0) > 0);
+        assert!(!counts.contains_key("disabled"));
+    }
+}
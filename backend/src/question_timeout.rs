@@ -0,0 +1,79 @@
+// Deadlines for the question pipeline (classification, law detection, generation, article fetches).
+// The whole pipeline runs under QUESTION_TIMEOUT_SECS; the article fetch step gets its own budget
+// (ARTICLE_FETCH_TIMEOUT_SECS) so a hung scrape returns the answer without quotes instead of
+// failing the question. Both are read in config.rs.
+//
+// Cancellation: when the client disconnects, axum drops the handler future and with it every
+// in-flight OpenRouter call and scrape. PipelineGuard only makes that visible in logs and metrics.
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Time kept after the article fetch for saving the answer before the pipeline deadline
+const SAVE_MARGIN: Duration = Duration::from_secs(5);
+
+/// Overall deadline for one question
+pub fn question_timeout() -> Duration {
+    crate::config::get().llm.question_timeout
+}
+
+/// Deadline for fetching the cited articles: the article fetch budget from now, but never past
+/// the pipeline deadline minus the time needed to save the answer
+pub fn article_fetch_deadline(pipeline_deadline: Instant) -> Instant {
+    article_fetch_deadline_from(Instant::now(), pipeline_deadline, crate::config::get().llm.article_fetch_timeout)
+}
+
+fn article_fetch_deadline_from(now: Instant, pipeline_deadline: Instant, budget: Duration) -> Instant {
+    let latest = pipeline_deadline.checked_sub(SAVE_MARGIN).unwrap_or(pipeline_deadline);
+    (now + budget).min(latest).max(now)
+}
+
+/// Logs and counts question pipelines dropped before finishing (client disconnect or timeout)
+pub struct PipelineGuard {
+    chat_id: i64,
+    started_at: Instant,
+    finished: bool,
+}
+
+impl PipelineGuard {
+    pub fn new(chat_id: i64) -> Self {
+        PipelineGuard { chat_id, started_at: Instant::now(), finished: false }
+    }
+
+    pub fn finish(mut self) {
+        self.finished = true;
+    }
+}
+
+impl Drop for PipelineGuard {
+    fn drop(&mut self) {
+        if !self.finished {
+            println!(
+                "🛑 Question pipeline for chat {} cancelled after {}ms - upstream calls aborted",
+                self.chat_id,
+                self.started_at.elapsed().as_millis()
+            );
+            crate::metrics::record_question_cancelled();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_article_fetch_deadline_respects_pipeline_deadline() {
+        let now = Instant::now();
+        let budget = Duration::from_secs(25);
+
+        // Plenty of time left: the article budget applies
+        assert_eq!(article_fetch_deadline_from(now, now + Duration::from_secs(120), budget), now + budget);
+        // Close to the pipeline deadline: leave room to save the answer
+        assert_eq!(
+            article_fetch_deadline_from(now, now + Duration::from_secs(10), budget),
+            now + Duration::from_secs(5)
+        );
+        // Already past it: fetch nothing rather than overrun
+        assert_eq!(article_fetch_deadline_from(now, now + Duration::from_secs(2), budget), now);
+    }
+}
@@ -0,0 +1,206 @@
+// Referral codes: every user can hand out a personal code (created the first time they look at
+// it). A newly registered account with a verified e-mail that enters someone's code within
+// REDEEM_WINDOW_DAYS of signing up links itself to the referrer once, and both get
+// REFERRAL_BONUS_MESSAGES extra trial messages. A referrer is rewarded for at most
+// MAX_REWARDED_REFERRALS accounts, so mass-registered accounts can't farm messages. Plans without a
+// message limit (professional, team) have nothing to top up and get no bonus.
+use crate::models::ApiError;
+use crate::promo_codes::{normalize_code, random_code};
+use crate::simple_auth::AuthAppState;
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Trial messages the referrer and the new account each receive
+pub const REFERRAL_BONUS_MESSAGES: i32 = 10;
+
+/// Referred accounts a referrer gets bonus messages for; later ones still link to them
+const MAX_REWARDED_REFERRALS: i64 = 10;
+
+/// How long after registering an account can still enter a referral code
+const REDEEM_WINDOW_DAYS: i32 = 14;
+
+/// Length of generated referral codes (users.referral_code is VARCHAR(12))
+const REFERRAL_CODE_LENGTH: usize = 8;
+
+fn database_error(e: sqlx::Error) -> ApiError {
+    eprintln!("Referral database error: {}", e);
+    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Greška baze podataka")
+}
+
+/// The user's referral code, created on first use. A generated code that collides with another
+/// user's is retried with a fresh one.
+pub async fn get_or_create_referral_code(pool: &PgPool, user_id: Uuid) -> Result<String, sqlx::Error> {
+    for _ in 0..5 {
+        let result = sqlx::query_scalar::<_, Option<String>>(
+            "UPDATE users SET referral_code = COALESCE(referral_code, $2) WHERE id = $1 RETURNING referral_code",
+        )
+        .bind(user_id)
+        .bind(random_code(REFERRAL_CODE_LENGTH))
+        .fetch_one(pool)
+        .await;
+
+        match result {
+            Ok(Some(code)) => return Ok(code),
+            Ok(None) => return Err(sqlx::Error::RowNotFound),
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(sqlx::Error::Protocol("Could not generate a unique referral code".to_string()))
+}
+
+/// Add bonus trial messages to a user on a plan with a message limit
+async fn grant_bonus_messages(tx: &mut Transaction<'_, Postgres>, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let granted = sqlx::query(
+        "UPDATE users SET trial_messages_remaining = COALESCE(trial_messages_remaining, 0) + $2, updated_at = NOW()
+         WHERE id = $1 AND account_type NOT IN ('professional', 'team', 'premium')",
+    )
+    .bind(user_id)
+    .bind(REFERRAL_BONUS_MESSAGES)
+    .execute(&mut **tx)
+    .await?
+    .rows_affected();
+    Ok(granted > 0)
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReferralSummary {
+    pub referral_code: String,
+    pub referred_users: i64,
+    pub bonus_messages: i32, // Per successful referral, for each side
+    pub referred: bool,      // Whether this user has used someone's code
+}
+
+/// The user's referral code and how many accounts signed up with it
+pub async fn get_referral_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
+    headers: HeaderMap,
+) -> Result<Json<ReferralSummary>, ApiError> {
+    let user_id = crate::database::authenticate(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    let referral_code = get_or_create_referral_code(&pool, user_id).await.map_err(database_error)?;
+    let (referred_users, referred): (i64, bool) = sqlx::query_as(
+        "SELECT (SELECT COUNT(*) FROM users WHERE referred_by = $1),
+                (SELECT referred_by IS NOT NULL FROM users WHERE id = $1)",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(database_error)?;
+
+    Ok(Json(ReferralSummary {
+        referral_code,
+        referred_users,
+        bonus_messages: REFERRAL_BONUS_MESSAGES,
+        referred,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RedeemReferralRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RedeemReferralResponse {
+    pub success: bool,
+    pub bonus_messages: i32, // 0 when the user's plan has no message limit
+    pub message: String,
+}
+
+/// A new account enters the code of the user who referred it
+pub async fn redeem_referral_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
+    headers: HeaderMap,
+    Json(request): Json<RedeemReferralRequest>,
+) -> Result<Json<RedeemReferralResponse>, ApiError> {
+    let user_id = crate::database::authenticate(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+    crate::promo_codes::throttle_code_attempts(user_id)?;
+
+    let email_verified: bool = sqlx::query_scalar("SELECT COALESCE(email_verified, false) FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(database_error)?;
+    if !email_verified {
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "EMAIL_NOT_VERIFIED",
+            "Potvrdite email adresu pre korišćenja koda za preporuku",
+        ));
+    }
+
+    let referrer_id: Uuid = sqlx::query_scalar("SELECT id FROM users WHERE referral_code = $1 AND account_status = 'active'")
+        .bind(normalize_code(&request.code))
+        .fetch_optional(&pool)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "REFERRAL_CODE_INVALID", "Kod za preporuku ne postoji"))?;
+
+    if referrer_id == user_id {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "REFERRAL_OWN_CODE", "Ne možete iskoristiti sopstveni kod"));
+    }
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+
+    // Only once, only for a new account, and not back to someone this user referred
+    let linked = sqlx::query(
+        "UPDATE users SET referred_by = $2, referred_at = NOW()
+         WHERE id = $1
+           AND referred_by IS NULL
+           AND created_at > NOW() - make_interval(days => $3)
+           AND NOT EXISTS (SELECT 1 FROM users WHERE id = $2 AND referred_by = $1)",
+    )
+    .bind(user_id)
+    .bind(referrer_id)
+    .bind(REDEEM_WINDOW_DAYS)
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?
+    .rows_affected();
+
+    if linked == 0 {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "REFERRAL_NOT_ALLOWED",
+            "Kod za preporuku može se iskoristiti samo jednom, u prvih 14 dana od registracije",
+        ));
+    }
+
+    let user_bonus = grant_bonus_messages(&mut tx, user_id).await.map_err(database_error)?;
+
+    // Locking the referrer first keeps concurrent redemptions from all counting under the cap; the
+    // count runs afterwards so it sees the ones committed meanwhile
+    sqlx::query("SELECT 1 FROM users WHERE id = $1 FOR UPDATE")
+        .bind(referrer_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+    let referred_users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE referred_by = $1")
+        .bind(referrer_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(database_error)?;
+    if referred_users <= MAX_REWARDED_REFERRALS {
+        grant_bonus_messages(&mut tx, referrer_id).await.map_err(database_error)?;
+    }
+    tx.commit().await.map_err(database_error)?;
+
+    println!("🤝 User {} was referred by {}", user_id, referrer_id);
+    let bonus_messages = if user_bonus { REFERRAL_BONUS_MESSAGES } else { 0 };
+    Ok(Json(RedeemReferralResponse {
+        success: true,
+        bonus_messages,
+        message: if user_bonus {
+            format!("Dobili ste {} dodatnih poruka", bonus_messages)
+        } else {
+            "Kod za preporuku je iskorišćen".to_string()
+        },
+    }))
+}
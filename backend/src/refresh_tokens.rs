@@ -0,0 +1,174 @@
+// Refresh tokens for the custom (non-Supabase) JWTs: opaque random strings stored hashed in
+// authentication_tokens (token_type 'jwt_refresh'). Every refresh rotates the token; all tokens
+// descending from one login share a family_id, so presenting an already rotated token (a sign it
+// was stolen) revokes the whole family.
+use crate::sessions::hash_token;
+use rand::Rng;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const REFRESH_TOKEN_TTL_DAYS: i64 = 30;
+
+pub enum RefreshOutcome {
+    Rotated { user_id: Uuid, refresh_token: String },
+    Reused,  // Token was already rotated - its family has been revoked
+    Invalid, // Unknown, expired or revoked
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct StoredRefreshToken {
+    id: i64,
+    user_id: Uuid,
+    family_id: Option<Uuid>,
+    expires_at: chrono::DateTime<chrono::Utc>,
+    used_at: Option<chrono::DateTime<chrono::Utc>>,
+    revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+fn generate_refresh_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(64)
+        .map(char::from)
+        .collect()
+}
+
+async fn insert_refresh_token<'e, E>(user_id: Uuid, family_id: Uuid, executor: E) -> Result<String, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let token = generate_refresh_token();
+    let expires_at = chrono::Utc::now() + chrono::Duration::days(REFRESH_TOKEN_TTL_DAYS);
+
+    sqlx::query(
+        "INSERT INTO authentication_tokens (user_id, token_type, token, expires_at, family_id)
+         VALUES ($1, 'jwt_refresh', $2, $3, $4)"
+    )
+    .bind(user_id)
+    .bind(hash_token(&token))
+    .bind(expires_at)
+    .bind(family_id)
+    .execute(executor)
+    .await?;
+
+    Ok(token)
+}
+
+/// Start a new token family (one per login)
+pub async fn issue(user_id: Uuid, pool: &PgPool) -> Result<String, sqlx::Error> {
+    insert_refresh_token(user_id, Uuid::new_v4(), pool).await
+}
+
+/// First token family of a user who signed in before refresh tokens existed. Each user gets
+/// exactly one: after it, or after a logout or revocation, only a real login starts a family
+/// (users who already had refresh tokens were marked by migration 0016). None if already used.
+pub async fn issue_legacy_bootstrap(user_id: Uuid, pool: &PgPool) -> Result<Option<String>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let claimed = sqlx::query(
+        "UPDATE users SET refresh_bootstrapped_at = NOW()
+         WHERE id = $1 AND refresh_bootstrapped_at IS NULL"
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if claimed == 0 {
+        return Ok(None);
+    }
+
+    let token = insert_refresh_token(user_id, Uuid::new_v4(), &mut *tx).await?;
+    tx.commit().await?;
+    Ok(Some(token))
+}
+
+/// Exchange a refresh token for a new one in the same family
+pub async fn rotate(token: &str, pool: &PgPool) -> Result<RefreshOutcome, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let stored = sqlx::query_as::<_, StoredRefreshToken>(
+        "SELECT id, user_id, family_id, expires_at, used_at, revoked_at
+         FROM authentication_tokens
+         WHERE token = $1 AND token_type = 'jwt_refresh'
+         FOR UPDATE"
+    )
+    .bind(hash_token(token))
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(stored) = stored else {
+        return Ok(RefreshOutcome::Invalid);
+    };
+
+    if stored.revoked_at.is_some() || stored.expires_at <= chrono::Utc::now() {
+        return Ok(RefreshOutcome::Invalid);
+    }
+
+    // Rows from before families were tracked form a family of their own
+    let family_id = stored.family_id.unwrap_or_else(Uuid::new_v4);
+
+    if stored.used_at.is_some() {
+        sqlx::query(
+            "UPDATE authentication_tokens SET revoked_at = NOW()
+             WHERE token_type = 'jwt_refresh' AND (family_id = $1 OR id = $2) AND revoked_at IS NULL"
+        )
+        .bind(family_id)
+        .bind(stored.id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        eprintln!("⚠️  SECURITY: Refresh token reuse for user {} - token family revoked", stored.user_id);
+        return Ok(RefreshOutcome::Reused);
+    }
+
+    sqlx::query("UPDATE authentication_tokens SET used_at = NOW() WHERE id = $1")
+        .bind(stored.id)
+        .execute(&mut *tx)
+        .await?;
+
+    let refresh_token = insert_refresh_token(stored.user_id, family_id, &mut *tx).await?;
+    tx.commit().await?;
+
+    Ok(RefreshOutcome::Rotated { user_id: stored.user_id, refresh_token })
+}
+
+/// Revoke the family of a refresh token (logout). Returns false for unknown tokens.
+pub async fn revoke_family(token: &str, pool: &PgPool) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE authentication_tokens t SET revoked_at = NOW()
+         FROM authentication_tokens presented
+         WHERE presented.token = $1 AND presented.token_type = 'jwt_refresh'
+           AND t.token_type = 'jwt_refresh' AND t.revoked_at IS NULL
+           AND (t.family_id = presented.family_id OR t.id = presented.id)"
+    )
+    .bind(hash_token(token))
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Revoke every refresh token of a user (sign out everywhere, password change)
+pub async fn revoke_all(user_id: Uuid, pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE authentication_tokens SET revoked_at = NOW()
+         WHERE user_id = $1 AND token_type = 'jwt_refresh' AND revoked_at IS NULL"
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Drop refresh tokens expired for more than a day (kept briefly so late reuse is still recognized)
+pub async fn purge_expired(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "DELETE FROM authentication_tokens WHERE token_type = 'jwt_refresh' AND expires_at < NOW() - INTERVAL '1 day'"
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
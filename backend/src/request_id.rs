@@ -0,0 +1,128 @@
+// Request correlation IDs. Every request gets an X-Request-Id - the client's if it sent one, else
+// a new UUID - which is returned on the response, recorded on the request's tracing span (so every
+// log line of the request carries it) and added to JSON error bodies as details.request_id, so an
+// error a user reports can be found in the logs.
+use axum::{
+    body::{Body, HttpBody},
+    extract::Request,
+    http::{header, HeaderName},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Error bodies larger than this are passed through untouched
+const MAX_ERROR_BODY_BYTES: u64 = 64 * 1024;
+
+/// Assigns an id to requests that arrive without one
+pub fn set_request_id_layer() -> SetRequestIdLayer<MakeRequestUuid> {
+    SetRequestIdLayer::new(REQUEST_ID_HEADER, MakeRequestUuid)
+}
+
+/// Copies the request's id onto the response
+pub fn propagate_request_id_layer() -> PropagateRequestIdLayer {
+    PropagateRequestIdLayer::new(REQUEST_ID_HEADER)
+}
+
+fn request_id<B>(request: &axum::http::Request<B>) -> Option<&str> {
+    request.headers().get(REQUEST_ID_HEADER).and_then(|value| value.to_str().ok())
+}
+
+/// Span for TraceLayer - handlers fill in user_id once they know who is asking
+pub fn make_span<B>(request: &axum::http::Request<B>) -> tracing::Span {
+    tracing::info_span!(
+        "request",
+        request_id = request_id(request).unwrap_or("-"),
+        method = %request.method(),
+        path = %request.uri().path(),
+        user_id = tracing::field::Empty,
+    )
+}
+
+/// Middleware adding the request id to ErrorResponse bodies
+pub async fn attach_to_errors(request: Request, next: Next) -> Response {
+    let request_id = request_id(&request).map(str::to_string);
+    let response = next.run(request).await;
+
+    let Some(request_id) = request_id else {
+        return response;
+    };
+    patch_error_body(response, |body| add_request_id(body, &request_id)).await
+}
+
+/// Rewrite the JSON body of an error response in place. `patch` returns false to leave the body
+/// as it was; success responses, other content types and oversized bodies are passed through.
+pub async fn patch_error_body(response: Response, patch: impl FnOnce(&mut Value) -> bool) -> Response {
+    let status = response.status();
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("application/json"));
+    let small_enough = response.body().size_hint().upper().is_some_and(|size| size <= MAX_ERROR_BODY_BYTES);
+    if !(status.is_client_error() || status.is_server_error()) || !is_json || !small_enough {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, MAX_ERROR_BODY_BYTES as usize).await else {
+        return Response::from_parts(parts, Body::empty());
+    };
+
+    let patched = serde_json::from_slice::<Value>(&bytes)
+        .ok()
+        .and_then(|mut value| patch(&mut value).then(|| serde_json::to_vec(&value).ok()).flatten());
+    match patched {
+        Some(patched) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(patched))
+        }
+        None => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+/// Put the id into the details of an ErrorResponse-shaped body; other JSON is left alone
+fn add_request_id(body: &mut Value, request_id: &str) -> bool {
+    let Some(object) = body.as_object_mut() else {
+        return false;
+    };
+    if !object.contains_key("error") {
+        return false;
+    }
+    match object.entry("details").or_insert(Value::Null) {
+        details @ Value::Null => {
+            *details = serde_json::json!({ "request_id": request_id });
+            true
+        }
+        Value::Object(details) => {
+            details.insert("request_id".to_string(), Value::from(request_id));
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_add_request_id() {
+        let mut without_details = json!({ "error": "NOT_FOUND", "message": "Nije pronađeno", "details": null });
+        assert!(add_request_id(&mut without_details, "abc"));
+        assert_eq!(without_details["details"], json!({ "request_id": "abc" }));
+
+        let mut with_details = json!({ "error": "TRIAL_ABUSE_DETECTED", "message": "", "details": { "reason": "disposable_email" } });
+        assert!(add_request_id(&mut with_details, "abc"));
+        assert_eq!(with_details["details"], json!({ "reason": "disposable_email", "request_id": "abc" }));
+
+        // Not an ErrorResponse
+        let mut other = json!({ "success": false });
+        assert!(!add_request_id(&mut other, "abc"));
+        assert_eq!(other, json!({ "success": false }));
+    }
+}
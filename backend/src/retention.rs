@@ -0,0 +1,212 @@
+// Data retention: each user chooses how long their chats are kept - 30, 90 or 365 days, or forever
+// (the default, users.retention_days NULL). The daily cleanup job deletes messages older than the
+// window and chats without activity within it, soft-deleted ones included; revisions, shares and
+// other rows hanging off them go by cascade. Documents attached to a deleted chat are deleted with
+// it (their chat_id would only be cleared), and so are contracts generated in a deleted chat or
+// message, files on disk included. GET /api/admin/retention/report shows what the next run would
+// delete without deleting anything.
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
+
+/// Chat `c` of user `u` had no activity within the user's window
+const CHAT_EXPIRED: &str = "u.retention_days IS NOT NULL AND COALESCE(c.updated_at, c.created_at) < NOW() - make_interval(days => u.retention_days)";
+/// Message `m` in chat `c` of user `u` is older than the user's window
+const MESSAGE_EXPIRED: &str = "u.retention_days IS NOT NULL AND m.created_at < NOW() - make_interval(days => u.retention_days)";
+
+/// Documents (id, user_id) attached to an expired chat
+fn expired_documents() -> String {
+    format!(
+        "SELECT d.id, d.user_id FROM documents d JOIN chats c ON c.id = d.chat_id JOIN users u ON u.id = c.user_id
+         WHERE {}",
+        CHAT_EXPIRED
+    )
+}
+
+/// Contracts (file_id, user_id) generated in an expired chat or message
+fn expired_contracts() -> String {
+    format!(
+        "SELECT cd.file_id, cd.user_id FROM contract_documents cd JOIN chats c ON c.id = cd.chat_id JOIN users u ON u.id = c.user_id
+         WHERE {chat_expired}
+         UNION
+         SELECT cd.file_id, cd.user_id FROM contract_documents cd
+         JOIN messages m ON m.contract_file_id = cd.file_id::text
+         JOIN chats c ON c.id = m.chat_id JOIN users u ON u.id = c.user_id
+         WHERE {message_expired}",
+        chat_expired = CHAT_EXPIRED,
+        message_expired = MESSAGE_EXPIRED,
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetentionPolicy {
+    #[serde(rename = "30_days")]
+    Days30,
+    #[serde(rename = "90_days")]
+    Days90,
+    #[serde(rename = "365_days")]
+    Days365,
+    #[serde(rename = "forever")]
+    Forever,
+}
+
+impl RetentionPolicy {
+    /// users.retention_days
+    pub fn days(&self) -> Option<i32> {
+        match self {
+            RetentionPolicy::Days30 => Some(30),
+            RetentionPolicy::Days90 => Some(90),
+            RetentionPolicy::Days365 => Some(365),
+            RetentionPolicy::Forever => None,
+        }
+    }
+}
+
+/// What the next purge would delete for one user
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RetentionReportEntry {
+    pub user_id: Uuid,
+    pub email: String,
+    pub retention_days: i32,
+    pub chats: i64,
+    pub messages: i64, // Including the messages of the deleted chats
+    pub documents: i64,
+    pub contracts: i64,
+    pub oldest_message_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RetentionReport {
+    pub dry_run: bool,
+    pub users: Vec<RetentionReportEntry>,
+    pub total_chats: i64,
+    pub total_messages: i64,
+    pub total_documents: i64,
+    pub total_contracts: i64,
+}
+
+/// What a purge deleted; messages of deleted chats aren't counted
+#[derive(Debug)]
+pub struct Purged {
+    pub chats: u64,
+    pub messages: u64,
+    pub documents: u64,
+    pub contracts: u64,
+    pub contract_files: usize,
+}
+
+async fn report(pool: &PgPool) -> Result<Vec<RetentionReportEntry>, sqlx::Error> {
+    let query = format!(
+        "WITH expired_chats AS (
+             SELECT c.id, c.user_id FROM chats c JOIN users u ON u.id = c.user_id WHERE {chat_expired}
+         ),
+         expired_messages AS (
+             SELECT m.id, m.created_at, c.user_id FROM messages m
+             JOIN chats c ON c.id = m.chat_id
+             JOIN users u ON u.id = c.user_id
+             WHERE ({message_expired}) OR m.chat_id IN (SELECT id FROM expired_chats)
+         ),
+         expired_documents AS ({expired_documents}),
+         expired_contracts AS ({expired_contracts})
+         SELECT u.id AS user_id, u.email, u.retention_days,
+                (SELECT COUNT(*) FROM expired_chats e WHERE e.user_id = u.id) AS chats,
+                COUNT(m.id) AS messages,
+                (SELECT COUNT(*) FROM expired_documents d WHERE d.user_id = u.id) AS documents,
+                (SELECT COUNT(*) FROM expired_contracts cd WHERE cd.user_id = u.id) AS contracts,
+                MIN(m.created_at) AS oldest_message_at
+         FROM users u
+         LEFT JOIN expired_messages m ON m.user_id = u.id
+         WHERE u.retention_days IS NOT NULL
+         GROUP BY u.id
+         HAVING COUNT(m.id) > 0 OR EXISTS (SELECT 1 FROM expired_chats e WHERE e.user_id = u.id)
+         ORDER BY COUNT(m.id) DESC",
+        chat_expired = CHAT_EXPIRED,
+        message_expired = MESSAGE_EXPIRED,
+        expired_documents = expired_documents(),
+        expired_contracts = expired_contracts(),
+    );
+    sqlx::query_as::<_, RetentionReportEntry>(&query).fetch_all(pool).await
+}
+
+/// Delete what is past each user's retention window, contract files on disk included
+pub async fn purge_expired(pool: &PgPool) -> Result<Purged, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    // Found before the chats and messages that lead to them are gone
+    let contract_ids: Vec<Uuid> = sqlx::query_scalar(&format!("SELECT file_id FROM ({}) expired", expired_contracts()))
+        .fetch_all(&mut *tx)
+        .await?;
+    let contracts = sqlx::query("DELETE FROM contract_documents WHERE file_id = ANY($1)")
+        .bind(&contract_ids)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+    let documents = sqlx::query(&format!("DELETE FROM documents WHERE id IN (SELECT id FROM ({}) expired)", expired_documents()))
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+    let chats = sqlx::query(&format!("DELETE FROM chats c USING users u WHERE c.user_id = u.id AND {}", CHAT_EXPIRED))
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+    let messages = sqlx::query(&format!(
+        "DELETE FROM messages m USING chats c, users u WHERE m.chat_id = c.id AND c.user_id = u.id AND {}",
+        MESSAGE_EXPIRED
+    ))
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    tx.commit().await?;
+
+    let contract_files = contract_ids.into_iter().map(crate::contracts::delete_contract_files).sum();
+    Ok(Purged { chats, messages, documents, contracts, contract_files })
+}
+
+/// Admin: what the next retention purge would delete, per user (dry run)
+pub async fn retention_report_handler(
+    State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+) -> Result<ResponseJson<RetentionReport>, StatusCode> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let users = report(&pool).await.map_err(|e| {
+        eprintln!("Failed to build retention report: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(ResponseJson(RetentionReport {
+        dry_run: true,
+        total_chats: users.iter().map(|entry| entry.chats).sum(),
+        total_messages: users.iter().map(|entry| entry.messages).sum(),
+        total_documents: users.iter().map(|entry| entry.documents).sum(),
+        total_contracts: users.iter().map(|entry| entry.contracts).sum(),
+        users,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_values() {
+        let policy: RetentionPolicy = serde_json::from_str("\"90_days\"").unwrap();
+        assert_eq!(policy, RetentionPolicy::Days90);
+        assert_eq!(policy.days(), Some(90));
+        assert_eq!(RetentionPolicy::Forever.days(), None);
+        assert!(serde_json::from_str::<RetentionPolicy>("\"7_days\"").is_err());
+    }
+}
@@ -0,0 +1,258 @@
+// Semantic law retrieval (RAG): cached law articles are embedded into pgvector, and the articles
+// closest to a question are injected into the answer prompt. This helps questions that don't name
+// a law or article number, where the LLM law-name guess and regex extraction have nothing to go on.
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tokio::time::{interval, Duration};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+
+// 1536 dimensions - law_article_embeddings.embedding is sized for it (migrations/0001_baseline.sql)
+const EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
+/// Articles embedded per API call / per job tick
+const EMBEDDING_BATCH_SIZE: i64 = 64;
+const BATCHES_PER_TICK: usize = 5;
+
+/// Long articles are cut before embedding - the start carries most of the meaning
+const MAX_EMBEDDED_CHARS: usize = 6000;
+
+/// Articles below this cosine similarity are not worth the prompt tokens
+const MIN_SIMILARITY: f64 = 0.35;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RetrievedArticle {
+    pub law_name: String,
+    pub article_number: String,
+    pub content: String,
+    pub similarity: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ArticleToEmbed {
+    law_id: i64,
+    law_name: String,
+    article_number: String,
+    content: String,
+}
+
+/// Whether the pgvector extension (and so the embeddings table) is installed
+pub async fn is_available(pool: &PgPool) -> bool {
+    sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM pg_extension WHERE extname = 'vector')")
+        .fetch_one(pool)
+        .await
+        .unwrap_or(false)
+}
+
+/// pgvector text literal, e.g. "[0.1,0.2]" - bound as TEXT and cast with ::vector
+fn vector_literal(embedding: &[f32]) -> String {
+    let values: Vec<String> = embedding.iter().map(|v| v.to_string()).collect();
+    format!("[{}]", values.join(","))
+}
+
+fn embedding_input(law_name: &str, article_number: &str, content: &str) -> String {
+    let text = format!("{} - Član {}\n{}", law_name, article_number, content);
+    text.chars().take(MAX_EMBEDDED_CHARS).collect()
+}
+
+async fn embed_texts(texts: &[String], openai_api_key: &str) -> Result<Vec<Vec<f32>>, String> {
+    let client = reqwest::Client::new();
+    let response = client
+        .post("https://api.openai.com/v1/embeddings")
+        .header("Authorization", format!("Bearer {}", openai_api_key))
+        .json(&serde_json::json!({
+            "model": EMBEDDING_MODEL,
+            "input": texts,
+        }))
+        .timeout(Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| format!("Embedding API error: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Embedding API returned {}: {}", status, body));
+    }
+
+    let mut parsed: EmbeddingResponse = response.json().await
+        .map_err(|e| format!("Failed to parse embedding response: {}", e))?;
+    parsed.data.sort_by_key(|d| d.index);
+
+    if parsed.data.len() != texts.len() {
+        return Err(format!("Embedding API returned {} embeddings for {} inputs", parsed.data.len(), texts.len()));
+    }
+
+    Ok(parsed.data.into_iter().map(|d| d.embedding).collect())
+}
+
+/// Embed up to one batch of indexed articles that don't have an embedding yet.
+/// Returns how many were embedded (0 = nothing left to do).
+async fn embed_pending_articles(openai_api_key: &str, pool: &PgPool) -> Result<usize, String> {
+    let pending = sqlx::query_as::<_, ArticleToEmbed>(
+        "SELECT a.law_id, c.law_name, a.article_number, a.content
+         FROM law_articles a
+         JOIN law_cache c ON c.id = a.law_id
+         LEFT JOIN law_article_embeddings e ON e.law_id = a.law_id AND e.article_number = a.article_number
+         WHERE e.law_id IS NULL
+         ORDER BY a.law_id, a.position
+         LIMIT $1"
+    )
+    .bind(EMBEDDING_BATCH_SIZE)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load articles to embed: {}", e))?;
+
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    let inputs: Vec<String> = pending
+        .iter()
+        .map(|a| embedding_input(&a.law_name, &a.article_number, &a.content))
+        .collect();
+    let embeddings = embed_texts(&inputs, openai_api_key).await?;
+
+    for (article, embedding) in pending.iter().zip(embeddings) {
+        // Fails the FK check if the law was re-indexed meanwhile - it gets picked up on the next tick
+        let result = sqlx::query(
+            "INSERT INTO law_article_embeddings (law_id, article_number, embedding, model)
+             VALUES ($1, $2, $3::vector, $4)
+             ON CONFLICT (law_id, article_number) DO UPDATE SET embedding = EXCLUDED.embedding, model = EXCLUDED.model, created_at = NOW()"
+        )
+        .bind(article.law_id)
+        .bind(&article.article_number)
+        .bind(vector_literal(&embedding))
+        .bind(EMBEDDING_MODEL)
+        .execute(pool)
+        .await;
+
+        if let Err(e) = result {
+            error!("⚠️  Failed to store embedding for law {} Član {}: {}", article.law_id, article.article_number, e);
+        }
+    }
+
+    Ok(pending.len())
+}
+
+/// Background job embedding newly indexed law articles. Re-indexed laws lose their embeddings
+/// (ON DELETE CASCADE from law_articles), so changed laws are picked up again automatically.
+pub async fn start_embedding_job(pool: Arc<PgPool>, openai_api_key: String, shutdown: CancellationToken) {
+    if !is_available(&pool).await {
+        info!("🧭 RETRIEVAL: pgvector not installed - semantic law retrieval disabled");
+        return;
+    }
+
+    let mut interval = interval(Duration::from_secs(600)); // 10 minutes
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.cancelled() => {
+                info!("🛑 Embedding job stopped");
+                return;
+            }
+        }
+
+        let mut embedded = 0;
+        for _ in 0..BATCHES_PER_TICK {
+            match embed_pending_articles(&openai_api_key, &pool).await {
+                Ok(0) => break,
+                Ok(count) => embedded += count,
+                Err(e) => {
+                    error!("❌ RETRIEVAL: Embedding batch failed: {}", e);
+                    break;
+                }
+            }
+        }
+
+        if embedded > 0 {
+            info!("🧭 RETRIEVAL: Embedded {} law article(s)", embedded);
+        }
+    }
+}
+
+/// Top-k cached articles semantically closest to the question, most similar first.
+/// Returns nothing (rather than an error) when retrieval isn't available.
+pub async fn retrieve_relevant_articles(
+    question: &str,
+    openai_api_key: &str,
+    top_k: i64,
+    pool: &PgPool,
+) -> Vec<RetrievedArticle> {
+    if openai_api_key.is_empty() || !is_available(pool).await {
+        return Vec::new();
+    }
+
+    let embedding = match embed_texts(&[question.to_string()], openai_api_key).await {
+        Ok(mut embeddings) => embeddings.remove(0),
+        Err(e) => {
+            println!("⚠️ DEBUG: Question embedding failed: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let result = sqlx::query_as::<_, RetrievedArticle>(
+        "SELECT c.law_name, a.article_number, a.content, 1 - (e.embedding <=> $1::vector) AS similarity
+         FROM law_article_embeddings e
+         JOIN law_articles a ON a.law_id = e.law_id AND a.article_number = e.article_number
+         JOIN law_cache c ON c.id = e.law_id
+         ORDER BY e.embedding <=> $1::vector
+         LIMIT $2"
+    )
+    .bind(vector_literal(&embedding))
+    .bind(top_k)
+    .fetch_all(pool)
+    .await;
+
+    match result {
+        Ok(articles) => articles.into_iter().filter(|a| a.similarity >= MIN_SIMILARITY).collect(),
+        Err(e) => {
+            println!("⚠️ DEBUG: Semantic article retrieval failed: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Format retrieved articles as a system prompt section
+pub fn format_retrieved_context(articles: &[RetrievedArticle]) -> Option<String> {
+    if articles.is_empty() {
+        return None;
+    }
+
+    let sections: Vec<String> = articles
+        .iter()
+        .map(|a| {
+            let content: String = a.content.chars().take(MAX_EMBEDDED_CHARS).collect();
+            format!("[{}] Član {}\n{}", a.law_name, a.article_number, content)
+        })
+        .collect();
+
+    Some(format!(
+        "RELEVANTNI ČLANOVI IZ BAZE ZAKONA (koristi ih ako se odnose na pitanje, navedi zakon i član koji citiraš):\n\n{}",
+        sections.join("\n\n")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vector_literal() {
+        assert_eq!(vector_literal(&[0.5, -1.0, 0.25]), "[0.5,-1,0.25]");
+        assert_eq!(vector_literal(&[]), "[]");
+    }
+}
@@ -19,6 +19,8 @@ pub struct Subscriber {
     pub original_app_user_id: String,
     pub entitlements: HashMap<String, Entitlement>,
     pub subscriptions: HashMap<String, Subscription>,
+    #[serde(default)]
+    pub non_subscriptions: HashMap<String, Vec<NonSubscription>>, // Consumables, by product ID
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -28,6 +30,14 @@ pub struct Entitlement {
     pub purchase_date: String,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NonSubscription {
+    pub id: String,
+    pub purchase_date: String,
+    pub store: String,
+    pub store_transaction_id: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Subscription {
     pub expires_date: Option<String>,
@@ -57,9 +67,12 @@ pub struct WebhookEventData {
     pub expiration_at_ms: Option<i64>,
     pub store: String,
     pub environment: String, // "PRODUCTION", "SANDBOX"
+    pub price_in_purchased_currency: Option<f64>, // VAT included
+    pub currency: Option<String>,
+    pub transaction_id: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SubscriptionStatus {
     pub account_type: String,
     pub subscription_type: Option<String>, // "monthly" or "yearly"
@@ -255,11 +268,25 @@ pub fn product_id_to_plan_info(product_id: &str) -> Option<(&str, &str)> {
     }
 }
 
+/// Map consumable message pack product IDs to the number of messages they add
+pub fn product_id_to_message_pack(product_id: &str) -> Option<i32> {
+    match product_id {
+        "com.nikola.normaai.messages.20" => Some(20),
+        _ => None,
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_message_pack_mapping() {
+        assert_eq!(product_id_to_message_pack("com.nikola.normaai.messages.20"), Some(20));
+        assert_eq!(product_id_to_message_pack("com.nikola.normaai.individual.monthly"), None);
+        assert_eq!(product_id_to_plan_info("com.nikola.normaai.messages.20"), None);
+    }
+
     #[test]
     fn test_product_id_mapping() {
         assert_eq!(
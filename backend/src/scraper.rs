@@ -1,20 +1,34 @@
 use axum::{
     extract::{State, Json},
-    response::Json as ResponseJson,
-    http::StatusCode,
+    response::{IntoResponse, Json as ResponseJson, Response},
+    http::{HeaderMap, StatusCode},
 };
 use scraper::{Html, Selector};
 use crate::models::*;
+use regex::Regex;
 use sqlx::PgPool;
+use std::sync::OnceLock;
 
 type AppState = (PgPool, String, String, Option<String>); // (pool, api_key, jwt_secret, supabase_jwt_secret)
 
 pub async fn fetch_law_content_handler(
     State((pool, _, _, _)): State<AppState>,
+    headers: HeaderMap,
     Json(request): Json<FetchLawContentRequest>,
-) -> Result<ResponseJson<LawContent>, StatusCode> {
+) -> Result<Response, StatusCode> {
+    // Cached laws are versioned by cached_at, so clients can revalidate instead of re-downloading
+    let law_name = extract_law_name_from_url(&request.url);
+    if let Ok(Some(cached)) = get_cached_law(law_name.clone(), &pool).await {
+        let etag = crate::http_cache::law_etag(&cached.law_name, cached.cached_at);
+        let content = LawContent {
+            title: law_name,
+            content: cached.content,
+        };
+        return Ok(crate::http_cache::conditional_json(&headers, &etag, content));
+    }
+
     match fetch_law_content_direct(request.url, &pool).await {
-        Ok(content) => Ok(ResponseJson(content)),
+        Ok(content) => Ok(ResponseJson(content).into_response()),
         Err(e) => {
             eprintln!("Failed to fetch law content: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -37,7 +51,66 @@ pub async fn fetch_law_content_direct(url: String, pool: &PgPool) -> Result<LawC
         });
     }
     
-    let response = reqwest::get(&url)
+    Ok(scrape_law_with_fallback(&url, pool).await?.law)
+}
+
+/// A freshly scraped law page with the cache validators the server sent
+pub struct ScrapedLawPage {
+    pub law: LawContent, // Content is already cleaned for the AI
+    pub source: &'static str,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+/// Scrape a law page, falling back to alternate URLs (other source sites) registered for it
+/// when the primary site is down, blocks us, or returns something we can't parse.
+pub async fn scrape_law_with_fallback(url: &str, pool: &PgPool) -> Result<ScrapedLawPage, String> {
+    let primary_error = match scrape_law_page(url).await {
+        Ok(page) => return Ok(page),
+        Err(e) => e,
+    };
+
+    let alternates = crate::law_sources::alternate_urls(url, pool).await;
+    if alternates.is_empty() {
+        return Err(primary_error);
+    }
+
+    let mut errors = vec![primary_error];
+    for alternate_url in alternates {
+        println!("🔁 DEBUG: Primary source failed, trying alternate {}", alternate_url);
+        match scrape_law_page(&alternate_url).await {
+            Ok(page) => {
+                println!("✅ DEBUG: Loaded law from alternate source {} ({})", page.source, alternate_url);
+                return Ok(page);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    Err(format!("All law sources failed: {}", errors.join("; ")))
+}
+
+/// Fetch and parse a single law page with the parser of its source site, bypassing the cache
+pub async fn scrape_law_page(url: &str) -> Result<ScrapedLawPage, String> {
+    let source = crate::law_sources::source_for_url(url);
+    let started_at = std::time::Instant::now();
+    let result = fetch_and_parse_law_page(url, source).await;
+    crate::metrics::record_scrape(source.name(), started_at.elapsed(), result.is_ok());
+    crate::health::observe_law_scrape(result.is_ok());
+    result
+}
+
+async fn fetch_and_parse_law_page(url: &str, source: &'static dyn crate::law_sources::LawSource) -> Result<ScrapedLawPage, String> {
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .user_agent("Mozilla/5.0 (compatible; NormaAI/1.0; +https://chat.normaai.rs)")
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let response = client
+        .get(url)
+        .send()
         .await
         .map_err(|e| {
             let error = format!("Failed to fetch URL: {}", e);
@@ -45,7 +118,17 @@ pub async fn fetch_law_content_direct(url: String, pool: &PgPool) -> Result<LawC
             error
         })?;
     
-    println!("✅ DEBUG: HTTP response received, status: {}", response.status());
+    println!("✅ DEBUG: HTTP response received from {}, status: {}", source.name(), response.status());
+
+    if !response.status().is_success() {
+        return Err(format!("{} returned HTTP {}", source.name(), response.status()));
+    }
+
+    let header = |name: reqwest::header::HeaderName| {
+        response.headers().get(name).and_then(|v| v.to_str().ok()).map(String::from)
+    };
+    let etag = header(reqwest::header::ETAG);
+    let last_modified = header(reqwest::header::LAST_MODIFIED);
     
     let html_content = response
         .text()
@@ -57,8 +140,12 @@ pub async fn fetch_law_content_direct(url: String, pool: &PgPool) -> Result<LawC
         })?;
 
     println!("✅ DEBUG: HTML content received, length: {} chars", html_content.len());
+
+    if source.is_blocked_page(&html_content) {
+        return Err(format!("{} blocked the request (captcha or access denied page)", source.name()));
+    }
     
-    let result = parse_law_content(html_content);
+    let result = source.parse(&html_content);
     match result {
         Ok(content) => {
             println!("✅ DEBUG: Law content parsed - Title: {}, Content: {} chars",
@@ -68,13 +155,18 @@ pub async fn fetch_law_content_direct(url: String, pool: &PgPool) -> Result<LawC
             let cleaned_content = clean_content_for_ai(&content.content);
 
             // Return cleaned content
-            Ok(LawContent {
-                title: content.title,
-                content: cleaned_content,
+            Ok(ScrapedLawPage {
+                law: LawContent {
+                    title: content.title,
+                    content: cleaned_content,
+                },
+                source: source.name(),
+                etag,
+                last_modified,
             })
         },
         Err(e) => {
-            println!("❌ DEBUG: Failed to parse law content: {}", e);
+            println!("❌ DEBUG: Failed to parse law content from {}: {}", source.name(), e);
             Err(e)
         }
     }
@@ -101,11 +193,12 @@ fn extract_law_name_from_url(url: &str) -> String {
     }
 }
 
-fn parse_law_content(html: String) -> Result<LawContent, String> {
-    let document = Html::parse_document(&html);
+/// Parse a law page using the title/content selectors of its source site (see law_sources.rs)
+pub fn parse_law_content(html: &str, title_selector: &str, content_selectors: &[&str]) -> Result<LawContent, String> {
+    let document = Html::parse_document(html);
     
     // Try to get title from h1 or title tag
-    let title_selector = Selector::parse("h1, .naslov, .title")
+    let title_selector = Selector::parse(title_selector)
         .map_err(|e| format!("Failed to parse title selector: {}", e))?;
     
     let title = document
@@ -114,17 +207,7 @@ fn parse_law_content(html: String) -> Result<LawContent, String> {
         .map(|el| el.text().collect::<Vec<_>>().join(" ").trim().to_string())
         .unwrap_or_else(|| "Zakon".to_string());
 
-    // Extract main content - paragraf.rs typically uses specific classes
-    let content_selectors = vec![
-        ".sadrzaj",
-        ".content",
-        ".zakon-content", 
-        "#content",
-        "article",
-        "main",
-        ".main-content"
-    ];
-
+    // Extract main content using the first selector that matches
     let mut content = String::new();
     
     for selector_str in content_selectors {
@@ -307,19 +390,22 @@ pub fn clean_content_for_ai(content: &str) -> String {
     let article_spaced = add_article_spacing(&cleaned);
     
     // Only remove excessive whitespace (4+ newlines), preserve double and triple
-    let re = regex::Regex::new(r"\n{4,}").unwrap();
+    static EXCESS_NEWLINES: OnceLock<Regex> = OnceLock::new();
+    let re = EXCESS_NEWLINES.get_or_init(|| Regex::new(r"\n{4,}").unwrap());
     re.replace_all(&article_spaced, "\n\n").to_string()
 }
 
 fn add_article_spacing(content: &str) -> String {
-    use regex::Regex;
-    
+    // Compiled once - every scraped law goes through here
+    static CLAN_PATTERN: OnceLock<Regex> = OnceLock::new();
+    static CLEANUP_PATTERN: OnceLock<Regex> = OnceLock::new();
+
     // Add double line break before each "Član" (except first one)
-    let clan_pattern = Regex::new(r"(?m)^(Član \d+[a-z]?)").unwrap();
+    let clan_pattern = CLAN_PATTERN.get_or_init(|| Regex::new(r"(?m)^(Član \d+[a-z]?)").unwrap());
     let mut result = clan_pattern.replace_all(content, "\n\n$1").to_string();
     
     // Clean up any triple newlines that might have been created
-    let cleanup_pattern = Regex::new(r"\n{3,}").unwrap();
+    let cleanup_pattern = CLEANUP_PATTERN.get_or_init(|| Regex::new(r"\n{3,}").unwrap());
     result = cleanup_pattern.replace_all(&result, "\n\n").to_string();
     
     // Trim any leading newlines
@@ -0,0 +1,97 @@
+// Secrets at rest. Values the server must read back (2FA secrets, single-use e-mail tokens) are
+// stored AES-256-GCM encrypted as base64(nonce || ciphertext || tag), with associated data tying a
+// ciphertext to its purpose so one can't be swapped in for another. Single-use tokens are looked up
+// by an HMAC of the token rather than the token itself, so a database leak exposes neither.
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hmac::{Hmac, Mac};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use sha2::{Digest, Sha256};
+
+pub type Key = [u8; 32];
+
+/// Independent keys for different uses of the same configured secret
+pub fn derive_key(purpose: &str, key_material: &str) -> Key {
+    Sha256::digest(format!("{}:{}", purpose, key_material).as_bytes()).into()
+}
+
+fn aead_key(key: &Key) -> LessSafeKey {
+    LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).expect("AES-256 key is 32 bytes"))
+}
+
+pub fn seal(key: &Key, plaintext: &[u8], aad: &[u8]) -> Result<String, String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| "Failed to generate nonce".to_string())?;
+
+    let mut sealed = plaintext.to_vec();
+    aead_key(key)
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(aad), &mut sealed)
+        .map_err(|_| "Encryption failed".to_string())?;
+
+    let mut stored = nonce.to_vec();
+    stored.extend_from_slice(&sealed);
+    Ok(BASE64.encode(stored))
+}
+
+pub fn open(key: &Key, stored: &str, aad: &[u8]) -> Result<Vec<u8>, String> {
+    let bytes = BASE64.decode(stored).map_err(|_| "Invalid encrypted value".to_string())?;
+    if bytes.len() <= NONCE_LEN {
+        return Err("Invalid encrypted value".to_string());
+    }
+
+    let (nonce, sealed) = bytes.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| "Invalid nonce".to_string())?;
+    let mut sealed = sealed.to_vec();
+    let plaintext = aead_key(key)
+        .open_in_place(nonce, Aad::from(aad), &mut sealed)
+        .map_err(|_| "Decryption failed (wrong key?)".to_string())?;
+    Ok(plaintext.to_vec())
+}
+
+/// Hex HMAC-SHA256, for looking up a secret without storing it
+pub fn lookup_hash(key: &Key, value: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(value.as_bytes());
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+/// TOKEN_ENCRYPTION_KEY, or JWT_SECRET when it isn't set (config.rs refuses to start with neither,
+/// so this is never the public default secret). Changing it invalidates outstanding password reset
+/// and e-mail verification links.
+fn token_key_material() -> &'static str {
+    let config = crate::config::get();
+    config.token_encryption_key.as_deref().unwrap_or(&config.jwt_secret)
+}
+
+/// (HMAC key, encryption key) for authentication_tokens
+pub fn token_keys() -> (Key, Key) {
+    let material = token_key_material();
+    (derive_key("normaai-token-lookup", material), derive_key("normaai-token-encryption", material))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let key = derive_key("test", "secret");
+        let sealed = seal(&key, b"reset-token", b"password_reset").unwrap();
+        assert_ne!(sealed, seal(&key, b"reset-token", b"password_reset").unwrap()); // Fresh nonce
+        assert_eq!(open(&key, &sealed, b"password_reset").unwrap(), b"reset-token");
+
+        assert!(open(&key, &sealed, b"email_verification").is_err()); // Bound to its purpose
+        assert!(open(&derive_key("test", "other"), &sealed, b"password_reset").is_err());
+        assert!(open(&key, "not base64!", b"password_reset").is_err());
+    }
+
+    #[test]
+    fn test_lookup_hash() {
+        let key = derive_key("lookup", "secret");
+        assert_eq!(lookup_hash(&key, "abc").len(), 64);
+        assert_eq!(lookup_hash(&key, "abc"), lookup_hash(&key, "abc"));
+        assert_ne!(lookup_hash(&key, "abc"), lookup_hash(&derive_key("lookup", "other"), "abc"));
+    }
+}
@@ -1,13 +1,18 @@
 // Session management module for tracking user sessions and enforcing device limits
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use sha2::{Digest, Sha256};
 use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
 use tracing::{info, warn};
 use uuid::Uuid;
 
 const MAX_CONCURRENT_SESSIONS: i64 = 5;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeviceInfo {
     pub session_id: Option<String>,  // Stable UUID per app instance (survives token refresh)
     pub name: Option<String>,        // "iPhone 14 Pro"
@@ -46,7 +51,7 @@ pub async fn create_or_update_session(
 ) -> Result<Uuid, sqlx::Error> {
     let token_hash = hash_token(token);
     let expires_at = chrono::Utc::now() + chrono::Duration::hours(24 * 30); // 30 days
-    let device_info_json = device_info.as_ref().map(|d| serde_json::to_value(d).ok()).flatten();
+    let device_info_json = device_info.as_ref().and_then(|d| serde_json::to_value(d).ok());
 
     // Check if session already exists (same token)
     let existing_session: Option<(Uuid,)> = sqlx::query_as(
@@ -394,3 +399,171 @@ pub async fn cleanup_user_sessions(pool: &Pool<Postgres>, user_id: Uuid) -> Resu
 
     Ok(deleted_count)
 }
+
+// ==================== IP GEOLOCATION ====================
+// Approximate location of session IPs for the "active sessions" screen. Looked up through an HTTP
+// geolocation API and cached in memory, since the same few IPs are listed on every visit to the
+// screen. Lookups send user IPs to a third party, so they only run when GEOIP_API_URL is configured
+// (e.g. https://ipapi.co/{ip}/json/).
+
+const GEOIP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+const GEOIP_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+const GEOIP_FAILURE_TTL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+const GEOIP_CACHE_MAX_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct GeoLocation {
+    pub city: Option<String>,
+    #[serde(alias = "country_name")] // ipapi.co; ip-api.com uses "country"
+    pub country: Option<String>,
+    #[serde(alias = "countryCode")]
+    pub country_code: Option<String>,
+}
+
+type GeoCache = HashMap<IpAddr, (Instant, Option<GeoLocation>)>;
+
+static GEOIP_CACHE: OnceLock<Mutex<GeoCache>> = OnceLock::new();
+
+fn geoip_cache() -> &'static Mutex<GeoCache> {
+    GEOIP_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+static GEOIP_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+fn geoip_client() -> &'static reqwest::Client {
+    GEOIP_CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(GEOIP_TIMEOUT)
+            .user_agent("norma-ai-backend")
+            .build()
+            .expect("Failed to build geolocation client")
+    })
+}
+
+/// Private, loopback and other non-routable addresses have no meaningful location
+fn is_public_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            !(v4.is_private() || v4.is_loopback() || v4.is_link_local() || v4.is_unspecified() || v4.is_broadcast())
+        }
+        IpAddr::V6(v6) => {
+            let unique_local = (v6.segments()[0] & 0xfe00) == 0xfc00;
+            let link_local = (v6.segments()[0] & 0xffc0) == 0xfe80;
+            !(v6.is_loopback() || v6.is_unspecified() || unique_local || link_local)
+        }
+    }
+}
+
+fn geoip_url(template: &str, ip: &IpAddr) -> String {
+    template.replace("{ip}", &ip.to_string())
+}
+
+fn parse_geolocation(body: serde_json::Value) -> Option<GeoLocation> {
+    // Both providers report lookup failures in the body with a 200 status
+    if body.get("error").and_then(|v| v.as_bool()) == Some(true) || body.get("status").and_then(|v| v.as_str()) == Some("fail") {
+        return None;
+    }
+    let location: GeoLocation = serde_json::from_value(body).ok()?;
+    (location.city.is_some() || location.country.is_some()).then_some(location)
+}
+
+async fn fetch_geolocation(url: &str) -> Result<Option<GeoLocation>, String> {
+    let response = geoip_client()
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("Geolocation request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Geolocation API error: {}", response.status()));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse geolocation response: {}", e))?;
+    Ok(parse_geolocation(body))
+}
+
+/// Approximate location of an IP address (None when unknown, private or lookups are disabled)
+pub async fn geolocate(ip: IpAddr) -> Option<GeoLocation> {
+    let template = crate::config::get().geoip_api_url.as_deref()?;
+    if !is_public_ip(&ip) {
+        return None;
+    }
+    if let Some((expires_at, location)) = geoip_cache().lock().unwrap().get(&ip) {
+        if *expires_at > Instant::now() {
+            return location.clone();
+        }
+    }
+
+    let (location, ttl) = match fetch_geolocation(&geoip_url(template, &ip)).await {
+        Ok(location) => (location, GEOIP_CACHE_TTL),
+        Err(e) => {
+            warn!(ip = %ip, "{}", e);
+            (None, GEOIP_FAILURE_TTL)
+        }
+    };
+
+    let mut cache = geoip_cache().lock().unwrap();
+    if cache.len() >= GEOIP_CACHE_MAX_ENTRIES {
+        let now = Instant::now();
+        cache.retain(|_, (expires_at, _)| *expires_at > now);
+        if cache.len() >= GEOIP_CACHE_MAX_ENTRIES {
+            cache.clear();
+        }
+    }
+    cache.insert(ip, (Instant::now() + ttl, location.clone()));
+
+    location
+}
+
+/// Locations of several IPs, looked up concurrently
+pub async fn geolocate_all(ips: impl IntoIterator<Item = IpAddr>) -> HashMap<IpAddr, GeoLocation> {
+    let mut lookups = tokio::task::JoinSet::new();
+    for ip in ips.into_iter().collect::<std::collections::HashSet<_>>() {
+        lookups.spawn(async move { (ip, geolocate(ip).await) });
+    }
+
+    let mut locations = HashMap::new();
+    while let Some(result) = lookups.join_next().await {
+        if let Ok((ip, Some(location))) = result {
+            locations.insert(ip, location);
+        }
+    }
+    locations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_public_ip() {
+        assert!(is_public_ip(&"93.87.12.201".parse().unwrap()));
+        assert!(is_public_ip(&"2a02:2f0c:8100::1".parse().unwrap()));
+        assert!(!is_public_ip(&"192.168.1.10".parse().unwrap()));
+        assert!(!is_public_ip(&"127.0.0.1".parse().unwrap()));
+        assert!(!is_public_ip(&"fd00::1".parse().unwrap()));
+        assert!(!is_public_ip(&"::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_geolocation_url_and_parsing() {
+        let ip: IpAddr = "93.87.12.201".parse().unwrap();
+        assert_eq!(geoip_url("https://ipapi.co/{ip}/json/", &ip), "https://ipapi.co/93.87.12.201/json/");
+        assert_eq!(geoip_url("http://ip-api.com/json/{ip}", &ip), "http://ip-api.com/json/93.87.12.201");
+
+        let ipapi = serde_json::json!({"ip": "93.87.12.201", "city": "Belgrade", "country_name": "Serbia", "country_code": "RS"});
+        assert_eq!(
+            parse_geolocation(ipapi),
+            Some(GeoLocation {
+                city: Some("Belgrade".to_string()),
+                country: Some("Serbia".to_string()),
+                country_code: Some("RS".to_string()),
+            })
+        );
+        let ip_api = serde_json::json!({"status": "success", "city": "Novi Sad", "country": "Serbia", "countryCode": "RS"});
+        assert_eq!(parse_geolocation(ip_api).and_then(|l| l.city).as_deref(), Some("Novi Sad"));
+        assert_eq!(parse_geolocation(serde_json::json!({"error": true, "reason": "RateLimited"})), None);
+        assert_eq!(parse_geolocation(serde_json::json!({"status": "fail", "message": "reserved range"})), None);
+    }
+}
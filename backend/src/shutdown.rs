@@ -0,0 +1,69 @@
+// Graceful shutdown: on SIGTERM/SIGINT the server stops accepting connections, in-flight requests
+// (mostly long LLM answers) get SHUTDOWN_DRAIN_TIMEOUT_SECONDS to finish, and background jobs stop
+// at their next tick via the shared CancellationToken. Work spawned from handlers (law preload
+// jobs, data exports, event WebSockets) watches the same token through token().
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+static TOKEN: OnceLock<CancellationToken> = OnceLock::new();
+
+/// The process-wide shutdown token
+pub fn token() -> CancellationToken {
+    TOKEN.get_or_init(CancellationToken::new).clone()
+}
+
+/// SHUTDOWN_DRAIN_TIMEOUT_SECONDS, see config.rs
+pub fn drain_timeout() -> Duration {
+    crate::config::get().shutdown_drain_timeout
+}
+
+/// Cancel the token on the first SIGINT (Ctrl+C) or SIGTERM (Fly.io deploys)
+pub async fn listen_for_signals(shutdown: CancellationToken) {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("🛑 Received SIGINT"),
+        _ = terminate => info!("🛑 Received SIGTERM"),
+        _ = shutdown.cancelled() => return,
+    }
+
+    shutdown.cancel();
+}
+
+/// Wait for background jobs to observe the cancellation, giving up at the deadline
+pub async fn join_background_jobs(jobs: Vec<(&'static str, JoinHandle<()>)>, deadline: tokio::time::Instant) {
+    for (name, job) in jobs {
+        match tokio::time::timeout_at(deadline, job).await {
+            Ok(Ok(())) => info!("✅ {} stopped", name),
+            Ok(Err(e)) => warn!("⚠️  {} ended abnormally: {}", name, e),
+            Err(_) => {
+                warn!("⚠️  {} did not stop before the drain deadline", name);
+                return;
+            }
+        }
+    }
+}
@@ -1,6 +1,11 @@
 // Simplified auth module without compile-time database validation
+use crate::audit_log::{AuditAction, AuditSource};
 use crate::database::get_user_status_optimized;
 use crate::models::*;
+use crate::pricing::Proration;
+use crate::promo_codes::AppliedPromoCode;
+use crate::sessions::GeoLocation;
+use crate::subscriptions::Transition;
 use axum::{
     extract::State,
     http::{HeaderMap, StatusCode},
@@ -10,6 +15,7 @@ use bcrypt::{hash, DEFAULT_COST};
 use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use sqlx::{Pool, Postgres, Row};
 use uuid::Uuid;
 use validator::{Validate, ValidationError};
@@ -24,7 +30,8 @@ fn validate_password_strength(password: &str) -> Result<(), ValidationError> {
         .any(|c| "!@#$%^&*()_+-=[]{}|;:,.<>?".contains(c));
 
     if !(has_uppercase && has_lowercase && has_digit && has_special) {
-        return Err(ValidationError::new(
+        return Err(crate::i18n::field_error(
+            "weak_password",
             "Lozinka mora sadržati velika i mala slova, broj i specijalni karakter",
         ));
     }
@@ -97,22 +104,125 @@ pub fn verify_token(token: &str, jwt_secret: &str) -> Result<Claims, String> {
     .map_err(|e| format!("Token verification failed: {}", e))
 }
 
-// Verify Supabase JWT token
-pub fn verify_supabase_token(
+// Supabase signing keys (asymmetric JWT signing), cached so verification doesn't hit the network per request
+const JWKS_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+// Minimum gap between refetches triggered by an unknown key id (key rotation), so bogus kids can't flood Supabase
+const JWKS_MIN_REFETCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+struct CachedJwks {
+    keys: jsonwebtoken::jwk::JwkSet,
+    fetched_at: std::time::Instant,
+}
+
+fn jwks_cache() -> &'static tokio::sync::RwLock<Option<CachedJwks>> {
+    static JWKS_CACHE: std::sync::OnceLock<tokio::sync::RwLock<Option<CachedJwks>>> = std::sync::OnceLock::new();
+    JWKS_CACHE.get_or_init(|| tokio::sync::RwLock::new(None))
+}
+
+fn supabase_jwks_url() -> Option<String> {
+    crate::config::get()
+        .supabase_url
+        .as_ref()
+        .map(|url| format!("{}/auth/v1/.well-known/jwks.json", url))
+}
+
+/// Whether Supabase tokens can be verified at all (shared secret or signing keys)
+pub fn supabase_auth_configured(supabase_jwt_secret: Option<&str>) -> bool {
+    supabase_jwt_secret.is_some() || supabase_jwks_url().is_some()
+}
+
+async fn fetch_jwks(url: &str) -> Result<jsonwebtoken::jwk::JwkSet, String> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(5))
+        .build()
+        .map_err(|e| format!("Failed to build JWKS client: {}", e))?;
+
+    client
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch Supabase JWKS: {}", e))?
+        .json::<jsonwebtoken::jwk::JwkSet>()
+        .await
+        .map_err(|e| format!("Invalid Supabase JWKS: {}", e))
+}
+
+/// Signing key for a key id, refetching the key set when it's stale or the kid is new
+async fn supabase_signing_key(kid: &str) -> Result<jsonwebtoken::jwk::Jwk, String> {
+    let url = supabase_jwks_url().ok_or_else(|| "SUPABASE_URL not configured".to_string())?;
+
+    {
+        let cache = jwks_cache().read().await;
+        if let Some(cached) = cache.as_ref() {
+            let fresh = cached.fetched_at.elapsed() < JWKS_CACHE_TTL;
+            match cached.keys.find(kid) {
+                Some(key) if fresh => return Ok(key.clone()),
+                None if cached.fetched_at.elapsed() < JWKS_MIN_REFETCH_INTERVAL => {
+                    return Err(format!("Unknown Supabase signing key: {}", kid));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut cache = jwks_cache().write().await;
+    // Another request may have refreshed the set while we waited for the lock
+    if let Some(cached) = cache.as_ref() {
+        if cached.fetched_at.elapsed() < JWKS_MIN_REFETCH_INTERVAL {
+            return cached.keys.find(kid).cloned()
+                .ok_or_else(|| format!("Unknown Supabase signing key: {}", kid));
+        }
+    }
+
+    match fetch_jwks(&url).await {
+        Ok(keys) => {
+            let key = keys.find(kid).cloned();
+            *cache = Some(CachedJwks { keys, fetched_at: std::time::Instant::now() });
+            key.ok_or_else(|| format!("Unknown Supabase signing key: {}", kid))
+        }
+        Err(e) => {
+            // Keep verifying with the last known keys while Supabase is unreachable
+            eprintln!("⚠️  {}", e);
+            cache.as_ref()
+                .and_then(|cached| cached.keys.find(kid).cloned())
+                .ok_or(e)
+        }
+    }
+}
+
+// Verify Supabase JWT token: RS256/ES256 against the project's JWKS, HS256 with the legacy shared secret
+pub async fn verify_supabase_token(
     token: &str,
-    supabase_jwt_secret: &str,
+    supabase_jwt_secret: Option<&str>,
 ) -> Result<SupabaseClaims, String> {
-    let mut validation = Validation::new(Algorithm::HS256);
+    let header = jsonwebtoken::decode_header(token)
+        .map_err(|e| format!("Supabase token verification failed: {}", e))?;
+
+    let (decoding_key, algorithm) = match header.alg {
+        Algorithm::RS256 | Algorithm::ES256 => {
+            let kid = header.kid.as_deref()
+                .ok_or_else(|| "Supabase token has no key id".to_string())?;
+            let jwk = supabase_signing_key(kid).await?;
+            let key = DecodingKey::from_jwk(&jwk)
+                .map_err(|e| format!("Unusable Supabase signing key: {}", e))?;
+            (key, header.alg)
+        }
+        Algorithm::HS256 => {
+            let secret = supabase_jwt_secret
+                .ok_or_else(|| "SUPABASE_JWT_SECRET not configured for HS256 token".to_string())?;
+            (DecodingKey::from_secret(secret.as_ref()), Algorithm::HS256)
+        }
+        other => return Err(format!("Unsupported Supabase token algorithm: {:?}", other)),
+    };
+
+    let mut validation = Validation::new(algorithm);
     validation.set_audience(&["authenticated"]);
     validation.validate_exp = true;
 
-    decode::<SupabaseClaims>(
-        token,
-        &DecodingKey::from_secret(supabase_jwt_secret.as_ref()),
-        &validation,
-    )
-    .map(|data| data.claims)
-    .map_err(|e| format!("Supabase token verification failed: {}", e))
+    decode::<SupabaseClaims>(token, &decoding_key, &validation)
+        .map(|data| data.claims)
+        .map_err(|e| format!("Supabase token verification failed: {}", e))
 }
 
 // Query Supabase auth.identities to get OAuth providers for a user email
@@ -154,16 +264,17 @@ pub async fn verify_any_token(
     supabase_jwt_secret: Option<&str>,
     pool: &Pool<Postgres>,
 ) -> Result<Uuid, String> {
-    // Try Supabase token first if we have the secret
-    if let Some(supabase_secret) = supabase_jwt_secret {
-        if let Ok(claims) = verify_supabase_token(token, supabase_secret) {
+    // Try Supabase token first if Supabase auth is configured
+    if supabase_auth_configured(supabase_jwt_secret) {
+        if let Ok(claims) = verify_supabase_token(token, supabase_jwt_secret).await {
             // Parse Supabase user ID
             let auth_user_id = Uuid::parse_str(&claims.sub)
                 .map_err(|_| "Invalid Supabase user ID in token".to_string())?;
 
-            // Look up user by auth_user_id
+            // Look up user by auth_user_id (an identity whose account was merged signs in to the survivor)
             let user = sqlx::query_as::<_, User>(
-                "SELECT * FROM users WHERE auth_user_id = $1 AND account_status = 'active'",
+                "SELECT * FROM users
+                 WHERE id = (SELECT COALESCE(merged_into, id) FROM users WHERE auth_user_id = $1) AND account_status = 'active'",
             )
             .bind(auth_user_id)
             .fetch_optional(pool)
@@ -171,6 +282,14 @@ pub async fn verify_any_token(
             .map_err(|e| format!("Database error: {}", e))?
             .ok_or_else(|| "User not found for Supabase token".to_string())?;
 
+            // Pick up name/avatar changes made at the OAuth provider without delaying the request
+            let sync_pool = pool.clone();
+            tokio::spawn(async move {
+                if let Err(e) = sync_supabase_profile(user.id, false, &sync_pool).await {
+                    eprintln!("⚠️  Failed to sync Supabase profile for user {}: {}", user.id, e);
+                }
+            });
+
             return Ok(user.id);
         }
     }
@@ -183,10 +302,67 @@ pub async fn verify_any_token(
     Ok(user_id)
 }
 
+// Sign-ins of accounts with 2FA (a Supabase session being linked, a legacy token starting a
+// refresh token family) need a TOTP or recovery code
+async fn require_second_factor(
+    user_id: Uuid,
+    code: Option<&str>,
+    pool: &Pool<Postgres>,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let error = |status: StatusCode, error: &str, message: &str| {
+        (
+            status,
+            Json(ErrorResponse {
+                error: error.to_string(),
+                message: message.to_string(),
+                details: None,
+            }),
+        )
+    };
+
+    let enabled = crate::totp::is_enabled(user_id, pool).await.map_err(|e| {
+        eprintln!("2FA lookup error: {}", e);
+        error(StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Greška baze podataka")
+    })?;
+    if !enabled {
+        return Ok(());
+    }
+
+    let code = code
+        .filter(|c| !c.trim().is_empty())
+        .ok_or_else(|| error(StatusCode::UNAUTHORIZED, "TWO_FACTOR_REQUIRED", "Unesite kod za dvostruku potvrdu identiteta"))?;
+    let verified = crate::totp::verify_second_factor(user_id, code, pool).await.map_err(|e| {
+        eprintln!("2FA verification error: {}", e);
+        error(StatusCode::UNAUTHORIZED, "TWO_FACTOR_ERROR", "Greška provere koda")
+    })?;
+    if !verified {
+        return Err(error(StatusCode::UNAUTHORIZED, "INVALID_TWO_FACTOR_CODE", "Neispravan kod za dvostruku potvrdu identiteta"));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LinkUserRequest {
+    pub two_factor_code: Option<String>, // TOTP or recovery code, for accounts with 2FA
+}
+
 // Link Supabase auth user to backend user (for registration and OAuth)
+#[utoipa::path(
+    post,
+    path = "/api/auth/link-user",
+    tag = "auth",
+    request_body(content = Option<LinkUserRequest>, description = "Only needed for accounts with 2FA"),
+    responses(
+        (status = 200, description = "User linked; returns custom tokens when needed", body = AuthResponse),
+        (status = 401, description = "Missing or invalid token, or the account's second factor is missing or wrong", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn link_user_handler(
-    State((pool, _, _jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
     headers: axum::http::HeaderMap,
+    payload: Option<Json<LinkUserRequest>>,
 ) -> Result<Json<AuthResponse>, (StatusCode, Json<ErrorResponse>)> {
     // Extract token for session creation
     let token = headers
@@ -197,14 +373,12 @@ pub async fn link_user_handler(
 
     // Extract Supabase auth_user_id DIRECTLY from JWT token (not from public.users)
     // We need the auth.users.id, not the public.users.id!
-    let supabase_user_id = if let Some(supabase_secret) = supabase_jwt_secret.as_deref() {
-        token
-            .as_ref()
-            .and_then(|t| verify_supabase_token(t, supabase_secret).ok())
-            .map(|claims| Uuid::parse_str(&claims.sub).ok())
-            .flatten()
-    } else {
-        None
+    let supabase_user_id = match token.as_deref() {
+        Some(t) => verify_supabase_token(t, supabase_jwt_secret.as_deref())
+            .await
+            .ok()
+            .and_then(|claims| Uuid::parse_str(&claims.sub).ok()),
+        None => None,
     };
 
     let supabase_user_id = supabase_user_id.ok_or_else(|| {
@@ -305,8 +479,10 @@ pub async fn link_user_handler(
         (None, None, None)
     };
 
-    // Check if user already exists in public.users by auth_user_id
-    let existing_user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE auth_user_id = $1")
+    // Check if user already exists in public.users by auth_user_id - or was merged into another account
+    let existing_user = sqlx::query_as::<_, User>(
+        "SELECT * FROM users WHERE id = (SELECT COALESCE(merged_into, id) FROM users WHERE auth_user_id = $1)",
+    )
         .bind(supabase_user_id)
         .fetch_optional(&pool)
         .await
@@ -321,7 +497,16 @@ pub async fn link_user_handler(
             )
         })?;
 
-    let (user_id, migrated_chats) = if let Some(user) = existing_user {
+    // Linking is the sign-in that creates the session, so accounts with 2FA need the second factor
+    // before anything else happens (restore, chat migration, session)
+    if let Some(user) = &existing_user {
+        let code = payload.as_ref().and_then(|Json(p)| p.two_factor_code.as_deref());
+        require_second_factor(user.id, code, &pool).await?;
+    }
+
+    let device_session_id = headers.get("X-Device-Session-Id").and_then(|h| h.to_str().ok());
+
+    let (user_id, migrated_chats, email_verified) = if let Some(user) = existing_user {
         // Check if user is deleted and within grace period - auto-restore
         if user.account_status == "deleted" {
             if let Some(deleted_at) = user.deleted_at {
@@ -343,6 +528,18 @@ pub async fn link_user_handler(
                         })?;
 
                     println!("✅ Auto-restored deleted account for user {}", user.email);
+                    crate::audit_log::record(
+                        &pool,
+                        user.id,
+                        AuditAction::AccountRestored,
+                        AuditSource::Request(&headers),
+                        serde_json::json!({ "on_login": true }),
+                    )
+                    .await;
+
+                    if let Err(e) = crate::email_service::send_account_restored_email(&_resend_api_key, &user.email).await {
+                        eprintln!("❌ Failed to send account restored email: {:?}", e);
+                    }
                 } else {
                     // Grace period expired
                     return Err((
@@ -386,7 +583,12 @@ pub async fn link_user_handler(
             println!("✅ Auto-verified email for OAuth user {}", user.email);
         }
 
-        (user.id, 0)
+        // Login is a good moment to pick up name/avatar changes from the provider
+        if let Err(e) = sync_supabase_profile(user.id, true, &pool).await {
+            eprintln!("⚠️  Failed to sync Supabase profile for user {}: {}", user.email, e);
+        }
+
+        (user.id, 0, user.email_verified || is_oauth)
     } else {
         // Create new registered user with trial (5 messages)
         let new_user_id = Uuid::new_v4();
@@ -418,9 +620,29 @@ pub async fn link_user_handler(
             )
         })?;
 
-        (new_user_id, 0)
+        // Free daily question asked on this device before registering becomes the first chat
+        let claimed_chats = match device_session_id {
+            Some(device_session_id) => crate::free_question::claim_free_questions(device_session_id, new_user_id, &pool)
+                .await
+                .unwrap_or_else(|e| {
+                    eprintln!("⚠️  {}", e);
+                    0
+                }),
+            None => 0,
+        };
+
+        (new_user_id, claimed_chats, email_verified)
     };
 
+    // Guest chats from this device and chats of a duplicate account move onto the linked user
+    let migrated_chats = migrated_chats
+        + crate::chat_migration::migrate_chats(&pool, user_id, device_session_id, &email, email_verified)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("⚠️  Failed to migrate chats to user {}: {}", user_id, e);
+                0
+            });
+
     // Create session for this login
     if let Some(ref token_str) = token {
         // Extract device session ID from custom header
@@ -504,6 +726,18 @@ pub async fn link_user_handler(
             .and_then(|s| s.split(',').next())
             .and_then(|s| s.trim().parse::<std::net::IpAddr>().ok());
 
+        // Checked before the session is created, which would make the device look known
+        let new_device = match device_info.as_ref() {
+            Some(device) => crate::login_alerts::register_device(&pool, user_id, device)
+                .await
+                .unwrap_or_else(|e| {
+                    eprintln!("⚠️ Failed to register device (non-fatal): {}", e);
+                    false
+                }),
+            None => false,
+        };
+        let alert_device = if new_device { device_info.clone() } else { None };
+
         match crate::sessions::create_or_update_session(
             &pool,
             user_id,
@@ -515,6 +749,27 @@ pub async fn link_user_handler(
         {
             Ok(session_id) => {
                 println!("✅ Session created/updated: {} for user {}", session_id, user_id);
+
+                if let Some(device) = alert_device {
+                    println!("🔔 New device sign-in for user {}, sending alert", user_id);
+                    let resend_api_key = _resend_api_key.clone();
+                    let jwt_secret = jwt_secret.clone();
+                    let email = email.clone();
+                    let pool = pool.clone();
+                    tokio::spawn(async move {
+                        crate::login_alerts::send_new_device_alert(
+                            &pool,
+                            &resend_api_key,
+                            &jwt_secret,
+                            &email,
+                            user_id,
+                            session_id,
+                            &device,
+                            ip_address,
+                        )
+                        .await;
+                    });
+                }
             }
             Err(e) => {
                 eprintln!("⚠️ Failed to create session (non-fatal): {}", e);
@@ -532,18 +787,28 @@ pub async fn link_user_handler(
     }))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CheckProviderRequest {
     pub email: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct CheckProviderResponse {
     pub has_oauth: bool,
     pub providers: Vec<String>,
     pub user_exists: bool, // NEW: explicitly indicate if user exists
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/auth/check-provider",
+    tag = "auth",
+    request_body = CheckProviderRequest,
+    responses(
+        (status = 200, description = "Sign-in providers of the e-mail address", body = CheckProviderResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    )
+)]
 pub async fn check_provider_handler(
     State((pool, _, _, _, _, _resend_api_key)): State<AuthAppState>,
     Json(request): Json<CheckProviderRequest>,
@@ -567,6 +832,16 @@ pub async fn check_provider_handler(
 }
 
 // User status endpoint - uses optimized single-query approach
+#[utoipa::path(
+    get,
+    path = "/api/auth/user-status",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Plan, limits and profile of the user", body = UserStatusResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn user_status_handler(
     State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
     headers: axum::http::HeaderMap,
@@ -596,129 +871,154 @@ pub async fn user_status_handler(
     }
 }
 
-// Refresh JWT token
+// Refresh JWT token: rotates the presented refresh token and mints a new access token
+#[utoipa::path(
+    post,
+    path = "/api/auth/refresh",
+    tag = "auth",
+    request_body(content = Option<RefreshRequest>),
+    responses(
+        (status = 200, description = "New access and refresh token", body = AuthResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 403, description = "Two-factor code required (TWO_FACTOR_REQUIRED)", body = ErrorResponse)
+    )
+)]
 pub async fn refresh_handler(
     State((pool, _, jwt_secret, _, _, _resend_api_key)): State<AuthAppState>,
     headers: axum::http::HeaderMap,
+    payload: Option<Json<RefreshRequest>>,
 ) -> Result<Json<AuthResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // Get current token from Authorization header
-    let auth_header = headers
-        .get("Authorization")
-        .and_then(|header| header.to_str().ok())
-        .and_then(|header| header.strip_prefix("Bearer "));
+    let database_error = |e: sqlx::Error| {
+        eprintln!("Refresh token database error: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "DATABASE_ERROR".to_string(),
+                message: "Greška baze podataka".to_string(),
+                details: Some(serde_json::json!({"details": e.to_string()})),
+            }),
+        )
+    };
+    let unauthorized = |error: &str, message: &str| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: error.to_string(),
+                message: message.to_string(),
+                details: None,
+            }),
+        )
+    };
 
-    if let Some(token) = auth_header {
-        match verify_token(token, &jwt_secret) {
-            Ok(claims) => {
-                // Parse user ID and validate user still exists and is active
-                if let Ok(user_id) = Uuid::parse_str(&claims.sub) {
-                    // Check if user still exists and is active in database
-                    let user = sqlx::query("SELECT email, account_status FROM users WHERE id = $1")
-                        .bind(&user_id)
-                        .fetch_optional(&pool)
-                        .await
-                        .map_err(|e| {
-                            (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(ErrorResponse {
-                                    error: "DATABASE_ERROR".to_string(),
-                                    message: "Greška baze podataka".to_string(),
-                                    details: Some(serde_json::json!({"details": e.to_string()})),
-                                }),
-                            )
-                        })?;
+    let payload = payload.map(|Json(p)| p);
+    let presented = payload
+        .as_ref()
+        .and_then(|p| p.refresh_token.clone())
+        .filter(|t| !t.is_empty());
+
+    let (user_id, refresh_token) = if let Some(presented) = presented {
+        match crate::refresh_tokens::rotate(&presented, &pool).await.map_err(database_error)? {
+            crate::refresh_tokens::RefreshOutcome::Rotated { user_id, refresh_token } => (user_id, refresh_token),
+            crate::refresh_tokens::RefreshOutcome::Reused => {
+                return Err(unauthorized(
+                    "REFRESH_TOKEN_REUSED",
+                    "Sesija je prekinuta iz bezbednosnih razloga - prijavite se ponovo",
+                ));
+            }
+            crate::refresh_tokens::RefreshOutcome::Invalid => {
+                return Err(unauthorized("INVALID_REFRESH_TOKEN", "Neispravan ili istekao token za osvežavanje"));
+            }
+        }
+    } else {
+        // Clients from before refresh tokens: a still valid access token is exchanged for a first
+        // refresh token once per user, ever. After that - and after a logout or revocation - only
+        // the refresh token or a new login can be used.
+        let token = headers
+            .get("Authorization")
+            .and_then(|header| header.to_str().ok())
+            .and_then(|header| header.strip_prefix("Bearer "))
+            .ok_or_else(|| unauthorized("MISSING_TOKEN", "Token nije pronađen"))?;
 
-                    let user = user.ok_or((
-                        StatusCode::UNAUTHORIZED,
-                        Json(ErrorResponse {
-                            error: "USER_NOT_FOUND".to_string(),
-                            message: "Korisnik ne postoji".to_string(),
-                            details: None,
-                        }),
-                    ))?;
+        let user_id = verify_token(token, &jwt_secret)
+            .ok()
+            .and_then(|claims| Uuid::parse_str(&claims.sub).ok())
+            .ok_or_else(|| unauthorized("INVALID_TOKEN", "Neispravan token"))?;
 
-                    let email: String = user.get("email");
-                    let account_status: String = user.get("account_status");
+        // Starting a token family is the login of a custom-JWT account, so it needs the second
+        // factor. Rotations don't: families from before 2FA was enabled were revoked at enrollment.
+        require_second_factor(user_id, payload.as_ref().and_then(|p| p.two_factor_code.as_deref()), &pool).await?;
 
-                    // Check if account is active
-                    if account_status != "active" {
-                        return Err((
-                            StatusCode::UNAUTHORIZED,
-                            Json(ErrorResponse {
-                                error: "ACCOUNT_INACTIVE".to_string(),
-                                message: "Nalog nije aktivan".to_string(),
-                                details: None,
-                            }),
-                        ));
-                    }
+        let refresh_token = crate::refresh_tokens::issue_legacy_bootstrap(user_id, &pool)
+            .await
+            .map_err(database_error)?
+            .ok_or_else(|| unauthorized("REFRESH_TOKEN_REQUIRED", "Potreban je token za osvežavanje"))?;
+        (user_id, refresh_token)
+    };
 
-                    // Update last_login
-                    sqlx::query("UPDATE users SET last_login = NOW() WHERE id = $1")
-                        .bind(&user_id)
-                        .execute(&pool)
-                        .await
-                        .ok(); // Don't fail refresh if this fails
+    // Check if user still exists and is active in database
+    let user = sqlx::query("SELECT email, account_status FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(database_error)?;
 
-                    let new_token = generate_token(user_id, &email, &jwt_secret).map_err(|e| {
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(ErrorResponse {
-                                error: "TOKEN_ERROR".to_string(),
-                                message: "Greška generisanja novog tokena".to_string(),
-                                details: Some(serde_json::json!({"details": e})),
-                            }),
-                        )
-                    })?;
+    let active_email = user
+        .filter(|user| user.get::<String, _>("account_status") == "active")
+        .map(|user| user.get::<String, _>("email"));
 
-                    return Ok(Json(AuthResponse {
-                        success: true,
-                        user_id: Some(user_id),
-                        access_token: Some(new_token),
-                        refresh_token: None,
-                        migrated_chats: None,
-                        message: "Token uspešno osvežen".to_string(),
-                    }));
-                }
-            }
-            Err(_) => {
-                return Err((
-                    StatusCode::UNAUTHORIZED,
-                    Json(ErrorResponse {
-                        error: "INVALID_TOKEN".to_string(),
-                        message: "Neispravan token".to_string(),
-                        details: None,
-                    }),
-                ));
-            }
+    let Some(email) = active_email else {
+        // Don't leave a usable token behind for a deleted/suspended account
+        if let Err(e) = crate::refresh_tokens::revoke_all(user_id, &pool).await {
+            eprintln!("Failed to revoke refresh tokens of inactive user {}: {}", user_id, e);
         }
-    }
+        return Err(unauthorized("ACCOUNT_INACTIVE", "Nalog nije aktivan"));
+    };
 
-    Err((
-        StatusCode::UNAUTHORIZED,
-        Json(ErrorResponse {
-            error: "MISSING_TOKEN".to_string(),
-            message: "Token nije pronađen".to_string(),
-            details: None,
-        }),
-    ))
+    // Update last_login
+    sqlx::query("UPDATE users SET last_login = NOW() WHERE id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .ok(); // Don't fail refresh if this fails
+
+    let new_token = generate_token(user_id, &email, &jwt_secret).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "TOKEN_ERROR".to_string(),
+                message: "Greška generisanja novog tokena".to_string(),
+                details: Some(serde_json::json!({"details": e})),
+            }),
+        )
+    })?;
+
+    Ok(Json(AuthResponse {
+        success: true,
+        user_id: Some(user_id),
+        access_token: Some(new_token),
+        refresh_token: Some(refresh_token),
+        migrated_chats: None,
+        message: "Token uspešno osvežen".to_string(),
+    }))
 }
 
 // Forgot password endpoint
+#[utoipa::path(
+    post,
+    path = "/api/auth/forgot-password",
+    tag = "auth",
+    request_body = ForgotPasswordRequest,
+    responses(
+        (status = 200, description = "Reset e-mail sent if the account exists", body = PasswordResetResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse)
+    )
+)]
 pub async fn forgot_password_handler(
     State((pool, _, _, _, _, _resend_api_key)): State<AuthAppState>,
     Json(request): Json<ForgotPasswordRequest>,
 ) -> Result<Json<PasswordResetResponse>, (StatusCode, Json<ErrorResponse>)> {
     // Validate input
-    if let Err(e) = request.validate() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "VALIDATION_ERROR".to_string(),
-                message: "Email adresa nije validna".to_string(),
-                details: Some(serde_json::to_value(e.field_errors()).unwrap()),
-            }),
-        ));
-    }
+    request.validate().map_err(|e| crate::i18n::validation_error(&e))?;
 
     // Check if user exists
     let user =
@@ -777,10 +1077,6 @@ pub async fn forgot_password_handler(
             }
         }
 
-        return Ok(Json(PasswordResetResponse {
-            success: true,
-            message: "Instrukcije za resetovanje lozinke su poslate na email.".to_string(),
-        }));
     }
 
     // Always return success to prevent email enumeration attacks
@@ -791,21 +1087,22 @@ pub async fn forgot_password_handler(
 }
 
 // Reset password endpoint
+#[utoipa::path(
+    post,
+    path = "/api/auth/reset-password",
+    tag = "auth",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 200, description = "Password changed", body = MessageResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse)
+    )
+)]
 pub async fn reset_password_handler(
     State((pool, _, _, _, _, _resend_api_key)): State<AuthAppState>,
     Json(request): Json<ResetPasswordRequest>,
 ) -> Result<Json<MessageResponse>, (StatusCode, Json<ErrorResponse>)> {
     // Validate input
-    if let Err(e) = request.validate() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "VALIDATION_ERROR".to_string(),
-                message: "Podaci nisu validni".to_string(),
-                details: Some(serde_json::to_value(e.field_errors()).unwrap()),
-            }),
-        ));
-    }
+    request.validate().map_err(|e| crate::i18n::validation_error(&e))?;
 
     // Find and validate reset token
     let reset_token = AuthenticationToken::find_by_token(&pool, &request.token, "password_reset")
@@ -890,6 +1187,16 @@ pub async fn reset_password_handler(
 }
 
 // Request email verification (send/resend verification email)
+#[utoipa::path(
+    post,
+    path = "/api/auth/request-email-verification",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Verification e-mail sent", body = VerificationEmailResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn request_email_verification_handler(
     State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
     headers: axum::http::HeaderMap,
@@ -998,6 +1305,16 @@ pub async fn request_email_verification_handler(
 }
 
 // Email verification endpoint
+#[utoipa::path(
+    post,
+    path = "/api/auth/verify-email",
+    tag = "auth",
+    request_body = VerifyEmailRequest,
+    responses(
+        (status = 200, description = "E-mail verified", body = MessageResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse)
+    )
+)]
 pub async fn verify_email_handler(
     State((pool, _, _, _, _, _resend_api_key)): State<AuthAppState>,
     Json(request): Json<VerifyEmailRequest>,
@@ -1073,9 +1390,37 @@ pub async fn verify_email_handler(
 }
 
 // Logout endpoint
-pub async fn logout_handler() -> Result<Json<MessageResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // Since we're using stateless JWT tokens, logout is handled client-side
-    // by removing the token from storage
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout",
+    tag = "auth",
+    request_body(content = Option<LogoutRequest>),
+    responses(
+        (status = 200, description = "Refresh token revoked", body = MessageResponse)
+    )
+)]
+pub async fn logout_handler(
+    State((pool, _, _, _, _, _resend_api_key)): State<AuthAppState>,
+    payload: Option<Json<LogoutRequest>>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<ErrorResponse>)> {
+    // Access tokens are stateless and expire within the hour; the refresh token family is revoked
+    // so the session can't be extended. The client removes its tokens from storage.
+    if let Some(refresh_token) = payload.and_then(|Json(p)| p.refresh_token).filter(|t| !t.is_empty()) {
+        crate::refresh_tokens::revoke_family(&refresh_token, &pool)
+            .await
+            .map_err(|e| {
+                eprintln!("Failed to revoke refresh token on logout: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        error: "DATABASE_ERROR".to_string(),
+                        message: "Greška prilikom odjave".to_string(),
+                        details: Some(serde_json::json!({"details": e.to_string()})),
+                    }),
+                )
+            })?;
+    }
+
     Ok(Json(MessageResponse {
         success: true,
         message: "Uspešno ste se odjavili".to_string(),
@@ -1083,6 +1428,18 @@ pub async fn logout_handler() -> Result<Json<MessageResponse>, (StatusCode, Json
 }
 
 // Create premium subscription
+#[utoipa::path(
+    post,
+    path = "/api/subscription/create",
+    tag = "subscription",
+    request_body = CreateSubscriptionRequest,
+    responses(
+        (status = 200, description = "Subscription created", body = SubscriptionResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn create_subscription_handler(
     State((pool, _, jwt_secret, _, _, _resend_api_key)): State<AuthAppState>,
     headers: axum::http::HeaderMap,
@@ -1098,6 +1455,11 @@ pub async fn create_subscription_handler(
         match verify_token(token, &jwt_secret) {
             Ok(claims) => {
                 if let Ok(user_id) = Uuid::parse_str(&claims.sub) {
+                    // With card payments set up, plans are activated by the Stripe webhook once paid
+                    if crate::config::get().stripe.is_some() {
+                        return Err(checkout_required());
+                    }
+
                     // Calculate subscription dates based on billing period
                     let now = chrono::Utc::now();
                     let (expires_at, next_billing_date) = match request.billing_period.as_str() {
@@ -1121,24 +1483,17 @@ pub async fn create_subscription_handler(
                         }
                     };
 
-                    // Extract price from pricing object or calculate based on plan and billing period
-                    let price = request
-                        .pricing
-                        .get("price")
-                        .and_then(|p| p.as_i64())
-                        .unwrap_or_else(|| {
-                            match (request.plan_id.as_str(), request.billing_period.as_str()) {
-                                ("individual", "monthly") => 3400,
-                                ("individual", "yearly") => 34000,
-                                ("professional", "monthly") => 6400,
-                                ("professional", "yearly") => 64000,
-                                ("team", "monthly") => 24900, // Base team price
-                                ("team", "yearly") => 249000,
-                                ("premium", "monthly") => 6400, // Migrate premium to professional pricing
-                                ("premium", "yearly") => 64000,
-                                _ => 6400, // Default to professional monthly
-                            }
-                        }) as i32;
+                    // The list price, never what the client sent
+                    let Some(price) = crate::pricing::plan_price_rsd(&request.plan_id, &request.billing_period) else {
+                        return Err((
+                            StatusCode::BAD_REQUEST,
+                            Json(ErrorResponse {
+                                error: "INVALID_PLAN".to_string(),
+                                message: "Neispravan plan ID".to_string(),
+                                details: None,
+                            }),
+                        ));
+                    };
 
                     // Map plan_id to account_type (keeping premium for backward compatibility)
                     let account_type = match request.plan_id.as_str() {
@@ -1149,41 +1504,7 @@ pub async fn create_subscription_handler(
                         _ => "professional",         // Default fallback
                     };
 
-                    // Generate team_id for team plans
-                    let team_id = if request.plan_id == "team" {
-                        Some(Uuid::new_v4())
-                    } else {
-                        None
-                    };
-
-                    // Create subscription by updating user account
-                    sqlx::query(
-                        "UPDATE users SET
-                            account_type = $1,
-                            premium_expires_at = $2,
-                            subscription_type = $3,
-                            subscription_started_at = $4,
-                            next_billing_date = $5,
-                            subscription_status = 'active',
-                            team_id = $6,
-                            trial_messages_remaining = CASE
-                                WHEN $1 = 'individual' THEN 20
-                                WHEN $1 IN ('professional', 'team') THEN NULL
-                                ELSE trial_messages_remaining
-                            END,
-                            updated_at = NOW()
-                        WHERE id = $7",
-                    )
-                    .bind(account_type)
-                    .bind(expires_at)
-                    .bind(&request.billing_period)
-                    .bind(now)
-                    .bind(next_billing_date)
-                    .bind(team_id)
-                    .bind(user_id)
-                    .execute(&pool)
-                    .await
-                    .map_err(|e| {
+                    let database_error = |e: sqlx::Error| {
                         (
                             StatusCode::INTERNAL_SERVER_ERROR,
                             Json(ErrorResponse {
@@ -1192,7 +1513,73 @@ pub async fn create_subscription_handler(
                                 details: Some(serde_json::json!({"details": e.to_string()})),
                             }),
                         )
-                    })?;
+                    };
+                    let mut tx = pool.begin().await.map_err(database_error)?;
+
+                    // A promo code is redeemed in the same transaction, so it only counts as used
+                    // if the subscription is created
+                    let promo_code = match request.promo_code.as_deref().filter(|code| !code.trim().is_empty()) {
+                        Some(code) => Some(
+                            crate::promo_codes::redeem(&mut tx, user_id, code, account_type, &request.billing_period, price)
+                                .await
+                                .map_err(|e| <(StatusCode, Json<ErrorResponse>)>::from(crate::models::ApiError::from(e)))?,
+                        ),
+                        None => None,
+                    };
+                    let price_after_discount = price - promo_code.as_ref().map_or(0, |promo| promo.discount_rsd);
+
+                    let activate = Transition::Activate {
+                        plan: account_type.to_string(),
+                        billing_period: request.billing_period.clone(),
+                        started_at: now,
+                        period_ends_at: Some(next_billing_date),
+                    };
+                    crate::subscriptions::transition_in(&mut tx, user_id, &activate).await.map_err(database_error)?;
+
+                    // Account credit left over from a downgrade goes towards the price
+                    let previous_credit: i32 = sqlx::query_scalar(
+                        "UPDATE users SET account_credit_rsd = GREATEST(users.account_credit_rsd - $2, 0)
+                        FROM (SELECT account_credit_rsd FROM users WHERE id = $1) AS previous
+                        WHERE users.id = $1
+                        RETURNING previous.account_credit_rsd",
+                    )
+                    .bind(user_id)
+                    .bind(price_after_discount)
+                    .fetch_one(&mut *tx)
+                    .await
+                    .map_err(database_error)?;
+                    let proration = crate::pricing::prorate(None, price_after_discount, previous_credit, now);
+
+                    // Nothing is charged without card payments - the period is only paid with credit
+                    sqlx::query("UPDATE users SET subscription_paid_rsd = $1 WHERE id = $2")
+                        .bind(proration.covered_rsd())
+                        .bind(user_id)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(database_error)?;
+                    tx.commit().await.map_err(database_error)?;
+
+                    crate::audit_log::record(
+                        &pool,
+                        user_id,
+                        AuditAction::SubscriptionCreated,
+                        AuditSource::Request(&headers),
+                        serde_json::json!({
+                            "plan": request.plan_id,
+                            "billing_period": request.billing_period,
+                            "promo_code": promo_code.as_ref().map(|promo| &promo.code),
+                        }),
+                    )
+                    .await;
+
+                    let plan_name = match request.plan_id.as_str() {
+                        "individual" => "Individual",
+                        "professional" => "Professional",
+                        "team" => "Team",
+                        "premium" => "Professional", // Migrate premium to professional
+                        _ => "Professional",
+                    };
+                    let period_name = if request.billing_period == "yearly" { "godišnje" } else { "mesečno" };
 
                     return Ok(Json(SubscriptionResponse {
                         success: true,
@@ -1201,21 +1588,9 @@ pub async fn create_subscription_handler(
                         status: "active".to_string(),
                         expires_at: Some(expires_at),
                         price_rsd: price,
-                        message: format!(
-                            "{} pretplata aktivirana ({})",
-                            match request.plan_id.as_str() {
-                                "individual" => "Individual",
-                                "professional" => "Professional",
-                                "team" => "Team",
-                                "premium" => "Professional", // Migrate premium to professional
-                                _ => "Professional",
-                            },
-                            if request.billing_period == "yearly" {
-                                "godišnje"
-                            } else {
-                                "mesečno"
-                            }
-                        ),
+                        message: format!("{} pretplata aktivirana ({})", plan_name, period_name),
+                        proration: Some(proration),
+                        promo_code,
                     }));
                 }
             }
@@ -1243,6 +1618,16 @@ pub async fn create_subscription_handler(
 }
 
 // Get subscription status
+#[utoipa::path(
+    get,
+    path = "/api/subscription/status",
+    tag = "subscription",
+    responses(
+        (status = 200, description = "Current subscription", body = SubscriptionResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn subscription_status_handler(
     State((pool, _, jwt_secret, _, _, _resend_api_key)): State<AuthAppState>,
     headers: axum::http::HeaderMap,
@@ -1334,6 +1719,8 @@ pub async fn subscription_status_handler(
                             expires_at: premium_expires_at,
                             price_rsd: price,
                             message: "Status pretplate".to_string(),
+                            proration: None,
+                            promo_code: None,
                         }));
                     } else {
                         return Ok(Json(SubscriptionResponse {
@@ -1344,6 +1731,8 @@ pub async fn subscription_status_handler(
                             expires_at: None,
                             price_rsd: 0,
                             message: "Korisnik nije pronađen".to_string(),
+                            proration: None,
+                            promo_code: None,
                         }));
                     }
                 }
@@ -1372,6 +1761,16 @@ pub async fn subscription_status_handler(
 }
 
 // Cancel subscription
+#[utoipa::path(
+    post,
+    path = "/api/subscription/cancel",
+    tag = "subscription",
+    responses(
+        (status = 200, description = "Subscription cancelled at the end of the period", body = MessageResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn cancel_subscription_handler(
     State((pool, _, jwt_secret, _, _, _resend_api_key)): State<AuthAppState>,
     headers: axum::http::HeaderMap,
@@ -1386,21 +1785,23 @@ pub async fn cancel_subscription_handler(
         match verify_token(token, &jwt_secret) {
             Ok(claims) => {
                 if let Ok(user_id) = Uuid::parse_str(&claims.sub) {
-                    // Cancel premium subscription (keep premium until billing period ends)
-                    sqlx::query(
-                        "UPDATE users SET
-                            premium_expires_at = next_billing_date,
-                            subscription_type = NULL,
-                            subscription_started_at = NULL,
-                            next_billing_date = NULL,
-                            subscription_status = 'cancelled',
-                            updated_at = NOW()
-                        WHERE id = $1 AND account_type = 'premium'",
-                    )
-                    .bind(user_id)
-                    .execute(&pool)
-                    .await
-                    .map_err(|e| {
+                    // A card subscription stops renewing in Stripe; its webhook ends it with the period
+                    crate::payments::cancel_stripe_subscription(&pool, user_id).await.map_err(|e| {
+                        eprintln!("❌ Failed to cancel Stripe subscription for user {}: {}", user_id, e);
+                        (
+                            StatusCode::BAD_GATEWAY,
+                            Json(ErrorResponse {
+                                error: "PAYMENT_PROVIDER_ERROR".to_string(),
+                                message: "Greška otkazivanja pretplate".to_string(),
+                                details: None,
+                            }),
+                        )
+                    })?;
+
+                    // The plan stays usable until the billing period ends
+                    crate::subscriptions::transition(&pool, user_id, &Transition::Cancel)
+                        .await
+                        .map_err(|e| {
                         (
                             StatusCode::INTERNAL_SERVER_ERROR,
                             Json(ErrorResponse {
@@ -1411,6 +1812,15 @@ pub async fn cancel_subscription_handler(
                         )
                     })?;
 
+                    crate::audit_log::record(
+                        &pool,
+                        user_id,
+                        AuditAction::SubscriptionCancelled,
+                        AuditSource::Request(&headers),
+                        serde_json::json!({}),
+                    )
+                    .await;
+
                     return Ok(Json(MessageResponse {
                         success: true,
                         message: "Pretplata je uspešno otkazana".to_string(),
@@ -1441,44 +1851,57 @@ pub async fn cancel_subscription_handler(
 }
 
 // Enhanced trial start endpoint with bypass detection
-#[derive(serde::Deserialize, Validate)]
+#[derive(serde::Deserialize, Validate, ToSchema)]
 pub struct ForgotPasswordRequest {
-    #[validate(email(message = "Neispravna email adresa"))]
+    #[validate(email(code = "invalid_email", message = "Neispravna email adresa"))]
     pub email: String,
 }
 
-#[derive(serde::Deserialize, Validate)]
+#[derive(serde::Deserialize, Validate, ToSchema)]
 pub struct ResetPasswordRequest {
-    #[validate(length(min = 32, max = 256, message = "Neispravan token"))]
+    #[validate(length(min = 32, max = 256, code = "invalid_token", message = "Neispravan token"))]
     pub token: String,
     #[validate(length(
         min = 8,
         max = 128,
+        code = "password_length",
         message = "Lozinka mora imati između 8 i 128 karaktera"
     ))]
     #[validate(custom = "validate_password_strength")]
     pub new_password: String,
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, ToSchema)]
 pub struct VerifyEmailRequest {
     pub token: String,
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    pub refresh_token: Option<String>,
+    pub two_factor_code: Option<String>, // TOTP or recovery code, for accounts with 2FA
+}
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct LogoutRequest {
+    pub refresh_token: Option<String>,
+}
+
+#[derive(serde::Deserialize, ToSchema)]
 pub struct CreateSubscriptionRequest {
-    pub plan_id: String,            // "individual", "professional", "team", "premium"
-    pub billing_period: String,     // "monthly" or "yearly"
-    pub pricing: serde_json::Value, // Price details from frontend
+    pub plan_id: String,        // "individual", "professional", "team", "premium"
+    pub billing_period: String, // "monthly" or "yearly" - the price is the list price (pricing.rs)
+    #[serde(default)]
+    pub promo_code: Option<String>, // Checked beforehand with /api/promo-codes/validate
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, ToSchema)]
 pub struct MessageResponse {
     pub success: bool,
     pub message: String,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, ToSchema)]
 pub struct SubscriptionResponse {
     pub success: bool,
     pub subscription_id: Option<String>,
@@ -1487,9 +1910,158 @@ pub struct SubscriptionResponse {
     pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
     pub price_rsd: i32,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proration: Option<Proration>, // What was credited and charged
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub promo_code: Option<AppliedPromoCode>,
+}
+
+#[derive(sqlx::FromRow)]
+struct BillingState {
+    account_type: String,
+    subscription_type: Option<String>,
+    subscription_started_at: Option<chrono::DateTime<chrono::Utc>>,
+    next_billing_date: Option<chrono::DateTime<chrono::Utc>>,
+    subscription_status: Option<String>,
+    account_credit_rsd: i32,
+    subscription_paid_rsd: i32,
+}
+
+/// With card payments set up, subscriptions start through Stripe Checkout
+fn checkout_required() -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::PAYMENT_REQUIRED,
+        Json(ErrorResponse {
+            error: "CHECKOUT_REQUIRED".to_string(),
+            message: "Pretplata se plaća karticom".to_string(),
+            details: Some(serde_json::json!({ "checkout_endpoint": "/api/payments/checkout" })),
+        }),
+    )
+}
+
+/// With card payments set up, a plan or billing period change goes through the user's Stripe
+/// subscription, which charges the prorated difference; users without one start it through Checkout
+async fn change_stripe_plan(
+    pool: &Pool<Postgres>,
+    headers: &HeaderMap,
+    user_id: Uuid,
+    plan: String,
+    billing_period: &str,
+    price_rsd: i32,
+    action: AuditAction,
+) -> Result<Json<SubscriptionResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let expires_at = match crate::payments::change_stripe_subscription(pool, user_id, &plan, billing_period, price_rsd).await {
+        Ok(Some(expires_at)) => expires_at,
+        Ok(None) => return Err(checkout_required()),
+        Err(e) => {
+            eprintln!("❌ Failed to change Stripe subscription for user {}: {}", user_id, e);
+            return Err((
+                StatusCode::BAD_GATEWAY,
+                Json(ErrorResponse {
+                    error: "PAYMENT_PROVIDER_ERROR".to_string(),
+                    message: "Promena plana nije naplaćena".to_string(),
+                    details: None,
+                }),
+            ));
+        }
+    };
+
+    crate::audit_log::record(
+        pool,
+        user_id,
+        action,
+        AuditSource::Request(headers),
+        serde_json::json!({ "plan": plan, "billing_period": billing_period, "charged_through": "stripe" }),
+    )
+    .await;
+
+    Ok(Json(SubscriptionResponse {
+        success: true,
+        subscription_id: Some(user_id.to_string()),
+        plan_type: plan,
+        status: "active".to_string(),
+        expires_at: Some(expires_at),
+        price_rsd,
+        message: "Plan je promenjen, a razlika naplaćena karticom".to_string(),
+        proration: None,
+        promo_code: None,
+    }))
+}
+
+/// Move a user to a plan and billing period, starting a new period now. The unused part of what
+/// the current period was paid with and any account credit go towards the new price; what's left
+/// over stays as account credit (see pricing.rs). Nothing is charged here, so the new period is
+/// only paid with that credit - this is the path without card payments, like create_subscription.
+async fn switch_plan(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    plan: &str,
+    billing_period: &str,
+    new_price_rsd: i32,
+) -> Result<(crate::pricing::Proration, chrono::DateTime<chrono::Utc>), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let billing = sqlx::query_as::<_, BillingState>(
+        "SELECT account_type, subscription_type, subscription_started_at, next_billing_date, subscription_status,
+                account_credit_rsd, subscription_paid_rsd
+         FROM users WHERE id = $1 FOR UPDATE",
+    )
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let current = match billing {
+        BillingState {
+            subscription_type: Some(billing_period),
+            subscription_started_at: Some(started_at),
+            next_billing_date: Some(ends_at),
+            ..
+        } if billing.subscription_status.as_deref() == Some("active") => Some(crate::pricing::CurrentPeriod {
+            plan: billing.account_type.clone(),
+            billing_period,
+            started_at,
+            ends_at,
+            paid_rsd: billing.subscription_paid_rsd,
+        }),
+        _ => None,
+    };
+
+    let now = chrono::Utc::now();
+    let proration = crate::pricing::prorate(current.as_ref(), new_price_rsd, billing.account_credit_rsd, now);
+    let next_billing_date = now + crate::pricing::billing_period_length(billing_period);
+
+    let activate = Transition::Activate {
+        plan: plan.to_string(),
+        billing_period: billing_period.to_string(),
+        started_at: now,
+        period_ends_at: Some(next_billing_date),
+    };
+    crate::subscriptions::transition_in(&mut tx, user_id, &activate).await?;
+
+    sqlx::query("UPDATE users SET account_credit_rsd = $1, subscription_paid_rsd = $2 WHERE id = $3")
+        .bind(proration.account_credit_rsd)
+        .bind(proration.covered_rsd())
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok((proration, next_billing_date))
 }
 
 // Change plan endpoint
+#[utoipa::path(
+    put,
+    path = "/api/subscription/change-plan",
+    tag = "subscription",
+    request_body = ChangePlanRequest,
+    responses(
+        (status = 200, description = "Plan changed, with the proration", body = SubscriptionResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn change_plan_handler(
     State((pool, _, jwt_secret, _, _, _resend_api_key)): State<AuthAppState>,
     headers: HeaderMap,
@@ -1564,72 +2136,50 @@ pub async fn change_plan_handler(
     }
 
     // Get pricing
-    let price_rsd = match (request.plan_id.as_str(), request.billing_period.as_str()) {
-        ("individual", "monthly") => 3400,
-        ("individual", "yearly") => 34000,
-        ("professional", "monthly") => 6400,
-        ("professional", "yearly") => 64000,
-        ("team", "monthly") => 24900,
-        ("team", "yearly") => 249000,
-        _ => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "INVALID_PLAN_COMBINATION".to_string(),
-                    message: "Neispravna kombinacija plana i perioda".to_string(),
-                    details: None,
-                }),
-            ));
-        }
-    };
-
-    // Calculate next billing date
-    let next_billing_date = if request.billing_period == "yearly" {
-        chrono::Utc::now() + chrono::Duration::days(365)
-    } else {
-        chrono::Utc::now() + chrono::Duration::days(30)
+    let Some(price_rsd) = crate::pricing::plan_price_rsd(&request.plan_id, &request.billing_period) else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "INVALID_PLAN_COMBINATION".to_string(),
+                message: "Neispravna kombinacija plana i perioda".to_string(),
+                details: None,
+            }),
+        ));
     };
 
-    // Update user's subscription plan
-    let update_result = sqlx::query(
-        "UPDATE users SET
-            account_type = $1,
-            subscription_type = $2,
-            subscription_started_at = NOW(),
-            next_billing_date = $3,
-            subscription_status = 'active',
-            team_id = $4,
-            trial_messages_remaining = $5,
-            updated_at = NOW()
-         WHERE id = $6",
-    )
-    .bind(&request.plan_id)
-    .bind(&request.billing_period)
-    .bind(next_billing_date)
-    .bind(if request.plan_id == "team" {
-        Some(uuid::Uuid::new_v4())
-    } else {
-        None
-    })
-    .bind(if request.plan_id == "individual" {
-        Some(20)
-    } else {
-        None
-    })
-    .bind(user_id)
-    .execute(&pool)
-    .await;
+    if crate::config::get().stripe.is_some() {
+        let plan = request.plan_id.clone();
+        return change_stripe_plan(&pool, &headers, user_id, plan, &request.billing_period, price_rsd, AuditAction::PlanChanged).await;
+    }
 
-    match update_result {
-        Ok(_) => Ok(Json(SubscriptionResponse {
-            success: true,
-            subscription_id: Some(user_id.to_string()),
-            plan_type: request.plan_id,
-            status: "active".to_string(),
-            expires_at: Some(next_billing_date),
-            price_rsd,
-            message: "Plan je uspešno promenjen".to_string(),
-        })),
+    match switch_plan(&pool, user_id, &request.plan_id, &request.billing_period, price_rsd).await {
+        Ok((proration, next_billing_date)) => {
+            crate::audit_log::record(
+                &pool,
+                user_id,
+                AuditAction::PlanChanged,
+                AuditSource::Request(&headers),
+                serde_json::json!({
+                    "plan": request.plan_id,
+                    "billing_period": request.billing_period,
+                    "amount_due_rsd": proration.amount_due_rsd,
+                    "account_credit_rsd": proration.account_credit_rsd,
+                }),
+            )
+            .await;
+
+            Ok(Json(SubscriptionResponse {
+                success: true,
+                subscription_id: Some(user_id.to_string()),
+                plan_type: request.plan_id,
+                status: "active".to_string(),
+                expires_at: Some(next_billing_date),
+                price_rsd,
+                message: "Plan je uspešno promenjen".to_string(),
+                proration: Some(proration),
+                promo_code: None,
+            }))
+        }
         Err(e) => {
             eprintln!("Database error during plan change: {}", e);
             Err((
@@ -1645,6 +2195,18 @@ pub async fn change_plan_handler(
 }
 
 // Change billing period endpoint
+#[utoipa::path(
+    put,
+    path = "/api/subscription/billing-period",
+    tag = "subscription",
+    request_body = ChangeBillingPeriodRequest,
+    responses(
+        (status = 200, description = "Billing period changed, with the proration", body = SubscriptionResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn change_billing_period_handler(
     State((pool, _, jwt_secret, _, _, _resend_api_key)): State<AuthAppState>,
     headers: HeaderMap,
@@ -1740,56 +2302,49 @@ pub async fn change_billing_period_handler(
     };
 
     // Get pricing based on current plan and new billing period
-    let price_rsd = match (user.account_type.as_str(), request.billing_period.as_str()) {
-        ("individual", "monthly") => 3400,
-        ("individual", "yearly") => 34000,
-        ("professional", "monthly") | ("premium", "monthly") => 6400,
-        ("professional", "yearly") | ("premium", "yearly") => 64000,
-        ("team", "monthly") => 24900,
-        ("team", "yearly") => 249000,
-        _ => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "INVALID_PLAN_TYPE".to_string(),
-                    message: "Nepoznat tip plana".to_string(),
-                    details: None,
-                }),
-            ));
-        }
-    };
-
-    // Calculate next billing date
-    let next_billing_date = if request.billing_period == "yearly" {
-        chrono::Utc::now() + chrono::Duration::days(365)
-    } else {
-        chrono::Utc::now() + chrono::Duration::days(30)
+    let plan = if user.account_type == "premium" { "professional".to_string() } else { user.account_type };
+    let Some(price_rsd) = crate::pricing::plan_price_rsd(&plan, &request.billing_period) else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "INVALID_PLAN_TYPE".to_string(),
+                message: "Nepoznat tip plana".to_string(),
+                details: None,
+            }),
+        ));
     };
 
-    // Update billing period
-    let update_result = sqlx::query(
-        "UPDATE users SET
-            subscription_type = $1,
-            next_billing_date = $2,
-            updated_at = NOW()
-         WHERE id = $3",
-    )
-    .bind(&request.billing_period)
-    .bind(next_billing_date)
-    .bind(user_id)
-    .execute(&pool)
-    .await;
+    if crate::config::get().stripe.is_some() {
+        return change_stripe_plan(&pool, &headers, user_id, plan, &request.billing_period, price_rsd, AuditAction::BillingPeriodChanged).await;
+    }
 
-    match update_result {
-        Ok(_) => Ok(Json(SubscriptionResponse {
-            success: true,
-            subscription_id: Some(user_id.to_string()),
-            plan_type: user.account_type,
-            status: "active".to_string(),
-            expires_at: Some(next_billing_date),
-            price_rsd,
-            message: "Period naplate je uspešno promenjen".to_string(),
-        })),
+    match switch_plan(&pool, user_id, &plan, &request.billing_period, price_rsd).await {
+        Ok((proration, next_billing_date)) => {
+            crate::audit_log::record(
+                &pool,
+                user_id,
+                AuditAction::BillingPeriodChanged,
+                AuditSource::Request(&headers),
+                serde_json::json!({
+                    "billing_period": request.billing_period,
+                    "amount_due_rsd": proration.amount_due_rsd,
+                    "account_credit_rsd": proration.account_credit_rsd,
+                }),
+            )
+            .await;
+
+            Ok(Json(SubscriptionResponse {
+                success: true,
+                subscription_id: Some(user_id.to_string()),
+                plan_type: plan,
+                status: "active".to_string(),
+                expires_at: Some(next_billing_date),
+                price_rsd,
+                message: "Period naplate je uspešno promenjen".to_string(),
+                proration: Some(proration),
+                promo_code: None,
+            }))
+        }
         Err(e) => {
             eprintln!("Database error during billing period change: {}", e);
             Err((
@@ -1805,13 +2360,13 @@ pub async fn change_billing_period_handler(
 }
 
 // Request structs for new endpoints
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct ChangePlanRequest {
     pub plan_id: String,
     pub billing_period: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct ChangeBillingPeriodRequest {
     pub billing_period: String,
 }
@@ -1819,6 +2374,19 @@ pub struct ChangeBillingPeriodRequest {
 // ==================== ACCOUNT DELETION ENDPOINTS ====================
 
 /// Request account deletion (soft delete with 30-day grace period)
+#[utoipa::path(
+    post,
+    path = "/api/auth/delete-account",
+    tag = "auth",
+    request_body = DeleteAccountRequest,
+    responses(
+        (status = 200, description = "Account scheduled for deletion after a 30-day grace period", body = DeleteAccountResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 409, description = "The user administers a team (TEAM_ADMIN)", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn request_delete_account_handler(
     State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
     headers: axum::http::HeaderMap,
@@ -1913,7 +2481,10 @@ pub async fn request_delete_account_handler(
 
     // Cancel active subscription if exists
     if user.subscription_status == Some("active".to_string()) {
-        crate::database::cancel_subscription(user.id, &pool)
+        if let Err(e) = crate::payments::cancel_stripe_subscription(&pool, user.id).await {
+            eprintln!("❌ Failed to cancel Stripe subscription for user {}: {}", user.id, e);
+        }
+        crate::subscriptions::transition(&pool, user.id, &Transition::Cancel)
             .await
             .map_err(|e| {
                 eprintln!("Failed to cancel subscription: {}", e);
@@ -1945,7 +2516,18 @@ pub async fn request_delete_account_handler(
 
     let grace_period_ends = deleted_at + chrono::Duration::days(30);
 
-    // TODO: Send email notification about deletion and grace period
+    crate::audit_log::record(
+        &pool,
+        user.id,
+        AuditAction::AccountDeletionRequested,
+        AuditSource::Request(&headers),
+        serde_json::json!({ "grace_period_ends": grace_period_ends }),
+    )
+    .await;
+
+    if let Err(e) = crate::email_service::send_account_deletion_email(&_resend_api_key, &user.email, grace_period_ends).await {
+        eprintln!("❌ Failed to send account deletion email: {:?}", e);
+    }
 
     Ok(Json(crate::models::DeleteAccountResponse {
         success: true,
@@ -1955,6 +2537,17 @@ pub async fn request_delete_account_handler(
 }
 
 /// Restore account during grace period (called manually or automatically on login)
+#[utoipa::path(
+    post,
+    path = "/api/auth/restore-account",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Account restored", body = RestoreAccountResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 410, description = "Grace period expired (GRACE_PERIOD_EXPIRED)", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn restore_account_handler(
     State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
     headers: axum::http::HeaderMap,
@@ -2035,7 +2628,18 @@ pub async fn restore_account_handler(
             )
         })?;
 
-    // TODO: Send email notification about restoration
+    crate::audit_log::record(
+        &pool,
+        restored_user.id,
+        AuditAction::AccountRestored,
+        AuditSource::Request(&headers),
+        serde_json::json!({}),
+    )
+    .await;
+
+    if let Err(e) = crate::email_service::send_account_restored_email(&_resend_api_key, &restored_user.email).await {
+        eprintln!("❌ Failed to send account restored email: {:?}", e);
+    }
 
     Ok(Json(crate::models::RestoreAccountResponse {
         success: true,
@@ -2046,17 +2650,28 @@ pub async fn restore_account_handler(
 
 // ==================== SESSION MANAGEMENT ====================
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SessionResponse {
     pub id: String,
     pub device_name: Option<String>,
     pub ip_address: Option<String>,
+    pub location: Option<GeoLocation>, // Approximate, from the IP address
     pub created_at: String,
     pub last_seen_at: String,
     pub is_current: bool,
 }
 
 /// Get all active sessions for the authenticated user
+#[utoipa::path(
+    get,
+    path = "/api/auth/sessions",
+    tag = "sessions",
+    responses(
+        (status = 200, description = "Active sessions of the user", body = Vec<SessionResponse>),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_sessions_handler(
     State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
     headers: axum::http::HeaderMap,
@@ -2084,7 +2699,7 @@ pub async fn get_sessions_handler(
         .get("Authorization")
         .and_then(|h| h.to_str().ok())
         .and_then(|s| s.strip_prefix("Bearer "))
-        .map(|t| crate::sessions::hash_token(t));
+        .map(crate::sessions::hash_token);
 
     let sessions = crate::sessions::get_user_sessions(&pool, user_id)
         .await
@@ -2114,6 +2729,8 @@ pub async fn get_sessions_handler(
         None
     };
 
+    let locations = crate::sessions::geolocate_all(sessions.iter().filter_map(|s| s.ip_address)).await;
+
     let response: Vec<SessionResponse> = sessions
         .into_iter()
         .map(|s| {
@@ -2131,6 +2748,7 @@ pub async fn get_sessions_handler(
                 id: s.id.to_string(),
                 device_name,
                 ip_address: s.ip_address.map(|ip| ip.to_string()),
+                location: s.ip_address.and_then(|ip| locations.get(&ip).cloned()),
                 created_at: s.created_at.to_rfc3339(),
                 last_seen_at: s.last_seen_at.to_rfc3339(),
                 is_current,
@@ -2141,12 +2759,25 @@ pub async fn get_sessions_handler(
     Ok(Json(response))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct RevokeSessionRequest {
     pub session_id: String,
 }
 
 /// Revoke a specific session
+#[utoipa::path(
+    post,
+    path = "/api/auth/sessions/revoke",
+    tag = "sessions",
+    request_body = RevokeSessionRequest,
+    responses(
+        (status = 200, description = "Session revoked", body = Object),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Session not found", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn revoke_session_handler(
     State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
     headers: axum::http::HeaderMap,
@@ -2206,6 +2837,15 @@ pub async fn revoke_session_handler(
         ));
     }
 
+    crate::audit_log::record(
+        &pool,
+        user_id,
+        AuditAction::SessionRevoked,
+        AuditSource::Request(&headers),
+        serde_json::json!({ "session_id": session_id }),
+    )
+    .await;
+
     Ok(Json(serde_json::json!({
         "success": true,
         "message": "Sesija uspešno uklonjena"
@@ -2213,6 +2853,16 @@ pub async fn revoke_session_handler(
 }
 
 /// Revoke all sessions except the current one
+#[utoipa::path(
+    post,
+    path = "/api/auth/sessions/revoke-all",
+    tag = "sessions",
+    responses(
+        (status = 200, description = "Other sessions revoked", body = Object),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn revoke_all_sessions_handler(
     State((pool, _, jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
     headers: axum::http::HeaderMap,
@@ -2254,6 +2904,15 @@ pub async fn revoke_all_sessions_handler(
             )
         })?;
 
+    crate::audit_log::record(
+        &pool,
+        user_id,
+        AuditAction::AllSessionsRevoked,
+        AuditSource::Request(&headers),
+        serde_json::json!({ "revoked_sessions": revoked_count }),
+    )
+    .await;
+
     Ok(Json(serde_json::json!({
         "success": true,
         "message": format!("Uklonjeno {} sesija", revoked_count),
@@ -2263,33 +2922,36 @@ pub async fn revoke_all_sessions_handler(
 
 // ==================== PASSWORD CHANGE ====================
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct ChangePasswordRequest {
-    #[validate(length(min = 8, message = "Lozinka mora imati najmanje 8 karaktera"))]
+    #[validate(length(min = 8, code = "password_too_short", message = "Lozinka mora imati najmanje 8 karaktera"))]
     #[validate(custom = "validate_password_strength")]
     pub new_password: String,
 }
 
 /// Change user password (requires Supabase auth)
+#[utoipa::path(
+    post,
+    path = "/api/auth/change-password",
+    tag = "auth",
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, description = "Password changed", body = Object),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn change_password_handler(
     State((pool, _, _jwt_secret, _, supabase_jwt_secret, _resend_api_key)): State<AuthAppState>,
     headers: axum::http::HeaderMap,
     Json(payload): Json<ChangePasswordRequest>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
     // Validate password requirements
-    payload.validate().map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "VALIDATION_ERROR".to_string(),
-                message: format!("Validacija neuspešna: {}", e),
-                details: Some(serde_json::json!({"details": e.to_string()})),
-            }),
-        )
-    })?;
+    payload.validate().map_err(|e| crate::i18n::validation_error(&e))?;
 
     // Extract user ID from Supabase token
-    let (user_id, token) = if let Some(supabase_secret) = supabase_jwt_secret.as_deref() {
+    let (user_id, token) = if supabase_auth_configured(supabase_jwt_secret.as_deref()) {
         let token_str = headers
             .get("Authorization")
             .and_then(|h| h.to_str().ok())
@@ -2305,7 +2967,7 @@ pub async fn change_password_handler(
                 )
             })?;
 
-        let claims = verify_supabase_token(token_str, supabase_secret).map_err(|_| {
+        let claims = verify_supabase_token(token_str, supabase_jwt_secret.as_deref()).await.map_err(|_| {
             (
                 StatusCode::UNAUTHORIZED,
                 Json(ErrorResponse {
@@ -2358,6 +3020,26 @@ pub async fn change_password_handler(
             )
         })?;
 
+    // Refresh tokens belong to public.users, the token subject is the auth.users ID
+    let account_id: Option<Uuid> = sqlx::query_scalar("SELECT COALESCE(merged_into, id) FROM users WHERE auth_user_id = $1")
+        .bind(user_id)
+        .fetch_optional(&pool)
+        .await
+        .unwrap_or(None);
+    if let Some(account_id) = account_id {
+        if let Err(e) = crate::refresh_tokens::revoke_all(account_id, &pool).await {
+            eprintln!("Failed to revoke refresh tokens on password change: {}", e);
+        }
+        crate::audit_log::record(
+            &pool,
+            account_id,
+            AuditAction::PasswordChanged,
+            AuditSource::Request(&headers),
+            serde_json::json!({ "revoked_sessions": revoked_count }),
+        )
+        .await;
+    }
+
     Ok(Json(serde_json::json!({
         "success": true,
         "message": "Lozinka uspešno promenjena. Automatski ste odjavljeni sa drugih uređaja.",
@@ -2365,6 +3047,277 @@ pub async fn change_password_handler(
     })))
 }
 
+// ==================== TWO-FACTOR AUTHENTICATION ====================
+
+#[derive(Deserialize, ToSchema)]
+pub struct TwoFactorCodeRequest {
+    pub code: String,
+}
+
+fn two_factor_error(status: StatusCode, error: &str, message: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+            message: message.to_string(),
+            details: None,
+        }),
+    )
+}
+
+fn two_factor_database_error(e: impl std::fmt::Display) -> (StatusCode, Json<ErrorResponse>) {
+    eprintln!("2FA database error: {}", e);
+    two_factor_error(StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Greška baze podataka")
+}
+
+#[derive(sqlx::FromRow)]
+struct TwoFactorState {
+    email: String,
+    totp_secret_encrypted: Option<String>,
+    totp_enabled_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+async fn two_factor_user(
+    headers: &HeaderMap,
+    jwt_secret: &str,
+    supabase_jwt_secret: Option<&str>,
+    pool: &Pool<Postgres>,
+) -> Result<(Uuid, TwoFactorState), (StatusCode, Json<ErrorResponse>)> {
+    let user_id = crate::database::verify_user_from_headers_async(headers, jwt_secret, supabase_jwt_secret, pool)
+        .await
+        .ok_or_else(|| two_factor_error(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "Neautorizovan pristup"))?;
+
+    let state = sqlx::query_as::<_, TwoFactorState>(
+        "SELECT email, totp_secret_encrypted, totp_enabled_at FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(two_factor_database_error)?
+    .ok_or_else(|| two_factor_error(StatusCode::NOT_FOUND, "USER_NOT_FOUND", "Korisnik nije pronađen"))?;
+
+    Ok((user_id, state))
+}
+
+/// Start 2FA enrollment: a new secret for the authenticator app, active once confirmed via /verify
+#[utoipa::path(
+    post,
+    path = "/api/auth/2fa/setup",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Secret and otpauth URI for the authenticator app", body = Object),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 409, description = "Already enabled", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn two_factor_setup_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _)): State<AuthAppState>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let (user_id, state) = two_factor_user(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    if state.totp_enabled_at.is_some() {
+        return Err(two_factor_error(
+            StatusCode::CONFLICT,
+            "TWO_FACTOR_ALREADY_ENABLED",
+            "Dvostruka potvrda identiteta je već uključena",
+        ));
+    }
+
+    let secret = crate::totp::generate_secret();
+    let encrypted = crate::totp::encrypt_secret(&secret).map_err(two_factor_database_error)?;
+
+    sqlx::query("UPDATE users SET totp_secret_encrypted = $2, totp_last_used_step = NULL WHERE id = $1")
+        .bind(user_id)
+        .bind(&encrypted)
+        .execute(&pool)
+        .await
+        .map_err(two_factor_database_error)?;
+
+    Ok(Json(serde_json::json!({
+        "secret": crate::totp::encode_secret(&secret),
+        "otpauth_url": crate::totp::otpauth_url(&secret, &state.email),
+    })))
+}
+
+/// Confirm enrollment with a first code. Returns the recovery codes (shown only once) and, for
+/// custom-JWT clients, a new refresh token - all older refresh tokens are revoked.
+#[utoipa::path(
+    post,
+    path = "/api/auth/2fa/verify",
+    tag = "auth",
+    request_body = TwoFactorCodeRequest,
+    responses(
+        (status = 200, description = "2FA enabled; recovery codes are shown only once", body = Object),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn two_factor_verify_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _)): State<AuthAppState>,
+    headers: HeaderMap,
+    Json(request): Json<TwoFactorCodeRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let (user_id, state) = two_factor_user(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    if state.totp_enabled_at.is_some() {
+        return Err(two_factor_error(
+            StatusCode::CONFLICT,
+            "TWO_FACTOR_ALREADY_ENABLED",
+            "Dvostruka potvrda identiteta je već uključena",
+        ));
+    }
+    if state.totp_secret_encrypted.is_none() {
+        return Err(two_factor_error(
+            StatusCode::BAD_REQUEST,
+            "TWO_FACTOR_NOT_SETUP",
+            "Prvo započnite podešavanje dvostruke potvrde identiteta",
+        ));
+    }
+
+    if !crate::totp::verify_totp(user_id, &request.code, &pool).await.map_err(two_factor_database_error)? {
+        return Err(two_factor_error(
+            StatusCode::BAD_REQUEST,
+            "INVALID_TWO_FACTOR_CODE",
+            "Neispravan kod za dvostruku potvrdu identiteta",
+        ));
+    }
+
+    let recovery_codes = crate::totp::generate_recovery_codes();
+    crate::totp::store_recovery_codes(user_id, &recovery_codes, &pool)
+        .await
+        .map_err(two_factor_database_error)?;
+
+    sqlx::query("UPDATE users SET totp_enabled_at = NOW(), updated_at = NOW() WHERE id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .map_err(two_factor_database_error)?;
+
+    // Sessions started without the second factor must not keep refreshing
+    crate::refresh_tokens::revoke_all(user_id, &pool)
+        .await
+        .map_err(two_factor_database_error)?;
+
+    let uses_custom_token = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .is_some_and(|token| verify_token(token, &jwt_secret).is_ok());
+    let refresh_token = if uses_custom_token {
+        Some(crate::refresh_tokens::issue(user_id, &pool).await.map_err(two_factor_database_error)?)
+    } else {
+        None
+    };
+
+    println!("🔐 2FA enabled for user {}", user_id);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "recovery_codes": recovery_codes,
+        "refresh_token": refresh_token,
+        "message": "Dvostruka potvrda identiteta je uključena. Sačuvajte kodove za oporavak na sigurnom mestu.",
+    })))
+}
+
+/// Turn 2FA off (requires a current code or a recovery code)
+#[utoipa::path(
+    post,
+    path = "/api/auth/2fa/disable",
+    tag = "auth",
+    request_body = TwoFactorCodeRequest,
+    responses(
+        (status = 200, description = "2FA disabled", body = Object),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn two_factor_disable_handler(
+    State((pool, _, jwt_secret, _, supabase_jwt_secret, _)): State<AuthAppState>,
+    headers: HeaderMap,
+    Json(request): Json<TwoFactorCodeRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let (user_id, state) = two_factor_user(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool).await?;
+
+    if state.totp_enabled_at.is_none() {
+        return Err(two_factor_error(
+            StatusCode::BAD_REQUEST,
+            "TWO_FACTOR_NOT_ENABLED",
+            "Dvostruka potvrda identiteta nije uključena",
+        ));
+    }
+
+    if !crate::totp::verify_second_factor(user_id, &request.code, &pool).await.map_err(two_factor_database_error)? {
+        return Err(two_factor_error(
+            StatusCode::BAD_REQUEST,
+            "INVALID_TWO_FACTOR_CODE",
+            "Neispravan kod za dvostruku potvrdu identiteta",
+        ));
+    }
+
+    sqlx::query(
+        "UPDATE users SET totp_secret_encrypted = NULL, totp_enabled_at = NULL, totp_last_used_step = NULL, updated_at = NOW()
+         WHERE id = $1"
+    )
+    .bind(user_id)
+    .execute(&pool)
+    .await
+    .map_err(two_factor_database_error)?;
+
+    sqlx::query("DELETE FROM two_factor_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .map_err(two_factor_database_error)?;
+
+    println!("🔓 2FA disabled for user {}", user_id);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": "Dvostruka potvrda identiteta je isključena",
+    })))
+}
+
+// ==================== PROFILE SYNC ====================
+
+/// Minimum time between automatic profile syncs from auth.users for the same user
+const PROFILE_SYNC_INTERVAL_SECS: f64 = 3600.0;
+
+/// Refresh name and avatar from Supabase auth.users.raw_user_meta_data.
+///
+/// Fields the user edited manually (PATCH /api/profile, see profile.rs) are left alone.
+/// Unless `force` is set, users synced within PROFILE_SYNC_INTERVAL_SECS are skipped.
+/// Returns true if the user row was refreshed.
+pub async fn sync_supabase_profile(user_id: Uuid, force: bool, pool: &Pool<Postgres>) -> Result<bool, String> {
+    let result = sqlx::query(
+        r#"
+        UPDATE users u
+        SET name = CASE WHEN u.name_overridden THEN u.name
+                   ELSE COALESCE(NULLIF(a.raw_user_meta_data->>'full_name', ''), NULLIF(a.raw_user_meta_data->>'name', ''), u.name)
+                   END,
+            oauth_profile_picture_url = CASE WHEN u.avatar_overridden THEN u.oauth_profile_picture_url
+                   ELSE COALESCE(NULLIF(a.raw_user_meta_data->>'avatar_url', ''), NULLIF(a.raw_user_meta_data->>'picture', ''), u.oauth_profile_picture_url)
+                   END,
+            profile_synced_at = NOW()
+        FROM auth.users a
+        WHERE u.id = $1
+          AND a.id = u.auth_user_id
+          AND ($2 OR u.profile_synced_at IS NULL OR u.profile_synced_at < NOW() - make_interval(secs => $3))
+        "#,
+    )
+    .bind(user_id)
+    .bind(force)
+    .bind(PROFILE_SYNC_INTERVAL_SECS)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to sync profile: {}", e))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
 // ==================== EMAIL FUNCTIONS ====================
 // NOTE: Email sending is handled by backend using Resend API
 // Backend generates tokens and sends emails via email_service module
@@ -0,0 +1,378 @@
+// Text-to-speech for reading answers aloud. Audio for saved assistant messages is cached in
+// message_audio so replaying an answer (or seeking through it) doesn't call the provider again.
+use axum::{
+    body::{Body, Bytes},
+    extract::{Json, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::Response,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+use crate::database;
+
+type AppState = (PgPool, String, String, String, Option<String>); // (pool, openrouter_api_key, openai_api_key, jwt_secret, supabase_jwt_secret)
+
+const OPENAI_SPEECH_URL: &str = "https://api.openai.com/v1/audio/speech";
+const TTS_TIMEOUT_SECONDS: u64 = 90;
+
+/// OpenAI rejects inputs over 4096 characters, so longer answers are synthesized in chunks
+const MAX_CHUNK_CHARS: usize = 4000;
+
+/// Raw text requests are for short snippets - whole answers should be sent by message_id
+const MAX_RAW_TEXT_CHARS: usize = 20_000;
+
+const SERBIAN_INSTRUCTIONS: &str = "Čitaj tekst na srpskom jeziku, smirenim i jasnim tonom, kao pravni savetnik.";
+
+#[derive(Debug, Deserialize)]
+pub struct SynthesizeRequest {
+    pub message_id: Option<i64>, // Assistant message to read aloud (cached)
+    pub text: Option<String>,    // Or arbitrary text (not cached)
+}
+
+/// Read an assistant message or raw text aloud as audio/mpeg. Fresh audio is streamed to the client
+/// as the provider produces it; cached audio honors `Range: bytes=...` so players can seek and resume.
+pub async fn synthesize_handler(
+    State((pool, _, openai_api_key, jwt_secret, supabase_jwt_secret)): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<SynthesizeRequest>,
+) -> Result<Response, StatusCode> {
+    let user_id = database::verify_user_from_headers_async(&headers, &jwt_secret, supabase_jwt_secret.as_deref(), &pool)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let user = database::get_user(Some(user_id), &pool).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    if !user.is_registered() {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let voice = crate::config::get().speech.tts_voice.clone();
+
+    let (spoken, cache_entry) = match (request.message_id, request.text) {
+        (Some(message_id), _) => {
+            let content: String = sqlx::query_scalar(
+                "SELECT m.content FROM messages m
+                 JOIN chats c ON m.chat_id = c.id
+                 WHERE m.id = $1 AND c.user_id = $2 AND m.role = 'assistant' AND c.deleted_at IS NULL"
+            )
+            .bind(message_id)
+            .bind(user_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| {
+                eprintln!("Failed to load message for synthesis: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or(StatusCode::NOT_FOUND)?;
+
+            let spoken = speakable_text(&content);
+            // Hash the spoken text so an edited/regenerated answer isn't served stale audio
+            let text_hash = format!("{:x}", Sha256::digest(format!("{}:{}", voice, spoken).as_bytes()));
+
+            let cached: Option<Vec<u8>> = sqlx::query_scalar(
+                "SELECT audio FROM message_audio WHERE message_id = $1 AND text_hash = $2"
+            )
+            .bind(message_id)
+            .bind(&text_hash)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| {
+                eprintln!("Failed to load cached audio: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+
+            if let Some(audio) = cached {
+                println!("🔊 Serving cached audio for message {} ({} bytes)", message_id, audio.len());
+                let range = headers.get(header::RANGE).and_then(|v| v.to_str().ok());
+                return cached_audio_response(audio, range);
+            }
+            (spoken, Some(AudioCacheEntry { message_id, text_hash, pool: pool.clone() }))
+        }
+        (None, Some(text)) => {
+            let spoken = speakable_text(&text);
+            if spoken.is_empty() || spoken.chars().count() > MAX_RAW_TEXT_CHARS {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            (spoken, None)
+        }
+        (None, None) => return Err(StatusCode::BAD_REQUEST),
+    };
+
+    let body = stream_speech(spoken, voice, openai_api_key, cache_entry).await.map_err(|e| {
+        eprintln!("❌ {}", e);
+        StatusCode::BAD_GATEWAY
+    })?;
+    // The length isn't known until the provider is done, so streamed audio can't serve ranges
+    let response = audio_response_builder()
+        .status(StatusCode::OK)
+        .header(header::ACCEPT_RANGES, HeaderValue::from_static("none"))
+        .body(body);
+    build_audio_response(response)
+}
+
+fn audio_response_builder() -> axum::http::response::Builder {
+    Response::builder()
+        .header(header::CONTENT_TYPE, HeaderValue::from_static("audio/mpeg"))
+        .header(header::CACHE_CONTROL, HeaderValue::from_static("private, max-age=86400"))
+}
+
+fn build_audio_response(response: Result<Response, axum::http::Error>) -> Result<Response, StatusCode> {
+    response.map_err(|e| {
+        eprintln!("Failed to build audio response: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Full (200) or partial (206) response for cached audio, 416 when the range is unsatisfiable
+fn cached_audio_response(audio: Vec<u8>, range: Option<&str>) -> Result<Response, StatusCode> {
+    let total = audio.len();
+    let builder = audio_response_builder().header(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+    let response = match range {
+        None => builder
+            .status(StatusCode::OK)
+            .header(header::CONTENT_LENGTH, total)
+            .body(Body::from(audio)),
+        Some(range) => match parse_byte_range(range, total) {
+            Some((start, end)) => builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, total))
+                .header(header::CONTENT_LENGTH, end - start + 1)
+                .body(Body::from(audio[start..=end].to_vec())),
+            None => builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", total))
+                .body(Body::empty()),
+        },
+    };
+    build_audio_response(response)
+}
+
+/// Parse a single `bytes=start-end`, `bytes=start-` or `bytes=-suffix` range into inclusive offsets.
+/// None when the range is malformed or outside the content (multi-range requests aren't supported).
+fn parse_byte_range(range: &str, total: usize) -> Option<(usize, usize)> {
+    let spec = range.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') || total == 0 {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: usize = suffix.parse().ok()?;
+            if suffix == 0 {
+                return None;
+            }
+            (total.saturating_sub(suffix), total - 1)
+        }
+        (start, "") => (start.parse().ok()?, total - 1),
+        (start, end) => (start.parse().ok()?, end.parse::<usize>().ok()?.min(total - 1)),
+    };
+    (start <= end && start < total).then_some((start, end))
+}
+
+/// Strip markdown so the voice doesn't read out asterisks and heading marks
+fn speakable_text(text: &str) -> String {
+    text.lines()
+        .map(|line| line.trim().trim_start_matches('#').trim_start_matches("- ").trim_start_matches('>'))
+        .map(|line| line.replace(['*', '`'], ""))
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty() && line.chars().any(|c| c.is_alphanumeric()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Split at paragraph, then sentence boundaries so every chunk fits the provider's input limit
+fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
+    // A run-on "sentence" (e.g. a long enumerated list) falls back to word boundaries
+    let units = text.split_inclusive(['\n', '.', '?', '!']).flat_map(|sentence| {
+        if sentence.chars().count() > max_chars {
+            sentence.split_inclusive(char::is_whitespace).collect::<Vec<_>>()
+        } else {
+            vec![sentence]
+        }
+    });
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for unit in units {
+        if current.chars().count() + unit.chars().count() > max_chars && !current.trim().is_empty() {
+            chunks.push(current.trim().to_string());
+            current.clear();
+        }
+        current.push_str(unit);
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current.trim().to_string());
+    }
+    chunks
+}
+
+/// Where finished audio of a saved message is cached
+struct AudioCacheEntry {
+    message_id: i64,
+    text_hash: String,
+    pool: PgPool,
+}
+
+impl AudioCacheEntry {
+    async fn store(self, voice: &str, audio: &[u8]) {
+        sqlx::query(
+            "INSERT INTO message_audio (message_id, text_hash, voice, audio)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (message_id) DO UPDATE
+             SET text_hash = EXCLUDED.text_hash, voice = EXCLUDED.voice, audio = EXCLUDED.audio, created_at = NOW()"
+        )
+        .bind(self.message_id)
+        .bind(&self.text_hash)
+        .bind(voice)
+        .bind(audio)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| eprintln!("⚠️  Failed to cache audio for message {}: {}", self.message_id, e))
+        .ok();
+    }
+}
+
+async fn request_chunk(
+    client: &reqwest::Client,
+    text: &str,
+    model: &str,
+    voice: &str,
+    api_key: &str,
+) -> Result<reqwest::Response, String> {
+    let response = client
+        .post(OPENAI_SPEECH_URL)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .timeout(std::time::Duration::from_secs(TTS_TIMEOUT_SECONDS))
+        .json(&serde_json::json!({
+            "model": model,
+            "voice": voice,
+            "input": text,
+            "instructions": SERBIAN_INSTRUCTIONS,
+            "response_format": "mp3",
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("Speech synthesis request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("Speech synthesis API error {}: {}", status, error_text));
+    }
+    Ok(response)
+}
+
+/// Synthesize Serbian speech with OpenAI's TTS API and stream it out as it arrives. Chunks are
+/// concatenated - MP3 frames are self-contained, so the result plays as one file. The first
+/// chunk is requested before returning, so a provider error is still an error response; a later
+/// failure (or the client going away) ends the stream early and nothing is cached.
+async fn stream_speech(
+    text: String,
+    voice: String,
+    api_key: String,
+    cache_entry: Option<AudioCacheEntry>,
+) -> Result<Body, String> {
+    let model = &crate::config::get().speech.tts_model;
+    let client = reqwest::Client::new();
+    let mut chunks = chunk_text(&text, MAX_CHUNK_CHARS).into_iter();
+    let first = chunks.next().ok_or("Nothing to synthesize")?;
+    let first_response = request_chunk(&client, &first, model, &voice, &api_key).await?;
+
+    let (sender, receiver) = tokio::sync::mpsc::channel::<Result<Bytes, std::io::Error>>(16);
+    tokio::spawn(async move {
+        let mut audio = Vec::new();
+        let mut response = Some(first_response);
+        loop {
+            let mut current = match response.take() {
+                Some(current) => current,
+                None => match chunks.next() {
+                    Some(chunk) => match request_chunk(&client, &chunk, model, &voice, &api_key).await {
+                        Ok(next) => next,
+                        Err(e) => {
+                            eprintln!("❌ {}", e);
+                            let _ = sender.send(Err(std::io::Error::other(e))).await;
+                            return;
+                        }
+                    },
+                    None => break,
+                },
+            };
+            loop {
+                match current.chunk().await {
+                    Ok(Some(bytes)) => {
+                        if cache_entry.is_some() {
+                            audio.extend_from_slice(&bytes);
+                        }
+                        if sender.send(Ok(bytes)).await.is_err() {
+                            return; // Client went away
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        eprintln!("❌ Failed to read synthesized audio: {}", e);
+                        let _ = sender.send(Err(std::io::Error::other(e))).await;
+                        return;
+                    }
+                }
+            }
+        }
+
+        if let Some(cache_entry) = cache_entry {
+            if !audio.is_empty() {
+                println!("🔊 Synthesized audio for message {} ({} bytes)", cache_entry.message_id, audio.len());
+                cache_entry.store(&voice, &audio).await;
+            }
+        }
+    });
+
+    Ok(Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(receiver)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_respect_limit_and_keep_text() {
+        let text = "Prva rečenica. Druga rečenica je malo duža? Treća!\nNovi pasus.";
+        let chunks = chunk_text(text, 20);
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 20));
+        assert_eq!(chunks.join(" ").split_whitespace().count(), text.split_whitespace().count());
+        assert_eq!(speakable_text("## **Član 5**\n- stav *1*"), "Član 5\nstav 1");
+    }
+
+    #[test]
+    fn test_parse_byte_range() {
+        assert_eq!(parse_byte_range("bytes=0-99", 1000), Some((0, 99)));
+        assert_eq!(parse_byte_range("bytes=900-", 1000), Some((900, 999)));
+        assert_eq!(parse_byte_range("bytes=-100", 1000), Some((900, 999)));
+        assert_eq!(parse_byte_range("bytes=500-5000", 1000), Some((500, 999)));
+        assert_eq!(parse_byte_range("bytes=1000-", 1000), None);
+        assert_eq!(parse_byte_range("bytes=20-10", 1000), None);
+        assert_eq!(parse_byte_range("bytes=0-1,5-6", 1000), None);
+        assert_eq!(parse_byte_range("items=0-1", 1000), None);
+    }
+
+    #[test]
+    fn test_cached_audio_ranges() {
+        let audio: Vec<u8> = (0..100).collect();
+
+        let full = cached_audio_response(audio.clone(), None).unwrap();
+        assert_eq!(full.status(), StatusCode::OK);
+        assert_eq!(full.headers()[header::ACCEPT_RANGES], "bytes");
+        assert_eq!(full.headers()[header::CONTENT_LENGTH], "100");
+
+        let partial = cached_audio_response(audio.clone(), Some("bytes=10-19")).unwrap();
+        assert_eq!(partial.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(partial.headers()[header::CONTENT_RANGE], "bytes 10-19/100");
+        assert_eq!(partial.headers()[header::CONTENT_LENGTH], "10");
+
+        let unsatisfiable = cached_audio_response(audio, Some("bytes=200-")).unwrap();
+        assert_eq!(unsatisfiable.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(unsatisfiable.headers()[header::CONTENT_RANGE], "bytes */100");
+    }
+}
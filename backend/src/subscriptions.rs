@@ -0,0 +1,436 @@
+// The subscription state machine. Every change to a user's subscription columns (plan, billing
+// period, status, paid period, grace period, message allowance, team) goes through transition(),
+// whoever causes it - a web purchase or plan change (simple_auth), a RevenueCat or Stripe webhook
+// (webhooks, payments), the dunning job, account merges (account_linking) or account deletion. apply() works out the new state from
+// the current one; callers write anything provider-specific (platform, Stripe ids, account credit)
+// themselves.
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+/// Monthly message allowance of the individual plan
+pub const INDIVIDUAL_MONTHLY_MESSAGES: i32 = 20;
+
+/// users.subscription_status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionState {
+    Active,
+    Grace,   // Payment failed, plan kept until grace_period_ends_at
+    PastDue, // Grace period over without payment, free tier until it's paid
+    Cancelled,
+    Expired,
+}
+
+impl SubscriptionState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriptionState::Active => "active",
+            SubscriptionState::Grace => "grace",
+            SubscriptionState::PastDue => "past_due",
+            SubscriptionState::Cancelled => "cancelled",
+            SubscriptionState::Expired => "expired",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "active" => Some(SubscriptionState::Active),
+            "grace" => Some(SubscriptionState::Grace),
+            "past_due" => Some(SubscriptionState::PastDue),
+            "cancelled" => Some(SubscriptionState::Cancelled),
+            "expired" => Some(SubscriptionState::Expired),
+            _ => None,
+        }
+    }
+
+    /// Whether the subscriber keeps their plan
+    pub fn has_access(&self) -> bool {
+        matches!(self, SubscriptionState::Active | SubscriptionState::Grace)
+    }
+}
+
+/// The subscription columns of a user
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct Subscription {
+    pub account_type: String,
+    pub subscription_type: Option<String>, // Billing period: "monthly" or "yearly"
+    pub subscription_status: Option<String>,
+    pub subscription_started_at: Option<DateTime<Utc>>,
+    pub next_billing_date: Option<DateTime<Utc>>,
+    pub premium_expires_at: Option<DateTime<Utc>>, // Plan usable until then
+    pub grace_period_ends_at: Option<DateTime<Utc>>,
+    pub trial_messages_remaining: Option<i32>, // None: unlimited
+    pub team_id: Option<Uuid>,
+}
+
+impl Subscription {
+    /// On a paid plan that hasn't been cancelled or lapsed (older rows have no status)
+    pub fn is_subscribed(&self) -> bool {
+        self.account_type != "trial_registered"
+            && self
+                .subscription_status
+                .as_deref()
+                .is_none_or(|status| SubscriptionState::parse(status).is_some_and(|state| state.has_access()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Transition {
+    /// Start a paid period on a plan - a purchase, or a switch to another plan or billing period
+    Activate {
+        plan: String,
+        billing_period: String,
+        started_at: DateTime<Utc>,
+        period_ends_at: Option<DateTime<Utc>>,
+    },
+    /// The current plan was paid for until `period_ends_at`
+    Renew { period_ends_at: Option<DateTime<Utc>> },
+    /// Stop renewing; the plan stays usable until the paid period ends
+    Cancel,
+    /// A renewal failed; the plan stays usable until `ends_at` while it is retried
+    Grace { ends_at: DateTime<Utc> },
+    /// The grace period ended unpaid; free tier until it's paid
+    PastDue,
+    /// The subscription ended (`at` unknown for a cancellation the store didn't date); free tier
+    Expire { at: Option<DateTime<Utc>> },
+    /// Take over the subscription of an account merged into this one
+    TakeOver { subscription: Subscription },
+    /// The subscription moved to the account this one was merged into; back to the trial
+    HandOver,
+    /// A trial account absorbed another trial: keep the better of the two allowances, not their sum
+    MergeTrial { trial_messages_remaining: Option<i32> },
+}
+
+impl Transition {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Transition::Activate { .. } => "activate",
+            Transition::Renew { .. } => "renew",
+            Transition::Cancel => "cancel",
+            Transition::Grace { .. } => "grace",
+            Transition::PastDue => "past_due",
+            Transition::Expire { .. } => "expire",
+            Transition::TakeOver { .. } => "take_over",
+            Transition::HandOver => "hand_over",
+            Transition::MergeTrial { .. } => "merge_trial",
+        }
+    }
+}
+
+/// Message allowance of a plan; None is unlimited
+fn plan_messages(plan: &str) -> Option<i32> {
+    match plan {
+        "individual" => Some(INDIVIDUAL_MONTHLY_MESSAGES),
+        _ => None,
+    }
+}
+
+/// The subscription after `transition`
+pub fn apply(current: &Subscription, transition: &Transition) -> Subscription {
+    let mut next = current.clone();
+    match transition {
+        Transition::Activate { plan, billing_period, started_at, period_ends_at } => {
+            // Switching billing period on the same plan keeps what's left of the allowance
+            if !(current.is_subscribed() && current.account_type == *plan) {
+                next.trial_messages_remaining = plan_messages(plan);
+            }
+            next.team_id = if plan == "team" { current.team_id.or_else(|| Some(Uuid::new_v4())) } else { None };
+            next.account_type = plan.clone();
+            next.subscription_type = Some(billing_period.clone());
+            next.subscription_status = Some(SubscriptionState::Active.as_str().to_string());
+            next.subscription_started_at = Some(*started_at);
+            next.next_billing_date = *period_ends_at;
+            next.premium_expires_at = *period_ends_at;
+            next.grace_period_ends_at = None;
+        }
+        Transition::Renew { period_ends_at } => {
+            // A new paid period refills the allowance; a repeated sync of the same one doesn't
+            let extended = match (period_ends_at, current.premium_expires_at) {
+                (Some(ends_at), Some(paid_until)) => *ends_at > paid_until,
+                (Some(_), None) => true,
+                (None, _) => false,
+            };
+            if extended && current.account_type == "individual" {
+                next.trial_messages_remaining = plan_messages("individual");
+            }
+            next.subscription_status = Some(SubscriptionState::Active.as_str().to_string());
+            next.next_billing_date = *period_ends_at;
+            next.premium_expires_at = period_ends_at.or(current.premium_expires_at);
+            next.grace_period_ends_at = None;
+        }
+        Transition::Cancel => {
+            if !current.is_subscribed() {
+                return next;
+            }
+            next.subscription_status = Some(SubscriptionState::Cancelled.as_str().to_string());
+            next.premium_expires_at = current.next_billing_date.or(current.premium_expires_at);
+            next.subscription_type = None;
+            next.subscription_started_at = None;
+            next.next_billing_date = None;
+        }
+        Transition::Grace { ends_at } => {
+            next.subscription_status = Some(SubscriptionState::Grace.as_str().to_string());
+            next.grace_period_ends_at = Some(*ends_at);
+            next.premium_expires_at = Some(*ends_at);
+        }
+        Transition::PastDue => {
+            if current.subscription_status.as_deref() != Some(SubscriptionState::Grace.as_str()) {
+                return next; // Paid (or ended) since the grace period started
+            }
+            // grace_period_ends_at stays, so a later sync still knows the grace period is over
+            next.subscription_status = Some(SubscriptionState::PastDue.as_str().to_string());
+            next.account_type = "trial_registered".to_string();
+            next.trial_messages_remaining = Some(0); // The original trial messages were used up
+            next.premium_expires_at = current.grace_period_ends_at.or(current.premium_expires_at);
+            next.next_billing_date = None;
+        }
+        Transition::Expire { at } => {
+            if current.account_type == "trial_registered" && current.subscription_status.is_none() {
+                return next; // Never subscribed
+            }
+            let state = if at.is_some() { SubscriptionState::Expired } else { SubscriptionState::Cancelled };
+            next.subscription_status = Some(state.as_str().to_string());
+            next.account_type = "trial_registered".to_string();
+            next.trial_messages_remaining = Some(0);
+            next.premium_expires_at = at.or(current.grace_period_ends_at);
+            next.next_billing_date = None;
+            next.grace_period_ends_at = None;
+        }
+        Transition::TakeOver { subscription } => {
+            next = subscription.clone();
+        }
+        Transition::HandOver => {
+            next = Subscription {
+                account_type: "trial_registered".to_string(),
+                subscription_type: None,
+                subscription_status: None,
+                subscription_started_at: None,
+                next_billing_date: None,
+                premium_expires_at: None,
+                grace_period_ends_at: None,
+                trial_messages_remaining: current.trial_messages_remaining,
+                team_id: None,
+            };
+        }
+        Transition::MergeTrial { trial_messages_remaining } => {
+            if current.account_type != "trial_registered" {
+                return next;
+            }
+            // None (unlimited) never applies to a trial; a missing value counts as nothing left
+            next.trial_messages_remaining = current.trial_messages_remaining.max(*trial_messages_remaining);
+        }
+    }
+    next
+}
+
+/// A user's subscription, locking the row until the caller's transaction ends
+pub async fn load_for_update(conn: &mut PgConnection, user_id: Uuid) -> Result<Subscription, sqlx::Error> {
+    sqlx::query_as::<_, Subscription>(
+        "SELECT account_type, subscription_type, subscription_status, subscription_started_at, next_billing_date,
+                premium_expires_at, grace_period_ends_at, trial_messages_remaining, team_id
+         FROM users WHERE id = $1 FOR UPDATE",
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// Apply a transition to a user's stored subscription, returning it before and after. Runs in the
+/// caller's connection (or transaction); the user's row is locked until that ends.
+pub async fn transition_in(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    transition: &Transition,
+) -> Result<(Subscription, Subscription), sqlx::Error> {
+    let current = load_for_update(&mut *conn, user_id).await?;
+
+    let next = apply(&current, transition);
+    if next != current {
+        sqlx::query(
+            "UPDATE users SET
+                account_type = $1,
+                subscription_type = $2,
+                subscription_status = $3,
+                subscription_started_at = $4,
+                next_billing_date = $5,
+                premium_expires_at = $6,
+                grace_period_ends_at = $7,
+                trial_messages_remaining = $8,
+                team_id = $9,
+                updated_at = NOW()
+             WHERE id = $10",
+        )
+        .bind(&next.account_type)
+        .bind(&next.subscription_type)
+        .bind(&next.subscription_status)
+        .bind(next.subscription_started_at)
+        .bind(next.next_billing_date)
+        .bind(next.premium_expires_at)
+        .bind(next.grace_period_ends_at)
+        .bind(next.trial_messages_remaining)
+        .bind(next.team_id)
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    tracing::info!(
+        user_id = %user_id,
+        transition = transition.name(),
+        from = current.subscription_status.as_deref().unwrap_or("-"),
+        to = next.subscription_status.as_deref().unwrap_or("-"),
+        "Subscription transition"
+    );
+    Ok((current, next))
+}
+
+/// transition_in in a transaction of its own
+pub async fn transition(pool: &PgPool, user_id: Uuid, transition: &Transition) -> Result<(Subscription, Subscription), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let change = transition_in(&mut tx, user_id, transition).await?;
+    tx.commit().await?;
+    Ok(change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn trial() -> Subscription {
+        Subscription {
+            account_type: "trial_registered".to_string(),
+            subscription_type: None,
+            subscription_status: None,
+            subscription_started_at: None,
+            next_billing_date: None,
+            premium_expires_at: None,
+            grace_period_ends_at: None,
+            trial_messages_remaining: Some(2),
+            team_id: None,
+        }
+    }
+
+    fn activate(plan: &str, billing_period: &str, now: DateTime<Utc>) -> Transition {
+        Transition::Activate {
+            plan: plan.to_string(),
+            billing_period: billing_period.to_string(),
+            started_at: now,
+            period_ends_at: Some(now + crate::pricing::billing_period_length(billing_period)),
+        }
+    }
+
+    #[test]
+    fn test_activate() {
+        let now = Utc::now();
+        let individual = apply(&trial(), &activate("individual", "monthly", now));
+        assert_eq!(individual.subscription_status.as_deref(), Some("active"));
+        assert_eq!(individual.trial_messages_remaining, Some(INDIVIDUAL_MONTHLY_MESSAGES));
+        assert_eq!(individual.premium_expires_at, Some(now + Duration::days(30)));
+        assert_eq!(individual.next_billing_date, individual.premium_expires_at);
+
+        // A billing period switch keeps the allowance, a plan switch resets it
+        let used = Subscription { trial_messages_remaining: Some(7), ..individual };
+        assert_eq!(apply(&used, &activate("individual", "yearly", now)).trial_messages_remaining, Some(7));
+        let professional = apply(&used, &activate("professional", "monthly", now));
+        assert_eq!(professional.trial_messages_remaining, None);
+
+        // Team plans get a team, other plans leave it
+        let team = apply(&professional, &activate("team", "monthly", now));
+        assert!(team.team_id.is_some());
+        assert_eq!(apply(&team, &activate("team", "yearly", now)).team_id, team.team_id);
+        assert_eq!(apply(&team, &activate("individual", "monthly", now)).team_id, None);
+
+        // Paying again ends a grace period
+        let grace = apply(&team, &Transition::Grace { ends_at: now + Duration::days(7) });
+        assert_eq!(apply(&grace, &activate("team", "monthly", now)).grace_period_ends_at, None);
+    }
+
+    #[test]
+    fn test_renew() {
+        let now = Utc::now();
+        let individual = apply(&trial(), &activate("individual", "monthly", now));
+        let used = Subscription { trial_messages_remaining: Some(0), ..individual.clone() };
+
+        let next_period = Some(now + Duration::days(60));
+        let renewed = apply(&used, &Transition::Renew { period_ends_at: next_period });
+        assert_eq!(renewed.premium_expires_at, next_period);
+        assert_eq!(renewed.trial_messages_remaining, Some(INDIVIDUAL_MONTHLY_MESSAGES));
+
+        // The same period synced again doesn't refill the allowance
+        let resynced = apply(&used, &Transition::Renew { period_ends_at: individual.premium_expires_at });
+        assert_eq!(resynced.trial_messages_remaining, Some(0));
+        assert_eq!(resynced, used);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let now = Utc::now();
+        let professional = apply(&trial(), &activate("professional", "monthly", now));
+        let cancelled = apply(&professional, &Transition::Cancel);
+        assert_eq!(cancelled.subscription_status.as_deref(), Some("cancelled"));
+        assert_eq!(cancelled.account_type, "professional"); // Until the period ends
+        assert_eq!(cancelled.premium_expires_at, professional.next_billing_date);
+        assert_eq!((cancelled.subscription_type.as_deref(), cancelled.next_billing_date), (None, None));
+
+        // Nothing to cancel
+        assert_eq!(apply(&trial(), &Transition::Cancel), trial());
+        assert!(!cancelled.is_subscribed());
+    }
+
+    #[test]
+    fn test_grace_and_past_due() {
+        let now = Utc::now();
+        let individual = apply(&trial(), &activate("individual", "monthly", now));
+        let ends_at = now + Duration::days(7);
+        let grace = apply(&individual, &Transition::Grace { ends_at });
+        assert_eq!(grace.subscription_status.as_deref(), Some("grace"));
+        assert_eq!(grace.account_type, "individual");
+        assert_eq!((grace.grace_period_ends_at, grace.premium_expires_at), (Some(ends_at), Some(ends_at)));
+        assert!(grace.is_subscribed());
+
+        let past_due = apply(&grace, &Transition::PastDue);
+        assert_eq!(past_due.subscription_status.as_deref(), Some("past_due"));
+        assert_eq!(past_due.account_type, "trial_registered");
+        assert_eq!(past_due.trial_messages_remaining, Some(0));
+        assert_eq!(past_due.grace_period_ends_at, Some(ends_at));
+
+        // Only a running grace period can end unpaid
+        assert_eq!(apply(&individual, &Transition::PastDue), individual);
+    }
+
+    #[test]
+    fn test_expire() {
+        let now = Utc::now();
+        let professional = apply(&trial(), &activate("professional", "yearly", now));
+        let expired = apply(&professional, &Transition::Expire { at: Some(now) });
+        assert_eq!(expired.subscription_status.as_deref(), Some("expired"));
+        assert_eq!(expired.account_type, "trial_registered");
+        assert_eq!(expired.trial_messages_remaining, Some(0));
+        assert_eq!(expired.premium_expires_at, Some(now));
+
+        let undated = apply(&professional, &Transition::Expire { at: None });
+        assert_eq!(undated.subscription_status.as_deref(), Some("cancelled"));
+
+        // A trial that never subscribed keeps its messages
+        assert_eq!(apply(&trial(), &Transition::Expire { at: None }), trial());
+    }
+
+    #[test]
+    fn test_merge() {
+        let now = Utc::now();
+        let team = apply(&trial(), &activate("team", "monthly", now));
+        assert_eq!(apply(&trial(), &Transition::TakeOver { subscription: team.clone() }), team);
+
+        let handed_over = apply(&team, &Transition::HandOver);
+        assert_eq!(handed_over.account_type, "trial_registered");
+        assert_eq!((handed_over.subscription_status, handed_over.team_id), (None, None));
+
+        // Trials keep the larger allowance; a paid plan's allowance isn't touched
+        let merged = apply(&trial(), &Transition::MergeTrial { trial_messages_remaining: Some(5) });
+        assert_eq!(merged.trial_messages_remaining, Some(5));
+        assert_eq!(apply(&trial(), &Transition::MergeTrial { trial_messages_remaining: Some(1) }), trial());
+        assert_eq!(apply(&team, &Transition::MergeTrial { trial_messages_remaining: Some(5) }), team);
+    }
+}
@@ -0,0 +1,234 @@
+// Two-factor authentication: RFC 6238 TOTP (HMAC-SHA1, 30s, 6 digits) with one-time recovery codes.
+// Secrets are stored AES-256-GCM encrypted (key from TOTP_ENCRYPTION_KEY); recovery codes only as hashes.
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::secret_box;
+
+const TIME_STEP_SECONDS: i64 = 30;
+const CODE_DIGITS: u32 = 6;
+/// Accepted clock drift between server and authenticator app, in time steps
+const ALLOWED_DRIFT_STEPS: i64 = 1;
+const SECRET_BYTES: usize = 20;
+pub const RECOVERY_CODE_COUNT: usize = 10;
+const ISSUER: &str = "Norma AI";
+
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; SECRET_BYTES];
+    rand::thread_rng().fill(&mut secret[..]);
+    secret
+}
+
+/// Base32 form typed into / scanned by authenticator apps
+pub fn encode_secret(secret: &[u8]) -> String {
+    data_encoding::BASE32_NOPAD.encode(secret)
+}
+
+/// otpauth:// URI for the enrollment QR code
+pub fn otpauth_url(secret: &[u8], account: &str) -> String {
+    let mut url = reqwest::Url::parse("otpauth://totp/").expect("valid otpauth base");
+    url.path_segments_mut()
+        .expect("otpauth URL has a path")
+        .pop_if_empty()
+        .push(&format!("{}:{}", ISSUER, account));
+    url.query_pairs_mut()
+        .append_pair("secret", &encode_secret(secret))
+        .append_pair("issuer", ISSUER)
+        .append_pair("algorithm", "SHA1")
+        .append_pair("digits", &CODE_DIGITS.to_string())
+        .append_pair("period", &TIME_STEP_SECONDS.to_string());
+    url.to_string()
+}
+
+fn hotp(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([digest[offset], digest[offset + 1], digest[offset + 2], digest[offset + 3]]) & 0x7fff_ffff;
+    binary % 10u32.pow(CODE_DIGITS)
+}
+
+fn code_for_step(secret: &[u8], step: i64) -> String {
+    format!("{:0width$}", hotp(secret, step as u64), width = CODE_DIGITS as usize)
+}
+
+/// Time step of a code valid around `unix_time`, if any. Steps up to `last_used_step` are
+/// rejected so an observed code can't be replayed.
+pub fn verify_code(secret: &[u8], code: &str, unix_time: i64, last_used_step: Option<i64>) -> Option<i64> {
+    let code = code.trim().replace(' ', "");
+    if code.len() != CODE_DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let current = unix_time / TIME_STEP_SECONDS;
+    (current - ALLOWED_DRIFT_STEPS..=current + ALLOWED_DRIFT_STEPS)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| code_for_step(secret, *step) == code)
+}
+
+fn encryption_key(key_material: &str) -> secret_box::Key {
+    secret_box::derive_key("normaai-totp", key_material)
+}
+
+fn seal_secret(key: &secret_box::Key, secret: &[u8]) -> Result<String, String> {
+    secret_box::seal(key, secret, b"").map_err(|e| format!("Failed to encrypt 2FA secret: {}", e))
+}
+
+fn open_secret(key: &secret_box::Key, stored: &str) -> Result<Vec<u8>, String> {
+    secret_box::open(key, stored, b"")
+        .map_err(|e| format!("Failed to decrypt 2FA secret (wrong TOTP_ENCRYPTION_KEY?): {}", e))
+}
+
+pub fn encrypt_secret(secret: &[u8]) -> Result<String, String> {
+    seal_secret(&encryption_key(&crate::config::get().totp_encryption_key), secret)
+}
+
+pub fn decrypt_secret(stored: &str) -> Result<Vec<u8>, String> {
+    open_secret(&encryption_key(&crate::config::get().totp_encryption_key), stored)
+}
+
+/// Recovery codes shown once at enrollment, formatted xxxxx-xxxxx
+pub fn generate_recovery_codes() -> Vec<String> {
+    const ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789"; // No 0/o, 1/l/i
+    let mut rng = rand::thread_rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let chars: String = (0..10).map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())] as char).collect();
+            format!("{}-{}", &chars[..5], &chars[5..])
+        })
+        .collect()
+}
+
+fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
+
+/// Replace the user's recovery codes
+pub async fn store_recovery_codes(user_id: Uuid, codes: &[String], pool: &PgPool) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM two_factor_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    for code in codes {
+        sqlx::query("INSERT INTO two_factor_recovery_codes (user_id, code_hash) VALUES ($1, $2)")
+            .bind(user_id)
+            .bind(hash_recovery_code(code))
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await
+}
+
+pub async fn is_enabled(user_id: Uuid, pool: &PgPool) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar("SELECT totp_enabled_at IS NOT NULL FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map(|enabled| enabled.unwrap_or(false))
+}
+
+/// Check a TOTP code against the user's enrolled (or pending, during setup) secret and
+/// remember its time step. Returns false for wrong, replayed or missing codes.
+pub async fn verify_totp(user_id: Uuid, code: &str, pool: &PgPool) -> Result<bool, String> {
+    let row: Option<(Option<String>, Option<i64>)> = sqlx::query_as(
+        "SELECT totp_secret_encrypted, totp_last_used_step FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load 2FA secret: {}", e))?;
+
+    let Some((Some(encrypted), last_used_step)) = row else {
+        return Ok(false);
+    };
+
+    let secret = decrypt_secret(&encrypted)?;
+    let Some(step) = verify_code(&secret, code, chrono::Utc::now().timestamp(), last_used_step) else {
+        return Ok(false);
+    };
+
+    // Conditional update so two requests with the same code can't both pass
+    let result = sqlx::query(
+        "UPDATE users SET totp_last_used_step = $2
+         WHERE id = $1 AND (totp_last_used_step IS NULL OR totp_last_used_step < $2)"
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record 2FA code use: {}", e))?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Second factor for an enrolled user: a TOTP code or an unused recovery code (consumed)
+pub async fn verify_second_factor(user_id: Uuid, code: &str, pool: &PgPool) -> Result<bool, String> {
+    if code.trim().chars().all(|c| c.is_ascii_digit() || c == ' ') {
+        return verify_totp(user_id, code, pool).await;
+    }
+
+    let result = sqlx::query(
+        "UPDATE two_factor_recovery_codes SET used_at = NOW()
+         WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(hash_recovery_code(code))
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to check recovery code: {}", e))?;
+
+    if result.rows_affected() == 1 {
+        println!("🔑 2FA recovery code used by user {}", user_id);
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 appendix B (SHA1 secret), truncated to 6 digits
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        assert_eq!(verify_code(RFC_SECRET, "287082", 59, None), Some(1));
+        assert_eq!(verify_code(RFC_SECRET, "081804", 1111111109, None), Some(37037036));
+        assert_eq!(verify_code(RFC_SECRET, "005924", 1234567890, None), Some(41152263));
+        assert_eq!(verify_code(RFC_SECRET, "000000", 59, None), None);
+    }
+
+    #[test]
+    fn test_used_step_is_rejected() {
+        assert_eq!(verify_code(RFC_SECRET, "287082", 59, Some(1)), None);
+        assert_eq!(verify_code(RFC_SECRET, "287082", 59, Some(0)), Some(1));
+    }
+
+    #[test]
+    fn test_secret_encryption_roundtrip() {
+        let secret = generate_secret();
+        let key = encryption_key("totp-key");
+        let encrypted = seal_secret(&key, &secret).unwrap();
+        assert_ne!(encrypted, encode_secret(&secret));
+        assert_eq!(open_secret(&key, &encrypted).unwrap(), secret);
+        assert!(open_secret(&encryption_key("other-key"), &encrypted).is_err());
+    }
+
+    #[test]
+    fn test_recovery_code_hash_ignores_format() {
+        assert_eq!(hash_recovery_code("abcde-fghjk"), hash_recovery_code(" ABCDE FGHJK "));
+    }
+}
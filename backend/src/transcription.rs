@@ -0,0 +1,202 @@
+// Speech-to-text providers. OpenAI's hosted Whisper is the default; TRANSCRIPTION_PROVIDER=self_hosted
+// sends audio to a self-hosted Whisper server with an OpenAI-compatible API (e.g. faster-whisper-server)
+// so recordings don't leave our infrastructure.
+use crate::config::TranscriptionProvider;
+use async_trait::async_trait;
+
+/// Language hint passed to every provider - recordings are Serbian legal questions
+pub const LANGUAGE: &str = "sr";
+
+const OPENAI_TRANSCRIPTIONS_URL: &str = "https://api.openai.com/v1/audio/transcriptions";
+/// Self-hosted servers often run on CPU, so allow them much longer than the hosted API
+const SELF_HOSTED_TIMEOUT_SECONDS: u64 = 180;
+const OPENAI_TIMEOUT_SECONDS: u64 = 60;
+
+/// Container format of an uploaded recording, as Whisper needs a matching file name and MIME type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioFormat {
+    pub mime: &'static str,
+    pub extension: &'static str,
+}
+
+const WAV: AudioFormat = AudioFormat { mime: "audio/wav", extension: "wav" };
+const MP4: AudioFormat = AudioFormat { mime: "audio/mp4", extension: "m4a" };
+const OGG: AudioFormat = AudioFormat { mime: "audio/ogg", extension: "ogg" };
+const WEBM: AudioFormat = AudioFormat { mime: "audio/webm", extension: "webm" };
+const MP3: AudioFormat = AudioFormat { mime: "audio/mpeg", extension: "mp3" };
+const FLAC: AudioFormat = AudioFormat { mime: "audio/flac", extension: "flac" };
+
+/// MPEG audio frame sync (11 set bits) with a non-zero layer - layer 0 is AAC ADTS, which Whisper rejects
+fn is_mpeg_audio_frame(bytes: &[u8]) -> bool {
+    bytes.len() >= 2 && bytes[0] == 0xFF && bytes[1] & 0xE0 == 0xE0 && bytes[1] & 0x06 != 0
+}
+
+/// Detect the recording's format from its magic bytes, falling back to the Content-Type header.
+/// Mobile recorders label their output inconsistently (iOS sends m4a as audio/x-m4a or video/mp4,
+/// some Android webviews send webm/ogg as application/octet-stream), so the bytes win.
+/// None when the format isn't one Whisper accepts.
+pub fn detect_audio_format(bytes: &[u8], content_type: Option<&str>) -> Option<AudioFormat> {
+    if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WAVE" {
+        return Some(WAV);
+    }
+    if bytes.len() >= 8 && &bytes[4..8] == b"ftyp" {
+        return Some(MP4);
+    }
+    if bytes.starts_with(b"OggS") {
+        return Some(OGG);
+    }
+    if bytes.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        return Some(WEBM);
+    }
+    if bytes.starts_with(b"fLaC") {
+        return Some(FLAC);
+    }
+    if bytes.starts_with(b"ID3") || is_mpeg_audio_frame(bytes) {
+        return Some(MP3);
+    }
+
+    // e.g. "audio/webm;codecs=opus"
+    let mime = content_type?.split(';').next()?.trim().to_ascii_lowercase();
+    match mime.as_str() {
+        "audio/wav" | "audio/wave" | "audio/x-wav" => Some(WAV),
+        "audio/mp4" | "audio/m4a" | "audio/x-m4a" | "video/mp4" => Some(MP4),
+        "audio/ogg" | "audio/opus" | "application/ogg" => Some(OGG),
+        "audio/webm" | "video/webm" => Some(WEBM),
+        "audio/mpeg" | "audio/mp3" => Some(MP3),
+        "audio/flac" | "audio/x-flac" => Some(FLAC),
+        _ => None,
+    }
+}
+
+/// A speech-to-text backend
+#[async_trait]
+pub trait Transcriber: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn transcribe(&self, audio: Vec<u8>, format: AudioFormat, language: &str) -> Result<String, String>;
+}
+
+/// OpenAI's hosted Whisper API
+pub struct OpenAiWhisper {
+    api_key: String,
+    model: String,
+}
+
+impl OpenAiWhisper {
+    pub fn new(api_key: String, model: String) -> Self {
+        Self { api_key, model }
+    }
+}
+
+#[async_trait]
+impl Transcriber for OpenAiWhisper {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    async fn transcribe(&self, audio: Vec<u8>, format: AudioFormat, language: &str) -> Result<String, String> {
+        post_transcription(OPENAI_TRANSCRIPTIONS_URL, Some(&self.api_key), &self.model, audio, format, language, OPENAI_TIMEOUT_SECONDS).await
+    }
+}
+
+/// Self-hosted Whisper server exposing the OpenAI-compatible /v1/audio/transcriptions endpoint
+pub struct SelfHostedWhisper {
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+impl SelfHostedWhisper {
+    pub fn new(base_url: String, api_key: Option<String>, model: String) -> Self {
+        Self { base_url: base_url.trim_end_matches('/').to_string(), api_key, model }
+    }
+}
+
+#[async_trait]
+impl Transcriber for SelfHostedWhisper {
+    fn name(&self) -> &'static str {
+        "self_hosted"
+    }
+
+    async fn transcribe(&self, audio: Vec<u8>, format: AudioFormat, language: &str) -> Result<String, String> {
+        let url = format!("{}/v1/audio/transcriptions", self.base_url);
+        post_transcription(&url, self.api_key.as_deref(), &self.model, audio, format, language, SELF_HOSTED_TIMEOUT_SECONDS).await
+    }
+}
+
+async fn post_transcription(
+    url: &str,
+    api_key: Option<&str>,
+    model: &str,
+    audio: Vec<u8>,
+    format: AudioFormat,
+    language: &str,
+    timeout_seconds: u64,
+) -> Result<String, String> {
+    let part = reqwest::multipart::Part::bytes(audio)
+        .file_name(format!("recording.{}", format.extension))
+        .mime_str(format.mime)
+        .map_err(|e| format!("Invalid audio MIME type: {}", e))?;
+    let form = reqwest::multipart::Form::new()
+        .part("file", part)
+        .text("model", model.to_string())
+        .text("language", language.to_string());
+
+    let mut request = reqwest::Client::new()
+        .post(url)
+        .timeout(std::time::Duration::from_secs(timeout_seconds))
+        .multipart(form);
+    if let Some(key) = api_key {
+        request = request.header("Authorization", format!("Bearer {}", key));
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Transcription request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("Transcription API error {}: {}", status, error_text));
+    }
+
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse transcription response: {}", e))?;
+
+    Ok(body["text"].as_str().unwrap_or("").trim().to_string())
+}
+
+/// Provider selected by TRANSCRIPTION_PROVIDER (see config.rs)
+pub fn from_config(openai_api_key: &str) -> Box<dyn Transcriber> {
+    match &crate::config::get().speech.transcription {
+        TranscriptionProvider::OpenAi { model } => Box::new(OpenAiWhisper::new(openai_api_key.to_string(), model.clone())),
+        TranscriptionProvider::SelfHosted { base_url, api_key, model } => {
+            Box::new(SelfHostedWhisper::new(base_url.clone(), api_key.clone(), model.clone()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_magic_bytes_win_over_content_type() {
+        let m4a = b"\x00\x00\x00\x20ftypM4A \x00\x00\x00\x00";
+        assert_eq!(detect_audio_format(m4a, Some("application/octet-stream")), Some(MP4));
+        assert_eq!(detect_audio_format(b"OggS\x00\x02\x00\x00", Some("audio/wav")), Some(OGG));
+        assert_eq!(detect_audio_format(&[0x1A, 0x45, 0xDF, 0xA3, 0x9F], None), Some(WEBM));
+        assert_eq!(detect_audio_format(b"RIFF\x24\x08\x00\x00WAVEfmt ", None), Some(WAV));
+    }
+
+    #[test]
+    fn test_content_type_fallback() {
+        assert_eq!(detect_audio_format(b"\x00\x01", Some("audio/webm;codecs=opus")), Some(WEBM));
+        assert_eq!(detect_audio_format(b"\x00\x01", Some("audio/x-m4a")), Some(MP4));
+        assert_eq!(detect_audio_format(b"\x00\x01", Some("text/plain")), None);
+        assert_eq!(detect_audio_format(b"\x00\x01", None), None);
+    }
+}
@@ -0,0 +1,279 @@
+// Serbian Latin <-> Cyrillic transliteration.
+// Answers are generated and stored in Latin; the script is applied to the response (and generated
+// contract documents) only. URLs, e-mail addresses, code spans, Roman numerals and foreign words
+// (q/w/x/y) stay Latin. The other direction normalizes Cyrillic law texts and names for matching.
+use regex::Regex;
+use sqlx::PgPool;
+use std::borrow::Cow;
+use std::sync::OnceLock;
+use uuid::Uuid;
+
+use crate::models::QuestionResponse;
+
+pub use norma_api_types::ResponseScript;
+
+/// The user's saved answer script (Latin when unset or unknown)
+pub async fn preferred_script(user_id: Option<Uuid>, pool: &PgPool) -> ResponseScript {
+    let Some(user_id) = user_id else {
+        return ResponseScript::Latin;
+    };
+
+    let script: Option<String> = sqlx::query_scalar("SELECT response_script FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("⚠️  Failed to load response script preference: {}", e);
+            None
+        });
+
+    script.as_deref().and_then(ResponseScript::parse).unwrap_or_default()
+}
+
+/// Segments copied verbatim: code spans, markdown link targets, URLs, e-mail addresses
+fn protected_segments() -> &'static Regex {
+    static PROTECTED: OnceLock<Regex> = OnceLock::new();
+    PROTECTED.get_or_init(|| {
+        Regex::new(r"`[^`]*`|\]\([^)]*\)|https?://\S+|www\.\S+|[\w.+-]+@[\w-]+\.[\w.-]+").unwrap()
+    })
+}
+
+fn words() -> &'static Regex {
+    static WORD: OnceLock<Regex> = OnceLock::new();
+    WORD.get_or_init(|| Regex::new(r"\p{L}+").unwrap())
+}
+
+// Prefix + ž where "dž" is two letters (nadživeti -> надживети, not наџивети)
+const DZ_SPLIT_PREFIXES: &[&str] = &["nadž", "podž"];
+// Loanwords where "nj" is two letters (injekcija -> инјекција)
+const NJ_SPLIT_PARTS: &[&str] = &["injek", "konjunk", "konjug"];
+
+fn roman_numerals() -> &'static Regex {
+    static ROMAN: OnceLock<Regex> = OnceLock::new();
+    ROMAN.get_or_init(|| {
+        Regex::new(r"^M{0,3}(CM|CD|D?C{0,3})(XC|XL|L?X{0,3})(IX|IV|V?I{0,3})$").unwrap()
+    })
+}
+
+/// "IV", "XII" - and a single letter only when it's numbered like a heading ("I.", "V)"),
+/// since a lone "I" or "V" is usually the word "i" / "v" at the start of a sentence
+fn is_roman_numeral(word: &str, next: Option<char>) -> bool {
+    if word.is_empty() || !roman_numerals().is_match(word) {
+        return false;
+    }
+    word.len() >= 2 || matches!(next, Some('.') | Some(')'))
+}
+
+fn is_foreign_word(word: &str) -> bool {
+    word.chars().any(|c| matches!(c.to_ascii_lowercase(), 'q' | 'w' | 'x' | 'y'))
+}
+
+fn single_letter(c: char) -> Option<char> {
+    let lower = match c.to_lowercase().next()? {
+        'a' => 'а', 'b' => 'б', 'c' => 'ц', 'č' => 'ч', 'ć' => 'ћ', 'd' => 'д', 'đ' => 'ђ',
+        'e' => 'е', 'f' => 'ф', 'g' => 'г', 'h' => 'х', 'i' => 'и', 'j' => 'ј', 'k' => 'к',
+        'l' => 'л', 'm' => 'м', 'n' => 'н', 'o' => 'о', 'p' => 'п', 'r' => 'р', 's' => 'с',
+        'š' => 'ш', 't' => 'т', 'u' => 'у', 'v' => 'в', 'z' => 'з', 'ž' => 'ж',
+        _ => return None,
+    };
+    if c.is_uppercase() {
+        lower.to_uppercase().next()
+    } else {
+        Some(lower)
+    }
+}
+
+fn digraph(first: char, second: char) -> Option<char> {
+    let lower = match (first.to_lowercase().next()?, second.to_lowercase().next()?) {
+        ('l', 'j') => 'љ',
+        ('n', 'j') => 'њ',
+        ('d', 'ž') => 'џ',
+        _ => return None,
+    };
+    // "Lj", "LJ" -> Љ; "lj" -> љ
+    if first.is_uppercase() {
+        lower.to_uppercase().next()
+    } else {
+        Some(lower)
+    }
+}
+
+fn transliterate_word(word: &str, next: Option<char>) -> String {
+    if is_foreign_word(word) || is_roman_numeral(word, next) {
+        return word.to_string();
+    }
+
+    let lower = word.to_lowercase();
+    let split_dz = DZ_SPLIT_PREFIXES.iter().any(|p| lower.starts_with(p));
+    let split_nj = NJ_SPLIT_PARTS.iter().any(|p| lower.contains(p));
+
+    let chars: Vec<char> = word.chars().collect();
+    let mut result = String::with_capacity(word.len() * 2);
+    let mut i = 0;
+    while i < chars.len() {
+        if let Some(&next) = chars.get(i + 1) {
+            let is_split = match (chars[i].to_ascii_lowercase(), next.to_lowercase().next()) {
+                ('d', Some('ž')) => split_dz,
+                ('n', Some('j')) => split_nj,
+                _ => false,
+            };
+            if !is_split {
+                if let Some(letter) = digraph(chars[i], next) {
+                    result.push(letter);
+                    i += 2;
+                    continue;
+                }
+            }
+        }
+        result.push(single_letter(chars[i]).unwrap_or(chars[i]));
+        i += 1;
+    }
+    result
+}
+
+fn transliterate_plain(text: &str) -> String {
+    words().replace_all(text, |caps: &regex::Captures| {
+        let word = caps.get(0).unwrap();
+        transliterate_word(word.as_str(), text[word.end()..].chars().next())
+    })
+    .into_owned()
+}
+
+/// Transliterate Serbian Latin text to Cyrillic, leaving Latin-only tokens untouched
+pub fn to_cyrillic(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() * 2);
+    let mut last = 0;
+    for protected in protected_segments().find_iter(text) {
+        result.push_str(&transliterate_plain(&text[last..protected.start()]));
+        result.push_str(protected.as_str());
+        last = protected.end();
+    }
+    result.push_str(&transliterate_plain(&text[last..]));
+    result
+}
+
+fn latin_letter(c: char) -> Option<&'static str> {
+    Some(match c {
+        'а' => "a", 'б' => "b", 'в' => "v", 'г' => "g", 'д' => "d", 'ђ' => "đ", 'е' => "e",
+        'ж' => "ž", 'з' => "z", 'и' => "i", 'ј' => "j", 'к' => "k", 'л' => "l", 'љ' => "lj",
+        'м' => "m", 'н' => "n", 'њ' => "nj", 'о' => "o", 'п' => "p", 'р' => "r", 'с' => "s",
+        'т' => "t", 'ћ' => "ć", 'у' => "u", 'ф' => "f", 'х' => "h", 'ц' => "c", 'ч' => "č",
+        'џ' => "dž", 'ш' => "š",
+        'А' => "A", 'Б' => "B", 'В' => "V", 'Г' => "G", 'Д' => "D", 'Ђ' => "Đ", 'Е' => "E",
+        'Ж' => "Ž", 'З' => "Z", 'И' => "I", 'Ј' => "J", 'К' => "K", 'Л' => "L", 'Љ' => "Lj",
+        'М' => "M", 'Н' => "N", 'Њ' => "Nj", 'О' => "O", 'П' => "P", 'Р' => "R", 'С' => "S",
+        'Т' => "T", 'Ћ' => "Ć", 'У' => "U", 'Ф' => "F", 'Х' => "H", 'Ц' => "C", 'Ч' => "Č",
+        'Џ' => "Dž", 'Ш' => "Š",
+        _ => return None,
+    })
+}
+
+fn is_cyrillic(c: char) -> bool {
+    matches!(c, '\u{0400}'..='\u{04FF}')
+}
+
+fn latin_word(word: &str) -> String {
+    let chars: Vec<char> = word.chars().collect();
+    let mut result = String::with_capacity(word.len());
+    for (i, c) in chars.iter().enumerate() {
+        match latin_letter(*c) {
+            // Љ/Њ/Џ in an all-caps word become "LJ"/"NJ"/"DŽ"
+            Some(latin) if latin.chars().count() == 2 && chars.get(i + 1).is_some_and(|next| next.is_uppercase()) => {
+                result.push_str(&latin.to_uppercase());
+            }
+            Some(latin) => result.push_str(latin),
+            None => result.push(*c),
+        }
+    }
+    result
+}
+
+/// Serbian Cyrillic -> Latin, word by word. Words with letters Serbian doesn't have (ъ, щ, я -
+/// a Bulgarian or Russian quote) are left as they are; text without Cyrillic is returned unchanged.
+pub fn to_latin(text: &str) -> Cow<'_, str> {
+    let is_serbian_word = |word: &str| {
+        let mut cyrillic = word.chars().filter(|c| is_cyrillic(*c)).peekable();
+        cyrillic.peek().is_some() && cyrillic.all(|c| latin_letter(c).is_some())
+    };
+    if !words().find_iter(text).any(|word| is_serbian_word(word.as_str())) {
+        return Cow::Borrowed(text);
+    }
+
+    Cow::Owned(
+        words()
+            .replace_all(text, |caps: &regex::Captures| {
+                let word = &caps[0];
+                if is_serbian_word(word) { latin_word(word) } else { word.to_string() }
+            })
+            .into_owned(),
+    )
+}
+
+/// Apply the requested script to a text (Latin text is returned as-is)
+pub fn in_script(text: &str, script: ResponseScript) -> String {
+    match script {
+        ResponseScript::Latin => text.to_string(),
+        ResponseScript::Cyrillic => to_cyrillic(text),
+    }
+}
+
+/// Apply the requested script to everything the user reads in an answer
+pub fn apply_script(response: &mut QuestionResponse, script: ResponseScript) {
+    if script != ResponseScript::Cyrillic {
+        return;
+    }
+
+    response.answer = to_cyrillic(&response.answer);
+    for quote in response.law_quotes.iter_mut() {
+        *quote = to_cyrillic(quote);
+    }
+    if let Some(law_name) = response.law_name.as_mut() {
+        *law_name = to_cyrillic(law_name);
+    }
+    if let Some(chat_title) = response.chat_title.as_mut() {
+        *chat_title = to_cyrillic(chat_title);
+    }
+    for warning in response.warnings.iter_mut() {
+        warning.message = to_cyrillic(&warning.message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_cyrillic_digraphs_and_case() {
+        assert_eq!(to_cyrillic("Ljubav, NJIVA i džep"), "Љубав, ЊИВА и џеп");
+        assert_eq!(to_cyrillic("Član 12a Zakona o radu"), "Члан 12а Закона о раду");
+        assert_eq!(to_cyrillic("nadživeti, injekcija"), "надживети, инјекција");
+    }
+
+    #[test]
+    fn test_to_latin_round_trip_and_case() {
+        assert_eq!(to_latin("Члан 12а Закона о раду"), "Član 12a Zakona o radu");
+        assert_eq!(to_latin("Љубав, ЊИВА и џеп"), "Ljubav, NJIVA i džep");
+        assert_eq!(to_latin(&to_cyrillic("Zaključen između poslodavca i zaposlenog")), "Zaključen između poslodavca i zaposlenog");
+        // Latin is left alone, and so are words with non-Serbian letters - without blocking the rest
+        assert!(matches!(to_latin("Član 5"), Cow::Borrowed(_)));
+        assert!(matches!(to_latin("съдът, щом"), Cow::Borrowed(_)));
+        assert_eq!(to_latin("Закон о раду (на бугарском: съдът)"), "Zakon o radu (na bugarskom: съдът)");
+    }
+
+    #[test]
+    fn test_to_cyrillic_keeps_latin_only_tokens() {
+        assert_eq!(
+            to_cyrillic("Vidi https://www.paragraf.rs/propisi/zakon-o-radu.html ili pišite na info@normaai.rs"),
+            "Види https://www.paragraf.rs/propisi/zakon-o-radu.html или пишите на info@normaai.rs"
+        );
+        assert_eq!(to_cyrillic("Glava IV, Word dokument"), "Глава IV, Word документ");
+        assert_eq!(to_cyrillic("I. Opšte odredbe"), "I. Опште одредбе");
+    }
+
+    #[test]
+    fn test_to_cyrillic_lone_letters_are_words() {
+        assert_eq!(to_cyrillic("I poslodavac i zaposleni"), "И послодавац и запослени");
+        assert_eq!(to_cyrillic("V slučaju spora, CIVIL"), "В случају спора, ЦИВИЛ");
+        assert_eq!(to_cyrillic("[Član 5](#fn-1) i `kod`"), "[Члан 5](#fn-1) и `kod`");
+    }
+}
@@ -0,0 +1,282 @@
+// Trial abuse detection. Besides the per-IP cap on free questions, trial accounts are tied to the
+// address and device they ask from (X-Device-Session-Id, when the client sends it) and refused when
+// the pattern looks like someone cycling through accounts for fresh trial messages: too many accounts
+// on one device, too many new accounts from one address, or a throwaway email address. Paying
+// accounts are never checked.
+use crate::models::{ApiError, ErrorResponse};
+use axum::http::{HeaderMap, StatusCode};
+use sqlx::PgPool;
+use std::net::IpAddr;
+use uuid::Uuid;
+
+/// Trial accounts a device may use; later ones are refused, earlier ones keep working
+const MAX_TRIAL_ACCOUNTS_PER_DEVICE: i64 = 2;
+
+/// New trial accounts one address may bring in per window. Mobile carriers put many subscribers
+/// behind one CGNAT address, so this only catches bulk sign-ups; the device limit does the rest.
+const MAX_TRIAL_ACCOUNTS_PER_NETWORK: i64 = 15;
+const NETWORK_WINDOW_HOURS: i32 = 24;
+
+/// Stands in for the device of requests without X-Device-Session-Id (one row per account)
+const UNKNOWN_DEVICE: Uuid = Uuid::nil();
+
+/// Well-known throwaway mail providers; extend with DISPOSABLE_EMAIL_DOMAINS (comma separated)
+const DISPOSABLE_EMAIL_DOMAINS: &[&str] = &[
+    "10minutemail.com",
+    "guerrillamail.com",
+    "guerrillamail.net",
+    "mailinator.com",
+    "maildrop.cc",
+    "sharklasers.com",
+    "temp-mail.org",
+    "tempmail.com",
+    "throwawaymail.com",
+    "trashmail.com",
+    "yopmail.com",
+    "getnada.com",
+    "dispostable.com",
+    "fakeinbox.com",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrialAbuseReason {
+    DisposableEmail,
+    TooManyAccountsOnDevice,
+    TooManyAccountsOnNetwork,
+}
+
+impl TrialAbuseReason {
+    pub fn code(&self) -> &'static str {
+        match self {
+            TrialAbuseReason::DisposableEmail => "disposable_email",
+            TrialAbuseReason::TooManyAccountsOnDevice => "too_many_accounts_on_device",
+            TrialAbuseReason::TooManyAccountsOnNetwork => "too_many_accounts_on_network",
+        }
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            TrialAbuseReason::DisposableEmail => {
+                "Probni period nije dostupan za privremene email adrese. Registrujte se sa trajnom adresom."
+            }
+            TrialAbuseReason::TooManyAccountsOnDevice => {
+                "Na ovom uređaju je već iskorišćen probni period. Izaberite plan da biste nastavili."
+            }
+            TrialAbuseReason::TooManyAccountsOnNetwork => {
+                "Sa ove mreže je aktivirano previše probnih naloga. Pokušajte kasnije ili izaberite plan."
+            }
+        }
+    }
+
+    /// 403 with a TRIAL_ABUSE_DETECTED body; details.reason tells the frontend which case it is
+    pub fn error(&self) -> ApiError {
+        ApiError {
+            status: StatusCode::FORBIDDEN,
+            body: Some(ErrorResponse {
+                error: "TRIAL_ABUSE_DETECTED".to_string(),
+                message: self.message().to_string(),
+                details: Some(serde_json::json!({ "reason": self.code() })),
+            }),
+        }
+    }
+}
+
+/// What is known about the trial account asking a question
+#[derive(Debug, Default)]
+struct TrialSignals {
+    disposable_email: bool,
+    earlier_accounts_on_device: i64,  // Other trial accounts first seen on the device before this one
+    earlier_accounts_on_network: i64, // Other trial accounts first seen on the address in the window before this one
+}
+
+fn evaluate(signals: &TrialSignals) -> Option<TrialAbuseReason> {
+    if signals.disposable_email {
+        Some(TrialAbuseReason::DisposableEmail)
+    } else if signals.earlier_accounts_on_device >= MAX_TRIAL_ACCOUNTS_PER_DEVICE {
+        Some(TrialAbuseReason::TooManyAccountsOnDevice)
+    } else if signals.earlier_accounts_on_network >= MAX_TRIAL_ACCOUNTS_PER_NETWORK {
+        Some(TrialAbuseReason::TooManyAccountsOnNetwork)
+    } else {
+        None
+    }
+}
+
+fn is_disposable_email_with(email: &str, extra_domains: &[String]) -> bool {
+    let Some((_, domain)) = email.trim().rsplit_once('@') else {
+        return false;
+    };
+    let domain = domain.to_lowercase();
+    let matches = |listed: &str| {
+        let listed = listed.trim().to_lowercase();
+        !listed.is_empty() && (domain == listed || domain.ends_with(&format!(".{}", listed)))
+    };
+
+    DISPOSABLE_EMAIL_DOMAINS.iter().any(|listed| matches(listed))
+        || extra_domains.iter().any(|listed| matches(listed))
+}
+
+fn is_disposable_email(email: &str) -> bool {
+    is_disposable_email_with(email, &crate::config::get().disposable_email_domains)
+}
+
+/// Network an address belongs to: the address itself for IPv4 (neighbours in a /24 are often
+/// unrelated mobile users), the /64 for IPv6 (one connection; devices rotate the rest)
+fn ip_network(ip: &str) -> Option<String> {
+    match ip.trim().parse::<IpAddr>().ok()? {
+        IpAddr::V4(v4) => Some(format!("{}/32", v4)),
+        IpAddr::V6(v6) => {
+            let segments = v6.segments();
+            Some(format!("{:x}:{:x}:{:x}:{:x}::/64", segments[0], segments[1], segments[2], segments[3]))
+        }
+    }
+}
+
+/// Record the device a trial account asks from and refuse the question when the account looks
+/// like trial abuse. No-op for paying accounts and anonymous requests.
+pub async fn check_trial_device(
+    user_id: Option<Uuid>,
+    headers: &HeaderMap,
+    client_ip: &str,
+    pool: &PgPool,
+) -> Result<(), ApiError> {
+    let Some(user_id) = user_id else {
+        return Ok(());
+    };
+    let user = crate::database::get_user(Some(user_id), pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to load user for trial checks: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let Some(user) = user.filter(|user| user.account_type.starts_with("trial")) else {
+        return Ok(());
+    };
+
+    let mut signals = TrialSignals { disposable_email: is_disposable_email(&user.email), ..Default::default() };
+
+    // Clients that don't send a device id still get the network check
+    let device_session_id = headers
+        .get("X-Device-Session-Id")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| Uuid::parse_str(s.trim()).ok())
+        .filter(|id| *id != UNKNOWN_DEVICE);
+    let (accounts, network_accounts) = device_signals(device_session_id, user_id, client_ip, pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to record trial device: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    signals.earlier_accounts_on_device = accounts;
+    signals.earlier_accounts_on_network = network_accounts;
+
+    match evaluate(&signals) {
+        Some(reason) => {
+            eprintln!(
+                "🚫 Trial abuse suspected for user {} ({}): {:?}",
+                user_id,
+                reason.code(),
+                signals
+            );
+            crate::metrics::record_trial_abuse_rejection(reason.code());
+            Err(reason.error())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Upsert the (device, account) pair and count the accounts on the same device and address that
+/// came before it
+async fn device_signals(
+    device_session_id: Option<Uuid>,
+    user_id: Uuid,
+    client_ip: &str,
+    pool: &PgPool,
+) -> Result<(i64, i64), sqlx::Error> {
+    let network = ip_network(client_ip);
+
+    let first_seen_at: chrono::DateTime<chrono::Utc> = sqlx::query_scalar(
+        "INSERT INTO trial_devices (device_session_id, user_id, ip_address, ip_subnet)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (device_session_id, user_id)
+         DO UPDATE SET ip_address = EXCLUDED.ip_address, ip_subnet = EXCLUDED.ip_subnet, last_seen_at = NOW()
+         RETURNING first_seen_at",
+    )
+    .bind(device_session_id.unwrap_or(UNKNOWN_DEVICE))
+    .bind(user_id)
+    .bind(client_ip)
+    .bind(&network)
+    .fetch_one(pool)
+    .await?;
+
+    let earlier_accounts: i64 = match device_session_id {
+        Some(device_session_id) => {
+            sqlx::query_scalar(
+                "SELECT COUNT(*) FROM trial_devices WHERE device_session_id = $1 AND user_id <> $2 AND first_seen_at < $3",
+            )
+            .bind(device_session_id)
+            .bind(user_id)
+            .bind(first_seen_at)
+            .fetch_one(pool)
+            .await?
+        }
+        None => 0,
+    };
+
+    let Some(network) = network else {
+        return Ok((earlier_accounts, 0));
+    };
+    let earlier_network_accounts: i64 = sqlx::query_scalar(
+        "SELECT COUNT(DISTINCT user_id) FROM trial_devices
+         WHERE ip_subnet = $1 AND user_id <> $2 AND first_seen_at < $3
+           AND first_seen_at > $3 - make_interval(hours => $4)",
+    )
+    .bind(network)
+    .bind(user_id)
+    .bind(first_seen_at)
+    .bind(NETWORK_WINDOW_HOURS)
+    .fetch_one(pool)
+    .await?;
+
+    Ok((earlier_accounts, earlier_network_accounts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disposable_email_detection() {
+        assert!(is_disposable_email_with("ana@mailinator.com", &[]));
+        assert!(is_disposable_email_with("Ana@Eu.YopMail.com ", &[]));
+        assert!(!is_disposable_email_with("ana@gmail.com", &[]));
+        assert!(!is_disposable_email_with("not-an-email", &[]));
+        let extra = ["example.org".to_string(), "burner.rs".to_string()];
+        assert!(is_disposable_email_with("ana@burner.rs", &extra));
+        assert!(!is_disposable_email_with("ana@gmail.com", &extra));
+    }
+
+    #[test]
+    fn test_ip_network_and_evaluation() {
+        assert_eq!(ip_network("93.87.12.201").as_deref(), Some("93.87.12.201/32"));
+        assert_eq!(ip_network("2a02:2f0c:8100:1::5").as_deref(), Some("2a02:2f0c:8100:1::/64"));
+        assert_eq!(ip_network("unknown"), None);
+
+        assert_eq!(evaluate(&TrialSignals::default()), None);
+        assert_eq!(
+            evaluate(&TrialSignals { earlier_accounts_on_device: 1, earlier_accounts_on_network: 8, ..Default::default() }),
+            None
+        );
+        assert_eq!(
+            evaluate(&TrialSignals { earlier_accounts_on_device: 2, ..Default::default() }),
+            Some(TrialAbuseReason::TooManyAccountsOnDevice)
+        );
+        assert_eq!(
+            evaluate(&TrialSignals { earlier_accounts_on_network: 15, ..Default::default() }),
+            Some(TrialAbuseReason::TooManyAccountsOnNetwork)
+        );
+        assert_eq!(
+            evaluate(&TrialSignals { disposable_email: true, earlier_accounts_on_device: 5, ..Default::default() }),
+            Some(TrialAbuseReason::DisposableEmail)
+        );
+    }
+}
@@ -1,15 +1,17 @@
 use axum::{
-    extract::State,
+    extract::{Path, Query, State},
     http::{HeaderMap, StatusCode},
     response::Json as ResponseJson,
     Json,
 };
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
-use crate::revenuecat::{RevenueCatClient, WebhookEvent, product_id_to_plan_info};
+use crate::revenuecat::{RevenueCatClient, SubscriptionStatus, WebhookEvent, product_id_to_message_pack, product_id_to_plan_info};
+use crate::subscriptions::{Subscription, SubscriptionState, Transition};
 
 type AppState = (PgPool, String, String, Option<String>, Option<String>, String); // (pool, api_key, jwt_secret, supabase_url, supabase_jwt_secret, resend_api_key)
 
@@ -19,6 +21,14 @@ pub struct WebhookResponse {
     pub message: String,
 }
 
+/// Response for link-purchase: the subscription state the app should unlock features from
+#[derive(Debug, Serialize)]
+pub struct LinkPurchaseResponse {
+    pub success: bool,
+    pub message: String,
+    pub subscription: SubscriptionStatus,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct LinkPurchaseRequest {
     pub receipt_token: String,
@@ -37,38 +47,26 @@ pub struct LinkPurchaseRequest {
 ///
 /// Best practice: Instead of handling each event type differently,
 /// we fetch the latest subscriber state from RevenueCat API and sync it.
+///
+/// Every payload is persisted to `webhook_events` before processing, so a
+/// RevenueCat API or database failure no longer loses the event: it is retried
+/// in the background with exponential backoff and ends up in the dead-letter
+/// state (replayable via the admin endpoint) once retries are exhausted.
 pub async fn handle_revenuecat_webhook(
     State((pool, api_key, _, _, _, _)): State<AppState>,
     headers: HeaderMap,
-    ResponseJson(payload): ResponseJson<WebhookEvent>,
+    ResponseJson(raw_payload): ResponseJson<serde_json::Value>,
 ) -> Result<ResponseJson<WebhookResponse>, (StatusCode, String)> {
-    // Log webhook details with parsed product information
-    let plan_info = product_id_to_plan_info(&payload.event.product_id);
-    info!(
-        "Received RevenueCat webhook: event_type={}, product_id={}, plan_info={:?}, user={}, environment={}",
-        payload.event.event_type,
-        payload.event.product_id,
-        plan_info,
-        payload.event.app_user_id,
-        payload.event.environment  // ← Will show "SANDBOX" or "PRODUCTION"
-    );
-
     // 1. Verify webhook signature
-    let webhook_secret = std::env::var("REVENUECAT_WEBHOOK_SECRET")
-        .unwrap_or_else(|_| String::new());
-
-    if !webhook_secret.is_empty() {
+    if let Some(webhook_secret) = &crate::config::get().revenuecat_webhook_secret {
         let authorization = headers
             .get("Authorization")
             .and_then(|h| h.to_str().ok())
             .unwrap_or("");
 
-        let revenuecat_client = RevenueCatClient::new(
-            std::env::var("REVENUECAT_API_KEY")
-                .unwrap_or_else(|_| api_key.clone())
-        );
+        let revenuecat_client = revenuecat_client(&api_key);
 
-        if !revenuecat_client.verify_webhook_signature(authorization, &webhook_secret) {
+        if !revenuecat_client.verify_webhook_signature(authorization, webhook_secret) {
             warn!("Invalid webhook signature");
             return Err((
                 StatusCode::UNAUTHORIZED,
@@ -77,131 +75,550 @@ pub async fn handle_revenuecat_webhook(
         }
     }
 
-    // 2. Extract user ID from webhook
-    let app_user_id = &payload.event.app_user_id;
-    let user_id = match Uuid::parse_str(app_user_id) {
-        Ok(id) => id,
+    // 2. Log webhook details with parsed product information
+    let parsed = serde_json::from_value::<WebhookEvent>(raw_payload.clone());
+    let (event_type, app_user_id) = match &parsed {
+        Ok(payload) => {
+            let plan_info = product_id_to_plan_info(&payload.event.product_id);
+            info!(
+                "Received RevenueCat webhook: event_type={}, product_id={}, plan_info={:?}, user={}, environment={}",
+                payload.event.event_type,
+                payload.event.product_id,
+                plan_info,
+                payload.event.app_user_id,
+                payload.event.environment  // ← Will show "SANDBOX" or "PRODUCTION"
+            );
+            (Some(payload.event.event_type.clone()), Some(payload.event.app_user_id.clone()))
+        }
         Err(e) => {
-            error!("Invalid user ID in webhook: {}", e);
-            return Err((
-                StatusCode::BAD_REQUEST,
-                format!("Invalid user ID: {}", e),
-            ));
+            warn!("Received RevenueCat webhook with unexpected payload shape: {}", e);
+            (None, None)
         }
     };
 
-    // 3. Fetch latest subscription status from RevenueCat
-    let revenuecat_client = RevenueCatClient::new(
-        std::env::var("REVENUECAT_API_KEY")
-            .unwrap_or_else(|_| api_key.clone())
-    );
+    // 3. Persist the event before doing any work that can fail
+    let external_id = raw_payload["event"]["id"].as_str();
+    let stored = store_webhook_event(&pool, "revenuecat", external_id, event_type.as_deref(), app_user_id.as_deref(), &raw_payload)
+        .await
+        .map_err(|e| {
+            error!("Failed to persist webhook event: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to persist webhook event: {}", e),
+            )
+        })?;
+    let Some(event_id) = stored else {
+        info!("Ignoring redelivered RevenueCat webhook {:?}", external_id);
+        return Ok(ResponseJson(WebhookResponse {
+            success: true,
+            message: "Webhook already received".to_string(),
+        }));
+    };
 
-    let subscription_status = match revenuecat_client.get_subscription_status(app_user_id).await {
-        Ok(status) => status,
+    // 4. Process in the background - failures are retried by the webhook retry job
+    tokio::spawn(async move {
+        process_webhook_event(&pool, &api_key, event_id).await;
+    });
+
+    Ok(ResponseJson(WebhookResponse {
+        success: true,
+        message: format!("Webhook accepted (event {})", event_id),
+    }))
+}
+
+// ==================== WEBHOOK EVENT QUEUE ====================
+
+/// Maximum processing attempts before an event is moved to the dead-letter state
+const MAX_WEBHOOK_ATTEMPTS: i32 = 8;
+
+/// How often the retry job looks for due events
+const WEBHOOK_RETRY_INTERVAL_SECS: u64 = 60;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct WebhookEventRecord {
+    pub id: i64,
+    pub source: String,
+    pub external_id: Option<String>, // Provider's event id, unique per source
+    pub event_type: Option<String>,
+    pub app_user_id: Option<String>,
+    pub payload: serde_json::Value,
+    pub status: String, // 'pending', 'processing', 'processed', 'failed', 'dead'
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: chrono::DateTime<chrono::Utc>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub processed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Why processing an event failed - determines whether it is worth retrying
+pub enum WebhookProcessError {
+    Retryable(String),
+    Permanent(String),
+}
+
+/// RevenueCat client using REVENUECAT_API_KEY, or the route's key when that isn't set
+fn revenuecat_client(fallback_api_key: &str) -> RevenueCatClient {
+    RevenueCatClient::new(
+        crate::config::get()
+            .revenuecat_api_key
+            .clone()
+            .unwrap_or_else(|| fallback_api_key.to_string()),
+    )
+}
+
+/// In-app notification about a processed webhook event - once, even if the event is replayed
+pub async fn notify_subscription_change(pool: &PgPool, user_id: Uuid, webhook_event_id: i64, title: &str, body: Option<&str>) {
+    let already_notified = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(
+             SELECT 1 FROM user_notifications
+             WHERE user_id = $1 AND kind = 'subscription' AND (data->>'webhook_event_id')::BIGINT = $2
+         )"
+    )
+    .bind(user_id)
+    .bind(webhook_event_id)
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+    if already_notified {
+        return;
+    }
+
+    crate::notifications::notify(
+        pool,
+        user_id,
+        crate::notifications::NotificationKind::Subscription,
+        title,
+        body,
+        Some(serde_json::json!({ "webhook_event_id": webhook_event_id })),
+    )
+    .await;
+}
+
+/// Invoice a web (Stripe) purchase or renewal. App Store and Play Store purchases are invoiced by
+/// the stores. Keyed by the store transaction, so replays and retries don't invoice twice.
+async fn invoice_stripe_charge(
+    pool: &PgPool,
+    user_id: Uuid,
+    webhook_event_id: i64,
+    event: &crate::revenuecat::WebhookEventData,
+) -> Result<(), String> {
+    if !matches!(event.event_type.as_str(), "INITIAL_PURCHASE" | "RENEWAL") || event.store != "STRIPE" {
+        return Ok(());
+    }
+    let (Some(price), Some(currency)) = (event.price_in_purchased_currency, event.currency.as_deref()) else {
+        warn!(webhook_event_id = webhook_event_id, "Stripe charge without a price, not invoiced");
+        return Ok(());
+    };
+    let (plan, billing_period) = product_id_to_plan_info(&event.product_id).unwrap_or(("professional", "monthly"));
+    let external_id = event
+        .transaction_id
+        .clone()
+        .unwrap_or_else(|| format!("revenuecat-webhook-{}", webhook_event_id));
+
+    crate::invoices::record_charge(
+        pool,
+        user_id,
+        crate::invoices::Charge {
+            description: crate::invoices::subscription_description(plan, billing_period),
+            plan: Some(plan),
+            billing_period: Some(billing_period),
+            total_minor: (price * 100.0).round() as i64,
+            currency,
+            paid_at: chrono::DateTime::from_timestamp_millis(event.purchased_at_ms).unwrap_or_else(chrono::Utc::now),
+            source: "revenuecat",
+            external_id: &external_id,
+        },
+    )
+    .await
+    .map(|_| ())
+}
+
+/// Exponential backoff between attempts: 1, 2, 4, 8... minutes, capped at 6 hours
+pub fn webhook_retry_backoff(attempts: i32) -> chrono::Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    let minutes = 2i64.saturating_pow(exponent).min(6 * 60);
+    chrono::Duration::minutes(minutes)
+}
+
+/// Persist a raw webhook payload, returning the event ID - or None when the provider's event id
+/// is already stored (a redelivery, which is acknowledged without processing it again)
+pub async fn store_webhook_event(
+    pool: &PgPool,
+    source: &str,
+    external_id: Option<&str>,
+    event_type: Option<&str>,
+    app_user_id: Option<&str>,
+    payload: &serde_json::Value,
+) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar(
+        "INSERT INTO webhook_events (source, external_id, event_type, app_user_id, payload)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (source, external_id) DO NOTHING
+         RETURNING id"
+    )
+    .bind(source)
+    .bind(external_id)
+    .bind(event_type)
+    .bind(app_user_id)
+    .bind(payload)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Claim an event for processing and sync the subscriber state it refers to.
+/// Records the outcome (processed / failed with next attempt / dead) on the row.
+pub async fn process_webhook_event(pool: &PgPool, api_key: &str, event_id: i64) {
+    // Atomically claim the event so the retry job and the request task never process it twice
+    let claimed = sqlx::query_as::<_, WebhookEventRecord>(
+        "UPDATE webhook_events
+         SET status = 'processing', attempts = attempts + 1, updated_at = NOW()
+         WHERE id = $1
+           AND (status IN ('pending', 'failed')
+                OR (status = 'processing' AND updated_at < NOW() - INTERVAL '10 minutes'))
+         RETURNING *"
+    )
+    .bind(event_id)
+    .fetch_optional(pool)
+    .await;
+
+    let event = match claimed {
+        Ok(Some(event)) => event,
+        Ok(None) => return, // Already processed or claimed by another worker
         Err(e) => {
-            error!("Failed to fetch subscription status: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to fetch subscription status: {}", e),
-            ));
+            error!(event_id = event_id, "Failed to claim webhook event: {}", e);
+            return;
         }
     };
 
-    // 4. Update user in database
-    match update_user_subscription(&pool, user_id, &subscription_status).await {
-        Ok(_) => {
-            info!(
-                user_id = %user_id,
-                account_type = %subscription_status.account_type,
-                "Successfully updated user subscription from webhook"
-            );
-            Ok(ResponseJson(WebhookResponse {
-                success: true,
-                message: "Webhook processed successfully".to_string(),
-            }))
+    let outcome = sync_subscription_from_event(pool, api_key, &event).await;
+
+    let update = match outcome {
+        Ok(()) => {
+            info!(event_id = event.id, attempts = event.attempts, "Webhook event processed");
+            crate::metrics::record_webhook_outcome("processed");
+            sqlx::query(
+                "UPDATE webhook_events
+                 SET status = 'processed', last_error = NULL, processed_at = NOW(), updated_at = NOW()
+                 WHERE id = $1"
+            )
+            .bind(event.id)
+            .execute(pool)
+            .await
         }
-        Err(e) => {
-            error!("Failed to update user subscription: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to update user: {}", e),
-            ))
+        Err(failure) => {
+            let (error_message, permanent) = match failure {
+                WebhookProcessError::Retryable(e) => (e, false),
+                WebhookProcessError::Permanent(e) => (e, true),
+            };
+
+            let next_status = if permanent || event.attempts >= MAX_WEBHOOK_ATTEMPTS {
+                error!(event_id = event.id, attempts = event.attempts, "Webhook event moved to dead-letter: {}", error_message);
+                crate::metrics::record_webhook_outcome("dead");
+                "dead"
+            } else {
+                warn!(event_id = event.id, attempts = event.attempts, "Webhook event failed, will retry: {}", error_message);
+                crate::metrics::record_webhook_outcome("failed");
+                "failed"
+            };
+            let next_attempt_at = chrono::Utc::now() + webhook_retry_backoff(event.attempts);
+
+            sqlx::query(
+                "UPDATE webhook_events
+                 SET status = $1, last_error = $2, next_attempt_at = $3, updated_at = NOW()
+                 WHERE id = $4"
+            )
+            .bind(next_status)
+            .bind(&error_message)
+            .bind(next_attempt_at)
+            .bind(event.id)
+            .execute(pool)
+            .await
+        }
+    };
+
+    if let Err(e) = update {
+        error!(event_id = event.id, "Failed to record webhook event outcome: {}", e);
+    }
+}
+
+/// Fetch the latest subscriber state from RevenueCat and write it to the user. Stripe events carry
+/// the state themselves and are applied by payments.rs.
+async fn sync_subscription_from_event(
+    pool: &PgPool,
+    api_key: &str,
+    event: &WebhookEventRecord,
+) -> Result<(), WebhookProcessError> {
+    if event.source == "stripe" {
+        return crate::payments::process_stripe_event(pool, event).await;
+    }
+
+    let payload: WebhookEvent = serde_json::from_value(event.payload.clone())
+        .map_err(|e| WebhookProcessError::Permanent(format!("Invalid webhook payload: {}", e)))?;
+
+    let app_user_id = &payload.event.app_user_id;
+    let user_id = Uuid::parse_str(app_user_id)
+        .map_err(|e| WebhookProcessError::Permanent(format!("Invalid user ID: {}", e)))?;
+    // Purchases made on an account that was later merged belong to the surviving account
+    let user_id = crate::account_linking::resolve_user_id(pool, user_id)
+        .await
+        .map_err(|e| WebhookProcessError::Retryable(format!("Failed to resolve user: {}", e)))?;
+
+    // Message packs are consumables - they add messages and don't touch the subscription
+    if let Some(messages) = product_id_to_message_pack(&payload.event.product_id) {
+        if event.event_type.as_deref() != Some("NON_RENEWING_PURCHASE") {
+            return Ok(());
+        }
+        let external_id = payload
+            .event
+            .transaction_id
+            .clone()
+            .unwrap_or_else(|| format!("revenuecat-webhook-{}", event.id));
+        let credited = crate::message_packs::credit_pack(
+            pool,
+            user_id,
+            &payload.event.product_id,
+            messages,
+            "revenuecat_webhook",
+            &external_id,
+        )
+        .await
+        .map_err(|e| WebhookProcessError::Retryable(format!("Failed to credit message pack: {}", e)))?;
+        info!(user_id = %user_id, messages = messages, credited = credited, "Message pack purchase processed");
+        return Ok(());
+    }
+
+    let revenuecat_client = revenuecat_client(api_key);
+
+    let subscription_status = revenuecat_client
+        .get_subscription_status(app_user_id)
+        .await
+        .map_err(|e| WebhookProcessError::Retryable(format!("Failed to fetch subscription status: {}", e)))?;
+
+    // A replayed BILLING_ISSUE for a renewal that has since gone through doesn't restart the grace period
+    let billing_issue = event.event_type.as_deref() == Some("BILLING_ISSUE") && !subscription_status.is_active;
+    update_user_subscription(pool, user_id, &subscription_status, billing_issue)
+        .await
+        .map_err(|e| WebhookProcessError::Retryable(format!("Failed to update user: {}", e)))?;
+
+    crate::audit_log::record(
+        pool,
+        user_id,
+        crate::audit_log::AuditAction::SubscriptionSynced,
+        crate::audit_log::AuditSource::Webhook,
+        serde_json::json!({
+            "webhook_event_id": event.id,
+            "event_type": event.event_type,
+            "account_type": subscription_status.account_type,
+            "is_active": subscription_status.is_active,
+        }),
+    )
+    .await;
+
+    if let Some((title, body)) = event.event_type.as_deref().and_then(crate::notifications::subscription_notification) {
+        notify_subscription_change(pool, user_id, event.id, title, body).await;
+    }
+
+    invoice_stripe_charge(pool, user_id, event.id, &payload.event)
+        .await
+        .map_err(|e| WebhookProcessError::Retryable(format!("Failed to issue invoice: {}", e)))?;
+
+    info!(
+        user_id = %user_id,
+        account_type = %subscription_status.account_type,
+        "Successfully updated user subscription from webhook"
+    );
+
+    Ok(())
+}
+
+/// Background job that retries failed webhook events once their backoff has elapsed
+pub async fn start_webhook_retry_job(pool: std::sync::Arc<PgPool>, api_key: String, shutdown: CancellationToken) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(WEBHOOK_RETRY_INTERVAL_SECS));
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.cancelled() => {
+                info!("🛑 Webhook retry job stopped");
+                return;
+            }
+        }
+
+        let due_events: Result<Vec<i64>, sqlx::Error> = sqlx::query_scalar(
+            "SELECT id FROM webhook_events
+             WHERE (status IN ('pending', 'failed') AND next_attempt_at <= NOW())
+                OR (status = 'processing' AND updated_at < NOW() - INTERVAL '10 minutes')
+             ORDER BY next_attempt_at ASC
+             LIMIT 50"
+        )
+        .fetch_all(pool.as_ref())
+        .await;
+
+        match due_events {
+            Ok(event_ids) => {
+                if !event_ids.is_empty() {
+                    info!("🔁 Retrying {} webhook event(s)", event_ids.len());
+                }
+                for event_id in event_ids {
+                    process_webhook_event(&pool, &api_key, event_id).await;
+                }
+            }
+            Err(e) => {
+                error!("❌ Failed to fetch due webhook events: {}", e);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListWebhookEventsQuery {
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Admin: list webhook events, defaulting to the dead-letter queue
+pub async fn list_webhook_events(
+    State((pool, _, _, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ListWebhookEventsQuery>,
+) -> Result<ResponseJson<Vec<WebhookEventRecord>>, (StatusCode, String)> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err((StatusCode::UNAUTHORIZED, "Unauthorized".to_string()));
+    }
+
+    let status = query.status.unwrap_or_else(|| "dead".to_string());
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+
+    let events = sqlx::query_as::<_, WebhookEventRecord>(
+        "SELECT * FROM webhook_events WHERE status = $1 ORDER BY created_at DESC LIMIT $2"
+    )
+    .bind(&status)
+    .bind(limit)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to list webhook events: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to list webhook events: {}", e))
+    })?;
+
+    Ok(ResponseJson(events))
+}
+
+/// Admin: reset a failed/dead event and process it immediately
+pub async fn replay_webhook_event(
+    State((pool, api_key, _, _, _, _)): State<AppState>,
+    headers: HeaderMap,
+    Path(event_id): Path<i64>,
+) -> Result<ResponseJson<WebhookEventRecord>, (StatusCode, String)> {
+    if !crate::admin::is_admin_request(&headers) {
+        return Err((StatusCode::UNAUTHORIZED, "Unauthorized".to_string()));
+    }
+
+    let reset = sqlx::query(
+        "UPDATE webhook_events
+         SET status = 'pending', attempts = 0, next_attempt_at = NOW(), updated_at = NOW()
+         WHERE id = $1 AND status IN ('failed', 'dead')"
+    )
+    .bind(event_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to reset webhook event: {}", e)))?;
+
+    if reset.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "No failed webhook event with this ID".to_string()));
+    }
+
+    info!(event_id = event_id, "Replaying webhook event");
+    process_webhook_event(&pool, &api_key, event_id).await;
+
+    let event = sqlx::query_as::<_, WebhookEventRecord>("SELECT * FROM webhook_events WHERE id = $1")
+        .bind(event_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to load webhook event: {}", e)))?;
+
+    Ok(ResponseJson(event))
+}
+
+/// Transitions that bring a stored subscription in line with what the store reports, given the
+/// state resolve_state worked out for it
+fn store_transitions(
+    current: &Subscription,
+    status: &crate::revenuecat::SubscriptionStatus,
+    state: SubscriptionState,
+    grace_ends_at: Option<chrono::DateTime<chrono::Utc>>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Vec<Transition> {
+    let activate = || Transition::Activate {
+        plan: status.account_type.clone(),
+        billing_period: status.subscription_type.clone().unwrap_or_else(|| "monthly".to_string()),
+        started_at: now,
+        period_ends_at: status.expires_at,
+    };
+    let same_plan = current.is_subscribed()
+        && current.account_type == status.account_type
+        && (status.subscription_type.is_none() || current.subscription_type == status.subscription_type);
+
+    match (state, grace_ends_at) {
+        (SubscriptionState::Active, _) if same_plan => vec![Transition::Renew { period_ends_at: status.expires_at }],
+        (SubscriptionState::Active, _) => vec![activate()],
+        // The plan stays as it was until the grace period ends, even once the store lets it lapse
+        (SubscriptionState::Grace, Some(ends_at)) if status.is_active && !same_plan => {
+            vec![activate(), Transition::Grace { ends_at }]
+        }
+        (SubscriptionState::Grace, Some(ends_at)) => vec![Transition::Grace { ends_at }],
+        (SubscriptionState::Grace | SubscriptionState::PastDue, _) => vec![Transition::PastDue],
+        (SubscriptionState::Expired | SubscriptionState::Cancelled, _) => {
+            vec![Transition::Expire { at: status.expires_at.or(grace_ends_at) }]
         }
     }
 }
 
 /// Update user subscription information in the database
-async fn update_user_subscription(
+pub async fn update_user_subscription(
     pool: &PgPool,
     user_id: Uuid,
     status: &crate::revenuecat::SubscriptionStatus,
+    billing_issue: bool,
 ) -> Result<(), String> {
-    // Determine subscription_status
-    // Grace period: billing issues detected but subscription hasn't expired yet
-    let subscription_status = if status.in_grace_period {
-        "active" // Keep active during grace period
-    } else if status.is_active {
-        "active"
-    } else if status.expires_at.is_some() {
-        "expired"
-    } else {
-        "cancelled"
-    };
-
-    // Calculate next billing date (for active subscriptions)
-    let next_billing_date = if status.is_active {
-        status.expires_at
-    } else {
-        None
+    let database_error = |e: sqlx::Error| match e {
+        sqlx::Error::RowNotFound => format!("User not found: {}", user_id),
+        e => format!("Database error: {}", e),
     };
+    let mut tx = pool.begin().await.map_err(database_error)?;
+    let current = crate::subscriptions::load_for_update(&mut tx, user_id).await.map_err(database_error)?;
+
+    // A billing problem starts (or continues) the payment grace period - see dunning.rs
+    let now = chrono::Utc::now();
+    let (state, grace_ends_at) = crate::dunning::resolve_state(
+        status.is_active,
+        billing_issue || status.in_grace_period,
+        status.expires_at,
+        current.grace_period_ends_at,
+        now,
+    );
 
-    // Determine final account_type and messages
-    // If subscription is not active AND not in grace period, downgrade to trial
-    let (final_account_type, messages_remaining) = if !status.is_active && !status.in_grace_period {
-        // Subscription expired/cancelled - revert to trial with 0 messages
-        // (they already used their original 5 trial messages)
-        ("trial_registered", Some(0))
-    } else {
-        // Active subscription - use proper account type and message limits
-        let messages = match status.account_type.as_str() {
-            "individual" => Some(20),
-            "professional" => None, // Unlimited
-            _ => Some(5), // Fallback to trial
-        };
-        (status.account_type.as_str(), messages)
-    };
+    for transition in store_transitions(&current, status, state, grace_ends_at, now) {
+        crate::subscriptions::transition_in(&mut tx, user_id, &transition).await.map_err(database_error)?;
+    }
 
-    // Update user record
-    let result = sqlx::query(
+    sqlx::query(
         "UPDATE users SET
-            account_type = $1,
-            subscription_type = $2,
-            subscription_status = $3,
-            premium_expires_at = $4,
-            next_billing_date = $5,
-            trial_messages_remaining = $6,
-            platform = $7,
-            revenuecat_subscriber_id = $8,
-            last_receipt_validation = NOW(),
-            updated_at = NOW()
-        WHERE id = $9"
+            platform = $1,
+            revenuecat_subscriber_id = $2,
+            last_receipt_validation = NOW()
+        WHERE id = $3"
     )
-    .bind(final_account_type)
-    .bind(&status.subscription_type)
-    .bind(subscription_status)
-    .bind(status.expires_at)
-    .bind(next_billing_date)
-    .bind(messages_remaining)
     .bind(&status.platform)
     .bind(user_id.to_string()) // Use user UUID as RevenueCat subscriber ID
     .bind(user_id)
-    .execute(pool)
+    .execute(&mut *tx)
     .await
-    .map_err(|e| format!("Database error: {}", e))?;
+    .map_err(database_error)?;
+    tx.commit().await.map_err(database_error)?;
 
-    if result.rows_affected() == 0 {
-        return Err(format!("User not found: {}", user_id));
-    }
+    crate::dunning::announce(pool, user_id, current.subscription_status.as_deref(), state, grace_ends_at).await;
 
     Ok(())
 }
@@ -227,10 +644,7 @@ pub async fn verify_subscription(
     info!("Manual subscription verification for user {}", user_id);
 
     // Fetch subscription status from RevenueCat
-    let revenuecat_client = RevenueCatClient::new(
-        std::env::var("REVENUECAT_API_KEY")
-            .unwrap_or_else(|_| api_key.clone())
-    );
+    let revenuecat_client = revenuecat_client(&api_key);
 
     let subscription_status = match revenuecat_client.get_subscription_status(&user_id.to_string()).await {
         Ok(status) => status,
@@ -245,7 +659,7 @@ pub async fn verify_subscription(
     };
 
     // Update database
-    match update_user_subscription(&pool, user_id, &subscription_status).await {
+    match update_user_subscription(&pool, user_id, &subscription_status, false).await {
         Ok(_) => {
             info!(
                 user_id = %user_id,
@@ -277,7 +691,7 @@ pub async fn link_purchase(
     State((pool, api_key, jwt_secret, _, supabase_jwt_secret, _)): State<AppState>,
     headers: HeaderMap,
     Json(payload): Json<LinkPurchaseRequest>,
-) -> Result<ResponseJson<WebhookResponse>, (StatusCode, String)> {
+) -> Result<ResponseJson<LinkPurchaseResponse>, (StatusCode, String)> {
     // Verify user authentication
     let user_id = crate::database::verify_user_from_headers_async(
         &headers,
@@ -296,10 +710,7 @@ pub async fn link_purchase(
         "Linking purchase to user"
     );
 
-    let revenuecat_client = RevenueCatClient::new(
-        std::env::var("REVENUECAT_API_KEY")
-            .unwrap_or_else(|_| api_key.clone())
-    );
+    let revenuecat_client = revenuecat_client(&api_key);
 
     // Link purchase to user in RevenueCat
     match revenuecat_client
@@ -329,20 +740,21 @@ pub async fn link_purchase(
     };
 
     // Update database
-    match update_user_subscription(&pool, user_id, &subscription_status).await {
+    match update_user_subscription(&pool, user_id, &subscription_status, false).await {
         Ok(_) => {
             info!(
                 user_id = %user_id,
                 account_type = %subscription_status.account_type,
                 "Successfully linked and activated subscription"
             );
-            Ok(ResponseJson(WebhookResponse {
+            Ok(ResponseJson(LinkPurchaseResponse {
                 success: true,
                 message: format!(
                     "Subscription activated: {} ({})",
                     subscription_status.account_type,
                     subscription_status.subscription_type.as_deref().unwrap_or("N/A")
                 ),
+                subscription: subscription_status,
             }))
         }
         Err(e) => {
@@ -359,6 +771,62 @@ pub async fn link_purchase(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_webhook_retry_backoff() {
+        assert_eq!(webhook_retry_backoff(1), chrono::Duration::minutes(1));
+        assert_eq!(webhook_retry_backoff(2), chrono::Duration::minutes(2));
+        assert_eq!(webhook_retry_backoff(4), chrono::Duration::minutes(8));
+        // Capped at 6 hours no matter how many attempts
+        assert_eq!(webhook_retry_backoff(30), chrono::Duration::hours(6));
+    }
+
+    fn store_status(account_type: &str, is_active: bool, expires_at: Option<chrono::DateTime<chrono::Utc>>) -> SubscriptionStatus {
+        SubscriptionStatus {
+            account_type: account_type.to_string(),
+            subscription_type: Some("monthly".to_string()),
+            expires_at,
+            is_active,
+            platform: Some("ios".to_string()),
+            in_grace_period: false,
+        }
+    }
+
+    #[test]
+    fn test_store_transitions() {
+        let now = chrono::Utc::now();
+        let expires_at = Some(now + chrono::Duration::days(30));
+        let subscribed = Subscription {
+            account_type: "individual".to_string(),
+            subscription_type: Some("monthly".to_string()),
+            subscription_status: Some("active".to_string()),
+            subscription_started_at: Some(now),
+            next_billing_date: Some(now),
+            premium_expires_at: Some(now),
+            grace_period_ends_at: None,
+            trial_messages_remaining: Some(4),
+            team_id: None,
+        };
+
+        // Same plan renews, another plan activates
+        let active = store_status("individual", true, expires_at);
+        assert_eq!(
+            store_transitions(&subscribed, &active, SubscriptionState::Active, None, now),
+            vec![Transition::Renew { period_ends_at: expires_at }]
+        );
+        let upgraded = store_transitions(&subscribed, &store_status("professional", true, expires_at), SubscriptionState::Active, None, now);
+        assert!(matches!(upgraded.as_slice(), [Transition::Activate { plan, .. }] if plan == "professional"));
+
+        let ends_at = now + chrono::Duration::days(7);
+        assert_eq!(
+            store_transitions(&subscribed, &active, SubscriptionState::Grace, Some(ends_at), now),
+            vec![Transition::Grace { ends_at }]
+        );
+        assert_eq!(
+            store_transitions(&subscribed, &store_status("individual", false, Some(now)), SubscriptionState::Expired, None, now),
+            vec![Transition::Expire { at: Some(now) }]
+        );
+    }
+
     #[test]
     fn test_subscription_status_mapping() {
         // Test that active subscription maps to "active"
@@ -83,6 +83,8 @@ dependencies {
     implementation("androidx.core:core:1.13.1")
     implementation("androidx.core:core-ktx:1.13.1")
     implementation("com.google.android.material:material:1.12.0")
+    // Biometric app lock (BiometricAuth.kt)
+    implementation("androidx.biometric:biometric:1.1.0")
     // Google Play Billing for In-App Purchases
     implementation("com.android.billingclient:billing:7.1.1")
     implementation("com.android.billingclient:billing-ktx:7.1.1")
@@ -0,0 +1,61 @@
+package com.nikola.norma_ai
+
+import android.app.Activity
+import androidx.biometric.BiometricManager
+import androidx.biometric.BiometricManager.Authenticators.BIOMETRIC_WEAK
+import androidx.biometric.BiometricPrompt
+import androidx.core.content.ContextCompat
+import androidx.fragment.app.FragmentActivity
+
+// Biometric app lock, driven from Rust (src/biometric.rs) over JNI.
+// Results go back through nativeOnResult, implemented on the Rust side.
+object BiometricAuth {
+    // Kind codes shared with Rust: 0 = none/unavailable, 4 = biometric (fingerprint, face, iris)
+    private const val KIND_NONE = 0
+    private const val KIND_BIOMETRIC = 4
+
+    // Prompt results: 1 = success, 0 = cancelled/failed, -1 = unavailable
+    private const val RESULT_SUCCESS = 1
+    private const val RESULT_FAILED = 0
+    private const val RESULT_UNAVAILABLE = -1
+
+    @JvmStatic
+    external fun nativeOnResult(requestId: Long, result: Int)
+
+    @JvmStatic
+    fun biometricKind(activity: Activity): Int {
+        val status = BiometricManager.from(activity).canAuthenticate(BIOMETRIC_WEAK)
+        return if (status == BiometricManager.BIOMETRIC_SUCCESS) KIND_BIOMETRIC else KIND_NONE
+    }
+
+    @JvmStatic
+    fun authenticate(activity: Activity, requestId: Long, reason: String) {
+        val fragmentActivity = activity as? FragmentActivity
+        if (fragmentActivity == null || biometricKind(activity) == KIND_NONE) {
+            nativeOnResult(requestId, RESULT_UNAVAILABLE)
+            return
+        }
+
+        val callback = object : BiometricPrompt.AuthenticationCallback() {
+            override fun onAuthenticationSucceeded(result: BiometricPrompt.AuthenticationResult) {
+                nativeOnResult(requestId, RESULT_SUCCESS)
+            }
+
+            // Called for cancel, lockout and too many attempts. A single non-matching finger only
+            // triggers onAuthenticationFailed and the prompt stays open, so we don't report that.
+            override fun onAuthenticationError(errorCode: Int, errString: CharSequence) {
+                nativeOnResult(requestId, RESULT_FAILED)
+            }
+        }
+
+        val promptInfo = BiometricPrompt.PromptInfo.Builder()
+            .setTitle("Norma AI")
+            .setSubtitle(reason)
+            .setNegativeButtonText("Otkaži")
+            .setAllowedAuthenticators(BIOMETRIC_WEAK)
+            .build()
+
+        BiometricPrompt(fragmentActivity, ContextCompat.getMainExecutor(activity), callback)
+            .authenticate(promptInfo)
+    }
+}
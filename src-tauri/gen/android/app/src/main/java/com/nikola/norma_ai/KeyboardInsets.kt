@@ -0,0 +1,43 @@
+package com.nikola.norma_ai
+
+import android.app.Activity
+import android.graphics.Color
+import android.view.View
+import android.view.ViewGroup
+import androidx.core.view.ViewCompat
+import androidx.core.view.WindowInsetsCompat
+
+// Keyboard inset handling for the WebView, installed from Rust (src/android_webview_helper.rs).
+// With targetSdk 35+ the app is edge-to-edge and adjustResize no longer shrinks the WebView,
+// so on some devices the soft keyboard covers the question input. We listen for IME insets and
+// pad the WebView's container by the keyboard height instead - same as the iOS webview_helper.
+object KeyboardInsets {
+    @JvmStatic
+    fun install(activity: Activity, webView: View) {
+        val container = webView.parent as? ViewGroup ?: return
+
+        // Store original padding at install time so repeated inset passes don't stack
+        val originalBottom = container.paddingBottom
+
+        // White background prevents a black flash behind the WebView while it resizes
+        container.setBackgroundColor(Color.WHITE)
+
+        ViewCompat.setOnApplyWindowInsetsListener(container) { view, insets ->
+            val keyboardHeight = if (insets.isVisible(WindowInsetsCompat.Type.ime())) {
+                insets.getInsets(WindowInsetsCompat.Type.ime()).bottom
+            } else {
+                0
+            }
+
+            val bottom = originalBottom + keyboardHeight
+            if (view.paddingBottom != bottom) {
+                view.setPadding(view.paddingLeft, view.paddingTop, view.paddingRight, bottom)
+            }
+
+            // Let the WebView still see the insets (CSS safe-area values)
+            insets
+        }
+
+        activity.runOnUiThread { ViewCompat.requestApplyInsets(container) }
+    }
+}
@@ -0,0 +1,64 @@
+// BiometricFFIBridge.swift
+// C FFI Bridge between Swift LocalAuthentication and Rust (src/biometric.rs)
+// Used for the Face ID / Touch ID app lock
+
+import Foundation
+import LocalAuthentication
+
+// Biometric kind codes, shared with the Rust side:
+// 0 = none/unavailable, 1 = Touch ID, 2 = Face ID, 3 = Optic ID
+@_cdecl("ios_biometric_kind")
+public func ios_biometric_kind() -> Int32 {
+    let context = LAContext()
+    var error: NSError?
+
+    guard context.canEvaluatePolicy(.deviceOwnerAuthenticationWithBiometrics, error: &error) else {
+        if let error = error {
+            print("⚠️ Biometrics unavailable: \(error.localizedDescription)")
+        }
+        return 0
+    }
+
+    switch context.biometryType {
+    case .touchID:
+        return 1
+    case .faceID:
+        return 2
+    default:
+        if #available(iOS 17.0, *), context.biometryType == .opticID {
+            return 3
+        }
+        return 0
+    }
+}
+
+// Show the Face ID / Touch ID prompt (async operation wrapped in sync function)
+// Returns 1 = success, 0 = cancelled/failed, -1 = unavailable
+@_cdecl("ios_biometric_authenticate")
+public func ios_biometric_authenticate(_ reason: UnsafePointer<CChar>) -> Int32 {
+    let localizedReason = String(cString: reason)
+    let context = LAContext()
+    var error: NSError?
+
+    guard context.canEvaluatePolicy(.deviceOwnerAuthenticationWithBiometrics, error: &error) else {
+        print("❌ Biometrics unavailable: \(error?.localizedDescription ?? "unknown")")
+        return -1
+    }
+
+    // Use DispatchSemaphore to make async code synchronous for FFI
+    // (Rust calls this from a blocking thread, never the main thread)
+    let semaphore = DispatchSemaphore(value: 0)
+    var result: Int32 = 0
+
+    context.evaluatePolicy(.deviceOwnerAuthenticationWithBiometrics, localizedReason: localizedReason) { success, evaluationError in
+        if success {
+            result = 1
+        } else if let evaluationError = evaluationError {
+            print("🔒 Biometric authentication failed: \(evaluationError.localizedDescription)")
+        }
+        semaphore.signal()
+    }
+
+    semaphore.wait()
+    return result
+}
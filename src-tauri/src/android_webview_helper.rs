@@ -0,0 +1,42 @@
+// Android keyboard inset handling
+// Edge-to-edge Android (targetSdk 35+) no longer resizes the WebView for the soft keyboard, so the
+// question input can end up under it. The inset listener lives in Kotlin (KeyboardInsets.kt in
+// gen/android) since JNI can't implement Java interfaces; here we install it on the main WebView.
+
+use jni::objects::{JObject, JValue};
+use jni::JNIEnv;
+use tauri::wry::prelude::find_class;
+use tauri::WebviewWindow;
+
+// Package from the bundle identifier (com.nikola.norma-ai -> com.nikola.norma_ai)
+const KEYBOARD_INSETS_CLASS: &str = "com/nikola/norma_ai/KeyboardInsets";
+
+pub fn enable_keyboard_insets(webview_window: &WebviewWindow) {
+    let _ = webview_window.with_webview(|webview| {
+        webview.jni_handle().exec(|env, activity, webview| {
+            match install_keyboard_insets(env, activity, webview) {
+                Ok(()) => println!("✅ Android keyboard inset listener installed"),
+                Err(e) => {
+                    // A pending Java exception would abort the next JNI call on this thread
+                    let _ = env.exception_clear();
+                    println!("❌ Failed to install keyboard inset listener: {}", e);
+                }
+            }
+        });
+    });
+}
+
+fn install_keyboard_insets(env: &mut JNIEnv, activity: &JObject, webview: &JObject) -> jni::errors::Result<()> {
+    if webview.is_null() {
+        return Err(jni::errors::Error::NullPtr("webview"));
+    }
+
+    let class = find_class(env, activity, KEYBOARD_INSETS_CLASS.to_string())?;
+    env.call_static_method(
+        class,
+        "install",
+        "(Landroid/app/Activity;Landroid/view/View;)V",
+        &[JValue::Object(activity), JValue::Object(webview)],
+    )?;
+    Ok(())
+}
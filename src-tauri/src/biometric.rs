@@ -0,0 +1,322 @@
+// Biometric app lock (mobile)
+// Lawyers keep sensitive conversations on their phones, so the app can be locked behind
+// Face ID / Touch ID (iOS) or fingerprint / face unlock (Android).
+// iOS: Uses FFI bridge to Swift LocalAuthentication (ios/Sources/BiometricFFIBridge.swift)
+// Android: JNI calls into Kotlin BiometricPrompt (gen/android/.../BiometricAuth.kt)
+// Lock state lives here rather than in the frontend: the app starts locked, and locks again
+// when it was in the background for longer than the configured auto-lock timeout.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{command, AppHandle, Runtime, State};
+use tauri_plugin_store::StoreExt;
+
+const SETTINGS_STORE: &str = "settings.json";
+const SETTINGS_KEY: &str = "app_lock";
+
+const DEFAULT_TIMEOUT_SECONDS: u64 = 60;
+const MAX_TIMEOUT_SECONDS: u64 = 60 * 60;
+// How recent the biometric check before a settings change has to be
+const SETTINGS_AUTH_MAX_AGE_SECONDS: u64 = 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AppLockSettings {
+    pub enabled: bool,
+    pub timeout_seconds: u64, // 0 = lock every time the app goes to the background
+}
+
+impl Default for AppLockSettings {
+    fn default() -> Self {
+        Self { enabled: false, timeout_seconds: DEFAULT_TIMEOUT_SECONDS }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BiometricStatus {
+    pub available: bool,
+    pub kind: &'static str, // "face_id" | "touch_id" | "optic_id" | "biometric" | "none"
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppLockStatus {
+    pub enabled: bool,
+    pub locked: bool,
+}
+
+pub struct AppLockState {
+    locked: Mutex<bool>,
+    background_since: Mutex<Option<Instant>>,
+    authenticated_at: Mutex<Option<Instant>>, // Last successful prompt, spent by a settings change
+}
+
+impl Default for AppLockState {
+    fn default() -> Self {
+        // Cold start counts as "away for too long"
+        Self { locked: Mutex::new(true), background_since: Mutex::new(None), authenticated_at: Mutex::new(None) }
+    }
+}
+
+fn load_settings<R: Runtime>(app: &AppHandle<R>) -> AppLockSettings {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(SETTINGS_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+// Platform biometric kind codes (shared by the Swift and Kotlin bridges)
+fn kind_name(code: i32) -> &'static str {
+    match code {
+        1 => "touch_id",
+        2 => "face_id",
+        3 => "optic_id",
+        4 => "biometric",
+        _ => "none",
+    }
+}
+
+// Check whether the device has biometrics enrolled
+#[command]
+pub async fn biometric_status<R: Runtime>(app: AppHandle<R>) -> Result<BiometricStatus, String> {
+    let code = biometric_kind(&app).await?;
+    Ok(BiometricStatus { available: code > 0, kind: kind_name(code) })
+}
+
+// Prompt for Face ID / fingerprint. Returns false when the user cancels or doesn't match.
+#[command]
+pub async fn biometric_authenticate<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, AppLockState>,
+    reason: String,
+) -> Result<bool, String> {
+    let success = authenticate(&app, reason).await?;
+    if success {
+        *state.locked.lock().unwrap() = false;
+        *state.authenticated_at.lock().unwrap() = Some(Instant::now());
+        println!("🔓 App unlocked");
+    }
+    Ok(success)
+}
+
+#[command]
+pub fn app_lock_get_settings<R: Runtime>(app: AppHandle<R>) -> AppLockSettings {
+    load_settings(&app)
+}
+
+// Enable/disable the lock or change the timeout. While the lock is (or is about to be) enabled this
+// needs a fresh biometric_authenticate, and it's refused outright while the app is locked - only
+// the authenticate path unlocks.
+#[command]
+pub async fn app_lock_set_settings<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, AppLockState>,
+    settings: AppLockSettings,
+) -> Result<AppLockSettings, String> {
+    let current = load_settings(&app);
+    if current.enabled && *state.locked.lock().unwrap() {
+        return Err("The app is locked".to_string());
+    }
+    if current.enabled || settings.enabled {
+        let authenticated_at = state.authenticated_at.lock().unwrap().take();
+        if !authenticated_at.is_some_and(|at| at.elapsed().as_secs() <= SETTINGS_AUTH_MAX_AGE_SECONDS) {
+            return Err("Authenticate before changing the app lock".to_string());
+        }
+    }
+    if settings.enabled && biometric_kind(&app).await? <= 0 {
+        return Err("Biometric authentication is not available on this device".to_string());
+    }
+
+    let settings = AppLockSettings {
+        timeout_seconds: settings.timeout_seconds.min(MAX_TIMEOUT_SECONDS),
+        ..settings
+    };
+
+    let store = app.store(SETTINGS_STORE).map_err(|e| format!("Failed to open settings: {}", e))?;
+    let value = serde_json::to_value(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    store.set(SETTINGS_KEY, value);
+    store.save().map_err(|e| format!("Failed to save settings: {}", e))?;
+    Ok(settings)
+}
+
+// Called when the app goes to the background (visibilitychange -> hidden)
+#[command]
+pub fn app_lock_background(state: State<'_, AppLockState>) {
+    let mut background_since = state.background_since.lock().unwrap();
+    if background_since.is_none() {
+        *background_since = Some(Instant::now());
+    }
+}
+
+// Called on startup and when the app returns to the foreground
+#[command]
+pub fn app_lock_status<R: Runtime>(app: AppHandle<R>, state: State<'_, AppLockState>) -> AppLockStatus {
+    let settings = load_settings(&app);
+    let mut locked = state.locked.lock().unwrap();
+
+    if let Some(since) = state.background_since.lock().unwrap().take() {
+        if since.elapsed().as_secs() >= settings.timeout_seconds {
+            *locked = true;
+        }
+    }
+
+    AppLockStatus { enabled: settings.enabled, locked: settings.enabled && *locked }
+}
+
+// ============================================================================
+// iOS LocalAuthentication Implementation
+// ============================================================================
+
+#[cfg(target_os = "ios")]
+mod ios_ffi {
+    use std::ffi::CString;
+    use std::os::raw::c_char;
+
+    extern "C" {
+        // These functions are implemented in Swift (BiometricFFIBridge.swift)
+        fn ios_biometric_kind() -> i32;
+        fn ios_biometric_authenticate(reason: *const c_char) -> i32;
+    }
+
+    pub fn kind() -> i32 {
+        unsafe { ios_biometric_kind() }
+    }
+
+    // 1 = success, 0 = cancelled/failed, -1 = biometrics unavailable
+    pub fn authenticate(reason: &str) -> Result<bool, String> {
+        let reason = CString::new(reason).map_err(|e| format!("Invalid reason: {}", e))?;
+        match unsafe { ios_biometric_authenticate(reason.as_ptr()) } {
+            1 => Ok(true),
+            0 => Ok(false),
+            _ => Err("Biometric authentication is not available".to_string()),
+        }
+    }
+}
+
+// The Swift side blocks on a semaphore until the system prompt finishes
+#[cfg(target_os = "ios")]
+async fn biometric_kind<R: Runtime>(_app: &AppHandle<R>) -> Result<i32, String> {
+    tauri::async_runtime::spawn_blocking(ios_ffi::kind)
+        .await
+        .map_err(|e| format!("Biometric check failed: {}", e))
+}
+
+#[cfg(target_os = "ios")]
+async fn authenticate<R: Runtime>(_app: &AppHandle<R>, reason: String) -> Result<bool, String> {
+    tauri::async_runtime::spawn_blocking(move || ios_ffi::authenticate(&reason))
+        .await
+        .map_err(|e| format!("Biometric authentication failed: {}", e))?
+}
+
+// ============================================================================
+// Android BiometricPrompt Implementation
+// ============================================================================
+
+#[cfg(target_os = "android")]
+mod android_jni {
+    use jni::objects::{JClass, JObject, JValue};
+    use jni::sys::{jint, jlong};
+    use jni::JNIEnv;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicI64, Ordering};
+    use std::sync::Mutex;
+    use tauri::wry::prelude::find_class;
+    use tauri::{AppHandle, Manager, Runtime};
+    use tokio::sync::oneshot;
+
+    const BIOMETRIC_AUTH_CLASS: &str = "com/nikola/norma_ai/BiometricAuth";
+
+    // Prompts waiting for BiometricAuth.nativeOnResult, by request id
+    static PENDING: Mutex<Option<HashMap<i64, oneshot::Sender<i32>>>> = Mutex::new(None);
+    static NEXT_REQUEST_ID: AtomicI64 = AtomicI64::new(1);
+
+    // Run JNI code with the activity on the webview's thread and wait for its result
+    async fn with_activity<R, T, F>(app: &AppHandle<R>, f: F) -> Result<T, String>
+    where
+        R: Runtime,
+        T: Send + 'static,
+        F: FnOnce(&mut JNIEnv, &JObject) -> jni::errors::Result<T> + Send + 'static,
+    {
+        let window = app.get_webview_window("main").ok_or("Main window not found")?;
+        let (tx, rx) = oneshot::channel();
+        window
+            .with_webview(move |webview| {
+                webview.jni_handle().exec(move |env, activity, _webview| {
+                    let result = f(env, activity);
+                    if result.is_err() {
+                        // A pending Java exception would abort the next JNI call on this thread
+                        let _ = env.exception_clear();
+                    }
+                    let _ = tx.send(result.map_err(|e| format!("JNI call failed: {}", e)));
+                });
+            })
+            .map_err(|e| format!("Failed to access webview: {}", e))?;
+        rx.await.map_err(|_| "Biometric bridge dropped the request".to_string())?
+    }
+
+    pub async fn kind<R: Runtime>(app: &AppHandle<R>) -> Result<i32, String> {
+        with_activity(app, |env, activity| {
+            let class = find_class(env, activity, BIOMETRIC_AUTH_CLASS.to_string())?;
+            env.call_static_method(class, "biometricKind", "(Landroid/app/Activity;)I", &[JValue::Object(activity)])?
+                .i()
+        })
+        .await
+    }
+
+    pub async fn authenticate<R: Runtime>(app: &AppHandle<R>, reason: String) -> Result<bool, String> {
+        let request_id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        PENDING.lock().unwrap().get_or_insert_with(HashMap::new).insert(request_id, tx);
+
+        let shown = with_activity(app, move |env, activity| {
+            let class = find_class(env, activity, BIOMETRIC_AUTH_CLASS.to_string())?;
+            let reason = env.new_string(reason)?;
+            env.call_static_method(
+                class,
+                "authenticate",
+                "(Landroid/app/Activity;JLjava/lang/String;)V",
+                &[JValue::Object(activity), JValue::Long(request_id), JValue::Object(&*reason)],
+            )?;
+            Ok(())
+        })
+        .await;
+
+        if let Err(e) = shown {
+            if let Some(pending) = PENDING.lock().unwrap().as_mut() {
+                pending.remove(&request_id);
+            }
+            return Err(e);
+        }
+
+        // 1 = success, 0 = cancelled/failed, -1 = biometrics unavailable
+        match rx.await.map_err(|_| "Biometric prompt was dismissed".to_string())? {
+            1 => Ok(true),
+            0 => Ok(false),
+            _ => Err("Biometric authentication is not available".to_string()),
+        }
+    }
+
+    // Called from Kotlin when the BiometricPrompt finishes
+    #[no_mangle]
+    pub extern "system" fn Java_com_nikola_norma_1ai_BiometricAuth_nativeOnResult(
+        _env: JNIEnv,
+        _class: JClass,
+        request_id: jlong,
+        result: jint,
+    ) {
+        let sender = PENDING.lock().unwrap().as_mut().and_then(|pending| pending.remove(&request_id));
+        if let Some(sender) = sender {
+            let _ = sender.send(result);
+        }
+    }
+}
+
+#[cfg(target_os = "android")]
+async fn biometric_kind<R: Runtime>(app: &AppHandle<R>) -> Result<i32, String> {
+    android_jni::kind(app).await
+}
+
+#[cfg(target_os = "android")]
+async fn authenticate<R: Runtime>(app: &AppHandle<R>, reason: String) -> Result<bool, String> {
+    android_jni::authenticate(app, reason).await
+}
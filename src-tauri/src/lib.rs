@@ -1,14 +1,36 @@
 // iOS-specific module for keyboard scroll prevention and WebView process termination handling
 #[cfg(target_os = "ios")]
 mod webview_helper;
+// Simulator tests for webview_helper, run by tests/webview_helper.rs
+#[cfg(all(target_os = "ios", feature = "webview-tests"))]
+pub use webview_helper::tests as webview_helper_tests;
 
-#[cfg(target_os = "ios")]
+// Android-specific module for keyboard inset handling (parity with the iOS webview_helper)
+#[cfg(target_os = "android")]
+mod android_webview_helper;
+
+#[cfg(any(target_os = "ios", target_os = "android"))]
 use tauri::Manager;
 
 // Simple IAP module for mobile platforms
 #[cfg(any(target_os = "ios", target_os = "android"))]
 mod simple_iap;
 
+// Biometric app lock for mobile (Face ID / Touch ID / fingerprint)
+#[cfg(any(target_os = "ios", target_os = "android"))]
+mod biometric;
+
+// Native "answer ready" notifications for desktop
+#[cfg(not(any(target_os = "ios", target_os = "android")))]
+mod notifications;
+
+// Global shortcut + always-on-top quick ask window for desktop
+#[cfg(not(any(target_os = "ios", target_os = "android")))]
+mod quick_ask;
+
+// Offline draft queue: unsent questions persisted to the store and flushed when back online
+mod offline_queue;
+
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
@@ -30,6 +52,8 @@ pub fn run() {
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_oauth::init()); // OAuth for desktop (localhost callback)
 
     // Mobile-specific plugins (no updater or process)
@@ -41,12 +65,23 @@ pub fn run() {
         // REMOVED: .plugin(tauri_plugin_iap::init()) - crashes on iOS 18 with Tauri 2.9.3
         // Using custom simple_iap implementation instead
 
+    #[cfg(any(target_os = "ios", target_os = "android"))]
+    let builder = builder.manage(biometric::AppLockState::default());
+
     builder
-        .setup(|_app| {
+        .manage(offline_queue::OfflineQueueState::default())
+        .setup(|app| {
+            // Notify the frontend when queued offline drafts can be sent
+            offline_queue::start_connectivity_watcher(app.handle().clone());
+
+            // Desktop: register the quick ask global shortcut
+            #[cfg(not(any(target_os = "ios", target_os = "android")))]
+            quick_ask::init(app.handle());
+
             // iOS: Prevent keyboard from scrolling webview and creating extra space
             #[cfg(target_os = "ios")]
             {
-                if let Some(webview_window) = _app.get_webview_window("main") {
+                if let Some(webview_window) = app.get_webview_window("main") {
                     // Prevent keyboard from scrolling webview
                     webview_helper::disable_scroll_on_keyboard_show(&webview_window);
 
@@ -72,6 +107,14 @@ pub fn run() {
                     println!("✅ iOS WebView inspector enabled");
                 }
             }
+
+            // Android: Pad the webview by the soft keyboard height so it doesn't cover the input
+            #[cfg(target_os = "android")]
+            {
+                if let Some(webview_window) = app.get_webview_window("main") {
+                    android_webview_helper::enable_keyboard_insets(&webview_window);
+                }
+            }
             Ok(())
         })
         .invoke_handler({
@@ -79,15 +122,39 @@ pub fn run() {
             {
                 tauri::generate_handler![
                     greet,
+                    offline_queue::offline_queue_add,
+                    offline_queue::offline_queue_list,
+                    offline_queue::offline_queue_remove,
+                    offline_queue::offline_queue_flush,
                     simple_iap::iap_init,
                     simple_iap::iap_get_products,
                     simple_iap::iap_purchase,
                     simple_iap::iap_restore,
+                    simple_iap::iap_validate_purchase,
+                    biometric::biometric_status,
+                    biometric::biometric_authenticate,
+                    biometric::app_lock_get_settings,
+                    biometric::app_lock_set_settings,
+                    biometric::app_lock_background,
+                    biometric::app_lock_status,
                 ]
             }
             #[cfg(not(any(target_os = "ios", target_os = "android")))]
             {
-                tauri::generate_handler![greet]
+                tauri::generate_handler![
+                    greet,
+                    offline_queue::offline_queue_add,
+                    offline_queue::offline_queue_list,
+                    offline_queue::offline_queue_remove,
+                    offline_queue::offline_queue_flush,
+                    notifications::notify_answer_ready,
+                    quick_ask::quick_ask_show,
+                    quick_ask::quick_ask_hide,
+                    quick_ask::quick_ask_resize,
+                    quick_ask::quick_ask_open_in_main,
+                    quick_ask::quick_ask_get_shortcut,
+                    quick_ask::quick_ask_set_shortcut,
+                ]
             }
         })
         .run(tauri::generate_context!())
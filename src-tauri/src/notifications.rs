@@ -0,0 +1,54 @@
+// Native OS notifications (desktop)
+// Long answers can take a minute, and users switch to other windows meanwhile. The frontend calls
+// notify_answer_ready when an answer arrives; we only notify if the main window isn't focused,
+// and also flash the taskbar/dock icon so the app is easy to find.
+
+use tauri::{command, AppHandle, Manager, Runtime, UserAttentionType};
+use tauri_plugin_notification::NotificationExt;
+
+// Notification centers truncate long bodies anyway - keep the preview to a couple of lines
+const MAX_BODY_CHARS: usize = 160;
+
+fn preview(body: &str) -> String {
+    let plain: String = body
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(['*', '#', '`'], "");
+
+    if plain.chars().count() <= MAX_BODY_CHARS {
+        return plain;
+    }
+    let truncated: String = plain.chars().take(MAX_BODY_CHARS).collect();
+    match truncated.rfind(' ') {
+        Some(cut) => format!("{}…", &truncated[..cut]),
+        None => format!("{}…", truncated),
+    }
+}
+
+// Show "answer ready" when the main window is in the background.
+// Returns whether a notification was shown.
+#[command]
+pub fn notify_answer_ready<R: Runtime>(app: AppHandle<R>, title: String, body: String) -> Result<bool, String> {
+    let window = app.get_webview_window("main");
+    let focused = window
+        .as_ref()
+        .and_then(|w| w.is_focused().ok())
+        .unwrap_or(false);
+    if focused {
+        return Ok(false);
+    }
+
+    app.notification()
+        .builder()
+        .title(title)
+        .body(preview(&body))
+        .show()
+        .map_err(|e| format!("Failed to show notification: {}", e))?;
+
+    if let Some(window) = window {
+        let _ = window.request_user_attention(Some(UserAttentionType::Informational));
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,282 @@
+// Offline draft queue
+// Questions typed while the backend is unreachable are persisted to the Tauri store
+// (offline_queue.json) so they survive restarts, and are sent in order once connectivity returns.
+// Auth lives in the frontend (Supabase session), so the frontend passes its current headers to
+// offline_queue_flush; the Rust side only watches connectivity and emits an event when it's back.
+
+use norma_api_types::{QuestionRequest, QuestionResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::async_runtime::Mutex;
+use tauri::{command, AppHandle, Emitter, Manager, Runtime, State};
+use tauri_plugin_store::StoreExt;
+
+const STORE_FILE: &str = "offline_queue.json";
+const STORE_KEY: &str = "drafts";
+const UNREADABLE_KEY: &str = "unreadable_drafts";
+
+// Same backend as src/services/api.js
+const API_BASE_URL: &str = "https://norma-ai.fly.dev";
+// See backend/src/idempotency.rs
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+const MAX_QUEUED_DRAFTS: usize = 50;
+const CONNECTIVITY_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(180); // Answers can take a while
+
+// Events for the frontend
+const EVENT_ONLINE: &str = "offline-queue://online";
+const EVENT_SENT: &str = "offline-queue://sent";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueuedDraft {
+    pub id: String,
+    pub request: QuestionRequest, // Sent as-is to /api/question
+    pub created_at: u64,          // Unix milliseconds
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SentDraft {
+    pub id: String,
+    pub response: Option<QuestionResponse>, // None if the body couldn't be read
+}
+
+#[derive(Debug, Serialize)]
+pub struct FlushReport {
+    pub sent: Vec<SentDraft>,
+    pub remaining: Vec<QueuedDraft>,
+    pub offline: bool, // Stopped because the backend is still unreachable
+}
+
+/// Serializes read-modify-write of the queue and keeps two flushes from sending the same draft
+#[derive(Default)]
+pub struct OfflineQueueState {
+    lock: Mutex<()>,
+}
+
+// Entries that no longer parse (e.g. written by an older app version with a different request
+// shape) are moved under UNREADABLE_KEY instead of failing the whole queue, so the other drafts
+// still send and the unreadable ones aren't silently lost.
+fn load_drafts<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<QueuedDraft>, String> {
+    let store = app.store(STORE_FILE).map_err(|e| format!("Failed to open offline queue: {}", e))?;
+    let entries = match store.get(STORE_KEY) {
+        Some(Value::Array(entries)) => entries,
+        Some(other) => vec![other],
+        None => return Ok(Vec::new()),
+    };
+
+    let mut drafts = Vec::new();
+    let mut unreadable = Vec::new();
+    for entry in entries {
+        match serde_json::from_value::<QueuedDraft>(entry.clone()) {
+            Ok(draft) => drafts.push(draft),
+            Err(e) => {
+                eprintln!("⚠️ Skipping unreadable offline draft: {}", e);
+                unreadable.push(entry);
+            }
+        }
+    }
+
+    if !unreadable.is_empty() {
+        let mut set_aside = match store.get(UNREADABLE_KEY) {
+            Some(Value::Array(entries)) => entries,
+            _ => Vec::new(),
+        };
+        set_aside.extend(unreadable);
+        store.set(UNREADABLE_KEY, Value::Array(set_aside));
+        save_drafts(app, &drafts)?;
+    }
+    Ok(drafts)
+}
+
+fn save_drafts<R: Runtime>(app: &AppHandle<R>, drafts: &[QueuedDraft]) -> Result<(), String> {
+    let store = app.store(STORE_FILE).map_err(|e| format!("Failed to open offline queue: {}", e))?;
+    let value = serde_json::to_value(drafts).map_err(|e| format!("Failed to serialize offline queue: {}", e))?;
+    store.set(STORE_KEY, value);
+    store.save().map_err(|e| format!("Failed to save offline queue: {}", e))
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Queue a question that couldn't be sent
+#[command]
+pub async fn offline_queue_add<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, OfflineQueueState>,
+    request: QuestionRequest,
+) -> Result<QueuedDraft, String> {
+    if request.question.trim().is_empty() {
+        return Err("Draft has no question".to_string());
+    }
+
+    let _guard = state.lock.lock().await;
+    let mut drafts = load_drafts(&app)?;
+    if drafts.len() >= MAX_QUEUED_DRAFTS {
+        return Err(format!("Offline queue is full ({} drafts)", MAX_QUEUED_DRAFTS));
+    }
+
+    let draft = QueuedDraft {
+        id: uuid::Uuid::new_v4().to_string(),
+        request,
+        created_at: now_millis(),
+        attempts: 0,
+        last_error: None,
+    };
+    drafts.push(draft.clone());
+    save_drafts(&app, &drafts)?;
+
+    println!("📥 Queued offline draft {} ({} pending)", draft.id, drafts.len());
+    Ok(draft)
+}
+
+// Pending drafts, oldest first
+#[command]
+pub async fn offline_queue_list<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, OfflineQueueState>,
+) -> Result<Vec<QueuedDraft>, String> {
+    let _guard = state.lock.lock().await;
+    load_drafts(&app)
+}
+
+// Discard a draft (e.g. the user deleted it or sent it manually)
+#[command]
+pub async fn offline_queue_remove<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, OfflineQueueState>,
+    id: String,
+) -> Result<bool, String> {
+    let _guard = state.lock.lock().await;
+    let mut drafts = load_drafts(&app)?;
+    let before = drafts.len();
+    drafts.retain(|draft| draft.id != id);
+    if drafts.len() == before {
+        return Ok(false);
+    }
+    save_drafts(&app, &drafts)?;
+    Ok(true)
+}
+
+// Send queued drafts in order with the frontend's current auth headers.
+// Stops at the first network failure (still offline), auth error or rate limit -
+// later drafts would fail the same way. Drafts the backend rejects stay queued with last_error.
+#[command]
+pub async fn offline_queue_flush<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, OfflineQueueState>,
+    headers: HashMap<String, String>,
+) -> Result<FlushReport, String> {
+    let _guard = state.lock.lock().await;
+    let mut pending = load_drafts(&app)?.into_iter();
+
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let mut sent = Vec::new();
+    let mut remaining = Vec::new();
+    let mut offline = false;
+
+    while let Some(mut draft) = pending.next() {
+        // The draft id is the idempotency key: a retry after a lost response (or a crash before the
+        // queue was saved) replays the stored answer instead of asking and charging again
+        let mut request = client
+            .post(format!("{}/api/question", API_BASE_URL))
+            .json(&draft.request)
+            .header(IDEMPOTENCY_KEY_HEADER, draft.id.as_str());
+        for (name, value) in headers.iter().filter(|(name, _)| {
+            !name.eq_ignore_ascii_case("content-type") && !name.eq_ignore_ascii_case(IDEMPOTENCY_KEY_HEADER)
+        }) {
+            request = request.header(name.as_str(), value.as_str());
+        }
+
+        draft.attempts += 1;
+        let stop = match request.send().await {
+            Err(e) => {
+                draft.last_error = Some(format!("Network error: {}", e));
+                remaining.push(draft);
+                offline = true;
+                true
+            }
+            Ok(response) if response.status().is_success() => {
+                let body = response.json::<QuestionResponse>().await.ok();
+                let sent_draft = SentDraft { id: draft.id, response: body };
+                let _ = app.emit(EVENT_SENT, sent_draft.clone());
+                println!("📤 Sent offline draft {}", sent_draft.id);
+                sent.push(sent_draft);
+                false
+            }
+            Ok(response) => {
+                let status = response.status();
+                draft.last_error = Some(format!("HTTP {}", status.as_u16()));
+                remaining.push(draft);
+                status.as_u16() == 401 || status.as_u16() == 429 || status.is_server_error()
+            }
+        };
+
+        // Persist after every draft so a crash mid-flush can't send an answered question twice
+        save_drafts(&app, &[remaining.as_slice(), pending.as_slice()].concat())?;
+
+        if stop {
+            break;
+        }
+    }
+
+    remaining.extend(pending);
+    Ok(FlushReport { sent, remaining, offline })
+}
+
+async fn backend_reachable(client: &reqwest::Client) -> bool {
+    client
+        .get(format!("{}/health", API_BASE_URL))
+        .send()
+        .await
+        .map(|response| response.status().is_success())
+        .unwrap_or(false)
+}
+
+// While drafts are queued, poll the backend and emit EVENT_ONLINE (with the pending count)
+// when it becomes reachable, so the frontend can flush with fresh auth headers.
+pub fn start_connectivity_watcher<R: Runtime>(app: AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        let client = match reqwest::Client::builder().timeout(Duration::from_secs(5)).build() {
+            Ok(client) => client,
+            Err(e) => {
+                eprintln!("❌ Offline queue watcher disabled: {}", e);
+                return;
+            }
+        };
+        let mut was_online = false;
+
+        loop {
+            tokio::time::sleep(CONNECTIVITY_CHECK_INTERVAL).await;
+
+            let pending = {
+                let state = app.state::<OfflineQueueState>();
+                let _guard = state.lock.lock().await;
+                load_drafts(&app).map(|drafts| drafts.len()).unwrap_or(0)
+            };
+            if pending == 0 {
+                was_online = false;
+                continue;
+            }
+
+            let online = backend_reachable(&client).await;
+            if online && !was_online {
+                println!("🌐 Backend reachable - {} offline draft(s) ready to send", pending);
+                let _ = app.emit(EVENT_ONLINE, pending);
+            }
+            was_online = online;
+        }
+    });
+}
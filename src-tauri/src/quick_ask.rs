@@ -0,0 +1,168 @@
+// Quick ask (desktop)
+// A global shortcut (default Ctrl/Cmd+Shift+Space, configurable and persisted in settings.json)
+// toggles a small always-on-top window with just the question input, so users can ask Norma
+// without switching to the main app. The window is created on first use and hidden, not closed,
+// afterwards; it hides itself when it loses focus.
+
+use tauri::{
+    command, AppHandle, Emitter, LogicalSize, Manager, Runtime, WebviewUrl, WebviewWindow,
+    WebviewWindowBuilder, WindowEvent,
+};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use tauri_plugin_store::StoreExt;
+
+pub const WINDOW_LABEL: &str = "quick-ask";
+const DEFAULT_SHORTCUT: &str = "CommandOrControl+Shift+Space";
+
+const SETTINGS_STORE: &str = "settings.json";
+const SHORTCUT_KEY: &str = "quick_ask_shortcut";
+
+const WIDTH: f64 = 640.0;
+const COLLAPSED_HEIGHT: f64 = 72.0; // Just the input row
+const MAX_HEIGHT: f64 = 560.0; // Answers scroll inside the window beyond this
+
+// Sent to the quick-ask window every time it's shown, so the frontend focuses the input
+const EVENT_FOCUS_INPUT: &str = "quick-ask://focus";
+// Sent to the main window to open a chat created from quick ask
+const EVENT_OPEN_CHAT: &str = "quick-ask://open-chat";
+
+fn saved_shortcut<R: Runtime>(app: &AppHandle<R>) -> String {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(SHORTCUT_KEY))
+        .and_then(|value| value.as_str().map(String::from))
+        .unwrap_or_else(|| DEFAULT_SHORTCUT.to_string())
+}
+
+fn window<R: Runtime>(app: &AppHandle<R>) -> Result<WebviewWindow<R>, String> {
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        return Ok(window);
+    }
+
+    let window = WebviewWindowBuilder::new(app, WINDOW_LABEL, WebviewUrl::App("index.html?view=quick-ask".into()))
+        .title("Norma AI")
+        .inner_size(WIDTH, COLLAPSED_HEIGHT)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .visible(false)
+        .center()
+        .build()
+        .map_err(|e| format!("Failed to create quick ask window: {}", e))?;
+
+    let hide_on_blur = window.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Focused(false) = event {
+            let _ = hide_on_blur.hide();
+        }
+    });
+
+    Ok(window)
+}
+
+fn show_window<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let window = window(app)?;
+    window.show().map_err(|e| format!("Failed to show quick ask window: {}", e))?;
+    window.set_focus().map_err(|e| format!("Failed to focus quick ask window: {}", e))?;
+    let _ = window.emit_to(WINDOW_LABEL, EVENT_FOCUS_INPUT, ());
+    Ok(())
+}
+
+fn toggle_window<R: Runtime>(app: &AppHandle<R>) {
+    let visible = app
+        .get_webview_window(WINDOW_LABEL)
+        .and_then(|w| w.is_visible().ok())
+        .unwrap_or(false);
+
+    let result = if visible { quick_ask_hide(app.clone()) } else { show_window(app) };
+    if let Err(e) = result {
+        eprintln!("❌ Quick ask: {}", e);
+    }
+}
+
+fn register_shortcut<R: Runtime>(app: &AppHandle<R>, shortcut: &str) -> Result<(), String> {
+    app.global_shortcut()
+        .on_shortcut(shortcut, |app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                toggle_window(app);
+            }
+        })
+        .map_err(|e| format!("Failed to register shortcut '{}': {}", shortcut, e))
+}
+
+// Register the saved (or default) shortcut at startup. A shortcut taken by another app
+// shouldn't keep Norma from starting, so failures are only logged.
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let shortcut = saved_shortcut(app);
+    match register_shortcut(app, &shortcut) {
+        Ok(()) => println!("⌨️  Quick ask shortcut: {}", shortcut),
+        Err(e) => eprintln!("⚠️  {}", e),
+    }
+}
+
+#[command]
+pub fn quick_ask_show<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
+    show_window(&app)
+}
+
+#[command]
+pub fn quick_ask_hide<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        window.hide().map_err(|e| format!("Failed to hide quick ask window: {}", e))?;
+    }
+    Ok(())
+}
+
+// Fit the window to its content (input only, or input + answer). Width stays fixed.
+#[command]
+pub fn quick_ask_resize<R: Runtime>(app: AppHandle<R>, height: f64) -> Result<(), String> {
+    let window = window(&app)?;
+    window
+        .set_size(LogicalSize::new(WIDTH, height.clamp(COLLAPSED_HEIGHT, MAX_HEIGHT)))
+        .map_err(|e| format!("Failed to resize quick ask window: {}", e))
+}
+
+// Continue a quick ask conversation in the main window
+#[command]
+pub fn quick_ask_open_in_main<R: Runtime>(app: AppHandle<R>, chat_id: i64) -> Result<(), String> {
+    let main = app.get_webview_window("main").ok_or("Main window not found")?;
+    let _ = quick_ask_hide(app.clone());
+    main.show().map_err(|e| format!("Failed to show main window: {}", e))?;
+    main.unminimize().map_err(|e| format!("Failed to restore main window: {}", e))?;
+    main.set_focus().map_err(|e| format!("Failed to focus main window: {}", e))?;
+    main.emit_to("main", EVENT_OPEN_CHAT, chat_id)
+        .map_err(|e| format!("Failed to open chat in main window: {}", e))
+}
+
+#[command]
+pub fn quick_ask_get_shortcut<R: Runtime>(app: AppHandle<R>) -> String {
+    saved_shortcut(&app)
+}
+
+// Change the shortcut (e.g. "Alt+Space"). The old one is only released once the new one is
+// registered, so a shortcut already taken by another app leaves the current one working.
+#[command]
+pub fn quick_ask_set_shortcut<R: Runtime>(app: AppHandle<R>, shortcut: String) -> Result<String, String> {
+    let parsed: Shortcut = shortcut
+        .parse()
+        .map_err(|e| format!("Invalid shortcut '{}': {}", shortcut, e))?;
+    let current = saved_shortcut(&app);
+    let unchanged = current
+        .parse::<Shortcut>()
+        .map(|current| current == parsed)
+        .unwrap_or(false);
+    if unchanged {
+        return Ok(current);
+    }
+
+    register_shortcut(&app, &shortcut)?;
+    let _ = app.global_shortcut().unregister(current.as_str());
+
+    let store = app.store(SETTINGS_STORE).map_err(|e| format!("Failed to open settings: {}", e))?;
+    store.set(SHORTCUT_KEY, shortcut.clone());
+    store.save().map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    println!("⌨️  Quick ask shortcut changed: {} -> {}", current, shortcut);
+    Ok(shortcut)
+}
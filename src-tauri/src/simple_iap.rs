@@ -1,10 +1,22 @@
 // Minimal IAP Implementation for Tauri
 // iOS: Uses FFI bridge to Swift StoreKit 2
 // Android: JavaScript calls Kotlin IAPService directly via Tauri mobile bridge
+// Both: iap_validate_purchase links the receipt on the backend, which is the source of truth
+// for the subscription - features unlock from its answer, not from the store callback.
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
 use tauri::command;
 
+// Same backend as src/services/api.js
+const API_BASE_URL: &str = "https://norma-ai.fly.dev";
+
+// The Fly machine may be asleep (auto-stop) when a purchase completes; waking takes a few seconds
+const VALIDATE_MAX_ATTEMPTS: u32 = 5;
+const VALIDATE_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const VALIDATE_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SimplePurchase {
     pub product_id: String,
@@ -21,6 +33,22 @@ pub struct SimpleProduct {
     pub description: String,
 }
 
+/// Subscription state as stored by the backend after linking the receipt
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidatedSubscription {
+    pub account_type: String,
+    pub subscription_type: Option<String>, // "monthly" or "yearly"
+    pub expires_at: Option<String>,        // RFC 3339
+    pub is_active: bool,
+    pub platform: Option<String>,
+    pub in_grace_period: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinkPurchaseResponse {
+    subscription: ValidatedSubscription,
+}
+
 // Initialize the IAP system (iOS StoreKit / Android Play Billing)
 #[command]
 pub async fn iap_init() -> Result<bool, String> {
@@ -97,6 +125,78 @@ pub async fn iap_restore() -> Result<Vec<SimplePurchase>, String> {
     }
 }
 
+// Validate a purchase receipt with the backend (/api/subscription/link-purchase) and return the
+// authoritative subscription state. The frontend passes its auth headers (Supabase session).
+// Network errors and 502/503/504 (backend waking up) are retried with exponential backoff;
+// other errors are returned immediately.
+#[command]
+pub async fn iap_validate_purchase(
+    receipt_token: String,
+    is_restore: bool,
+    headers: HashMap<String, String>,
+) -> Result<ValidatedSubscription, String> {
+    let client = reqwest::Client::builder()
+        .timeout(VALIDATE_REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let body = serde_json::json!({
+        "receipt_token": receipt_token,
+        "is_restore": is_restore,
+    });
+
+    let mut backoff = VALIDATE_INITIAL_BACKOFF;
+    let mut last_error = String::new();
+
+    for attempt in 1..=VALIDATE_MAX_ATTEMPTS {
+        let mut request = client
+            .post(format!("{}/api/subscription/link-purchase", API_BASE_URL))
+            .json(&body);
+        for (name, value) in headers.iter().filter(|(name, _)| !name.eq_ignore_ascii_case("content-type")) {
+            request = request.header(name.as_str(), value.as_str());
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                let linked = response
+                    .json::<LinkPurchaseResponse>()
+                    .await
+                    .map_err(|e| format!("Failed to parse subscription state: {}", e))?;
+                println!(
+                    "✅ Purchase validated: {} (active: {})",
+                    linked.subscription.account_type, linked.subscription.is_active
+                );
+                return Ok(linked.subscription);
+            }
+            Ok(response) => {
+                let status = response.status();
+                let text = response.text().await.unwrap_or_default();
+                last_error = format!("HTTP {}: {}", status.as_u16(), text);
+                if !matches!(status.as_u16(), 502..=504) {
+                    return Err(format!("Purchase validation failed: {}", last_error));
+                }
+            }
+            Err(e) => last_error = format!("Network error: {}", e),
+        }
+
+        if attempt < VALIDATE_MAX_ATTEMPTS {
+            println!(
+                "⏳ Purchase validation attempt {} failed ({}), retrying in {}s",
+                attempt,
+                last_error,
+                backoff.as_secs()
+            );
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+
+    Err(format!(
+        "Purchase validation failed after {} attempts: {}",
+        VALIDATE_MAX_ATTEMPTS, last_error
+    ))
+}
+
 // ============================================================================
 // iOS StoreKit 2 Implementation
 // ============================================================================
@@ -41,9 +41,23 @@ thread_local! {
 
 pub fn disable_scroll_on_keyboard_show(webview_window: &WebviewWindow) {
     let _ = webview_window.with_webview(|webview| unsafe {
+        install_keyboard_observers(webview.inner());
+    });
+}
+
+/// Installs the keyboard show/hide observers for a WKWebView pointer.
+/// Returns the observer tokens so callers (tests) can remove them again.
+///
+/// # Safety
+/// Must be called on the main thread with a valid WKWebView that outlives the observers.
+pub(crate) unsafe fn install_keyboard_observers(
+    webview_ptr: *mut std::ffi::c_void,
+) -> Vec<Retained<ProtocolObject<dyn NSObjectProtocol>>> {
+    unsafe {
         #[allow(deprecated)]
-        let webview: &objc2_ui_kit::UIWebView = &*webview.inner().cast();
+        let webview: &objc2_ui_kit::UIWebView = &*webview_ptr.cast();
         let notification_center = NSNotificationCenter::defaultCenter();
+        let mut observers = Vec::with_capacity(3);
         let scroll_view_arc = Arc::new(webview.scrollView());
         let old_delegate_arc = Arc::new(std::sync::Mutex::new(None));
 
@@ -66,7 +80,7 @@ pub fn disable_scroll_on_keyboard_show(webview_window: &WebviewWindow) {
         // UIKeyboardWillShowNotification: Install scroll-preventing delegate
         let scroll_view_will_show = scroll_view_arc.clone();
         let old_delegate_will_show = old_delegate_arc.clone();
-        create_observer(
+        observers.push(create_observer(
             &notification_center,
             &UIKeyboardWillShowNotification,
             move |_notification| {
@@ -93,14 +107,14 @@ pub fn disable_scroll_on_keyboard_show(webview_window: &WebviewWindow) {
                     }
                 });
             },
-        );
+        ));
 
         // UIKeyboardDidShowNotification: Shrink webview by keyboard height
         let scroll_view_did_show = scroll_view_arc.clone();
         let keyboard_height_did_show = keyboard_height_arc.clone();
         let original_height_did_show = original_height_arc.clone();
         let original_bottom_inset_did_show = original_bottom_inset_arc.clone();
-        create_observer(
+        observers.push(create_observer(
             &notification_center,
             &UIKeyboardDidShowNotification,
             move |notification| {
@@ -133,14 +147,14 @@ pub fn disable_scroll_on_keyboard_show(webview_window: &WebviewWindow) {
                 // Keep scroll-preventing delegate active until keyboard fully hides
                 // (delegate restoration moved to UIKeyboardWillHideNotification)
             },
-        );
+        ));
 
         // UIKeyboardWillHideNotification: Restore original dimensions and delegate
         let scroll_view_will_hide = scroll_view_arc.clone();
         let original_height_will_hide = original_height_arc.clone();
         let original_bottom_inset_will_hide = original_bottom_inset_arc.clone();
         let old_delegate_will_hide = old_delegate_arc.clone();
-        create_observer(
+        observers.push(create_observer(
             &notification_center,
             &UIKeyboardWillHideNotification,
             move |_notification| {
@@ -162,8 +176,10 @@ pub fn disable_scroll_on_keyboard_show(webview_window: &WebviewWindow) {
                     scroll_view_will_hide.setDelegate(None);
                 }
             },
-        );
-    });
+        ));
+
+        observers
+    }
 }
 
 #[derive(Debug)]
@@ -261,11 +277,19 @@ impl ProcessTerminationDelegate {
 /// This fixes the blank screen issue when iOS kills the WebContent process after backgrounding
 pub fn enable_process_termination_handler(webview_window: &WebviewWindow) {
     let _ = webview_window.with_webview(|webview| unsafe {
+        install_process_termination_handler(webview.inner() as *mut WKWebView);
+    });
+}
+
+/// Sets our ProcessTerminationDelegate as the navigation delegate of a WKWebView pointer.
+///
+/// # Safety
+/// Must be called on the main thread with a valid (or null) WKWebView pointer.
+pub(crate) unsafe fn install_process_termination_handler(wkwebview_ptr: *mut WKWebView) {
+    unsafe {
         // SAFETY: This is guaranteed to be called on the main thread
         let mtm = MainThreadMarker::new_unchecked();
 
-        // Cast to WKWebView
-        let wkwebview_ptr = webview.inner() as *mut WKWebView;
         if wkwebview_ptr.is_null() {
             println!("❌ Failed to get WKWebView pointer");
             return;
@@ -292,5 +316,149 @@ pub fn enable_process_termination_handler(webview_window: &WebviewWindow) {
                 println!("✅ WKNavigationDelegate set for process termination handling");
             }
         });
-    });
+    }
+}
+
+// Run on an iOS simulator (macOS CI). The observers and delegates need the main thread, which
+// libtest never gives a test, so tests/webview_helper.rs runs these with its own harness:
+//   cargo dinghy -d <simulator> test --features webview-tests --test webview_helper
+#[cfg(feature = "webview-tests")]
+pub mod tests {
+    use super::*;
+    use objc2::{class, runtime::AnyObject, ClassType};
+    use objc2_core_foundation::CGSize;
+    use objc2_foundation::NSDictionary;
+
+    // WKWebView lives in WebKit, which the test binary doesn't otherwise link
+    #[link(name = "WebKit", kind = "framework")]
+    extern "C" {}
+
+    const WEBVIEW_HEIGHT: f64 = 800.0;
+    const KEYBOARD_HEIGHT: f64 = 300.0;
+
+    /// Every test with its name, for the harness
+    pub const TESTS: &[(&str, fn())] = &[
+        ("keyboard_show_shrinks_webview_and_hide_restores_it", keyboard_show_shrinks_webview_and_hide_restores_it),
+        ("keyboard_show_pins_scroll_offset_until_hide", keyboard_show_pins_scroll_offset_until_hide),
+        ("content_process_termination_reloads_webview", content_process_termination_reloads_webview),
+    ];
+
+    fn main_thread() -> MainThreadMarker {
+        MainThreadMarker::new().expect("webview tests must run on the main thread")
+    }
+
+    fn headless_webview(mtm: MainThreadMarker) -> Retained<WKWebView> {
+        let frame = CGRect::new(CGPoint::new(0.0, 0.0), CGSize::new(390.0, WEBVIEW_HEIGHT));
+        unsafe { msg_send![WKWebView::alloc(mtm), initWithFrame: frame] }
+    }
+
+    fn scroll_view(webview: &WKWebView) -> Retained<UIScrollView> {
+        unsafe { msg_send![webview, scrollView] }
+    }
+
+    fn post_keyboard_notification(name: &NSNotificationName, keyboard_height: f64) {
+        let keyboard_frame = CGRect::new(
+            CGPoint::new(0.0, WEBVIEW_HEIGHT - keyboard_height),
+            CGSize::new(390.0, keyboard_height),
+        );
+        unsafe {
+            let value: Retained<AnyObject> = msg_send![class!(NSValue), valueWithCGRect: keyboard_frame];
+            let key = NSString::from_str("UIKeyboardFrameEndUserInfoKey");
+            let user_info = NSDictionary::<NSString, AnyObject>::from_slices(&[&*key], &[&*value]);
+            let user_info: Retained<NSDictionary> = Retained::cast_unchecked(user_info);
+
+            // Observers were registered without a queue, so they run synchronously here
+            NSNotificationCenter::defaultCenter().postNotificationName_object_userInfo(
+                name,
+                None,
+                Some(&user_info),
+            );
+        }
+    }
+
+    fn remove_observers(observers: Vec<Retained<ProtocolObject<dyn NSObjectProtocol>>>) {
+        let center = NSNotificationCenter::defaultCenter();
+        for observer in observers {
+            unsafe { center.removeObserver(&observer) };
+        }
+    }
+
+    fn delegate_class_name(scroll_view: &UIScrollView) -> Option<String> {
+        scroll_view.delegate().map(|delegate| {
+            let delegate: &AnyObject = AsRef::<AnyObject>::as_ref(&*delegate);
+            delegate.class().name().to_str().unwrap_or_default().to_string()
+        })
+    }
+
+    fn keyboard_show_shrinks_webview_and_hide_restores_it() {
+        let mtm = main_thread();
+        let webview = headless_webview(mtm);
+        let scroll_view = scroll_view(&webview);
+        let original_inset = scroll_view.contentInset().bottom;
+        let observers = unsafe { install_keyboard_observers(Retained::as_ptr(&webview) as *mut _) };
+
+        post_keyboard_notification(unsafe { UIKeyboardWillShowNotification }, KEYBOARD_HEIGHT);
+        post_keyboard_notification(unsafe { UIKeyboardDidShowNotification }, KEYBOARD_HEIGHT);
+        assert_eq!(webview.frame().size.height, WEBVIEW_HEIGHT - KEYBOARD_HEIGHT);
+        assert_eq!(scroll_view.contentInset().bottom, original_inset - KEYBOARD_HEIGHT);
+
+        // A second show (e.g. keyboard type switch) must not shrink twice
+        post_keyboard_notification(unsafe { UIKeyboardDidShowNotification }, KEYBOARD_HEIGHT);
+        assert_eq!(webview.frame().size.height, WEBVIEW_HEIGHT - KEYBOARD_HEIGHT);
+
+        post_keyboard_notification(unsafe { UIKeyboardWillHideNotification }, KEYBOARD_HEIGHT);
+        assert_eq!(webview.frame().size.height, WEBVIEW_HEIGHT);
+        assert_eq!(scroll_view.contentInset().bottom, original_inset);
+
+        remove_observers(observers);
+    }
+
+    fn keyboard_show_pins_scroll_offset_until_hide() {
+        let mtm = main_thread();
+        let webview = headless_webview(mtm);
+        let scroll_view = scroll_view(&webview);
+        let original_delegate = delegate_class_name(&scroll_view);
+        let observers = unsafe { install_keyboard_observers(Retained::as_ptr(&webview) as *mut _) };
+
+        post_keyboard_notification(unsafe { UIKeyboardWillShowNotification }, KEYBOARD_HEIGHT);
+        assert_eq!(
+            delegate_class_name(&scroll_view).as_deref(),
+            Some(KeyboardScrollPreventDelegate::NAME)
+        );
+
+        // Scrolling while the keyboard is up snaps back to the offset at show time
+        let pinned = scroll_view.contentOffset();
+        scroll_view.setContentOffset(CGPoint::new(pinned.x, pinned.y + 120.0));
+        assert_eq!(scroll_view.contentOffset().y, pinned.y);
+
+        post_keyboard_notification(unsafe { UIKeyboardWillHideNotification }, KEYBOARD_HEIGHT);
+        assert_eq!(delegate_class_name(&scroll_view), original_delegate);
+
+        remove_observers(observers);
+    }
+
+    fn content_process_termination_reloads_webview() {
+        let mtm = main_thread();
+        let webview = headless_webview(mtm);
+        unsafe { install_process_termination_handler(Retained::as_ptr(&webview) as *mut _) };
+
+        let delegate: Option<Retained<AnyObject>> = unsafe { msg_send![&*webview, navigationDelegate] };
+        let delegate = delegate.expect("navigation delegate should be installed");
+        assert_eq!(
+            delegate.class().name().to_str().unwrap(),
+            ProcessTerminationDelegate::NAME
+        );
+
+        unsafe {
+            let html = NSString::from_str("<html><body>Norma AI</body></html>");
+            let _: Option<Retained<AnyObject>> =
+                msg_send![&*webview, loadHTMLString: &*html, baseURL: std::ptr::null::<AnyObject>()];
+
+            // What WebKit calls when iOS kills the WebContent process
+            let _: () = msg_send![&*delegate, webViewWebContentProcessDidTerminate: &*webview];
+
+            let is_loading: bool = msg_send![&*webview, isLoading];
+            assert!(is_loading, "terminated webview should be reloading");
+        }
+    }
 }
@@ -0,0 +1,35 @@
+// Runs the iOS webview helper tests (src/webview_helper.rs) on the main thread. libtest runs every
+// test on a spawned thread, where UIKit and WebKit can't be used, so this target has no harness.
+
+#[cfg(target_os = "ios")]
+fn main() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let tests = norma_ai_lib::webview_helper_tests::TESTS;
+    println!("running {} webview tests", tests.len());
+    let mut failed = Vec::new();
+    for &(name, test) in tests {
+        match catch_unwind(AssertUnwindSafe(test)) {
+            Ok(()) => println!("test {} ... ok", name),
+            Err(_) => {
+                println!("test {} ... FAILED", name);
+                failed.push(name);
+            }
+        }
+    }
+
+    println!(
+        "\ntest result: {}. {} passed; {} failed",
+        if failed.is_empty() { "ok" } else { "FAILED" },
+        tests.len() - failed.len(),
+        failed.len()
+    );
+    if !failed.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(target_os = "ios"))]
+fn main() {
+    println!("webview tests only run on iOS - skipped");
+}
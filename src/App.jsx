@@ -6,14 +6,19 @@ import LawSelector from "./components/LawSelector";
 import AnnouncementBar from "./components/AnnouncementBar";
 import ConfirmDialog from "./components/ConfirmDialog";
 import ErrorDialog from "./components/ErrorDialog";
+import ConsentDialog from "./components/ConsentDialog";
 import AuthPage from "./components/AuthPage";
 import PlanSelectionModal from "./components/PlanSelectionModal";
 import SubscriptionManagementModal from "./components/SubscriptionManagementModal";
 import SettingsModal from "./components/SettingsModal";
 import UpdateChecker from "./components/UpdateChecker";
+import AppLockScreen from "./components/AppLockScreen";
 import { ChatSkeleton } from "./components/Skeleton";
 import { ThemeProvider } from "./contexts/ThemeContext";
 import apiService from "./services/api";
+import { notifyAnswerReady } from "./services/notifications";
+import { isLocked, watchAppLock } from "./services/appLock";
+import offlineQueue from "./services/offlineQueue";
 
 function App() {
   const [chats, setChats] = useState([]);
@@ -37,6 +42,9 @@ function App() {
   // Plan selection state
   const [planSelectionModalOpen, setPlanSelectionModalOpen] = useState(false);
 
+  // Biometric app lock (mobile)
+  const [isAppLocked, setIsAppLocked] = useState(false);
+
   // Subscription management state
   const [subscriptionModalOpen, setSubscriptionModalOpen] = useState(false);
 
@@ -49,6 +57,11 @@ function App() {
   const [errorDialogOpen, setErrorDialogOpen] = useState(false);
   const [errorMessage, setErrorMessage] = useState('');
 
+  // Terms of use the backend wants accepted before answering ({ version, resolve })
+  const [consentRequest, setConsentRequest] = useState(null);
+  const [consentSubmitting, setConsentSubmitting] = useState(false);
+  const [consentError, setConsentError] = useState(null);
+
   // Mobile menu state
   const [isMobileMenuOpen, setIsMobileMenuOpen] = useState(false);
 
@@ -74,6 +87,78 @@ function App() {
     }
   }, [isAuthenticated]);
 
+  // Questions refused with CONSENT_REQUIRED wait for the user to accept the terms, then are retried
+  useEffect(() => {
+    apiService.setConsentHandler((version) =>
+      new Promise((resolve) => {
+        setConsentError(null);
+        setConsentRequest({ version, resolve });
+      })
+    );
+    return () => apiService.setConsentHandler(null);
+  }, []);
+
+  const handleConsentAccept = async () => {
+    setConsentSubmitting(true);
+    try {
+      await apiService.acceptConsent(consentRequest.version);
+      consentRequest.resolve(true);
+      setConsentRequest(null);
+    } catch (error) {
+      console.error('Error accepting terms:', error);
+      setConsentError(error.message);
+      // The terms changed while the dialog was open - the next click accepts the new version
+      if (error.code === 'CONSENT_VERSION_OUTDATED') {
+        apiService.getConsentStatus()
+          .then((status) => setConsentRequest((request) => request && { ...request, version: status.current_version }))
+          .catch((err) => console.warn('Could not reload the terms version:', err));
+      }
+    } finally {
+      setConsentSubmitting(false);
+    }
+  };
+
+  const handleConsentDecline = () => {
+    consentRequest?.resolve(false);
+    setConsentRequest(null);
+  };
+
+  // Mobile: lock on startup and after the auto-lock timeout in the background
+  useEffect(() => {
+    isLocked().then(setIsAppLocked);
+    return watchAppLock(setIsAppLocked);
+  }, []);
+
+  // Desktop: questions queued while offline are sent once the backend is reachable again
+  const currentChatIdRef = useRef(null);
+  useEffect(() => {
+    currentChatIdRef.current = currentChatId;
+  }, [currentChatId]);
+
+  useEffect(() => {
+    if (!isAuthenticated || !offlineQueue.available) return;
+    offlineQueue.startAutoFlush(({ response }) => {
+      loadChats().catch(err => console.warn('Could not refresh chat list:', err));
+      loadMessages(currentChatIdRef.current);
+      if (response?.answer) {
+        notifyAnswerReady('Norma AI', response.answer);
+      }
+    });
+  }, [isAuthenticated]);
+
+  // Desktop quick ask window hands its chat over to the main window
+  useEffect(() => {
+    if (!window.__TAURI__) return;
+    let unlisten;
+    import("@tauri-apps/api/event").then(({ listen }) =>
+      listen("quick-ask://open-chat", (event) => {
+        loadChats().catch(err => console.warn('Could not refresh chat list:', err));
+        setCurrentChatId(event.payload);
+      })
+    ).then((fn) => { unlisten = fn; });
+    return () => unlisten?.();
+  }, []);
+
   // Detect Tauri iOS app for platform-specific styling
   useEffect(() => {
     if (window.__TAURI__) {
@@ -621,15 +706,15 @@ function App() {
     };
     setMessages(prev => [...prev, userMessage]);
 
-    try {
-      const requestData = {
-        question,
-        document_content: documentContent,
-        document_filename: documentFilename,
-        chat_id: activeChatId
-        // law_name and law_url removed - will be auto-detected by backend
-      };
+    const requestData = {
+      question,
+      document_content: documentContent,
+      document_filename: documentFilename,
+      chat_id: activeChatId
+      // law_name and law_url removed - will be auto-detected by backend
+    };
 
+    try {
       console.log('🔍 App: Sending API request:', {
         question,
         hasDocumentContent: !!documentContent,
@@ -675,13 +760,20 @@ function App() {
         aiMessage
       ]);
 
+      // Desktop: native notification if the user switched away while waiting
+      const notificationTitle = chats.find(chat => chat.id === activeChatId)?.title || 'Norma AI';
+      notifyAnswerReady(notificationTitle, response.answer);
+
       // Update chat title with first user message if it's still default
       // Check before refreshing chat list to avoid race condition
       const currentChat = chats.find(chat => chat.id === activeChatId);
       if (currentChat && currentChat.title === 'Nova konverzacija') {
         try {
-          const newTitle = generateChatTitle(question);
-          await apiService.updateChatTitle(activeChatId, newTitle);
+          // Backend generates a title after the first exchange - fall back to the question text
+          const newTitle = response.chat_title || generateChatTitle(question);
+          if (!response.chat_title) {
+            await apiService.updateChatTitle(activeChatId, newTitle);
+          }
 
           // Update local state immediately
           setChats(prevChats =>
@@ -719,7 +811,20 @@ function App() {
       setMessages(prev => prev.filter(msg => !(msg.isOptimistic && msg.content === question)));
       
       const errorMsg = error.message || error.toString();
-      
+
+      // Desktop: keep the question and send it when the connection is back (fetch throws a
+      // TypeError when the request never reached the server)
+      if (offlineQueue.available && (error instanceof TypeError || !navigator.onLine)) {
+        try {
+          await offlineQueue.enqueue(requestData);
+          setErrorMessage('Niste povezani na internet. Pitanje je sačuvano i biće poslato automatski kada se veza uspostavi.');
+          setErrorDialogOpen(true);
+          return;
+        } catch (queueError) {
+          console.error('Could not queue offline draft:', queueError);
+        }
+      }
+
       // Handle different error types
       if (errorMsg === 'Session expired. Please log in again.') {
         console.log('Session expired during message send, clearing auth state');
@@ -805,6 +910,7 @@ function App() {
   return (
     <ThemeProvider>
       <UpdateChecker />
+      {isAppLocked && <AppLockScreen onUnlock={() => setIsAppLocked(false)} />}
 
       {/* Show loading screen during auth initialization */}
       {authLoading ? (
@@ -890,6 +996,14 @@ function App() {
             buttonText="U redu"
           />
 
+          <ConsentDialog
+            isOpen={consentRequest !== null}
+            onAccept={handleConsentAccept}
+            onDecline={handleConsentDecline}
+            isSubmitting={consentSubmitting}
+            error={consentError}
+          />
+
           <PlanSelectionModal
             isOpen={planSelectionModalOpen}
             onClose={handleClosePlanSelection}
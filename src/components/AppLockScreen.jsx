@@ -0,0 +1,47 @@
+import React, { useEffect, useState } from 'react';
+import { authenticate } from '../services/appLock';
+import './AppLockScreen.css';
+
+/**
+ * Full-screen cover shown while the app is locked. Prompts for biometrics right away
+ * and offers a retry button if the user cancels.
+ */
+const AppLockScreen = ({ onUnlock }) => {
+  const [error, setError] = useState('');
+  const [prompting, setPrompting] = useState(false);
+
+  const unlock = async () => {
+    if (prompting) return;
+    setPrompting(true);
+    setError('');
+    try {
+      if (await authenticate()) {
+        onUnlock();
+      } else {
+        setError('Otključavanje nije uspelo. Pokušajte ponovo.');
+      }
+    } catch (err) {
+      console.error('Biometric unlock failed:', err);
+      setError('Biometrijska provera trenutno nije dostupna.');
+    } finally {
+      setPrompting(false);
+    }
+  };
+
+  useEffect(() => {
+    unlock();
+  }, []);
+
+  return (
+    <div className="app-lock-screen">
+      <div className="app-lock-icon">🔒</div>
+      <h2>Norma AI je zaključana</h2>
+      {error && <p className="app-lock-error">{error}</p>}
+      <button className="app-lock-button" onClick={unlock} disabled={prompting}>
+        Otključaj
+      </button>
+    </div>
+  );
+};
+
+export default AppLockScreen;
@@ -0,0 +1,28 @@
+import React from 'react';
+import Modal from './Modal';
+
+// Shown when the backend refuses a question because the current terms of use aren't accepted yet
+const ConsentDialog = ({ isOpen, onAccept, onDecline, isSubmitting = false, error = null }) => {
+  return (
+    <Modal isOpen={isOpen} onClose={onDecline} title="Uslovi korišćenja" type="confirm">
+      <div className="confirm-message">
+        Pre postavljanja pitanja potrebno je da prihvatite{' '}
+        <a href="https://normaai.rs/uslovi.html" target="_blank" rel="noopener noreferrer">
+          uslove korišćenja
+        </a>
+        . Norma AI daje opšte pravne informacije i ne zamenjuje savet advokata.
+        {error && <p className="consent-error">{error}</p>}
+      </div>
+      <div className="confirm-actions">
+        <button className="confirm-btn cancel" onClick={onDecline} disabled={isSubmitting}>
+          Odustani
+        </button>
+        <button className="confirm-btn primary" onClick={onAccept} disabled={isSubmitting}>
+          {isSubmitting ? 'Čuvanje...' : 'Prihvatam'}
+        </button>
+      </div>
+    </Modal>
+  );
+};
+
+export default ConsentDialog;
@@ -0,0 +1,98 @@
+import { useState, useEffect, useRef } from "react";
+import apiService from "../services/api";
+import "./QuickAsk.css";
+
+/**
+ * Quick ask window (desktop) - opened by the global shortcut, see src-tauri/src/quick_ask.rs.
+ * Asks in a new chat and shows the answer inline; the chat can then be continued in the main window.
+ */
+function QuickAsk() {
+  const [question, setQuestion] = useState("");
+  const [answer, setAnswer] = useState(null);
+  const [chatId, setChatId] = useState(null);
+  const [isLoading, setIsLoading] = useState(false);
+  const [error, setError] = useState(null);
+  const inputRef = useRef(null);
+  const containerRef = useRef(null);
+
+  const invoke = async (command, args) => {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke(command, args);
+  };
+
+  // The shell emits quick-ask://focus every time the window is shown
+  useEffect(() => {
+    let unlisten;
+    import("@tauri-apps/api/event").then(({ listen }) =>
+      listen("quick-ask://focus", () => inputRef.current?.focus())
+    ).then((fn) => { unlisten = fn; });
+    inputRef.current?.focus();
+    return () => unlisten?.();
+  }, []);
+
+  // Grow the window with the answer
+  useEffect(() => {
+    if (containerRef.current) {
+      invoke("quick_ask_resize", { height: containerRef.current.scrollHeight })
+        .catch((err) => console.warn("Could not resize quick ask window:", err));
+    }
+  }, [answer, error, isLoading]);
+
+  const handleKeyDown = (event) => {
+    if (event.key === "Escape") {
+      invoke("quick_ask_hide");
+    }
+  };
+
+  const handleSubmit = async (event) => {
+    event.preventDefault();
+    const trimmed = question.trim();
+    if (!trimmed || isLoading) return;
+
+    setIsLoading(true);
+    setError(null);
+    setAnswer(null);
+    try {
+      const newChatId = await apiService.createChat("Nova konverzacija");
+      const response = await apiService.askQuestion({ question: trimmed, chat_id: newChatId });
+      setChatId(newChatId);
+      setAnswer(response.answer);
+      setQuestion("");
+    } catch (err) {
+      console.error("Quick ask failed:", err);
+      setError("Greška prilikom slanja pitanja. Molimo pokušajte ponovo.");
+    } finally {
+      setIsLoading(false);
+    }
+  };
+
+  return (
+    <div className="quick-ask" ref={containerRef} onKeyDown={handleKeyDown}>
+      <form className="quick-ask-form" onSubmit={handleSubmit}>
+        <input
+          ref={inputRef}
+          className="quick-ask-input"
+          value={question}
+          onChange={(e) => setQuestion(e.target.value)}
+          placeholder="Postavite pravno pitanje..."
+          disabled={isLoading}
+        />
+      </form>
+      {isLoading && <div className="quick-ask-status">Norma razmišlja...</div>}
+      {error && <div className="quick-ask-error">{error}</div>}
+      {answer && (
+        <div className="quick-ask-answer">
+          <div className="quick-ask-answer-text">{answer}</div>
+          <button
+            className="quick-ask-open"
+            onClick={() => invoke("quick_ask_open_in_main", { chatId })}
+          >
+            Nastavi u aplikaciji
+          </button>
+        </div>
+      )}
+    </div>
+  );
+}
+
+export default QuickAsk;
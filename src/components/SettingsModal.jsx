@@ -5,6 +5,12 @@ import ConfirmDialog from './ConfirmDialog';
 import ErrorDialog from './ErrorDialog';
 import InfoDialog from './InfoDialog';
 import apiService, { supabase } from '../services/api';
+import {
+  isAppLockSupported,
+  getBiometricStatus,
+  getAppLockSettings,
+  setAppLockSettings
+} from '../services/appLock';
 import './SettingsModal.css';
 
 const SettingsModal = ({
@@ -38,6 +44,11 @@ const SettingsModal = ({
   const [errorDialog, setErrorDialog] = useState({ isOpen: false, message: '' });
   const [infoDialog, setInfoDialog] = useState({ isOpen: false, message: '' });
 
+  // Biometric app lock (mobile)
+  const [biometricAvailable, setBiometricAvailable] = useState(false);
+  const [appLock, setAppLock] = useState({ enabled: false, timeout_seconds: 60 });
+  const [savingAppLock, setSavingAppLock] = useState(false);
+
   // Reset to account tab when modal opens
   useEffect(() => {
     if (isOpen) {
@@ -68,6 +79,29 @@ const SettingsModal = ({
     fetchUserProviders();
   }, [isOpen]);
 
+  // Load app lock settings when devices tab is opened
+  useEffect(() => {
+    if (activeTab !== 'devices' || !isOpen || !isAppLockSupported) return;
+
+    getBiometricStatus().then(status => setBiometricAvailable(status.available));
+    getAppLockSettings()
+      .then(setAppLock)
+      .catch(error => console.error('Error loading app lock settings:', error));
+  }, [activeTab, isOpen]);
+
+  const handleAppLockChange = async (changes) => {
+    setSavingAppLock(true);
+    try {
+      const saved = await setAppLockSettings({ ...appLock, ...changes });
+      if (saved) setAppLock(saved);
+    } catch (error) {
+      console.error('Error saving app lock settings:', error);
+      setErrorDialog({ isOpen: true, message: 'Greška pri čuvanju podešavanja zaključavanja.' });
+    } finally {
+      setSavingAppLock(false);
+    }
+  };
+
   // Load sessions when devices tab is opened
   useEffect(() => {
     if (activeTab === 'devices' && isOpen) {
@@ -618,6 +652,41 @@ const SettingsModal = ({
                   })}
                 </div>
               )}
+
+              {isAppLockSupported && biometricAvailable && (
+                <>
+                  <div className="settings-section-header">
+                    <h4>Zaključavanje aplikacije</h4>
+                  </div>
+                  <div className="settings-info-group">
+                    <label className="settings-info-item">
+                      <span className="settings-label">Otključavanje biometrijom</span>
+                      <input
+                        type="checkbox"
+                        checked={appLock.enabled}
+                        disabled={savingAppLock}
+                        onChange={(e) => handleAppLockChange({ enabled: e.target.checked })}
+                      />
+                    </label>
+                    {appLock.enabled && (
+                      <label className="settings-info-item">
+                        <span className="settings-label">Zaključaj nakon</span>
+                        <select
+                          value={appLock.timeout_seconds}
+                          disabled={savingAppLock}
+                          onChange={(e) => handleAppLockChange({ timeout_seconds: Number(e.target.value) })}
+                        >
+                          <option value={0}>Odmah</option>
+                          <option value={60}>1 minut</option>
+                          <option value={300}>5 minuta</option>
+                          <option value={900}>15 minuta</option>
+                          <option value={3600}>1 sat</option>
+                        </select>
+                      </label>
+                    )}
+                  </div>
+                </>
+              )}
             </div>
           )}
 
@@ -21,6 +21,14 @@ export const PRODUCT_IDS = {
   TEAM_YEARLY: 'com.nikola.normaai.team.yearly',
 };
 
+/**
+ * Consumable message packs (one-off purchases, not subscriptions)
+ * Credited by the backend: POST /api/message-packs/purchase
+ */
+export const MESSAGE_PACK_PRODUCT_IDS = {
+  MESSAGES_20: 'com.nikola.normaai.messages.20',
+};
+
 /**
  * Map plan type and billing period to product ID
  * @param {string} planType - 'individual', 'professional', or 'team'
@@ -1,9 +1,13 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import QuickAsk from "./components/QuickAsk";
+
+// The desktop quick ask window loads index.html?view=quick-ask
+const isQuickAsk = new URLSearchParams(window.location.search).get("view") === "quick-ask";
 
 ReactDOM.createRoot(document.getElementById("root")).render(
   <React.StrictMode>
-    <App />
+    {isQuickAsk ? <QuickAsk /> : <App />}
   </React.StrictMode>,
 );
@@ -124,6 +124,9 @@ class ApiService {
   // Expose Supabase client for direct access (needed for OAuth callbacks)
   supabase = supabase;
 
+  // Asks the user to accept the current terms of use; resolves to true once accepted
+  consentHandler = null;
+
   // ==================== INTERNAL METHODS ====================
 
   /**
@@ -152,7 +155,7 @@ class ApiService {
   /**
    * Make an authenticated API call with automatic Supabase token refresh
    */
-  async makeAuthenticatedRequest(url, options = {}, retryCount = 0) {
+  async makeAuthenticatedRequest(url, options = {}, retryCount = 0, consentAsked = false) {
     const maxRetries = 1;
 
     try {
@@ -179,7 +182,18 @@ class ApiService {
         }
 
         // Retry the original request with the new token
-        return this.makeAuthenticatedRequest(url, options, retryCount + 1);
+        return this.makeAuthenticatedRequest(url, options, retryCount + 1, consentAsked);
+      }
+
+      // Current terms not accepted yet: ask the user, then retry once
+      if (response.status === 403 && this.consentHandler && !consentAsked) {
+        const errorData = await response.clone().json().catch(() => ({}));
+        if (errorData.error === "CONSENT_REQUIRED") {
+          const accepted = await this.consentHandler(errorData.details?.version);
+          if (accepted) {
+            return this.makeAuthenticatedRequest(url, options, retryCount, true);
+          }
+        }
       }
 
       return response;
@@ -192,6 +206,40 @@ class ApiService {
     }
   }
 
+  /**
+   * Register the callback that asks the user to accept the current terms of use when a request
+   * is refused with CONSENT_REQUIRED
+   */
+  setConsentHandler(handler) {
+    this.consentHandler = handler;
+  }
+
+  /**
+   * Which version of the terms of use is in force and whether the user accepted it
+   */
+  async getConsentStatus() {
+    const response = await this.makeAuthenticatedRequest(`${API_BASE_URL}/api/consent/status`);
+    if (!response.ok) throw new Error(`HTTP ${response.status}`);
+    return await response.json();
+  }
+
+  /**
+   * Accept the version of the terms of use the user was shown
+   */
+  async acceptConsent(version) {
+    const response = await this.makeAuthenticatedRequest(`${API_BASE_URL}/api/consent/accept`, {
+      method: "POST",
+      body: JSON.stringify({ version }),
+    });
+    const data = await response.json().catch(() => ({}));
+    if (!response.ok) {
+      const error = new Error(data.message || `HTTP ${response.status}`);
+      error.code = data.error;
+      throw error;
+    }
+    return data;
+  }
+
   // ==================== AUTHENTICATION METHODS ====================
 
   /**
@@ -830,9 +878,10 @@ class ApiService {
   }
 
   /**
-   * Link OAuth/email user to backend after Supabase auth
+   * Link OAuth/email user to backend after Supabase auth.
+   * Accounts with 2FA need the code: returns { twoFactorRequired: true } until it is given.
    */
-  async linkOAuthUser(session) {
+  async linkOAuthUser(session, twoFactorCode = null) {
     if (!session || !session.access_token) {
       console.warn("No session to link");
       return;
@@ -846,9 +895,16 @@ class ApiService {
           Authorization: `Bearer ${session.access_token}`,
           "Content-Type": "application/json",
         },
+        body: JSON.stringify({ two_factor_code: twoFactorCode }),
       });
 
-      if (!linkResponse.ok) {
+      if (linkResponse.status === 401) {
+        const errorData = await linkResponse.json().catch(() => ({}));
+        if (["TWO_FACTOR_REQUIRED", "INVALID_TWO_FACTOR_CODE"].includes(errorData.error)) {
+          return { twoFactorRequired: true, error: errorData.message };
+        }
+        console.error("Failed to link OAuth user to backend:", errorData);
+      } else if (!linkResponse.ok) {
         const errorText = await linkResponse.text();
         console.error("Failed to link OAuth user to backend:", errorText);
       } else {
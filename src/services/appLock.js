@@ -0,0 +1,96 @@
+/**
+ * Biometric app lock (mobile Tauri only)
+ * Lock state and settings live in the Rust shell (src-tauri/src/biometric.rs); this service
+ * reports background/foreground transitions and asks whether the lock screen should show.
+ */
+
+const isTauriApp = Boolean(window.__TAURI__);
+const isMobileDevice = /iPhone|iPad|iPod|Android/i.test(navigator.userAgent);
+
+export const isAppLockSupported = isTauriApp && isMobileDevice;
+
+async function invoke(command, args) {
+  const { invoke } = await import('@tauri-apps/api/core');
+  return await invoke(command, args);
+}
+
+/**
+ * Biometric availability
+ * @returns {Promise<Object>} { available, kind: 'face_id' | 'touch_id' | 'optic_id' | 'biometric' | 'none' }
+ */
+export async function getBiometricStatus() {
+  if (!isAppLockSupported) return { available: false, kind: 'none' };
+  try {
+    return await invoke('biometric_status');
+  } catch (error) {
+    console.warn('Could not check biometrics:', error);
+    return { available: false, kind: 'none' };
+  }
+}
+
+/**
+ * Show the system Face ID / fingerprint prompt
+ * @returns {Promise<boolean>} true when the user authenticated
+ */
+export async function authenticate(reason = 'Otključajte Norma AI') {
+  return await invoke('biometric_authenticate', { reason });
+}
+
+/**
+ * @returns {Promise<Object>} { enabled, timeout_seconds }
+ */
+export async function getAppLockSettings() {
+  if (!isAppLockSupported) return { enabled: false, timeout_seconds: 60 };
+  return await invoke('app_lock_get_settings');
+}
+
+/**
+ * Save lock settings. Changing an enabled lock, or enabling it, asks for biometrics first - the
+ * shell refuses the change without a fresh prompt (and enabling proves the user can unlock).
+ * @returns {Promise<Object|null>} Saved settings, or null if the user cancelled the prompt
+ */
+export async function setAppLockSettings(settings) {
+  const current = await getAppLockSettings();
+  if (
+    (settings.enabled || current.enabled) &&
+    !(await authenticate('Potvrdite identitet za promenu zaključavanja aplikacije'))
+  ) {
+    return null;
+  }
+  return await invoke('app_lock_set_settings', { settings });
+}
+
+/**
+ * Whether the lock screen should show right now (startup / back from background)
+ * @returns {Promise<boolean>}
+ */
+export async function isLocked() {
+  if (!isAppLockSupported) return false;
+  try {
+    const status = await invoke('app_lock_status');
+    return status.locked;
+  } catch (error) {
+    console.warn('Could not read app lock status:', error);
+    return false;
+  }
+}
+
+/**
+ * Track background/foreground transitions
+ * @param {Function} onLockChange - Called with the new locked state when the app returns
+ * @returns {Function} Cleanup
+ */
+export function watchAppLock(onLockChange) {
+  if (!isAppLockSupported) return () => {};
+
+  const handleVisibility = () => {
+    if (document.visibilityState === 'hidden') {
+      invoke('app_lock_background').catch(() => {});
+    } else {
+      isLocked().then(onLockChange);
+    }
+  };
+
+  document.addEventListener('visibilitychange', handleVisibility);
+  return () => document.removeEventListener('visibilitychange', handleVisibility);
+}
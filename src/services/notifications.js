@@ -0,0 +1,26 @@
+/**
+ * Native notifications (desktop Tauri only)
+ * The Rust side (src-tauri/src/notifications.rs) only shows the notification when the
+ * main window is in the background, so this is safe to call after every answer.
+ */
+
+const isTauriApp = Boolean(window.__TAURI__);
+const isMobileDevice = /iPhone|iPad|iPod|Android/i.test(navigator.userAgent);
+
+/**
+ * Notify that an answer is ready
+ * @param {string} title - Notification title (e.g. the chat title)
+ * @param {string} body - Answer text; the shell trims it to a short preview
+ * @returns {Promise<boolean>} Whether a notification was shown
+ */
+export async function notifyAnswerReady(title, body) {
+  if (!isTauriApp || isMobileDevice) return false;
+
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    return await invoke('notify_answer_ready', { title, body });
+  } catch (error) {
+    console.warn('Could not show answer notification:', error);
+    return false;
+  }
+}
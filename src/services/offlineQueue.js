@@ -0,0 +1,87 @@
+/**
+ * Offline Draft Queue
+ * Questions typed while offline are persisted by the Tauri shell (src-tauri/src/offline_queue.rs)
+ * and sent once the backend is reachable again. Web builds have no queue.
+ */
+
+import apiService from './api';
+
+const isTauriApp = Boolean(window.__TAURI__);
+
+class OfflineQueueService {
+  constructor() {
+    this.flushing = null;
+    this.listening = false;
+  }
+
+  get available() {
+    return isTauriApp;
+  }
+
+  /**
+   * Queue a question request (same body as apiService.askQuestion)
+   * @returns {Promise<Object>} The queued draft ({ id, request, created_at, attempts, last_error })
+   */
+  async enqueue(questionRequest) {
+    const { invoke } = await import('@tauri-apps/api/core');
+    return await invoke('offline_queue_add', { request: questionRequest });
+  }
+
+  /**
+   * Pending drafts, oldest first
+   */
+  async list() {
+    if (!isTauriApp) return [];
+    const { invoke } = await import('@tauri-apps/api/core');
+    return await invoke('offline_queue_list');
+  }
+
+  async remove(id) {
+    const { invoke } = await import('@tauri-apps/api/core');
+    return await invoke('offline_queue_remove', { id });
+  }
+
+  /**
+   * Send queued drafts with the current session's auth headers
+   * @returns {Promise<Object>} { sent: [{ id, response }], remaining: [...], offline }
+   */
+  async flush() {
+    if (!isTauriApp) return { sent: [], remaining: [], offline: false };
+    if (this.flushing) return this.flushing;
+
+    this.flushing = (async () => {
+      try {
+        const { invoke } = await import('@tauri-apps/api/core');
+        const headers = await apiService.getAuthHeaders();
+        return await invoke('offline_queue_flush', { headers });
+      } finally {
+        this.flushing = null;
+      }
+    })();
+    return this.flushing;
+  }
+
+  /**
+   * Flush automatically when connectivity returns and report each sent draft
+   * @param {Function} onSent - Called with { id, response } for every draft the backend answered
+   */
+  async startAutoFlush(onSent) {
+    if (!isTauriApp || this.listening) return;
+    this.listening = true;
+
+    const { listen } = await import('@tauri-apps/api/event');
+    await listen('offline-queue://sent', (event) => onSent?.(event.payload));
+
+    const tryFlush = () =>
+      this.flush().catch((error) => console.error('Offline queue flush failed:', error));
+
+    // Rust watcher polls the backend; the browser event covers network interface changes
+    await listen('offline-queue://online', tryFlush);
+    window.addEventListener('online', tryFlush);
+
+    tryFlush();
+  }
+}
+
+export const offlineQueue = new OfflineQueueService();
+export default offlineQueue;
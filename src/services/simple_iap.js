@@ -5,6 +5,7 @@
  */
 
 import { invoke } from '@tauri-apps/api/core';
+import apiService from './api';
 
 class SimpleIAPService {
   constructor() {
@@ -84,6 +85,23 @@ class SimpleIAPService {
       throw error;
     }
   }
+
+  /**
+   * Validate a purchase with the backend and get the authoritative subscription state.
+   * Retries while the backend wakes up, so this can take several seconds.
+   * @param {string} receiptToken - Purchase token (Android) or transaction receipt (iOS)
+   * @param {boolean} isRestore - Whether this is a restore operation
+   * @returns {Promise<Object>} { account_type, subscription_type, expires_at, is_active, platform, in_grace_period }
+   */
+  async validatePurchase(receiptToken, isRestore = false) {
+    try {
+      const headers = await apiService.getAuthHeaders();
+      return await invoke('iap_validate_purchase', { receiptToken, isRestore, headers });
+    } catch (error) {
+      console.error('Purchase validation failed:', error);
+      throw error;
+    }
+  }
 }
 
 // Export singleton instance
@@ -252,15 +252,25 @@ export async function completePurchaseFlow(planType, billingPeriod, userId, apiC
       return purchaseResult; // Cancelled or failed
     }
 
-    // Step 2: Link purchase to user in RevenueCat
+    // Step 2: Validate the purchase with the backend (links it in RevenueCat)
     try {
-      await apiClient.linkPurchase(purchaseResult.purchaseToken, false);
+      const subscription = await simpleIAP.validatePurchase(purchaseResult.purchaseToken, false);
 
       // Step 3: Finish transaction (acknowledge it)
       await finishTransaction(purchaseResult.purchaseToken);
 
+      if (!subscription.is_active) {
+        return {
+          success: true,
+          pendingValidation: true,
+          subscription,
+          message: 'Purchase successful. Your subscription will be activated shortly.',
+        };
+      }
+
       return {
         success: true,
+        subscription,
         message: 'Subscription activated successfully',
       };
     } catch (linkError) {
@@ -306,7 +316,7 @@ export async function completeRestoreFlow(userId, apiClient) {
     // Link restored purchases to user in RevenueCat
     for (const purchase of restored) {
       try {
-        await apiClient.linkPurchase(purchase.purchaseToken, true);
+        await simpleIAP.validatePurchase(purchase.purchaseToken, true);
       } catch (error) {
         console.error('Failed to link restored purchase:', error);
         // Continue with other purchases